@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+
+use crate::error::Warning;
+use crate::lexer::Comment;
+use crate::span::{NodeId, NodeSpan, Span};
+
 /// 整个程序（Program）的 AST 节点。
 ///
 /// AST（抽象语法树）是“语法结构的树形表示”，它比 Token 流更接近我们对代码结构的理解：
@@ -6,19 +12,193 @@
 ///
 /// 目前（Step2~Step5）只支持最小语句集，所以 Program 里只是一组 `Stmt`。
 ///
-/// 说明：为了保持最小实现，这里的 AST 节点暂不保存 Span。
-/// 错误定位主要由 Parser 在报错时提供（使用当前 Token 的 Span）。
+/// 位置信息：语句、代码块、声明（包括单个声明项、组件字段、import 的每一项）、UI 节点和表达式
+/// （包括标识符、字面量）都有自己的位置（见 `NodeSpan`），各个检查和 CodeGen 报错时用它指回源码。
+/// import/re-export 的模块路径另有 `source_span`。
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Program {
+    /// 顶层 import 声明：`import { f } from './util';`
+    ///
+    /// 单文件编译时不允许出现；多文件编译由 `module::link` 解析并合并后清空。
+    pub imports: Vec<ImportDecl>,
+    /// 系统模块的 import：`import preferences from '@ohos.data.preferences';`
+    ///
+    /// 多文件编译时由 `module::load_modules_with` 按 `ResolveConfig::system_modules` 从 `imports` 里分出来，
+    /// 不加载任何文件，CodeGen 生成对应 Rust 路径的 `use`。
+    pub system_imports: Vec<SystemImport>,
+    /// 顶层 re-export 声明：`export { a, b as c } from './m';`（同样由 `module::link` 处理）
+    pub reexports: Vec<ReExportDecl>,
+    /// 合并模块时产生的别名：`import { a as b }` 等，CodeGen 生成 `use crate::a as b;`
+    pub aliases: Vec<Alias>,
+    /// 合并模块时产生的命名空间导入：`import * as util`，CodeGen 生成 `mod util { .. }`
+    pub namespaces: Vec<Namespace>,
     /// 顶层函数声明列表：`function foo(...) { ... }`
     ///
     /// Step6 起允许在顶层定义多个函数。CodeGen 会把它们翻译成 Rust 的 `fn foo(...) { ... }`，
     /// 并且放在 `fn main()` 之前。
     pub funcs: Vec<FuncDecl>,
+    /// 外部函数声明：`declare function now(): number;`（常见于 `.d.ets` 声明文件）
+    ///
+    /// 只有签名没有函数体（`body` 为空块）。CodeGen 为它们生成一个转发到外部 Rust 函数的
+    /// 包装函数（见 `codegen::ExternBindings`），没有配置绑定时生成 `unimplemented!()` 桩函数。
+    pub declare_funcs: Vec<FuncDecl>,
+    /// 顶层 class 声明：`class NotFound extends Error {}`
+    ///
+    /// 目前只支持自定义异常类（直接或间接继承 `Error`），类体必须为空。
+    pub classes: Vec<ClassDecl>,
+    /// 顶层继承 `UIAbility` 的类：`export default class EntryAbility extends UIAbility { .. }`
+    pub abilities: Vec<AbilityDecl>,
+    /// 顶层 namespace 声明：`namespace Utils { export function f() {} }`
+    pub namespace_decls: Vec<NamespaceDecl>,
+    /// 顶层 ArkUI 自定义组件：`@Entry @Component struct Index { .. }`
+    pub components: Vec<ComponentDecl>,
+    /// 顶层 `@Builder function Header(title: string) { .. }`：可以在各个组件的 `build()` 里复用的 UI
+    pub builders: Vec<BuilderDecl>,
+    /// 顶层 `@Styles function fancy() { .. }` / `@Extend(Text) function bold() { .. }`：可以复用的属性方法
+    pub styles: Vec<StyleDecl>,
     /// 顶层语句列表。
     ///
     /// Rust 要求可执行语句必须放在函数体里，所以这些顶层语句会在 CodeGen 时被放进 `fn main(){...}`。
     pub stmts: Vec<Stmt>,
+    /// 解析时产生的警告（例如 `ParseOptions::asi` 自动插入的分号），不影响生成的代码。
+    pub warnings: Vec<Warning>,
+    /// 源码里的注释，`CodegenOptions::comments` 打开时输出到对应的语句旁边。
+    pub comments: Comments,
+    /// 源文件名，`CodegenOptions::provenance` 的注释里用。
+    ///
+    /// `module` 加载文件时填上；直接编译源码字符串、把多个文件合并成一个 Program 时为 None。
+    pub file: Option<String>,
+}
+
+/// 按位置挂在语句和函数上的注释。
+///
+/// - 语句、函数前面的注释（包括 `export`、装饰器前面的）记在节点的起始位置上
+/// - 和语句的最后一个 token 在同一行的注释、写在语句的表达式中间的注释记在语句的结束位置上，
+///   生成的代码里接在语句后面（`let a = 1 /* 一 */ + 2;` -> `let a = 1i32 + 2i32; // 一`）
+///
+/// 位置是源文件里的 byte offset，只对同一个文件有意义，所以 `module::link` 合并多个文件时不保留注释。
+/// 和 `NodeSpan` 一样，任意两个 `Comments` 都相等，注释不影响 AST 的比较。
+#[derive(Clone, Debug, Default)]
+pub struct Comments {
+    /// 节点起始位置 -> 写在它前面的注释
+    pub leading: BTreeMap<usize, Vec<Comment>>,
+    /// 语句结束位置 -> 跟在它后面、同一行的注释
+    pub trailing: BTreeMap<usize, Vec<Comment>>,
+}
+
+impl PartialEq for Comments {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Comments {}
+
+/// import 声明：`import { a, b as c } from './util';` 或 `import * as util from './util';`
+///
+/// `source` 保存引号里的模块路径原文（不带 `.ets` 后缀也可以）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportDecl {
+    pub clause: ImportClause,
+    pub source: String,
+    /// 模块路径字符串（含引号）在源码里的位置
+    pub source_span: Span,
+    /// 从 `import` 到分号的位置
+    pub span: NodeSpan,
+}
+
+/// import 导入的内容。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportClause {
+    /// `{ a, b as c }`
+    Named(Vec<ImportSpec>),
+    /// `* as util`：通过 `util.f()` 访问
+    Namespace(String),
+    /// `hilog`（默认导入）：目前只用于系统模块，`import hilog from '@ohos.hilog'`
+    Default(String),
+}
+
+/// 系统模块（`@ohos.*`、`@kit.*`）的 import，见 `ResolveConfig::system_modules`。
+///
+/// `rust_path` 是映射到的 Rust 路径；为 None 表示模块里的 API 由转换器内置处理
+/// （例如 `hilog`、`UIAbility`），import 本身不生成代码。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemImport {
+    pub clause: ImportClause,
+    pub source: String,
+    pub rust_path: Option<String>,
+    /// 原来的 import 声明的位置
+    pub span: NodeSpan,
+}
+
+impl Stmt {
+    /// 语句在源码里的位置；表达式语句取表达式的位置。
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::VarDecl(s) => s.span.0,
+            Stmt::Assign(s) => s.span.0,
+            Stmt::ExprStmt(e) => e.span(),
+            Stmt::Block(b) => b.span.0,
+            Stmt::If(s) => s.span.0,
+            Stmt::While(s) => s.span.0,
+            Stmt::Return(s) => s.span.0,
+            Stmt::FuncDecl(f) => f.span.0,
+            Stmt::Throw(s) => s.span.0,
+            Stmt::Try(s) => s.span.0,
+        }
+    }
+}
+
+impl SystemImport {
+    /// import 在当前模块里引入的名字。
+    pub fn local_names(&self) -> Vec<&str> {
+        match &self.clause {
+            ImportClause::Named(specs) => specs.iter().map(|s| s.local_name()).collect(),
+            ImportClause::Namespace(name) | ImportClause::Default(name) => vec![name],
+        }
+    }
+}
+
+/// 花括号里的一项：`a` 或 `a as b`（import 和 re-export 共用）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportSpec {
+    /// 目标模块导出的名字
+    pub name: String,
+    /// `as` 之后的新名字（没有重命名时为 None）
+    pub alias: Option<String>,
+    /// 从名字到新名字的位置
+    pub span: NodeSpan,
+}
+
+impl ImportSpec {
+    /// 在当前模块里可见的名字。
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// re-export 声明：`export { a, b as c } from './m';`，常见于把多个模块汇总导出的 barrel 文件。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReExportDecl {
+    pub specs: Vec<ImportSpec>,
+    pub source: String,
+    pub source_span: Span,
+}
+
+/// 名字 `name` 指向顶层函数 `target` 的别名。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alias {
+    pub name: String,
+    pub target: String,
+    /// 来自 re-export（库模式下生成 `pub use`）
+    pub is_export: bool,
+}
+
+/// 命名空间导入：`util.f()` 对应 `members` 里名为 `f` 的别名。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Namespace {
+    pub name: String,
+    pub members: Vec<Alias>,
 }
 
 /// 语句（Statement）枚举。
@@ -31,9 +211,8 @@ pub enum Stmt {
     VarDecl(VarDecl),
     /// 赋值语句：`x = expr;`
     ///
-    /// 注意：这里把赋值当作“语句”而不是“表达式”，是为了保持 Step4 的范围最小：
-    /// - 不支持像 `a = b = 1;` 这种链式赋值表达式
-    /// - 只支持最常见的 `Ident = Expr ;`
+    /// 最常见的 `Ident = Expr ;` 单独作为语句保存；链式赋值 `a = b = 1;` 的右侧是 `Expr::Assign`。
+    /// 目标是字段/下标的赋值（`obj.x = 1;`）是表达式语句 `ExprStmt(Expr::Assign)`。
     Assign(AssignStmt),
     /// 表达式语句：`console.log(123);`
     ExprStmt(Expr),
@@ -51,6 +230,17 @@ pub enum Stmt {
     /// Rust 的 main 返回类型是 `()`，因此 `return <expr>;` 的“返回值”在 Rust 中没有意义。
     /// CodeGen 会把它当作“提前结束”处理：先计算 expr（若存在），再 `return;`。
     Return(ReturnStmt),
+    /// 块内函数声明：`{ function helper() { ... } }`
+    ///
+    /// 顶层函数仍然放在 `Program.funcs` 里；只有出现在代码块（包括函数体）内部的函数声明
+    /// 才会成为这种语句。与 JS 一致，它在所在代码块内“提升”（hoisting）：声明之前也能调用。
+    FuncDecl(FuncDecl),
+    /// throw 语句：`throw expr;`
+    Throw(ThrowStmt),
+    /// 异常处理：`try { ... } catch (e) { ... } finally { ... }`
+    ///
+    /// catch 和 finally 至少出现一个。
+    Try(TryStmt),
 }
 
 /// 顶层函数声明结构体（Step6）。
@@ -61,6 +251,10 @@ pub enum Stmt {
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FuncDecl {
+    /// 是否带 `export` 修饰（只有顶层函数可以导出，其它模块才能 import 它）。
+    ///
+    /// 库输出模式下，导出的函数生成为 `pub fn`。
+    pub is_export: bool,
     pub name: String,
     pub params: Vec<Param>,
     /// 返回类型标注（可选）。
@@ -68,6 +262,22 @@ pub struct FuncDecl {
     /// 说明：类型标注只用于 CodeGen，不做完整类型推导/检查。
     pub ret_type: Option<TypeAnn>,
     pub body: BlockStmt,
+    /// 写在函数前面的装饰器（`@Builder function f() {}`），按源码顺序。
+    pub decorators: Vec<Decorator>,
+    /// 从 `function`（或方法名）到函数体结束的位置
+    pub span: NodeSpan,
+}
+
+/// 装饰器：`@Entry`、`@Watch('onChange')`。
+///
+/// 解析器接受任意名字的装饰器；代码生成还不认识的装饰器会被忽略，并记一个 `IgnoredDecorator` 警告。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decorator {
+    pub name: String,
+    /// 括号里的实参；没写括号（`@Entry`）时为空
+    pub args: Vec<Expr>,
+    /// `@Name(...)` 整体在源码里的位置
+    pub span: Span,
 }
 
 /// 函数参数结构体（Step6）。
@@ -76,31 +286,146 @@ pub struct Param {
     pub name: String,
     /// 参数类型标注（可选）。
     pub ty: Option<TypeAnn>,
+    /// 是否为剩余参数：`...args: number[]`
+    ///
+    /// 剩余参数只能是最后一个参数，CodeGen 会把它映射为 Rust 的 `Vec<T>` 参数。
+    pub is_rest: bool,
+    /// 参数（含类型标注）在源码里的位置
+    pub span: NodeSpan,
 }
 
-/// 基础类型标注（Step6）。
+/// 类型标注。
 ///
-/// 本项目只支持 4 个基础类型（与需求一致）：
-/// - number / string / boolean / void
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// 基础类型（Step6）：number / string / boolean / void
+///
+/// 复合类型：
+/// - `T[]`：数组类型，映射为 Rust 的 `Vec<T>`
+/// - `(a: A, b: B) => R`：函数类型（回调），参数位置映射为 `&dyn Fn(A, B) -> R`，
+///   其它位置映射为 `Box<dyn Fn(A, B) -> R>`
+/// - `T | null` / `T | undefined`：可空类型，映射为 `Option<T>`；[`NullKind`] 记下写的是哪一种，
+///   `console.log` 打印缺省值时据此输出 `null` 或 `undefined`
+/// - `Promise<T>`：异步结果，参数位置映射为 `impl Future<Output = Result<T, ArkError>>`，
+///   其它位置映射为 `Pin<Box<dyn Future<Output = Result<T, ArkError>>>>`
+/// - `Date`：时间点，映射为生成代码里的 `ArkDate`（`std::time::SystemTime` 或 chrono 的 `DateTime<Utc>`）
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TypeAnn {
     Number,
     String,
     Boolean,
     Void,
+    Date,
+    Array(Box<TypeAnn>),
+    Function(FuncTypeAnn),
+    Nullable(Box<TypeAnn>, NullKind),
+    Promise(Box<TypeAnn>),
+}
+
+/// 可空类型里缺省值的写法。两者生成的 Rust 类型相同（都是 `None`），只影响打印出来的文字；
+/// `T | null | undefined` 记为 `Null`。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullKind {
+    Null,
+    Undefined,
+}
+
+impl NullKind {
+    /// 缺省值打印出来的文字，与 JS 一致。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NullKind::Null => "null",
+            NullKind::Undefined => "undefined",
+        }
+    }
+}
+
+/// 函数类型标注：`(x: number, y: number) => number`
+///
+/// 参数名只是文档作用，不影响类型，所以这里只保存参数类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuncTypeAnn {
+    pub params: Vec<TypeAnn>,
+    pub ret: Box<TypeAnn>,
 }
 
 /// 变量声明结构体（let/const）。
 ///
-/// Step2 限制：初始化表达式只允许是字面量（Literal）。
+/// 一条声明语句可以声明多个变量：`let a = 1, b = 2;` 的 `decls` 有两项。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct VarDecl {
+    /// 是否带 `export` 修饰（只允许顶层 `export const`）
+    pub is_export: bool,
     /// 是否为常量（const 为 true，let 为 false）
     pub is_const: bool,
-    /// 变量名
-    pub name: String,
-    /// 初始值（支持任意表达式）
-    pub init: Expr,
+    /// 逗号分隔的各个声明项（至少一项）
+    pub decls: Vec<Declarator>,
+    /// 从 `let`/`const` 到分号的位置
+    pub span: NodeSpan,
+}
+
+impl VarDecl {
+    /// 这条语句声明的所有变量名（按出现顺序）。
+    pub fn names(&self) -> Vec<&str> {
+        self.decls.iter().flat_map(|d| d.pattern.names()).collect()
+    }
+}
+
+/// 一个声明项：`x: number = 1`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declarator {
+    /// 绑定模式：普通变量名，或解构模式 `[a, b]` / `{x, y}`
+    pub pattern: Pattern,
+    /// 类型标注（可选）：`let x: string | null = null;`
+    pub ty: Option<TypeAnn>,
+    /// 初始值（支持任意表达式）；`let x: number;` 没有初始值，之后再赋值
+    pub init: Option<Expr>,
+    /// 从模式到初始值结束的位置
+    pub span: NodeSpan,
+}
+
+/// 变量声明左侧的绑定模式。
+///
+/// 例：
+/// - `let x = 1;` -> `Pattern::Ident("x")`
+/// - `const [a, b] = pair;` -> `Pattern::Array([Ident("a"), Ident("b")])`
+/// - `const {x, y: py} = point;` -> `Pattern::Object([{x, Ident("x")}, {y, Ident("py")}])`
+///
+/// 模式可以嵌套：`const [a, [b, c]] = nested;`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// 普通变量名
+    Ident(String),
+    /// 数组解构：按下标依次取元素
+    Array(Vec<Pattern>),
+    /// 对象解构：按字段名取值
+    Object(Vec<PropPattern>),
+}
+
+/// 对象解构里的一项：`key` 或 `key: pattern`。
+///
+/// 简写 `{x}` 等价于 `{x: x}`。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropPattern {
+    /// 被读取的字段名
+    pub key: String,
+    /// 字段值绑定到的模式
+    pub value: Pattern,
+}
+
+impl Pattern {
+    /// 模式里声明的所有变量名（按出现顺序）。
+    pub fn names(&self) -> Vec<&str> {
+        let mut out = Vec::new();
+        self.collect_names(&mut out);
+        out
+    }
+
+    fn collect_names<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Pattern::Ident(name) => out.push(name),
+            Pattern::Array(items) => items.iter().for_each(|p| p.collect_names(out)),
+            Pattern::Object(props) => props.iter().for_each(|p| p.value.collect_names(out)),
+        }
+    }
 }
 
 /// 赋值语句结构体：`name = value;`
@@ -108,12 +433,15 @@ pub struct VarDecl {
 pub struct AssignStmt {
     pub name: String,
     pub value: Expr,
+    pub span: NodeSpan,
 }
 
 /// 代码块结构体：`{ stmt* }`
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BlockStmt {
     pub stmts: Vec<Stmt>,
+    /// 从 `{` 到 `}` 的位置
+    pub span: NodeSpan,
 }
 
 /// if/else 结构体。
@@ -122,6 +450,7 @@ pub struct IfStmt {
     pub cond: Expr,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
+    pub span: NodeSpan,
 }
 
 /// while 结构体。
@@ -129,25 +458,325 @@ pub struct IfStmt {
 pub struct WhileStmt {
     pub cond: Expr,
     pub body: Box<Stmt>,
+    pub span: NodeSpan,
 }
 
 /// return 结构体：可选返回值。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ReturnStmt {
     pub value: Option<Expr>,
+    pub span: NodeSpan,
+}
+
+/// namespace 声明：`namespace Utils { export function f() { .. } }`。
+///
+/// 目前 namespace 里只能放函数；CodeGen 生成 `mod utils { pub fn f() { .. } }`，
+/// 外部通过 `Utils.f()` 调用导出的函数。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamespaceDecl {
+    /// 是否带 `export` 修饰（库模式下生成 `pub mod`，也可以被其它模块 import）
+    pub is_export: bool,
+    pub name: String,
+    pub funcs: Vec<FuncDecl>,
+    pub span: NodeSpan,
+}
+
+/// ArkUI 自定义组件：
+/// ```text
+/// @Entry
+/// @Component
+/// struct Index {
+///   @State message: string = 'Hello';
+///   build() { .. }
+///   aboutToAppear(): void { .. }
+/// }
+/// ```
+///
+/// 成员只能是字段、`build()`、`@Builder` / `@Styles` 方法和普通方法（生命周期方法等），方法里用 `this.x` 访问字段。
+/// CodeGen 生成一个 Rust struct 和带这些方法的 impl。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentDecl {
+    /// 是否带 `export` 修饰
+    pub is_export: bool,
+    pub name: String,
+    /// 写在 struct 前面的装饰器（`@Entry`、`@Component`），按源码顺序
+    pub decorators: Vec<Decorator>,
+    pub fields: Vec<FieldDecl>,
+    /// `build() { .. }` 里的声明式 UI（见 `UiNode`）；没写 `build` 时为 None
+    pub build: Option<Vec<UiNode>>,
+    /// 除 `build` 以外的方法；`is_export` 总是 false，可见性跟随组件
+    pub methods: Vec<FuncDecl>,
+    /// `@Builder item(x: number) { .. }` 方法，在 `build()` 里用 `this.item(1)` 调用；`is_export` 总是 false
+    pub builders: Vec<BuilderDecl>,
+    /// `@Styles fancy() { .. }` 方法，只能在这个组件的 `build()` 里用；`is_export` 总是 false
+    pub styles: Vec<StyleDecl>,
+    pub span: NodeSpan,
+}
+
+impl ComponentDecl {
+    /// 是否带有名为 `name` 的装饰器（例如 `Entry`）。
+    pub fn has_decorator(&self, name: &str) -> bool {
+        self.decorators.iter().any(|d| d.name == name)
+    }
+}
+
+/// `@Builder` 函数：函数体按声明式 UI 语法解析（见 `UiNode`），可以在多个 `build()` 里调用。
+/// ```text
+/// @Builder
+/// function Header(title: string) {
+///   Text(title).fontSize(30)
+/// }
+/// ```
+///
+/// 调用写成 `Header('Home')`（大写开头时解析为 `UiNode::Element`）或 `header('Home')`（普通语句）；
+/// 组件里的 `@Builder` 方法用 `this.item(1)` 调用。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuilderDecl {
+    /// 是否带 `export` 修饰
+    pub is_export: bool,
+    pub name: String,
+    pub params: Vec<Param>,
+    /// 所有装饰器（包括 `@Builder` 本身），按源码顺序
+    pub decorators: Vec<Decorator>,
+    pub body: Vec<UiNode>,
+    /// 从名字到函数体结束的位置
+    pub span: NodeSpan,
+}
+
+/// `@Styles` / `@Extend` 函数：一组可以复用的属性方法，函数体里只能写属性方法。
+/// ```text
+/// @Styles function card() {
+///   .width('100%')
+///   .backgroundColor(Color.White)
+/// }
+///
+/// @Extend(Text) function title(size: number) {
+///   .fontSize(size)
+///   .fontWeight(FontWeight.Bold)
+/// }
+/// ```
+///
+/// 像属性方法一样使用：`Column() { .. }.card()`、`Text('Hi').title(24)`。
+/// `@Styles` 可以用在任何 UI 组件上，不能有参数；`@Extend(Text)` 只能用在 `Text` 上，可以有参数。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StyleDecl {
+    /// 是否带 `export` 修饰
+    pub is_export: bool,
+    pub name: String,
+    pub params: Vec<Param>,
+    /// 所有装饰器（包括 `@Styles` / `@Extend` 本身），按源码顺序
+    pub decorators: Vec<Decorator>,
+    /// `@Extend(Text)` 扩展的 UI 组件名；`@Styles` 为 None
+    pub extend: Option<String>,
+    pub attrs: Vec<UiAttr>,
+    /// 从名字到函数体结束的位置
+    pub span: NodeSpan,
+}
+
+/// 组件的字段：`@State count: number = 0;`
+///
+/// 类型标注和初始值都可以省略（但至少要有一个，见 CodeGen）；没有初始值的字段在创建组件时传入。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDecl {
+    pub decorators: Vec<Decorator>,
+    pub name: String,
+    pub ty: Option<TypeAnn>,
+    pub init: Option<Expr>,
+    /// 从名字到分号的位置（不含装饰器）
+    pub span: NodeSpan,
+}
+
+impl FieldDecl {
+    /// 是否带有名为 `name` 的装饰器（例如 `State`）。
+    pub fn has_decorator(&self, name: &str) -> bool {
+        self.decorators.iter().any(|d| d.name == name)
+    }
+}
+
+/// `build()` 里的声明式 UI 语法：
+/// ```text
+/// build() {
+///   Column({ space: 10 }) {
+///     Text(this.message)
+///       .fontSize(20)
+///     if (this.count > 0) {
+///       Button('Reset').onClick(() => { this.count = 0; })
+///     }
+///   }
+/// }
+/// ```
+///
+/// 以大写字母开头、后面紧跟 `(` 的名字是 UI 组件（`Column`、`Text`、自定义组件、`ForEach`）；
+/// 其它的按普通语句解析，这样 `build` 里也可以写 `console.log(..)`。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UiNode {
+    /// UI 组件：`Text(this.message).fontSize(20)`
+    Element(UiElement),
+    /// 条件渲染：`if (cond) { .. } else { .. }`，两个分支里都是 UI
+    If(UiIf),
+    /// 普通语句
+    Stmt(Stmt),
+}
+
+impl UiNode {
+    /// 节点在源码里的位置。
+    pub fn span(&self) -> Span {
+        match self {
+            UiNode::Element(e) => e.span.0,
+            UiNode::If(i) => i.span.0,
+            UiNode::Stmt(s) => s.span(),
+        }
+    }
+}
+
+/// UI 组件：`Name(args) { children }.attr(args)...`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiElement {
+    pub name: String,
+    pub args: Vec<UiArg>,
+    /// 尾随的 `{ .. }` 里的子节点；没写时为空
+    pub children: Vec<UiNode>,
+    /// 链式调用的属性方法：`.fontSize(20).width('100%')`，按源码顺序
+    pub attrs: Vec<UiAttr>,
+    /// 从组件名到最后一个属性方法的位置
+    pub span: NodeSpan,
+}
+
+/// 属性方法：`.fontSize(20)`、`.onClick(() => { .. })`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiAttr {
+    pub name: String,
+    pub args: Vec<UiArg>,
+    /// 从 `.` 到 `)` 的位置
+    pub span: NodeSpan,
+}
+
+/// UI 组件和属性方法的实参。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UiArg {
+    /// 普通表达式：`this.message`、`20`
+    Expr(Expr),
+    /// 对象字面量：`{ space: 10 }`、传给自定义组件的 `{ label: 'x', count: this.count }`
+    Object(Vec<(String, Expr)>),
+    /// 箭头函数：`() => { this.count = 0; }`、`(item: string) => { Text(item) }`
+    Handler(UiHandler),
+}
+
+/// 箭头函数实参。函数体同样按 UI 语法解析（`ForEach` 的第二个参数里是 UI）；
+/// `=> expr` 简写的函数体是只有一个节点的列表。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiHandler {
+    pub params: Vec<Param>,
+    pub body: Vec<UiNode>,
+    pub span: NodeSpan,
+}
+
+/// UI 里的条件渲染。`else if` 表示为 else 分支里只有一个 `UiNode::If`。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiIf {
+    pub cond: Expr,
+    pub then_branch: Vec<UiNode>,
+    pub else_branch: Vec<UiNode>,
+    pub span: NodeSpan,
+}
+
+/// class 声明：`class Name extends Base {}`。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassDecl {
+    /// 是否带 `export` 修饰
+    pub is_export: bool,
+    pub name: String,
+    /// 父类名（可选）
+    pub extends: Option<String>,
+    /// 写在 class 前面的装饰器，按源码顺序（见 `Decorator`）。
+    pub decorators: Vec<Decorator>,
+    /// 外部声明的类：`declare class`。没有父类时表示外部的类型，不生成代码（不是异常类）
+    pub is_declare: bool,
+    /// `declare class` 里的方法签名：`bar(x: number): string;`，函数体为空。普通 class 的类体必须为空，这里总是空的
+    pub methods: Vec<FuncDecl>,
+    /// `declare class` 里的字段签名：`name: string;`，没有初始值
+    pub fields: Vec<FieldDecl>,
+    pub span: NodeSpan,
+}
+
+/// 继承 `UIAbility` 的类（HarmonyOS 应用的 Ability 入口文件）：
+/// ```text
+/// export default class EntryAbility extends UIAbility {
+///   onCreate(want: Want, launchParam: AbilityConstant.LaunchParam): void { .. }
+///   onWindowStageCreate(windowStage: window.WindowStage): void {
+///     windowStage.loadContent('pages/Index');
+///   }
+/// }
+/// ```
+///
+/// 生命周期方法（见 `ABILITY_LIFECYCLE`）的参数类型由运行时决定，源码里的类型标注只检查写法、不保存（`ty` 为 None）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbilityDecl {
+    /// 是否带 `export`（或 `export default`）修饰
+    pub is_export: bool,
+    pub name: String,
+    /// 写在 class 前面的装饰器，按源码顺序
+    pub decorators: Vec<Decorator>,
+    /// 所有方法，包括生命周期方法和普通方法；`is_export` 总是 false
+    pub methods: Vec<FuncDecl>,
+    pub span: NodeSpan,
+}
+
+/// `UIAbility` 的生命周期方法名，按系统调用的顺序。
+pub const ABILITY_LIFECYCLE: [&str; 6] = [
+    "onCreate",
+    "onWindowStageCreate",
+    "onForeground",
+    "onBackground",
+    "onWindowStageDestroy",
+    "onDestroy",
+];
+
+/// throw 结构体：抛出的值。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThrowStmt {
+    pub value: Expr,
+    pub span: NodeSpan,
+}
+
+/// try 结构体。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryStmt {
+    /// `try { ... }` 里的语句
+    pub block: BlockStmt,
+    /// `catch (e) { ... }`（可选）
+    pub catch: Option<CatchClause>,
+    /// `finally { ... }`（可选）
+    pub finally: Option<BlockStmt>,
+    pub span: NodeSpan,
+}
+
+/// catch 子句：`catch (e) { ... }`，参数可以省略：`catch { ... }`。
+///
+/// 参数可以带异常类标注：`catch (e: NotFound) { ... }`，只捕获该类（及其子类）的异常。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatchClause {
+    pub param: Option<String>,
+    /// 只捕获的异常类名（可选）
+    pub ty: Option<String>,
+    pub body: BlockStmt,
+    /// 从 `catch` 到代码块结束的位置
+    pub span: NodeSpan,
 }
 
 /// 表达式（Expression）枚举。
 ///
-/// Step2/Step3 的最小表达式集：
-/// - 字面量：number/string/boolean
-/// - 函数调用：仅支持 console.log(literal)
+/// 子集支持的表达式：
+/// - 字面量（number / string / boolean / `null` / `undefined`，见 `Literal`）和标识符
+/// - 一元、二元运算（算术、比较、`&&` / `||` / `??`），括号
+/// - 函数调用：普通函数、`console.*`、命名空间里的函数、方法调用、调用返回的函数（见 `Callee`），实参可以展开
+/// - `new` 异常对象、赋值表达式、属性读取和下标读取
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Expr {
-    /// 字面量表达式：123, "abc", true
-    Literal(Literal),
-    /// 标识符引用：`x`
-    Ident(String),
+    /// 字面量表达式：123, "abc", true（第二项是字面量的位置）
+    Literal(Literal, NodeSpan),
+    /// 标识符引用：`x`（第二项是标识符的位置）
+    Ident(String, NodeSpan),
     /// 一元运算：`!x`、`-x`
     Unary(UnaryExpr),
     /// 二元运算：`a + b`、`a && b` 等
@@ -156,8 +785,93 @@ pub enum Expr {
     ///
     /// 说明：如果不把括号保存进 AST，CodeGen 很容易丢失用户写的括号，导致语义变化。
     Group(Box<Expr>),
-    /// 函数调用表达式：console.log(...)
+    /// 函数调用表达式：`f(x)`、`console.log(..)`、`s.trim()`，被调用的是什么见 `Callee`
     Call(CallExpr),
+    /// 展开表达式：`...xs`
+    ///
+    /// 只允许出现在函数调用的实参列表里（`f(...xs)`），由 Parser 保证。
+    Spread(Box<Expr>),
+    /// 创建对象：`new NotFound("msg")`
+    ///
+    /// 目前只能创建异常类的实例，并且只能作为 throw 的值。
+    New(NewExpr),
+    /// 赋值表达式：`a = b = 0`、`(x = next()) != 0`、`obj.x = 1`、`arr[i] = 2`
+    ///
+    /// 和 TS 一样，表达式的值就是赋给目标的值。
+    Assign(AssignExpr),
+    /// 属性读取：`s.length`、`xs.length`、`this.count`、`e.message`、`Math.PI`
+    ///
+    /// 方法调用见 `Callee::Method`。
+    Member(MemberExpr),
+    /// 下标读取：`xs[i]`、`s[0]`
+    Index(IndexExpr),
+}
+
+impl Expr {
+    /// 表达式在源码里的位置；括号和展开取里面的表达式的位置。
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal(_, span) | Expr::Ident(_, span) => span.0,
+            Expr::Group(inner) | Expr::Spread(inner) => inner.span(),
+            Expr::Unary(e) => e.span.0,
+            Expr::Binary(e) => e.span.0,
+            Expr::Call(e) => e.span.0,
+            Expr::New(e) => e.span.0,
+            Expr::Assign(e) => e.span.0,
+            Expr::Member(e) => e.span.0,
+            Expr::Index(e) => e.span.0,
+        }
+    }
+
+    /// 表达式节点的编号（见 `NodeId`）；括号和展开取里面的表达式的编号。
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expr::Literal(_, span) | Expr::Ident(_, span) => span.1,
+            Expr::Group(inner) | Expr::Spread(inner) => inner.id(),
+            Expr::Unary(e) => e.span.1,
+            Expr::Binary(e) => e.span.1,
+            Expr::Call(e) => e.span.1,
+            Expr::New(e) => e.span.1,
+            Expr::Assign(e) => e.span.1,
+            Expr::Member(e) => e.span.1,
+            Expr::Index(e) => e.span.1,
+        }
+    }
+}
+
+/// 下标读取表达式结构体：`object[index]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexExpr {
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+    pub span: NodeSpan,
+}
+
+/// 属性读取表达式结构体：`object.property`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberExpr {
+    pub object: Box<Expr>,
+    pub property: String,
+    pub span: NodeSpan,
+}
+
+/// 赋值表达式结构体：`target = value`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssignExpr {
+    pub target: AssignTarget,
+    pub value: Box<Expr>,
+    pub span: NodeSpan,
+}
+
+/// 赋值目标（赋值号左边能写什么）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssignTarget {
+    /// 变量：`x = ...`
+    Ident(String),
+    /// 字段：`obj.x = ...`
+    Member { object: String, property: String },
+    /// 下标：`arr[i] = ...`
+    Index { object: String, index: Box<Expr> },
 }
 
 /// 一元表达式结构体。
@@ -165,6 +879,7 @@ pub enum Expr {
 pub struct UnaryExpr {
     pub op: UnaryOp,
     pub expr: Box<Expr>,
+    pub span: NodeSpan,
 }
 
 /// 一元运算符枚举。
@@ -180,6 +895,7 @@ pub struct BinaryExpr {
     pub op: BinaryOp,
     pub left: Box<Expr>,
     pub right: Box<Expr>,
+    pub span: NodeSpan,
 }
 
 /// 二元运算符枚举。
@@ -198,6 +914,8 @@ pub enum BinaryOp {
     GtEq,
     AndAnd,
     OrOr,
+    /// 空值合并 `??`：左侧为 null/undefined 时取右侧
+    NullishCoalesce,
 }
 
 /// 函数调用表达式结构体。
@@ -205,28 +923,103 @@ pub enum BinaryOp {
 /// Step2 约束：只支持一个参数，并且参数必须是字面量。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CallExpr {
-    /// 被调用的函数（目前只能是 console.log）
+    /// 被调用的函数
     pub callee: Callee,
     /// 参数列表（目前只支持一个参数）
     pub args: Vec<Expr>,
+    pub span: NodeSpan,
+}
+
+/// new 表达式结构体。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewExpr {
+    /// 类名
+    pub class: String,
+    /// 构造参数
+    pub args: Vec<Expr>,
+    pub span: NodeSpan,
 }
 
 /// 被调用者枚举。
-/// Step2 仅支持 `console.log`。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Callee {
-    ConsoleLog,
+    /// `console.log` / `console.error` / ...
+    Console(ConsoleFn),
     Ident(String),
+    /// `Promise.resolve` / `Promise.reject` / `Promise.all`
+    Promise(PromiseFn),
+    /// `util.f(...)`：通过命名空间调用函数
+    ///
+    /// Parser 分不清 `x` 是命名空间还是变量，`s.trim()` 也会解析成这种形式，由 CodeGen 区分。
+    Member { object: String, property: String },
+    /// `expr.method(...)`：在任意表达式的值上调用方法（`"a b".split(" ")`、`s.trim().toUpperCase()`）
+    Method { object: Box<Expr>, method: String },
+    /// `f(x)(y)`：调用另一个调用的结果（返回函数的工厂函数、柯里化函数）
+    Expr(Box<Expr>),
+}
+
+/// `console` 上的输出方法。
+///
+/// `error`/`warn` 写到标准错误（`eprintln!`），其余写到标准输出（`println!`）。
+/// `assert` 的第一个实参是条件，其余实参是失败时的消息（生成 `assert!`）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleFn {
+    Log,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Assert,
+}
+
+impl ConsoleFn {
+    /// 源码里的方法名：`log`、`error` 等。
+    pub fn name(self) -> &'static str {
+        match self {
+            ConsoleFn::Log => "log",
+            ConsoleFn::Error => "error",
+            ConsoleFn::Warn => "warn",
+            ConsoleFn::Info => "info",
+            ConsoleFn::Debug => "debug",
+            ConsoleFn::Assert => "assert",
+        }
+    }
+}
+
+/// `Promise` 上的内置静态方法。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromiseFn {
+    Resolve,
+    Reject,
+    All,
+}
+
+impl PromiseFn {
+    /// 源码里的方法名：`resolve`、`reject`、`all`。
+    pub fn name(self) -> &'static str {
+        match self {
+            PromiseFn::Resolve => "resolve",
+            PromiseFn::Reject => "reject",
+            PromiseFn::All => "all",
+        }
+    }
 }
 
 /// 字面量（Literal）枚举。
 /// 对应 ArkTS 的基础类型值。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Literal {
-    /// 数字字面量（i32）
+    /// 数字字面量（i32），包括值是整数的指数写法：`1e3`
     Number(i32),
+    /// 小数字面量，保存源码里的写法：`0.5`、`2.5e-7`（只有 f64 数字模式能用）；
+    /// 超出 i32 范围的整数也是源码里的写法（十六进制换成十进制）：`3000000000`、`1e21`
+    Float(String),
     /// 字符串字面量
     String(String),
     /// 布尔字面量
     Bool(bool),
+    /// `null`：映射为 Rust 的 `None`
+    Null,
+    /// `undefined`：与 `null` 一样映射为 `None`
+    Undefined,
 }
@@ -0,0 +1,109 @@
+use crate::codegen::{configured_crates, uses_crate, CodegenOptions, ExternBindings, OutputFile, OutputMode, EXTERNAL_CRATES};
+use crate::runtime;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// 把生成的文件组装成一个完整的 Cargo 项目（CLI 的 `--cargo-project <dir>`）：
+/// `Cargo.toml`（见 `manifest`）加上 `src/` 下的源码文件。
+///
+/// `files` 是 `codegen::generate_tree` 的结果（或者 `generate_with` 生成的单个文件），第一个文件是 crate 根，
+/// 放到 `src/main.rs`（库和原生模块是 `src/lib.rs`），其它模块文件按原来的相对路径放在 `src/` 下。
+/// 代码用到了运行时 crate `runtime::CRATE_NAME`（见 `CodegenOptions::runtime`）时，把它放在项目的 `arkts_runtime/` 下。
+pub fn project(
+    name: &str,
+    files: &[OutputFile],
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Vec<OutputFile> {
+    let root = match mode {
+        OutputMode::Binary => "main.rs",
+        OutputMode::Library | OutputMode::NativeModule => "lib.rs",
+    };
+    let mut out = vec![OutputFile {
+        path: PathBuf::from("Cargo.toml"),
+        code: manifest(name, files, mode, externs, options),
+    }];
+    for (i, file) in files.iter().enumerate() {
+        let path = if i == 0 { Path::new(root) } else { &file.path };
+        out.push(OutputFile {
+            path: Path::new("src").join(path),
+            code: file.code.clone(),
+        });
+    }
+    if uses_runtime(files, options) {
+        for file in runtime::project(options.edition) {
+            out.push(OutputFile {
+                path: Path::new(runtime::CRATE_NAME).join(file.path),
+                code: file.code,
+            });
+        }
+    }
+    out
+}
+
+/// 生成的代码对应的 `Cargo.toml`：
+/// - `[package]`：包名是 `package_name(name)`，edition 是 `CodegenOptions::edition`
+/// - 原生模块加 `[lib] crate-type = ["cdylib"]`，依赖 `napi-ohos` 和 `napi-derive-ohos`
+/// - `[dependencies]`：代码里用到的外部 crate（`rand`、`serde_json` 等，见 `codegen::EXTERNAL_CRATES`）；
+///   `--bind` 等配置的路径所在的 crate 不知道版本和来源，写成注释，由用户补全；
+///   运行时 crate 是 `project` 放进项目的 path 依赖
+pub fn manifest(
+    name: &str,
+    files: &[OutputFile],
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> String {
+    let used = |c: &str| files.iter().any(|f| uses_crate(&f.code, c));
+    let mut out = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{}\"\n",
+        package_name(name),
+        options.edition.as_str()
+    );
+    if mode == OutputMode::NativeModule {
+        out.push_str("\n[lib]\ncrate-type = [\"cdylib\"]\n");
+    }
+    out.push_str("\n[dependencies]\n");
+    for (krate, dep) in EXTERNAL_CRATES {
+        // `#[napi]` 展开后的代码要用 `napi_ohos`，原生模块总是依赖这两个 crate
+        let napi = mode == OutputMode::NativeModule && krate.starts_with("napi");
+        if used(krate) || napi {
+            out.push_str(dep);
+            out.push('\n');
+        }
+    }
+    if uses_runtime(files, options) {
+        out.push_str(&format!("{0} = {{ path = \"{0}\" }}\n", runtime::CRATE_NAME));
+    }
+    let configured: BTreeSet<&str> = configured_crates(externs, options)
+        .filter(|c| !EXTERNAL_CRATES.iter().any(|(known, _)| known == c) && *c != runtime::CRATE_NAME)
+        .filter(|c| used(c))
+        .collect();
+    for krate in configured {
+        out.push_str(&format!("# {krate} = \"*\"  # used by the configured Rust paths: fill in a version or path\n"));
+    }
+    out
+}
+
+/// `CodegenOptions::runtime` 是运行时 crate（或者它里面的路径），而且代码里用到了它。
+fn uses_runtime(files: &[OutputFile], options: &CodegenOptions) -> bool {
+    let configured = options.runtime.as_deref().and_then(|path| path.split("::").next()) == Some(runtime::CRATE_NAME);
+    configured && files.iter().any(|f| uses_crate(&f.code, runtime::CRATE_NAME))
+}
+
+/// 合法的 Cargo 包名：字母、数字、`-`、`_` 以外的字符换成 `-`，全部小写；
+/// 结果为空时是 `app`，以数字开头时加上 `app-` 前缀。
+///
+/// 例：`My App` -> `my-app`，`2048` -> `app-2048`。
+pub fn package_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    match name.trim_matches('-') {
+        "" => "app".to_string(),
+        n if n.starts_with(|c: char| c.is_ascii_digit()) => format!("app-{n}"),
+        n => n.to_string(),
+    }
+}
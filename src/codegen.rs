@@ -1,9 +1,25 @@
 use crate::ast::{
-    AssignStmt, BinaryExpr, BinaryOp, BlockStmt, Callee, CallExpr, Expr, FuncDecl, IfStmt, Literal,
-    Param, Program, ReturnStmt, Stmt, TypeAnn, UnaryExpr, UnaryOp, VarDecl, WhileStmt,
+    AbilityDecl, AssignExpr, AssignStmt, AssignTarget, BinaryExpr, BinaryOp, BlockStmt, BuilderDecl, Callee, CallExpr, CatchClause, Comments, ComponentDecl, ConsoleFn, Declarator, Expr, FieldDecl, FuncDecl,
+    FuncTypeAnn, IfStmt, ImportClause, Literal, NamespaceDecl, NewExpr, Param, Pattern, Program, PromiseFn, ReturnStmt, Stmt, StyleDecl, SystemImport, ThrowStmt, TryStmt,
+    TypeAnn, UiArg, UiAttr, UiElement, UiNode, UnaryExpr, UnaryOp, VarDecl, WhileStmt, ABILITY_LIFECYCLE,
 };
-use crate::error::Error;
-use crate::span::Span;
+use crate::error::{Error, ErrorCode, Warning};
+use crate::flow;
+use crate::lexer::Comment;
+use crate::layout::{self, Formatter, LayoutOptions};
+use crate::module::{Unit, Use};
+use crate::rename::{self, NameMap};
+use crate::runtime;
+use crate::sema;
+use crate::source_map::{self, SourceMap};
+use crate::typeck;
+use crate::span::{NodeSpan, Span};
+use crate::ui::{UiBackendRef, UiCall, UiCode};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
 
 /// CodeGen 的对外入口：把 AST（Program）生成 Rust 源码字符串。
 ///
@@ -11,8 +27,581 @@ use crate::span::Span;
 /// - 只处理 Step2/Step4 的 AST：变量声明、赋值、表达式（含优先级）、函数调用。
 /// - 生成“完整 Rust 程序”，因此总是输出 `fn main(){ ... }` 结构。
 /// - 这里的输出是字符串，是否写入文件由 CLI（main.rs）负责。
+///
+/// 不提供 `proc_macro2::TokenStream` 形式的输出：CodeGen 一边遍历 AST 一边拼字符串，
+/// 要直接从 AST 构造 token 就得再写一份完整的后端，只把字符串再解析一遍又没有意义。
+/// 需要结构化结果的构建工具用 `syn::parse_file` 解析这里返回的代码。
 pub fn generate(program: &Program) -> Result<String, Error> {
-    Ok(gen_program(program)?)
+    gen_program(program)
+}
+
+/// 输出形式。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// 可执行程序：顶层语句放进 `fn main()`，所有函数都是私有的
+    #[default]
+    Binary,
+    /// 库：没有 `fn main()`，`export` 的函数/常量生成为 `pub` 项，`ArkError` 也是 `pub`。
+    ///
+    /// 库没有地方执行顶层代码，所以顶层只允许 `const NAME = <字面量>;`，
+    /// 生成 Rust 的 `const NAME: T = ..;`。
+    Library,
+    /// HarmonyOS 原生模块：在库的基础上，给每个导出函数加一个 napi 包装（见 `gen_napi_exports`），
+    /// 编译成 `cdylib` 后 ArkTS 可以 `import native from 'libentry.so'` 调回这些函数。
+    ///
+    /// 生成的代码依赖 `napi-ohos` 和 `napi-derive-ohos` 两个 crate。
+    NativeModule,
+}
+
+impl OutputMode {
+    /// 没有 `main`、导出项是 `pub` 的输出形式
+    fn is_library(self) -> bool {
+        matches!(self, OutputMode::Library | OutputMode::NativeModule)
+    }
+}
+
+/// 按指定的输出形式生成 Rust 源码。
+pub fn generate_as(program: &Program, mode: OutputMode) -> Result<String, Error> {
+    generate_with(program, mode, &ExternBindings::new(), &CodegenOptions::default())
+}
+
+/// 代码生成选项。默认值就是推荐配置。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// `console.log` 等一律用 `{:?}` 调试格式输出（字符串带引号、可空值输出 `Some(..)`/`None`）。
+    ///
+    /// 默认关闭：按 JS 的样子输出，让生成程序的输出和直接运行 ArkTS 一致。
+    pub debug_format: bool,
+    /// `Math.random()` 生成 `rand::random::<f64>()`（生成的代码要依赖 `rand` crate）。
+    ///
+    /// 默认关闭：在输出里附带一个不依赖第三方 crate 的 xorshift 随机数函数 `__ark_random`。
+    pub use_rand: bool,
+    /// 支持 `JSON.stringify` / `JSON.parse`，生成 `serde_json` 调用（生成的代码要依赖 `serde_json` crate）。
+    ///
+    /// 默认关闭：这时使用 `JSON` 报 `JsonNeedsSerde`。
+    pub use_serde_json: bool,
+    /// `Date` 生成 chrono 代码：`ArkDate` 是 `chrono::DateTime<chrono::Utc>`（生成的代码要依赖 `chrono` crate）。
+    ///
+    /// 默认关闭：`ArkDate` 是 `std::time::SystemTime`，格式化等用输出里附带的辅助函数实现。
+    pub use_chrono: bool,
+    /// `console.assert` / `assert` 生成 `debug_assert!`：只在 debug 构建里检查。
+    ///
+    /// 默认关闭：生成 `assert!`，release 构建里也检查。
+    pub debug_assert: bool,
+    /// `parseInt` / `parseFloat` / `Number` 转换失败（JS 里得到 NaN）时的处理方式。
+    pub nan: NanMode,
+    /// ArkTS 的 number 映射成哪种 Rust 类型。
+    pub number_mode: NumberMode,
+    /// ArkTS 的 string 映射成哪种 Rust 类型，见 `StringMode`。
+    pub string_mode: StringMode,
+    /// number 是 i32 时，`/` 按 JS 的语义得到小数：`7 / 2` 是 3.5（生成 `7.0 / 2.0`），
+    /// 结果按浮点数处理（见 `is_float_expr`），可以用 `Math.floor` 等转回整数。
+    ///
+    /// 默认关闭：生成 Rust 的整数除法（`7 / 2` 是 3）。`%` 不受影响：整数的 `%` 和 JS 的结果相同（符号跟被除数），
+    /// 只有除数是 0 时不同（JS 是 NaN）；f64 数字模式和浮点数的 `%` 和 JS 一样得到 NaN。
+    ///
+    /// 整数 `/` / `%` 的除数是常量 0 时报 `IntegerDivisionByZero`；运行时才是 0 的，`ArithMode::Plain` /
+    /// `ArithMode::Wrapping` 下 panic，`ArithMode::Checked` 下抛出 `ArkError::Error`。
+    pub js_division: bool,
+    /// number 是 i32 时整数 `+ - * / %` 和取负溢出的处理方式，见 `ArithMode`。
+    pub arith: ArithMode,
+    /// if/while 的条件按 JS 的 truthy 规则转成 bool，见 `gen_condition`；类型检查对不是 boolean 的条件
+    /// 只给 `TruthyCondition` 警告（见 `typeck::warnings`）。
+    ///
+    /// 默认关闭：条件原样生成，类型检查对不是 boolean 的条件报 `ConditionMustBeBool`。
+    pub truthy_conditions: bool,
+    /// 组件 `build()` 里的 UI 组件用哪个后端生成代码（见 `UiBackend`），例如 `UiBackendRef::by_name("egui")`。
+    ///
+    /// 默认不配置：UI 组件只作为注释列在 `build` 前面，`build` 里只执行普通语句（见 `gen_ui_outline`）。
+    pub ui_backend: Option<UiBackendRef>,
+    /// 提供 `UIAbility` trait 和 `Want`、`LaunchParam`、`WindowStage` 类型的 Rust 模块路径（例如 `ohos_runtime::ability`），
+    /// 生成的 Ability 类用 `use` 引入它们（见 `gen_ability`）。
+    ///
+    /// 默认不配置：用运行时里的最小实现（见 `runtime::ABILITY_HELPERS`），`loadContent` 只打印页面路径。
+    pub ability_runtime: Option<String>,
+    /// `hilog.info(..)` 等 HarmonyOS 日志调用生成哪种 Rust 代码，见 `gen_hilog_call`。
+    pub hilog: HilogMode,
+    /// 资源引用 `$r('app.string.title')` / `$rawfile('logo.png')` 调用的 Rust 函数路径，
+    /// 函数接收资源名（`&str`）、返回 `String`，见 `gen_resource_ref`。
+    ///
+    /// 默认不配置：资源引用生成资源名的字符串常量。
+    pub resource_lookup: Option<String>,
+    /// 函数名、变量名、参数名改成 Rust 习惯的 snake_case：`fooBar` -> `foo_bar`（见 `rename::snake_case_map`）。
+    ///
+    /// 默认关闭：保持 ArkTS 里的 camelCase 名字，生成的代码会有 rustc 的 `non_snake_case` 警告。
+    /// 原生模块给 JS 用的导出名不受影响。
+    pub snake_case: bool,
+    /// 生成的代码使用的 Rust edition，见 `Edition`。
+    pub edition: Edition,
+    /// 生成的代码交给 `prettyplease` 或 `rustfmt` 格式化，见 `layout::Formatter`。
+    pub formatter: Formatter,
+    /// 库代码（`OutputMode::Library` / `NativeModule`）里 const 声明以外的顶层语句不报 `TopLevelCodeInLibrary`，
+    /// 而是跳过，只给出 `TopLevelCodeSkipped` 警告（见 `library_warnings`）。
+    ///
+    /// 默认关闭：库没有地方执行顶层代码，有这样的语句时编译失败。
+    pub skip_top_level_code: bool,
+    /// 输出代码的缩进、左大括号位置和文件末尾的换行，见 `layout::LayoutOptions`。
+    pub layout: LayoutOptions,
+    /// 提供数字转换、`Date`、`Math.random()` 辅助函数和组件状态类型的 Rust 模块路径（例如 `arkts_runtime`，见 `runtime::project`），
+    /// 生成的代码用 `use` 引入用到的项，不再附带它们的定义（见 `runtime`）。
+    ///
+    /// 默认不配置：用到的辅助项的定义写在输出文件开头。
+    pub runtime: Option<String>,
+    /// 原样加在生成的文件最开头的代码，例如 `#![allow(dead_code)]`、`use` 语句和辅助函数；模块树输出只加在 crate 根。
+    /// 它和后面的代码之间空一行，之后才格式化和排版（见 `formatter`、`layout`）。
+    ///
+    /// 默认不配置。
+    pub prelude: Option<String>,
+    /// 用户提供的内置函数映射（见 `Intrinsics`）：调用表里的被调用者时按模板生成代码，代替内置的生成规则。
+    /// 被同名声明遮蔽的名字（例如自己声明的 `Math` 变量）不受影响。
+    ///
+    /// 默认为空：全部用内置的生成规则。
+    pub intrinsics: Intrinsics,
+    /// 保留源码里的注释（见 `ast::Comments`）：语句和声明前面的注释生成在对应的 Rust 代码前面，
+    /// 语句行尾的注释跟在生成的最后一行后面，都写成 `//` 注释。
+    ///
+    /// 默认关闭：生成的代码里只有 CodeGen 自己加的注释。
+    pub comments: bool,
+    /// 函数、方法、组件和 class 前面的 JSDoc 注释（`/** .. */`）生成 `///` 文档注释：
+    /// `@param` 生成 `# Arguments` 一节，`@returns` 生成 `# Returns` 一节。
+    ///
+    /// 默认关闭。和 `comments` 互不影响：只打开这一项时其它注释不输出。
+    pub doc_comments: bool,
+    /// 每条语句、每个函数前面加一行源码位置注释 `// main.ets:3`，方便对照生成的代码和原来的 ArkTS。
+    /// 多个文件合并成一个输出文件时不知道代码来自哪个文件，只写行号：`// line 3`。
+    ///
+    /// 默认关闭。
+    pub provenance: bool,
+}
+
+/// hilog 日志的输出方式，见 `CodegenOptions::hilog`。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HilogMode {
+    /// `println!` / `eprintln!`（默认）：不依赖第三方 crate，`warn` 以上的级别写到标准错误
+    #[default]
+    Println,
+    /// `log::info!` 等（生成的代码要依赖 `log` crate）
+    Log,
+    /// `tracing::info!` 等（生成的代码要依赖 `tracing` crate）
+    Tracing,
+}
+
+/// 整数运算的语义，见 `CodegenOptions::arith`。只影响 number 是 i32 时两边都是 number 的整数运算，
+/// f64 数字模式、字符串拼接和浮点数运算不受影响。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArithMode {
+    /// 普通运算符 `a + b`（默认）：debug 构建里溢出会 panic，release 构建里回绕
+    #[default]
+    Plain,
+    /// `a.wrapping_add(b)`：溢出时总是回绕，不会 panic
+    Wrapping,
+    /// `a.checked_add(b)`：溢出（以及除以 0）时和 `throw` 一样抛出 `ArkError::Error`，可以被 try/catch 接住
+    Checked,
+}
+
+/// string 的映射方式，见 `CodegenOptions::string_mode`。
+///
+/// 字符串拼接在 `Owned` 以外的模式下生成 `format!("{}{}", a, b)`；
+/// 字符串方法的结果（`s.trim()` 等）仍然是 `String`，存进 string 类型的变量、参数、返回值时按模式转换（见 `gen_string_as`）。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StringMode {
+    /// `String`（默认）：字面量写成 `String::from("..")`
+    #[default]
+    Owned,
+    /// `&'static str`：字面量直接写成 `".."`，不分配内存；库代码里可以有字符串常量。
+    /// 只适合所有字符串值都来自字面量的程序，把拼接、字符串方法等运行时生成的字符串存进 string 变量报 `StaticStringNotLiteral`
+    Static,
+    /// `Rc<str>`：字面量写成 `Rc::<str>::from("..")`，复制一个值只增加引用计数，适合在多处共享的字符串
+    Shared,
+}
+
+impl StringMode {
+    /// string 对应的 Rust 类型名。
+    fn rust_type(self) -> &'static str {
+        match self {
+            StringMode::Owned => "String",
+            StringMode::Static => "&'static str",
+            StringMode::Shared => "std::rc::Rc<str>",
+        }
+    }
+
+    /// 字符串字面量，`escaped` 已经按 Rust 字符串的规则转义。
+    fn literal(self, escaped: &str) -> String {
+        match self {
+            StringMode::Owned => format!("String::from(\"{escaped}\")"),
+            StringMode::Static => format!("\"{escaped}\""),
+            StringMode::Shared => format!("std::rc::Rc::<str>::from(\"{escaped}\")"),
+        }
+    }
+}
+
+/// 生成的代码使用的 Rust edition，见 `CodegenOptions::edition`。
+///
+/// - 2015：外部 crate（`rand`、`serde_json`、`--bind` 绑定的路径等）在 crate 根写 `extern crate`，
+///   其它模块文件里用 `use` 引入；`Promise.all` 要用 async 块，报 `AsyncNeedsEdition2018`
+/// - 2018 及以上：不需要 `extern crate`
+///
+/// `rustfmt` 格式化（见 `layout::Formatter`）也按这里的 edition 解析代码。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Edition {
+    E2015,
+    E2018,
+    /// 默认
+    #[default]
+    E2021,
+    E2024,
+}
+
+impl Edition {
+    /// 写进 `Cargo.toml`、传给 `rustfmt` / `rustc` 的 `--edition` 的形式：`"2021"`。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Edition::E2015 => "2015",
+            Edition::E2018 => "2018",
+            Edition::E2021 => "2021",
+            Edition::E2024 => "2024",
+        }
+    }
+}
+
+/// 从 `"2021"` 这样的年份解析；不认识的返回 `Err(())`。
+impl FromStr for Edition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        [Edition::E2015, Edition::E2018, Edition::E2021, Edition::E2024]
+            .into_iter()
+            .find(|e| e.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// number 的映射方式，见 `CodegenOptions::number_mode`。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    /// `i32`（默认）：整数运算更快，生成的代码更像手写的 Rust；
+    /// 除法是整数除法，小数字面量和 `Math.PI` 不能用，浮点数只来自 `Math.random()` 等（见 `is_float_expr`）
+    #[default]
+    I32,
+    /// `f64`：和 ArkTS 一样所有数字都是浮点数，字面量写成 `1f64`，`7 / 2` 得到 3.5
+    F64,
+}
+
+impl NumberMode {
+    /// number 对应的 Rust 类型名。
+    fn rust_type(self) -> &'static str {
+        match self {
+            NumberMode::I32 => "i32",
+            NumberMode::F64 => "f64",
+        }
+    }
+}
+
+/// 数字转换失败时的处理方式，见 `CodegenOptions::nan`。
+///
+/// number 是 i32 时放不下 NaN，只能换成别的行为。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NanMode {
+    /// 得到 0（默认）；`NumberMode::F64` 时和 JS 一样得到 `f64::NAN`
+    #[default]
+    Zero,
+    /// panic，消息是 `parseInt: not a number` 这样的文字
+    Panic,
+    /// 和 `throw` 一样抛出 `ArkError::Error`，可以被 try/catch 接住
+    Throw,
+}
+
+/// 外部函数绑定：`declare function` 声明的名字 -> 实际调用的 Rust 路径。
+///
+/// 例：`{"now": "my_runtime::clock::now"}` 让 `declare function now(): number;` 生成
+///
+/// ```text
+/// fn now() -> i32 {
+///     my_runtime::clock::now()
+/// }
+/// ```
+///
+/// 没有配置绑定的外部函数生成 `unimplemented!()` 桩函数：代码能编译，真正调用时才 panic。
+pub type ExternBindings = HashMap<String, String>;
+
+/// 内置函数的映射表：ArkTS 里的被调用者 -> Rust 代码模板（见 `CodegenOptions::intrinsics`）。
+///
+/// 被调用者写成源码里的样子：`console.log`、`Math.floor`、`hilog.info`、`parseInt`。
+/// 模板里的 `$0`、`$1` ... 换成对应的实参，`$args` 换成用 `, ` 连接的全部实参，例如：
+///
+/// ```text
+/// "Math.floor" => "libm::floor($0)"
+/// "console.log" => "log::info!(\"{:?}\", ($args))"
+/// ```
+///
+/// 每个实参按普通表达式生成（不支持展开实参），模板里用了几次就原样出现几次。
+/// 只改变生成的代码，类型检查仍然按内置函数原来的签名进行。
+///
+/// 也可以给新的被调用者（`"foo" => "bar($0)"`）配置模板：语义检查把它们当作内置函数（见 `sema::check_with`），
+/// 调用的结果类型未知。
+pub type Intrinsics = HashMap<String, String>;
+
+/// 模块树输出里的一个文件：`path` 是相对于输出目录的路径（crate 根是 `main.rs` / `lib.rs`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub code: String,
+}
+
+/// 生成模块树：每个 `.ets` 文件一个 Rust 文件（见 `module::link_tree`）。
+///
+/// `program` 是合并后的 Program（用于全局的名字查找和异常分析），`units` 决定每个文件的内容。
+/// 返回的第一个文件总是 crate 根。
+pub fn generate_tree(
+    program: &Program,
+    units: &[Unit],
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<OutputFile>, Error> {
+    tree_code(program, units, None, mode, externs, options)
+}
+
+/// 同 `generate_tree`，另外给每个文件生成源码映射（见 `SourceMap`）。
+///
+/// 位置标记是注释，`Formatter::Prettyplease` 格式化时会丢掉注释，这时报 `SourceMapFormatter`（见 `check_mapped_formatter`）。
+pub fn generate_tree_mapped(
+    program: &Program,
+    units: &[Unit],
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<(OutputFile, SourceMap)>, Error> {
+    tree_mapped(program, units, None, mode, externs, options)
+}
+
+/// 带外部函数绑定和生成选项的 `generate_as`。绑定了没有声明过的名字报 `UnknownExternBinding`。
+pub fn generate_with(
+    program: &Program,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    program_code(program, None, mode, externs, options)
+}
+
+/// 同 `generate_with`，另外生成源码映射（见 `SourceMap`）：每条语句、每个函数生成的第一行对应的源码区间。
+///
+/// 位置标记是注释，`Formatter::Prettyplease` 格式化时会丢掉注释，这时报 `SourceMapFormatter`（见 `check_mapped_formatter`）。
+pub fn generate_mapped(
+    program: &Program,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<(String, SourceMap), Error> {
+    program_mapped(program, None, mode, externs, options)
+}
+
+/// 生成已经检查过的程序：`types` 是 `check_program_typed`（同一个 `program`、同样的 `options`）的类型检查结果，
+/// 检查没有错误时才能用。不再重复语义检查、类型检查和数据流检查，其它同 `generate_with`。
+pub fn generate_checked(
+    program: &Program,
+    types: &typeck::Types,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    program_code(program, Some(types), mode, externs, options)
+}
+
+/// 已经检查过的程序的 `generate_mapped`（见 `generate_checked`）。
+pub fn generate_checked_mapped(
+    program: &Program,
+    types: &typeck::Types,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<(String, SourceMap), Error> {
+    program_mapped(program, Some(types), mode, externs, options)
+}
+
+/// 已经检查过的程序的 `generate_tree`：`program` 是检查过的合并后的 Program（见 `generate_checked`）。
+pub fn generate_tree_checked(
+    program: &Program,
+    units: &[Unit],
+    types: &typeck::Types,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<OutputFile>, Error> {
+    tree_code(program, units, Some(types), mode, externs, options)
+}
+
+/// 已经检查过的程序的 `generate_tree_mapped`（见 `generate_tree_checked`）。
+pub fn generate_tree_checked_mapped(
+    program: &Program,
+    units: &[Unit],
+    types: &typeck::Types,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<(OutputFile, SourceMap)>, Error> {
+    tree_mapped(program, units, Some(types), mode, externs, options)
+}
+
+/// `generate_with` / `generate_checked`：`types` 为 None 时先检查程序。
+fn program_code(
+    program: &Program,
+    types: Option<&typeck::Types>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    let (program, names) = renamed(program, mode, options);
+    let mut cg = CodeGen::prepare(&program, names, mode, externs, options, types);
+    finish(&with_prelude(cg.gen_program(&program)?, options), options)
+}
+
+/// `generate_mapped` / `generate_checked_mapped`。
+fn program_mapped(
+    program: &Program,
+    types: Option<&typeck::Types>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<(String, SourceMap), Error> {
+    let (program, names) = renamed(program, mode, options);
+    check_mapped_formatter(options)?;
+    let mut cg = CodeGen::prepare(&program, names, mode, externs, options, types);
+    cg.map_spans = Some(Vec::new());
+    let code = finish(&with_prelude(cg.gen_program(&program)?, options), options)?;
+    Ok(source_map::extract(&code, &cg.map_spans.unwrap_or_default()))
+}
+
+/// `generate_tree` / `generate_tree_checked`。
+fn tree_code(
+    program: &Program,
+    units: &[Unit],
+    types: Option<&typeck::Types>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<OutputFile>, Error> {
+    let (program, names) = renamed(program, mode, options);
+    let units = renamed_units(units, &names);
+    let mut cg = CodeGen::prepare(&program, names, mode, externs, options, types);
+    let mut files = cg.gen_tree(&program, &units)?;
+    files[0].code = with_prelude(std::mem::take(&mut files[0].code), options);
+    for file in &mut files {
+        file.code = finish(&file.code, options)?;
+    }
+    Ok(files)
+}
+
+/// `generate_tree_mapped` / `generate_tree_checked_mapped`。
+fn tree_mapped(
+    program: &Program,
+    units: &[Unit],
+    types: Option<&typeck::Types>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+) -> Result<Vec<(OutputFile, SourceMap)>, Error> {
+    let (program, names) = renamed(program, mode, options);
+    let units = renamed_units(units, &names);
+    check_mapped_formatter(options)?;
+    let mut cg = CodeGen::prepare(&program, names, mode, externs, options, types);
+    cg.map_spans = Some(Vec::new());
+    let mut files = cg.gen_tree(&program, &units)?;
+    files[0].code = with_prelude(std::mem::take(&mut files[0].code), options);
+    let spans = cg.map_spans.unwrap_or_default();
+    files
+        .into_iter()
+        .map(|file| {
+            let (code, map) = source_map::extract(&finish(&file.code, options)?, &spans);
+            Ok((OutputFile { path: file.path, code }, map))
+        })
+        .collect()
+}
+
+/// 源码映射靠注释里的位置标记找到格式化以后的行号；`Formatter::Prettyplease` 会丢掉全部注释，
+/// 生成的映射是空的，所以直接报 `SourceMapFormatter`，不返回一个看起来没问题的空映射。
+fn check_mapped_formatter(options: &CodegenOptions) -> Result<(), Error> {
+    match options.formatter {
+        Formatter::Prettyplease => Err(Error::new(ErrorCode::SourceMapFormatter, Span::default())),
+        Formatter::None | Formatter::Rustfmt => Ok(()),
+    }
+}
+
+/// 按 `renamed` 的映射表给模块树的每个文件改名。
+fn renamed_units<'u>(units: &'u [Unit], names: &NameMap) -> Cow<'u, [Unit]> {
+    match names.is_empty() {
+        true => Cow::Borrowed(units),
+        false => {
+            let mut units = units.to_vec();
+            rename::apply_units(&mut units, names);
+            Cow::Owned(units)
+        }
+    }
+}
+
+/// 在 `code` 开头加上 `CodegenOptions::prelude`，和后面的代码之间空一行。
+fn with_prelude(code: String, options: &CodegenOptions) -> String {
+    match options.prelude.as_deref().map(str::trim_end) {
+        Some(prelude) if !prelude.is_empty() => format!("{prelude}\n\n{code}"),
+        _ => code,
+    }
+}
+
+/// 按 `CodegenOptions::formatter` 格式化，再按 `CodegenOptions::layout` 排版。
+fn finish(code: &str, options: &CodegenOptions) -> Result<String, Error> {
+    Ok(layout::apply(&layout::format(code, options.formatter, options.edition)?, &options.layout))
+}
+
+/// CodeGen 的警告：`library_warnings`，以及可执行程序里改了名的顶层函数 `main` 的 `MainFunctionRenamed`
+/// （见 `rename::rename_main`）。
+pub fn warnings(program: &Program, mode: OutputMode, options: &CodegenOptions) -> Vec<Warning> {
+    let mut out = library_warnings(program, mode, options);
+    if mode == OutputMode::Binary {
+        out.extend(
+            program
+                .funcs
+                .iter()
+                .filter(|f| f.name == "main")
+                .map(|f| Warning::new(ErrorCode::MainFunctionRenamed, f.span.0)),
+        );
+    }
+    out
+}
+
+/// 库代码里被跳过的顶层语句的 `TopLevelCodeSkipped` 警告（见 `CodegenOptions::skip_top_level_code`），
+/// 其它情况为空。
+pub fn library_warnings(program: &Program, mode: OutputMode, options: &CodegenOptions) -> Vec<Warning> {
+    if !mode.is_library() || !options.skip_top_level_code {
+        return Vec::new();
+    }
+    program
+        .stmts
+        .iter()
+        .filter(|s| !matches!(s, Stmt::VarDecl(VarDecl { is_const: true, .. })))
+        .map(|s| Warning::new(ErrorCode::TopLevelCodeSkipped, s.span()))
+        .collect()
+}
+
+/// 改过名的程序和名字映射表：`CodegenOptions::snake_case` 打开时按 `rename::snake_case_map` 改名，
+/// 不是合法 Rust 标识符的名字（关键字、带 `$` 的名字等）总是改成合法、不重复的名字（见 `rename::sanitize`）。
+/// 可执行程序里的顶层函数 `main` 改成 `main_`，和生成的入口函数区分开（见 `rename::rename_main`）。
+/// 不用改名时是原来的程序和空表。
+fn renamed<'a>(program: &'a Program, mode: OutputMode, options: &CodegenOptions) -> (Cow<'a, Program>, NameMap) {
+    let mut names = match options.snake_case {
+        true => rename::snake_case_map(program),
+        false => NameMap::default(),
+    };
+    rename::sanitize(program, &mut names, options.edition);
+    if mode == OutputMode::Binary {
+        rename::rename_main(program, &mut names);
+    }
+    if names.is_empty() {
+        return (Cow::Borrowed(program), names);
+    }
+    let mut program = program.clone();
+    rename::apply(&mut program, &names);
+    (Cow::Owned(program), names)
 }
 
 /// 生成完整 Rust 程序。
@@ -27,17 +616,7 @@ pub fn generate(program: &Program) -> Result<String, Error> {
 ///
 /// 这里采用非常简单的缩进策略：每条语句前面统一加 4 个空格。
 pub fn gen_program(program: &Program) -> Result<String, Error> {
-    let mut out = String::new();
-    for f in &program.funcs {
-        out.push_str(&gen_func_decl(f)?);
-        out.push('\n');
-    }
-    out.push_str("fn main() {\n");
-    for stmt in &program.stmts {
-        gen_stmt_into(&mut out, 1, ReturnCtx::Main, stmt)?;
-    }
-    out.push_str("}\n");
-    Ok(out)
+    CodeGen::new(program).gen_program(program)
 }
 
 /// 生成单条语句。
@@ -47,25 +626,10 @@ pub fn gen_program(program: &Program) -> Result<String, Error> {
 /// - 表达式语句：这里统一在表达式后补 `;`
 pub fn gen_stmt(stmt: &Stmt) -> Result<String, Error> {
     let mut out = String::new();
-    gen_stmt_into(&mut out, 0, ReturnCtx::Main, stmt)?;
+    CodeGen::default().gen_stmt_into(&mut out, 0, &ReturnCtx::Main, stmt)?;
     Ok(out.trim_end_matches('\n').to_string())
 }
 
-/// 生成变量声明。
-///
-/// 映射规则：
-/// - ArkTS `let` -> Rust `let mut`
-/// - ArkTS `const` -> Rust `let`
-///
-/// 例：
-/// - `let x = 1;` -> `let mut x = 1i32;`
-/// - `const s = "hi";` -> `let s = String::from("hi");`
-fn gen_var_decl(v: &VarDecl) -> Result<String, Error> {
-    let keyword = if v.is_const { "let" } else { "let mut" };
-    let init = gen_expr(&v.init)?;
-    Ok(format!("{keyword} {} = {init};", v.name))
-}
-
 /// 生成表达式。
 ///
 /// 生成表达式（Step4：含一元/二元/括号/调用/标识符）。
@@ -73,201 +637,5749 @@ fn gen_var_decl(v: &VarDecl) -> Result<String, Error> {
 /// 核心要求：生成的 Rust 表达式必须与 AST 的求值顺序一致。
 /// 因此在必要时需要补括号（例如 `(1+2)*3` 不能生成 `1+2*3`）。
 pub fn gen_expr(expr: &Expr) -> Result<String, Error> {
-    gen_expr_bp(expr, 0)
+    CodeGen::default().gen_expr(expr)
+}
+
+/// 代码生成器的上下文。
+///
+/// 大部分翻译规则是“逐节点”的，但有些需要看到整个程序的信息，
+/// 例如调用点需要知道被调函数的签名（是否有剩余参数）才能正确组装实参。
+#[derive(Default)]
+struct CodeGen {
+    /// 作用域栈：最外层是顶层函数，之后每进入一个函数体/代码块压入一层。
+    ///
+    /// 用于名字解析：调用点查找函数签名、块内函数判断是否捕获了外层局部变量。
+    scopes: Vec<HashMap<String, Binding>>,
+    /// 已分配的临时变量个数，用于生成不重名的 `__destructure0`、`__try1`……
+    temps: usize,
+    /// 可能把异常抛到函数外的函数名：它们的返回类型会变成 `Result<T, ArkError>`。
+    throwing: BTreeSet<String>,
+    /// 当前函数（或 main）是否返回 `Result`。
+    fn_throws: bool,
+    /// 由内到外的 try/catch 标签块：异常先交给最内层的标签块，没有时才 `return Err(..)`。
+    try_labels: Vec<String>,
+    /// 由内到外的 finally 保护区：`(返回值暂存变量, 标签)`。
+    /// 其中的 return 先把返回值存起来并跳出标签块，等 finally 执行完再真正返回。
+    finally_slots: Vec<(String, String)>,
+    /// 自定义异常类：`(类名, 父类名)`，按声明顺序。每个类对应 `ArkError` 的一个变体。
+    error_classes: Vec<(String, String)>,
+    /// 生成可执行程序还是库
+    mode: OutputMode,
+    /// namespace 名 -> 生成的 Rust 模块名（`MathUtils` -> `math_utils`）
+    mod_names: HashMap<String, String>,
+    /// 是否正在生成 namespace 里的函数（导出的函数要加 `pub` 才能从模块外调用）
+    in_namespace: bool,
+    /// 外部函数绑定（见 `ExternBindings`）
+    externs: ExternBindings,
+    /// 是否正在生成模块树里的非根模块（导出的项要 `pub(crate)`）
+    in_submodule: bool,
+    /// 声明时没有初始值的变量（`let x: number;`）。
+    /// 捕获它们的闭包不能提前到函数声明的位置之前，否则 Rust 闭包会捕获到还没赋值的变量。
+    uninit_vars: BTreeSet<String>,
+    /// 当前函数体里要声明成 `let mut` 的变量（见 `mutable_names`）
+    mutable: BTreeSet<String>,
+    /// 生成选项（见 `CodegenOptions`）
+    options: CodegenOptions,
+    /// main 最后运行 `@Entry` 组件的语句（见 `entry_component`）
+    entry: Vec<String>,
+    /// 正在生成的组件里带状态装饰器的字段：字段名 -> 装饰器（见 `gen_component`）
+    state_fields: BTreeMap<String, StateKind>,
+    /// 当前生成的程序（或模块）里声明的组件：`build` 里用到它们时创建子组件（见 `gen_child_component`）
+    components: Vec<ComponentDecl>,
+    /// 顶层的 `@Builder` 函数（见 `BuilderDecl`）
+    builders: Vec<BuilderDecl>,
+    /// 正在生成的组件的 `@Builder` 方法名
+    component_builders: BTreeSet<String>,
+    /// 顶层的 `@Styles` / `@Extend` 函数（见 `StyleDecl`）
+    styles: Vec<StyleDecl>,
+    /// 正在生成的组件的 `@Styles` 方法名
+    component_styles: BTreeSet<String>,
+    /// 正在生成的生命周期方法里由运行时提供的参数（`windowStage` 等），它们的方法调用原样生成（见 `gen_lifecycle_method`）
+    ability_params: BTreeSet<String>,
+    /// 系统模块 import 引入、映射到 Rust 路径的名字：对它们的调用原样生成（见 `gen_system_uses`）
+    system_names: BTreeSet<String>,
+    /// 类型检查推断出来的表达式类型（见 `typeck::Types`、`expr_type`）
+    types: typeck::Types,
+    /// 程序已经检查过，`types` 是检查的结果（见 `generate_checked`）：不再重复检查
+    checked: bool,
+    /// 正在生成的文件用到的运行时辅助项（见 `helper`）；`with_helpers` 输出它们以后清空
+    helpers: RefCell<BTreeSet<&'static str>>,
+    /// `CodegenOptions::snake_case` 时的名字映射表（见 `rename`），原生模块的导出名用它查回原名
+    names: NameMap,
+    /// 还没输出的源码注释（`CodegenOptions::comments` 和 `doc_comments` 都关闭时为空）；输出后从表里删掉，避免重复
+    comments: Comments,
+    /// class 名 -> 生成在 `ArkError` 对应变体前面的注释行（见 `set_source`）
+    class_comments: HashMap<String, Vec<String>>,
+    /// 正在生成的代码所在的源文件（见 `Program::file`、`gen_provenance`）
+    file: Option<String>,
+    /// 生成源码映射时（见 `generate_mapped`）位置标记对应的源码区间和源文件，下标写在标记里；不生成时为 None
+    map_spans: Option<Vec<(Span, Option<String>)>>,
+}
+
+/// 组件字段上的状态装饰器，决定字段包在哪种类型里（定义见 `runtime::STATE_HELPERS`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum StateKind {
+    /// `@State`：组件自己的状态，`ArkState<T>`
+    State,
+    /// `@Prop`：父组件传进来的一份拷贝，改了不影响父组件，`ArkProp<T>`
+    Prop,
+    /// `@Link`：和父组件共享同一个值，`ArkLink<T>`（`Rc<RefCell<..>>`）
+    Link,
+    /// `@Provide('key')`：提供给所有后代组件的值，也是 `ArkLink<T>`；括号里是查找用的名字，省略时用字段名
+    Provide(String),
+    /// `@Consume('key')`：从祖先组件的 `@Provide` 里按名字取得的 `ArkLink<T>`
+    Consume(String),
+}
+
+impl StateKind {
+    /// 包装类型的名字
+    fn wrapper(&self) -> &'static str {
+        match self {
+            StateKind::State => "ArkState",
+            StateKind::Prop => "ArkProp",
+            StateKind::Link | StateKind::Provide(_) | StateKind::Consume(_) => "ArkLink",
+        }
+    }
+}
+
+/// 组件的一个字段：`(字段声明, 类型, 状态装饰器)`，见 `component_fields`。
+type ComponentField<'c> = (&'c FieldDecl, TypeAnn, Option<StateKind>);
+
+/// 交给后端的普通属性（同 `UiCall::attrs`）和调用 `@Styles` 函数的语句，见 `ui_attrs`。
+type UiAttrs = (Vec<(String, Vec<UiCode>)>, Vec<String>);
+
+/// 作用域里一个名字绑定到的东西。
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Binding {
+    /// 局部变量或参数
+    Var {
+        /// 声明（或从初始值推断出）的类型；推断不出来时为 None
+        ty: Option<TypeAnn>,
+        /// 可空变量是否已被 `x != null` 这类条件收窄为非空：读取时需要取出 `Option` 里的值
+        narrowed: bool,
+        /// 没有类型标注、初始值是浮点数（见 `is_float_expr`）的变量：`let avg = total / n;`
+        float: bool,
+        /// 被块内函数（闭包）捕获、又被赋值的变量：声明成 `Cell`/`RefCell`，见 `cell_vars`
+        cell: bool,
+    },
+    /// 函数声明
+    Func {
+        /// 参数类型（省略标注的参数已替换为默认类型）
+        params: Vec<TypeAnn>,
+        /// 返回类型
+        ret: TypeAnn,
+        /// 剩余参数的位置（没有剩余参数则为 None）
+        rest_idx: Option<usize>,
+        /// 是否生成为闭包（块内函数捕获了外层局部变量时为 true）
+        is_closure: bool,
+    },
+}
+
+#[derive(Clone)]
+enum ReturnCtx {
+    Main,
+    Function(TypeAnn),
+}
+
+impl CodeGen {
+    /// 生成 `program` 用的 CodeGen：`names` 是 `renamed` 改名用的映射表，`types` 是已经做过的检查的结果（见 `generate_checked`）。
+    fn prepare(
+        program: &Program,
+        names: NameMap,
+        mode: OutputMode,
+        externs: &ExternBindings,
+        options: &CodegenOptions,
+        types: Option<&typeck::Types>,
+    ) -> Self {
+        let mut cg = CodeGen::new(program);
+        if let Some(types) = types {
+            cg.types = types.clone();
+            cg.checked = true;
+        }
+        cg.names = names;
+        cg.mode = mode;
+        cg.externs = externs.clone();
+        cg.set_options(program, options);
+        cg
+    }
+
+    /// 设置生成选项。`NanMode::Throw` 时数字转换函数会抛异常，`ArithMode::Checked` 时整数运算会抛异常，
+    /// 要重新计算哪些函数会抛异常。
+    fn set_options(&mut self, program: &Program, options: &CodegenOptions) {
+        self.options = options.clone();
+        self.set_source(program);
+        let mut builtins = Vec::new();
+        if options.nan == NanMode::Throw {
+            builtins.extend(["parseInt", "parseFloat", "Number"]);
+        }
+        if options.arith == ArithMode::Checked && options.number_mode == NumberMode::I32 {
+            builtins.push(CHECKED_ARITH);
+        }
+        if !builtins.is_empty() {
+            self.throwing = throwing_funcs(program, &builtins);
+        }
+    }
+
+    /// 接下来生成 `program` 的代码：换成它的注释（见 `CodegenOptions::comments` / `doc_comments`）和文件名。
+    ///
+    /// class 对应的是 `ArkError` 的变体，生成 `ArkError` 时已经看不到 class 声明，所以这里先取出它们的注释。
+    fn set_source(&mut self, program: &Program) {
+        self.file = program.file.clone();
+        self.comments = match self.options.comments || self.options.doc_comments {
+            true => program.comments.clone(),
+            false => Comments::default(),
+        };
+        for c in &program.classes {
+            let lines = self.item_comment_lines(c.span.0.start);
+            if !lines.is_empty() {
+                self.class_comments.insert(c.name.clone(), lines);
+            }
+        }
+    }
+
+    /// `CodegenOptions::provenance` 打开时，输出 `span` 对应的源码位置注释：`// main.ets:3`，不知道文件名时是 `// line 3`。
+    /// 没有位置的节点（手写的 AST 等）不输出。
+    fn gen_provenance(&self, out: &mut String, indent: usize, span: Span) {
+        if !self.options.provenance || span == Span::default() {
+            return;
+        }
+        match &self.file {
+            Some(file) => push_line(out, indent, &format!("// {file}:{}", span.start_line)),
+            None => push_line(out, indent, &format!("// line {}", span.start_line)),
+        }
+    }
+
+    /// 生成源码映射时，在下一行代码前面加一个位置标记（见 `source_map::marker`），代码生成完以后换成 `span` 的映射。
+    fn gen_map_marker(&mut self, out: &mut String, indent: usize, span: Span) {
+        let Some(spans) = &mut self.map_spans else { return };
+        if span == Span::default() {
+            return;
+        }
+        push_line(out, indent, &source_map::marker(spans.len()));
+        spans.push((span, self.file.clone()));
+    }
+
+    /// 输出写在 `start` 位置开始的语句前面的注释。
+    fn gen_leading_comments(&mut self, out: &mut String, indent: usize, start: usize) {
+        let comments = self.comments.leading.remove(&start).unwrap_or_default();
+        if !self.options.comments {
+            return;
+        }
+        for line in comments.iter().flat_map(comment_lines) {
+            push_line(out, indent, &line);
+        }
+    }
+
+    /// 输出写在 `start` 位置开始的声明（函数、组件等）前面的注释，见 `item_comment_lines`。
+    fn gen_item_comments(&mut self, out: &mut String, indent: usize, start: usize) {
+        for line in self.item_comment_lines(start) {
+            push_line(out, indent, &line);
+        }
+    }
+
+    /// 声明前面的注释生成的行：紧挨着声明的 JSDoc 注释在 `CodegenOptions::doc_comments` 打开时生成文档注释
+    /// （见 `doc_lines`），其它注释在 `CodegenOptions::comments` 打开时生成 `//` 注释。
+    fn item_comment_lines(&mut self, start: usize) -> Vec<String> {
+        let comments = self.comments.leading.remove(&start).unwrap_or_default();
+        let doc = comments
+            .last()
+            .filter(|c| self.options.doc_comments && c.is_doc())
+            .map_or(comments.len(), |_| comments.len() - 1);
+        let mut lines = Vec::new();
+        for (i, comment) in comments.iter().enumerate() {
+            if i == doc {
+                lines.extend(doc_lines(comment));
+            } else if self.options.comments {
+                lines.extend(comment_lines(comment));
+            }
+        }
+        lines
+    }
+
+    /// 把在 `end` 位置结束的语句的行尾注释接在 `out` 的最后一行后面；`from` 是这条语句的代码在 `out` 里的起点，
+    /// 语句没有生成代码时注释单独占一行。
+    fn gen_trailing_comments(&mut self, out: &mut String, indent: usize, from: usize, end: usize) {
+        let Some(comments) = self.comments.trailing.remove(&end).filter(|_| self.options.comments) else {
+            return;
+        };
+        let lines: Vec<String> = comments.iter().flat_map(comment_lines).collect();
+        if out.len() > from && out.ends_with('\n') {
+            out.pop();
+            out.push(' ');
+            out.push_str(&lines.join(" "));
+            out.push('\n');
+        } else {
+            push_line(out, indent, &lines.join(" "));
+        }
+    }
+
+    fn new(program: &Program) -> Self {
+        let mut globals: HashMap<String, Binding> = program
+            .funcs
+            .iter()
+            .chain(&program.declare_funcs)
+            .map(|f| (f.name.clone(), func_binding(f, false)))
+            .collect();
+        // 别名和命名空间成员与它们指向的函数共用同一个签名
+        for (key, target) in link_targets(program) {
+            if let Some(binding) = globals.get(&target).cloned() {
+                globals.insert(key, binding);
+            }
+        }
+        // `@Builder` 函数在没有配置 UI 后端时就是普通函数（见 `gen_builder`）；`@Styles` 函数见 `gen_style`
+        for b in &program.builders {
+            globals.insert(b.name.clone(), func_binding(&builder_fn(b), false));
+        }
+        for s in &program.styles {
+            globals.insert(s.name.clone(), func_binding(&style_fn(s), false));
+        }
+        // namespace 里导出的函数从外部以 `Utils.f` 的名字调用
+        for ns in &program.namespace_decls {
+            for f in ns.funcs.iter().filter(|f| f.is_export) {
+                globals.insert(member_key(&ns.name, &f.name), func_binding(f, false));
+            }
+        }
+        Self {
+            scopes: vec![globals],
+            temps: 0,
+            throwing: throwing_funcs(program, &[]),
+            fn_throws: false,
+            try_labels: Vec::new(),
+            finally_slots: Vec::new(),
+            error_classes: program
+                .classes
+                .iter()
+                .filter(|c| !c.is_declare || c.extends.is_some())
+                .map(|c| (c.name.clone(), c.extends.clone().unwrap_or_default()))
+                .collect(),
+            mode: OutputMode::Binary,
+            mod_names: program
+                .namespace_decls
+                .iter()
+                .map(|ns| (ns.name.clone(), snake_case(&ns.name)))
+                .collect(),
+            in_namespace: false,
+            externs: ExternBindings::new(),
+            in_submodule: false,
+            uninit_vars: BTreeSet::new(),
+            mutable: BTreeSet::new(),
+            options: CodegenOptions::default(),
+            entry: Vec::new(),
+            state_fields: BTreeMap::new(),
+            components: program.components.clone(),
+            builders: program.builders.clone(),
+            component_builders: BTreeSet::new(),
+            styles: program.styles.clone(),
+            component_styles: BTreeSet::new(),
+            ability_params: BTreeSet::new(),
+            system_names: program
+                .system_imports
+                .iter()
+                .filter(|i| i.rust_path.is_some())
+                .flat_map(|i| i.local_names())
+                .map(String::from)
+                .collect(),
+            types: typeck::Types::default(),
+            checked: false,
+            helpers: RefCell::default(),
+            names: NameMap::default(),
+            comments: Comments::default(),
+            class_comments: HashMap::new(),
+            file: None,
+            map_spans: None,
+        }
+    }
+
+    /// 从内到外查找名字绑定。
+    fn resolve(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    /// 在当前（最内层）作用域登记一个名字。
+    fn declare(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    /// `name` 是不是声明成 `Cell`/`RefCell` 的变量（见 `Binding::Var::cell`）。
+    fn is_cell(&self, name: &str) -> bool {
+        matches!(self.resolve(name), Some(Binding::Var { cell: true, .. }))
+    }
+
+    /// 读取变量的表达式：普通变量是 `x`；`Cell` 变量是 `x.get()`，`RefCell` 变量是 `x.clone().into_inner()`。
+    ///
+    /// `RefCell` 不用 `x.borrow()`：`Ref` 临时值活到语句结束，同一条语句里调用的闭包再 `borrow_mut` 会 panic。
+    fn var_place(&self, name: &str) -> String {
+        match self.resolve(name) {
+            Some(Binding::Var { cell: true, ty, .. }) if is_copy_type(ty.as_ref()) => format!("{name}.get()"),
+            Some(Binding::Var { cell: true, .. }) => format!("{name}.clone().into_inner()"),
+            _ => name.to_string(),
+        }
+    }
+
+    fn is_closure(&self, name: &str) -> bool {
+        matches!(self.resolve(name), Some(Binding::Func { is_closure: true, .. }))
+    }
+
+    /// 块内函数是否捕获了外层的局部变量（包括外层的闭包）。
+    ///
+    /// Rust 的嵌套 `fn` 看不到外层函数的局部变量，所以一旦捕获就只能生成闭包。
+    fn captures_locals(&self, f: &FuncDecl) -> bool {
+        free_vars(f).iter().any(|name| {
+            matches!(
+                self.resolve(name),
+                Some(Binding::Var { .. }) | Some(Binding::Func { is_closure: true, .. })
+            )
+        })
+    }
+
+    /// 表达式的类型（推断不出来时返回 None）。
+    ///
+    /// 用类型检查的结果（`typeck::Types`，可空变量的收窄已经算在里面）；i32 数字模式下的小数推断为 None
+    /// （见 `is_float_expr`）。类型检查推断不出类型的表达式（解构出来的变量等）和 CodeGen 临时构造的表达式
+    /// 按下面的规则从作用域里的变量推断。
+    fn expr_type(&self, expr: &Expr) -> Option<TypeAnn> {
+        if let Some(ty) = self.types.get(expr) {
+            return match ty {
+                TypeAnn::Number if self.is_float_expr(expr) => None,
+                ty => Some(ty.clone()),
+            };
+        }
+        match expr {
+            Expr::Literal(Literal::Number(_), _) | Expr::Literal(Literal::Float(_), _) => Some(TypeAnn::Number),
+            Expr::Literal(Literal::String(_), _) => Some(TypeAnn::String),
+            Expr::Literal(Literal::Bool(_), _) => Some(TypeAnn::Boolean),
+            Expr::Literal(Literal::Null, _) | Expr::Literal(Literal::Undefined, _) => None,
+            Expr::Ident(name, _) => match self.resolve(name)? {
+                Binding::Var { ty, narrowed, .. } => match (ty, narrowed) {
+                    (Some(TypeAnn::Nullable(inner, _)), true) => Some(inner.as_ref().clone()),
+                    _ => ty.clone(),
+                },
+                Binding::Func { params, ret, .. } => Some(TypeAnn::Function(FuncTypeAnn {
+                    params: params.clone(),
+                    ret: Box::new(ret.clone()),
+                })),
+            },
+            Expr::Group(inner) => self.expr_type(inner),
+            Expr::Call(call) => match &call.callee {
+                Callee::Console(_) => Some(TypeAnn::Void),
+                Callee::Ident(name) if is_conversion_builtin(name) && self.resolve(name).is_none() => {
+                    match name.as_str() {
+                        "String" => Some(TypeAnn::String),
+                        // 浮点数，见 `is_float_expr`
+                        _ if self.is_float_expr(expr) => None,
+                        _ => Some(TypeAnn::Number),
+                    }
+                }
+                Callee::Ident(name) if is_resource_builtin(name) && self.resolve(name).is_none() => {
+                    Some(TypeAnn::String)
+                }
+                Callee::Ident(name) => match self.expr_type(&Expr::Ident(name.clone(), NodeSpan::default()))? {
+                    TypeAnn::Function(f) => Some(*f.ret),
+                    _ => None,
+                },
+                Callee::Promise(PromiseFn::Resolve) => {
+                    let value = self.expr_type(call.args.first()?)?;
+                    Some(TypeAnn::Promise(Box::new(value)))
+                }
+                Callee::Promise(PromiseFn::Reject) => None,
+                Callee::Member { object, property } => {
+                    match self.resolve(&member_key(object, property)) {
+                        Some(Binding::Func { ret, .. }) => Some(ret.clone()),
+                        Some(Binding::Var { .. }) => None,
+                        // `Math.random()` 是浮点数，见 `is_float_expr`
+                        None if self.is_math_call(call) => {
+                            (property != "random" || self.f64_mode()).then_some(TypeAnn::Number)
+                        }
+                        // `JSON.parse` 的结果类型由上下文（变量的类型标注）决定
+                        None if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "JSON") => {
+                            (property == "stringify").then_some(TypeAnn::String)
+                        }
+                        // `Date.now()` 是浮点数（毫秒），见 `is_float_expr`
+                        None if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "Date") => {
+                            self.f64_mode().then_some(TypeAnn::Number)
+                        }
+                        None => self.method_type(&Expr::Ident(object.clone(), NodeSpan::default()), property),
+                    }
+                }
+                Callee::Method { object, method } => self.method_type(object, method),
+                Callee::Expr(inner) => match self.expr_type(inner)? {
+                    TypeAnn::Function(f) => Some(*f.ret),
+                    _ => None,
+                },
+                Callee::Promise(PromiseFn::All) => match self.expr_type(call.args.first()?)? {
+                    TypeAnn::Array(elem) => match *elem {
+                        TypeAnn::Promise(inner) => {
+                            Some(TypeAnn::Promise(Box::new(TypeAnn::Array(inner))))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                },
+            },
+            Expr::Unary(u) => match u.op {
+                UnaryOp::Not => Some(TypeAnn::Boolean),
+                UnaryOp::Neg => Some(TypeAnn::Number),
+            },
+            Expr::Binary(b) => match b.op {
+                BinaryOp::Add => {
+                    let is_string = |e: &Expr| self.expr_type(e) == Some(TypeAnn::String);
+                    if is_string(&b.left) || is_string(&b.right) {
+                        Some(TypeAnn::String)
+                    } else if self.is_float_expr(expr) {
+                        None
+                    } else {
+                        Some(TypeAnn::Number)
+                    }
+                }
+                BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                    (!self.is_float_expr(expr)).then_some(TypeAnn::Number)
+                }
+                BinaryOp::NullishCoalesce => {
+                    if is_null_literal(&b.left) {
+                        return self.expr_type(&b.right);
+                    }
+                    match self.expr_type(&b.left) {
+                        Some(TypeAnn::Nullable(inner, kind)) => {
+                            if is_null_literal(&b.right) {
+                                Some(TypeAnn::Nullable(inner, kind))
+                            } else {
+                                self.expr_type(&b.right).or(Some(*inner))
+                            }
+                        }
+                        other => other,
+                    }
+                }
+                _ => Some(TypeAnn::Boolean),
+            },
+            Expr::Assign(a) => self.assign_type(a),
+            Expr::Member(m) if self.field_type(&m.object, &m.property).is_some() => {
+                self.field_type(&m.object, &m.property)
+            }
+            Expr::Member(m) => match self.expr_type(&m.object)? {
+                TypeAnn::String | TypeAnn::Array(_) if m.property == "length" => Some(TypeAnn::Number),
+                _ => None,
+            },
+            Expr::Index(ix) => match self.expr_type(&ix.object)? {
+                TypeAnn::String => Some(TypeAnn::String),
+                TypeAnn::Array(elem) => Some(*elem),
+                _ => None,
+            },
+            Expr::New(n) if self.is_builtin_date(n) => Some(TypeAnn::Date),
+            Expr::Spread(_) | Expr::New(_) => None,
+        }
+    }
+
+    /// `object.method(..)` 的返回类型：`toString()`、字符串的内置方法（见 `string_method`）和 `Date` 的方法
+    /// （`getTime()` 是浮点数，见 `is_float_expr`）。
+    fn method_type(&self, object: &Expr, method: &str) -> Option<TypeAnn> {
+        if method == "toString" {
+            return Some(TypeAnn::String);
+        }
+        match self.expr_type(object)? {
+            TypeAnn::String => string_method(method).map(|(_, _, ret)| ret),
+            TypeAnn::Date if method == "toISOString" => Some(TypeAnn::String),
+            TypeAnn::Date => (method == "getTime" && self.f64_mode()).then_some(TypeAnn::Number),
+            _ => None,
+        }
+    }
+
+    /// 赋值表达式的类型：优先用目标的声明类型，推断不出来时用右侧值的类型。
+    fn assign_type(&self, a: &AssignExpr) -> Option<TypeAnn> {
+        self.target_type(&a.target).or_else(|| self.expr_type(&a.value))
+    }
+
+    /// 赋值目标的类型（推断不出来时返回 None）。
+    fn target_type(&self, target: &AssignTarget) -> Option<TypeAnn> {
+        match target {
+            AssignTarget::Ident(name) => self.var_type(name),
+            AssignTarget::Index { object, .. } => match self.var_type(object)? {
+                TypeAnn::Array(elem) => Some(*elem),
+                _ => None,
+            },
+            // 组件字段 `this.x` 的类型见 `gen_component`；其它对象还没有类型，字段类型未知。
+            AssignTarget::Member { object, property } => self.var_type(&member_key(object, property)),
+        }
+    }
+
+    /// 变量的声明类型（不考虑收窄）。
+    fn var_type(&self, name: &str) -> Option<TypeAnn> {
+        match self.resolve(name)? {
+            Binding::Var { ty, .. } => ty.clone(),
+            Binding::Func { .. } => None,
+        }
+    }
+
+    /// 判空的条件，见 `null_check`。
+    fn null_check(&self, cond: &Expr) -> Option<(String, bool)> {
+        null_check(cond, self.options.truthy_conditions, |name| {
+            matches!(self.var_type(name), Some(TypeAnn::Nullable(_, _)))
+        })
+    }
+
+    /// 在一层“收窄作用域”里生成分支：分支内读取该变量会自动取出 `Option` 里的值。
+    fn gen_narrowed_body(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmt: &Stmt,
+        narrowed: Option<&str>,
+    ) -> Result<(), Error> {
+        let Some(name) = narrowed else {
+            return self.gen_block_body(out, indent, ctx, stmt);
+        };
+        let ty = self.var_type(name);
+        let scope = HashMap::from([(
+            name.to_string(),
+            Binding::Var {
+                ty,
+                narrowed: true,
+                float: false,
+                cell: self.is_cell(name),
+            },
+        )]);
+        self.scopes.push(scope);
+        let result = self.gen_block_body(out, indent, ctx, stmt);
+        self.scopes.pop();
+        result
+    }
+
+    /// 按目标类型生成表达式，必要时在值和 `Option` 之间做转换：
+    /// - 目标可空、值为 `null`/`undefined` -> `None`
+    /// - 目标可空、值本身已经是可空类型 -> 原样
+    /// - 目标可空、值是普通类型 -> `Some(value)`
+    /// - 目标不可空、值为 `null` -> 报错 `NullNotAllowed`
+    fn gen_expr_as(&mut self, expr: &Expr, target: Option<&TypeAnn>) -> Result<String, Error> {
+        match target {
+            Some(TypeAnn::Nullable(inner, _)) => {
+                if is_null_literal(expr) {
+                    Ok("None".to_string())
+                } else if matches!(self.expr_type(expr), Some(TypeAnn::Nullable(_, _))) {
+                    self.gen_expr(expr)
+                } else {
+                    Ok(format!("Some({})", self.gen_string_as(expr, Some(inner))?))
+                }
+            }
+            Some(_) if is_null_literal(expr) => Err(Error::new(ErrorCode::NullNotAllowed, Span::default())),
+            // 小数放不进 i32：需要 f64 模式，或者先用 `Math.floor` 等取整
+            Some(TypeAnn::Number) if self.is_float_expr(expr) => {
+                Err(Error::new(ErrorCode::FractionalNumberNeedsF64, Span::default()))
+            }
+            _ => self.gen_string_as(expr, target),
+        }
+    }
+
+    /// 存进 string 类型的位置（变量、参数、返回值、字段）时按 `StringMode` 转换，目标不是 string 时原样生成：
+    /// - `Static`：只能是字面量、变量、字段、数组元素和用户函数的返回值，其它（拼接、字符串方法等）报 `StaticStringNotLiteral`
+    /// - `Shared`：字面量、变量、字段、数组元素和用户函数的返回值已经是 `Rc<str>`，原样生成（变量 clone 一份，
+    ///   只增加引用计数，不会把它移走）；其它（拼接、字符串方法等得到的 `String`）包成 `std::rc::Rc::<str>::from(..)`
+    fn gen_string_as(&mut self, expr: &Expr, target: Option<&TypeAnn>) -> Result<String, Error> {
+        if target != Some(&TypeAnn::String) {
+            return self.gen_expr(expr);
+        }
+        match self.options.string_mode {
+            StringMode::Owned => self.gen_expr(expr),
+            StringMode::Static if !self.is_static_str(expr) => {
+                Err(Error::new(ErrorCode::StaticStringNotLiteral, Span::default()))
+            }
+            StringMode::Static => self.gen_expr(expr),
+            StringMode::Shared if self.is_static_str(expr) => match strip_group(expr) {
+                Expr::Ident(name, _) => {
+                    let value = self.gen_expr(expr)?;
+                    Ok(if value == *name { format!("{value}.clone()") } else { value })
+                }
+                _ => self.gen_expr(expr),
+            },
+            StringMode::Shared => Ok(format!("std::rc::Rc::<str>::from({})", self.gen_expr(expr)?)),
+        }
+    }
+
+    /// 值是不是已经是 string 对应的 Rust 类型（`Static` 下的 `&'static str`、`Shared` 下的 `Rc<str>`）：字面量，
+    /// 或者从 string 类型的变量、字段、数组、用户函数里取出来的值。
+    fn is_static_str(&self, expr: &Expr) -> bool {
+        match strip_group(expr) {
+            Expr::Literal(Literal::String(_), _) | Expr::Ident(_, _) | Expr::Member(_) | Expr::Index(_) => true,
+            Expr::Call(CallExpr {
+                callee: Callee::Ident(name),
+                ..
+            }) => matches!(self.resolve(name), Some(Binding::Func { .. })),
+            _ => false,
+        }
+    }
+
+    /// 生成之前的检查（语义、类型、数据流），在（合并后的）整个程序上做一次；已经检查过的程序跳过（见 `generate_checked`）。
+    fn check(&mut self, program: &Program) -> Result<(), Error> {
+        if self.checked {
+            return Ok(());
+        }
+        sema::check_with(program, &self.options.intrinsics)?;
+        self.types = typeck::check_with(program, &self.options)?;
+        flow::check(program)?;
+        self.checked = true;
+        Ok(())
+    }
+
+    fn gen_program(&mut self, program: &Program) -> Result<String, Error> {
+        // import/re-export 要靠 `compile_file` 加载其它文件来解析，单份源码里无法处理
+        let sources = program.imports.iter().map(|i| i.span.0);
+        if let Some(span) = sources.chain(program.reexports.iter().map(|r| r.source_span)).next() {
+            return Err(Error::new(ErrorCode::UnresolvedImport, span));
+        }
+        self.check(program)?;
+        let mut out = String::new();
+        self.check_error_classes()?;
+        self.check_externs(program)?;
+        let main_stmts = match self.mode.is_library() {
+            true => {
+                out.push_str(&self.gen_library_consts(&program.stmts)?);
+                Vec::new()
+            }
+            false => {
+                let (consts, stmts) = self.gen_shared_consts(program, &HashSet::new())?;
+                out.push_str(&consts);
+                stmts
+            }
+        };
+        out.push_str(&self.gen_links(program));
+        out.push_str(&self.gen_items(program)?);
+        if self.mode == OutputMode::NativeModule {
+            let exports: Vec<(&str, &FuncDecl)> =
+                program.funcs.iter().filter(|f| f.is_export).map(|f| (f.name.as_str(), f)).collect();
+            out.push_str(&self.gen_napi_exports(&exports)?);
+        }
+        if self.mode.is_library() {
+            return Ok(self.with_extern_crates(self.with_error_enum(self.with_helpers(out))));
+        }
+        self.entry = [
+            entry_ability(&program.abilities),
+            entry_component(&program.components, self.options.ui_backend.as_ref())?,
+        ]
+        .concat();
+        out.push_str(&self.gen_top_level_fn("", "main", &[], &main_stmts)?);
+        Ok(self.with_extern_crates(self.with_error_enum(self.with_helpers(out))))
+    }
+
+    /// 绑定了没有声明过的外部函数：`UnknownExternBinding`
+    fn check_externs(&self, program: &Program) -> Result<(), Error> {
+        for name in self.externs.keys() {
+            if !program.declare_funcs.iter().any(|f| &f.name == name) {
+                return Err(Error::new(ErrorCode::UnknownExternBinding, Span::default()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 外部函数包装、顶层函数和 namespace。
+    fn gen_items(&mut self, program: &Program) -> Result<String, Error> {
+        let mut out = String::new();
+        self.components = program.components.clone();
+        for f in &program.declare_funcs {
+            out.push_str(&self.gen_extern_func(f));
+            out.push('\n');
+        }
+        for f in &program.funcs {
+            out.push_str(&self.gen_func_decl(f, 0)?);
+            out.push('\n');
+        }
+        for ns in &program.namespace_decls {
+            out.push_str(&self.gen_namespace(ns)?);
+            out.push('\n');
+        }
+        for s in &program.styles {
+            out.push_str(&self.gen_style(s, s.is_export, 0, None)?);
+            out.push('\n');
+        }
+        for b in &program.builders {
+            out.push_str(&self.gen_builder(b, b.is_export, 0, None)?);
+            out.push('\n');
+        }
+        for c in &program.components {
+            out.push_str(&self.gen_component(c)?);
+            out.push('\n');
+        }
+        for a in &program.abilities {
+            out.push_str(&self.gen_ability(a)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// 生成 ArkUI 组件（见 `ComponentDecl`）：一个 struct 加上带构造函数和方法的 impl。
+    ///
+    /// ```text
+    /// struct Index {
+    ///     message: String,
+    /// }
+    ///
+    /// impl Index {
+    ///     fn new() -> Self {
+    ///         Self {
+    ///             message: String::from("Hello"),
+    ///         }
+    ///     }
+    ///
+    ///     fn build(&mut self) {
+    ///         println!("{}", self.message.clone());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// - 字段类型取类型标注，没有标注时从初始值推断，都推断不出来报 `MissingFieldType`
+    /// - 没有初始值的字段（由父组件传入）成为 `new` 的参数
+    /// - 方法的第一个参数是 `&mut self`；方法体里 `this.x` 生成 `self.x`，`this.m()` 生成 `self.m()`
+    /// - 方法里抛出的异常还不支持传播，报 `UnsupportedComponentThrow`
+    /// - `build()` 和 `@Builder` 方法见 `gen_builder`，`@Styles` 方法见 `gen_style`，都生成在普通方法前面
+    ///
+    /// `@State count: number = 0;` 是状态字段：类型包一层 `ArkState<i32>`（定义见 `runtime::STATE_HELPERS`），
+    /// 读取 `this.count` 生成 `self.count.value`，赋值 `this.count = v` 生成 `self.count.set(v)`。
+    /// `set` 会把版本号加一；组件有状态字段和 `build()` 时再生成一个 `__ark_rerender` 方法，
+    /// 所有状态字段的版本号之和和上次 build 时不同才重新调用 `build()`（见 `entry_component`）。
+    ///
+    /// 其它状态装饰器（见 `StateKind`）：
+    /// - `@Prop`：`ArkProp<T>`，没有初始值时由 `new` 的参数传入（父组件的值的拷贝）
+    /// - `@Link`：`ArkLink<T>`，`new` 直接接收父组件共享出来的 `ArkLink`；读取生成 `self.x.get()`
+    /// - `@Provide`：`ArkLink<T>`，另外生成 `__ark_provide(ctx)` 把它按名字登记到 `ArkContext`
+    /// - `@Consume`：`new` 多一个 `ctx: &ArkContext` 参数，用 `ctx.consume("key")` 取得祖先提供的值
+    ///
+    /// `@State` / `@Provide` 必须有初始值（`StateFieldNeedsInitializer`），
+    /// `@Link` / `@Consume` 不能有初始值（`StateFieldHasInitializer`）。
+    ///
+    /// 只有整个赋值才算修改：`this.items.push(x)` 这种原地修改不会增加版本号。
+    fn gen_component(&mut self, c: &ComponentDecl) -> Result<String, Error> {
+        let vis = self.item_vis(c.is_export);
+        let fields = self.component_fields(c)?;
+
+        let mut out = String::new();
+        self.gen_item_comments(&mut out, 0, c.span.0.start);
+        out.push_str(&format!("{vis}struct {} {{\n", c.name));
+        for (field, ty, kind) in &fields {
+            let ty = self.rust_type(ty);
+            match kind {
+                Some(kind) => out.push_str(&format!("    {}: {}<{ty}>,\n", field.name, self.helper(kind.wrapper()))),
+                None => out.push_str(&format!("    {}: {ty},\n", field.name)),
+            }
+        }
+        out.push_str("}\n\n");
+
+        out.push_str(&format!("impl {} {{\n", c.name));
+        let mut params: Vec<String> = fields
+            .iter()
+            .filter(|(field, _, kind)| field.init.is_none() && !matches!(kind, Some(StateKind::Consume(_))))
+            .map(|(field, ty, kind)| match kind {
+                Some(StateKind::Link) => format!("{}: ArkLink<{}>", field.name, self.rust_type(ty)),
+                _ => format!("{}: {}", field.name, self.rust_type(ty)),
+            })
+            .collect();
+        if fields.iter().any(|(_, _, kind)| matches!(kind, Some(StateKind::Consume(_)))) {
+            params.push(format!("ctx: &{}", self.helper("ArkContext")));
+        }
+        out.push_str(&format!("    {vis}fn new({}) -> Self {{\n        Self {{\n", params.join(", ")));
+        for (field, ty, kind) in &fields {
+            let name = &field.name;
+            let value = match (&field.init, kind) {
+                (Some(init), _) => {
+                    let value = self.gen_expr_as(init, Some(ty))?;
+                    match kind {
+                        Some(kind) => format!("{}::new({value})", self.helper(kind.wrapper())),
+                        None => value,
+                    }
+                }
+                (None, Some(StateKind::Prop)) => format!("{}::new({name})", self.helper("ArkProp")),
+                (None, Some(StateKind::Consume(key))) => format!("ctx.consume({key:?})"),
+                (None, _) => {
+                    out.push_str(&format!("            {name},\n"));
+                    continue;
+                }
+            };
+            out.push_str(&format!("            {name}: {value},\n"));
+        }
+        out.push_str("        }\n    }\n");
+
+        let state: BTreeMap<String, StateKind> = fields
+            .iter()
+            .filter_map(|(field, _, kind)| Some((field.name.clone(), kind.clone()?)))
+            .collect();
+        if !state.is_empty() && c.build.is_some() {
+            let versions: Vec<String> = state
+                .iter()
+                .map(|(name, kind)| match kind {
+                    StateKind::State | StateKind::Prop => format!("self.{name}.version"),
+                    _ => format!("self.{name}.version()"),
+                })
+                .collect();
+            // 配置了 UI 后端时 `build` 有额外的参数（`ui` 等），原样转交
+            let ui_params = self.ui_build_params();
+            let params: String = ui_params.iter().map(|(name, ty)| format!(", {name}: {ty}")).collect();
+            let args: Vec<&str> = ui_params.iter().map(|(name, _)| name.as_str()).collect();
+            out.push_str(&format!(
+                "\n    fn __ark_rerender(&mut self, rendered: &mut Option<u64>{params}) {{\n        \
+                 let version = {};\n        if *rendered != Some(version) {{\n            \
+                 *rendered = Some(version);\n            self.build({});\n        }}\n    }}\n",
+                versions.join(" + "),
+                args.join(", ")
+            ));
+        }
+        let provides: Vec<String> = state
+            .iter()
+            .filter_map(|(name, kind)| match kind {
+                StateKind::Provide(key) => Some(format!("        ctx.provide({key:?}, self.{name}.clone());\n")),
+                _ => None,
+            })
+            .collect();
+        if !provides.is_empty() {
+            out.push_str(&format!("\n    fn __ark_provide(&self, ctx: &mut {}) {{\n", self.helper("ArkContext")));
+            out.push_str(&provides.concat());
+            out.push_str("    }\n");
+        }
+
+        // 方法体里的 `this.x` / `this.m()` 按 `this.x` 这个名字查找（和 namespace 成员一样）
+        let mut scope: HashMap<String, Binding> = fields
+            .into_iter()
+            .map(|(field, ty, _)| {
+                let binding = Binding::Var { ty: Some(ty), narrowed: false, float: false, cell: false };
+                (member_key("this", &field.name), binding)
+            })
+            .collect();
+        for m in &c.methods {
+            scope.insert(member_key("this", &m.name), func_binding(m, false));
+        }
+        for b in &c.builders {
+            scope.insert(member_key("this", &b.name), func_binding(&builder_fn(b), false));
+        }
+        for s in &c.styles {
+            scope.insert(member_key("this", &s.name), func_binding(&style_fn(s), false));
+        }
+        self.scopes.push(scope);
+        self.state_fields = state;
+        self.component_builders = c.builders.iter().map(|b| b.name.clone()).collect();
+        self.component_styles = c.styles.iter().map(|s| s.name.clone()).collect();
+        let build = c.build.as_ref().map(|nodes| BuilderDecl {
+            is_export: false,
+            name: "build".to_string(),
+            params: Vec::new(),
+            decorators: Vec::new(),
+            body: nodes.clone(),
+            span: c.span,
+        });
+        let mut result = build
+            .iter()
+            .chain(&c.builders)
+            .try_for_each(|b| {
+                out.push('\n');
+                out.push_str(&self.gen_builder(b, c.is_export, 1, Some("&mut self"))?);
+                Ok(())
+            });
+        if result.is_ok() {
+            result = c.styles.iter().try_for_each(|s| {
+                out.push('\n');
+                out.push_str(&self.gen_style(s, c.is_export, 1, Some("&mut self"))?);
+                Ok(())
+            });
+        }
+        if result.is_ok() {
+            result = c.methods.iter().try_for_each(|m| {
+                if stmts_throw(&m.body.stmts, &self.throwing) {
+                    return Err(Error::new(ErrorCode::UnsupportedComponentThrow, m.span.0));
+                }
+                let m = FuncDecl {
+                    is_export: c.is_export,
+                    ..m.clone()
+                };
+                out.push('\n');
+                out.push_str(&self.gen_fn_item(&m, 1, Some("&mut self"))?);
+                Ok(())
+            });
+        }
+        self.scopes.pop();
+        self.state_fields.clear();
+        self.component_builders.clear();
+        self.component_styles.clear();
+        result?;
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// 生成 Ability 类（见 `AbilityDecl`）：一个空 struct，生命周期方法实现运行时的 `UIAbility` trait，
+    /// 其它方法放在普通的 impl 里。
+    ///
+    /// ```text
+    /// struct EntryAbility;
+    ///
+    /// impl UIAbility for EntryAbility {
+    ///     fn onWindowStageCreate(&mut self, windowStage: &mut WindowStage) {
+    ///         windowStage.loadContent(String::from("pages/Index"));
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// `UIAbility` 等由 `CodegenOptions::ability_runtime` 指定的模块提供，没有配置时用运行时里的 `runtime::ABILITY_HELPERS`。
+    /// 方法里抛出的异常不支持传播，报 `UnsupportedAbilityThrow`。
+    fn gen_ability(&mut self, a: &AbilityDecl) -> Result<String, Error> {
+        let (lifecycle, methods): (Vec<&FuncDecl>, Vec<&FuncDecl>) = a
+            .methods
+            .iter()
+            .partition(|m| ABILITY_LIFECYCLE.contains(&m.name.as_str()));
+        if let Some(m) = a.methods.iter().find(|m| stmts_throw(&m.body.stmts, &self.throwing)) {
+            return Err(Error::new(ErrorCode::UnsupportedAbilityThrow, m.span.0));
+        }
+        let mut out = String::new();
+        self.gen_item_comments(&mut out, 0, a.span.0.start);
+        out.push_str(&format!("{}struct {};\n", self.item_vis(a.is_export), a.name));
+        // 方法体里 `this.m()` 按 `this.m` 这个名字查找（同组件）
+        let scope = methods
+            .iter()
+            .map(|m| (member_key("this", &m.name), func_binding(m, false)))
+            .collect();
+        self.scopes.push(scope);
+        let result = self.gen_ability_impls(&mut out, a, &lifecycle, &methods);
+        self.scopes.pop();
+        result?;
+        Ok(out)
+    }
+
+    fn gen_ability_impls(
+        &mut self,
+        out: &mut String,
+        a: &AbilityDecl,
+        lifecycle: &[&FuncDecl],
+        methods: &[&FuncDecl],
+    ) -> Result<(), Error> {
+        if !methods.is_empty() {
+            out.push_str(&format!("\nimpl {} {{\n", a.name));
+            for (i, m) in methods.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                let m = FuncDecl {
+                    is_export: a.is_export,
+                    ..(*m).clone()
+                };
+                out.push_str(&self.gen_fn_item(&m, 1, Some("&mut self"))?);
+            }
+            out.push_str("}\n");
+        }
+        if self.options.ability_runtime.is_none() {
+            for helper in &runtime::ABILITY_HELPERS {
+                self.helper(helper.name);
+            }
+        }
+        out.push_str(&format!("\nimpl UIAbility for {} {{\n", a.name));
+        for (i, m) in lifecycle.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&self.gen_lifecycle_method(m)?);
+        }
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    /// 生成 `UIAbility` 的一个生命周期方法，参数类型见 `lifecycle_params`。
+    ///
+    /// 源码可以少写后面的参数（生成 `_: &LaunchParam`），多写报 `InvalidLifecycleParams`。
+    /// 这些参数是运行时的对象：`windowStage.loadContent('pages/Index')` 原样生成方法调用，由运行时决定有哪些方法。
+    fn gen_lifecycle_method(&mut self, m: &FuncDecl) -> Result<String, Error> {
+        let types = lifecycle_params(&m.name);
+        if m.params.len() > types.len() {
+            return Err(Error::new(ErrorCode::InvalidLifecycleParams, m.span.0));
+        }
+        let mut params = vec!["&mut self".to_string()];
+        for (i, ty) in types.iter().enumerate() {
+            match m.params.get(i) {
+                Some(p) => params.push(format!("{}: {ty}", p.name)),
+                None => params.push(format!("_: {ty}")),
+            }
+        }
+        let names: BTreeSet<String> = m.params.iter().map(|p| p.name.clone()).collect();
+        let scope = names
+            .iter()
+            .map(|name| (name.clone(), Binding::Var { ty: None, narrowed: false, float: false, cell: false }))
+            .collect();
+        let mut out = format!("    fn {}({}) {{\n", m.name, params.join(", "));
+        let body = FuncDecl {
+            params: Vec::new(),
+            ..m.clone()
+        };
+        self.scopes.push(scope);
+        let outer = std::mem::replace(&mut self.ability_params, names);
+        let result = self.gen_func_body(&mut out, 2, &body, TypeAnn::Void);
+        self.ability_params = outer;
+        self.scopes.pop();
+        result?;
+        out.push_str("    }\n");
+        Ok(out)
+    }
+
+    /// 组件的字段：`(字段, 类型, 状态装饰器)`。
+    ///
+    /// 类型取类型标注，没有标注时从初始值推断，都推断不出来报 `MissingFieldType`；
+    /// 状态字段的初始值不符合要求时报 `StateFieldNeedsInitializer` / `StateFieldHasInitializer`。
+    fn component_fields<'c>(
+        &self,
+        c: &'c ComponentDecl,
+    ) -> Result<Vec<ComponentField<'c>>, Error> {
+        let mut fields = Vec::new();
+        for field in &c.fields {
+            let at = field.span.0;
+            let ty = match (&field.ty, &field.init) {
+                (Some(ty), _) => ty.clone(),
+                (None, Some(init)) => self
+                    .expr_type(init)
+                    .ok_or_else(|| Error::new(ErrorCode::MissingFieldType, at))?,
+                (None, None) => return Err(Error::new(ErrorCode::MissingFieldType, at)),
+            };
+            let kind = state_kind(field).map_err(|e| e.at(at))?;
+            match (&kind, &field.init) {
+                (Some(StateKind::State | StateKind::Provide(_)), None) => {
+                    return Err(Error::new(ErrorCode::StateFieldNeedsInitializer, at));
+                }
+                (Some(StateKind::Link | StateKind::Consume(_)), Some(_)) => {
+                    return Err(Error::new(ErrorCode::StateFieldHasInitializer, at));
+                }
+                _ => {}
+            }
+            fields.push((field, ty, kind));
+        }
+        Ok(fields)
+    }
+
+    /// 把 `build()` 里的 UI 树以注释的形式列在 `build` 方法前面，实参按 Rust 代码生成：
+    ///
+    /// ```text
+    ///     // Column() {
+    ///     //     Text(self.message.clone())
+    ///     //         .fontSize(20i32)
+    ///     // }
+    ///     fn build(&mut self) { .. }
+    /// ```
+    ///
+    /// 箭头函数实参只列出参数：`|item| { .. }`；普通语句不列出（它们在方法体里）。
+    fn gen_ui_outline(&mut self, out: &mut String, indent: usize, nodes: &[UiNode], depth: usize) -> Result<(), Error> {
+        let base = indent;
+        let indent = format!("{}// {}", "    ".repeat(base), "    ".repeat(depth));
+        for node in nodes {
+            match node {
+                UiNode::Element(e) => {
+                    let args = self.gen_ui_args(&e.args)?;
+                    if e.children.is_empty() {
+                        out.push_str(&format!("{indent}{}({args})\n", e.name));
+                    } else {
+                        out.push_str(&format!("{indent}{}({args}) {{\n", e.name));
+                        self.gen_ui_outline(out, base, &e.children, depth + 1)?;
+                        out.push_str(&format!("{indent}}}\n"));
+                    }
+                    for attr in &e.attrs {
+                        let args = self.gen_ui_args(&attr.args)?;
+                        out.push_str(&format!("{indent}    .{}({args})\n", attr.name));
+                    }
+                }
+                UiNode::If(i) => {
+                    out.push_str(&format!("{indent}if {} {{\n", self.gen_expr(&i.cond)?));
+                    let mut branch = i;
+                    loop {
+                        self.gen_ui_outline(out, base, &branch.then_branch, depth + 1)?;
+                        match branch.else_branch.as_slice() {
+                            [] => break,
+                            [UiNode::If(next)] => {
+                                out.push_str(&format!("{indent}}} else if {} {{\n", self.gen_expr(&next.cond)?));
+                                branch = next;
+                            }
+                            nodes => {
+                                out.push_str(&format!("{indent}}} else {{\n"));
+                                self.gen_ui_outline(out, base, nodes, depth + 1)?;
+                                break;
+                            }
+                        }
+                    }
+                    out.push_str(&format!("{indent}}}\n"));
+                }
+                UiNode::Stmt(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_ui_args(&mut self, args: &[UiArg]) -> Result<String, Error> {
+        let mut parts = Vec::new();
+        for arg in args {
+            parts.push(match arg {
+                UiArg::Expr(e) => self.gen_ui_expr(e)?,
+                UiArg::Object(props) => {
+                    let mut fields = Vec::new();
+                    for (key, value) in props {
+                        fields.push(format!("{key}: {}", self.gen_ui_expr(value)?));
+                    }
+                    format!("{{ {} }}", fields.join(", "))
+                }
+                UiArg::Handler(h) => {
+                    let params: Vec<&str> = h.params.iter().map(|p| p.name.as_str()).collect();
+                    format!("|{}| {{ .. }}", params.join(", "))
+                }
+            });
+        }
+        Ok(parts.join(", "))
+    }
+
+    /// UI 实参里的表达式。ArkUI 的枚举值（没有被同名变量遮蔽的 `FontWeight.Bold`）写成 `FontWeight::Bold`。
+    fn gen_ui_expr(&mut self, e: &Expr) -> Result<String, Error> {
+        match self.ui_enum(e) {
+            Some((name, member)) => Ok(format!("{name}::{member}")),
+            None => self.gen_expr(e),
+        }
+    }
+
+    /// ArkUI 的枚举值 `FontWeight.Bold`：首字母大写、没有被同名变量遮蔽的名字的成员。
+    fn ui_enum(&self, e: &Expr) -> Option<(String, String)> {
+        match e {
+            Expr::Member(m) => match &*m.object {
+                Expr::Ident(name, _)
+                    if name.starts_with(|c: char| c.is_ascii_uppercase()) && self.is_builtin(&m.object, name) =>
+                {
+                    Some((name.clone(), m.property.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// 配置的 UI 后端的 `build` 参数（见 `UiBackend::build_params`），没有配置时为空。
+    fn ui_build_params(&self) -> Vec<(String, String)> {
+        match &self.options.ui_backend {
+            Some(backend) => backend.build_params(),
+            None => Vec::new(),
+        }
+    }
+
+    /// UI 函数（`build`、`@Builder`、`@Styles`）在普通参数后面的后端参数（见 `UiBackend::build_params`）；
+    /// 同时登记后端用到的运行时辅助项（见 `UiBackend::runtime_helpers`）。
+    fn ui_fn_params(&self, backend: &UiBackendRef) -> Vec<String> {
+        for name in backend.runtime_helpers() {
+            self.helper(name);
+        }
+        backend.build_params().iter().map(|(name, ty)| format!("{name}: {ty}")).collect()
+    }
+
+    /// 生成组件的 `build()` 和 `@Builder` 函数 / 方法（见 `BuilderDecl`）。
+    ///
+    /// 没有配置 UI 后端时，函数体里的普通语句和条件照常执行，UI 组件作为注释列在函数前面（见 `gen_ui_outline`）；
+    /// 配置了后端时 UI 组件由后端生成（见 `gen_ui_fn`）。函数体里抛出异常报 `UnsupportedComponentThrow`。
+    fn gen_builder(
+        &mut self,
+        b: &BuilderDecl,
+        is_export: bool,
+        indent: usize,
+        receiver: Option<&str>,
+    ) -> Result<String, Error> {
+        self.gen_builder_fn(b, is_export, indent, receiver).map_err(|e| e.at(b.span.0))
+    }
+
+    fn gen_builder_fn(
+        &mut self,
+        b: &BuilderDecl,
+        is_export: bool,
+        indent: usize,
+        receiver: Option<&str>,
+    ) -> Result<String, Error> {
+        let f = FuncDecl {
+            is_export,
+            body: BlockStmt { stmts: lower_ui(&b.body, &self.builders), span: NodeSpan::default() },
+            ..builder_fn(b)
+        };
+        if stmts_throw(&f.body.stmts, &self.throwing) {
+            return Err(Error::new(ErrorCode::UnsupportedComponentThrow, b.span.0));
+        }
+        match self.options.ui_backend.clone() {
+            Some(backend) => self.gen_ui_fn(&f, &b.body, &backend, indent, receiver),
+            None => {
+                let mut out = String::new();
+                self.gen_ui_outline(&mut out, indent, &b.body, 0)?;
+                out.push_str(&self.gen_fn_item(&f, indent, receiver)?);
+                Ok(out)
+            }
+        }
+    }
+
+    /// 配置了 UI 后端（`CodegenOptions::ui_backend`）时的 `build` 方法和 `@Builder` 函数：
+    ///
+    /// ```text
+    ///     fn build(&mut self, ui: &mut ArkUi) {
+    ///         ui.open("Column", vec![]);
+    ///         ui.open("Text", vec![format!("{:?}", self.message.value.clone())]);
+    ///         ui.close();
+    ///         ui.close();
+    ///     }
+    /// ```
+    ///
+    /// 参数是 `f` 自己的参数加上后端决定的参数（`UiBackend::build_params`），UI 树按顺序生成，见 `gen_ui_nodes`。
+    fn gen_ui_fn(
+        &mut self,
+        f: &FuncDecl,
+        nodes: &[UiNode],
+        backend: &UiBackendRef,
+        indent: usize,
+        receiver: Option<&str>,
+    ) -> Result<String, Error> {
+        let mutable = mutable_params(f);
+        let mut params: Vec<String> = receiver.iter().map(|r| r.to_string()).collect();
+        for p in &f.params {
+            params.push(mut_prefix(&mutable, &p.name) + &self.gen_param(p));
+        }
+        params.extend(self.ui_fn_params(backend));
+        let mut out = String::new();
+        push_indent(&mut out, indent);
+        out.push_str(&format!("{}fn {}({}) {{\n", self.item_vis(f.is_export), f.name, params.join(", ")));
+        let scope = f
+            .params
+            .iter()
+            .map(|p| (p.name.clone(), Binding::Var { ty: Some(param_type(p)), narrowed: false, float: false, cell: false }))
+            .collect();
+        let outer_throws = std::mem::replace(&mut self.fn_throws, false);
+        let outer_labels = std::mem::take(&mut self.try_labels);
+        let outer_slots = std::mem::take(&mut self.finally_slots);
+        let outer_mutable = std::mem::replace(&mut self.mutable, mutable_names(&ui_stmts(nodes)));
+        self.scopes.push(scope);
+        let result = self.gen_ui_block(&mut out, indent + 1, nodes, backend);
+        self.scopes.pop();
+        self.fn_throws = outer_throws;
+        self.try_labels = outer_labels;
+        self.finally_slots = outer_slots;
+        self.mutable = outer_mutable;
+        result?;
+        push_indent(&mut out, indent);
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// 在新的作用域里生成一组 UI 节点（组件的子节点、if 的分支等）。
+    fn gen_ui_block(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        nodes: &[UiNode],
+        backend: &UiBackendRef,
+    ) -> Result<(), Error> {
+        self.scopes.push(HashMap::new());
+        let result = self.gen_ui_nodes(out, indent, nodes, backend);
+        self.scopes.pop();
+        result
+    }
+
+    /// 按顺序生成 UI 节点：
+    /// - `@Builder` 调用（见 `builder_call`）：普通的函数调用，末尾加上后端的实参（`Header(title, ui);`）
+    /// - 普通语句照常生成（连续的几条一起生成，后面的语句能用前面声明的变量）
+    /// - `if` 生成 Rust 的 `if`，分支里还是 UI 节点
+    /// - `ForEach(arr, (item, index) => { .. })` 生成 `for` 循环，见 `gen_for_each`
+    /// - 程序里声明的组件：创建子组件并调用它的 `build`，见 `gen_child_component`
+    /// - 其它（内置组件）：后端的 `open_element`，子节点（多缩进一层），后端的 `close_element`
+    fn gen_ui_nodes(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        nodes: &[UiNode],
+        backend: &UiBackendRef,
+    ) -> Result<(), Error> {
+        let mut rest = nodes;
+        while let Some(node) = rest.first() {
+            let n = self.gen_ui_run(out, indent, rest, backend).map_err(|e| e.at(node.span()))?;
+            rest = &rest[n..];
+        }
+        Ok(())
+    }
+
+    /// 生成 `nodes` 开头的一个 UI 节点（连续的普通语句一起生成），返回用掉的节点数。
+    fn gen_ui_run(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        nodes: &[UiNode],
+        backend: &UiBackendRef,
+    ) -> Result<usize, Error> {
+        let node = &nodes[0];
+        let mut n = 1;
+        if let Some(call) = self.builder_call(node)? {
+            let call = self.gen_expr(&Expr::Call(call))?;
+            push_line(out, indent, &format!("{};", with_ui_args(call, &backend.build_params())));
+            return Ok(n);
+        }
+        match node {
+            UiNode::Stmt(_) => {
+                n = nodes
+                    .iter()
+                    .take_while(|node| matches!(node, UiNode::Stmt(_)) && !matches!(self.builder_call(node), Ok(Some(_))))
+                    .count();
+                let stmts = lower_ui(&nodes[..n], &self.builders);
+                if stmts_throw(&stmts, &self.throwing) {
+                    return Err(Error::new(ErrorCode::UnsupportedComponentThrow, node.span()));
+                }
+                self.gen_stmts_in_scope(out, indent, &ReturnCtx::Function(TypeAnn::Void), &stmts)?;
+            }
+            UiNode::If(i) => {
+                push_indent(out, indent);
+                out.push_str(&format!("if {} {{\n", self.gen_condition(&i.cond)?));
+                let mut branch = i;
+                loop {
+                    self.gen_ui_block(out, indent + 1, &branch.then_branch, backend)?;
+                    match branch.else_branch.as_slice() {
+                        [] => break,
+                        [UiNode::If(next)] => {
+                            push_indent(out, indent);
+                            out.push_str(&format!("}} else if {} {{\n", self.gen_condition(&next.cond)?));
+                            branch = next;
+                        }
+                        nodes => {
+                            push_indent(out, indent);
+                            out.push_str("} else {\n");
+                            self.gen_ui_block(out, indent + 1, nodes, backend)?;
+                            break;
+                        }
+                    }
+                }
+                push_indent(out, indent);
+                out.push_str("}\n");
+            }
+            UiNode::Element(e) if e.name == "ForEach" => self.gen_for_each(out, indent, e, backend)?,
+            UiNode::Element(e) if self.components.iter().any(|c| c.name == e.name) => {
+                self.gen_child_component(out, indent, e, backend)?
+            }
+            UiNode::Element(e) => {
+                let (attrs, styles) = self.ui_attrs(Some(&e.name), &e.attrs, backend)?;
+                let call = UiCall {
+                    name: e.name.clone(),
+                    args: self.ui_codes(&e.args, backend)?,
+                    attrs,
+                    has_children: !e.children.is_empty(),
+                };
+                for line in backend.open_element(&call) {
+                    push_line(out, indent, &line);
+                }
+                for line in &styles {
+                    push_line(out, indent + 1, line);
+                }
+                self.gen_ui_block(out, indent + 1, &e.children, backend)?;
+                for line in backend.close_element(&call) {
+                    push_line(out, indent, &line);
+                }
+            }
+        }
+        Ok(n)
+    }
+
+    /// UI 里对 `@Builder` 的调用：`Header('x')`（UI 组件的写法）、`header('x')` 或组件里的 `this.item(1)`。
+    ///
+    /// 组件写法的实参只能是普通表达式，不能带子节点，否则报 `InvalidBuilderArgs`。
+    fn builder_call(&self, node: &UiNode) -> Result<Option<CallExpr>, Error> {
+        match node {
+            UiNode::Element(e) if self.builders.iter().any(|b| b.name == e.name) => match builder_element_call(e) {
+                Some(call) => Ok(Some(call)),
+                None => Err(Error::new(ErrorCode::InvalidBuilderArgs, e.span.0)),
+            },
+            UiNode::Stmt(Stmt::ExprStmt(Expr::Call(call))) => Ok(match &call.callee {
+                Callee::Ident(name) if self.builders.iter().any(|b| &b.name == name) => Some(call.clone()),
+                Callee::Member { object, property } if object == "this" && self.component_builders.contains(property) => {
+                    Some(call.clone())
+                }
+                _ => None,
+            }),
+            _ => Ok(None),
+        }
+    }
+
+    /// 把 UI 组件 `element`（在 `@Styles` 函数里为 None）的属性方法分成两部分：
+    /// 交给后端的普通属性，以及调用 `@Styles` / `@Extend` 辅助函数的语句（`card(ui);`、`self.fancy(ui);`）。
+    fn ui_attrs(
+        &mut self,
+        element: Option<&str>,
+        attrs: &[UiAttr],
+        backend: &UiBackendRef,
+    ) -> Result<UiAttrs, Error> {
+        let mut plain = Vec::new();
+        let mut styles = Vec::new();
+        for attr in attrs {
+            let at = |e: Error| e.at(attr.span.0);
+            match self.style_call(element, attr)? {
+                Some(call) => {
+                    let call = self.gen_expr(&Expr::Call(call)).map_err(at)?;
+                    styles.push(format!("{};", with_ui_args(call, &backend.build_params())));
+                }
+                None => plain.push((attr.name.clone(), self.ui_codes(&attr.args, backend).map_err(at)?)),
+            }
+        }
+        Ok((plain, styles))
+    }
+
+    /// 属性方法是否是对 `@Styles` / `@Extend` 的调用：组件的 `@Styles` 方法生成 `this.fancy()`，
+    /// 顶层函数生成 `card()`。
+    ///
+    /// 实参只能是普通表达式（`InvalidStyleArgs`）；`@Extend(Text)` 用在别的组件上报 `ExtendTargetMismatch`。
+    fn style_call(&self, element: Option<&str>, attr: &UiAttr) -> Result<Option<CallExpr>, Error> {
+        let callee = if self.component_styles.contains(&attr.name) {
+            Callee::Member {
+                object: "this".to_string(),
+                property: attr.name.clone(),
+            }
+        } else {
+            let mut styles = self.styles.iter().filter(|s| s.name == attr.name).peekable();
+            if styles.peek().is_none() {
+                return Ok(None);
+            }
+            if !styles.any(|s| s.extend.is_none() || s.extend.as_deref() == element) {
+                return Err(Error::new(ErrorCode::ExtendTargetMismatch, attr.span.0));
+            }
+            Callee::Ident(attr.name.clone())
+        };
+        match expr_args(&attr.args) {
+            Some(args) => Ok(Some(CallExpr {
+                callee,
+                args,
+                span: NodeSpan::default(),
+            })),
+            None => Err(Error::new(ErrorCode::InvalidStyleArgs, attr.span.0)),
+        }
+    }
+
+    /// 生成 `@Styles` / `@Extend` 函数和组件的 `@Styles` 方法（见 `StyleDecl`）。
+    ///
+    /// 配置了 UI 后端时生成一个设置这些属性的函数，属性由后端的 `UiBackend::set_attr` 生成：
+    ///
+    /// ```text
+    /// fn title(size: i32, ui: &mut ArkUi) {
+    ///     ui.attr("fontSize", vec![format!("{:?}", size)]);
+    /// }
+    /// ```
+    ///
+    /// 使用它的 UI 组件在 `open_element` 之后调用 `title(24i32, ui);`。
+    /// 没有配置后端时属性只是注释，这里也只把函数列成注释。
+    fn gen_style(
+        &mut self,
+        s: &StyleDecl,
+        is_export: bool,
+        indent: usize,
+        receiver: Option<&str>,
+    ) -> Result<String, Error> {
+        let scope = s
+            .params
+            .iter()
+            .map(|p| (p.name.clone(), Binding::Var { ty: Some(param_type(p)), narrowed: false, float: false, cell: false }))
+            .collect();
+        self.scopes.push(scope);
+        let result = self.gen_style_body(s, is_export, indent, receiver);
+        self.scopes.pop();
+        result.map_err(|e| e.at(s.span.0))
+    }
+
+    fn gen_style_body(
+        &mut self,
+        s: &StyleDecl,
+        is_export: bool,
+        indent: usize,
+        receiver: Option<&str>,
+    ) -> Result<String, Error> {
+        let mut out = String::new();
+        let Some(backend) = self.options.ui_backend.clone() else {
+            let decorator = match &s.extend {
+                Some(target) => format!("@Extend({target})"),
+                None => "@Styles".to_string(),
+            };
+            let params: Vec<&str> = s.params.iter().map(|p| p.name.as_str()).collect();
+            push_line(&mut out, indent, &format!("// {decorator} {}({}) {{", s.name, params.join(", ")));
+            for attr in &s.attrs {
+                let args = self.gen_ui_args(&attr.args).map_err(|e| e.at(attr.span.0))?;
+                push_line(&mut out, indent, &format!("//     .{}({args})", attr.name));
+            }
+            push_line(&mut out, indent, "// }");
+            return Ok(out);
+        };
+        let (attrs, styles) = self.ui_attrs(s.extend.as_deref(), &s.attrs, &backend)?;
+        let mut params: Vec<String> = receiver.iter().map(|r| r.to_string()).collect();
+        params.extend(s.params.iter().map(|p| self.gen_param(p)));
+        params.extend(self.ui_fn_params(&backend));
+        push_line(
+            &mut out,
+            indent,
+            &format!("{}fn {}({}) {{", self.item_vis(is_export), s.name, params.join(", ")),
+        );
+        for (name, args) in &attrs {
+            for line in backend.set_attr(name, args) {
+                push_line(&mut out, indent + 1, &line);
+            }
+        }
+        for line in &styles {
+            push_line(&mut out, indent + 1, line);
+        }
+        push_line(&mut out, indent, "}");
+        Ok(out)
+    }
+
+    /// 把 UI 实参生成为交给后端的 `UiCode`。箭头函数的函数体在这里就地生成（参数登记为局部变量）。
+    fn ui_codes(&mut self, args: &[UiArg], backend: &UiBackendRef) -> Result<Vec<UiCode>, Error> {
+        let mut codes = Vec::new();
+        for arg in args {
+            codes.push(match arg {
+                UiArg::Expr(e) => self.ui_code(e)?,
+                UiArg::Object(props) => {
+                    let mut fields = Vec::new();
+                    for (key, value) in props {
+                        fields.push((key.clone(), self.ui_code(value)?));
+                    }
+                    UiCode::Object(fields)
+                }
+                UiArg::Handler(h) => {
+                    let scope = h
+                        .params
+                        .iter()
+                        .map(|p| (p.name.clone(), Binding::Var { ty: p.ty.clone(), narrowed: false, float: false, cell: false }))
+                        .collect();
+                    self.scopes.push(scope);
+                    let mut body = String::new();
+                    let result = self.gen_ui_nodes(&mut body, 0, &h.body, backend);
+                    self.scopes.pop();
+                    result.map_err(|e| e.at(h.span.0))?;
+                    UiCode::Handler {
+                        params: h.params.iter().map(|p| p.name.clone()).collect(),
+                        body: body.lines().map(str::to_string).collect(),
+                    }
+                }
+            });
+        }
+        Ok(codes)
+    }
+
+    fn ui_code(&mut self, e: &Expr) -> Result<UiCode, Error> {
+        Ok(match self.ui_enum(e) {
+            Some((name, member)) => UiCode::Enum(name, member),
+            None => UiCode::Expr(self.gen_expr(e)?),
+        })
+    }
+
+    /// `ForEach(arr, (item, index) => { .. }, keyGen?)` 生成 for 循环（`keyGen` 只用于 ArkUI 的增量更新，这里忽略）：
+    ///
+    /// ```text
+    /// for (index, item) in self.items.value.clone().into_iter().enumerate() {
+    ///     let index = index as i32;
+    ///     ..
+    /// }
+    /// ```
+    ///
+    /// 没有 `index` 参数时是 `for item in .. {`。实参不是这种形式时报 `InvalidForEach`。
+    fn gen_for_each(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        e: &UiElement,
+        backend: &UiBackendRef,
+    ) -> Result<(), Error> {
+        let (arr, handler) = match e.args.as_slice() {
+            [UiArg::Expr(arr), UiArg::Handler(h)] | [UiArg::Expr(arr), UiArg::Handler(h), UiArg::Handler(_)]
+                if (1..=2).contains(&h.params.len()) =>
+            {
+                (arr, h)
+            }
+            _ => return Err(Error::new(ErrorCode::InvalidForEach, e.span.0)),
+        };
+        let elem = match self.expr_type(arr) {
+            Some(TypeAnn::Array(elem)) => Some(*elem),
+            _ => None,
+        };
+        // 局部变量要 clone，否则循环会把它移走
+        let items = match strip_group(arr) {
+            Expr::Ident(_, _) => read_place(self.gen_expr(arr)?, None),
+            _ => self.gen_expr(arr)?,
+        };
+        let item = &handler.params[0];
+        let binding = Binding::Var { ty: item.ty.clone().or(elem), narrowed: false, float: false, cell: false };
+        let mut scope = HashMap::from([(item.name.clone(), binding)]);
+        push_indent(out, indent);
+        match handler.params.get(1) {
+            None => out.push_str(&format!("for {} in {items} {{\n", item.name)),
+            Some(index) => {
+                out.push_str(&format!(
+                    "for ({}, {}) in {items}.into_iter().enumerate() {{\n",
+                    index.name, item.name
+                ));
+                push_line(out, indent + 1, &format!("let {0} = {0} as {1};", index.name, self.num_type()));
+                let binding = Binding::Var { ty: Some(TypeAnn::Number), narrowed: false, float: false, cell: false };
+                scope.insert(index.name.clone(), binding);
+            }
+        }
+        self.scopes.push(scope);
+        let result = self.gen_ui_nodes(out, indent + 1, &handler.body, backend);
+        self.scopes.pop();
+        result.map_err(|e| e.at(handler.span.0))?;
+        push_indent(out, indent);
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    /// 在 `build` 里使用程序里声明的组件 `Child({ title: this.name, count: this.count })`：
+    ///
+    /// ```text
+    /// {
+    ///     let mut __child = Child::new(self.name.clone(), self.count.clone());
+    ///     __child.build(ui);
+    /// }
+    /// ```
+    ///
+    /// - 没有初始值的字段必须传入（`MissingComponentProp`），传入子组件没有的字段报 `UnknownComponentProp`
+    /// - 有初始值的字段也可以传入，创建后再覆盖
+    /// - `@Link` 字段要传入父组件的共享字段 `this.x`（`@Link` / `@Provide` / `@Consume`），
+    ///   生成 `self.x.clone()`（复制的是 `Rc`，两边共享同一个值），否则报 `LinkNeedsSharedSource`
+    /// - 子组件有 `@Consume` 字段时用父组件的 `__ark_provide` 准备 `ArkContext`；
+    ///   父组件没有 `@Provide` 字段时报 `ConsumeWithoutProvide`，给 `@Consume` 字段传值报 `UnknownComponentProp`
+    /// - 实参不是一个对象字面量（或者没有实参）时报 `InvalidComponentArgs`
+    fn gen_child_component(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        e: &UiElement,
+        backend: &UiBackendRef,
+    ) -> Result<(), Error> {
+        let Some(child) = self.components.iter().find(|c| c.name == e.name).cloned() else {
+            return Ok(());
+        };
+        let props: &[(String, Expr)] = match e.args.as_slice() {
+            [] => &[],
+            [UiArg::Object(props)] => props,
+            _ => return Err(Error::new(ErrorCode::InvalidComponentArgs, e.span.0)),
+        };
+        for (key, value) in props {
+            if !child.fields.iter().any(|f| &f.name == key) {
+                return Err(Error::new(ErrorCode::UnknownComponentProp, value.span()));
+            }
+        }
+        let mut args = Vec::new();
+        let mut overrides = Vec::new();
+        let mut needs_ctx = false;
+        for (field, ty, kind) in self.component_fields(&child)? {
+            let name = &field.name;
+            let value = props.iter().find(|(key, _)| key == name).map(|(_, value)| value);
+            match (&kind, &field.init, value) {
+                (Some(StateKind::Consume(_)), _, None) => needs_ctx = true,
+                (Some(StateKind::Consume(_)), _, Some(value)) => {
+                    return Err(Error::new(ErrorCode::UnknownComponentProp, value.span()));
+                }
+                (Some(StateKind::Link), _, Some(value)) => args.push(self.link_source(value)?),
+                (_, None, Some(value)) => args.push(self.gen_expr_as(value, Some(&ty))?),
+                (_, None, None) => return Err(Error::new(ErrorCode::MissingComponentProp, e.span.0)),
+                (_, Some(_), Some(value)) => {
+                    let value = self.gen_expr_as(value, Some(&ty))?;
+                    overrides.push(match kind {
+                        Some(StateKind::State | StateKind::Prop) => format!("__child.{name}.value = {value};"),
+                        Some(_) => format!("__child.{name}.set({value});"),
+                        None => format!("__child.{name} = {value};"),
+                    });
+                }
+                (_, Some(_), None) => {}
+            }
+        }
+
+        push_line(out, indent, "{");
+        if needs_ctx {
+            if !self.state_fields.values().any(|kind| matches!(kind, StateKind::Provide(_))) {
+                return Err(Error::new(ErrorCode::ConsumeWithoutProvide, e.span.0));
+            }
+            push_line(out, indent + 1, &format!("let mut __ctx = {}::new();", self.helper("ArkContext")));
+            push_line(out, indent + 1, "self.__ark_provide(&mut __ctx);");
+            args.push("&__ctx".to_string());
+        }
+        push_line(out, indent + 1, &format!("let mut __child = {}::new({});", child.name, args.join(", ")));
+        for line in &overrides {
+            push_line(out, indent + 1, line);
+        }
+        let build_args: Vec<String> = backend.build_params().into_iter().map(|(name, _)| name).collect();
+        push_line(out, indent + 1, &format!("__child.build({});", build_args.join(", ")));
+        push_line(out, indent, "}");
+        Ok(())
+    }
+
+    /// 传给子组件 `@Link` 字段的值：父组件的共享字段 `this.x`，生成 `self.x.clone()`。
+    fn link_source(&self, value: &Expr) -> Result<String, Error> {
+        match strip_group(value) {
+            Expr::Member(m) if matches!(&*m.object, Expr::Ident(object, _) if object == "this") => {
+                match self.state_fields.get(&m.property) {
+                    Some(StateKind::Link | StateKind::Provide(_) | StateKind::Consume(_)) => {
+                        Ok(format!("self.{}.clone()", m.property))
+                    }
+                    _ => Err(Error::new(ErrorCode::LinkNeedsSharedSource, value.span())),
+                }
+            }
+            _ => Err(Error::new(ErrorCode::LinkNeedsSharedSource, value.span())),
+        }
+    }
+
+    /// 生成执行顶层语句的函数（`main`，或模块树里各模块的 `__init`）。
+    ///
+    /// `calls` 是开头要先调用的无参函数：`(路径, 是否会抛异常)`，用于 main 先执行依赖模块的顶层代码。
+    ///
+    /// 顶层代码里未被捕获的异常：函数返回 `Result`，main 的话 Rust 运行时会打印错误并以非 0 退出，
+    /// 效果接近 JS 里“未捕获异常终止程序”。
+    fn gen_top_level_fn(
+        &mut self,
+        vis: &str,
+        name: &str,
+        calls: &[(String, bool)],
+        stmts: &[Stmt],
+    ) -> Result<String, Error> {
+        let mut out = String::new();
+        self.fn_throws = stmts_throw(stmts, &self.throwing) || calls.iter().any(|(_, throws)| *throws);
+        if self.fn_throws {
+            out.push_str(&format!("{vis}fn {name}() -> Result<(), ArkError> {{\n"));
+        } else {
+            out.push_str(&format!("{vis}fn {name}() {{\n"));
+        }
+        for (path, throws) in calls {
+            out.push_str(&format!("    {path}(){};\n", if *throws { "?" } else { "" }));
+        }
+        self.mutable = mutable_names(stmts);
+        self.gen_stmts(&mut out, 1, &ReturnCtx::Main, stmts)?;
+        if name == "main" {
+            for line in &self.entry {
+                out.push_str(&format!("    {line}\n"));
+            }
+        }
+        if self.fn_throws {
+            out.push_str("    Ok(())\n");
+        }
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// 生成模块树：每个 `Unit`（一个 `.ets` 文件）一个 Rust 文件。
+    ///
+    /// - 入口文件是 crate 根（`main.rs`，库模式下 `lib.rs`），开头是 `mod x;` 声明和 `ArkError`
+    /// - 其它文件按模块路径放置：`common/log` -> `common/log.rs`；有子模块的模块放在 `mod.rs` 里
+    ///   （`common.ets` 和 `common/log.ets` 同时存在时 -> `common/mod.rs` + `common/log.rs`）
+    /// - import 生成 `use crate::util::f;`，re-export 生成 `pub(crate) use`（库模式下 `pub use`）
+    /// - 导出的函数是 `pub(crate)`（库模式下 `pub`），未导出的保持私有
+    /// - 依赖模块的顶层语句放进 `pub(crate) fn __init()`，main 开头按加载顺序调用
+    fn gen_tree(&mut self, program: &Program, units: &[Unit]) -> Result<Vec<OutputFile>, Error> {
+        self.check(program)?;
+        self.check_error_classes()?;
+        self.check_externs(program)?;
+
+        // 所有模块路径（含只有子模块、没有对应文件的中间目录）
+        let mut mods: BTreeSet<Vec<String>> = BTreeSet::new();
+        for u in units {
+            for i in 1..=u.path.len() {
+                mods.insert(u.path[..i].to_vec());
+            }
+        }
+        let children = |parent: &[String]| -> Vec<String> {
+            mods.iter()
+                .filter(|m| m.len() == parent.len() + 1 && m.starts_with(parent))
+                .map(|m| m[parent.len()].clone())
+                .collect()
+        };
+        // 根模块的子模块整个 crate 都能访问；更深的模块要 `pub(crate)` 才能被其它目录的文件 use
+        let mode = self.mode;
+        let mod_decls = |parent: &[String]| -> String {
+            let vis = match mode {
+                OutputMode::Library | OutputMode::NativeModule => "pub ",
+                OutputMode::Binary if parent.is_empty() => "",
+                OutputMode::Binary => "pub(crate) ",
+            };
+            let mut out: String = children(parent)
+                .iter()
+                .map(|c| format!("{vis}mod {c};\n"))
+                .collect();
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out
+        };
+
+        let mut files = Vec::new();
+        let mut inits = Vec::new();
+        let mut root = String::new();
+        for u in units {
+            let is_root = u.path.is_empty();
+            self.set_source(&u.program);
+            // 每个模块有自己的常量作用域，`main`/`__init` 里也要能看到本模块的常量
+            self.scopes.push(HashMap::new());
+            let imported: HashSet<String> = units
+                .iter()
+                .flat_map(|other| &other.uses)
+                .filter_map(|used| match used {
+                    Use::Item { module, target, .. } if *module == u.path => Some(target.clone()),
+                    _ => None,
+                })
+                .collect();
+            let (mut body, stmts) = self.gen_unit(u, &imported)?;
+            if is_root {
+                if self.mode == OutputMode::Binary {
+                    self.entry = [
+                        entry_ability(&u.program.abilities),
+                        entry_component(&u.program.components, self.options.ui_backend.as_ref())?,
+                    ]
+                    .concat();
+                    body.push_str(&self.gen_top_level_fn("", "main", &inits, &stmts)?);
+                }
+                self.scopes.pop();
+                if self.mode == OutputMode::NativeModule {
+                    // 入口文件自己导出的函数，加上它 re-export 的其它文件里的函数
+                    let mut exports: Vec<(&str, &FuncDecl)> =
+                        u.program.funcs.iter().filter(|f| f.is_export).map(|f| (f.name.as_str(), f)).collect();
+                    for used in &u.uses {
+                        if let Use::Item { target, name, is_export: true, .. } = used {
+                            if let Some(f) = program.funcs.iter().find(|f| &f.name == target) {
+                                exports.push((name.as_str(), f));
+                            }
+                        }
+                    }
+                    body.push_str(&self.gen_napi_exports(&exports)?);
+                }
+                root = self.with_helpers(body);
+                continue;
+            }
+            if self.mode == OutputMode::Binary && !stmts.is_empty() {
+                let init = self.gen_top_level_fn("pub(crate) ", "__init", &[], &stmts)?;
+                inits.push((format!("crate::{}::__init", u.path.join("::")), self.fn_throws));
+                body.push_str(&init);
+            }
+            self.scopes.pop();
+            let body = self.with_helpers(body);
+            let mut code = mod_decls(&u.path);
+            if body.contains("ArkError") {
+                code.push_str("use crate::ArkError;\n\n");
+            }
+            code.push_str(&body);
+            files.push(OutputFile {
+                path: module_file(&u.path, !children(&u.path).is_empty()),
+                code,
+            });
+        }
+        // 没有对应 `.ets` 文件的中间目录：只有子模块声明
+        for m in &mods {
+            if !units.iter().any(|u| &u.path == m) {
+                files.push(OutputFile {
+                    path: module_file(m, true),
+                    code: mod_decls(m),
+                });
+            }
+        }
+
+        let needs_error_enum = root.contains("ArkError")
+            || files.iter().any(|f| f.code.contains("ArkError"))
+            || !self.error_classes.is_empty();
+        let mut code = mod_decls(&[]);
+        if needs_error_enum {
+            code.push_str(&self.error_enum());
+        }
+        code.push_str(&root);
+        let root_name = match self.mode {
+            OutputMode::Binary => "main.rs",
+            OutputMode::Library | OutputMode::NativeModule => "lib.rs",
+        };
+        // 2015 edition：其它模块用到的外部 crate 也要在 crate 根声明，模块里再 `use` 进来
+        let mut crates = self.extern_crates(&code);
+        for f in &mut files {
+            let used = self.extern_crates(&f.code);
+            let uses: String = used.iter().map(|c| format!("use {c};\n")).collect();
+            if !uses.is_empty() {
+                f.code.insert_str(0, &(uses + "\n"));
+            }
+            crates.extend(used);
+        }
+        if !crates.is_empty() {
+            let decls: String = crates.iter().map(|c| format!("extern crate {c};\n")).collect();
+            code.insert_str(0, &(decls + "\n"));
+        }
+        files.insert(
+            0,
+            OutputFile {
+                path: PathBuf::from(root_name),
+                code,
+            },
+        );
+        Ok(files)
+    }
+
+    /// 模块树里一个文件的内容（不含 `mod` 声明和 `__init`/`main`）：`use` 声明、常量、函数。
+    /// 同时返回留给 `__init`/`main` 的顶层语句（见 `gen_shared_consts`）。
+    ///
+    /// `imported` 是其它文件从这个文件 import 的名字：其中的 const 要生成模块级的 `const`。
+    fn gen_unit(&mut self, unit: &Unit, imported: &HashSet<String>) -> Result<(String, Vec<Stmt>), Error> {
+        let mut out = String::new();
+        for u in &unit.uses {
+            match u {
+                Use::Item {
+                    module,
+                    target,
+                    name,
+                    is_export,
+                } => {
+                    let vis = match (is_export, self.mode) {
+                        (false, _) => "",
+                        (true, OutputMode::Library | OutputMode::NativeModule) => "pub ",
+                        (true, OutputMode::Binary) => "pub(crate) ",
+                    };
+                    // namespace 声明对应的是 Rust 模块，用模块名
+                    let item = self.mod_names.get(target).unwrap_or(target);
+                    let rename = if name == target { String::new() } else { format!(" as {name}") };
+                    out.push_str(&format!("{vis}use crate::{}::{item}{rename};\n", module.join("::")));
+                }
+                Use::Module { module, name } => {
+                    out.push_str(&format!("use crate::{} as {name};\n", module.join("::")));
+                }
+            }
+        }
+        if !unit.uses.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&gen_system_uses(&unit.program.system_imports));
+
+        self.in_submodule = !unit.path.is_empty();
+        let items = self.gen_unit_items(unit, imported);
+        self.in_submodule = false;
+        let (items, stmts) = items?;
+        out.push_str(&items);
+        Ok((out, stmts))
+    }
+
+    fn gen_unit_items(&mut self, unit: &Unit, imported: &HashSet<String>) -> Result<(String, Vec<Stmt>), Error> {
+        let mut out = String::new();
+        let stmts = match self.mode.is_library() {
+            true => {
+                out.push_str(&self.gen_library_consts(&unit.program.stmts)?);
+                Vec::new()
+            }
+            false => {
+                let (consts, stmts) = self.gen_shared_consts(&unit.program, imported)?;
+                out.push_str(&consts);
+                stmts
+            }
+        };
+        out.push_str(&self.gen_items(&unit.program)?);
+        Ok((out, stmts))
+    }
+
+    /// 导出项的可见性：
+    /// - 库模式、namespace 里：`pub`
+    /// - 模块树里的非根模块：`pub(crate)`（其它文件要能 `use` 它）
+    /// - 其它情况（单文件可执行程序）：私有
+    fn item_vis(&self, is_export: bool) -> &'static str {
+        if !is_export {
+            ""
+        } else if self.mode.is_library() || self.in_namespace {
+            "pub "
+        } else if self.in_submodule {
+            "pub(crate) "
+        } else {
+            ""
+        }
+    }
+
+    /// 生成模块合并时留下的别名和命名空间：
+    ///
+    /// ```text
+    /// use crate::double as twice;
+    ///
+    /// mod util {
+    ///     pub(crate) use super::square;
+    /// }
+    /// ```
+    ///
+    /// 库模式下，re-export 产生的别名生成 `pub use`。
+    fn gen_links(&self, program: &Program) -> String {
+        let mut out = gen_system_uses(&program.system_imports);
+        for a in &program.aliases {
+            let vis = if a.is_export && self.mode.is_library() {
+                "pub "
+            } else {
+                ""
+            };
+            out.push_str(&format!("{vis}use crate::{} as {};\n", a.target, a.name));
+        }
+        if !program.aliases.is_empty() {
+            out.push('\n');
+        }
+        for ns in &program.namespaces {
+            out.push_str(&format!("mod {} {{\n", ns.name));
+            for m in &ns.members {
+                if m.name == m.target {
+                    out.push_str(&format!("    pub(crate) use super::{};\n", m.target));
+                } else {
+                    out.push_str(&format!("    pub(crate) use super::{} as {};\n", m.target, m.name));
+                }
+            }
+            out.push_str("}\n\n");
+        }
+        out
+    }
+
+    /// 生成 namespace：
+    ///
+    /// ```text
+    /// mod utils {
+    ///     use super::*;
+    ///
+    ///     pub fn f() { .. }
+    /// }
+    /// ```
+    ///
+    /// - 导出的函数生成 `pub fn`，其它函数只在模块内部可见
+    /// - 函数体用到了模块外的名字（顶层函数、其它 namespace、`ArkError`）时才加 `use super::*;`
+    /// - 库模式下 `export namespace` 生成 `pub mod`
+    fn gen_namespace(&mut self, ns: &NamespaceDecl) -> Result<String, Error> {
+        let siblings: HashMap<String, Binding> = ns
+            .funcs
+            .iter()
+            .map(|f| (f.name.clone(), func_binding(f, false)))
+            .collect();
+        let view = namespace_view(ns, &self.throwing);
+        let outer_throwing = std::mem::replace(&mut self.throwing, view);
+        self.scopes.push(siblings);
+        self.in_namespace = true;
+        let body = self.gen_namespace_funcs(ns);
+        self.in_namespace = false;
+        self.scopes.pop();
+        self.throwing = outer_throwing;
+        let body = body?;
+
+        let uses_outer = ns.funcs.iter().any(|f| {
+            free_vars(f)
+                .iter()
+                .any(|name| !ns.funcs.iter().any(|g| &g.name == name) && !is_global_builtin(name))
+        });
+        let vis = self.item_vis(ns.is_export);
+        let mut out = format!("{vis}mod {} {{\n", snake_case(&ns.name));
+        if uses_outer || body.contains("ArkError") {
+            out.push_str("    use super::*;\n\n");
+        }
+        out.push_str(body.trim_end_matches('\n'));
+        out.push_str("\n}\n");
+        Ok(out)
+    }
+
+    fn gen_namespace_funcs(&mut self, ns: &NamespaceDecl) -> Result<String, Error> {
+        let mut out = String::new();
+        for f in &ns.funcs {
+            out.push_str(&self.gen_func_decl(f, 1)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// 在文件开头加上生成 `out` 时登记的运行时辅助项（见 `helper`）的定义和它们用到的辅助项：`Math.random()` 的
+    /// `__ark_random`（见 `runtime::RANDOM_HELPER`）、`Date`、数字转换、字符串方法和值转文字的辅助函数（见 `runtime::DATE_HELPERS`、
+    /// `runtime::CONVERSION_HELPERS`、`runtime::STRING_HELPERS`、`runtime::FORMAT_HELPERS`），组件状态字段的 `ArkState` 等类型
+    /// （见 `runtime::STATE_HELPERS`），Ability 和 UI 后端的运行时（见 `runtime::ABILITY_HELPERS`、`UiBackend::runtime_helpers`）；
+    /// 用到了 `Date` 类型时加上 `ArkDate` 类型别名。配置了 `CodegenOptions::runtime` 时不加定义，改成从运行时模块 `use` 这些项。
+    fn with_helpers(&self, mut out: String) -> String {
+        let used = std::mem::take(&mut *self.helpers.borrow_mut());
+        let required = match &self.options.runtime {
+            Some(path) => {
+                out = runtime::imports(out, path, &used);
+                used
+            }
+            None => {
+                let required = runtime::required(&used);
+                out = runtime::inline(out, &required);
+                required
+            }
+        };
+        if let Some(path) = &self.options.ability_runtime {
+            if out.contains("impl UIAbility for ") {
+                out.insert_str(0, &format!("use {path}::{{LaunchParam, UIAbility, Want, WindowStage}};\n\n"));
+            }
+        }
+        if let Some(backend) = &self.options.ui_backend {
+            if out.contains("fn build(&mut self") {
+                out.insert_str(0, &backend.prelude());
+            }
+        }
+        if required.contains("ArkDate") {
+            let alias = if self.options.use_chrono {
+                "chrono::DateTime<chrono::Utc>"
+            } else {
+                "std::time::SystemTime"
+            };
+            out.insert_str(0, &format!("type ArkDate = {alias};\n\n"));
+        }
+        out
+    }
+
+    /// `Edition::E2015` 时在开头给用到的外部 crate 写 `extern crate name;`，其它 edition 原样返回。
+    fn with_extern_crates(&self, mut out: String) -> String {
+        let crates = self.extern_crates(&out);
+        if !crates.is_empty() {
+            let decls: String = crates.iter().map(|c| format!("extern crate {c};\n")).collect();
+            out.insert_str(0, &(decls + "\n"));
+        }
+        out
+    }
+
+    /// `Edition::E2015` 时 `code` 里用到的外部 crate：内置支持的 crate（`rand`、`serde_json` 等），
+    /// 以及 `--bind`、`ability_runtime`、`resource_lookup` 配置的路径的第一段。
+    fn extern_crates(&self, code: &str) -> BTreeSet<String> {
+        if self.options.edition != Edition::E2015 {
+            return BTreeSet::new();
+        }
+        EXTERNAL_CRATES
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(configured_crates(&self.externs, &self.options))
+            .filter(|c| uses_crate(code, c))
+            .map(String::from)
+            .collect()
+    }
+
+    /// throw/try、Promise 生成的代码都会用到 `ArkError`：先生成完整程序，再决定要不要在开头输出定义。
+    fn with_error_enum(&self, mut out: String) -> String {
+        if out.contains("ArkError") || !self.error_classes.is_empty() {
+            out.insert_str(0, &self.error_enum());
+        }
+        out
+    }
+
+    /// 库模式下的顶层语句：只允许 `const NAME = <常量表达式>;`，
+    /// 生成 `const NAME: i32 = 1i32;`（导出的加 `pub`），其它语句报 `TopLevelCodeInLibrary`。
+    ///
+    /// 常量表达式由 number/boolean 字面量、前面声明过的常量和运算符组成：
+    /// `const AREA = WIDTH * 2;` -> `const AREA: i32 = WIDTH * 2i32;`
+    ///
+    /// 字符串常量只在 `StringMode::Static` 下可以（`const NAME: &'static str = "..";`），
+    /// 其它模式不行：Rust 的 `const` 不能调用 `String::from`；函数调用也不行。
+    ///
+    /// `CodegenOptions::skip_top_level_code` 时跳过 const 声明以外的语句（`library_warnings` 给出警告）。
+    fn gen_library_consts(&mut self, stmts: &[Stmt]) -> Result<String, Error> {
+        let mut out = String::new();
+        for s in stmts {
+            let Stmt::VarDecl(VarDecl {
+                is_export,
+                is_const: true,
+                decls,
+                ..
+            }) = s
+            else {
+                if self.options.skip_top_level_code {
+                    continue;
+                }
+                return Err(Error::new(ErrorCode::TopLevelCodeInLibrary, s.span()));
+            };
+            for d in decls {
+                let item = self.gen_module_const(d, *is_export);
+                out.push_str(&item.map_err(|_| Error::new(ErrorCode::TopLevelCodeInLibrary, s.span()))?);
+            }
+        }
+        Ok(out)
+    }
+
+    /// 生成可执行程序时，函数等顶层项里用到的顶层 `const`（见 `sema::shared_consts`）和其它模块 import 的 const（`imported`）
+    /// 不能是 `main`（`__init`）的局部变量，和库模式一样生成模块级的 `const`（见 `gen_library_consts`）；
+    /// 初始值不是常量表达式时报 `SharedConstNotConstant`。其它导出的 const 初始值是常量表达式时也生成模块级的 `const`。
+    /// 返回生成的代码和去掉这些声明以后留给 `main` 的语句。
+    fn gen_shared_consts(&mut self, program: &Program, imported: &HashSet<String>) -> Result<(String, Vec<Stmt>), Error> {
+        let mut shared = sema::shared_consts(program);
+        shared.extend(imported.iter().cloned());
+        let shared = sema::with_const_deps(program, shared);
+        let mut out = String::new();
+        let mut stmts = Vec::new();
+        for s in &program.stmts {
+            let Stmt::VarDecl(v) = s else {
+                stmts.push(s.clone());
+                continue;
+            };
+            let mut rest = Vec::new();
+            for d in &v.decls {
+                let required = d.pattern.names().iter().any(|name| shared.contains(*name));
+                if !(required || v.is_export && v.is_const) {
+                    rest.push(d.clone());
+                    continue;
+                }
+                match self.gen_module_const(d, v.is_export) {
+                    Ok(item) => out.push_str(&item),
+                    Err(()) if !required => rest.push(d.clone()),
+                    Err(()) => return Err(Error::new(ErrorCode::SharedConstNotConstant, s.span())),
+                }
+            }
+            if !rest.is_empty() {
+                stmts.push(Stmt::VarDecl(VarDecl { decls: rest, ..v.clone() }));
+            }
+        }
+        Ok((out, stmts))
+    }
+
+    /// 把一个顶层 const 声明项生成为模块级的 `const NAME: T = value;`（见 `gen_library_consts`）并登记。
+    /// 不是常量表达式时返回 Err，由调用方换成具体的错误码。
+    fn gen_module_const(&mut self, d: &Declarator, is_export: bool) -> Result<String, ()> {
+        let Declarator {
+            pattern: Pattern::Ident(name),
+            ty,
+            init: Some(init),
+            ..
+        } = d
+        else {
+            return Err(());
+        };
+        if !self.is_const_expr(init) && !self.is_const_str(init) {
+            return Err(());
+        }
+        let ty = match ty.clone().or_else(|| self.expr_type(init)) {
+            Some(t @ (TypeAnn::Number | TypeAnn::Boolean)) => t,
+            Some(TypeAnn::String) if self.is_const_str(init) => TypeAnn::String,
+            _ => return Err(()),
+        };
+        let vis = self.item_vis(is_export);
+        let value = self.gen_expr_as(init, Some(&ty)).map_err(|_| ())?;
+        self.declare(name, Binding::Var {
+            ty: Some(ty.clone()),
+            narrowed: false,
+            float: false,
+            cell: false,
+        });
+        Ok(format!("{vis}const {name}: {} = {value};\n\n", self.rust_type(&ty)))
+    }
+
+    /// `StringMode::Static` 下字符串常量也能写进 `const`：字符串字面量或者已声明的字符串常量。
+    fn is_const_str(&self, e: &Expr) -> bool {
+        self.options.string_mode == StringMode::Static
+            && match strip_group(e) {
+                Expr::Literal(Literal::String(_), _) => true,
+                Expr::Ident(name, _) => self.var_type(name) == Some(TypeAnn::String),
+                _ => false,
+            }
+    }
+
+    /// 能不能写进 Rust 的 `const`：number/boolean 字面量、已声明的常量，以及它们的运算。
+    fn is_const_expr(&self, e: &Expr) -> bool {
+        match e {
+            Expr::Literal(Literal::Number(_) | Literal::Float(_) | Literal::Bool(_), _) => true,
+            Expr::Ident(name, _) => matches!(self.var_type(name), Some(TypeAnn::Number | TypeAnn::Boolean)),
+            Expr::Unary(u) => self.is_const_expr(&u.expr),
+            Expr::Binary(b) if b.op != BinaryOp::NullishCoalesce => {
+                self.is_const_expr(&b.left) && self.is_const_expr(&b.right)
+            }
+            Expr::Group(inner) => self.is_const_expr(inner),
+            _ => false,
+        }
+    }
+
+    /// 在一个新的块作用域里生成一组语句（函数体、代码块、if/while 分支）。
+    ///
+    /// 块内函数声明的处理（JS 的 hoisting 语义）：
+    /// - 不捕获外层局部变量：生成嵌套 `fn`。Rust 的 item 本身在整个块内可见，天然等价于提升。
+    /// - 捕获了外层局部变量：生成 `let f = |..| { .. };` 闭包。闭包不能提升到它捕获的变量之前，
+    ///   所以放在“所捕获的本块变量都已声明”之后的最早位置，而不是原来的位置。
+    fn gen_stmts(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmts: &[Stmt],
+    ) -> Result<(), Error> {
+        self.scopes.push(HashMap::new());
+        let result = self.gen_stmts_in_scope(out, indent, ctx, stmts);
+        self.scopes.pop();
+        result
+    }
+
+    fn gen_stmts_in_scope(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmts: &[Stmt],
+    ) -> Result<(), Error> {
+        // 1) 先登记本块的变量和函数（函数声明会被提升，变量用于判断捕获）。
+        let cells = cell_vars(stmts);
+        let mut funcs = Vec::new();
+        for (i, s) in stmts.iter().enumerate() {
+            match s {
+                Stmt::VarDecl(v) => {
+                    for d in &v.decls {
+                        match &d.pattern {
+                            Pattern::Ident(name) => {
+                                if d.init.is_none() {
+                                    self.uninit_vars.insert(name.clone());
+                                }
+                                self.declare(
+                                    name,
+                                    Binding::Var {
+                                        ty: d.ty.clone(),
+                                        narrowed: false,
+                                        float: false,
+                                        cell: d.init.is_some() && cells.contains(name),
+                                    },
+                                )
+                            }
+                            // 解构出的变量类型要到生成时才能确定，这里只登记名字。
+                            p => {
+                                for name in p.names() {
+                                    self.declare(name, Binding::Var { ty: None, narrowed: false, float: false, cell: false });
+                                }
+                            }
+                        }
+                    }
+                }
+                Stmt::FuncDecl(f) => {
+                    self.declare(&f.name, func_binding(f, false));
+                    funcs.push((i, f));
+                }
+                _ => {}
+            }
+        }
+
+        // 2) 判断哪些函数要生成闭包。调用了闭包的函数本身也在捕获，所以迭代到不再变化为止。
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (_, f) in &funcs {
+                if !self.is_closure(&f.name) && self.captures_locals(f) {
+                    self.declare(&f.name, func_binding(f, true));
+                    changed = true;
+                }
+            }
+        }
+
+        // 3) 计算每个闭包的插入位置：在它捕获的本块变量/闭包之后。
+        let mut slots: Vec<Option<usize>> = vec![None; stmts.len()];
+        for (i, f) in &funcs {
+            if self.is_closure(&f.name) {
+                // Rust 闭包无法直接递归调用自己。
+                if free_vars(f).contains(&f.name) {
+                    return Err(Error::new(ErrorCode::RecursiveClosureUnsupported, Span::default()));
+                }
+                // 捕获了没有初始值的变量：留在原来的位置，明确赋值检查保证那里变量已经赋值。
+                let captures_uninit = free_vars(f).iter().any(|n| self.uninit_vars.contains(n));
+                slots[*i] = Some(if captures_uninit { *i } else { 0 });
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, f) in &funcs {
+                let Some(cur) = slots[*i] else { continue };
+                let mut pos = cur;
+                for name in free_vars(f) {
+                    for (j, s) in stmts.iter().enumerate() {
+                        match s {
+                            Stmt::VarDecl(v) if v.names().contains(&name.as_str()) => {
+                                pos = pos.max(j + 1)
+                            }
+                            Stmt::FuncDecl(g) if g.name == name && j != *i => {
+                                if let Some(p) = slots[j] {
+                                    pos = pos.max(p);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                if pos != cur {
+                    slots[*i] = Some(pos);
+                    changed = true;
+                }
+            }
+        }
+
+        // 4) 依次输出：位置 k 处先输出被安排在这里的闭包，再输出第 k 条语句。
+        //    同一位置的闭包按依赖排序：被调用的闭包先声明；互相调用的闭包没有合法的顺序，报错。
+        for k in 0..=stmts.len() {
+            let mut pending: Vec<&FuncDecl> =
+                funcs.iter().filter(|(i, _)| slots[*i] == Some(k)).map(|(_, f)| *f).collect();
+            while !pending.is_empty() {
+                let ready = pending.iter().position(|f| {
+                    let used = free_vars(f);
+                    !pending.iter().any(|g| g.name != f.name && used.contains(&g.name))
+                });
+                let Some(ready) = ready else {
+                    return Err(Error::new(ErrorCode::RecursiveClosureUnsupported, Span::default()));
+                };
+                let f = pending.remove(ready);
+                let escapes = stmts.iter().any(|s| stmt_returns_name(s, &f.name));
+                out.push_str(&self.gen_closure(f, indent, escapes)?);
+            }
+            if let Some(s) = stmts.get(k) {
+                self.gen_stmt_into(out, indent, ctx, s)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 生成变量声明。
+    ///
+    /// 映射规则：ArkTS 的 `let` 和 `const` 都生成 Rust 的 `let`；之后会被赋值（或被修改字段/下标）的变量
+    /// 加上 `mut`（见 `mutable_names`），其它变量不加，避免 rustc 的 `unused_mut` 警告。
+    ///
+    /// 例：
+    /// - `let x = 1;` -> `let x = 1i32;`，后面有 `x = 2;` 时是 `let mut x = 1i32;`
+    /// - `const s = "hi";` -> `let s = String::from("hi");`
+    /// - `let n: number | null = null;` -> `let n: Option<i32> = None;`
+    ///
+    /// 有类型标注时会同时输出 Rust 类型标注；没有标注时不能用 `null` 初始化，
+    /// 因为推断不出 `Option<T>` 里的 `T`。
+    ///
+    /// 解构声明会展开成多条 `let`，见 `gen_destructure`。
+    ///
+    /// 没有初始值的声明只声明变量：`let x: number;` -> `let x: i32;`。第一次赋值只是初始化，
+    /// 可能赋值两次以上时才要 `mut`。读取前一定赋过值由 `flow::check` 保证。
+    ///
+    /// 一条语句声明多个变量时按顺序每个变量一条 `let`：
+    /// `let a = 1, b = a;` -> `let a = 1i32;` `let b = a;`
+    fn gen_var_decl(&mut self, v: &VarDecl) -> Result<Vec<String>, Error> {
+        let mut lines = Vec::new();
+        for d in &v.decls {
+            lines.extend(self.gen_declarator(d).map_err(|e| e.at(d.span.0))?);
+        }
+        Ok(lines)
+    }
+
+    /// 声明变量 `name` 用的关键字：`let` 或 `let mut`（见 `gen_var_decl`）。
+    fn let_keyword(&self, name: &str) -> &'static str {
+        if self.mutable.contains(name) {
+            "let mut"
+        } else {
+            "let"
+        }
+    }
+
+    /// 生成一个声明项，见 `gen_var_decl`。
+    fn gen_declarator(&mut self, d: &Declarator) -> Result<Vec<String>, Error> {
+        let init_expr = match (&d.init, &d.pattern) {
+            (Some(init), _) => init,
+            (None, Pattern::Ident(name)) => {
+                self.declare(name, Binding::Var { ty: d.ty.clone(), narrowed: false, float: false, cell: false });
+                let keyword = self.let_keyword(name);
+                return match &d.ty {
+                    Some(t) => Ok(vec![format!("{keyword} {name}: {};", self.rust_type(t))]),
+                    None => Ok(vec![format!("{keyword} {name};")]),
+                };
+            }
+            (None, _) => return Err(Error::new(ErrorCode::MissingInitializer, d.span.0)),
+        };
+        if d.ty.is_none() && is_null_literal(init_expr) {
+            return Err(Error::new(ErrorCode::NullRequiresTypeAnnotation, d.span.0));
+        }
+        // `Shared` 下没有标注的 string 变量也存成 `Rc<str>`：`let b = a;` 复制引用计数，不把 `a` 移走
+        let target = match &d.ty {
+            None if self.options.string_mode == StringMode::Shared => {
+                self.expr_type(init_expr).filter(|t| *t == TypeAnn::String)
+            }
+            ty => ty.clone(),
+        };
+        let init = self.gen_expr_as(init_expr, target.as_ref())?;
+        let ty = d.ty.clone().or_else(|| self.expr_type(init_expr));
+        let name = match &d.pattern {
+            Pattern::Ident(name) => name,
+            pattern => return Ok(self.gen_destructure(pattern, init_expr, init, ty)),
+        };
+        let float = d.ty.is_none() && self.is_float_expr(init_expr);
+        // gen_stmts 已经在本块登记过这个变量，决定了要不要放进 Cell/RefCell
+        let cell = matches!(self.scopes.last().and_then(|s| s.get(name)), Some(Binding::Var { cell: true, .. }));
+        self.declare(name, Binding::Var { ty, narrowed: false, float, cell });
+        if cell {
+            return Ok(vec![self.gen_cell_decl(name, d.ty.as_ref(), &init)]);
+        }
+        let keyword = self.let_keyword(name);
+        match &d.ty {
+            Some(t) => Ok(vec![format!("{keyword} {name}: {} = {init};", self.rust_type(t))]),
+            None => Ok(vec![format!("{keyword} {name} = {init};")]),
+        }
+    }
+
+    /// 声明 `Cell`/`RefCell` 变量（见 `cell_vars`）：number/boolean 用 `Cell`，其它类型用 `RefCell`。
+    ///
+    /// `let n = 0;` -> `let n = std::cell::Cell::new(0i32);`
+    fn gen_cell_decl(&self, name: &str, ann: Option<&TypeAnn>, init: &str) -> String {
+        let cell = match is_copy_type(self.var_type(name).as_ref()) {
+            true => "std::cell::Cell",
+            false => "std::cell::RefCell",
+        };
+        match ann {
+            Some(t) => format!("let {name}: {cell}<{}> = {cell}::new({init});", self.rust_type(t)),
+            None => format!("let {name} = {cell}::new({init});"),
+        }
+    }
+
+    /// 生成解构声明：每个变量一条 `let`，值来自对源值的下标/字段访问。
+    ///
+    /// 例：
+    /// - `const [a, b] = pair;` -> `let a = pair[0];` `let b = pair[1];`
+    /// - `const {x, y: py} = point;` -> `let x = point.x.clone();` `let py = point.y.clone();`
+    ///
+    /// 初始值不是变量时（例如函数调用），先存进临时变量，保证只求值一次：
+    /// `const [a, b] = f();` -> `let __destructure0 = f();` `let a = __destructure0[0];` ...
+    ///
+    /// 注意：和 TS 不同，数组越界时生成的 Rust 代码会 panic，而不是得到 undefined。
+    fn gen_destructure(
+        &mut self,
+        pattern: &Pattern,
+        init_expr: &Expr,
+        init: String,
+        ty: Option<TypeAnn>,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        let source = match init_expr {
+            Expr::Ident(name, _) if *name == init => init,
+            _ => {
+                let tmp = format!("__destructure{}", self.temps);
+                self.temps += 1;
+                lines.push(format!("let {tmp} = {init};"));
+                tmp
+            }
+        };
+        self.bind_pattern(&mut lines, pattern, &source, ty);
+        lines
+    }
+
+    /// 把 `source`（一个可以取下标/字段的 Rust 位置表达式）按模式逐个绑定到变量。
+    fn bind_pattern(
+        &mut self,
+        lines: &mut Vec<String>,
+        pattern: &Pattern,
+        source: &str,
+        ty: Option<TypeAnn>,
+    ) {
+        match pattern {
+            Pattern::Ident(name) => {
+                // 从下标/字段里取值不能把它移走，非 Copy 类型（或类型未知时）需要 clone。
+                let value = match ty {
+                    Some(TypeAnn::Number) | Some(TypeAnn::Boolean) => source.to_string(),
+                    _ => format!("{source}.clone()"),
+                };
+                lines.push(format!("{} {name} = {value};", self.let_keyword(name)));
+                self.declare(name, Binding::Var { ty, narrowed: false, float: false, cell: false });
+            }
+            Pattern::Array(items) => {
+                let elem = match ty {
+                    Some(TypeAnn::Array(elem)) => Some(*elem),
+                    _ => None,
+                };
+                for (i, item) in items.iter().enumerate() {
+                    self.bind_pattern(lines, item, &format!("{source}[{i}]"), elem.clone());
+                }
+            }
+            Pattern::Object(props) => {
+                // 还没有对象类型，字段类型未知。
+                for prop in props {
+                    let field = format!("{source}.{}", prop.key);
+                    self.bind_pattern(lines, &prop.value, &field, None);
+                }
+            }
+        }
+    }
+
+    fn gen_expr(&mut self, expr: &Expr) -> Result<String, Error> {
+        self.gen_expr_bp(expr, 0)
+    }
+
+    fn gen_assign(&mut self, a: &AssignStmt) -> Result<Vec<String>, Error> {
+        let target = AssignTarget::Ident(a.name.clone());
+        Ok(self.gen_assign_lines(&target, &a.value, false)?.0)
+    }
+
+    /// 把（可能是链式的）赋值拆成按顺序执行的 Rust 语句，返回这些语句和赋值目标的位置表达式。
+    ///
+    /// Rust 的赋值表达式的值是 `()`，不能直接写 `a = b = 0`，所以先执行最里面的赋值，
+    /// 外层再读取内层目标：`a = b = 0;` -> `b = 0i32;` `a = b;`。
+    ///
+    /// `read_back`：调用方之后还要读取这个目标。这时不是简单变量/字面量的下标会先存进临时变量，
+    /// 保证下标表达式只求值一次：`let __index0 = f() as usize;` `arr[__index0] = 1i32;`。
+    fn gen_assign_lines(
+        &mut self,
+        target: &AssignTarget,
+        value: &Expr,
+        read_back: bool,
+    ) -> Result<(Vec<String>, String), Error> {
+        let mut lines = Vec::new();
+        let place = match target {
+            AssignTarget::Index { object, index }
+                if read_back && !matches!(strip_group(index), Expr::Ident(_, _) | Expr::Literal(_, _)) =>
+            {
+                let tmp = format!("__index{}", self.temps);
+                self.temps += 1;
+                lines.push(format!("let {tmp} = {};", self.gen_usize_index(index)?));
+                format!("{}[{tmp}]", self.object_place(object))
+            }
+            _ => self.gen_assign_target(target)?,
+        };
+        let ty = self.target_type(target);
+        let value = match strip_group(value) {
+            Expr::Assign(inner) => {
+                let (inner_lines, inner_place) =
+                    self.gen_assign_lines(&inner.target, &inner.value, true)?;
+                lines.extend(inner_lines);
+                let inner_ty = self.assign_type(inner);
+                let read = read_place(inner_place, inner_ty.as_ref());
+                match (&ty, &inner_ty) {
+                    (Some(TypeAnn::Nullable(_, _)), Some(t)) if !matches!(t, TypeAnn::Nullable(_, _)) => {
+                        format!("Some({read})")
+                    }
+                    _ => read,
+                }
+            }
+            // 保存浮点数的变量（见 `Binding::Var::float`）被赋值为整数时转成 f64
+            value if self.is_float_target(target) && !self.is_float_expr(value) => self.gen_as_f64(value)?,
+            value => self.gen_expr_as(value, ty.as_ref())?,
+        };
+        match target {
+            AssignTarget::Member { object, property } if self.is_state_field(object, property) => {
+                lines.push(format!("self.{property}.set({value});"));
+            }
+            AssignTarget::Ident(name) if self.is_cell(name) => {
+                match self.var_type(name) {
+                    ty if is_copy_type(ty.as_ref()) => lines.push(format!("{name}.set({value});")),
+                    _ => lines.push(format!("*{name}.borrow_mut() = {value};")),
+                }
+                return Ok((lines, self.var_place(name)));
+            }
+            _ => lines.push(format!("{place} = {value};")),
+        }
+        Ok((lines, place))
+    }
+
+    fn is_float_target(&self, target: &AssignTarget) -> bool {
+        matches!(target, AssignTarget::Ident(name) if matches!(self.resolve(name), Some(Binding::Var { float: true, .. })))
+    }
+
+    /// 生成 if/while 的条件。
+    ///
+    /// `CodegenOptions::truthy_conditions` 时按 JS 的 truthy 规则把不是 bool 的值转成 bool：
+    /// - number -> `n != 0`（浮点数是 `n != 0.0`，NaN 会被当成真）
+    /// - string -> `!s.is_empty()`
+    /// - 可空类型 -> `x.is_some()`（里面的值不再检查：`Some(0)` 也是真）
+    /// - `null` / `undefined` -> `false`
+    ///
+    /// `!`、`&&`、`||` 的操作数同样转换（`!s` -> `s.is_empty()`）；其它类型（bool、不知道类型的表达式）原样生成。
+    fn gen_condition(&mut self, cond: &Expr) -> Result<String, Error> {
+        if !self.options.truthy_conditions {
+            return self.gen_expr(cond);
+        }
+        self.gen_truthy(cond, false, 0)
+    }
+
+    /// `gen_condition` 的递归部分：`negate` 为 true 时生成取反后的条件。
+    fn gen_truthy(&mut self, e: &Expr, negate: bool, parent_bp: u8) -> Result<String, Error> {
+        let (code, bp) = match strip_group(e) {
+            Expr::Unary(u) if u.op == UnaryOp::Not => return self.gen_truthy(&u.expr, !negate, parent_bp),
+            Expr::Binary(b) if matches!(b.op, BinaryOp::AndAnd | BinaryOp::OrOr) => {
+                let bp = binary_bp(b.op);
+                let op = if b.op == BinaryOp::AndAnd { "&&" } else { "||" };
+                let left = self.gen_truthy(&b.left, false, bp)?;
+                let right = self.gen_truthy(&b.right, false, bp + 1)?;
+                if negate {
+                    (format!("!({left} {op} {right})"), 80)
+                } else {
+                    (format!("{left} {op} {right}"), bp)
+                }
+            }
+            inner if is_null_literal(inner) => (negate.to_string(), 100),
+            inner => match self.expr_type(inner) {
+                Some(TypeAnn::Number) => {
+                    let zero = if self.f64_mode() || self.is_float_expr(inner) { "0.0" } else { "0" };
+                    let op = if negate { "==" } else { "!=" };
+                    (format!("{} {op} {zero}", self.gen_expr_bp(inner, 41)?), 40)
+                }
+                Some(TypeAnn::String) => {
+                    let not = if negate { "" } else { "!" };
+                    (format!("{not}{}.is_empty()", self.gen_expr_bp(inner, 90)?), 80)
+                }
+                Some(TypeAnn::Nullable(_, _)) => {
+                    let check = if negate { "is_none" } else { "is_some" };
+                    (format!("{}.{check}()", self.gen_expr_bp(inner, 90)?), 90)
+                }
+                _ if negate => (format!("!{}", self.gen_expr_bp(inner, 80)?), 80),
+                _ => return self.gen_expr_bp(e, parent_bp),
+            },
+        };
+        Ok(if bp < parent_bp { format!("({code})") } else { code })
+    }
+
+    /// 赋值用作表达式的值时（例如 `while ((x = next()) != 0)`），生成块表达式：
+    /// 先赋值，再把目标的值作为块的结果：`{ x = next(); x }`。
+    fn gen_assign_expr(&mut self, a: &AssignExpr) -> Result<String, Error> {
+        let (lines, place) = self.gen_assign_lines(&a.target, &a.value, true)?;
+        let ty = self.assign_type(a);
+        Ok(format!("{{ {} {} }}", lines.join(" "), read_place(place, ty.as_ref())))
+    }
+
+    /// 赋值目标对应的 Rust 位置表达式：`x`、`obj.x`、`arr[i as usize]`。
+    ///
+    /// 下标是非负整数字面量时直接写数字：`arr[0]`。
+    fn gen_assign_target(&mut self, target: &AssignTarget) -> Result<String, Error> {
+        match target {
+            AssignTarget::Ident(name) => Ok(name.clone()),
+            AssignTarget::Member { object, property } if object == "this" => Ok(self.field_place(property)),
+            AssignTarget::Member { object, property } => Ok(format!("{}.{property}", self.object_place(object))),
+            AssignTarget::Index { object, index } => {
+                Ok(format!("{}[{}]", self.object_place(object), self.gen_usize_index(index)?))
+            }
+        }
+    }
+
+    /// 修改字段/下标时对象的位置表达式：`self`、`obj`，`RefCell` 变量是 `obj.borrow_mut()`。
+    fn object_place(&self, object: &str) -> String {
+        match self.is_cell(object) {
+            true => format!("{object}.borrow_mut()"),
+            false => self_path(object).to_string(),
+        }
+    }
+
+    /// 下标表达式转成 Rust 的 `usize`：非负字面量直接写 `0`，其它写 `i as usize`。
+    ///
+    /// 负数转成 `usize` 会变成很大的数：数组下标会越界 panic，字符串下标取不到字符。
+    fn gen_usize_index(&mut self, index: &Expr) -> Result<String, Error> {
+        match strip_group(index) {
+            Expr::Literal(Literal::Number(n), _) if *n >= 0 => Ok(n.to_string()),
+            // f64 转 usize 时负数会变成 0，先转成 i64 保证负数下标仍然越界
+            _ if self.f64_mode() => Ok(format!("{} as i64 as usize", self.gen_expr_bp(index, 80)?)),
+            _ => Ok(format!("{} as usize", self.gen_expr_bp(index, 80)?)),
+        }
+    }
+
+    /// 生成下标读取 `object[index]`：
+    /// - 数组：`xs[i as usize]`，元素不是 Copy 类型时加 `.clone()`；越界会 panic（JS 里是 undefined）
+    /// - 字符串：和 `s.charAt(i)` 一样取第 i 个字符（按 `char` 计算，见 `gen_string_method`），
+    ///   越界时是空字符串（JS 里 `s[i]` 是 undefined，`s.charAt(i)` 是空字符串）
+    fn gen_index(&mut self, object: &Expr, index: &Expr) -> Result<String, Error> {
+        match self.expr_type(object) {
+            Some(TypeAnn::String) => self.gen_string_method(object, "charAt", std::slice::from_ref(index)),
+            Some(TypeAnn::Array(elem)) => {
+                let recv = self.gen_expr_bp(object, 90)?;
+                let index = self.gen_usize_index(index)?;
+                Ok(read_place(format!("{recv}[{index}]"), Some(&elem)))
+            }
+            _ => Err(Error::new(ErrorCode::UnsupportedIndexAccess, Span::default())),
+        }
+    }
+
+    fn gen_return(&mut self, r: &ReturnStmt) -> Result<Vec<String>, Error> {
+        match &r.value {
+            None => Ok(vec!["return;".to_string()]),
+            Some(v) => {
+                let value = self.gen_expr(v)?;
+                Ok(vec![format!("let _ = {value};"), "return;".to_string()])
+            }
+        }
+    }
+
+    fn gen_block_body(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmt: &Stmt,
+    ) -> Result<(), Error> {
+        match stmt {
+            Stmt::Block(b) => self.gen_stmts(out, indent, ctx, &b.stmts),
+            _ => self.gen_stmts(out, indent, ctx, std::slice::from_ref(stmt)),
+        }
+    }
+
+    /// 生成一条语句；里面还没有位置的错误报在这条语句上（见 `Error::at`）。
+    fn gen_stmt_into(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmt: &Stmt,
+    ) -> Result<(), Error> {
+        let span = stmt.span();
+        // 函数声明前面的注释由 `gen_fn_item` / `gen_closure` 输出
+        if !matches!(stmt, Stmt::FuncDecl(_)) {
+            self.gen_leading_comments(out, indent, span.start);
+            self.gen_provenance(out, indent, span);
+            self.gen_map_marker(out, indent, span);
+        }
+        let from = out.len();
+        self.gen_stmt_kind(out, indent, ctx, stmt).map_err(|e| e.at(span))?;
+        self.gen_trailing_comments(out, indent, from, span.end);
+        Ok(())
+    }
+
+    fn gen_stmt_kind(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmt: &Stmt,
+    ) -> Result<(), Error> {
+        match stmt {
+            Stmt::VarDecl(v) => {
+                for line in self.gen_var_decl(v)? {
+                    push_indent(out, indent);
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+                Ok(())
+            }
+            Stmt::Assign(a) => {
+                for line in self.gen_assign(a)? {
+                    push_indent(out, indent);
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+                Ok(())
+            }
+            // 语句位置的赋值不需要结果值，直接拆成赋值语句
+            Stmt::ExprStmt(Expr::Assign(a)) => {
+                for line in self.gen_assign_lines(&a.target, &a.value, false)?.0 {
+                    push_indent(out, indent);
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+                Ok(())
+            }
+            Stmt::ExprStmt(e) => {
+                push_indent(out, indent);
+                out.push_str(&format!("{};", self.gen_expr(e)?));
+                out.push('\n');
+                Ok(())
+            }
+            Stmt::Return(r) => {
+                for line in self.gen_return_ctx(ctx, r)? {
+                    push_indent(out, indent);
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+                Ok(())
+            }
+            Stmt::Block(b) => {
+                out.push_str(&self.gen_block_ctx(ctx, b, indent)?);
+                Ok(())
+            }
+            Stmt::If(i) => {
+                out.push_str(&self.gen_if_ctx(ctx, i, indent)?);
+                Ok(())
+            }
+            Stmt::While(w) => {
+                out.push_str(&self.gen_while_ctx(ctx, w, indent)?);
+                Ok(())
+            }
+            Stmt::FuncDecl(f) => {
+                // 闭包已经由 gen_stmts 提前输出到合适的位置了。
+                if !self.is_closure(&f.name) {
+                    out.push_str(&self.gen_func_decl(f, indent)?);
+                }
+                Ok(())
+            }
+            Stmt::Throw(t) => {
+                push_indent(out, indent);
+                out.push_str(&self.gen_throw(t)?);
+                out.push('\n');
+                Ok(())
+            }
+            Stmt::Try(t) => self.gen_try_ctx(out, ctx, t, indent),
+        }
+    }
+
+    /// 检查自定义异常类：必须（直接或间接）继承 `Error`，且不能重名。
+    fn check_error_classes(&self) -> Result<(), Error> {
+        for (i, (name, _)) in self.error_classes.iter().enumerate() {
+            let duplicated = self.error_classes[..i].iter().any(|(n, _)| n == name);
+            if name == "Error" || duplicated {
+                return Err(Error::new(ErrorCode::DuplicateClass, Span::default()));
+            }
+            // 沿父类链向上走；步数超过类的个数说明有环。
+            let mut cur = name.as_str();
+            let mut reaches_error = false;
+            for _ in 0..=self.error_classes.len() {
+                match self.error_parent(cur) {
+                    Some("Error") => {
+                        reaches_error = true;
+                        break;
+                    }
+                    Some(parent) => cur = parent,
+                    None => break,
+                }
+            }
+            if !reaches_error {
+                return Err(Error::new(ErrorCode::UnsupportedClass, Span::default()));
+            }
+        }
+        Ok(())
+    }
+
+    fn error_parent(&self, class: &str) -> Option<&str> {
+        self.error_classes
+            .iter()
+            .find(|(n, _)| n == class)
+            .map(|(_, parent)| parent.as_str())
+    }
+
+    /// `class` 是否是 `ancestor` 本身或它的子类。
+    fn is_error_subclass(&self, class: &str, ancestor: &str) -> bool {
+        let mut cur = class;
+        for _ in 0..=self.error_classes.len() {
+            if cur == ancestor {
+                return true;
+            }
+            match self.error_parent(cur) {
+                Some(parent) => cur = parent,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// `ArkError` 的所有变体名：内置的 `Error` 加上每个自定义异常类。
+    fn error_variants(&self) -> Vec<&str> {
+        let mut out = vec!["Error"];
+        out.extend(self.error_classes.iter().map(|(n, _)| n.as_str()));
+        out
+    }
+
+    /// `ArkError` 的定义：有自定义异常类时按类生成（见 `gen_error_enum`）；只有内置的 `Error` 时用运行时里的定义
+    /// （见 `runtime::ERROR_HELPER`），配置了 `CodegenOptions::runtime` 时从运行时模块 `use` 进来。
+    /// 库模式下它是导出函数签名的一部分，必须公开。
+    fn error_enum(&self) -> String {
+        if !self.error_classes.is_empty() {
+            return self.gen_error_enum();
+        }
+        let public = self.mode.is_library();
+        match &self.options.runtime {
+            Some(path) => format!("{}use {path}::ArkError;\n\n", if public { "pub " } else { "" }),
+            None => runtime::definition("ArkError", public),
+        }
+    }
+
+    /// 生成带自定义异常类的异常类型定义：每个异常类一个变体，携带异常消息。
+    ///
+    /// ```text
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum ArkError {
+    ///     Error(String),
+    ///     NotFound(String),
+    /// }
+    /// ```
+    ///
+    /// 同时实现 `Display` 和 `std::error::Error`，方便和其它 Rust 代码互操作。
+    fn gen_error_enum(&self) -> String {
+        let variants = self.error_variants();
+        let mut out = String::new();
+        out.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+        // 库模式下它是导出函数签名的一部分，必须是 pub
+        if self.mode.is_library() {
+            out.push_str("pub ");
+        }
+        out.push_str("enum ArkError {\n");
+        for v in &variants {
+            for line in self.class_comments.get(*v).into_iter().flatten() {
+                push_line(&mut out, 1, line);
+            }
+            out.push_str(&format!("    {v}(String),\n"));
+        }
+        out.push_str("}\n\n");
+        out.push_str("impl std::fmt::Display for ArkError {\n");
+        out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {\n");
+        out.push_str("        match self {\n");
+        for v in &variants {
+            out.push_str(&format!("            ArkError::{v}(m) => write!(f, \"{v}: {{m}}\"),\n"));
+        }
+        out.push_str("        }\n");
+        out.push_str("    }\n");
+        out.push_str("}\n\n");
+        out.push_str("impl std::error::Error for ArkError {}\n\n");
+        out
+    }
+
+    /// 生成 throw：`throw "boom";` -> `return Err(ArkError::Error(String::from("boom")));`
+    ///
+    /// - `throw new NotFound("x");` -> `ArkError::NotFound(String::from("x"))`
+    /// - 在 try 块内部时改为跳出标签块：`break 'try0 Err(ArkError::Error(...));`。
+    ///
+    /// 异常只携带字符串消息，非字符串的值会先格式化成字符串。
+    fn gen_throw(&mut self, t: &ThrowStmt) -> Result<String, Error> {
+        let err = self.gen_error_value(&t.value)?;
+        Ok(self.propagate(&err))
+    }
+
+    /// 把被抛出（或被 reject）的值生成为一个 `ArkError` 值：
+    /// - `new NotFound(msg)` -> `ArkError::NotFound(msg)`
+    /// - 其它值 -> `ArkError::Error(..)`，非字符串值用 `{:?}` 格式化成消息
+    fn gen_error_value(&mut self, value: &Expr) -> Result<String, Error> {
+        let (variant, arg) = match strip_group(value) {
+            Expr::New(n) => {
+                if !self.error_variants().contains(&n.class.as_str()) {
+                    return Err(Error::new(ErrorCode::UnknownErrorClass, Span::default()));
+                }
+                match n.args.as_slice() {
+                    [] => (n.class.clone(), None),
+                    [msg] => (n.class.clone(), Some(msg)),
+                    _ => return Err(Error::new(ErrorCode::TooManyConstructorArgs, Span::default())),
+                }
+            }
+            value => ("Error".to_string(), Some(value)),
+        };
+        let message = match arg {
+            None => "String::new()".to_string(),
+            Some(arg) => {
+                let value = self.gen_expr(arg)?;
+                match self.expr_type(arg) {
+                    Some(TypeAnn::String) => value,
+                    _ => format!("format!(\"{{:?}}\", {value})"),
+                }
+            }
+        };
+        Ok(format!("ArkError::{variant}({message})"))
+    }
+
+    /// catch 子句匹配 try 结果的模式：列出它能捕获的所有 `ArkError` 变体，并把消息绑定到参数。
+    ///
+    /// - `catch (e)` -> `Err(ArkError::Error(e) | ArkError::NotFound(e))`
+    /// - `catch (e: NotFound)` -> `Err(ArkError::NotFound(e))`（包括 NotFound 的子类）
+    /// - `catch { }` -> `Err(_)`
+    fn catch_pattern(&self, c: &CatchClause) -> Result<String, Error> {
+        let Some(name) = &c.param else {
+            return Ok("Err(_)".to_string());
+        };
+        let ty = c.ty.as_deref().unwrap_or("Error");
+        if !self.error_variants().contains(&ty) {
+            return Err(Error::new(ErrorCode::UnknownErrorClass, c.span.0));
+        }
+        let arms: Vec<String> = self
+            .error_variants()
+            .into_iter()
+            .filter(|v| self.is_error_subclass(v, ty))
+            .map(|v| format!("ArkError::{v}({name})"))
+            .collect();
+        Ok(format!("Err({})", arms.join(" | ")))
+    }
+
+    /// 把一个 `ArkError` 值交给最近的异常处理者。
+    fn propagate(&self, err: &str) -> String {
+        match self.try_labels.last() {
+            Some(label) => format!("break '{label} Err({err});"),
+            None => format!("return Err({err});"),
+        }
+    }
+
+    /// 生成 try/catch/finally。
+    ///
+    /// try 块生成为一个带标签的块表达式，结果是 `Result<(), ArkError>`：
+    ///
+    /// ```text
+    /// let __try0: Result<(), ArkError> = 'try0: {
+    ///     ...            // throw -> break 'try0 Err(..);
+    ///     Ok(())
+    /// };
+    /// if let Err(ArkError(e)) = __try0 {
+    ///     ...            // catch 块，e 是异常消息（String）
+    /// }
+    /// ```
+    ///
+    /// 没有 catch，或 catch 块自己还会抛出时，再把异常继续向外传播。
+    /// 有 finally 时改用 `gen_try_finally`。
+    fn gen_try_ctx(
+        &mut self,
+        out: &mut String,
+        ctx: &ReturnCtx,
+        t: &TryStmt,
+        indent: usize,
+    ) -> Result<(), Error> {
+        let n = self.temps;
+        self.temps += 1;
+        if let Some(finally) = &t.finally {
+            return self.gen_try_finally(out, ctx, t, finally, indent, n);
+        }
+        let result = format!("__try{n}");
+
+        push_indent(out, indent);
+        out.push_str(&format!("let {result}: Result<(), ArkError> = 'try{n}: {{\n"));
+        self.gen_labeled_stmts(out, indent + 1, ctx, &t.block.stmts, format!("try{n}"))?;
+        push_indent(out, indent + 1);
+        out.push_str("Ok(())\n");
+        push_indent(out, indent);
+        out.push_str("};\n");
+
+        let mut rethrows = true;
+        if let Some(c) = &t.catch {
+            let pattern = self.catch_pattern(c)?;
+            // catch 只接住部分异常类，或自己还会抛出：结果仍可能是 Err，需要继续传播。
+            if !catches_all(c) || stmts_throw(&c.body.stmts, &self.throwing) {
+                push_indent(out, indent);
+                out.push_str(&format!("let {result} = match {result} {{\n"));
+                push_indent(out, indent + 1);
+                out.push_str(&format!("{pattern} => 'catch{n}: {{\n"));
+                self.try_labels.push(format!("catch{n}"));
+                let body = self.gen_catch_body(out, indent + 2, ctx, c);
+                self.try_labels.pop();
+                body?;
+                push_indent(out, indent + 2);
+                out.push_str("Ok(())\n");
+                push_indent(out, indent + 1);
+                out.push_str("}\n");
+                push_indent(out, indent + 1);
+                out.push_str("other => other,\n");
+                push_indent(out, indent);
+                out.push_str("};\n");
+            } else {
+                rethrows = false;
+                push_indent(out, indent);
+                out.push_str(&format!("if let {pattern} = {result} {{\n"));
+                self.gen_catch_body(out, indent + 1, ctx, c)?;
+                push_indent(out, indent);
+                out.push_str("}\n");
+            }
+        }
+
+        if rethrows {
+            self.gen_rethrow(out, indent, &result);
+        }
+        Ok(())
+    }
+
+    /// 生成带 finally 的 try。
+    ///
+    /// finally 必须在 try/catch 以任何方式结束之后执行（正常结束、抛出异常、return），
+    /// 所以把 try 和 catch 一起放进外层标签块 `'tryN`，离开它之后再执行 finally：
+    ///
+    /// ```text
+    /// let mut __ret0 = None;                  // 仅当 try/catch 里有 return
+    /// let __try0: Result<(), ArkError> = 'try0: {
+    ///     let __body0: Result<(), ArkError> = 'body0: {
+    ///         ...                             // return x -> __ret0 = Some(x); break 'try0 Ok(());
+    ///         Ok(())
+    ///     };
+    ///     if let Err(ArkError(e)) = __body0 {
+    ///         ...                             // catch 块
+    ///     }
+    ///     Ok(())
+    /// };
+    /// { ... }                                 // finally 块
+    /// if let Some(v) = __ret0 { return v; }   // 补上被推迟的 return
+    /// ```
+    fn gen_try_finally(
+        &mut self,
+        out: &mut String,
+        ctx: &ReturnCtx,
+        t: &TryStmt,
+        finally: &BlockStmt,
+        indent: usize,
+        n: usize,
+    ) -> Result<(), Error> {
+        let result = format!("__try{n}");
+        let slot = format!("__ret{n}");
+        let returns = try_blocks(t)
+            .iter()
+            .take(if t.catch.is_some() { 2 } else { 1 })
+            .any(|b| contains_return(&b.stmts));
+        if returns {
+            push_indent(out, indent);
+            out.push_str(&format!("let mut {slot} = None;\n"));
+        }
+
+        push_indent(out, indent);
+        out.push_str(&format!("let {result}: Result<(), ArkError> = 'try{n}: {{\n"));
+        self.finally_slots.push((slot.clone(), format!("try{n}")));
+        let body = match &t.catch {
+            Some(c) => self.gen_try_catch_in_finally(out, ctx, t, c, indent + 1, n),
+            None => self.gen_labeled_stmts(out, indent + 1, ctx, &t.block.stmts, format!("try{n}")),
+        };
+        self.finally_slots.pop();
+        body?;
+        push_indent(out, indent + 1);
+        out.push_str("Ok(())\n");
+        push_indent(out, indent);
+        out.push_str("};\n");
+
+        out.push_str(&self.gen_block_ctx(ctx, finally, indent)?);
+
+        if returns {
+            let bare = !self.fn_throws && matches!(ctx, ReturnCtx::Main | ReturnCtx::Function(TypeAnn::Void));
+            let (pattern, ret) = if bare {
+                ("Some(())", "return;")
+            } else {
+                ("Some(v)", "return v;")
+            };
+            push_indent(out, indent);
+            out.push_str(&format!("if let {pattern} = {slot} {{\n"));
+            for line in self.finish_return(vec![ret.to_string()]) {
+                push_indent(out, indent + 1);
+                out.push_str(&line);
+                out.push('\n');
+            }
+            push_indent(out, indent);
+            out.push_str("}\n");
+        }
+
+        let rethrows = match &t.catch {
+            Some(c) => !catches_all(c) || stmts_throw(&c.body.stmts, &self.throwing),
+            None => true,
+        };
+        if rethrows {
+            self.gen_rethrow(out, indent, &result);
+        }
+        Ok(())
+    }
+
+    /// `gen_try_finally` 里 try + catch 的部分：try 块的异常交给 `'bodyN`，
+    /// catch 块的异常直接离开外层的 `'tryN`。
+    fn gen_try_catch_in_finally(
+        &mut self,
+        out: &mut String,
+        ctx: &ReturnCtx,
+        t: &TryStmt,
+        c: &CatchClause,
+        indent: usize,
+        n: usize,
+    ) -> Result<(), Error> {
+        let body = format!("__body{n}");
+        push_indent(out, indent);
+        out.push_str(&format!("let {body}: Result<(), ArkError> = 'body{n}: {{\n"));
+        self.gen_labeled_stmts(out, indent + 1, ctx, &t.block.stmts, format!("body{n}"))?;
+        push_indent(out, indent + 1);
+        out.push_str("Ok(())\n");
+        push_indent(out, indent);
+        out.push_str("};\n");
+
+        let pattern = self.catch_pattern(c)?;
+        self.try_labels.push(format!("try{n}"));
+        let result = if catches_all(c) {
+            push_indent(out, indent);
+            out.push_str(&format!("if let {pattern} = {body} {{\n"));
+            self.gen_catch_body(out, indent + 1, ctx, c)
+        } else {
+            // 没被 catch 接住的异常直接离开外层标签块，执行完 finally 后再向外传播。
+            push_indent(out, indent);
+            out.push_str(&format!("match {body} {{\n"));
+            push_indent(out, indent + 1);
+            out.push_str(&format!("{pattern} => {{\n"));
+            let r = self.gen_catch_body(out, indent + 2, ctx, c);
+            push_indent(out, indent + 1);
+            out.push_str("}\n");
+            push_indent(out, indent + 1);
+            out.push_str(&format!("Err(e) => break 'try{n} Err(e),\n"));
+            push_indent(out, indent + 1);
+            out.push_str("Ok(()) => {}\n");
+            r
+        };
+        self.try_labels.pop();
+        result?;
+        push_indent(out, indent);
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    /// 在 try 标签块里生成语句：其中的异常都跳到 `label`。
+    fn gen_labeled_stmts(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        stmts: &[Stmt],
+        label: String,
+    ) -> Result<(), Error> {
+        self.try_labels.push(label);
+        let result = self.gen_stmts(out, indent, ctx, stmts);
+        self.try_labels.pop();
+        result
+    }
+
+    /// try 结束后，把仍未处理的异常继续向外传播。
+    fn gen_rethrow(&self, out: &mut String, indent: usize, result: &str) {
+        push_indent(out, indent);
+        out.push_str(&format!("if let Err(e) = {result} {{\n"));
+        push_indent(out, indent + 1);
+        out.push_str(&self.propagate("e"));
+        out.push('\n');
+        push_indent(out, indent);
+        out.push_str("}\n");
+    }
+
+    /// 生成 catch 块：catch 参数单独占一层作用域，类型是异常消息 string。
+    fn gen_catch_body(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        ctx: &ReturnCtx,
+        c: &CatchClause,
+    ) -> Result<(), Error> {
+        let mut scope = HashMap::new();
+        if let Some(name) = &c.param {
+            let binding = Binding::Var {
+                ty: Some(TypeAnn::String),
+                narrowed: false,
+                float: false,
+                cell: false,
+            };
+            scope.insert(name.clone(), binding);
+        }
+        self.scopes.push(scope);
+        let result = self.gen_stmts(out, indent, ctx, &c.body.stmts);
+        self.scopes.pop();
+        result
+    }
+
+    /// 生成 return。当前函数返回 `Result` 时，返回值再包一层 `Ok(...)`。
+    fn gen_return_ctx(&mut self, ctx: &ReturnCtx, r: &ReturnStmt) -> Result<Vec<String>, Error> {
+        let lines = match ctx {
+            ReturnCtx::Main => self.gen_return(r),
+            ReturnCtx::Function(ret) => match ret {
+                TypeAnn::Void => match &r.value {
+                    None => Ok(vec!["return;".to_string()]),
+                    Some(v) => {
+                        let value = self.gen_expr(v)?;
+                        Ok(vec![format!("let _ = {value};"), "return;".to_string()])
+                    }
+                },
+                // 返回回调时，返回类型是 `Box<dyn Fn(...)>`，需要把值装箱。
+                TypeAnn::Function(_) => match &r.value {
+                    Some(v) => Ok(vec![format!("return Box::new({});", self.gen_expr(v)?)]),
+                    None => Err(Error::new(ErrorCode::ReturnValueRequired, Span::default())),
+                },
+                // 可空返回类型：`return;` 等价于 `return undefined;`
+                TypeAnn::Nullable(_, _) => match &r.value {
+                    Some(v) => Ok(vec![format!("return {};", self.gen_expr_as(v, Some(ret))?)]),
+                    None => Ok(vec!["return None;".to_string()]),
+                },
+                _ => match &r.value {
+                    Some(v) => Ok(vec![format!("return {};", self.gen_expr_as(v, Some(ret))?)]),
+                    None => Err(Error::new(ErrorCode::ReturnValueRequired, Span::default())),
+                },
+            },
+        }?;
+        let lines = if self.fn_throws {
+            lines
+                .into_iter()
+                .map(|line| match line.strip_prefix("return") {
+                    Some(";") => "return Ok(());".to_string(),
+                    Some(rest) => format!("return Ok({});", rest.trim().trim_end_matches(';')),
+                    None => line,
+                })
+                .collect()
+        } else {
+            lines
+        };
+        Ok(self.finish_return(lines))
+    }
+
+    /// 在 finally 保护区里时，把最后的 `return x;` 改成“暂存返回值 + 跳出标签块”。
+    fn finish_return(&self, mut lines: Vec<String>) -> Vec<String> {
+        let Some((slot, label)) = self.finally_slots.last() else {
+            return lines;
+        };
+        let value = match lines.pop().as_deref().and_then(|l| l.strip_prefix("return")) {
+            Some(";") | None => "()".to_string(),
+            Some(rest) => rest.trim().trim_end_matches(';').to_string(),
+        };
+        lines.push(format!("{slot} = Some({value});"));
+        lines.push(format!("break '{label} Ok(());"));
+        lines
+    }
+
+    fn gen_block_ctx(&mut self, ctx: &ReturnCtx, b: &BlockStmt, indent: usize) -> Result<String, Error> {
+        let mut out = String::new();
+        push_indent(&mut out, indent);
+        out.push_str("{\n");
+        self.gen_stmts(&mut out, indent + 1, ctx, &b.stmts)?;
+        push_indent(&mut out, indent);
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    fn gen_if_ctx(&mut self, ctx: &ReturnCtx, stmt: &IfStmt, indent: usize) -> Result<String, Error> {
+        let cond = self.gen_condition(&stmt.cond)?;
+        let check = self.null_check(&stmt.cond);
+        let then_narrowed = check.as_ref().filter(|c| c.1).map(|c| c.0.as_str());
+        let else_narrowed = check.as_ref().filter(|c| !c.1).map(|c| c.0.as_str());
+
+        let mut out = String::new();
+        push_indent(&mut out, indent);
+        out.push_str("if ");
+        out.push_str(&cond);
+        out.push_str(" {\n");
+        self.gen_narrowed_body(&mut out, indent + 1, ctx, &stmt.then_branch, then_narrowed)?;
+        push_indent(&mut out, indent);
+        out.push('}');
+
+        if let Some(else_branch) = &stmt.else_branch {
+            out.push_str(" else {\n");
+            self.gen_narrowed_body(&mut out, indent + 1, ctx, else_branch, else_narrowed)?;
+            push_indent(&mut out, indent);
+            out.push_str("}\n");
+        } else {
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    fn gen_while_ctx(&mut self, ctx: &ReturnCtx, stmt: &WhileStmt, indent: usize) -> Result<String, Error> {
+        let cond = self.gen_condition(&stmt.cond)?;
+        let check = self.null_check(&stmt.cond);
+        let narrowed = check.as_ref().filter(|c| c.1).map(|c| c.0.as_str());
+
+        let mut out = String::new();
+        push_indent(&mut out, indent);
+        out.push_str("while ");
+        out.push_str(&cond);
+        out.push_str(" {\n");
+        self.gen_narrowed_body(&mut out, indent + 1, ctx, &stmt.body, narrowed)?;
+        push_indent(&mut out, indent);
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    fn gen_func_decl(&mut self, f: &FuncDecl, indent: usize) -> Result<String, Error> {
+        self.gen_fn_item(f, indent, None)
+    }
+
+    /// 生成函数项；`receiver` 是方法的 `self` 参数（`&mut self`），写在参数列表最前面。
+    fn gen_fn_item(&mut self, f: &FuncDecl, indent: usize, receiver: Option<&str>) -> Result<String, Error> {
+        let ret = effective_ret_type(f);
+        let mutable = mutable_params(f);
+        let mut params: Vec<String> = receiver.iter().map(|r| r.to_string()).collect();
+        for p in &f.params {
+            params.push(mut_prefix(&mutable, &p.name) + &self.gen_param(p));
+        }
+
+        let mut out = String::new();
+        self.gen_item_comments(&mut out, indent, f.span.0.start);
+        self.gen_provenance(&mut out, indent, f.span.0);
+        self.gen_map_marker(&mut out, indent, f.span.0);
+        push_indent(&mut out, indent);
+        out.push_str(self.item_vis(f.is_export));
+        out.push_str("fn ");
+        out.push_str(&f.name);
+        out.push('(');
+        out.push_str(&params.join(", "));
+        out.push(')');
+        if let Some(sig) = self.ret_sig(f, &ret) {
+            out.push_str(" -> ");
+            out.push_str(&sig);
+        }
+        out.push_str(" {\n");
+        self.gen_func_body(&mut out, indent + 1, f, ret)?;
+        push_indent(&mut out, indent);
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// 为 `declare function` 生成包装函数：配置了绑定时把参数原样转发给绑定的 Rust 路径，
+    /// 否则生成 `unimplemented!()` 桩函数（参数名加 `_` 前缀，避免 unused 警告）。
+    fn gen_extern_func(&mut self, f: &FuncDecl) -> String {
+        let ret = effective_ret_type(f);
+        let path = self.externs.get(&f.name).cloned();
+        let params: Vec<String> = f
+            .params
+            .iter()
+            .map(|p| match &path {
+                Some(_) => self.gen_param(p),
+                None => format!("_{}", self.gen_param(p)),
+            })
+            .collect();
+
+        let mut out = String::new();
+        self.gen_map_marker(&mut out, 0, f.span.0);
+        out.push_str(self.item_vis(f.is_export));
+        out.push_str(&format!("fn {}({})", f.name, params.join(", ")));
+        if ret != TypeAnn::Void {
+            out.push_str(&format!(" -> {}", self.rust_type(&ret)));
+        }
+        out.push_str(" {\n");
+        match path {
+            Some(path) => {
+                let args: Vec<&str> = f.params.iter().map(|p| p.name.as_str()).collect();
+                out.push_str(&format!("    {path}({})\n", args.join(", ")));
+            }
+            None => out.push_str(&format!("    unimplemented!(\"extern function `{}`\")\n", f.name)),
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// 原生模块（`OutputMode::NativeModule`）给导出函数生成的 napi 包装，`exports` 是 `(导出名, 函数)`：
+    ///
+    /// ```text
+    /// use napi_derive_ohos::napi;
+    ///
+    /// #[napi(js_name = "add")]
+    /// pub fn napi_add(a: i32, b: i32) -> i32 {
+    ///     add(a, b)
+    /// }
+    /// ```
+    ///
+    /// - JS 那边看到的名字保持 ArkTS 里的导出名（`js_name`），不按 napi 的默认规则改成驼峰
+    /// - 会抛异常的函数返回 `napi_ohos::Result<T>`，`ArkError` 转成 JS 的 Error（消息是它的 Display）
+    /// - 参数和返回值只能是 number、string、boolean 以及它们的数组和可空类型，其它类型
+    ///   （函数、Promise、Date）报 `UnsupportedNativeType`；剩余参数报 `UnsupportedNativeSignature`
+    fn gen_napi_exports(&self, exports: &[(&str, &FuncDecl)]) -> Result<String, Error> {
+        if exports.is_empty() {
+            return Ok(String::new());
+        }
+        let mut out = String::from("use napi_derive_ohos::napi;\n\n");
+        for (name, f) in exports {
+            let ret = effective_ret_type(f);
+            if !is_napi_type(&ret, true) {
+                return Err(Error::new(ErrorCode::UnsupportedNativeType, f.span.0));
+            }
+            let mut params = Vec::new();
+            for p in &f.params {
+                if p.is_rest {
+                    return Err(Error::new(ErrorCode::UnsupportedNativeSignature, f.span.0));
+                }
+                let ty = param_type(p);
+                if !is_napi_type(&ty, false) {
+                    return Err(Error::new(ErrorCode::UnsupportedNativeType, f.span.0));
+                }
+                params.push(format!("{}: {}", p.name, self.rust_type(&ty)));
+            }
+            let args: Vec<&str> = f.params.iter().map(|p| p.name.as_str()).collect();
+            let call = format!("{name}({})", args.join(", "));
+            // 改成 snake_case 的函数，JS 那边还是用原来的名字
+            let js_name = self.names.original(name).unwrap_or(name);
+            out.push_str(&format!("#[napi(js_name = \"{js_name}\")]\n"));
+            // `r#impl` 这样的原始标识符不能拼进别的名字
+            out.push_str(&format!("pub fn napi_{}({})", name.trim_start_matches("r#"), params.join(", ")));
+            if self.throwing.contains(&f.name) {
+                out.push_str(&format!(" -> napi_ohos::Result<{}> {{\n", self.rust_type(&ret)));
+                out.push_str(&format!(
+                    "    {call}.map_err(|e| napi_ohos::Error::from_reason(e.to_string()))\n"
+                ));
+            } else {
+                if ret != TypeAnn::Void {
+                    out.push_str(&format!(" -> {}", self.rust_type(&ret)));
+                }
+                out.push_str(&format!(" {{\n    {call}\n"));
+            }
+            out.push_str("}\n\n");
+        }
+        Ok(out)
+    }
+
+    /// 把捕获外层变量的块内函数生成为闭包：
+    ///
+    /// ```text
+    /// let add = |a: i32| -> i32 {
+    ///     return a + base;
+    /// };
+    /// ```
+    ///
+    /// 如果函数体给捕获的变量赋值，闭包是 `FnMut`，需要 `let mut`。
+    ///
+    /// `escapes`：闭包会被 `return` 出去（工厂函数返回闭包）。这时闭包活得比捕获的局部变量久，
+    /// 必须写成 `move` 闭包，把捕获的值搬进闭包里。
+    fn gen_closure(&mut self, f: &FuncDecl, indent: usize, escapes: bool) -> Result<String, Error> {
+        let ret = effective_ret_type(f);
+        let assigned = assigned_names(&f.body.stmts);
+        let mutable = mutable_params(f);
+        let params: Vec<String> = f
+            .params
+            .iter()
+            .map(|p| {
+                let prefix = mut_prefix(&mutable, &p.name);
+                format!("{prefix}{}: {}", p.name, self.rust_type(&param_type(p)))
+            })
+            .collect();
+        let locals = declared_names(f);
+        let mutates = assigned.iter().any(|name| !locals.contains(name) && !self.is_cell(name));
+
+        let mut out = String::new();
+        // 闭包不能带文档注释
+        self.gen_leading_comments(&mut out, indent, f.span.0.start);
+        self.gen_provenance(&mut out, indent, f.span.0);
+        self.gen_map_marker(&mut out, indent, f.span.0);
+        push_indent(&mut out, indent);
+        out.push_str(if mutates { "let mut " } else { "let " });
+        out.push_str(&f.name);
+        out.push_str(if escapes { " = move |" } else { " = |" });
+        out.push_str(&params.join(", "));
+        out.push('|');
+        if let Some(sig) = self.ret_sig(f, &ret) {
+            out.push_str(" -> ");
+            out.push_str(&sig);
+        }
+        out.push_str(" {\n");
+        self.gen_func_body(&mut out, indent + 1, f, ret)?;
+        push_indent(&mut out, indent);
+        out.push_str("};\n");
+        Ok(out)
+    }
+
+    /// 函数签名里 `->` 后面的返回类型；void 且不会抛出异常时返回 None（不写返回类型）。
+    fn ret_sig(&self, f: &FuncDecl, ret: &TypeAnn) -> Option<String> {
+        if self.throwing.contains(&f.name) {
+            Some(format!("Result<{}, ArkError>", self.rust_type(ret)))
+        } else if *ret != TypeAnn::Void {
+            Some(self.rust_type(ret))
+        } else {
+            None
+        }
+    }
+
+    /// 生成函数体：参数单独占一层作用域，函数体语句在其内层。
+    fn gen_func_body(
+        &mut self,
+        out: &mut String,
+        indent: usize,
+        f: &FuncDecl,
+        ret: TypeAnn,
+    ) -> Result<(), Error> {
+        let params = f
+            .params
+            .iter()
+            .map(|p| {
+                let ty = Some(param_type(p));
+                (p.name.clone(), Binding::Var { ty, narrowed: false, float: false, cell: false })
+            })
+            .collect();
+        self.scopes.push(params);
+        // 被块内函数捕获又被赋值的参数：先放进 Cell/RefCell（见 `cell_vars`）
+        let cells = cell_vars(&f.body.stmts);
+        for p in f.params.iter().filter(|p| cells.contains(&p.name)) {
+            if let Some(Binding::Var { cell, .. }) = self.scopes.last_mut().and_then(|s| s.get_mut(&p.name)) {
+                *cell = true;
+            }
+            push_indent(out, indent);
+            out.push_str(&self.gen_cell_decl(&p.name, None, &p.name));
+            out.push('\n');
+        }
+        // 函数有自己的异常出口：外层的 try 标签在函数体里不可见。
+        let throws = self.throwing.contains(&f.name);
+        let outer_throws = std::mem::replace(&mut self.fn_throws, throws);
+        let outer_labels = std::mem::take(&mut self.try_labels);
+        let outer_slots = std::mem::take(&mut self.finally_slots);
+        let outer_mutable = std::mem::replace(&mut self.mutable, mutable_names(&f.body.stmts));
+        let is_void = ret == TypeAnn::Void;
+        let result = self.gen_stmts(out, indent, &ReturnCtx::Function(ret), &f.body.stmts);
+        if throws && is_void {
+            push_indent(out, indent);
+            out.push_str("Ok(())\n");
+        } else if !is_void && matches!(f.body.stmts.last(), Some(Stmt::Try(_))) {
+            // 每条路径都在 try/catch 里 return 了，但 rustc 看不出来，需要显式标出。
+            push_indent(out, indent);
+            out.push_str("unreachable!()\n");
+        }
+        self.fn_throws = outer_throws;
+        self.try_labels = outer_labels;
+        self.finally_slots = outer_slots;
+        self.mutable = outer_mutable;
+        self.scopes.pop();
+        result.map_err(|e| e.at(f.span.0))
+    }
+
+    /// 调用系统模块 API 的实参：签名在 Rust 那边，这里只逐个生成表达式（不支持展开）。
+    fn gen_system_args(&mut self, args: &[Expr]) -> Result<String, Error> {
+        Ok(self.gen_plain_args(args)?.join(", "))
+    }
+
+    /// 逐个生成实参表达式，不支持展开实参（报 `UnsupportedSpreadArg`）。
+    fn gen_plain_args(&mut self, args: &[Expr]) -> Result<Vec<String>, Error> {
+        let mut out = Vec::new();
+        for a in args {
+            if matches!(a, Expr::Spread(_)) {
+                return Err(Error::new(ErrorCode::UnsupportedSpreadArg, Span::default()));
+            }
+            out.push(self.gen_expr(a)?);
+        }
+        Ok(out)
+    }
+
+    /// `CodegenOptions::intrinsics` 里 `call` 的被调用者对应的模板。
+    ///
+    /// 被调用者的名字：`console.log`、`Promise.all`、没有被声明遮蔽的 `f` 和 `obj.f`（`obj` 没有被声明遮蔽）；
+    /// 在任意表达式上调用的方法不查表。
+    fn intrinsic(&self, call: &CallExpr) -> Option<String> {
+        if self.options.intrinsics.is_empty() {
+            return None;
+        }
+        let name = match &call.callee {
+            Callee::Console(method) => format!("console.{}", method.name()),
+            Callee::Promise(method) => format!("Promise.{}", method.name()),
+            Callee::Ident(name) if self.resolve(name).is_none() => name.clone(),
+            Callee::Member { object, property } if self.resolve(object).is_none() => member_key(object, property),
+            _ => return None,
+        };
+        self.options.intrinsics.get(&name).cloned()
+    }
+
+    /// 按 `Intrinsics` 的模板生成调用：`$0`、`$1` ... 换成对应的实参，`$args` 换成全部实参。
+    /// 模板用到的实参比传入的多时报 `IntrinsicArgCount`。
+    fn gen_intrinsic(&mut self, template: &str, args: &[Expr]) -> Result<String, Error> {
+        let args = self.gen_plain_args(args)?;
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix("args") {
+                out.push_str(&args.join(", "));
+                rest = after;
+                continue;
+            }
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                out.push('$');
+                continue;
+            }
+            let arg = rest[..digits]
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| args.get(n))
+                .ok_or_else(|| Error::new(ErrorCode::IntrinsicArgCount, Span::default()))?;
+            out.push_str(arg);
+            rest = &rest[digits..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// 生成函数调用表达式。
+    ///
+    /// 映射规则：
+    /// - `console.log(e)` -> `println!("{}", e)`（每个实参的格式见 `gen_console_arg`）
+    /// - `console.log(a, b)` -> `println!("{} {}", a, b)`（和 JS 一样用空格连接各个参数）
+    /// - `console.log()` -> `println!()`（输出空行）
+    /// - `console.info/debug(..)` 同 `console.log`；`console.error/warn(..)` 改用 `eprintln!` 写到标准错误
+    /// - `f(a, b)` -> `f(a, b)`
+    /// - `f(a)(b)` -> `f(a)(b)`：`f` 返回 `Box<dyn Fn(..)>`，可以直接调用
+    ///
+    /// 目前约束：console 方法不支持展开实参，否则报 `UnsupportedConsoleArgs`。
+    ///
+    /// `CodegenOptions::intrinsics` 里配置了的被调用者按模板生成，见 `gen_intrinsic`。
+    ///
+    /// 没有被用户同名声明遮蔽的 `setTimeout`/`setInterval` 按内置定时器处理，见 `gen_timer_call`。
+    fn gen_call(&mut self, call: &CallExpr) -> Result<String, Error> {
+        if let Some(template) = self.intrinsic(call) {
+            return self.gen_intrinsic(&template, &call.args);
+        }
+        match call.callee {
+            Callee::Console(method) => {
+                let mac = match method {
+                    ConsoleFn::Log | ConsoleFn::Info | ConsoleFn::Debug => "println!",
+                    ConsoleFn::Error | ConsoleFn::Warn => "eprintln!",
+                    ConsoleFn::Assert => return self.gen_assert(&call.args),
+                };
+                if call.args.is_empty() {
+                    return Ok(format!("{mac}()"));
+                }
+                let mut placeholders = Vec::new();
+                let mut args = Vec::new();
+                for a in &call.args {
+                    if matches!(a, Expr::Spread(_)) {
+                        return Err(Error::new(ErrorCode::UnsupportedConsoleArgs, Span::default()));
+                    }
+                    let (placeholder, arg) = self.gen_console_arg(a)?;
+                    placeholders.push(placeholder);
+                    args.push(arg);
+                }
+                let placeholders = placeholders.join(" ");
+                Ok(format!("{mac}(\"{placeholders}\", {})", args.join(", ")))
+            }
+            Callee::Ident(ref name) if is_timer_builtin(name) && self.resolve(name).is_none() => {
+                self.gen_timer_call(name, &call.args)
+            }
+            Callee::Ident(ref name) if is_conversion_builtin(name) && self.resolve(name).is_none() => {
+                self.gen_conversion(name, &call.args)
+            }
+            Callee::Ident(ref name) if is_resource_builtin(name) && self.resolve(name).is_none() => {
+                self.gen_resource_ref(name, &call.args)
+            }
+            // `assert(cond, msg)`：只接受一条消息，其余和 `console.assert` 相同
+            Callee::Ident(ref name) if name == "assert" && self.resolve(name).is_none() => {
+                if call.args.is_empty() || call.args.len() > 2 {
+                    return Err(Error::new(ErrorCode::AssertArgCount, Span::default()));
+                }
+                self.gen_assert(&call.args)
+            }
+            Callee::Ident(ref name) if self.system_names.contains(name) && self.resolve(name).is_none() => {
+                Ok(format!("{name}({})", self.gen_system_args(&call.args)?))
+            }
+            Callee::Ident(ref name) => self.gen_named_call(name, name, &call.args),
+            Callee::Promise(method) => self.gen_promise_call(method, &call.args),
+            Callee::Member {
+                ref object,
+                ref property,
+            } => {
+                if self.is_math_call(call) {
+                    return Ok(self.gen_math_call(property, &call.args, 0)?.0);
+                }
+                if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "JSON") {
+                    return self.gen_json_call(property, &call.args);
+                }
+                if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "hilog") {
+                    return self.gen_hilog_call(property, &call.args);
+                }
+                if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "Date") {
+                    return self.gen_date_call(property, &call.args);
+                }
+                if self.system_names.contains(object) && self.resolve(object).is_none() {
+                    return Ok(format!("{object}::{property}({})", self.gen_system_args(&call.args)?));
+                }
+                let key = member_key(object, property);
+                if self.ability_params.contains(object)
+                    && matches!(self.resolve(object), Some(Binding::Var { ty: None, .. }))
+                {
+                    let mut args = Vec::new();
+                    for a in &call.args {
+                        args.push(self.gen_expr(a)?);
+                    }
+                    return Ok(format!("{object}.{property}({})", args.join(", ")));
+                }
+                if self.resolve(&key).is_none() {
+                    // 不是命名空间成员、而是变量：`s.trim()` 这样的方法调用
+                    if matches!(self.resolve(object), Some(Binding::Var { .. })) {
+                        return self.gen_method_call(&Expr::Ident(object.clone(), NodeSpan::default()), property, &call.args);
+                    }
+                    return Err(Error::new(ErrorCode::UnknownMember, Span::default()));
+                }
+                if object == "this" {
+                    // 组件方法调用同一组件的另一个方法
+                    return self.gen_named_call(&key, &format!("self.{property}"), &call.args);
+                }
+                let module = self.mod_names.get(object).unwrap_or(object);
+                self.gen_named_call(&key, &format!("{module}::{property}"), &call.args)
+            }
+            Callee::Method {
+                ref object,
+                ref method,
+            } => self.gen_method_call(object, method, &call.args),
+            Callee::Expr(ref inner) => {
+                // 实参按被调用函数类型里的参数类型生成（例如把函数名装箱成 `Box<dyn Fn>`）
+                let params = match self.expr_type(inner) {
+                    Some(TypeAnn::Function(f)) => f.params,
+                    _ => Vec::new(),
+                };
+                let callee = self.gen_expr_bp(inner, 90)?;
+                let mut args = Vec::new();
+                for (i, a) in call.args.iter().enumerate() {
+                    args.push(self.gen_expr_as(a, params.get(i))?);
+                }
+                Ok(format!("{callee}({})", args.join(", ")))
+            }
+        }
+    }
+
+    /// 生成方法调用 `object.method(args)`。目前只支持字符串的内置方法（见 `gen_string_method`）、
+    /// `Date` 的方法（见 `gen_date_method`）和 `x.toString()`（见 `gen_to_string`）。
+    fn gen_method_call(&mut self, object: &Expr, method: &str, args: &[Expr]) -> Result<String, Error> {
+        if method == "toString" {
+            if !args.is_empty() {
+                return Err(Error::new(ErrorCode::ConversionArgCount, Span::default()));
+            }
+            return self.gen_to_string(object);
+        }
+        match self.expr_type(object) {
+            Some(TypeAnn::String) => self.gen_string_method(object, method, args),
+            Some(TypeAnn::Date) => self.gen_date_method(object, method, args),
+            _ => Err(Error::new(ErrorCode::UnknownMethod, Span::default())),
+        }
+    }
+
+    /// 生成属性读取 `object.property`，返回 `(代码, bp)`。目前只有字符串和数组的 `length`：
+    /// - `s.length` -> `s.chars().count() as i32`
+    /// - `xs.length` -> `xs.len() as i32`
+    ///
+    /// 以及 f64 模式下的 `Math.PI` / `Math.E` -> `std::f64::consts::PI` / `E`。
+    ///
+    /// `as` 转换后面紧跟 `<` 会被 Rust 当成泛型参数，所以 bp 比比较运算低，在比较里会加括号。
+    fn gen_member(&mut self, object: &Expr, property: &str) -> Result<(String, u8), Error> {
+        if let Some(ty) = self.field_type(object, property) {
+            let place = self.field_place(property);
+            return match self.state_fields.get(property) {
+                // `get()` 已经返回拷贝
+                Some(StateKind::Link | StateKind::Provide(_) | StateKind::Consume(_)) => Ok((place, 90)),
+                _ => Ok((read_place(place, Some(&ty)), 90)),
+            };
+        }
+        match self.expr_type(object) {
+            Some(TypeAnn::String) if property == "length" => {
+                let recv = self.gen_expr_bp(object, 90)?;
+                Ok((format!("{recv}.chars().count() as {}", self.num_type()), 45))
+            }
+            Some(TypeAnn::Array(_)) if property == "length" => {
+                let recv = self.gen_expr_bp(object, 90)?;
+                Ok((format!("{recv}.len() as {}", self.num_type()), 45))
+            }
+            Some(TypeAnn::String) => Err(Error::new(ErrorCode::UnknownStringMethod, Span::default())),
+            _ if matches!(property, "PI" | "E") && self.is_builtin(object, "Math") => {
+                if !self.f64_mode() {
+                    // number 是 i32 时放不下 π
+                    return Err(Error::new(ErrorCode::MathConstantNeedsFloat, Span::default()));
+                }
+                Ok((format!("std::f64::consts::{property}"), 100))
+            }
+            _ => Err(Error::new(ErrorCode::UnsupportedMemberAccess, Span::default())),
+        }
+    }
+
+    /// 组件方法里的字段读取 `this.x`：返回字段的类型（见 `gen_component`），不是字段时返回 None。
+    fn field_type(&self, object: &Expr, property: &str) -> Option<TypeAnn> {
+        match object {
+            Expr::Ident(name, _) if name == "this" => self.var_type(&member_key(name, property)),
+            _ => None,
+        }
+    }
+
+    /// `this.x` 是不是正在生成的组件里带状态装饰器的字段（赋值要调用 `set`）。
+    fn is_state_field(&self, object: &str, property: &str) -> bool {
+        object == "this" && self.state_fields.contains_key(property)
+    }
+
+    /// 组件方法里 `this.x` 的 Rust 位置表达式：普通字段是 `self.x`，`@State` / `@Prop` 字段是 `self.x.value`，
+    /// 共享的字段（`@Link` / `@Provide` / `@Consume`）是 `self.x.get()`。
+    fn field_place(&self, property: &str) -> String {
+        match self.state_fields.get(property) {
+            Some(StateKind::State | StateKind::Prop) => format!("self.{property}.value"),
+            Some(_) => format!("self.{property}.get()"),
+            None => format!("self.{property}"),
+        }
+    }
+
+    /// `object` 是不是内置对象 `builtin`（`Math`、`JSON`），并且没有被同名的变量、namespace
+    /// 或 `import * as Math` 遮蔽。
+    fn is_builtin(&self, object: &Expr, builtin: &str) -> bool {
+        let Expr::Ident(name, _) = object else {
+            return false;
+        };
+        let prefix = format!("{builtin}.");
+        name == builtin
+            && self.resolve(name).is_none()
+            && !self.scopes.iter().any(|s| s.keys().any(|k| k.starts_with(&prefix)))
+    }
+
+    /// number 对应的 Rust 类型名：`i32` 或 `f64`。
+    fn num_type(&self) -> &'static str {
+        self.options.number_mode.rust_type()
+    }
+
+    /// 类型标注对应的 Rust 类型，number 和 string 按选项映射（见 `NumberMode`、`StringMode`）。
+    fn rust_type(&self, t: &TypeAnn) -> String {
+        self.note_type(t);
+        rust_type(t, self.options.number_mode, self.options.string_mode)
+    }
+
+    /// 参数的 Rust 写法 `name: T`，类型见 `rust_param_type`。
+    fn gen_param(&self, p: &Param) -> String {
+        let ty = param_type(p);
+        self.note_type(&ty);
+        format!("{}: {}", p.name, rust_param_type(&ty, self.options.number_mode, self.options.string_mode))
+    }
+
+    /// 要生成的类型里有 `Date` 时登记 `ArkDate` 类型别名（定义见 `with_helpers`）。
+    fn note_type(&self, t: &TypeAnn) {
+        if mentions_date(t) {
+            self.helper("ArkDate");
+        }
+    }
+
+    /// 生成的代码用到运行时辅助项 `name`（见 `runtime::required`）：登记下来，返回引用它的名字。
+    fn helper(&self, name: &'static str) -> &'static str {
+        self.helpers.borrow_mut().insert(name);
+        name
+    }
+
+    /// number 是不是映射成 f64（见 `NumberMode`）。
+    fn f64_mode(&self) -> bool {
+        self.options.number_mode == NumberMode::F64
+    }
+
+    /// 值是不是 f64：f64 模式下的 number，或者 i32 模式下的小数（见 `is_float_expr`）。
+    fn is_f64_expr(&self, e: &Expr) -> bool {
+        (self.f64_mode() && self.expr_type(e) == Some(TypeAnn::Number)) || self.is_float_expr(e)
+    }
+
+    /// 是不是内置 `Date` 的构造：`new Date()` / `new Date(ms)`。
+    fn is_builtin_date(&self, n: &NewExpr) -> bool {
+        self.is_builtin(&Expr::Ident(n.class.clone(), NodeSpan::default()), "Date")
+    }
+
+    /// 是不是内置 `Math` 上的函数调用：`Math.floor(x)` 等。
+    fn is_math_call(&self, call: &CallExpr) -> bool {
+        match &call.callee {
+            Callee::Member { object, .. } => self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "Math"),
+            _ => false,
+        }
+    }
+
+    /// 表达式的值是不是浮点数（f64）。
+    ///
+    /// number 目前是 i32，浮点数只来自 `Math.random()`、`parseFloat(s)`、毫秒时间戳（`Date.now()`、
+    /// `d.getTime()`，放不进 i32）和 `CodegenOptions::js_division` 下的除法，经过四则运算和没有类型标注的
+    /// 变量传播
+    /// （另一侧的整数转成 `f64::from(..)`），最后用 `Math.floor/ceil/round` 转回整数：
+    /// `Math.floor(Math.random() * 6) + 1`。
+    ///
+    /// `NumberMode::F64` 时所有数字都是 f64，不需要区分，总是返回 false。
+    ///
+    /// 类型检查登记过的表达式用它的结果（见 `typeck::Types::is_float`），CodeGen 临时构造的按下面的规则判断。
+    fn is_float_expr(&self, e: &Expr) -> bool {
+        if self.f64_mode() {
+            return false;
+        }
+        if self.types.get(e).is_some() {
+            return self.types.is_float(e);
+        }
+        match e {
+            Expr::Ident(name, _) => matches!(self.resolve(name), Some(Binding::Var { float: true, .. })),
+            Expr::Call(call) => match &call.callee {
+                Callee::Member { object, property } => {
+                    let object = Expr::Ident(object.clone(), NodeSpan::default());
+                    (property == "random" && self.is_builtin(&object, "Math"))
+                        || (property == "now" && self.is_builtin(&object, "Date"))
+                        || (property == "getTime" && self.expr_type(&object) == Some(TypeAnn::Date))
+                }
+                Callee::Method { object, method } => {
+                    method == "getTime" && self.expr_type(object) == Some(TypeAnn::Date)
+                }
+                Callee::Ident(name) if is_conversion_builtin(name) && self.resolve(name).is_none() => {
+                    name == "parseFloat" || (name == "Number" && call.args.first().is_some_and(|a| self.is_float_expr(a)))
+                }
+                _ => false,
+            },
+            Expr::Group(inner) => self.is_float_expr(inner),
+            Expr::Unary(u) => u.op == UnaryOp::Neg && self.is_float_expr(&u.expr),
+            // `CodegenOptions::js_division`：整数相除也得到小数
+            Expr::Binary(b) if b.op == BinaryOp::Div && self.options.js_division => true,
+            Expr::Binary(b) => {
+                matches!(
+                    b.op,
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod
+                ) && (self.is_float_expr(&b.left) || self.is_float_expr(&b.right))
+            }
+            _ => false,
+        }
+    }
+
+    /// 生成 `Math` 内置函数调用，返回 `(代码, bp)`。number 目前是 i32：
+    /// - `Math.random()` -> `__ark_random()`（输出里附带的 xorshift 函数），
+    ///   `CodegenOptions::use_rand` 时是 `rand::random::<f64>()`；结果是浮点数（见 `is_float_expr`）
+    /// - `Math.floor/ceil/round(x)` -> `x`：整数取整后不变；`x` 是浮点数时是 `x.floor() as i32` 等，
+    ///   `round` 和 JS 一样 .5 向上取整：`(x + 0.5).floor() as i32`
+    /// - `Math.abs(x)` -> `x.abs()`
+    /// - `Math.min(a, b, ..)` / `Math.max(..)` -> `a.min(b).min(..)`，只有一个实参时就是它本身
+    /// - `Math.sqrt(x)` -> `f64::from(x).sqrt() as i32`：结果向零截断，负数的平方根（NaN）得到 0
+    /// - `Math.pow(a, b)` -> `f64::from(a).powi(b) as i32`：结果向零截断，溢出时取 i32 的最大/最小值
+    ///
+    /// 结果直接是实参本身时，括号由 `parent_bp` 决定；`as` 转换的 bp 见 `gen_member`。
+    /// f64 模式见 `gen_math_call_f64`。
+    /// 实参个数不对报 `MathArgCount`，不认识的函数报 `UnknownMathFunction`。
+    fn gen_math_call(&mut self, method: &str, args: &[Expr], parent_bp: u8) -> Result<(String, u8), Error> {
+        let arity_ok = match method {
+            "floor" | "ceil" | "round" | "abs" | "sqrt" => args.len() == 1,
+            "pow" => args.len() == 2,
+            "random" => args.is_empty(),
+            "min" | "max" => !args.is_empty(),
+            _ => return Err(Error::new(ErrorCode::UnknownMathFunction, Span::default())),
+        };
+        if !arity_ok || args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            return Err(Error::new(ErrorCode::MathArgCount, Span::default()));
+        }
+        if self.f64_mode() && method != "random" {
+            return self.gen_math_call_f64(method, args);
+        }
+        match method {
+            "random" if self.options.use_rand => Ok(("rand::random::<f64>()".to_string(), 90)),
+            "random" => Ok((format!("{}()", self.helper("__ark_random")), 90)),
+            "round" if self.is_float_expr(&args[0]) => {
+                Ok((format!("({} + 0.5).floor() as i32", self.gen_expr_bp(&args[0], 60)?), 45))
+            }
+            "floor" | "ceil" if self.is_float_expr(&args[0]) => {
+                Ok((format!("{}.{method}() as i32", self.gen_expr_bp(&args[0], 90)?), 45))
+            }
+            "floor" | "ceil" | "round" => Ok((self.gen_expr_bp(&args[0], parent_bp)?, 100)),
+            "abs" => Ok((format!("{}.abs()", self.gen_expr_bp(&args[0], 90)?), 90)),
+            "min" | "max" if args.len() == 1 => Ok((self.gen_expr_bp(&args[0], parent_bp)?, 100)),
+            "min" | "max" => {
+                let mut out = self.gen_expr_bp(&args[0], 90)?;
+                for a in &args[1..] {
+                    out = format!("{out}.{method}({})", self.gen_expr(a)?);
+                }
+                Ok((out, 90))
+            }
+            "sqrt" => Ok((format!("f64::from({}).sqrt() as i32", self.gen_expr(&args[0])?), 45)),
+            _ => {
+                let base = self.gen_expr(&args[0])?;
+                let exp = self.gen_expr(&args[1])?;
+                Ok((format!("f64::from({base}).powi({exp}) as i32"), 45))
+            }
+        }
+    }
+
+    /// 生成字符串内置方法调用。
+    ///
+    /// 下标按字符（`char`）计算，而不是 JS 的 UTF-16 码元；只含 BMP 字符的字符串两者一致。
+    /// - `s.charAt(i)` -> `s.chars().nth(i as usize).map(String::from).unwrap_or_default()`：越界时是空字符串
+    /// - `s.charCodeAt(i)` -> 字符的码点（`char as i32`），越界时是 `-1`（JS 里是 NaN）。
+    ///   超出 BMP 的字符得到完整码点，而不是 JS 的 UTF-16 代理项
+    /// - `s.includes(t)` -> `s.contains(&t)`
+    /// - `s.indexOf(t)` -> `__ark_index_of(&s, t)`：找不到时为 `-1`，找到时把字节偏移换算成字符下标
+    /// - `s.slice(a, b)` -> `__ark_slice(&s, a, Some(b))`：负数下标从末尾倒数；`s.substring(a, b)` -> `__ark_substring(..)`：
+    ///   负数当 0，`a > b` 时交换。两者都把下标截断到 `[0, 长度]`，省略 `b`（`None`）表示到末尾。
+    ///   辅助函数见 `runtime::STRING_HELPERS`
+    /// - `s.toUpperCase()` / `s.toLowerCase()` -> `s.to_uppercase()` / `s.to_lowercase()`
+    /// - `s.trim()` -> `s.trim().to_string()`
+    /// - `s.split(sep)` -> `Vec<String>`；`sep` 是 `""` 字面量时按字符拆开（和 JS 一样）
+    /// - `s.replace(a, b)` -> `s.replacen(&a, &b, 1)`：和 JS 一样只替换第一处
+    ///
+    /// 方法名不认识报 `UnknownStringMethod`，实参个数不对报 `StringMethodArgCount`。
+    fn gen_string_method(&mut self, object: &Expr, method: &str, args: &[Expr]) -> Result<String, Error> {
+        let Some((min, max, _)) = string_method(method) else {
+            return Err(Error::new(ErrorCode::UnknownStringMethod, Span::default()));
+        };
+        if args.len() < min || args.len() > max || args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            return Err(Error::new(ErrorCode::StringMethodArgCount, Span::default()));
+        }
+        let recv = self.gen_expr_bp(object, 90)?;
+        match method {
+            "charAt" | "charCodeAt" => {
+                let index = match args.first() {
+                    Some(i) => self.gen_usize_index(i)?,
+                    None => "0".to_string(),
+                };
+                let nth = format!("{recv}.chars().nth({index})");
+                match method {
+                    "charAt" => Ok(format!("{nth}.map(String::from).unwrap_or_default()")),
+                    // f64 模式和 JS 一样越界时是 NaN
+                    _ if self.f64_mode() => Ok(format!("{nth}.map_or(f64::NAN, |c| f64::from(u32::from(c)))")),
+                    _ => Ok(format!("{nth}.map_or(-1i32, |c| c as i32)")),
+                }
+            }
+            "includes" => Ok(format!("{recv}.contains({})", self.gen_str_arg(&args[0])?)),
+            "indexOf" => {
+                let pat = self.gen_str_arg(&args[0])?;
+                let call = format!("{}(&{recv}, {pat})", self.helper("__ark_index_of"));
+                Ok(if self.f64_mode() { format!("f64::from({call})") } else { call })
+            }
+            "slice" | "substring" => {
+                // 下标按 i32 计算，f64 模式下先向零截断（和 JS 一样）
+                let mut index = |e: &Expr| -> Result<String, Error> {
+                    if self.f64_mode() {
+                        Ok(format!("{} as i32", self.gen_expr_bp(e, 80)?))
+                    } else {
+                        self.gen_expr(e)
+                    }
+                };
+                let start = index(&args[0])?;
+                let end = match args.get(1) {
+                    Some(e) => format!("Some({})", index(e)?),
+                    None => "None".to_string(),
+                };
+                let helper = self.helper(if method == "slice" { "__ark_slice" } else { "__ark_substring" });
+                Ok(format!("{helper}(&{recv}, {start}, {end})"))
+            }
+            "toUpperCase" => Ok(format!("{recv}.to_uppercase()")),
+            "toLowerCase" => Ok(format!("{recv}.to_lowercase()")),
+            "trim" => Ok(format!("{recv}.trim().to_string()")),
+            "split" => match &args[0] {
+                Expr::Literal(Literal::String(sep), _) if sep.is_empty() => {
+                    Ok(format!("{recv}.chars().map(String::from).collect::<Vec<String>>()"))
+                }
+                sep => {
+                    let sep = self.gen_str_arg(sep)?;
+                    Ok(format!("{recv}.split({sep}).map(String::from).collect::<Vec<String>>()"))
+                }
+            },
+            _ => {
+                let from = self.gen_str_arg(&args[0])?;
+                let to = self.gen_str_arg(&args[1])?;
+                Ok(format!("{recv}.replacen({from}, {to}, 1)"))
+            }
+        }
+    }
+
+    /// 作为 `&str` 传给 Rust 字符串方法的实参：字符串字面量直接写成 `"..."`，其它表达式取引用。
+    fn gen_str_arg(&mut self, arg: &Expr) -> Result<String, Error> {
+        match arg {
+            Expr::Literal(Literal::String(s), _) => Ok(format!("\"{}\"", escape_rust_string(s))),
+            _ => Ok(format!("&{}", self.gen_expr_bp(arg, 80)?)),
+        }
+    }
+
+    /// console 输出的一个实参：返回 `(占位符, Rust 实参)`。
+    ///
+    /// 默认按 JS 的样子输出：
+    /// - number/boolean/string 用 `{}`：数字没有后缀，字符串不带引号
+    /// - f64 经过 `__ark_number_to_string`，非有限值输出 `Infinity`/`-Infinity`/`NaN`
+    /// - 可空的基础类型经过 `__ark_fmt_nullable` 转成字符串，没有值时按标注输出 `null` 或 `undefined`（见 `NullKind`）
+    /// - `null`/`undefined` 字面量直接输出 `null`/`undefined`
+    /// - 其它类型（数组、函数……）没有 Display，仍用 `{:?}`
+    ///
+    /// `CodegenOptions::debug_format` 打开时一律用 `{:?}`。
+    fn gen_console_arg(&mut self, arg: &Expr) -> Result<(&'static str, String), Error> {
+        if self.options.debug_format {
+            return Ok(("{:?}", self.gen_expr(arg)?));
+        }
+        match arg {
+            Expr::Literal(Literal::Null, _) => return Ok(("{}", "\"null\"".to_string())),
+            Expr::Literal(Literal::Undefined, _) => return Ok(("{}", "\"undefined\"".to_string())),
+            _ => {}
+        }
+        let is_display = |t: &TypeAnn| matches!(t, TypeAnn::Number | TypeAnn::String | TypeAnn::Boolean);
+        match self.expr_type(arg) {
+            _ if self.is_f64_expr(arg) => {
+                Ok(("{}", format!("{}({})", self.helper("__ark_number_to_string"), self.gen_expr(arg)?)))
+            }
+            Some(t) if is_display(&t) => Ok(("{}", self.gen_expr(arg)?)),
+            // 和 Node.js 一样按 ISO 8601 格式输出
+            Some(TypeAnn::Date) => Ok(("{}", self.gen_date_method(arg, "toISOString", &[])?)),
+            Some(TypeAnn::Nullable(inner, kind)) if is_display(&inner) => {
+                let value = self.gen_expr_bp(arg, 80)?;
+                Ok(("{}", format!("{}(&{value}, \"{}\")", self.helper("__ark_fmt_nullable"), kind.as_str())))
+            }
+            _ => Ok(("{:?}", self.gen_expr(arg)?)),
+        }
+    }
+
+    /// 生成断言 `console.assert(cond, ...msg)` / `assert(cond, msg)`：
+    /// - 没有消息：`assert!(cond)`
+    /// - 有消息：`assert!(cond, "{} {}", a, b)`，消息按 `console.log` 的规则格式化（见 `gen_console_arg`）
+    ///
+    /// 失败时 panic（JS 的 `console.assert` 只打印一行错误并继续执行）；
+    /// 打开 `CodegenOptions::debug_assert` 时生成 `debug_assert!`。没有条件报 `AssertArgCount`。
+    fn gen_assert(&mut self, args: &[Expr]) -> Result<String, Error> {
+        let mac = if self.options.debug_assert { "debug_assert!" } else { "assert!" };
+        let Some((cond, msg)) = args.split_first() else {
+            return Err(Error::new(ErrorCode::AssertArgCount, Span::default()));
+        };
+        if args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            return Err(Error::new(ErrorCode::UnsupportedConsoleArgs, Span::default()));
+        }
+        let cond = self.gen_expr(cond)?;
+        if msg.is_empty() {
+            return Ok(format!("{mac}({cond})"));
+        }
+        let mut placeholders = Vec::new();
+        let mut values = Vec::new();
+        for a in msg {
+            let (placeholder, value) = self.gen_console_arg(a)?;
+            placeholders.push(placeholder);
+            values.push(value);
+        }
+        Ok(format!("{mac}({cond}, \"{}\", {})", placeholders.join(" "), values.join(", ")))
+    }
+
+    /// 生成按名字调用的函数：`key` 用来查签名和是否会抛异常，`path` 是生成代码里的写法
+    /// （普通调用两者相同；命名空间调用 `util.f()` 的 key 是 `util.f`，写法是 `util::f`）。
+    fn gen_named_call(&mut self, key: &str, path: &str, args: &[Expr]) -> Result<String, Error> {
+        let args = self.gen_call_args(key, args)?;
+        let call = format!("{path}({})", args.join(", "));
+        if !self.throwing.contains(key) {
+            return Ok(call);
+        }
+        Ok(self.propagate_call(call))
+    }
+
+    /// 取出 `Result<T, ArkError>` 里的值：在 try 块里出错时跳到标签块，否则用 `?` 向外传播。
+    fn propagate_call(&self, call: String) -> String {
+        match self.try_labels.last() {
+            Some(label) => format!("(match {call} {{ Ok(v) => v, Err(e) => break '{label} Err(e) }})"),
+            None => format!("{call}?"),
+        }
+    }
+
+    /// f64 模式下的 `Math` 函数：直接用 f64 的同名方法，`round` 和 JS 一样 .5 向上取整，`pow` 是 `powf`。
+    /// 实参已经在 `gen_math_call` 里检查过。
+    fn gen_math_call_f64(&mut self, method: &str, args: &[Expr]) -> Result<(String, u8), Error> {
+        let x = self.gen_expr_bp(&args[0], 90)?;
+        match method {
+            "round" => Ok((format!("({} + 0.5).floor()", self.gen_expr_bp(&args[0], 60)?), 90)),
+            "min" | "max" => {
+                let mut out = x;
+                for a in &args[1..] {
+                    out = format!("{out}.{method}({})", self.gen_expr(a)?);
+                }
+                Ok((out, 90))
+            }
+            "pow" => Ok((format!("{x}.powf({})", self.gen_expr(&args[1])?), 90)),
+            _ => Ok((format!("{x}.{method}()"), 90)),
+        }
+    }
+
+    /// 生成资源引用（结果是 string）：
+    /// - `$r('app.string.title')` -> `String::from("app.string.title")`
+    /// - `$rawfile('img/logo.png')` -> `String::from("rawfile/img/logo.png")`
+    ///
+    /// 配置了 `CodegenOptions::resource_lookup` 时改为调用它：`res::lookup("app.string.title")`。
+    ///
+    /// 实参必须是一个字符串字面量；`$r` 的资源名必须是 `app.类型.名字` 或 `sys.类型.名字`。否则报 `InvalidResourceRef`。
+    fn gen_resource_ref(&self, name: &str, args: &[Expr]) -> Result<String, Error> {
+        let [Expr::Literal(Literal::String(res), _)] = args else {
+            return Err(Error::new(ErrorCode::InvalidResourceRef, Span::default()));
+        };
+        let res = if name == "$rawfile" {
+            format!("rawfile/{res}")
+        } else {
+            let parts: Vec<&str> = res.split('.').collect();
+            if !matches!(parts.as_slice(), ["app" | "sys", ty, name] if !ty.is_empty() && !name.is_empty()) {
+                return Err(Error::new(ErrorCode::InvalidResourceRef, Span::default()));
+            }
+            res.clone()
+        };
+        Ok(match &self.options.resource_lookup {
+            Some(lookup) => format!("{lookup}({res:?})"),
+            None => format!("String::from({res:?})"),
+        })
+    }
+
+    /// 生成 HarmonyOS 的日志调用 `hilog.info(domain, tag, format, ...args)`（`debug`/`info`/`warn`/`error`/`fatal`）：
+    ///
+    /// ```text
+    /// hilog.info(0x0000, 'Index', 'count=%{public}d', n);
+    /// println!("{}: count={}", String::from("Index"), n);
+    /// ```
+    ///
+    /// - domain 不输出；tag 写在消息前面
+    /// - 格式串必须是字符串字面量（`HilogFormatNotLiteral`），`%{public}s`、`%{private}d`、`%s` 这类占位符
+    ///   按 `gen_console_arg` 的规则换成 `{}` 等，`%%` 是 `%`；占位符和实参个数不一致报 `HilogArgCount`
+    /// - 宏由 `CodegenOptions::hilog` 决定：`println!`（`warn` 以上用 `eprintln!`）、`log::info!` 或 `tracing::info!`，
+    ///   `fatal` 对应 `error` 级别
+    ///
+    /// 其它方法名报 `UnknownHilogFunction`。
+    fn gen_hilog_call(&mut self, method: &str, args: &[Expr]) -> Result<String, Error> {
+        let level = match method {
+            "debug" | "info" | "warn" | "error" => method,
+            "fatal" => "error",
+            _ => return Err(Error::new(ErrorCode::UnknownHilogFunction, Span::default())),
+        };
+        let mac = match self.options.hilog {
+            HilogMode::Println if matches!(level, "debug" | "info") => "println!".to_string(),
+            HilogMode::Println => "eprintln!".to_string(),
+            HilogMode::Log => format!("log::{level}!"),
+            HilogMode::Tracing => format!("tracing::{level}!"),
+        };
+        let [_domain, tag, format, rest @ ..] = args else {
+            return Err(Error::new(ErrorCode::HilogArgCount, Span::default()));
+        };
+        let Expr::Literal(Literal::String(format), _) = format else {
+            return Err(Error::new(ErrorCode::HilogFormatNotLiteral, Span::default()));
+        };
+        let (placeholder, tag) = self.gen_console_arg(tag)?;
+        let mut fmt = format!("{placeholder}: ");
+        let mut values = vec![tag];
+        let mut rest = rest.iter();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '%' if chars.peek() == Some(&'%') => {
+                    chars.next();
+                    fmt.push('%');
+                }
+                '%' => {
+                    // `%{public}` / `%{private}` 只影响日志脱敏，这里跳过
+                    if chars.peek() == Some(&'{') {
+                        for c in chars.by_ref() {
+                            if c == '}' {
+                                break;
+                            }
+                        }
+                    }
+                    chars.next();
+                    let arg = rest.next().ok_or_else(|| Error::new(ErrorCode::HilogArgCount, Span::default()))?;
+                    let (placeholder, value) = self.gen_console_arg(arg)?;
+                    fmt.push_str(placeholder);
+                    values.push(value);
+                }
+                '{' => fmt.push_str("{{"),
+                '}' => fmt.push_str("}}"),
+                c => fmt.push(c),
+            }
+        }
+        if rest.next().is_some() {
+            return Err(Error::new(ErrorCode::HilogArgCount, Span::default()));
+        }
+        Ok(format!("{mac}({fmt:?}, {})", values.join(", ")))
+    }
+
+    /// 生成 `JSON` 内置函数（需要打开 `CodegenOptions::use_serde_json`，否则报 `JsonNeedsSerde`）：
+    /// - `JSON.stringify(v)` -> `serde_json::to_string(&v).unwrap()`
+    /// - `JSON.parse(s)` -> `serde_json::from_str(&s)`：结果类型由变量的类型标注决定，
+    ///   解析失败和 JS 一样抛异常（`ArkError::Error`，消息是 serde_json 的错误信息）
+    ///
+    /// 都只接受 1 个参数，否则报 `JsonArgCount`；其它方法名报 `UnknownJsonFunction`。
+    fn gen_json_call(&mut self, method: &str, args: &[Expr]) -> Result<String, Error> {
+        if !matches!(method, "stringify" | "parse") {
+            return Err(Error::new(ErrorCode::UnknownJsonFunction, Span::default()));
+        }
+        if !self.options.use_serde_json {
+            return Err(Error::new(ErrorCode::JsonNeedsSerde, Span::default()));
+        }
+        let [arg] = args else {
+            return Err(Error::new(ErrorCode::JsonArgCount, Span::default()));
+        };
+        if matches!(arg, Expr::Spread(_)) {
+            return Err(Error::new(ErrorCode::JsonArgCount, Span::default()));
+        }
+        match method {
+            "stringify" => Ok(format!("serde_json::to_string(&{}).unwrap()", self.gen_expr_bp(arg, 80)?)),
+            _ => {
+                let text = self.gen_str_arg(arg)?;
+                Ok(self.propagate_call(format!(
+                    "serde_json::from_str({text}).map_err(|e| ArkError::Error(e.to_string()))"
+                )))
+            }
+        }
+    }
+
+    /// 生成 `Date` 上的静态函数。目前只有 `Date.now()`：当前时间的毫秒时间戳，是浮点数（见 `is_float_expr`）
+    /// - 默认：`__ark_millis(std::time::SystemTime::now())`（辅助函数见 `runtime::DATE_HELPERS`）
+    /// - `CodegenOptions::use_chrono`：`(chrono::Utc::now().timestamp_millis() as f64)`
+    ///
+    /// 其它函数名报 `UnknownDateFunction`，带实参报 `DateArgCount`。
+    fn gen_date_call(&mut self, method: &str, args: &[Expr]) -> Result<String, Error> {
+        if method != "now" {
+            return Err(Error::new(ErrorCode::UnknownDateFunction, Span::default()));
+        }
+        if !args.is_empty() {
+            return Err(Error::new(ErrorCode::DateArgCount, Span::default()));
+        }
+        if self.options.use_chrono {
+            Ok("(chrono::Utc::now().timestamp_millis() as f64)".to_string())
+        } else {
+            Ok(format!("{}(std::time::SystemTime::now())", self.helper("__ark_millis")))
+        }
+    }
+
+    /// 生成 `new Date(..)`，值的类型是 `ArkDate`：
+    /// - `new Date()` -> `std::time::SystemTime::now()` / `chrono::Utc::now()`
+    /// - `new Date(ms)` -> `__ark_from_millis(..)` / `chrono::DateTime::from_timestamp_millis(..).unwrap()`：
+    ///   `ms` 是毫秒时间戳（number 或 `Date.now()` 这样的浮点数）
+    ///
+    /// 不支持用日期字符串或年月日构造：实参是字符串等非数字类型时报 `UnsupportedDateArgs`，多于 1 个报 `DateArgCount`。
+    fn gen_new_date(&mut self, args: &[Expr]) -> Result<String, Error> {
+        let chrono = self.options.use_chrono;
+        match args {
+            [] if chrono => Ok("chrono::Utc::now()".to_string()),
+            [] => Ok("std::time::SystemTime::now()".to_string()),
+            [ms] => {
+                // 类型未知的值（例如保存了 `Date.now()` 的变量）交给 `f64::from` 处理
+                if matches!(self.expr_type(ms), Some(t) if t != TypeAnn::Number) {
+                    return Err(Error::new(ErrorCode::UnsupportedDateArgs, Span::default()));
+                }
+                if chrono {
+                    let ms = self.gen_expr_bp(ms, 80)?;
+                    Ok(format!("chrono::DateTime::from_timestamp_millis({ms} as i64).unwrap()"))
+                } else if self.is_float_expr(ms) {
+                    Ok(format!("{}({})", self.helper("__ark_from_millis"), self.gen_expr(ms)?))
+                } else {
+                    Ok(format!("{}({})", self.helper("__ark_from_millis"), self.gen_as_f64(ms)?))
+                }
+            }
+            _ => Err(Error::new(ErrorCode::DateArgCount, Span::default())),
+        }
+    }
+
+    /// 生成 `Date` 值的方法调用：
+    /// - `d.getTime()` -> `__ark_millis(d)` / `(d.timestamp_millis() as f64)`：毫秒时间戳，是浮点数
+    /// - `d.toISOString()` -> `__ark_iso_string(d)` / `d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)`：
+    ///   和 JS 一样是 UTC 时间，例如 `2024-01-02T03:04:05.678Z`
+    ///
+    /// 其它方法名报 `UnknownDateMethod`，带实参报 `DateArgCount`。
+    fn gen_date_method(&mut self, object: &Expr, method: &str, args: &[Expr]) -> Result<String, Error> {
+        if !matches!(method, "getTime" | "toISOString") {
+            return Err(Error::new(ErrorCode::UnknownDateMethod, Span::default()));
+        }
+        if !args.is_empty() {
+            return Err(Error::new(ErrorCode::DateArgCount, Span::default()));
+        }
+        if !self.options.use_chrono {
+            let recv = self.gen_expr(object)?;
+            return match method {
+                "getTime" => Ok(format!("{}({recv})", self.helper("__ark_millis"))),
+                _ => Ok(format!("{}({recv})", self.helper("__ark_iso_string"))),
+            };
+        }
+        let recv = self.gen_expr_bp(object, 90)?;
+        match method {
+            "getTime" => Ok(format!("({recv}.timestamp_millis() as f64)")),
+            _ => Ok(format!("{recv}.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)")),
+        }
+    }
+
+    /// 生成数字/字符串转换函数：
+    /// - `parseInt(s, radix?)` -> `__ark_parse_int(&s, radix)`：和 JS 一样跳过前导空白、只解析开头的数字
+    ///   （`"12px"` 得到 12），省略 `radix` 时按 0 处理（`0x` 开头按十六进制，否则十进制）；
+    ///   实参已经是 number 时原样返回，浮点数向零截断
+    /// - `parseFloat(s)` -> `__ark_parse_float(&s)`：开头最长的一段数字，结果是浮点数（见 `is_float_expr`）
+    /// - `Number(x)`：字符串 -> `__ark_number(&s)`（整个字符串必须是数字，空串是 0，小数向零截断），
+    ///   boolean -> `i32::from(b)`，number 原样返回
+    /// - `String(x)` -> 同 `x.toString()`（见 `gen_to_string`）
+    ///
+    /// 辅助函数见 `runtime::CONVERSION_HELPERS`，返回 `Option`；转换失败（JS 里是 NaN）时的行为由 `CodegenOptions::nan`
+    /// 决定。f64 模式下结果都是 f64，`parseInt(x)` 对 number 是 `x.trunc()`。实参个数不对报 `ConversionArgCount`，不能转换的类型报 `UnsupportedConversion`。
+    fn gen_conversion(&mut self, name: &str, args: &[Expr]) -> Result<String, Error> {
+        let arity_ok = match name {
+            "parseInt" => matches!(args.len(), 1 | 2),
+            _ => args.len() == 1,
+        };
+        if !arity_ok || args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            return Err(Error::new(ErrorCode::ConversionArgCount, Span::default()));
+        }
+        let arg = &args[0];
+        if name == "String" {
+            return self.gen_to_string(arg);
+        }
+        let f64_mode = self.f64_mode();
+        let (call, zero) = match (name, self.expr_type(arg)) {
+            ("parseInt" | "Number", _) if self.is_float_expr(arg) => {
+                return Ok(format!("({} as i32)", self.gen_expr_bp(arg, 80)?));
+            }
+            ("parseInt", Some(TypeAnn::Number)) if f64_mode => {
+                return Ok(format!("{}.trunc()", self.gen_expr_bp(arg, 90)?));
+            }
+            ("parseInt" | "parseFloat" | "Number", Some(TypeAnn::Number)) => return self.gen_expr_bp(arg, 90),
+            ("Number", Some(TypeAnn::Boolean)) if f64_mode => {
+                return Ok(format!("f64::from(u8::from({}))", self.gen_expr(arg)?));
+            }
+            ("Number", Some(TypeAnn::Boolean)) => return Ok(format!("i32::from({})", self.gen_expr(arg)?)),
+            ("parseFloat", _) if self.is_float_expr(arg) => return self.gen_expr_bp(arg, 90),
+            (_, Some(TypeAnn::String)) | (_, None) => {
+                let text = self.gen_str_arg(arg)?;
+                // 辅助函数返回 `Option<i32>` / `Option<f64>`，统一成 number 对应的类型；f64 模式下失败时是 NaN
+                let zero = if f64_mode { "f64::NAN" } else { "0" };
+                match (name, f64_mode) {
+                    ("parseInt", _) => {
+                        let radix = match args.get(1) {
+                            Some(r) => self.gen_expr(r)?,
+                            None => "0".to_string(),
+                        };
+                        let call = format!("{}({text}, {radix})", self.helper("__ark_parse_int"));
+                        (if f64_mode { format!("{call}.map(f64::from)") } else { call }, zero)
+                    }
+                    ("parseFloat", true) => (format!("{}({text})", self.helper("__ark_parse_float")), zero),
+                    ("parseFloat", false) => (format!("{}({text})", self.helper("__ark_parse_float")), "0.0"),
+                    (_, true) => (format!("{}({text})", self.helper("__ark_number")), zero),
+                    (_, false) => (format!("{}({text}).map(|n| n as i32)", self.helper("__ark_number")), zero),
+                }
+            }
+            _ => return Err(Error::new(ErrorCode::UnsupportedConversion, Span::default())),
+        };
+        match self.options.nan {
+            NanMode::Zero => Ok(format!("{call}.unwrap_or({zero})")),
+            NanMode::Panic => Ok(format!("{call}.expect(\"{name}: not a number\")")),
+            NanMode::Throw => Ok(self.propagate_call(format!(
+                "{call}.ok_or_else(|| ArkError::Error(String::from(\"{name}: not a number\")))"
+            ))),
+        }
+    }
+
+    /// 生成 `x.toString()` / `String(x)`，和 JS 的字符串转换一致：
+    /// - number / boolean / string -> `x.to_string()`（Rust 的 f64 格式化 `2.0` 得到 `"2"`，和 JS 一样）
+    /// - f64 -> `__ark_number_to_string(x)`，非有限值是 `"Infinity"`/`"-Infinity"`/`"NaN"`
+    /// - 可空值 -> `__ark_fmt_nullable(&x, "null")`，`None` 时是 `"null"` 或 `"undefined"`（同 `console.log`）；
+    ///   `null` / `undefined` 字面量就是对应的文字
+    /// - 数组 -> `__ark_join(&xs)`，元素用逗号连接：`[1, 2]` 得到 `"1,2"`
+    ///
+    /// 其它类型（函数、`Date` 等）报 `UnsupportedConversion`。
+    fn gen_to_string(&mut self, arg: &Expr) -> Result<String, Error> {
+        match strip_group(arg) {
+            Expr::Literal(Literal::Null, _) => return Ok("String::from(\"null\")".to_string()),
+            Expr::Literal(Literal::Undefined, _) => return Ok("String::from(\"undefined\")".to_string()),
+            _ => {}
+        }
+        let is_display = |t: &TypeAnn| matches!(t, TypeAnn::Number | TypeAnn::String | TypeAnn::Boolean);
+        match self.expr_type(arg) {
+            _ if self.is_f64_expr(arg) => Ok(format!("{}({})", self.helper("__ark_number_to_string"), self.gen_expr(arg)?)),
+            Some(t) if is_display(&t) => Ok(format!("{}.to_string()", self.gen_expr_bp(arg, 90)?)),
+            Some(TypeAnn::Nullable(inner, kind)) if is_display(&inner) => {
+                let value = self.gen_expr_bp(arg, 80)?;
+                Ok(format!("{}(&{value}, \"{}\")", self.helper("__ark_fmt_nullable"), kind.as_str()))
+            }
+            Some(TypeAnn::Array(elem)) if is_display(&elem) => {
+                let value = self.gen_expr_bp(arg, 80)?;
+                Ok(format!("{}(&{value})", self.helper("__ark_join")))
+            }
+            _ => Err(Error::new(ErrorCode::UnsupportedConversion, Span::default())),
+        }
+    }
+
+    /// 生成定时器调用。目前没有事件循环，定时器按同步方式执行：先睡眠，再调用回调。
+    ///
+    /// - `setTimeout(cb, ms)` -> `{ std::thread::sleep(..); cb(); }`
+    /// - `setInterval(cb, ms)` -> `loop { std::thread::sleep(..); cb(); }`（没有 clearInterval，永远重复）
+    /// - 省略 `ms` 时不睡眠；负数延迟按 0 处理；`ms` 之后的实参原样传给回调
+    ///
+    /// 回调必须是函数名（普通函数、闭包或回调参数），调用方式与 `cb(...)` 完全一致，
+    /// 所以剩余参数、会抛异常的回调都能复用普通调用的生成逻辑。
+    ///
+    /// 注意这和 JS 的语义不同：回调在 `setTimeout` 返回之前就执行完了，后面的代码能看到回调的效果，
+    /// 回调抛出的异常也会被包住 `setTimeout` 的 `try` 捕获；多个定时器按源码顺序执行，而不是按延迟。
+    /// 只有定时器按延迟从小到大写在程序最后时，输出才和 JS 相同。
+    fn gen_timer_call(&mut self, name: &str, args: &[Expr]) -> Result<String, Error> {
+        let Some(Expr::Ident(callback, _)) = args.first().map(strip_group) else {
+            return Err(Error::new(ErrorCode::TimerCallbackNotFunction, Span::default()));
+        };
+        let call = self.gen_call(&CallExpr {
+            callee: Callee::Ident(callback.clone()),
+            args: args.get(2..).unwrap_or_default().to_vec(),
+            span: NodeSpan::default(),
+        })?;
+        let sleep = match args.get(1) {
+            Some(ms) => format!(
+                "std::thread::sleep(std::time::Duration::from_millis({}.max(0{}) as u64)); ",
+                self.gen_expr_bp(ms, 90)?,
+                if self.f64_mode() { ".0" } else { "" }
+            ),
+            None => String::new(),
+        };
+        match name {
+            "setInterval" => Ok(format!("loop {{ {sleep}{call}; }}")),
+            _ => Ok(format!("{{ {sleep}{call}; }}")),
+        }
+    }
+
+    /// 生成 `Promise` 的内置静态方法。
+    ///
+    /// Promise 的结果统一是 `Result<T, ArkError>`，reject 对应 `Err`：
+    /// - `Promise.resolve(v)` -> `Box::pin(std::future::ready(Ok::<_, ArkError>(v)))`
+    /// - `Promise.reject(e)` -> `Box::pin(std::future::ready(Err(ArkError::..)))`
+    /// - `Promise.all(ps)` -> 一个依次 await 每个 Promise、遇到 `Err` 立刻返回的 async 块
+    ///
+    /// 这三个方法都只接受 1 个参数；生成的 async 块要求 Rust 2018 及以上 edition（见 `Edition`）。
+    /// `Promise.reject` 的成功类型只能从上下文推断，所以通常要配合类型标注使用。
+    fn gen_promise_call(&mut self, method: PromiseFn, args: &[Expr]) -> Result<String, Error> {
+        let [arg] = args else {
+            return Err(Error::new(ErrorCode::PromiseArgCount, Span::default()));
+        };
+        match method {
+            PromiseFn::Resolve => {
+                let value = self.gen_expr(arg)?;
+                Ok(format!("Box::pin(std::future::ready(Ok::<_, ArkError>({value})))"))
+            }
+            PromiseFn::Reject => {
+                let err = self.gen_error_value(arg)?;
+                Ok(format!("Box::pin(std::future::ready(Err({err})))"))
+            }
+            PromiseFn::All if self.options.edition == Edition::E2015 => {
+                Err(Error::new(ErrorCode::AsyncNeedsEdition2018, Span::default()))
+            }
+            PromiseFn::All => {
+                let promises = self.gen_expr(arg)?;
+                Ok(format!(
+                    "Box::pin(async move {{ let mut out = Vec::new(); for p in {promises} {{ out.push(p.await?); }} Ok::<_, ArkError>(out) }})"
+                ))
+            }
+        }
+    }
+
+    /// 生成普通函数调用的实参列表。
+    ///
+    /// 如果被调函数声明了剩余参数 `...rest: T[]`，那么从该位置开始的所有实参
+    /// 会被收集成一个 `Vec<T>` 传入：
+    /// - `f(1, 2, 3)` -> `f(vec![1i32, 2i32, 3i32])`
+    /// - `f(...xs)` -> `f(xs.to_vec())`
+    /// - `f(1, ...xs)` -> `f([vec![1i32], xs.to_vec()].concat())`
+    ///
+    /// Rust 没有“把数组展开成多个实参”的语法，所以展开实参只能对应剩余参数。
+    fn gen_call_args(&mut self, name: &str, args: &[Expr]) -> Result<Vec<String>, Error> {
+        let (params, rest_idx) = match self.resolve(name) {
+            Some(Binding::Func {
+                params, rest_idx, ..
+            }) => (params.clone(), *rest_idx),
+            _ => (Vec::new(), None),
+        };
+
+        let fixed_len = rest_idx.map_or(args.len(), |k| k.min(args.len()));
+        let mut out = Vec::new();
+        for (i, a) in args[..fixed_len].iter().enumerate() {
+            let arg = self.gen_expr_as(a, params.get(i))?;
+            // 回调参数是 `&dyn Fn(..)`（见 `rust_param_type`）：函数名、闭包、`Box<dyn Fn>` 变量和回调参数都借用着传，
+            // `&&dyn Fn` / `&Box<dyn Fn>` 会自动解引用
+            match params.get(i) {
+                Some(TypeAnn::Function(_)) => out.push(format!("&{arg}")),
+                _ => out.push(arg),
+            }
+        }
+        if rest_idx.is_some() {
+            out.push(self.gen_rest_arg(&args[fixed_len..])?);
+        }
+        Ok(out)
+    }
+
+    /// 把落在剩余参数位置上的实参组装成一个 `Vec` 表达式。
+    fn gen_rest_arg(&mut self, args: &[Expr]) -> Result<String, Error> {
+        if let [Expr::Spread(inner)] = args {
+            return Ok(format!("{}.to_vec()", self.gen_expr_bp(inner, 90)?));
+        }
+        if !args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+            let mut items = Vec::new();
+            for a in args {
+                items.push(self.gen_expr(a)?);
+            }
+            return Ok(gen_vec_literal(&items));
+        }
+
+        // 普通实参与展开实参混排：相邻的普通实参合并成一个 `vec![...]`，
+        // 每个展开实参变成一个 `.to_vec()`，最后用 `concat()` 拼起来。
+        let mut parts = Vec::new();
+        let mut pending = Vec::new();
+        for a in args {
+            match a {
+                Expr::Spread(inner) => {
+                    if !pending.is_empty() {
+                        parts.push(gen_vec_literal(&pending));
+                        pending.clear();
+                    }
+                    parts.push(format!("{}.to_vec()", self.gen_expr_bp(inner, 90)?));
+                }
+                _ => pending.push(self.gen_expr(a)?),
+            }
+        }
+        if !pending.is_empty() {
+            parts.push(gen_vec_literal(&pending));
+        }
+        Ok(format!("[{}].concat()", parts.join(", ")))
+    }
+
+    /// 生成表达式，`parent_bp` 是外层运算的绑定强度；里面还没有位置的错误报在这个表达式上。
+    fn gen_expr_bp(&mut self, expr: &Expr, parent_bp: u8) -> Result<String, Error> {
+        self.gen_expr_kind(expr, parent_bp).map_err(|e| e.at(expr.span()))
+    }
+
+    fn gen_expr_kind(&mut self, expr: &Expr, parent_bp: u8) -> Result<String, Error> {
+        // 这里用“表达式绑定强度（bp）”来决定是否加括号：
+        // - 子表达式 bp < 父表达式 bp 时，必须加括号，避免 Rust 按自己的优先级重排。
+        // - bp 数值越大，优先级越高（绑定越紧）。
+        let (s, bp) = match expr {
+            // number 是 i32 时放不下小数和超出范围的整数
+            Expr::Literal(Literal::Float(text), _) if !self.f64_mode() => {
+                let fractional = text.parse::<f64>().is_ok_and(|v| v.fract() != 0.0) || text.contains('.');
+                let code = if fractional { ErrorCode::FloatLiteralNeedsF64 } else { ErrorCode::InvalidNumber };
+                return Err(Error::new(code, Span::default()));
+            }
+            Expr::Literal(lit, _) => (gen_literal_expr(lit, self.options.number_mode, self.options.string_mode), 100),
+            Expr::Ident(name, _) => self.gen_ident(name),
+            Expr::Group(inner) => (format!("({})", self.gen_expr_bp(inner, 0)?), 100),
+            Expr::Call(CallExpr {
+                callee: Callee::Member { object, property },
+                args,
+                ..
+            }) if self.is_builtin(&Expr::Ident(object.clone(), NodeSpan::default()), "Math")
+                && !self.options.intrinsics.contains_key(&member_key(object, property)) =>
+            {
+                self.gen_math_call(property, args, parent_bp)?
+            }
+            Expr::Call(call) => (self.gen_call(call)?, 90),
+            Expr::Unary(u) => (self.gen_unary(u)?, 80),
+            // `??` 可能直接退化成某一侧操作数，括号由 gen_nullish 自己决定
+            Expr::Binary(b) if b.op == BinaryOp::NullishCoalesce => {
+                return self.gen_nullish(b, parent_bp);
+            }
+            Expr::Binary(b) => self.gen_binary(b)?,
+            // 展开实参只能被 gen_rest_arg 消费；走到这里说明被调函数没有剩余参数。
+            Expr::Spread(_) => return Err(Error::new(ErrorCode::SpreadRequiresRestParam, Span::default())),
+            Expr::New(n) if self.is_builtin_date(n) => (self.gen_new_date(&n.args)?, 90),
+            // 异常对象只能直接被 throw（由 gen_throw 处理）。
+            Expr::New(_) => return Err(Error::new(ErrorCode::NewOnlyInThrow, Span::default())),
+            Expr::Assign(a) => (self.gen_assign_expr(a)?, 100),
+            Expr::Member(m) => self.gen_member(&m.object, &m.property)?,
+            Expr::Index(ix) => (self.gen_index(&ix.object, &ix.index)?, 90),
+        };
+
+        if bp < parent_bp {
+            Ok(format!("({s})"))
+        } else {
+            Ok(s)
+        }
+    }
+
+    /// 生成变量读取。被收窄为非空的可空变量需要取出 `Option` 里的值：
+    /// - Copy 类型（number/boolean）：`x.unwrap()`
+    /// - 其它类型：`x.clone().unwrap()`，避免把值从变量里移走
+    ///
+    /// `Cell`/`RefCell` 变量读出里面的值，见 `var_place`。
+    fn gen_ident(&self, name: &str) -> (String, u8) {
+        let place = self.var_place(name);
+        match self.resolve(name) {
+            Some(Binding::Var {
+                ty: Some(TypeAnn::Nullable(inner, _)),
+                narrowed: true,
+                cell,
+                ..
+            }) => match inner.as_ref() {
+                TypeAnn::Number | TypeAnn::Boolean => (format!("{place}.unwrap()"), 90),
+                _ if *cell => (format!("{place}.unwrap()"), 90),
+                _ => (format!("{place}.clone().unwrap()"), 90),
+            },
+            Some(Binding::Var { cell: true, .. }) => (place, 90),
+            _ => (name.to_string(), 100),
+        }
+    }
+
+    /// `StringMode::Shared` 下 string 和字符串字面量比较：`s == 'a'` -> `&*s == "a"`，
+    /// 直接和 `&str` 比较，不为字面量新建一个 `Rc<str>`。
+    fn gen_shared_str_eq(&mut self, b: &BinaryExpr) -> Result<Option<String>, Error> {
+        if self.options.string_mode != StringMode::Shared || !matches!(b.op, BinaryOp::EqEq | BinaryOp::NotEq) {
+            return Ok(None);
+        }
+        let side = |e: &Expr| match strip_group(e) {
+            Expr::Literal(Literal::String(s), _) => Some(format!("\"{}\"", escape_rust_string(s))),
+            _ => None,
+        };
+        let (left, right) = match (side(&b.left), side(&b.right)) {
+            (None, Some(lit)) if self.expr_type(&b.left) == Some(TypeAnn::String) => {
+                (format!("&*{}", self.gen_expr_bp(&b.left, 80)?), lit)
+            }
+            (Some(lit), None) if self.expr_type(&b.right) == Some(TypeAnn::String) => {
+                (lit, format!("&*{}", self.gen_expr_bp(&b.right, 80)?))
+            }
+            _ => return Ok(None),
+        };
+        let op = if b.op == BinaryOp::EqEq { "==" } else { "!=" };
+        Ok(Some(format!("{left} {op} {right}")))
+    }
+
+    /// 与 `null`/`undefined` 比较：`x == null` -> `x.is_none()`，`x != null` -> `x.is_some()`。
+    ///
+    /// 如果一边是可空类型、另一边是普通值，则把普通值包成 `Some(...)` 再比较。
+    fn gen_nullable_eq(&mut self, b: &BinaryExpr) -> Result<Option<String>, Error> {
+        let is_eq = match b.op {
+            BinaryOp::EqEq => true,
+            BinaryOp::NotEq => false,
+            _ => return Ok(None),
+        };
+
+        let (l_null, r_null) = (is_null_literal(&b.left), is_null_literal(&b.right));
+        if l_null && r_null {
+            return Ok(Some(is_eq.to_string()));
+        }
+        if l_null || r_null {
+            let other = strip_group(if l_null { &b.right } else { &b.left });
+            // 判空看的是变量本身，不受收窄影响，所以直接用变量名。
+            let operand = match other {
+                Expr::Ident(name, _) => self.var_place(name),
+                _ => self.gen_expr_bp(other, 90)?,
+            };
+            let method = if is_eq { "is_none" } else { "is_some" };
+            return Ok(Some(format!("{operand}.{method}()")));
+        }
+
+        let is_nullable = |t: &Option<TypeAnn>| matches!(t, Some(TypeAnn::Nullable(_, _)));
+        let (lt, rt) = (self.expr_type(&b.left), self.expr_type(&b.right));
+        if is_nullable(&lt) == is_nullable(&rt) || lt.is_none() || rt.is_none() {
+            return Ok(None);
+        }
+        let op = if is_eq { "==" } else { "!=" };
+        let bp = binary_bp(b.op);
+        let (left, right) = if is_nullable(&lt) {
+            let left = self.gen_expr_bp(&b.left, bp)?;
+            (left, format!("Some({})", self.gen_expr(&b.right)?))
+        } else {
+            let right = self.gen_expr_bp(&b.right, bp + 1)?;
+            (format!("Some({})", self.gen_expr(&b.left)?), right)
+        };
+        Ok(Some(format!("{left} {op} {right}")))
+    }
+
+    fn gen_unary(&mut self, u: &UnaryExpr) -> Result<String, Error> {
+        let op = match u.op {
+            UnaryOp::Not => "!",
+            UnaryOp::Neg if self.is_int_arith(&u.expr, &u.expr) && !matches!(*u.expr, Expr::Literal(_, _)) => {
+                let x = self.gen_expr_bp(strip_group(&u.expr), 90)?;
+                return Ok(self.gen_int_arith(x, "neg", None));
+            }
+            UnaryOp::Neg => "-",
+        };
+        let rhs = self.gen_expr_bp(&u.expr, 80)?;
+        Ok(format!("{op}{rhs}"))
+    }
+
+    /// 空值合并 `a ?? b`。
+    ///
+    /// 映射规则（依据左侧的推断类型）：
+    /// - 左侧是可空类型 `T | null`：
+    ///   - 右侧是普通值：`a.unwrap_or(b)`；右侧含函数调用等有副作用的表达式时用
+    ///     `a.unwrap_or_else(|| b)`，保持 TS “左侧非空时不求值右侧”的语义
+    ///   - 右侧也可空：`a.or(b)` / `a.or_else(|| b)`，结果仍是 `Option`
+    ///   - 右侧是 `null`：结果就是 `a`
+    /// - 左侧不可能为空（普通类型或已收窄的变量）：右侧永远不会被求值，直接生成 `a`
+    /// - 左侧就是 `null`：直接生成 `b`
+    fn gen_nullish(&mut self, b: &BinaryExpr, parent_bp: u8) -> Result<String, Error> {
+        if is_null_literal(&b.left) {
+            return self.gen_expr_bp(&b.right, parent_bp);
+        }
+        if !matches!(self.expr_type(&b.left), Some(TypeAnn::Nullable(_, _))) {
+            return self.gen_expr_bp(&b.left, parent_bp);
+        }
+
+        let left = self.gen_option_operand(&b.left)?;
+        if is_null_literal(&b.right) {
+            return Ok(left);
+        }
+
+        let right_nullable = matches!(self.expr_type(&b.right), Some(TypeAnn::Nullable(_, _)));
+        let right = if right_nullable {
+            self.gen_option_operand(&b.right)?
+        } else {
+            self.gen_expr(&b.right)?
+        };
+        let lazy = !matches!(strip_group(&b.right), Expr::Literal(_, _) | Expr::Ident(_, _));
+        let method = match (right_nullable, lazy) {
+            (false, false) => "unwrap_or",
+            (false, true) => "unwrap_or_else",
+            (true, false) => "or",
+            (true, true) => "or_else",
+        };
+        if lazy {
+            Ok(format!("{left}.{method}(|| {right})"))
+        } else {
+            Ok(format!("{left}.{method}({right})"))
+        }
+    }
+
+    /// 生成作为 `Option` 使用（会被消费）的操作数。
+    ///
+    /// `Option<String>` 这类非 Copy 的变量需要先 `.clone()`，否则 `unwrap_or` 会把它移走，
+    /// 之后再读取这个变量就无法通过编译。
+    fn gen_option_operand(&mut self, expr: &Expr) -> Result<String, Error> {
+        if let Expr::Ident(name, _) = strip_group(expr) {
+            if let Some(TypeAnn::Nullable(inner, _)) = self.var_type(name) {
+                if !matches!(inner.as_ref(), TypeAnn::Number | TypeAnn::Boolean) {
+                    return Ok(format!("{name}.clone()"));
+                }
+            }
+        }
+        self.gen_expr_bp(expr, 90)
+    }
+
+    /// 生成二元表达式，返回代码和它的 bp（见 `gen_expr_kind`）。
+    fn gen_binary(&mut self, b: &BinaryExpr) -> Result<(String, u8), Error> {
+        if let Some(s) = self.gen_nullable_eq(b)? {
+            return Ok((s, binary_bp(b.op)));
+        }
+        if let Some(s) = self.gen_shared_str_eq(b)? {
+            return Ok((s, binary_bp(b.op)));
+        }
+
+        if self.is_string_concat(b) {
+            return Ok((self.gen_string_concat(b)?, 100));
+        }
+
+        let op = match b.op {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::EqEq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::AndAnd => "&&",
+            BinaryOp::OrOr => "||",
+            // `??` 在上面已经交给 gen_nullish 处理
+            BinaryOp::NullishCoalesce => return self.gen_nullish(b, 0).map(|s| (s, 0)),
+        };
+
+        let bp = binary_bp(b.op);
+        // 一侧是浮点数（`Math.random()`）时，另一侧的整数转成 f64；
+        // `CodegenOptions::js_division` 时除法的两侧都转成 f64
+        let js_div = b.op == BinaryOp::Div && self.options.js_division && !self.f64_mode();
+        let float = !matches!(b.op, BinaryOp::AndAnd | BinaryOp::OrOr)
+            && (js_div || self.is_float_expr(&b.left) || self.is_float_expr(&b.right));
+        let method = match b.op {
+            BinaryOp::Add => Some("add"),
+            BinaryOp::Sub => Some("sub"),
+            BinaryOp::Mul => Some("mul"),
+            BinaryOp::Div => Some("div"),
+            BinaryOp::Mod => Some("rem"),
+            _ => None,
+        };
+        // 整数除以常量 0 在 JS 里是 NaN / Infinity，i32 表示不了，rustc 也会拒绝编译（`unconditional_panic`）
+        if matches!(b.op, BinaryOp::Div | BinaryOp::Mod)
+            && !float
+            && !self.f64_mode()
+            && crate::lint::const_number(&b.right) == Some(0)
+        {
+            return Err(Error::new(ErrorCode::IntegerDivisionByZero, b.span.0));
+        }
+        if let Some(method) = method.filter(|_| !float && self.is_int_arith(&b.left, &b.right)) {
+            let left = self.gen_expr_bp(strip_group(&b.left), 90)?;
+            let right = self.gen_expr(&b.right)?;
+            return Ok((self.gen_int_arith(left, method, Some(right)), 90));
+        }
+        let left = if float && !self.is_float_expr(&b.left) {
+            self.gen_as_f64(&b.left)?
+        } else {
+            self.gen_expr_bp(&b.left, bp)?
+        };
+        let right = if float && !self.is_float_expr(&b.right) {
+            self.gen_as_f64(&b.right)?
+        } else {
+            self.gen_expr_bp(&b.right, bp + 1)?
+        };
+        Ok((format!("{left} {op} {right}"), bp))
+    }
+
+    /// 一侧是 string 的 `+`：字符串拼接。
+    fn is_string_concat(&self, b: &BinaryExpr) -> bool {
+        b.op == BinaryOp::Add && [&b.left, &b.right].iter().any(|e| self.expr_type(e) == Some(TypeAnn::String))
+    }
+
+    /// 字符串拼接生成 `format!`：`&str` / `Rc<str>` 不能用 `+` 拼接，`String + String` 要写成 `a + &b`，
+    /// 数字也要先转成字符串，`format!` 对所有字符串形式和数字都适用，而且只借用操作数，不会把变量移走。
+    ///
+    /// 左结合的连续拼接合成一个 `format!`：`s + "!" + 1` -> `format!("{}{}{}", s, "!", 1i32)`；
+    /// 字符串字面量直接写成 `&str`。`1 + 2 + s` 先算数字加法，左边的 `1 + 2` 不展开。
+    ///
+    /// 没有 Display 或者 Display 和 JS 不一样的操作数（可空值、`null` 字面量、f64、数组）先按 `String(x)` 转换
+    /// （见 `gen_to_string`）：`"a" + x`（`x: number | null`）在 `x` 没有值时得到 `"anull"`。
+    fn gen_string_concat(&mut self, b: &BinaryExpr) -> Result<String, Error> {
+        let mut parts = vec![&*b.right];
+        let mut left = &*b.left;
+        while let Expr::Binary(l) = strip_group(left) {
+            if !self.is_string_concat(l) {
+                break;
+            }
+            parts.push(&l.right);
+            left = &l.left;
+        }
+        parts.push(left);
+        parts.reverse();
+        let args = parts
+            .into_iter()
+            .map(|e| match strip_group(e) {
+                Expr::Literal(Literal::String(s), _) => Ok(format!("\"{}\"", escape_rust_string(s))),
+                e if is_null_literal(e) || self.is_f64_expr(e) => self.gen_to_string(e),
+                e => match self.expr_type(e) {
+                    None | Some(TypeAnn::Number | TypeAnn::String | TypeAnn::Boolean) => self.gen_expr(e),
+                    Some(_) => self.gen_to_string(e),
+                },
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(format!("format!(\"{}\", {})", "{}".repeat(args.len()), args.join(", ")))
+    }
+
+    /// 是否按 `CodegenOptions::arith` 生成方法形式的整数运算：选项不是 `Plain`、number 是 i32、两个操作数都是整数。
+    fn is_int_arith(&self, left: &Expr, right: &Expr) -> bool {
+        self.options.arith != ArithMode::Plain
+            && !self.f64_mode()
+            && [left, right]
+                .iter()
+                .all(|e| self.expr_type(e) == Some(TypeAnn::Number) && !self.is_float_expr(e))
+    }
+
+    /// 整数运算的方法形式：`a.wrapping_add(b)`；`Checked` 时是 `a.checked_add(b)`，结果为 None 时抛出 `ArkError::Error`。
+    /// `method` 是 `add` / `sub` / `mul` / `div` / `rem` / `neg`（`neg` 没有右操作数）。
+    fn gen_int_arith(&self, left: String, method: &str, right: Option<String>) -> String {
+        let right = right.unwrap_or_default();
+        match self.options.arith {
+            ArithMode::Checked => {
+                let what = if matches!(method, "div" | "rem") { "division by zero or overflow" } else { "integer overflow" };
+                self.propagate_call(format!(
+                    "{left}.checked_{method}({right}).ok_or_else(|| ArkError::Error(String::from(\"{what}\")))"
+                ))
+            }
+            _ => format!("{left}.wrapping_{method}({right})"),
+        }
+    }
+
+    /// 把整数表达式转成 f64：字面量直接写成 `6.0`，其它写 `f64::from(x)`；f64 模式下不需要转换。
+    fn gen_as_f64(&mut self, e: &Expr) -> Result<String, Error> {
+        if self.f64_mode() {
+            return self.gen_expr(e);
+        }
+        match strip_group(e) {
+            Expr::Literal(Literal::Number(n), _) => Ok(format!("{n}.0")),
+            inner => Ok(format!("f64::from({})", self.gen_expr(inner)?)),
+        }
+    }
+}
+
+/// 写 `indent` 级缩进，每级 4 个空格；最后按 `CodegenOptions::layout` 换成配置的缩进（见 `layout::apply`）。
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("    ");
+    }
+}
+
+/// 源码注释生成的 Rust 行注释，一行一项（见 `Comment::lines`）。
+///
+/// 以 `/`、`!` 开头的内容前面加一个空格，不会变成 `///` / `//!` 文档注释。
+fn comment_lines(comment: &Comment) -> Vec<String> {
+    comment
+        .lines()
+        .into_iter()
+        .map(|line| match line {
+            "" => "//".to_string(),
+            l if comment.is_block() || l.starts_with(['/', '!']) => format!("// {l}"),
+            l => format!("//{l}"),
+        })
+        .collect()
+}
+
+/// JSDoc 注释生成的 `///` 文档注释行：
+/// - 描述原样保留
+/// - `@param {number} n - 说明` -> `# Arguments` 一节里的 ``* `n` - 说明``（类型、`-` 和可选参数的 `[..]` 去掉）
+/// - `@returns` / `@return` -> `# Returns` 一节
+/// - 其它标签（`@example` 等）原样放在最后
+///
+/// 标签后面不以 `@` 开头的行接在这个标签的说明后面。代码块标成 `text`，不会被当作 Rust 文档测试运行。
+fn doc_lines(comment: &Comment) -> Vec<String> {
+    enum Section {
+        Desc,
+        Param,
+        Returns,
+        Other,
+    }
+    let mut desc: Vec<String> = Vec::new();
+    let mut params: Vec<(String, String)> = Vec::new();
+    let mut returns: Vec<String> = Vec::new();
+    let mut others: Vec<String> = Vec::new();
+    let mut section = Section::Desc;
+    let mut in_code = false;
+    for line in comment.lines() {
+        let line = match line.strip_prefix("```") {
+            Some(lang) if !in_code && lang.trim() != "rust" => {
+                in_code = true;
+                "```text".to_string()
+            }
+            Some(_) => {
+                in_code = !in_code;
+                line.to_string()
+            }
+            None => line.to_string(),
+        };
+        let tag = if in_code && !line.starts_with("```") { None } else { line.strip_prefix('@') };
+        let Some(tag) = tag else {
+            match section {
+                Section::Desc => desc.push(line),
+                Section::Param => {
+                    if let Some((_, text)) = params.last_mut() {
+                        append_doc_text(text, &line);
+                    }
+                }
+                Section::Returns => returns.push(line),
+                Section::Other => others.push(line),
+            }
+            continue;
+        };
+        let (name, rest) = split_doc_word(tag);
+        match name {
+            "param" => {
+                let rest = skip_doc_type(rest);
+                let (param, text) = split_doc_word(rest);
+                let param = param.trim_start_matches('[').trim_end_matches(']');
+                let param = param.split('=').next().unwrap_or_default();
+                let text = text.strip_prefix("- ").unwrap_or(text);
+                params.push((param.to_string(), text.to_string()));
+                section = Section::Param;
+            }
+            "returns" | "return" => {
+                let text = skip_doc_type(rest);
+                if !text.is_empty() {
+                    returns.push(text.to_string());
+                }
+                section = Section::Returns;
+            }
+            _ => {
+                others.push(line);
+                section = Section::Other;
+            }
+        }
+    }
+
+    let mut lines = desc;
+    let start_section = |lines: &mut Vec<String>, title: Option<&str>| {
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        if let Some(title) = title {
+            lines.push(format!("# {title}"));
+            lines.push(String::new());
+        }
+    };
+    if !params.is_empty() {
+        start_section(&mut lines, Some("Arguments"));
+        for (param, text) in params {
+            lines.push(match text.is_empty() {
+                true => format!("* `{param}`"),
+                false => format!("* `{param}` - {text}"),
+            });
+        }
+    }
+    if !returns.is_empty() {
+        start_section(&mut lines, Some("Returns"));
+        lines.extend(returns);
+    }
+    if !others.is_empty() {
+        start_section(&mut lines, None);
+        lines.extend(others);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+        .into_iter()
+        .map(|l| if l.is_empty() { "///".to_string() } else { format!("/// {l}") })
+        .collect()
+}
+
+/// `@param n 说明` 的多行说明接起来。
+fn append_doc_text(text: &mut String, line: &str) {
+    let line = line.trim();
+    if !text.is_empty() && !line.is_empty() {
+        text.push(' ');
+    }
+    text.push_str(line);
+}
+
+/// 拆出第一个词：`"n - 说明"` -> `("n", "- 说明")`。
+fn split_doc_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+/// 去掉 JSDoc 标签里的类型：`{number} n` -> `n`。
+fn skip_doc_type(s: &str) -> &str {
+    let s = s.trim_start();
+    match s.strip_prefix('{').and_then(|rest| rest.split_once('}')) {
+        Some((_, rest)) => rest.trim_start(),
+        None => s,
+    }
+}
+
+/// 写一行缩进好的代码。
+fn push_line(out: &mut String, indent: usize, line: &str) {
+    push_indent(out, indent);
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// 要声明成 `mut` 的参数：函数体里会被赋值（或被修改字段/下标），而且不放进 `Cell`/`RefCell`（见 `cell_vars`）。
+fn mutable_params(f: &FuncDecl) -> BTreeSet<String> {
+    let cells = cell_vars(&f.body.stmts);
+    assigned_names(&f.body.stmts).into_iter().filter(|name| !cells.contains(name)).collect()
+}
+
+/// 函数体里会被赋值（或被修改字段/下标）的参数要声明成 `mut`。
+fn mut_prefix(assigned: &BTreeSet<String>, name: &str) -> String {
+    if assigned.contains(name) {
+        "mut ".to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// 参数的实际类型：省略标注时使用默认类型。
+pub(crate) fn param_type(p: &Param) -> TypeAnn {
+    match &p.ty {
+        Some(t) => t.clone(),
+        // 剩余参数省略类型时默认 `number[]`，与普通参数默认 `number` 保持一致。
+        None if p.is_rest => TypeAnn::Array(Box::new(TypeAnn::Number)),
+        None => TypeAnn::Number,
+    }
+}
+
+fn func_binding(f: &FuncDecl, is_closure: bool) -> Binding {
+    Binding::Func {
+        params: f.params.iter().map(param_type).collect(),
+        ret: effective_ret_type(f),
+        rest_idx: f.params.iter().position(|p| p.is_rest),
+        is_closure,
+    }
+}
+
+/// 参数位置的类型映射。
+///
+/// 与 `rust_type` 的唯一区别：回调参数使用 `&dyn Fn(...)`（不需要装箱），调用方借用着传进来：`apply(&double, 1)`
+/// （见 `gen_call_args`）。回调可以转发很多次（`apply(f, 1) + apply(f, 2)`），递归调用时也不会产生无限多的泛型实例。
+fn rust_param_type(t: &TypeAnn, num: NumberMode, strs: StringMode) -> String {
+    match t {
+        TypeAnn::Function(f) => format!("&dyn {}", rust_fn_trait(f, num, strs)),
+        TypeAnn::Promise(inner) => format!("impl {}", rust_future_trait(inner, num, strs)),
+        _ => rust_type(t, num, strs),
+    }
+}
+
+/// 把函数类型映射为 Rust 的 `Fn` trait 写法：`Fn(i32, i32) -> i32`。
+///
+/// 返回类型为 void 时省略 `-> ()`。
+fn rust_fn_trait(f: &FuncTypeAnn, num: NumberMode, strs: StringMode) -> String {
+    let params: Vec<String> = f.params.iter().map(|p| rust_type(p, num, strs)).collect();
+    match f.ret.as_ref() {
+        TypeAnn::Void => format!("Fn({})", params.join(", ")),
+        ret => format!("Fn({}) -> {}", params.join(", "), rust_type(ret, num, strs)),
+    }
+}
+
+/// 组件方法里的 `this` 对应 Rust 的 `self`，其它对象名不变。
+fn self_path(object: &str) -> &str {
+    if object == "this" {
+        "self"
+    } else {
+        object
+    }
+}
+
+/// 找出 `@Entry` 组件（最多一个，否则报 `DuplicateEntry`），返回 main 最后运行它的语句：
+/// 创建组件，再依次调用生命周期方法
+/// `aboutToAppear` -> `build` -> `onPageShow` -> `onPageHide` -> `aboutToDisappear`（只调用组件定义了的）。
+///
+/// 组件有状态字段（见 `StateKind`）时，`build()` 换成 `__ark_rerender`（见 `gen_component`），
+/// 并且在 `onPageShow` / `onPageHide` 之后各检查一次：状态变了就重新 build。
+///
+/// 配置了 UI 后端时，`build` 的实参和之前要执行的语句由后端决定（`UiBackend::entry_build`）；
+/// 后端不需要 main 调用 `build`（egui 等由事件循环驱动）时只调用其它生命周期方法。
+///
+/// main 用 `new()` 创建它，所以它的字段都要有初始值，否则报 `EntryFieldNeedsInitializer`。
+fn entry_component(components: &[ComponentDecl], backend: Option<&UiBackendRef>) -> Result<Vec<String>, Error> {
+    let mut entries = components.iter().filter(|c| c.has_decorator("Entry"));
+    let Some(entry) = entries.next() else {
+        return Ok(Vec::new());
+    };
+    if let Some(second) = entries.next() {
+        return Err(Error::new(ErrorCode::DuplicateEntry, second.span.0));
+    }
+    if let Some(field) = entry.fields.iter().find(|f| f.init.is_none()) {
+        return Err(Error::new(ErrorCode::EntryFieldNeedsInitializer, field.span.0));
+    }
+    // `build` 之前的语句和 `build` 的实参；None 表示不调用 build
+    let build = match backend {
+        _ if entry.build.is_none() => None,
+        Some(backend) => backend.entry_build(),
+        None => Some((Vec::new(), Vec::new())),
+    };
+    let has = |name: &str| match name {
+        "build" => build.is_some(),
+        _ => entry.methods.iter().any(|m| m.name == name && m.params.is_empty()),
+    };
+    let rerender = has("build") && entry.fields.iter().any(|f| !matches!(state_kind(f), Ok(None)));
+    let (setup, args) = build.clone().unwrap_or_default();
+    let rerender_call = format!(
+        "__entry.__ark_rerender(&mut __rendered{});",
+        args.iter().map(|arg| format!(", {arg}")).collect::<String>()
+    );
+    let mut lines = vec![format!("let mut __entry = {}::new();", entry.name)];
+    lines.extend(setup);
+    if rerender {
+        lines.push("let mut __rendered = None;".to_string());
+    }
+    for name in ["aboutToAppear", "build", "onPageShow", "onPageHide", "aboutToDisappear"] {
+        match name {
+            "build" if rerender => lines.push(rerender_call.clone()),
+            "build" if has(name) => lines.push(format!("__entry.build({});", args.join(", "))),
+            _ if has(name) => lines.push(format!("__entry.{name}();")),
+            _ => {}
+        }
+        if rerender && matches!(name, "onPageShow" | "onPageHide") && has(name) {
+            lines.push(rerender_call.clone());
+        }
+    }
+    Ok(lines)
+}
+
+/// 系统模块 import 生成的 `use`（内置处理的模块不生成）：
+///
+/// ```text
+/// use ohos_runtime::data::preferences;                // import preferences from '@ohos.data.preferences'
+/// use ohos_runtime::router as nav;                    // import * as nav from '@ohos.router'
+/// use ohos_runtime::file::fs::readText as read;       // import { readText as read } from '@ohos.file.fs'
+/// ```
+///
+/// 对这些名字的调用原样生成：`preferences.getPreferences(ctx)` -> `preferences::getPreferences(ctx)`。
+fn gen_system_uses(imports: &[SystemImport]) -> String {
+    let mut out = String::new();
+    for import in imports {
+        let Some(path) = &import.rust_path else {
+            continue;
+        };
+        match &import.clause {
+            ImportClause::Named(specs) => {
+                for spec in specs {
+                    match &spec.alias {
+                        Some(alias) => out.push_str(&format!("use {path}::{} as {alias};\n", spec.name)),
+                        None => out.push_str(&format!("use {path}::{};\n", spec.name)),
+                    }
+                }
+            }
+            ImportClause::Namespace(name) | ImportClause::Default(name) => {
+                if path.rsplit("::").next() == Some(name.as_str()) {
+                    out.push_str(&format!("use {path};\n"));
+                } else {
+                    out.push_str(&format!("use {path} as {name};\n"));
+                }
+            }
+        }
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// main 依次运行每个 Ability 类的生命周期方法（只调用定义了的）：
+/// `onCreate` -> `onWindowStageCreate` -> `onForeground` -> `onBackground` -> `onWindowStageDestroy` -> `onDestroy`。
+///
+/// 参数由运行时的 `Want::new()`、`LaunchParam::new()`、`WindowStage::new()` 创建。
+fn entry_ability(abilities: &[AbilityDecl]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for a in abilities {
+        let has = |name: &str| a.methods.iter().any(|m| m.name == name);
+        if !ABILITY_LIFECYCLE.iter().any(|name| has(name)) {
+            continue;
+        }
+        lines.push(format!("let mut __ability = {};", a.name));
+        for name in ABILITY_LIFECYCLE {
+            match name {
+                _ if !has(name) => {}
+                "onCreate" => lines.push("__ability.onCreate(&Want::new(), &LaunchParam::new());".to_string()),
+                "onWindowStageCreate" => {
+                    lines.push("let mut __stage = WindowStage::new();".to_string());
+                    lines.push("__ability.onWindowStageCreate(&mut __stage);".to_string());
+                }
+                _ => lines.push(format!("__ability.{name}();")),
+            }
+        }
+    }
+    lines
+}
+
+/// 生命周期方法在 `UIAbility` trait 里 `&mut self` 之后的参数类型（见 `runtime::ABILITY_HELPERS`）。
+fn lifecycle_params(name: &str) -> &'static [&'static str] {
+    match name {
+        "onCreate" => &["&Want", "&LaunchParam"],
+        "onWindowStageCreate" => &["&mut WindowStage"],
+        _ => &[],
+    }
+}
+
+/// 字段上的状态装饰器（见 `StateKind`）。同一个字段写了多个状态装饰器时报 `ConflictingStateDecorators`。
+///
+/// `@Provide('key')` / `@Consume('key')` 的参数必须是字符串字面量，否则报 `InvalidDecoratorArgs`。
+fn state_kind(field: &FieldDecl) -> Result<Option<StateKind>, Error> {
+    let mut kinds = Vec::new();
+    for d in &field.decorators {
+        let key = || match d.args.as_slice() {
+            [] => Ok(field.name.clone()),
+            [Expr::Literal(Literal::String(key), _)] => Ok(key.clone()),
+            _ => Err(Error::new(ErrorCode::InvalidDecoratorArgs, field.span.0)),
+        };
+        kinds.push(match d.name.as_str() {
+            "State" => StateKind::State,
+            "Prop" => StateKind::Prop,
+            "Link" => StateKind::Link,
+            "Provide" => StateKind::Provide(key()?),
+            "Consume" => StateKind::Consume(key()?),
+            _ => continue,
+        });
+    }
+    if kinds.len() > 1 {
+        return Err(Error::new(ErrorCode::ConflictingStateDecorators, field.span.0));
+    }
+    Ok(kinds.pop())
+}
+
+/// `build()` 里实际执行的部分：普通语句，以及 UI 条件（`if` 没有 else 时补一个空的 else）。
+/// UI 组件本身不生成代码，只展开它的子节点；箭头函数实参（事件处理）不执行。
+/// 组件写法的 `@Builder` 调用（`Header('x')`）变成普通的函数调用。
+fn lower_ui(nodes: &[UiNode], builders: &[BuilderDecl]) -> Vec<Stmt> {
+    let mut stmts = Vec::new();
+    for node in nodes {
+        match node {
+            UiNode::Element(e) if builders.iter().any(|b| b.name == e.name) => {
+                if let Some(call) = builder_element_call(e) {
+                    stmts.push(Stmt::ExprStmt(Expr::Call(call)));
+                }
+            }
+            UiNode::Element(e) => stmts.extend(lower_ui(&e.children, builders)),
+            UiNode::If(i) => {
+                // `else if` 保持为 else 分支里直接是 if 语句
+                let else_branch = match i.else_branch.as_slice() {
+                    [UiNode::If(_)] => lower_ui(&i.else_branch, builders).remove(0),
+                    nodes => Stmt::Block(BlockStmt { stmts: lower_ui(nodes, builders), span: NodeSpan::default() }),
+                };
+                stmts.push(Stmt::If(IfStmt {
+                    cond: i.cond.clone(),
+                    then_branch: Box::new(Stmt::Block(BlockStmt { stmts: lower_ui(&i.then_branch, builders), span: NodeSpan::default() })),
+                    else_branch: Some(Box::new(else_branch)),
+                    span: NodeSpan::default(),
+                }));
+            }
+            UiNode::Stmt(s) => stmts.push(s.clone()),
+        }
+    }
+    stmts
+}
+
+/// `@Builder` 对应的函数声明（函数体为空，只用到签名）。
+fn builder_fn(b: &BuilderDecl) -> FuncDecl {
+    FuncDecl {
+        is_export: b.is_export,
+        name: b.name.clone(),
+        params: b.params.clone(),
+        ret_type: None,
+        body: BlockStmt { stmts: Vec::new(), span: NodeSpan::default() },
+        decorators: Vec::new(),
+        span: NodeSpan::default(),
+    }
+}
+
+/// 组件写法的 `@Builder` 调用 `Header('x')` 对应的函数调用；实参不全是普通表达式或带子节点时为 None。
+fn builder_element_call(e: &UiElement) -> Option<CallExpr> {
+    if !e.children.is_empty() {
+        return None;
+    }
+    Some(CallExpr {
+        callee: Callee::Ident(e.name.clone()),
+        args: expr_args(&e.args)?,
+        span: NodeSpan::default(),
+    })
 }
 
-fn gen_assign(a: &AssignStmt) -> Result<String, Error> {
-    let value = gen_expr(&a.value)?;
-    Ok(format!("{} = {value};", a.name))
+/// UI 实参都是普通表达式时取出这些表达式，有对象字面量或箭头函数时为 None。
+fn expr_args(args: &[UiArg]) -> Option<Vec<Expr>> {
+    args.iter()
+        .map(|arg| match arg {
+            UiArg::Expr(expr) => Some(expr.clone()),
+            _ => None,
+        })
+        .collect()
 }
 
-fn gen_return(r: &ReturnStmt) -> Result<Vec<String>, Error> {
-    match &r.value {
-        None => Ok(vec!["return;".to_string()]),
-        Some(v) => {
-            let value = gen_expr(v)?;
-            Ok(vec![format!("let _ = {value};"), "return;".to_string()])
-        }
+/// `@Styles` / `@Extend` 对应的函数声明（函数体为空，只用到签名）。
+fn style_fn(s: &StyleDecl) -> FuncDecl {
+    FuncDecl {
+        is_export: s.is_export,
+        name: s.name.clone(),
+        params: s.params.clone(),
+        ret_type: None,
+        body: BlockStmt { stmts: Vec::new(), span: NodeSpan::default() },
+        decorators: Vec::new(),
+        span: NodeSpan::default(),
     }
 }
 
-fn gen_block_body(out: &mut String, indent: usize, ctx: ReturnCtx, stmt: &Stmt) -> Result<(), Error> {
-    match stmt {
-        Stmt::Block(b) => {
-            for s in &b.stmts {
-                gen_stmt_into(out, indent, ctx, s)?;
-            }
-            Ok(())
-        }
-        _ => gen_stmt_into(out, indent, ctx, stmt),
+/// 在生成好的调用 `f(a)` 末尾加上 UI 后端的实参（见 `UiBackend::build_params`）：`f(a, ui)`。
+fn with_ui_args(call: String, params: &[(String, String)]) -> String {
+    let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+    match call.strip_suffix(')') {
+        _ if names.is_empty() => call,
+        Some(head) if head.ends_with('(') => format!("{head}{})", names.join(", ")),
+        Some(head) => format!("{head}, {})", names.join(", ")),
+        None => call,
     }
 }
 
-fn gen_stmt_into(out: &mut String, indent: usize, ctx: ReturnCtx, stmt: &Stmt) -> Result<(), Error> {
-    match stmt {
-        Stmt::VarDecl(v) => {
-            push_indent(out, indent);
-            out.push_str(&gen_var_decl(v)?);
-            out.push('\n');
-            Ok(())
-        }
-        Stmt::Assign(a) => {
-            push_indent(out, indent);
-            out.push_str(&gen_assign(a)?);
-            out.push('\n');
-            Ok(())
-        }
-        Stmt::ExprStmt(e) => {
-            push_indent(out, indent);
-            out.push_str(&format!("{};", gen_expr(e)?));
-            out.push('\n');
-            Ok(())
-        }
-        Stmt::Return(r) => {
-            for line in gen_return_ctx(ctx, r)? {
-                push_indent(out, indent);
-                out.push_str(&line);
-                out.push('\n');
+/// 判断 if/while 条件是否是对可空变量（`is_nullable`）的判空：`x != null` / `x == null`。
+///
+/// 返回 `(变量名, 判空结果为 false 时才非空)`：
+/// - `x != null`：条件为真的分支里 x 非空，返回 `(x, true)`
+/// - `x == null`：条件为假的分支里 x 非空，返回 `(x, false)`
+///
+/// `truthy_conditions`（见 `CodegenOptions::truthy_conditions`）时 `if (x)` / `if (!x)` 也算判空（见 `gen_condition`）。
+/// 类型检查用同样的规则收窄（见 `typeck::check`）。
+pub(crate) fn null_check(cond: &Expr, truthy_conditions: bool, is_nullable: impl Fn(&str) -> bool) -> Option<(String, bool)> {
+    if truthy_conditions {
+        let (inner, non_null_when_true) = match strip_group(cond) {
+            Expr::Unary(u) if u.op == UnaryOp::Not => (strip_group(&u.expr), false),
+            other => (other, true),
+        };
+        if let Expr::Ident(name, _) = inner {
+            if is_nullable(name) {
+                return Some((name.clone(), non_null_when_true));
             }
-            Ok(())
-        }
-        Stmt::Block(b) => {
-            out.push_str(&gen_block_ctx(ctx, b, indent)?);
-            Ok(())
-        }
-        Stmt::If(i) => {
-            out.push_str(&gen_if_ctx(ctx, i, indent)?);
-            Ok(())
-        }
-        Stmt::While(w) => {
-            out.push_str(&gen_while_ctx(ctx, w, indent)?);
-            Ok(())
         }
     }
-}
-
-fn push_indent(out: &mut String, indent: usize) {
-    for _ in 0..indent {
-        out.push_str("    ");
+    let Expr::Binary(b) = strip_group(cond) else {
+        return None;
+    };
+    let non_null_when_true = match b.op {
+        BinaryOp::NotEq => true,
+        BinaryOp::EqEq => false,
+        _ => return None,
+    };
+    let other = if is_null_literal(&b.right) {
+        strip_group(&b.left)
+    } else if is_null_literal(&b.left) {
+        strip_group(&b.right)
+    } else {
+        return None;
+    };
+    match other {
+        Expr::Ident(name, _) if is_nullable(name) => Some((name.clone(), non_null_when_true)),
+        _ => None,
     }
 }
 
-#[derive(Clone, Copy)]
-enum ReturnCtx {
-    Main,
-    Function(TypeAnn),
+/// 去掉外层括号：`((x))` -> `x`
+pub(crate) fn strip_group(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Group(inner) => strip_group(inner),
+        _ => expr,
+    }
 }
 
-fn gen_return_ctx(ctx: ReturnCtx, r: &ReturnStmt) -> Result<Vec<String>, Error> {
-    match ctx {
-        ReturnCtx::Main => gen_return(r),
-        ReturnCtx::Function(ret) => match ret {
-            TypeAnn::Void => match &r.value {
-                None => Ok(vec!["return;".to_string()]),
-                Some(v) => {
-                    let value = gen_expr(v)?;
-                    Ok(vec![format!("let _ = {value};"), "return;".to_string()])
-                }
-            },
-            _ => match &r.value {
-                Some(v) => Ok(vec![format!("return {};", gen_expr(v)?)]),
-                None => Err(Error::new("ReturnValueRequired", Span::default())),
-            },
-        },
+/// 读取一个位置表达式的值：不是 Copy 类型（number/boolean）时加 `.clone()`，避免把值移走。
+fn read_place(place: String, ty: Option<&TypeAnn>) -> String {
+    match is_copy_type(ty) {
+        true => place,
+        false => format!("{place}.clone()"),
     }
 }
 
-fn gen_block_ctx(ctx: ReturnCtx, b: &BlockStmt, indent: usize) -> Result<String, Error> {
-    let mut out = String::new();
-    push_indent(&mut out, indent);
-    out.push_str("{\n");
-    for s in &b.stmts {
-        gen_stmt_into(&mut out, indent + 1, ctx, s)?;
+/// 生成的 Rust 类型是不是 Copy：number、boolean 以及它们的可空类型；类型未知时当作不是。
+fn is_copy_type(ty: Option<&TypeAnn>) -> bool {
+    let is_copy = |t: &TypeAnn| matches!(t, TypeAnn::Number | TypeAnn::Boolean);
+    match ty {
+        Some(TypeAnn::Nullable(inner, _)) => is_copy(inner),
+        Some(t) => is_copy(t),
+        None => false,
     }
-    push_indent(&mut out, indent);
-    out.push_str("}\n");
-    Ok(out)
 }
 
-fn gen_if_ctx(ctx: ReturnCtx, stmt: &IfStmt, indent: usize) -> Result<String, Error> {
-    let cond = gen_expr(&stmt.cond)?;
+pub(crate) fn is_null_literal(expr: &Expr) -> bool {
+    matches!(
+        strip_group(expr),
+        Expr::Literal(Literal::Null, _) | Expr::Literal(Literal::Undefined, _)
+    )
+}
 
-    let mut out = String::new();
-    push_indent(&mut out, indent);
-    out.push_str("if ");
-    out.push_str(&cond);
-    out.push_str(" {\n");
-    gen_block_body(&mut out, indent + 1, ctx, &stmt.then_branch)?;
-    push_indent(&mut out, indent);
-    out.push('}');
-
-    if let Some(else_branch) = &stmt.else_branch {
-        out.push_str(" else {\n");
-        gen_block_body(&mut out, indent + 1, ctx, else_branch)?;
-        push_indent(&mut out, indent);
-        out.push_str("}\n");
+fn gen_vec_literal(items: &[String]) -> String {
+    if items.is_empty() {
+        "Vec::new()".to_string()
     } else {
-        out.push('\n');
+        format!("vec![{}]", items.join(", "))
     }
-    Ok(out)
 }
 
-fn gen_while_ctx(ctx: ReturnCtx, stmt: &WhileStmt, indent: usize) -> Result<String, Error> {
-    let cond = gen_expr(&stmt.cond)?;
-
-    let mut out = String::new();
-    push_indent(&mut out, indent);
-    out.push_str("while ");
-    out.push_str(&cond);
-    out.push_str(" {\n");
-    gen_block_body(&mut out, indent + 1, ctx, &stmt.body)?;
-    push_indent(&mut out, indent);
-    out.push_str("}\n");
-    Ok(out)
-}
-
-fn gen_func_decl(f: &FuncDecl) -> Result<String, Error> {
-    let ret = effective_ret_type(f);
-    let mut params = Vec::new();
-    for p in &f.params {
-        params.push(gen_param(p));
+/// 类型里有没有 `Date`（生成的代码里是 `ArkDate`）。
+fn mentions_date(t: &TypeAnn) -> bool {
+    match t {
+        TypeAnn::Date => true,
+        TypeAnn::Array(inner) | TypeAnn::Nullable(inner, _) | TypeAnn::Promise(inner) => mentions_date(inner),
+        TypeAnn::Function(f) => f.params.iter().any(mentions_date) || mentions_date(&f.ret),
+        _ => false,
     }
+}
 
-    let mut out = String::new();
-    out.push_str("fn ");
-    out.push_str(&f.name);
-    out.push('(');
-    out.push_str(&params.join(", "));
-    out.push(')');
-    if ret != TypeAnn::Void {
-        out.push_str(" -> ");
-        out.push_str(&rust_type(ret));
-    }
-    out.push_str(" {\n");
-    for s in &f.body.stmts {
-        gen_stmt_into(&mut out, 1, ReturnCtx::Function(ret), s)?;
+fn rust_type(t: &TypeAnn, num: NumberMode, strs: StringMode) -> String {
+    match t {
+        TypeAnn::Number => num.rust_type().to_string(),
+        TypeAnn::String => strs.rust_type().to_string(),
+        TypeAnn::Boolean => "bool".to_string(),
+        TypeAnn::Void => "()".to_string(),
+        // 类型别名，定义见 `with_helpers`
+        TypeAnn::Date => "ArkDate".to_string(),
+        TypeAnn::Array(elem) => format!("Vec<{}>", rust_type(elem, num, strs)),
+        // `impl Trait` 不能出现在返回值以外的嵌套位置（例如 Vec 元素、Fn 参数），
+        // 所以非参数位置统一装箱成 trait object。
+        TypeAnn::Function(f) => format!("Box<dyn {}>", rust_fn_trait(f, num, strs)),
+        TypeAnn::Nullable(inner, _) => format!("Option<{}>", rust_type(inner, num, strs)),
+        // 与函数类型同理：`impl Future` 只能放在参数位置，其它位置装箱并 pin 住。
+        TypeAnn::Promise(inner) => format!("std::pin::Pin<Box<dyn {}>>", rust_future_trait(inner, num, strs)),
     }
-    out.push_str("}\n");
-    Ok(out)
 }
 
-fn gen_param(p: &Param) -> String {
-    let ty = p.ty.unwrap_or(TypeAnn::Number);
-    format!("{}: {}", p.name, rust_type(ty))
+/// `Promise<T>` 对应的 `Future` trait 写法：`Future<Output = Result<T, ArkError>>`。
+fn rust_future_trait(inner: &TypeAnn, num: NumberMode, strs: StringMode) -> String {
+    format!("std::future::Future<Output = Result<{}, ArkError>>", rust_type(inner, num, strs))
 }
 
-fn rust_type(t: TypeAnn) -> String {
+/// napi 能在 JS 值和 Rust 值之间自动转换的类型（`void` 只能作返回值），见 `gen_napi_exports`。
+fn is_napi_type(t: &TypeAnn, is_ret: bool) -> bool {
     match t {
-        TypeAnn::Number => "i32".to_string(),
-        TypeAnn::String => "String".to_string(),
-        TypeAnn::Boolean => "bool".to_string(),
-        TypeAnn::Void => "()".to_string(),
+        TypeAnn::Number | TypeAnn::String | TypeAnn::Boolean => true,
+        TypeAnn::Void => is_ret,
+        TypeAnn::Array(inner) | TypeAnn::Nullable(inner, _) => is_napi_type(inner, false),
+        TypeAnn::Date | TypeAnn::Function(_) | TypeAnn::Promise(_) => false,
     }
 }
 
-fn effective_ret_type(f: &FuncDecl) -> TypeAnn {
-    match f.ret_type {
-        Some(t) => t,
+pub(crate) fn effective_ret_type(f: &FuncDecl) -> TypeAnn {
+    match &f.ret_type {
+        Some(t) => t.clone(),
         None => {
             if func_body_has_return_value(&f.body) {
                 TypeAnn::Number
@@ -298,87 +6410,702 @@ fn stmt_has_return_value(s: &Stmt) -> bool {
     }
 }
 
-/// 生成函数调用表达式。
+/// 语句里有没有 `return name;`（不进入嵌套函数）。
+fn stmt_returns_name(s: &Stmt, name: &str) -> bool {
+    let any = |stmts: &[Stmt]| stmts.iter().any(|s| stmt_returns_name(s, name));
+    match s {
+        Stmt::Return(r) => matches!(r.value.as_ref().map(strip_group), Some(Expr::Ident(n, _)) if n == name),
+        Stmt::Block(b) => any(&b.stmts),
+        Stmt::If(i) => {
+            stmt_returns_name(&i.then_branch, name)
+                || i.else_branch.as_ref().is_some_and(|b| stmt_returns_name(b, name))
+        }
+        Stmt::While(w) => stmt_returns_name(&w.body, name),
+        Stmt::Try(t) => {
+            any(&t.block.stmts)
+                || t.catch.as_ref().is_some_and(|c| any(&c.body.stmts))
+                || t.finally.as_ref().is_some_and(|f| any(&f.stmts))
+        }
+        _ => false,
+    }
+}
+
+/// 函数的自由变量：函数体（包括其内部的嵌套函数）用到、但不是在函数内部声明的名字。
 ///
-/// 映射规则：
-/// - `console.log(e)` -> `println!("{:?}", e)`
+/// 这里不区分块作用域，只要函数内部任何地方声明过就算“内部的”，足够用来判断是否捕获。
+pub(crate) fn free_vars(f: &FuncDecl) -> BTreeSet<String> {
+    let mut used = BTreeSet::new();
+    for s in &f.body.stmts {
+        collect_used_stmt(s, &mut used);
+    }
+    let declared = declared_names(f);
+    used.retain(|name| !declared.contains(name));
+    used
+}
+
+/// 函数内部声明的所有名字：参数、变量、嵌套函数（以及它们的参数/变量）。
+fn declared_names(f: &FuncDecl) -> BTreeSet<String> {
+    let mut out: BTreeSet<String> = f.params.iter().map(|p| p.name.clone()).collect();
+    for s in &f.body.stmts {
+        collect_declared_stmt(s, &mut out);
+    }
+    out
+}
+
+/// try 语句里的所有块：try 块、catch 块、finally 块。
+fn try_blocks(t: &TryStmt) -> Vec<&BlockStmt> {
+    let mut blocks = vec![&t.block];
+    blocks.extend(t.catch.as_ref().map(|c| &c.body));
+    blocks.extend(t.finally.as_ref());
+    blocks
+}
+
+/// catch 子句是否接住所有异常：没有标注异常类，或标注的就是 `Error`。
+fn catches_all(c: &CatchClause) -> bool {
+    c.ty.as_deref().is_none_or(|ty| ty == "Error")
+}
+
+/// 语句里是否有 return（不进入块内函数）。
+fn contains_return(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|s| match s {
+        Stmt::Return(_) => true,
+        Stmt::Block(b) => contains_return(&b.stmts),
+        Stmt::If(i) => {
+            contains_return(std::slice::from_ref(&i.then_branch))
+                || i.else_branch
+                    .as_deref()
+                    .is_some_and(|e| contains_return(std::slice::from_ref(e)))
+        }
+        Stmt::While(w) => contains_return(std::slice::from_ref(&w.body)),
+        Stmt::Try(t) => try_blocks(t).iter().any(|b| contains_return(&b.stmts)),
+        Stmt::VarDecl(_)
+        | Stmt::Assign(_)
+        | Stmt::ExprStmt(_)
+        | Stmt::FuncDecl(_)
+        | Stmt::Throw(_) => false,
+    })
+}
+
+/// 计算哪些函数会把异常抛出函数体（包括块内函数）。
 ///
-/// 目前约束：只允许 1 个参数。
-fn gen_call(call: &CallExpr) -> Result<String, Error> {
-    match call.callee {
-        Callee::ConsoleLog => {
-            if call.args.len() != 1 {
-                // AST 理论上不会出现这个情况（Step2 parser 固定生成一个参数）。
-                // 这里的分支属于“防御式编程”：即使未来 AST 扩展，错误也能被捕获。
-                return Err(Error::new("UnsupportedAst", Span::default()));
+/// 函数体里有未被 catch 的 throw，或调用了这样的函数，它自己也会抛出；
+/// 所以反复扫描直到集合不再变化。
+fn throwing_funcs(program: &Program, builtins: &[&str]) -> BTreeSet<String> {
+    fn collect<'a>(stmts: &'a [Stmt], out: &mut Vec<&'a FuncDecl>) {
+        for s in stmts {
+            match s {
+                Stmt::FuncDecl(f) => {
+                    out.push(f);
+                    collect(&f.body.stmts, out);
+                }
+                Stmt::Block(b) => collect(&b.stmts, out),
+                Stmt::If(i) => {
+                    collect(std::slice::from_ref(&i.then_branch), out);
+                    if let Some(e) = &i.else_branch {
+                        collect(std::slice::from_ref(e), out);
+                    }
+                }
+                Stmt::While(w) => collect(std::slice::from_ref(&w.body), out),
+                Stmt::Try(t) => {
+                    for b in try_blocks(t) {
+                        collect(&b.stmts, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut funcs: Vec<&FuncDecl> = Vec::new();
+    for f in &program.funcs {
+        funcs.push(f);
+        collect(&f.body.stmts, &mut funcs);
+    }
+    collect(&program.stmts, &mut funcs);
+
+    let links = link_targets(program);
+    // 会抛异常的内置函数（被同名的用户函数遮蔽时除外）
+    let mut throwing: BTreeSet<String> = builtins
+        .iter()
+        .filter(|b| !funcs.iter().copied().chain(&program.declare_funcs).any(|f| f.name == **b))
+        .map(|b| b.to_string())
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for f in &funcs {
+            if !throwing.contains(&f.name) && stmts_throw(&f.body.stmts, &throwing) {
+                throwing.insert(f.name.clone());
+                changed = true;
+            }
+        }
+        // namespace 里的函数以 `Utils.f` 记录；函数体里直接写 `f()` 指的是同一 namespace 里的 f
+        for ns in &program.namespace_decls {
+            let mut ns_funcs: Vec<&FuncDecl> = Vec::new();
+            for f in &ns.funcs {
+                collect(&f.body.stmts, &mut ns_funcs);
+            }
+            let view = namespace_view(ns, &throwing);
+            for f in &ns.funcs {
+                let key = member_key(&ns.name, &f.name);
+                if !throwing.contains(&key) && stmts_throw(&f.body.stmts, &view) {
+                    throwing.insert(key);
+                    changed = true;
+                }
+            }
+            for f in ns_funcs {
+                if !throwing.contains(&f.name) && stmts_throw(&f.body.stmts, &view) {
+                    throwing.insert(f.name.clone());
+                    changed = true;
+                }
             }
-            let arg = gen_expr(&call.args[0])?;
-            Ok(format!("println!(\"{{:?}}\", {arg})"))
         }
-        Callee::Ident(ref name) => {
-            let mut args = Vec::new();
-            for a in &call.args {
-                args.push(gen_expr(a)?);
+        // 通过别名/命名空间调用会抛异常的函数，同样会抛异常
+        for (key, target) in &links {
+            if throwing.contains(target) && !throwing.contains(key) {
+                throwing.insert(key.clone());
+                changed = true;
             }
-            Ok(format!("{name}({})", args.join(", ")))
         }
     }
+    throwing
 }
 
-fn gen_expr_bp(expr: &Expr, parent_bp: u8) -> Result<String, Error> {
-    // 这里用“表达式绑定强度（bp）”来决定是否加括号：
-    // - 子表达式 bp < 父表达式 bp 时，必须加括号，避免 Rust 按自己的优先级重排。
-    // - bp 数值越大，优先级越高（绑定越紧）。
-    let (s, bp) = match expr {
-        Expr::Literal(lit) => (gen_literal_expr(lit), 100),
-        Expr::Ident(name) => (name.clone(), 100),
-        Expr::Group(inner) => (format!("({})", gen_expr_bp(inner, 0)?), 100),
-        Expr::Call(call) => (gen_call(call)?, 90),
-        Expr::Unary(u) => (gen_unary(u)?, 80),
-        Expr::Binary(b) => (gen_binary(b)?, binary_bp(b.op)),
-    };
+/// `throwing_funcs` 的内置名字里有它时，整数运算会抛异常（`ArithMode::Checked`，见 `arith_throws`）。
+/// 不是合法的标识符，不会和函数名冲突。
+const CHECKED_ARITH: &str = "<checked arithmetic>";
+
+/// `ArithMode::Checked` 时可能抛异常的运算。这里不知道类型，`+` 只排除一边是字符串字面量的拼接，
+/// 所以只做字符串拼接的函数也可能被当作会抛异常。
+fn arith_throws(e: &Expr, throwing: &BTreeSet<String>) -> bool {
+    if !throwing.contains(CHECKED_ARITH) {
+        return false;
+    }
+    let is_str = |e: &Expr| matches!(strip_group(e), Expr::Literal(Literal::String(_), _));
+    match e {
+        Expr::Binary(b) => match b.op {
+            BinaryOp::Add => !is_str(&b.left) && !is_str(&b.right),
+            BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => true,
+            _ => false,
+        },
+        Expr::Unary(u) => u.op == UnaryOp::Neg && !matches!(*u.expr, Expr::Literal(_, _)),
+        _ => false,
+    }
+}
 
-    if bp < parent_bp {
-        Ok(format!("({s})"))
+/// namespace 内部看到的“会抛异常的函数”：同名的兄弟函数遮蔽外层的同名函数。
+fn namespace_view(ns: &NamespaceDecl, throwing: &BTreeSet<String>) -> BTreeSet<String> {
+    let mut view = throwing.clone();
+    for f in &ns.funcs {
+        if throwing.contains(&member_key(&ns.name, &f.name)) {
+            view.insert(f.name.clone());
+        } else {
+            view.remove(&f.name);
+        }
+    }
+    view
+}
+
+/// 模块路径对应的文件：`["common", "log"]` -> `common/log.rs`，有子模块时 `common/log/mod.rs`。
+fn module_file(path: &[String], has_children: bool) -> PathBuf {
+    let mut file: PathBuf = path.iter().collect();
+    if has_children {
+        file.push("mod.rs");
     } else {
-        Ok(s)
+        file.set_extension("rs");
     }
+    file
 }
 
-fn gen_unary(u: &UnaryExpr) -> Result<String, Error> {
-    let op = match u.op {
-        UnaryOp::Not => "!",
-        UnaryOp::Neg => "-",
-    };
-    let rhs = gen_expr_bp(&u.expr, 80)?;
-    Ok(format!("{op}{rhs}"))
-}
-
-fn gen_binary(b: &BinaryExpr) -> Result<String, Error> {
-    let op = match b.op {
-        BinaryOp::Add => "+",
-        BinaryOp::Sub => "-",
-        BinaryOp::Mul => "*",
-        BinaryOp::Div => "/",
-        BinaryOp::Mod => "%",
-        BinaryOp::EqEq => "==",
-        BinaryOp::NotEq => "!=",
-        BinaryOp::Lt => "<",
-        BinaryOp::LtEq => "<=",
-        BinaryOp::Gt => ">",
-        BinaryOp::GtEq => ">=",
-        BinaryOp::AndAnd => "&&",
-        BinaryOp::OrOr => "||",
+/// `MathUtils` -> `math_utils`：Rust 模块名习惯用 snake_case（见 `rename::snake_case`），全大写的 `UI` 也转成小写。
+fn snake_case(name: &str) -> String {
+    rename::snake_case(name).to_ascii_lowercase()
+}
+
+/// 别名与命名空间成员：`(作用域里的名字, 指向的顶层函数)`，命名空间成员的名字见 `member_key`。
+fn link_targets(program: &Program) -> Vec<(String, String)> {
+    let aliases = program.aliases.iter().map(|a| (a.name.clone(), a.target.clone()));
+    let members = program.namespaces.iter().flat_map(|ns| {
+        ns.members
+            .iter()
+            .map(|m| (member_key(&ns.name, &m.name), m.target.clone()))
+    });
+    aliases.chain(members).collect()
+}
+
+/// 这组语句是否可能让异常逃出去（没有被其中的 try/catch 接住）。
+fn stmts_throw(stmts: &[Stmt], throwing: &BTreeSet<String>) -> bool {
+    stmts.iter().any(|s| stmt_throws(s, throwing))
+}
+
+fn stmt_throws(s: &Stmt, throwing: &BTreeSet<String>) -> bool {
+    match s {
+        Stmt::Throw(_) => true,
+        Stmt::VarDecl(v) => v.decls.iter().filter_map(|d| d.init.as_ref()).any(|e| expr_throws(e, throwing)),
+        Stmt::Assign(a) => expr_throws(&a.value, throwing),
+        Stmt::ExprStmt(e) => expr_throws(e, throwing),
+        Stmt::Return(r) => r.value.as_ref().is_some_and(|v| expr_throws(v, throwing)),
+        Stmt::Block(b) => stmts_throw(&b.stmts, throwing),
+        Stmt::If(i) => {
+            expr_throws(&i.cond, throwing)
+                || stmt_throws(&i.then_branch, throwing)
+                || i.else_branch.as_deref().is_some_and(|e| stmt_throws(e, throwing))
+        }
+        Stmt::While(w) => expr_throws(&w.cond, throwing) || stmt_throws(&w.body, throwing),
+        // 函数声明本身不执行函数体。
+        Stmt::FuncDecl(_) => false,
+        Stmt::Try(t) => {
+            let escapes = match &t.catch {
+                Some(c) if catches_all(c) => stmts_throw(&c.body.stmts, throwing),
+                Some(c) => stmts_throw(&t.block.stmts, throwing) || stmts_throw(&c.body.stmts, throwing),
+                None => stmts_throw(&t.block.stmts, throwing),
+            };
+            escapes || t.finally.as_ref().is_some_and(|f| stmts_throw(&f.stmts, throwing))
+        }
+    }
+}
+
+fn expr_throws(e: &Expr, throwing: &BTreeSet<String>) -> bool {
+    match e {
+        Expr::Call(call) => {
+            let callback_throws = || match (&call.callee, call.args.first()) {
+                (Callee::Ident(name), Some(Expr::Ident(cb, _))) => {
+                    is_timer_builtin(name) && throwing.contains(cb)
+                }
+                _ => false,
+            };
+            let callee_throws = match &call.callee {
+                Callee::Ident(name) => throwing.contains(name),
+                // `JSON.parse` 解析失败时抛异常
+                Callee::Member { object, property } => {
+                    throwing.contains(&member_key(object, property)) || (object == "JSON" && property == "parse")
+                }
+                Callee::Expr(inner) | Callee::Method { object: inner, .. } => expr_throws(inner, throwing),
+                Callee::Console(_) | Callee::Promise(_) => false,
+            };
+            callee_throws
+                || callback_throws()
+                || call.args.iter().any(|a| expr_throws(a, throwing))
+        }
+        Expr::Group(inner) | Expr::Spread(inner) => expr_throws(inner, throwing),
+        Expr::Member(m) => expr_throws(&m.object, throwing),
+        Expr::Index(ix) => expr_throws(&ix.object, throwing) || expr_throws(&ix.index, throwing),
+        Expr::Unary(u) => arith_throws(e, throwing) || expr_throws(&u.expr, throwing),
+        Expr::Binary(b) => arith_throws(e, throwing) || expr_throws(&b.left, throwing) || expr_throws(&b.right, throwing),
+        Expr::New(n) => n.args.iter().any(|a| expr_throws(a, throwing)),
+        Expr::Assign(a) => {
+            let index_throws = match &a.target {
+                AssignTarget::Index { index, .. } => expr_throws(index, throwing),
+                AssignTarget::Ident(_) | AssignTarget::Member { .. } => false,
+            };
+            index_throws || expr_throws(&a.value, throwing)
+        }
+        Expr::Literal(_, _) | Expr::Ident(_, _) => false,
+    }
+}
+
+fn collect_declared_stmt(s: &Stmt, out: &mut BTreeSet<String>) {
+    match s {
+        Stmt::VarDecl(v) => {
+            out.extend(v.names().into_iter().map(String::from));
+        }
+        Stmt::FuncDecl(f) => {
+            out.insert(f.name.clone());
+            out.extend(declared_names(f));
+        }
+        Stmt::Block(b) => b.stmts.iter().for_each(|s| collect_declared_stmt(s, out)),
+        Stmt::If(i) => {
+            collect_declared_stmt(&i.then_branch, out);
+            if let Some(e) = &i.else_branch {
+                collect_declared_stmt(e, out);
+            }
+        }
+        Stmt::While(w) => collect_declared_stmt(&w.body, out),
+        Stmt::Try(t) => {
+            if let Some(name) = t.catch.as_ref().and_then(|c| c.param.as_ref()) {
+                out.insert(name.clone());
+            }
+            for b in try_blocks(t) {
+                b.stmts.iter().for_each(|s| collect_declared_stmt(s, out));
+            }
+        }
+        Stmt::Assign(_) | Stmt::ExprStmt(_) | Stmt::Return(_) | Stmt::Throw(_) => {}
+    }
+}
+
+fn collect_used_stmt(s: &Stmt, out: &mut BTreeSet<String>) {
+    match s {
+        Stmt::VarDecl(v) => v.decls.iter().filter_map(|d| d.init.as_ref()).for_each(|e| collect_used_expr(e, out)),
+        Stmt::Assign(a) => {
+            out.insert(a.name.clone());
+            collect_used_expr(&a.value, out);
+        }
+        Stmt::ExprStmt(e) => collect_used_expr(e, out),
+        Stmt::Block(b) => b.stmts.iter().for_each(|s| collect_used_stmt(s, out)),
+        Stmt::If(i) => {
+            collect_used_expr(&i.cond, out);
+            collect_used_stmt(&i.then_branch, out);
+            if let Some(e) = &i.else_branch {
+                collect_used_stmt(e, out);
+            }
+        }
+        Stmt::While(w) => {
+            collect_used_expr(&w.cond, out);
+            collect_used_stmt(&w.body, out);
+        }
+        Stmt::Return(r) => {
+            if let Some(v) = &r.value {
+                collect_used_expr(v, out);
+            }
+        }
+        Stmt::FuncDecl(f) => f.body.stmts.iter().for_each(|s| collect_used_stmt(s, out)),
+        Stmt::Throw(t) => collect_used_expr(&t.value, out),
+        Stmt::Try(t) => {
+            for b in try_blocks(t) {
+                b.stmts.iter().for_each(|s| collect_used_stmt(s, out));
+            }
+        }
+    }
+}
+
+fn collect_used_expr(e: &Expr, out: &mut BTreeSet<String>) {
+    match e {
+        Expr::Literal(_, _) => {}
+        Expr::Ident(name, _) => {
+            out.insert(name.clone());
+        }
+        Expr::Unary(u) => collect_used_expr(&u.expr, out),
+        Expr::Binary(b) => {
+            collect_used_expr(&b.left, out);
+            collect_used_expr(&b.right, out);
+        }
+        Expr::Group(inner) | Expr::Spread(inner) => collect_used_expr(inner, out),
+        Expr::Member(m) => collect_used_expr(&m.object, out),
+        Expr::Index(ix) => {
+            collect_used_expr(&ix.object, out);
+            collect_used_expr(&ix.index, out);
+        }
+        Expr::Call(c) => {
+            match &c.callee {
+                Callee::Ident(name) | Callee::Member { object: name, .. } => {
+                    out.insert(name.clone());
+                }
+                Callee::Expr(inner) | Callee::Method { object: inner, .. } => collect_used_expr(inner, out),
+                Callee::Console(_) | Callee::Promise(_) => {}
+            }
+            c.args.iter().for_each(|a| collect_used_expr(a, out));
+        }
+        Expr::New(n) => n.args.iter().for_each(|a| collect_used_expr(a, out)),
+        Expr::Assign(a) => {
+            match &a.target {
+                AssignTarget::Ident(name) | AssignTarget::Member { object: name, .. } => {
+                    out.insert(name.clone());
+                }
+                AssignTarget::Index { object, index } => {
+                    out.insert(object.clone());
+                    collect_used_expr(index, out);
+                }
+            }
+            collect_used_expr(&a.value, out);
+        }
+    }
+}
+
+/// 要声明成 `Cell`/`RefCell` 的名字：被块内函数捕获（这些函数生成闭包，见 `gen_stmts`），又在这组语句里被赋值。
+///
+/// 闭包一直借用着捕获的变量，闭包还要再调用时在外面读写这个变量，rustc 会报借用冲突（E0502/E0506）。
+/// 放进 `Cell`/`RefCell` 以后闭包只需要共享借用，读写都通过 `get`/`set`（`borrow`/`borrow_mut`）。
+fn cell_vars(stmts: &[Stmt]) -> BTreeSet<String> {
+    fn captured(s: &Stmt, out: &mut BTreeSet<String>) {
+        match s {
+            Stmt::FuncDecl(f) => out.extend(free_vars(f)),
+            Stmt::Block(b) => b.stmts.iter().for_each(|s| captured(s, out)),
+            Stmt::If(i) => {
+                captured(&i.then_branch, out);
+                if let Some(e) = &i.else_branch {
+                    captured(e, out);
+                }
+            }
+            Stmt::While(w) => captured(&w.body, out),
+            Stmt::Try(t) => {
+                for b in try_blocks(t) {
+                    b.stmts.iter().for_each(|s| captured(s, out));
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut out = BTreeSet::new();
+    stmts.iter().for_each(|s| captured(s, &mut out));
+    let assigned = assigned_names(stmts);
+    out.retain(|name| assigned.contains(name));
+    out
+}
+
+/// 一组语句里被赋值的名字（包括嵌套函数里的赋值）。
+///
+/// 给字段/下标赋值（`obj.x = 1`、`arr[i] = 2`）也算修改了 `obj`/`arr`。
+fn assigned_names(stmts: &[Stmt]) -> BTreeSet<String> {
+    fn walk(s: &Stmt, out: &mut BTreeSet<String>) {
+        match s {
+            Stmt::Assign(a) => {
+                out.insert(a.name.clone());
+                walk_expr(&a.value, out);
+            }
+            Stmt::VarDecl(v) => v.decls.iter().filter_map(|d| d.init.as_ref()).for_each(|e| walk_expr(e, out)),
+            Stmt::ExprStmt(e) => walk_expr(e, out),
+            Stmt::Return(r) => r.value.iter().for_each(|v| walk_expr(v, out)),
+            Stmt::Throw(t) => walk_expr(&t.value, out),
+            Stmt::Block(b) => b.stmts.iter().for_each(|s| walk(s, out)),
+            Stmt::If(i) => {
+                walk_expr(&i.cond, out);
+                walk(&i.then_branch, out);
+                if let Some(e) = &i.else_branch {
+                    walk(e, out);
+                }
+            }
+            Stmt::While(w) => {
+                walk_expr(&w.cond, out);
+                walk(&w.body, out);
+            }
+            Stmt::FuncDecl(f) => f.body.stmts.iter().for_each(|s| walk(s, out)),
+            Stmt::Try(t) => {
+                for b in try_blocks(t) {
+                    b.stmts.iter().for_each(|s| walk(s, out));
+                }
+            }
+        }
+    }
+    fn walk_expr(e: &Expr, out: &mut BTreeSet<String>) {
+        match e {
+            Expr::Assign(a) => {
+                match &a.target {
+                    AssignTarget::Ident(name) | AssignTarget::Member { object: name, .. } => {
+                        out.insert(name.clone());
+                    }
+                    AssignTarget::Index { object, index } => {
+                        out.insert(object.clone());
+                        walk_expr(index, out);
+                    }
+                }
+                walk_expr(&a.value, out);
+            }
+            Expr::Unary(u) => walk_expr(&u.expr, out),
+            Expr::Binary(b) => {
+                walk_expr(&b.left, out);
+                walk_expr(&b.right, out);
+            }
+            Expr::Group(inner) | Expr::Spread(inner) => walk_expr(inner, out),
+            Expr::Member(m) => walk_expr(&m.object, out),
+            Expr::Index(ix) => {
+                walk_expr(&ix.object, out);
+                walk_expr(&ix.index, out);
+            }
+            Expr::Call(c) => {
+                if let Callee::Expr(inner) | Callee::Method { object: inner, .. } = &c.callee {
+                    walk_expr(inner, out);
+                }
+                c.args.iter().for_each(|a| walk_expr(a, out));
+            }
+            Expr::New(n) => n.args.iter().for_each(|a| walk_expr(a, out)),
+            Expr::Literal(_, _) | Expr::Ident(_, _) => {}
+        }
+    }
+    let mut out = BTreeSet::new();
+    stmts.iter().for_each(|s| walk(s, &mut out));
+    out
+}
+
+/// 一个函数体里要声明成 `let mut` 的变量名（见 `gen_var_decl`）。
+///
+/// 有初始值的变量：函数体里给它赋值（或修改字段/下标）就需要 `mut`，见 `assigned_names`。
+/// 没有初始值的变量：第一次赋值只是初始化，从声明往后在某条执行路径上可能赋值两次以上才需要，
+/// 见 `assign_count`。
+///
+/// 和 `assigned_names` 一样按名字判断：同一个函数里同名的变量只要有一个需要，就都加上 `mut`
+/// （多余的 `mut` 只会让 rustc 给出警告）。嵌套函数在生成时单独计算。
+fn mutable_names(stmts: &[Stmt]) -> BTreeSet<String> {
+    fn walk(stmts: &[Stmt], once: &mut BTreeSet<String>, again: &mut BTreeSet<String>) {
+        for (i, s) in stmts.iter().enumerate() {
+            match s {
+                Stmt::VarDecl(v) => {
+                    for d in &v.decls {
+                        match (&d.init, &d.pattern) {
+                            (None, Pattern::Ident(name)) if assign_count(&stmts[i..], name) < 2 => {
+                                once.insert(name.clone());
+                            }
+                            (_, pattern) => again.extend(pattern.names().into_iter().map(String::from)),
+                        }
+                    }
+                }
+                Stmt::Block(b) => walk(&b.stmts, once, again),
+                Stmt::If(i) => {
+                    walk(std::slice::from_ref(&*i.then_branch), once, again);
+                    if let Some(e) = &i.else_branch {
+                        walk(std::slice::from_ref(&**e), once, again);
+                    }
+                }
+                Stmt::While(w) => walk(std::slice::from_ref(&*w.body), once, again),
+                Stmt::Try(t) => try_blocks(t).into_iter().for_each(|b| walk(&b.stmts, once, again)),
+                _ => {}
+            }
+        }
+    }
+    let (mut once, mut again) = (BTreeSet::new(), BTreeSet::new());
+    walk(stmts, &mut once, &mut again);
+    let mut names = assigned_names(stmts);
+    names.retain(|name| !once.contains(name) || again.contains(name));
+    names
+}
+
+/// 一组语句在一条执行路径上最多给 `name` 赋值几次，2 表示两次以上（见 `mutable_names`）。
+///
+/// 只是粗略估计，宁多勿少：if/else 取两个分支里多的一个，其它语句累加；
+/// 循环里的赋值、嵌套函数里的赋值、字段/下标赋值都算两次以上。
+fn assign_count(stmts: &[Stmt], name: &str) -> usize {
+    fn add(a: usize, b: usize) -> usize {
+        (a + b).min(2)
+    }
+    fn stmt(s: &Stmt, name: &str) -> usize {
+        match s {
+            Stmt::Assign(a) => add(usize::from(a.name == name), expr(&a.value, name)),
+            Stmt::VarDecl(v) => v.decls.iter().filter_map(|d| d.init.as_ref()).fold(0, |n, e| add(n, expr(e, name))),
+            Stmt::ExprStmt(e) => expr(e, name),
+            Stmt::Return(r) => r.value.as_ref().map_or(0, |v| expr(v, name)),
+            Stmt::Throw(t) => expr(&t.value, name),
+            Stmt::Block(b) => assign_count(&b.stmts, name),
+            Stmt::If(i) => {
+                let else_count = i.else_branch.as_ref().map_or(0, |e| stmt(e, name));
+                add(expr(&i.cond, name), stmt(&i.then_branch, name).max(else_count))
+            }
+            Stmt::While(w) => {
+                if add(expr(&w.cond, name), stmt(&w.body, name)) > 0 {
+                    2
+                } else {
+                    0
+                }
+            }
+            Stmt::FuncDecl(f) => {
+                if assigned_names(&f.body.stmts).contains(name) {
+                    2
+                } else {
+                    0
+                }
+            }
+            Stmt::Try(t) => try_blocks(t).into_iter().fold(0, |n, b| add(n, assign_count(&b.stmts, name))),
+        }
+    }
+    fn expr(e: &Expr, name: &str) -> usize {
+        match e {
+            Expr::Assign(a) => {
+                let target = match &a.target {
+                    AssignTarget::Ident(n) => usize::from(n == name),
+                    AssignTarget::Member { object, .. } => 2 * usize::from(object == name),
+                    AssignTarget::Index { object, index } => add(2 * usize::from(object == name), expr(index, name)),
+                };
+                add(target, expr(&a.value, name))
+            }
+            Expr::Unary(u) => expr(&u.expr, name),
+            Expr::Binary(b) => add(expr(&b.left, name), expr(&b.right, name)),
+            Expr::Group(inner) | Expr::Spread(inner) => expr(inner, name),
+            Expr::Member(m) => expr(&m.object, name),
+            Expr::Index(ix) => add(expr(&ix.object, name), expr(&ix.index, name)),
+            Expr::Call(c) => {
+                let callee = match &c.callee {
+                    Callee::Expr(inner) | Callee::Method { object: inner, .. } => expr(inner, name),
+                    _ => 0,
+                };
+                c.args.iter().fold(callee, |n, a| add(n, expr(a, name)))
+            }
+            Expr::New(n) => n.args.iter().fold(0, |count, a| add(count, expr(a, name))),
+            Expr::Literal(_, _) | Expr::Ident(_, _) => 0,
+        }
+    }
+    stmts.iter().fold(0, |n, s| add(n, stmt(s, name)))
+}
+
+/// UI 函数体里的全部语句（见 `mutable_names`）：和 `lower_ui` 一样展开组件和条件，
+/// 组件实参和属性方法里的箭头函数（`onClick`、`ForEach` 的循环体）当作嵌套函数。
+fn ui_stmts(nodes: &[UiNode]) -> Vec<Stmt> {
+    let mut stmts = Vec::new();
+    for node in nodes {
+        match node {
+            UiNode::Element(e) => {
+                for arg in e.args.iter().chain(e.attrs.iter().flat_map(|a| &a.args)) {
+                    if let UiArg::Handler(h) = arg {
+                        stmts.push(Stmt::FuncDecl(FuncDecl {
+                            is_export: false,
+                            name: String::new(),
+                            params: h.params.clone(),
+                            ret_type: None,
+                            body: BlockStmt { stmts: ui_stmts(&h.body), span: NodeSpan::default() },
+                            decorators: Vec::new(),
+                            span: NodeSpan::default(),
+                        }));
+                    }
+                }
+                stmts.extend(ui_stmts(&e.children));
+            }
+            UiNode::If(i) => stmts.push(Stmt::If(IfStmt {
+                cond: i.cond.clone(),
+                then_branch: Box::new(Stmt::Block(BlockStmt { stmts: ui_stmts(&i.then_branch), span: NodeSpan::default() })),
+                else_branch: Some(Box::new(Stmt::Block(BlockStmt { stmts: ui_stmts(&i.else_branch), span: NodeSpan::default() }))),
+                span: NodeSpan::default(),
+            })),
+            UiNode::Stmt(s) => stmts.push(s.clone()),
+        }
+    }
+    stmts
+}
+
+/// 命名空间成员在作用域/异常分析里的名字：`util.f`。
+///
+/// 名字里带 `.`，不会和任何普通标识符冲突。
+pub(crate) fn member_key(object: &str, property: &str) -> String {
+    format!("{object}.{property}")
+}
+
+/// 字符串内置方法表：方法名 -> `(最少实参个数, 最多实参个数, 返回类型)`。
+///
+/// 生成规则见 `gen_string_method`。
+pub(crate) fn string_method(name: &str) -> Option<(usize, usize, TypeAnn)> {
+    let entry = match name {
+        "charAt" => (0, 1, TypeAnn::String),
+        "charCodeAt" => (0, 1, TypeAnn::Number),
+        "includes" => (1, 1, TypeAnn::Boolean),
+        "indexOf" => (1, 1, TypeAnn::Number),
+        "slice" | "substring" => (1, 2, TypeAnn::String),
+        "toUpperCase" | "toLowerCase" | "trim" => (0, 0, TypeAnn::String),
+        "split" => (1, 1, TypeAnn::Array(Box::new(TypeAnn::String))),
+        "replace" => (2, 2, TypeAnn::String),
+        _ => return None,
     };
+    Some(entry)
+}
+
+/// 不需要声明就能调用的内置全局函数：定时器、`assert` 和数字/字符串转换函数。
+pub(crate) fn is_global_builtin(name: &str) -> bool {
+    is_timer_builtin(name) || is_conversion_builtin(name) || name == "assert"
+}
+
+/// 内置的数字/字符串转换函数名（见 `gen_conversion`）。
+pub(crate) fn is_conversion_builtin(name: &str) -> bool {
+    matches!(name, "parseInt" | "parseFloat" | "Number" | "String")
+}
 
-    let bp = binary_bp(b.op);
-    let left = gen_expr_bp(&b.left, bp)?;
-    let right = gen_expr_bp(&b.right, bp + 1)?;
-    Ok(format!("{left} {op} {right}"))
+/// 资源引用函数名（见 `gen_resource_ref`）。
+pub(crate) fn is_resource_builtin(name: &str) -> bool {
+    matches!(name, "$r" | "$rawfile")
+}
+
+/// 内置定时器函数名（回调会在调用处同步执行）。
+fn is_timer_builtin(name: &str) -> bool {
+    matches!(name, "setTimeout" | "setInterval")
 }
 
 fn binary_bp(op: BinaryOp) -> u8 {
     match op {
+        BinaryOp::NullishCoalesce => 10,
         BinaryOp::OrOr => 20,
         BinaryOp::AndAnd => 30,
         BinaryOp::EqEq | BinaryOp::NotEq => 40,
@@ -391,17 +7118,57 @@ fn binary_bp(op: BinaryOp) -> u8 {
 /// 把字面量转换为 Rust 表达式字符串。
 ///
 /// 映射规则：
-/// - number -> i32（通过 `1i32` 这种后缀强制类型，避免类型推断差异）
+/// - number -> i32（通过 `1i32` 这种后缀强制类型，避免类型推断差异）；f64 模式是 `1f64`、`0.5f64`
 /// - string -> String（统一用 `String::from("...")`）
 /// - boolean -> bool
-fn gen_literal_expr(lit: &Literal) -> String {
+fn gen_literal_expr(lit: &Literal, num: NumberMode, strs: StringMode) -> String {
     match lit {
-        Literal::Number(n) => format!("{n}i32"),
+        Literal::Number(n) => format!("{n}{}", num.rust_type()),
+        Literal::Float(text) => format!("{text}f64"),
         Literal::Bool(b) => b.to_string(),
-        Literal::String(s) => format!("String::from(\"{}\")", escape_rust_string(s)),
+        Literal::Null | Literal::Undefined => "None".to_string(),
+        Literal::String(s) => strs.literal(&escape_rust_string(s)),
     }
 }
 
+/// 生成的代码可能用到的外部 crate（见 `CodegenOptions` 的 `use_rand`、`hilog` 等选项、UI 后端和原生模块）：
+/// （代码里的 crate 名，`Cargo.toml` 里的依赖声明）。
+///
+/// `Edition::E2015` 时要写 `extern crate`；`cargo::manifest` 按这里的声明写依赖。
+pub(crate) const EXTERNAL_CRATES: &[(&str, &str)] = &[
+    ("rand", "rand = \"0.8\""),
+    ("serde_json", "serde_json = \"1\""),
+    ("chrono", "chrono = \"0.4\""),
+    ("log", "log = \"0.4\""),
+    ("tracing", "tracing = \"0.1\""),
+    ("egui", "egui = \"0.29\""),
+    ("tokio", "tokio = { version = \"1\", features = [\"full\"] }"),
+    ("napi_ohos", "napi-ohos = \"1\""),
+    ("napi_derive_ohos", "napi-derive-ohos = \"1\""),
+];
+
+/// `--bind`、`ability_runtime`、`resource_lookup`、`runtime` 配置的 Rust 路径所在的 crate（路径的第一段，不含 `std`、`crate` 等）。
+pub(crate) fn configured_crates<'a>(
+    externs: &'a ExternBindings,
+    options: &'a CodegenOptions,
+) -> impl Iterator<Item = &'a str> {
+    externs
+        .values()
+        .chain(&options.ability_runtime)
+        .chain(&options.resource_lookup)
+        .chain(&options.runtime)
+        .filter_map(|path| path.split("::").next())
+        .filter(|c| !matches!(*c, "crate" | "self" | "super" | "std" | "core" | "alloc" | ""))
+}
+
+/// `code` 里有没有以 `name::` 开头的路径（前面不是标识符的一部分）。
+pub(crate) fn uses_crate(code: &str, name: &str) -> bool {
+    let path = format!("{name}::");
+    code.match_indices(&path).any(|(i, _)| {
+        !code[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':')
+    })
+}
+
 /// 将字符串内容转义为可以放进 Rust 字符串字面量 `"..."` 的形式。
 ///
 /// 例如：源码里包含 `"` 或 `\` 时，需要变为 `\"`、`\\`。
@@ -0,0 +1,398 @@
+use crate::error::{Error, ErrorCode, Expected, Note, NoteCode, Warning};
+use crate::messages::{self, Lang};
+use crate::module::SourceFiles;
+use crate::span::Span;
+use std::fmt;
+
+/// 诊断信息的严重程度。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// 错误：编译失败，不生成代码
+    Error,
+    /// 警告：照常生成代码，只是提示这里可能有问题
+    Warning,
+    /// 说明：单独给出的提示信息
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Note => "Note",
+        })
+    }
+}
+
+/// 一条诊断信息：错误、警告或说明。
+///
+/// 字段和 `Error` 相同，多了严重程度。`notes` 是和它相关的其它位置（例如 `declared here` 指向的声明），
+/// `expected` 是语法错误时可以接受的 token 和实际遇到的 token，`args` 是说明文字里的参数（见 `Error::args`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub span: Span,
+    pub file: Option<String>,
+    pub notes: Vec<Note>,
+    pub expected: Option<Box<Expected>>,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Diagnostic {
+    /// 创建一条诊断信息。
+    pub fn new(severity: Severity, code: ErrorCode, span: Span) -> Self {
+        Self {
+            severity,
+            code,
+            span,
+            file: None,
+            notes: Vec::new(),
+            expected: None,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// 给诊断信息补上所在文件（已经有文件的保持不变，和 `Error::in_file` 相同）。
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.into());
+        }
+        self
+    }
+
+    /// 多文件编译时按位置所在的源文件（`Span::file`）补上文件名，已经有文件的保持不变。
+    /// 说明在别的文件里时也记上它的文件名，打印时显示在它自己的 `-->` 后面。
+    pub fn locate(mut self, files: &SourceFiles) -> Self {
+        if self.file.is_none() {
+            self.file = files.name(self.span).map(str::to_string);
+        }
+        for note in &mut self.notes {
+            match files.name(note.span) {
+                Some(file) if note.file.is_empty() && self.file.as_deref() != Some(file) => note.file = file.to_string(),
+                _ => {}
+            }
+        }
+        self
+    }
+
+    /// 给人看的说明，例如 “expected `;`, found `}`”（见 `Expected`）。
+    pub fn message(&self) -> Option<String> {
+        self.expected.as_ref().map(|e| e.to_string())
+    }
+
+    /// 给人看的诊断信息，格式和 rustc 相同，编辑器和 CI 工具能直接识别 `-->` 后面的位置并跳转：
+    ///
+    /// ```text
+    /// error[UseOfUninitialized]: use of possibly unassigned variable `x`
+    ///  --> input.ets:2:1
+    ///   |
+    /// 2 | console.log(x);
+    ///   | ^^^^^^^^^^^^^^
+    /// note: declared here
+    ///  --> input.ets:1:1
+    ///   |
+    /// 1 | let x: number;
+    ///   | ^^^^^^^^^^^^^^
+    /// ```
+    ///
+    /// `src` 是诊断信息所在文件（`file`，没有时只打印行列号）的源码。没有位置（`Span::default()`）的诊断信息
+    /// 没有 `-->` 和源码行；其它文件里的补充说明没有源码行。说明文字是英文，要中文用 `render_in`。
+    pub fn render(&self, src: &str) -> String {
+        self.render_in(src, Lang::En)
+    }
+
+    /// 用指定语言打印的 `render`：标题后面的说明取自消息表，填上 `args`（见 `messages::message`），语法错误用
+    /// 可以接受的 token 拼出的说明；`error[..]`、`-->` 这些格式部分不翻译，工具照样能识别。
+    pub fn render_in(&self, src: &str, lang: Lang) -> String {
+        let msg = match &self.expected {
+            Some(e) => messages::expected(e, lang),
+            None => messages::format(messages::message(self.code, lang), &self.args),
+        };
+        let mut out = format!("{}[{}]: {msg}\n", self.severity.to_string().to_lowercase(), self.code);
+        // 行号栏的宽度：所有要打印的行号里最长的那个
+        let width = std::iter::once(self.span)
+            .chain(self.notes.iter().map(|n| n.span))
+            .map(|span| span.start_line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let file = self.file.as_deref().unwrap_or("");
+        snippet(&mut out, file, src, self.span, width);
+        for note in &self.notes {
+            out.push_str(&format!("note: {}\n", note.message_in(lang)));
+            if note.file.is_empty() {
+                snippet(&mut out, file, src, note.span, width);
+            } else {
+                snippet(&mut out, &note.file, "", note.span, width);
+            }
+        }
+        out
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(e: Error) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: e.code,
+            span: e.span,
+            file: e.file.map(String::from),
+            notes: e.notes.into_vec(),
+            expected: e.expected,
+            args: e.args.into_vec(),
+        }
+    }
+}
+
+impl From<Warning> for Diagnostic {
+    fn from(w: Warning) -> Self {
+        Self {
+            file: w.file,
+            args: w.args,
+            ..Self::new(Severity::Warning, w.code, w.span)
+        }
+    }
+}
+
+/// 转回 `Error`（不管严重程度），给只返回一个错误的旧接口用（见 `CompileResult::into_result`）。
+impl From<Diagnostic> for Error {
+    fn from(d: Diagnostic) -> Self {
+        Self {
+            code: d.code,
+            span: d.span,
+            file: d.file.map(String::into_boxed_str),
+            notes: d.notes.into(),
+            expected: d.expected,
+            args: d.args.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 和 `Error` / `Warning` 的 Display 相同，开头是严重程度
+        if let Some(file) = &self.file {
+            write!(f, "{file}: ")?;
+        }
+        write!(
+            f,
+            "{}(code={}, span={}..{}, loc={}:{}..{}:{})",
+            self.severity,
+            self.code,
+            self.span.start,
+            self.span.end,
+            self.span.start_line,
+            self.span.start_col,
+            self.span.end_line,
+            self.span.end_col
+        )?;
+        if let Some(msg) = self.message() {
+            write!(f, ": {msg}")?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  note: ")?;
+            if !note.file.is_empty() {
+                write!(f, "{}:", note.file)?;
+            }
+            // 没有位置的说明（例如指向内置函数的拼写建议）只打印文字
+            if note.span != Span::default() {
+                write!(f, "{}:{}: ", note.span.start_line, note.span.start_col)?;
+            }
+            write!(f, "{}", note.message())?;
+        }
+        Ok(())
+    }
+}
+
+/// 打印 `-->` 位置行和 `span` 开头所在的源码行，下一行用 `^` 标出 span 覆盖的列（至少一个 `^`）。
+///
+/// 跨行的 span 只标到第一行的行尾；没有位置时什么都不打印，行号超出 `src` 的范围时只打印位置。
+fn snippet(out: &mut String, file: &str, src: &str, span: Span, width: usize) {
+    if span == Span::default() {
+        return;
+    }
+    let gutter = " ".repeat(width);
+    let path = if file.is_empty() { String::new() } else { format!("{file}:") };
+    out.push_str(&format!("{gutter}--> {path}{}:{}\n", span.start_line, span.start_col));
+    let Some(line) = src.lines().nth(span.start_line.saturating_sub(1)) else {
+        return;
+    };
+    let len = line.chars().count();
+    let start = span.start_col.saturating_sub(1).min(len);
+    let end = if span.end_line == span.start_line { span.end_col.saturating_sub(1) } else { len };
+    // 下划线前面的空白和源码对齐：tab 还是 tab
+    let pad: String = line.chars().take(start).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let carets = "^".repeat(end.min(len).saturating_sub(start).max(1));
+    out.push_str(&format!("{gutter} |\n"));
+    out.push_str(&format!("{:>width$} | {line}\n", span.start_line));
+    out.push_str(&format!("{gutter} | {pad}{carets}\n"));
+}
+
+/// 有拼写和 `name` 相近的名字时，给错误附带一条 `did you mean `..`?` 的补充说明，指向那个名字的声明
+/// （内置的名字没有位置，用 `Span::default()`，说明里只有文字）。
+///
+/// 用在 `UndeclaredVariable`、`UnknownFunction`、`UnknownType` 上：移植代码时的拼写错误很常见。
+pub(crate) fn did_you_mean(err: Error, name: &str, candidates: impl IntoIterator<Item = (String, Span)>) -> Error {
+    match closest_name(name, candidates) {
+        Some((similar, span)) => err.with_note_args("", span, NoteCode::DidYouMean, &[("name", &similar)]),
+        None => err,
+    }
+}
+
+/// `candidates` 里和 `name` 拼写最接近的名字及其位置：编辑距离不超过名字长度的三分之一（至少 1）才算相近，
+/// 距离相同时取字典序最小的。
+fn closest_name(name: &str, candidates: impl IntoIterator<Item = (String, Span)>) -> Option<(String, Span)> {
+    let mut candidates: Vec<(String, Span)> = candidates.into_iter().filter(|(n, _)| n != name).collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|(n, span)| (edit_distance(name, &n), n, span))
+        .filter(|(d, _, _)| *d <= limit)
+        .min_by_key(|(d, _, _)| *d)
+        .map(|(_, n, span)| (n, span))
+}
+
+/// 两个名字之间的编辑距离（Levenshtein）：把一个改成另一个最少要插入、删除、替换几个字符。
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = prev[j] + usize::from(ca != *cb);
+            cur.push(replace.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// 一次编译里收集到的全部诊断信息，按收集的顺序排列。
+///
+/// 以前编译遇到第一个错误就停下，修一个再编译一次才能看到下一个；现在各个检查（`sema`、`typeck`、`flow`）
+/// 在一个函数里出错后会接着检查其它函数，错误都收集在这里，一次报告出来（见 `crate::check_program`）。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一条诊断信息（可以直接传 `Error` / `Warning`）。
+    pub fn push(&mut self, d: impl Into<Diagnostic>) {
+        self.items.push(d.into());
+    }
+
+    pub fn extend<D: Into<Diagnostic>>(&mut self, items: impl IntoIterator<Item = D>) {
+        self.items.extend(items.into_iter().map(Into::into));
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(Diagnostic::is_error)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter().filter(|d| d.is_error())
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter().filter(|d| d.severity == Severity::Warning)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// 给还没有文件的诊断信息补上所在文件：单文件编译时检查阶段的诊断信息不知道文件名，由调用方补上。
+    pub fn in_file(self, file: &str) -> Self {
+        Self {
+            items: self.items.into_iter().map(|d| d.in_file(file)).collect(),
+        }
+    }
+
+    /// 按位置所在的源文件补上文件名，见 `Diagnostic::locate`。
+    pub fn locate(self, files: &SourceFiles) -> Self {
+        Self {
+            items: self.items.into_iter().map(|d| d.locate(files)).collect(),
+        }
+    }
+
+    /// 第一个错误。
+    pub fn first_error(&self) -> Option<Error> {
+        self.errors().next().cloned().map(Error::from)
+    }
+
+    /// 编译失败时报告的诊断信息：检查已经找到了错误就报告它们（`err` 一般就是其中的第一个），
+    /// 否则（只有 CodeGen 才发现的问题，例如不支持的写法）补上 `err`。
+    pub fn or_error(mut self, err: Error) -> Self {
+        if !self.has_errors() {
+            self.push(err);
+        }
+        self
+    }
+}
+
+impl From<Error> for Diagnostics {
+    fn from(e: Error) -> Self {
+        Self { items: vec![e.into()] }
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// 编译结果：生成的 Rust 源码（有错误时为 None）和这次编译的全部诊断信息（见 `crate::compile_with_diagnostics`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileResult {
+    pub output: Option<String>,
+    pub diagnostics: Diagnostics,
+}
+
+impl CompileResult {
+    pub fn is_ok(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// 转成 `compile` 那样的结果：成功时是生成的代码，失败时是第一个错误。
+    pub fn into_result(self) -> Result<String, Error> {
+        match self.output {
+            Some(code) => Ok(code),
+            None => Err(self
+                .diagnostics
+                .first_error()
+                .unwrap_or_else(|| Error::new(ErrorCode::CompileFailed, Span::default()))),
+        }
+    }
+}
@@ -1,42 +1,246 @@
+use crate::diagnostics::Diagnostic;
+use crate::module::SourceFiles;
+use crate::messages::{self, Lang};
+pub use crate::error_code::ErrorCode;
 use crate::span::Span;
 use std::fmt;
 
 /// 编译器统一错误类型。
 ///
 /// 设计要点：
-/// - `code`：机器可读的错误码（便于测试断言、分类统计）。
+/// - `code`：机器可读的错误码（便于测试断言、分类统计，见 `ErrorCode`）。
 /// - `span`：错误发生的位置（byte offset + line/col），便于定位。
+/// - `file`：错误所在的源文件（多文件编译时才有，单文件编译为 `None`）。
+/// - `notes`：和这个错误相关的其它位置（例如循环 import 经过的每一条 import）。
+/// - `expected`：语法错误时，这个位置可以接受的 token 和实际遇到的 token（见 `Expected`）；其它错误为 None。
+/// - `args`：说明文字里的参数，例如 `UndeclaredVariable` 的 `{name}`（见 `messages::message`）。
+///
+/// `file`、`notes`、`args` 大多为空，放在 Box 里（没有内容时不分配），`Result<_, Error>` 保持在 clippy 的默认大小以内。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Error {
-    pub code: String,
+    pub code: ErrorCode,
     pub span: Span,
+    pub file: Option<Box<str>>,
+    pub notes: Box<[Note]>,
+    pub expected: Option<Box<Expected>>,
+    pub args: Box<[(&'static str, String)]>,
+}
+
+/// 语法错误的结构化说明：这个位置可以接受的 token 和实际遇到的 token，
+/// 用来拼出“expected `)` or `,`, found `;`”这样的说明（见 `Error::message`）。
+///
+/// `tokens` 里是给人看的描述（例如 `` `)` ``、`expression`，见 `TokenKind::describe`），
+/// `found` 在文件末尾时是 `end of file`。放在 Box 里，不让每个 `Error` 都变大。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expected {
+    pub tokens: Vec<String>,
+    pub found: String,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected ")?;
+        if let Some((last, rest)) = self.tokens.split_last() {
+            if !rest.is_empty() {
+                write!(f, "{} or ", rest.join(", "))?;
+            }
+            write!(f, "{last}")?;
+        }
+        write!(f, ", found {}", self.found)
+    }
+}
+
+/// 错误的补充说明：指向另一个位置，附一句说明。
+///
+/// `file` 为空字符串表示和错误本身在同一份源码里（单文件编译时的语义错误）；
+/// `span` 为 `Span::default()` 表示说明不指向具体位置。说明文字按 `code` 从消息表里查，`args` 是文字里的参数
+/// （见 `messages::note`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Note {
+    pub file: String,
+    pub span: Span,
+    pub code: NoteCode,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Note {
+    /// 英文的说明文字，例如 “did you mean `total`?”。
+    pub fn message(&self) -> String {
+        self.message_in(Lang::En)
+    }
+
+    /// 用指定语言的说明文字。
+    pub fn message_in(&self, lang: Lang) -> String {
+        messages::note(self.code, &self.args, lang)
+    }
+}
+
+/// 补充说明的种类，括号里是说明文字用到的参数。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NoteCode {
+    /// 变量在这里声明
+    DeclaredHere,
+    /// 重复声明的名字第一次在这里声明
+    FirstDeclaredHere,
+    /// 变量在这里声明为 const
+    DeclaredConstHere,
+    /// 函数在这里声明
+    FunctionDeclaredHere,
+    /// 参数在这里声明
+    ParameterDeclaredHere,
+    /// 顶层 `let` 是 `main` 的局部变量，函数里用不到
+    TopLevelLet,
+    /// 拼写相近的名字（`{name}`）
+    DidYouMean,
+    /// 循环 import 经过的一条 import（`{file}` 是它 import 的文件）
+    Imports,
+    /// rustc 对生成代码的报错（`{message}`，不翻译）
+    Rustc,
 }
 
 impl Error {
-    /// 创建一个错误。`code` 通常使用短字符串（例如 `UnexpectedChar`）。
-    pub fn new(code: impl Into<String>, span: Span) -> Self {
+    /// 创建一个错误，例如 `Error::new(ErrorCode::UnexpectedChar, span)`。
+    pub fn new(code: ErrorCode, span: Span) -> Self {
         Self {
-            code: code.into(),
+            code,
             span,
+            file: None,
+            notes: Box::default(),
+            expected: None,
+            args: Box::default(),
         }
     }
+
+    /// 记下说明文字里的一个参数，例如 `.with_arg("name", "x")`。
+    pub fn with_arg(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        let mut args = std::mem::take(&mut self.args).into_vec();
+        args.push((name, value.into()));
+        self.args = args.into();
+        self
+    }
+
+    /// 记下这个位置可以接受的 token 和实际遇到的 token（解析器报语法错误时使用）。
+    pub fn with_expected(mut self, expected: &[&str], found: impl Into<String>) -> Self {
+        self.expected = Some(Box::new(Expected {
+            tokens: expected.iter().map(|s| s.to_string()).collect(),
+            found: found.into(),
+        }));
+        self
+    }
+
+    /// 给人看的说明，例如 “expected `)` or `,`, found `;`”；没有记下可以接受的 token 时为 None。
+    pub fn message(&self) -> Option<String> {
+        self.expected.as_ref().map(|e| e.to_string())
+    }
+
+    /// 给人看的错误信息：rustc 的格式，带上 `-->` 位置和出错的源码行，用 `^^^` 标出位置（见 `Diagnostic::render`）。
+    /// `src` 是错误所在文件的源码。
+    pub fn render(&self, src: &str) -> String {
+        Diagnostic::from(self.clone()).render(src)
+    }
+
+    /// 用指定语言打印的 `render`（见 `Diagnostic::render_in`）。
+    pub fn render_in(&self, src: &str, lang: Lang) -> String {
+        Diagnostic::from(self.clone()).render_in(src, lang)
+    }
+
+    /// 追加一条补充说明。
+    pub fn with_note(self, file: impl Into<String>, span: Span, code: NoteCode) -> Self {
+        self.with_note_args(file, span, code, &[])
+    }
+
+    /// 追加一条带参数的补充说明，例如 `(NoteCode::DidYouMean, &[("name", "total")])`。
+    pub fn with_note_args(mut self, file: impl Into<String>, span: Span, code: NoteCode, args: &[(&'static str, &str)]) -> Self {
+        let mut notes = std::mem::take(&mut self.notes).into_vec();
+        notes.push(Note {
+            file: file.into(),
+            span,
+            code,
+            args: args.iter().map(|(k, v)| (*k, v.to_string())).collect(),
+        });
+        self.notes = notes.into();
+        self
+    }
+
+    /// 给还没有位置（`Span::default()`）的错误补上位置；已经有位置的保持不变，保留最内层的位置。
+    ///
+    /// CodeGen 的错误大多在不知道位置的地方创建，由外层的语句/表达式用自己的位置补上（见 `ast::NodeSpan`）。
+    pub fn at(mut self, span: Span) -> Self {
+        if self.span == Span::default() {
+            self.span = span;
+        }
+        self
+    }
+
+    /// 给错误补上所在文件（已经有文件的错误保持不变，保留最内层的位置）。
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.into().into());
+        }
+        self
+    }
+
+    /// 按位置所在的源文件补上文件名（见 `Diagnostic::locate`）。
+    pub fn locate(self, files: &SourceFiles) -> Self {
+        Diagnostic::from(self).locate(files).into()
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Display 用于给人看的错误信息，CLI 会直接打印它。
-        write!(
-            f,
-            "Error(code={}, span={}..{}, loc={}:{}..{}:{})",
-            self.code,
-            self.span.start,
-            self.span.end,
-            self.span.start_line,
-            self.span.start_col,
-            self.span.end_line,
-            self.span.end_col
-        )
+        // Display 用于给人看的错误信息，CLI 会直接打印它（格式见 `Diagnostic` 的 Display）。
+        Diagnostic::from(self.clone()).fmt(f)
     }
 }
 
 impl std::error::Error for Error {}
+
+/// 编译警告：不阻止编译，但提示这里的写法在 Rust 里的含义可能和原来不完全一样。
+///
+/// 字段和 `Error` 相同（不需要补充说明和语法错误的说明）。警告由解析器收集在 `Program::warnings` 里，
+/// 多文件编译时 `module::link` 会补上所在文件。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub code: ErrorCode,
+    pub span: Span,
+    pub file: Option<String>,
+    /// 说明文字里的参数，例如没有使用的变量的 `name`（见 `messages::format`）
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Warning {
+    /// 创建一个警告。警告也用 `ErrorCode`（例如 `ErrorCode::TruthyCondition`）。
+    pub fn new(code: ErrorCode, span: Span) -> Self {
+        Self {
+            code,
+            span,
+            file: None,
+            args: Vec::new(),
+        }
+    }
+
+    /// 补上说明文字里的参数（和 `Error::with_arg` 相同）。
+    pub fn with_arg(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+
+    /// 给人看的警告信息，格式和 `Error::render` 相同。
+    pub fn render(&self, src: &str) -> String {
+        Diagnostic::from(self.clone()).render(src)
+    }
+
+    /// 给警告补上所在文件（已经有文件的警告保持不变）。
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.into());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Diagnostic::from(self.clone()).fmt(f)
+    }
+}
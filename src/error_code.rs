@@ -0,0 +1,381 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// 定义 `ErrorCode`：每个错误码只写一次，字符串形式（`as_str`）就是变体名，`ALL` 列出全部错误码。
+macro_rules! error_codes {
+    ($($(#[$doc:meta])* $name:ident,)*) => {
+        /// 错误码（警告也用同一套）：机器可读，便于测试断言、分类统计，库的使用者可以直接 `match`。
+        ///
+        /// 每个错误码都有固定的字符串形式（就是变体名，例如 `ErrorCode::UndeclaredVariable` 是 `"UndeclaredVariable"`），
+        /// 诊断信息里打印的、`FromStr` 接受的都是它；以后新增错误码不算破坏兼容，所以标了 `#[non_exhaustive]`。
+        #[non_exhaustive]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $($(#[$doc])* $name,)*
+        }
+
+        impl ErrorCode {
+            /// 全部错误码，按下面定义的顺序（词法、语法、模块、语义、类型、数据流、代码生成、警告）。
+            pub const ALL: &'static [ErrorCode] = &[$(ErrorCode::$name,)*];
+
+            /// 字符串形式：和变体名相同。
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => stringify!($name),)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    // ---------- 词法分析 ----------
+    /// 子集不支持的字符，例如 `#`、单独的 `&` / `?`
+    UnexpectedChar,
+    /// `/* ..` 没有闭合
+    UnterminatedBlockComment,
+    /// 数字超出 i32 的范围（f64 数字模式下只有超出 u64 的十六进制数字）
+    InvalidNumber,
+    /// 字符串在行尾或文件末尾还没有闭合
+    UnterminatedString,
+
+    // ---------- 语法分析 ----------
+    /// 不支持的 `export` 写法（例如 `export let`）
+    UnsupportedExport,
+    /// 装饰器后面不是函数、class 或组件
+    MisplacedDecorator,
+    /// 组件里有多个 `build()`
+    DuplicateBuild,
+    /// 组件里不支持的成员
+    UnsupportedComponentMember,
+    /// 缺少 `}`
+    MissingRBrace,
+    /// class / Ability 里不支持的成员
+    UnsupportedClassMember,
+    /// 这个位置不能出现这个 token
+    UnexpectedToken,
+    /// 还没解析完就到了文件末尾
+    UnexpectedEof,
+    /// 装饰器的参数不对，例如 `@Extend` 没有写组件名
+    InvalidDecoratorArgs,
+    /// UI 组件后面应该是 `.attr(..)` 属性方法
+    ExpectedUiAttrCall,
+    /// namespace 里不支持的成员
+    UnsupportedNamespaceMember,
+    /// `from` 后面应该是模块路径字符串
+    ExpectedModulePath,
+    /// class 只能在顶层声明
+    ClassNotAllowedHere,
+    /// import 只能在顶层
+    ImportNotAllowedHere,
+    /// namespace 只能在顶层声明
+    NamespaceNotAllowedHere,
+    /// `declare` 只能在顶层
+    DeclareNotAllowedHere,
+    /// export 只能在顶层
+    ExportNotAllowedHere,
+    /// 变量声明缺少初始值
+    MissingInitializer,
+    /// 缺少 `]`
+    MissingRBracket,
+    /// 剩余参数后面还有参数
+    RestParamMustBeLast,
+    /// 剩余参数的类型不是数组
+    RestParamMustBeArray,
+    /// 不支持的联合类型（只支持 `T | null` / `T | undefined`）
+    UnsupportedUnionType,
+    /// 不认识的类型名
+    UnknownType,
+    /// 缺少 `>`
+    MissingGt,
+    /// 缺少 `:`
+    ExpectedColon,
+    /// 缺少 `)`
+    MissingRParen,
+    /// 函数类型标注缺少 `=>`
+    ExpectedArrow,
+    /// 应该是代码块 `{ .. }`
+    ExpectedBlock,
+    /// `if` 没有 `else` 分支
+    MissingElse,
+    /// `try` 既没有 `catch` 也没有 `finally`
+    MissingCatchOrFinally,
+    /// `if` / `while` 的条件不是 bool
+    ConditionMustBeBool,
+    /// 不支持的写法，例如不认识的 `console` 方法
+    UnknownStructure,
+    /// `??` 和 `&&` / `||` 混用而没有加括号
+    MixedNullishAndLogical,
+    /// 应该是表达式
+    ExpectedExpr,
+    /// 应该是字面量
+    ExpectedLiteral,
+    /// 应该是标识符
+    ExpectedIdentifier,
+    /// 缺少 `;`
+    MissingSemicolon,
+    /// 缺少 `.`
+    ExpectedDot,
+    /// 缺少 `as`
+    ExpectedAs,
+    /// 缺少 `from`
+    ExpectedFrom,
+
+    // ---------- 模块 ----------
+    /// 找不到 import 的文件
+    ModuleNotFound,
+    /// `.d.ets` 声明文件里出现了声明以外的代码
+    CodeInDeclarationFile,
+    /// 不认识的 `@ohos.*` / `@kit.*` 系统模块
+    UnknownSystemModule,
+    /// 循环 import
+    CircularImport,
+    /// 不支持的模块路径（非相对路径且没有配置别名 / `base_dir`）
+    UnsupportedModulePath,
+    /// import 的 class 不能改名
+    UnsupportedClassAlias,
+    /// import 的 namespace 不能改名
+    UnsupportedNamespaceAlias,
+    /// 合并模块后顶层名字重复
+    DuplicateSymbol,
+    /// import 的 const 改了名，或者初始值不是 number/boolean 常量表达式
+    UnsupportedConstImport,
+    /// 不支持默认导入
+    UnsupportedDefaultImport,
+    /// 两个模块生成的 Rust 模块名相同
+    DuplicateModuleName,
+    /// import 的名字没有被导出
+    ImportNotExported,
+
+    // ---------- 语义检查 ----------
+    /// 同一个作用域里重复声明
+    DuplicateDeclaration,
+    /// 变量在声明之前使用
+    UsedBeforeDeclaration,
+    /// 使用没有声明的变量
+    UndeclaredVariable,
+    /// 调用没有声明的函数
+    UnknownFunction,
+    /// 给 const 赋值
+    ConstReassignment,
+
+    // ---------- 类型检查 ----------
+    /// 有返回值的函数在某条路径上没有 return
+    MissingReturn,
+    /// 类型不匹配
+    TypeMismatch,
+    /// 实参个数和形参个数不同
+    WrongArgCount,
+    /// 实参的类型和形参不匹配
+    ArgTypeMismatch,
+
+    // ---------- 数据流检查 ----------
+    /// 读取还没有明确赋值的变量
+    UseOfUninitialized,
+
+    // ---------- 代码生成 ----------
+    /// 这里不能是 null
+    NullNotAllowed,
+    /// 单份源码里不能处理 import / re-export
+    UnresolvedImport,
+    /// 外部绑定的名字没有对应的 `declare function`
+    UnknownExternBinding,
+    /// 组件方法里不能 throw
+    UnsupportedComponentThrow,
+    /// Ability 方法里不能 throw
+    UnsupportedAbilityThrow,
+    /// Ability 生命周期方法的参数不对
+    InvalidLifecycleParams,
+    /// 组件字段没有类型标注，也推断不出类型
+    MissingFieldType,
+    /// 状态字段缺少初始值
+    StateFieldNeedsInitializer,
+    /// `@Link` / `@Consume` 字段不能有初始值
+    StateFieldHasInitializer,
+    /// `@Builder` 的实参不对
+    InvalidBuilderArgs,
+    /// `@Extend` 的组件和使用的组件不同
+    ExtendTargetMismatch,
+    /// `@Styles` / `@Extend` 的实参不对
+    InvalidStyleArgs,
+    /// `ForEach` 的实参不对
+    InvalidForEach,
+    /// 自定义组件的实参不对
+    InvalidComponentArgs,
+    /// 自定义组件没有这个属性
+    UnknownComponentProp,
+    /// 自定义组件缺少必须传的属性
+    MissingComponentProp,
+    /// `@Consume` 找不到对应的 `@Provide`
+    ConsumeWithoutProvide,
+    /// `@Link` 的来源不是能共享的状态
+    LinkNeedsSharedSource,
+    /// 库模式下不能有顶层代码
+    TopLevelCodeInLibrary,
+    /// 函数等顶层项里用到的顶层 const 不是常量表达式（可执行程序里它要生成为模块级的 `const`）
+    SharedConstNotConstant,
+    /// 闭包不能递归调用自己，也不能互相调用
+    RecursiveClosureUnsupported,
+    /// `null` 初始值需要类型标注
+    NullRequiresTypeAnnotation,
+    /// 不支持的下标访问
+    UnsupportedIndexAccess,
+    /// class 重复声明
+    DuplicateClass,
+    /// 不支持的 class（只支持继承 `Error` 的异常类）
+    UnsupportedClass,
+    /// 不认识的异常类
+    UnknownErrorClass,
+    /// 构造异常对象的实参太多
+    TooManyConstructorArgs,
+    /// 有返回值的函数里 `return` 没有值
+    ReturnValueRequired,
+    /// 原生模块接口不支持的类型
+    UnsupportedNativeType,
+    /// 原生模块接口不支持的函数签名
+    UnsupportedNativeSignature,
+    /// 不支持的展开实参
+    UnsupportedSpreadArg,
+    /// 不支持的 `console` 实参
+    UnsupportedConsoleArgs,
+    /// `assert` 的实参个数不对
+    AssertArgCount,
+    /// 不认识的成员
+    UnknownMember,
+    /// 类型转换函数的实参个数不对
+    ConversionArgCount,
+    /// 不认识的方法
+    UnknownMethod,
+    /// 不认识的字符串方法
+    UnknownStringMethod,
+    /// `Math.PI` / `Math.E` 需要 f64 数字模式
+    MathConstantNeedsFloat,
+    /// 小数放不进 i32：需要 f64 数字模式，或者先取整
+    FractionalNumberNeedsF64,
+    /// number 是 i32 时整数 `/` / `%` 的除数是常量 0（JS 的结果 NaN / Infinity 放不进 i32），见 `CodegenOptions::js_division`
+    IntegerDivisionByZero,
+    /// 小数字面量需要 f64 数字模式
+    FloatLiteralNeedsF64,
+    /// 不支持的成员访问
+    UnsupportedMemberAccess,
+    /// 不认识的 `Math` 函数
+    UnknownMathFunction,
+    /// `Math` 函数的实参个数不对
+    MathArgCount,
+    /// 字符串方法的实参个数不对
+    StringMethodArgCount,
+    /// `$r(..)` / `$rawfile(..)` 的资源名不对
+    InvalidResourceRef,
+    /// 不认识的 hilog 函数
+    UnknownHilogFunction,
+    /// hilog 函数的实参个数不对
+    HilogArgCount,
+    /// hilog 的格式串不是字面量
+    HilogFormatNotLiteral,
+    /// 不认识的 `JSON` 函数
+    UnknownJsonFunction,
+    /// `JSON` 需要启用 serde
+    JsonNeedsSerde,
+    /// `JSON` 函数的实参个数不对
+    JsonArgCount,
+    /// 不认识的 `Date` 函数
+    UnknownDateFunction,
+    /// `Date` 函数的实参个数不对
+    DateArgCount,
+    /// 不支持的 `Date` 实参
+    UnsupportedDateArgs,
+    /// 不认识的 `Date` 方法
+    UnknownDateMethod,
+    /// 不支持的类型转换
+    UnsupportedConversion,
+    /// 定时器的回调不是函数
+    TimerCallbackNotFunction,
+    /// `Promise` 函数的实参个数不对
+    PromiseArgCount,
+    /// 展开实参只能传给剩余参数
+    SpreadRequiresRestParam,
+    /// `new` 只能出现在 `throw` 里
+    NewOnlyInThrow,
+    /// 有多个 `@Entry` 组件
+    DuplicateEntry,
+    /// `@Entry` 组件的字段缺少初始值
+    EntryFieldNeedsInitializer,
+    /// 一个字段上有多个状态装饰器
+    ConflictingStateDecorators,
+    /// `StringMode::Static` 下把运行时生成的字符串存进 string 类型的位置
+    StaticStringNotLiteral,
+    /// `Promise.all` 生成 async 块，要求 Rust 2018 及以上的 edition
+    AsyncNeedsEdition2018,
+    /// 找不到格式化工具：`rustfmt` 不在 PATH 里，或者没有打开 `prettyplease` feature
+    FormatterUnavailable,
+    /// 格式化工具处理生成的代码失败
+    FormatFailed,
+    /// 调用的实参比 `CodegenOptions::intrinsics` 里的代码模板用到的少
+    IntrinsicArgCount,
+    /// 生成源码映射时用了会丢掉注释的格式化工具（`Formatter::Prettyplease`），见 `codegen::generate_mapped`
+    SourceMapFormatter,
+    /// 找不到 `rustc`（或 `cargo`），不能检查生成的代码，见 `verify`
+    RustcUnavailable,
+    /// `rustc` 编译生成的代码时报错，见 `verify::map_back`
+    GeneratedCodeError,
+    /// 编译失败但没有具体的错误（见 `CompileResult::into_result`）
+    CompileFailed,
+
+    // ---------- 警告 ----------
+    /// 被忽略的装饰器
+    IgnoredDecorator,
+    /// 条件不是 bool，按 JS 的 truthy 规则转换
+    TruthyCondition,
+    /// 自动插入的分号
+    InsertedSemicolon,
+    /// 库代码里被跳过的顶层语句，见 `CodegenOptions::skip_top_level_code`
+    TopLevelCodeSkipped,
+    /// 可执行程序里的顶层函数 `main` 改了名，`fn main` 留给执行顶层代码的入口函数，见 `rename::rename_main`
+    MainFunctionRenamed,
+    /// 没有使用的变量
+    UnusedVariable,
+    /// 没有使用的函数
+    UnusedFunction,
+    /// lint `self_assignment`：把变量赋值给它自己
+    SelfAssignment,
+    /// lint `empty_branch`：分支是空代码块
+    EmptyBranch,
+    /// lint `self_comparison`：变量和它自己比较
+    SelfComparison,
+    /// lint `constant_condition`：`if` / `while` 的条件是常量
+    ConstantCondition,
+    /// lint `infinite_loop`：`while (true)` 里没有 return/throw，永远不会结束
+    InfiniteLoop,
+    /// lint `dead_store`：赋的值在下一次赋值或者离开作用域之前没有被读取
+    DeadStore,
+    /// 自定义 lint 规则（见 `lint::Lint::code`）：规则名和说明在诊断信息的参数 `lint` / `message` 里
+    CustomLint,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 从字符串形式解析错误码；不认识的字符串返回 `Err(())`。
+impl FromStr for ErrorCode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        ErrorCode::ALL.iter().copied().find(|code| code.as_str() == s).ok_or(())
+    }
+}
+
+/// 和字符串形式比较：`assert_eq!(err.code, "UndeclaredVariable")`。
+impl PartialEq<str> for ErrorCode {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ErrorCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
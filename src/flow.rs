@@ -0,0 +1,501 @@
+use crate::ast::{AssignTarget, BinaryOp, Callee, Expr, FuncDecl, Param, Program, Stmt};
+use crate::codegen::free_vars;
+use crate::error::{Error, ErrorCode, NoteCode};
+use crate::span::Span;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 数据流检查的入口：明确赋值检查（definite assignment）。
+///
+/// 没有初始值的 `let x: number;` 要在每条执行路径上都赋过值之后才能读取，否则报 `UseOfUninitialized`，
+/// 位置是读取它的最内层语句/表达式，补充说明指向声明。没有这一步时生成的 Rust 代码会读取未初始化的变量，
+/// 等到 rustc 才报错。
+///
+/// 顶层语句和每个函数（顶层函数、块内函数、namespace 函数、组件和 Ability 的方法）的函数体分别检查。
+/// 嵌套函数捕获的变量在函数声明的位置就必须已经赋值：Rust 闭包创建时就要捕获变量。
+pub fn check(program: &Program) -> Result<(), Error> {
+    match errors(program).into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// `check` 的“报告全部错误”版本（见 `Diagnostics`）：每个函数、每条顶层语句单独检查，
+/// 一项里遇到错误就跳过这一项剩下的部分。
+pub fn errors(program: &Program) -> Vec<Error> {
+    let funcs = program
+        .funcs
+        .iter()
+        .chain(program.namespace_decls.iter().flat_map(|ns| &ns.funcs))
+        .chain(program.components.iter().flat_map(|c| &c.methods))
+        .chain(program.abilities.iter().flat_map(|a| &a.methods));
+    let mut errors: Vec<Error> = funcs.filter_map(|f| func(f).err()).collect();
+    // 出错的顶层语句当作执行完了，接着检查后面的语句
+    let mut state = Some(BTreeMap::new());
+    for s in &program.stmts {
+        if let Err(err) = stmt(s, &mut state) {
+            // 同一个变量后面的读取不再重复报告
+            if let (Some(set), Some(note)) = (state.as_mut(), err.notes.first()) {
+                set.retain(|_, decl| *decl != note.span);
+            }
+            errors.push(err);
+        }
+    }
+    errors
+}
+
+/// 明确赋值检查的状态：还没有明确赋值的变量 -> 它的声明位置；None 表示执行不到这里（前面已经 return/throw）。
+type Unassigned = Option<BTreeMap<String, Span>>;
+
+/// 两条执行路径汇合：任意一条路径上没赋值就算没赋值。
+fn join(a: Unassigned, b: Unassigned) -> Unassigned {
+    match (a, b) {
+        (None, x) | (x, None) => x,
+        (Some(mut a), Some(b)) => {
+            a.extend(b);
+            Some(a)
+        }
+    }
+}
+
+fn func(f: &FuncDecl) -> Result<(), Error> {
+    stmts(&f.body.stmts, &mut Some(BTreeMap::new())).map_err(|e| e.at(f.span.0))
+}
+
+fn stmts(list: &[Stmt], state: &mut Unassigned) -> Result<(), Error> {
+    let before = state.clone();
+    let mut declared = Vec::new();
+    for s in list {
+        if let Stmt::VarDecl(v) = s {
+            declared.extend(v.names().into_iter().map(String::from));
+        }
+        stmt(s, state)?;
+    }
+    // 离开块作用域：块内的变量可能遮蔽了外层同名变量，恢复外层变量的状态
+    if let (Some(after), Some(before)) = (state.as_mut(), before) {
+        for name in declared {
+            match before.get(&name) {
+                Some(span) => after.insert(name, *span),
+                None => after.remove(&name),
+            };
+        }
+    }
+    Ok(())
+}
+
+fn stmt(s: &Stmt, state: &mut Unassigned) -> Result<(), Error> {
+    stmt_kind(s, state).map_err(|e| e.at(s.span()))
+}
+
+fn stmt_kind(s: &Stmt, state: &mut Unassigned) -> Result<(), Error> {
+    match s {
+        Stmt::VarDecl(v) => {
+            for d in &v.decls {
+                if let Some(init) = &d.init {
+                    expr(init, state).map_err(|e| e.at(d.span.0))?;
+                }
+                if let Some(set) = state {
+                    for name in d.pattern.names() {
+                        if d.init.is_some() {
+                            set.remove(name);
+                        } else {
+                            set.insert(name.to_string(), v.span.0);
+                        }
+                    }
+                }
+            }
+        }
+        Stmt::Assign(a) => {
+            expr(&a.value, state)?;
+            if let Some(set) = state {
+                set.remove(&a.name);
+            }
+        }
+        Stmt::ExprStmt(e) => expr(e, state)?,
+        Stmt::Return(r) => {
+            if let Some(v) = &r.value {
+                expr(v, state)?;
+            }
+            *state = None;
+        }
+        Stmt::Throw(t) => {
+            expr(&t.value, state)?;
+            *state = None;
+        }
+        Stmt::Block(b) => stmts(&b.stmts, state)?,
+        Stmt::If(i) => {
+            expr(&i.cond, state)?;
+            let mut then_state = state.clone();
+            stmt(&i.then_branch, &mut then_state)?;
+            if let Some(e) = &i.else_branch {
+                stmt(e, state)?;
+            }
+            *state = join(then_state, state.take());
+        }
+        Stmt::While(w) => {
+            // 循环体可能一次都不执行，所以循环后的状态就是条件求值之后的状态
+            expr(&w.cond, state)?;
+            stmt(&w.body, &mut state.clone())?;
+        }
+        Stmt::FuncDecl(f) => {
+            if let Some(set) = state {
+                if let Some((name, span)) = free_vars(f).iter().find_map(|name| Some((name, set.get(name)?))) {
+                    return Err(uninitialized(name, *span));
+                }
+            }
+            func(f)?;
+        }
+        Stmt::Try(t) => {
+            let before = state.clone();
+            stmts(&t.block.stmts, state)?;
+            // try 块可能在任何位置抛出异常，catch 块从 try 之前的状态开始
+            if let Some(c) = &t.catch {
+                let mut caught = before.clone();
+                stmts(&c.body.stmts, &mut caught)?;
+                *state = join(state.take(), caught);
+            }
+            if let Some(f) = &t.finally {
+                let mut finally = before;
+                stmts(&f.stmts, &mut finally)?;
+                match (state.as_mut(), finally) {
+                    (Some(after), Some(finally)) => after.retain(|name, _| finally.contains_key(name)),
+                    (_, None) => *state = None,
+                    (None, Some(_)) => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn uninitialized(name: &str, decl: Span) -> Error {
+    let err = Error::new(ErrorCode::UseOfUninitialized, Span::default()).with_arg("name", name);
+    err.with_note("", decl, NoteCode::DeclaredHere)
+}
+
+/// 读取变量：还没有明确赋值时报错。
+fn read(state: &Unassigned, name: &str) -> Result<(), Error> {
+    match state.as_ref().and_then(|set| set.get(name)) {
+        Some(span) => Err(uninitialized(name, *span)),
+        None => Ok(()),
+    }
+}
+
+fn expr(e: &Expr, state: &mut Unassigned) -> Result<(), Error> {
+    expr_kind(e, state).map_err(|err| err.at(e.span()))
+}
+
+fn expr_kind(e: &Expr, state: &mut Unassigned) -> Result<(), Error> {
+    match e {
+        Expr::Literal(_, _) => {}
+        Expr::Ident(name, _) => read(state, name)?,
+        Expr::Unary(u) => expr(&u.expr, state)?,
+        Expr::Binary(b) => {
+            expr(&b.left, state)?;
+            match b.op {
+                // 短路运算的右侧不一定执行，里面的赋值不算
+                BinaryOp::AndAnd | BinaryOp::OrOr | BinaryOp::NullishCoalesce => expr(&b.right, &mut state.clone())?,
+                _ => expr(&b.right, state)?,
+            }
+        }
+        Expr::Group(inner) | Expr::Spread(inner) => expr(inner, state)?,
+        Expr::Member(m) => expr(&m.object, state)?,
+        Expr::Index(ix) => {
+            expr(&ix.object, state)?;
+            expr(&ix.index, state)?;
+        }
+        Expr::Call(c) => {
+            match &c.callee {
+                // 命名空间名不会出现在未赋值集合里，`s.trim()` 的 `s` 则要检查
+                Callee::Ident(name) | Callee::Member { object: name, .. } => read(state, name)?,
+                Callee::Expr(inner) | Callee::Method { object: inner, .. } => expr(inner, state)?,
+                Callee::Console(_) | Callee::Promise(_) => {}
+            }
+            for a in &c.args {
+                expr(a, state)?;
+            }
+        }
+        Expr::New(n) => {
+            for a in &n.args {
+                expr(a, state)?;
+            }
+        }
+        Expr::Assign(a) => {
+            match &a.target {
+                AssignTarget::Ident(_) => {}
+                AssignTarget::Member { object, .. } => read(state, object)?,
+                AssignTarget::Index { object, index } => {
+                    read(state, object)?;
+                    expr(index, state)?;
+                }
+            }
+            expr(&a.value, state)?;
+            if let (AssignTarget::Ident(name), Some(set)) = (&a.target, state.as_mut()) {
+                set.remove(name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 死存储（dead store）：赋给局部变量的值在下一次赋值之前、或者离开作用域之前都没有被读取，返回这些赋值的位置。
+///
+/// 每个函数体（以及顶层语句）从后往前做活跃变量分析（liveness）：某一点上“活跃”的变量是之后可能被读取的变量，
+/// 给不活跃的变量赋值就是死存储。循环迭代到不动点；try 块里每条语句都可能抛出异常，
+/// catch / finally 开头活跃的变量在整个 try 块里都算活跃。
+///
+/// 只看在这个函数体里声明的变量和参数：被嵌套函数捕获的变量随时可能被读取，不报；
+/// 声明时的初始值（`let x = 0;`）也不报。
+pub fn dead_stores(program: &Program) -> Vec<Span> {
+    let funcs = program
+        .funcs
+        .iter()
+        .chain(program.namespace_decls.iter().flat_map(|ns| &ns.funcs))
+        .chain(program.components.iter().flat_map(|c| &c.methods))
+        .chain(program.abilities.iter().flat_map(|a| &a.methods));
+    let mut dead = Vec::new();
+    // 顶层函数用到的顶层变量也当作被捕获
+    let mut captured = BTreeSet::new();
+    for f in funcs {
+        captured.extend(free_vars(f));
+        dead.extend(Liveness::body(&f.params, &f.body.stmts, BTreeSet::new()));
+    }
+    dead.extend(Liveness::body(&[], &program.stmts, captured));
+    dead.sort_by_key(|span| span.start);
+    dead.dedup();
+    dead
+}
+
+/// 活跃变量的集合。
+type Live = BTreeSet<String>;
+
+/// 一个函数体的活跃变量分析（见 `dead_stores`）。
+struct Liveness {
+    /// 检查死存储的变量：函数体里声明的变量和参数，去掉被嵌套函数捕获的
+    tracked: BTreeSet<String>,
+    /// 外层 try 的 catch / finally 开头活跃的变量：抛出异常、return 时跳到那里
+    handlers: Live,
+    dead: Vec<Span>,
+}
+
+impl Liveness {
+    fn body(params: &[Param], list: &[Stmt], mut captured: BTreeSet<String>) -> Vec<Span> {
+        let mut tracked: BTreeSet<String> = params.iter().map(|p| p.name.clone()).collect();
+        locals(list, &mut tracked, &mut captured);
+        tracked.retain(|name| !captured.contains(name));
+        let mut l = Liveness {
+            tracked,
+            handlers: Live::new(),
+            dead: Vec::new(),
+        };
+        // 函数结束时局部变量都不再活跃
+        l.stmts(list, &mut Live::new());
+        l.dead
+    }
+
+    /// 给变量赋值：之后不活跃就是死存储；赋值之前的值不再活跃。
+    fn store(&mut self, name: &str, span: Span, live: &mut Live) {
+        if self.tracked.contains(name) && !live.remove(name) {
+            self.dead.push(span);
+        }
+    }
+
+    fn stmts(&mut self, list: &[Stmt], live: &mut Live) {
+        let after = live.clone();
+        for s in list.iter().rev() {
+            live.extend(self.handlers.iter().cloned());
+            self.stmt(s, live);
+        }
+        // 块内声明的变量可能遮蔽了外层同名变量：进入块之前，外层变量的活跃状态和离开块之后一样
+        let declared = list.iter().filter_map(|s| match s {
+            Stmt::VarDecl(v) => Some(v.names()),
+            _ => None,
+        });
+        for name in declared.flatten() {
+            match after.contains(name) {
+                true => live.insert(name.to_string()),
+                false => live.remove(name),
+            };
+        }
+    }
+
+    fn stmt(&mut self, s: &Stmt, live: &mut Live) {
+        match s {
+            Stmt::VarDecl(v) => {
+                for d in v.decls.iter().rev() {
+                    for name in d.pattern.names() {
+                        live.remove(name);
+                    }
+                    if let Some(init) = &d.init {
+                        self.expr(init, live);
+                    }
+                }
+            }
+            Stmt::Assign(a) => {
+                self.store(&a.name, a.span.0, live);
+                self.expr(&a.value, live);
+            }
+            Stmt::ExprStmt(e) => self.expr(e, live),
+            Stmt::Return(r) => {
+                *live = self.handlers.clone();
+                if let Some(v) = &r.value {
+                    self.expr(v, live);
+                }
+            }
+            Stmt::Throw(t) => {
+                *live = self.handlers.clone();
+                self.expr(&t.value, live);
+            }
+            Stmt::Block(b) => self.stmts(&b.stmts, live),
+            Stmt::If(i) => {
+                let mut then_live = live.clone();
+                self.stmt(&i.then_branch, &mut then_live);
+                if let Some(e) = &i.else_branch {
+                    self.stmt(e, live);
+                }
+                live.extend(then_live);
+                self.expr(&i.cond, live);
+            }
+            Stmt::While(w) => {
+                // 循环开头活跃的变量：条件读取的、循环之后活跃的、循环体开头活跃的（循环体结束回到开头）。
+                // 迭代到不动点之前报告的死存储不算数
+                let after = live.clone();
+                let reported = self.dead.len();
+                let mut head = after.clone();
+                loop {
+                    let mut next = head.clone();
+                    self.stmt(&w.body, &mut next);
+                    next.extend(after.iter().cloned());
+                    self.expr(&w.cond, &mut next);
+                    if next == head {
+                        break;
+                    }
+                    head = next;
+                }
+                self.dead.truncate(reported);
+                let mut body = head;
+                self.stmt(&w.body, &mut body);
+                *live = after;
+                live.extend(body);
+                self.expr(&w.cond, live);
+            }
+            // 嵌套函数单独分析；它捕获的变量不在 `tracked` 里
+            Stmt::FuncDecl(f) => {
+                let dead = Liveness::body(&f.params, &f.body.stmts, BTreeSet::new());
+                self.dead.extend(dead);
+            }
+            Stmt::Try(t) => {
+                let mut finally = live.clone();
+                if let Some(f) = &t.finally {
+                    self.stmts(&f.stmts, &mut finally);
+                }
+                let outer = self.handlers.clone();
+                if t.finally.is_some() {
+                    self.handlers.extend(finally.iter().cloned());
+                }
+                let mut caught = Live::new();
+                if let Some(c) = &t.catch {
+                    caught = finally.clone();
+                    self.stmts(&c.body.stmts, &mut caught);
+                    if let Some(param) = &c.param {
+                        caught.remove(param);
+                    }
+                }
+                self.handlers.extend(caught);
+                *live = finally;
+                self.stmts(&t.block.stmts, live);
+                self.handlers = outer;
+            }
+        }
+    }
+
+    /// 表达式从右往左（和求值顺序相反）处理。
+    fn expr(&mut self, e: &Expr, live: &mut Live) {
+        match e {
+            Expr::Literal(_, _) => {}
+            Expr::Ident(name, _) => {
+                live.insert(name.clone());
+            }
+            Expr::Unary(u) => self.expr(&u.expr, live),
+            Expr::Binary(b) => {
+                match b.op {
+                    // 短路运算的右侧不一定执行
+                    BinaryOp::AndAnd | BinaryOp::OrOr | BinaryOp::NullishCoalesce => {
+                        let mut right = live.clone();
+                        self.expr(&b.right, &mut right);
+                        live.extend(right);
+                    }
+                    _ => self.expr(&b.right, live),
+                }
+                self.expr(&b.left, live);
+            }
+            Expr::Group(inner) | Expr::Spread(inner) => self.expr(inner, live),
+            Expr::Member(m) => self.expr(&m.object, live),
+            Expr::Index(ix) => {
+                self.expr(&ix.index, live);
+                self.expr(&ix.object, live);
+            }
+            Expr::Call(c) => {
+                for a in c.args.iter().rev() {
+                    self.expr(a, live);
+                }
+                match &c.callee {
+                    Callee::Ident(name) | Callee::Member { object: name, .. } => {
+                        live.insert(name.clone());
+                    }
+                    Callee::Expr(inner) | Callee::Method { object: inner, .. } => self.expr(inner, live),
+                    Callee::Console(_) | Callee::Promise(_) => {}
+                }
+            }
+            Expr::New(n) => {
+                for a in n.args.iter().rev() {
+                    self.expr(a, live);
+                }
+            }
+            Expr::Assign(a) => match &a.target {
+                AssignTarget::Ident(name) => {
+                    self.store(name, a.span.0, live);
+                    self.expr(&a.value, live);
+                }
+                // 改字段、改元素要读取对象
+                AssignTarget::Member { object, .. } => {
+                    self.expr(&a.value, live);
+                    live.insert(object.clone());
+                }
+                AssignTarget::Index { object, index } => {
+                    self.expr(&a.value, live);
+                    self.expr(index, live);
+                    live.insert(object.clone());
+                }
+            },
+        }
+    }
+}
+
+/// 语句里声明的变量（不进入嵌套函数）放进 `out`，嵌套函数捕获的名字放进 `captured`。
+fn locals(list: &[Stmt], out: &mut BTreeSet<String>, captured: &mut BTreeSet<String>) {
+    for s in list {
+        match s {
+            Stmt::VarDecl(v) => out.extend(v.names().into_iter().map(String::from)),
+            Stmt::Block(b) => locals(&b.stmts, out, captured),
+            Stmt::If(i) => {
+                locals(std::slice::from_ref(&i.then_branch), out, captured);
+                if let Some(e) = &i.else_branch {
+                    locals(std::slice::from_ref(e), out, captured);
+                }
+            }
+            Stmt::While(w) => locals(std::slice::from_ref(&w.body), out, captured),
+            Stmt::FuncDecl(f) => captured.extend(free_vars(f)),
+            Stmt::Try(t) => {
+                locals(&t.block.stmts, out, captured);
+                if let Some(c) = &t.catch {
+                    out.extend(c.param.clone());
+                    locals(&c.body.stmts, out, captured);
+                }
+                if let Some(f) = &t.finally {
+                    locals(&f.stmts, out, captured);
+                }
+            }
+            Stmt::Assign(_) | Stmt::ExprStmt(_) | Stmt::Return(_) | Stmt::Throw(_) => {}
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::codegen::Edition;
+use crate::error::{Error, ErrorCode};
+use crate::span::Span;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// 生成代码的排版选项：缩进、左大括号的位置、文件末尾的换行，见 `CodegenOptions::layout`。
+///
+/// 默认值和 rustfmt 的默认风格相同，这时 `apply` 不做任何修改。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutOptions {
+    /// 每一级缩进
+    pub indent: Indent,
+    /// 左大括号的位置
+    pub brace_style: BraceStyle,
+    /// 文件末尾是否有换行
+    pub trailing_newline: bool,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            indent: Indent::Spaces(4),
+            brace_style: BraceStyle::SameLine,
+            trailing_newline: true,
+        }
+    }
+}
+
+/// 一级缩进的写法。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// 若干个空格
+    Spaces(usize),
+    /// 一个 tab
+    Tabs,
+}
+
+impl Indent {
+    fn unit(self) -> String {
+        match self {
+            Indent::Spaces(n) => " ".repeat(n),
+            Indent::Tabs => "\t".to_string(),
+        }
+    }
+}
+
+/// 函数、类型、控制流语句的左大括号放在哪里。
+///
+/// 只影响以 `fn` / `impl` / `struct` / `if` / `else` / `while` / `match` 等关键字（或循环标签）开头的行，
+/// 结构体字面量、闭包、`let x = if ..` 这样表达式里的大括号保持不变（和 rustfmt 的 `brace_style` 相同）。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BraceStyle {
+    /// `fn main() {`（默认）
+    #[default]
+    SameLine,
+    /// 左大括号单独一行，和上一行对齐；`} else {` 拆成三行
+    NextLine,
+}
+
+/// 会开始一个代码块的关键字，见 `BraceStyle`。
+const BLOCK_KEYWORDS: &[&str] = &[
+    "fn", "async", "unsafe", "impl", "struct", "enum", "trait", "mod", "if", "else", "while", "for", "loop", "match",
+];
+
+/// 按 `options` 重新排版 CodeGen 生成的代码。
+///
+/// CodeGen 统一按 4 个空格一级缩进、左大括号跟在行尾生成代码（见 `codegen::push_indent`），这里逐行改成配置的样式。
+/// 生成的代码里没有跨行的字符串字面量，所以逐行处理不会改到字符串的内容。
+pub fn apply(code: &str, options: &LayoutOptions) -> String {
+    if *options == LayoutOptions::default() {
+        return code.to_string();
+    }
+    let unit = options.indent.unit();
+    let mut out = String::new();
+    for line in code.lines() {
+        let content = line.trim_start_matches(' ');
+        if content.is_empty() {
+            out.push('\n');
+            continue;
+        }
+        // 不满 4 个空格的部分是对齐用的（例如多行参数），原样保留
+        let spaces = line.len() - content.len();
+        let indent = unit.repeat(spaces / 4) + &" ".repeat(spaces % 4);
+        let mut push = |s: &str| {
+            out.push_str(&indent);
+            out.push_str(s);
+            out.push('\n');
+        };
+        match split_block_start(content).filter(|_| options.brace_style == BraceStyle::NextLine) {
+            Some((closes, head)) => {
+                if closes {
+                    push("}");
+                }
+                push(head);
+                push("{");
+            }
+            None => push(content),
+        }
+    }
+    if !options.trailing_newline {
+        out.truncate(out.trim_end_matches('\n').len());
+    }
+    out
+}
+
+/// 以 ` {` 结尾、开始一个代码块的行：返回（是否以 `} ` 开头，去掉大括号后的内容）。
+fn split_block_start(content: &str) -> Option<(bool, &str)> {
+    let head = content.strip_suffix(" {")?;
+    let (closes, head) = match head.strip_prefix("} ") {
+        Some(rest) => (true, rest),
+        None => (false, head),
+    };
+    let item = head.strip_prefix("pub(crate) ").or_else(|| head.strip_prefix("pub ")).unwrap_or(head);
+    let word = item.split([' ', '(', '<']).next().unwrap_or_default();
+    (BLOCK_KEYWORDS.contains(&word) || word.starts_with('\'')).then_some((closes, head))
+}
+
+/// 生成的代码交给哪个格式化工具，见 `CodegenOptions::formatter`。格式化在 `LayoutOptions` 之前进行。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Formatter {
+    /// 不格式化（默认），保持 CodeGen 的输出
+    #[default]
+    None,
+    /// 在进程内用 `prettyplease` 格式化，需要打开 crate 的 `prettyplease` feature。
+    /// `prettyplease` 不保留普通注释（`///` 文档注释会保留）
+    Prettyplease,
+    /// 调用 `PATH` 里的 `rustfmt`
+    Rustfmt,
+}
+
+/// 用 `formatter` 格式化一个 Rust 源文件，`edition` 是代码使用的 Rust edition。
+///
+/// 找不到 `rustfmt`、没有打开 `prettyplease` feature 时报 `FormatterUnavailable`；
+/// 格式化工具解析代码失败时报 `FormatFailed`。
+pub fn format(code: &str, formatter: Formatter, edition: Edition) -> Result<String, Error> {
+    match formatter {
+        Formatter::None => Ok(code.to_string()),
+        Formatter::Prettyplease => prettyplease(code),
+        Formatter::Rustfmt => rustfmt(code, edition),
+    }
+}
+
+#[cfg(feature = "prettyplease")]
+fn prettyplease(code: &str) -> Result<String, Error> {
+    let file = syn::parse_file(code).map_err(|_| Error::new(ErrorCode::FormatFailed, Span::default()))?;
+    Ok(prettyplease::unparse(&file))
+}
+
+#[cfg(not(feature = "prettyplease"))]
+fn prettyplease(_code: &str) -> Result<String, Error> {
+    Err(Error::new(ErrorCode::FormatterUnavailable, Span::default()))
+}
+
+fn rustfmt(code: &str, edition: Edition) -> Result<String, Error> {
+    let unavailable = |_| Error::new(ErrorCode::FormatterUnavailable, Span::default());
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", edition.as_str(), "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(unavailable)?;
+    // stdin 在这条语句结束时关闭，rustfmt 才会开始输出
+    child.stdin.take().expect("stdin is piped").write_all(code.as_bytes()).map_err(unavailable)?;
+    let out = child.wait_with_output().map_err(unavailable)?;
+    match String::from_utf8(out.stdout) {
+        Ok(formatted) if out.status.success() => Ok(formatted),
+        _ => Err(Error::new(ErrorCode::FormatFailed, Span::default())),
+    }
+}
@@ -1,17 +1,40 @@
-use crate::error::Error;
-use crate::lexer::token::{Token, TokenKind};
+use crate::error::{Error, ErrorCode};
+use crate::lexer::token::{Comment, Token, TokenKind};
 use crate::span::Span;
 
 /// 词法分析入口：把源代码切成一串 Token。
 ///
 /// Step1 目标：
 /// - 支持关键字/标识符/数字/字符串/运算符/符号
-/// - 跳过空白与注释
-/// - 出错时返回携带 Span 的 Error（包含 line/col）
+/// - 跳过空白与注释；注释挂在相邻的 token 上（见 `Token::leading` / `Token::trailing`）
+/// - 出错时返回携带 Span 的 Error（包含 line/col）；有多个错误时返回第一个
 pub fn lex(src: &str) -> Result<Vec<Token>, Error> {
+    let tokens = lex_recovering(src);
+    match tokens.iter().find_map(error_of) {
+        Some(err) => Err(err),
+        None => Ok(tokens),
+    }
+}
+
+/// 带错误恢复的词法分析：遇到非法字符、没闭合的字符串等不停下，
+/// 在出错的位置放一个 `TokenKind::Error` token，跳过出错的那段源码接着扫描。
+///
+/// - 非法字符：跳过这一个字符
+/// - 没闭合的字符串：跳到行尾（或 EOF）
+/// - 没闭合的块注释：跳到 EOF
+/// - 超出范围的十六进制数字：跳过整个数字
+pub fn lex_recovering(src: &str) -> Vec<Token> {
     Lexer::new(src).lex_all()
 }
 
+/// Error token 对应的错误（其它 token 返回 None）。
+pub fn error_of(token: &Token) -> Option<Error> {
+    match &token.kind {
+        TokenKind::Error(code) => Some(Error::new(*code, token.span)),
+        _ => None,
+    }
+}
+
 /// 词法分析器的内部状态（扫描指针）。
 ///
 /// 这里用 `byte_pos` 保存当前位置的 byte offset（UTF-8）。
@@ -21,6 +44,8 @@ struct Lexer<'a> {
     byte_pos: usize,
     line: usize,
     col: usize,
+    /// 已经跳过、还没挂到 token 上的注释
+    comments: Vec<Comment>,
 }
 
 impl<'a> Lexer<'a> {
@@ -30,23 +55,39 @@ impl<'a> Lexer<'a> {
             byte_pos: 0,
             line: 1,
             col: 1,
+            comments: Vec::new(),
         }
     }
 
-    /// 扫描整个输入，直到 EOF。
-    fn lex_all(mut self) -> Result<Vec<Token>, Error> {
+    /// 扫描整个输入，直到 EOF。出错的地方产生 Error token（见 `lex_recovering`）。
+    fn lex_all(mut self) -> Vec<Token> {
         let mut tokens = Vec::new();
 
         while !self.is_eof() {
             // 先跳过空白和注释，保证下一个字符是“有意义的 Token 起点”
-            self.skip_ws_and_comments()?;
+            if let Err(err) = self.skip_ws_and_comments() {
+                // 没闭合的块注释：从注释开头到 EOF 都算出错的源码
+                let start = Mark {
+                    offset: err.span.start,
+                    line: err.span.start_line,
+                    col: err.span.start_col,
+                };
+                tokens.push(self.token(TokenKind::Error(err.code), start));
+                break;
+            }
+            // 和前一个 token 在同一行开始的注释跟在它后面，其它的留给下一个 token
+            if let Some(prev) = tokens.last_mut() {
+                let line = prev.span.end_line;
+                let same_line = self.comments.iter().take_while(|c| c.span.start_line == line).count();
+                prev.trailing.extend(self.comments.drain(..same_line));
+            }
             if self.is_eof() {
                 break;
             }
 
             // 记录 token 起点位置（byte offset + line/col）
             let start_pos = self.mark();
-            let ch = self.peek_char().ok_or_else(|| self.err_at("UnexpectedEof", start_pos))?;
+            let Some(ch) = self.peek_char() else { break };
 
             // 根据当前字符决定要识别哪一种 token
             let kind = match ch {
@@ -66,13 +107,27 @@ impl<'a> Lexer<'a> {
                     self.bump_char();
                     TokenKind::RBrace
                 }
+                '[' => {
+                    self.bump_char();
+                    TokenKind::LBracket
+                }
+                ']' => {
+                    self.bump_char();
+                    TokenKind::RBracket
+                }
                 ',' => {
                     self.bump_char();
                     TokenKind::Comma
                 }
                 '.' => {
-                    self.bump_char();
-                    TokenKind::Dot
+                    // 匹配 `...` 或 `.`
+                    if self.peek_is("...") {
+                        self.bump_str("...");
+                        TokenKind::Ellipsis
+                    } else {
+                        self.bump_char();
+                        TokenKind::Dot
+                    }
                 }
                 ':' => {
                     self.bump_char();
@@ -82,6 +137,10 @@ impl<'a> Lexer<'a> {
                     self.bump_char();
                     TokenKind::Semicolon
                 }
+                '@' => {
+                    self.bump_char();
+                    TokenKind::At
+                }
                 '+' => {
                     self.bump_char();
                     TokenKind::Plus
@@ -104,9 +163,11 @@ impl<'a> Lexer<'a> {
                 }
                 '=' => {
                     self.bump_char();
-                    // 匹配 `==` 或 `=`
+                    // 匹配 `==`、`=>` 或 `=`
                     if self.try_bump('=') {
                         TokenKind::EqEq
+                    } else if self.try_bump('>') {
+                        TokenKind::FatArrow
                     } else {
                         TokenKind::Eq
                     }
@@ -144,47 +205,56 @@ impl<'a> Lexer<'a> {
                     if self.try_bump('&') {
                         TokenKind::AndAnd
                     } else {
-                        return Err(self.err_at("UnexpectedChar", start_pos));
+                        TokenKind::Error(ErrorCode::UnexpectedChar)
                     }
                 }
                 '|' => {
                     self.bump_char();
-                    // 只支持 `||`，单独的 `|` 在子集中是非法字符
+                    // `||` 是逻辑或；单独的 `|` 只用于联合类型标注
                     if self.try_bump('|') {
                         TokenKind::OrOr
                     } else {
-                        return Err(self.err_at("UnexpectedChar", start_pos));
+                        TokenKind::Pipe
                     }
                 }
-                '"' => self.lex_string()?,
-                c if c.is_ascii_digit() => self.lex_number()?,
+                '?' => {
+                    self.bump_char();
+                    // 只支持 `??`（空值合并），单独的 `?` 在子集中是非法字符
+                    if self.try_bump('?') {
+                        TokenKind::QuestionQuestion
+                    } else {
+                        TokenKind::Error(ErrorCode::UnexpectedChar)
+                    }
+                }
+                '"' | '\'' => self.lex_string().unwrap_or_else(|e| TokenKind::Error(e.code)),
+                c if c.is_ascii_digit() => self.lex_number().unwrap_or_else(|e| TokenKind::Error(e.code)),
                 c if is_ident_start(c) => self.lex_ident_or_keyword(),
                 _ => {
-                    // 其它字符：Step1 子集不支持，直接报错
+                    // 其它字符：Step1 子集不支持，报错并跳过这个字符
                     self.bump_char();
-                    return Err(self.err_at("UnexpectedChar", start_pos));
+                    TokenKind::Error(ErrorCode::UnexpectedChar)
                 }
             };
 
-            // token 结束位置：注意 `mark()` 取的是“当前扫描指针”，所以 end 是开区间
-            let end_pos = self.mark();
-            tokens.push(Token {
-                kind,
-                span: Span::new_with_line_col(
-                    start_pos.offset,
-                    end_pos.offset,
-                    start_pos.line,
-                    start_pos.col,
-                    end_pos.line,
-                    end_pos.col,
-                ),
-            });
+            tokens.push(self.token(kind, start_pos));
         }
 
-        Ok(tokens)
+        tokens
     }
 
-    /// 跳过空白与注释。
+    /// 构造一个从 `start` 到当前扫描指针的 token，前面还没挂上的注释都挂在它上面。
+    fn token(&mut self, kind: TokenKind, start: Mark) -> Token {
+        // token 结束位置：注意 `mark()` 取的是“当前扫描指针”，所以 end 是开区间
+        let end = self.mark();
+        Token {
+            kind,
+            span: Span::new_with_line_col(start.offset, end.offset, start.line, start.col, end.line, end.col),
+            leading: std::mem::take(&mut self.comments),
+            trailing: Vec::new(),
+        }
+    }
+
+    /// 跳过空白与注释，注释记到 `comments` 里。
     ///
     /// - 空白：` ` `\t` `\r` `\n`
     /// - 单行注释：`// ... \n`
@@ -202,6 +272,7 @@ impl<'a> Lexer<'a> {
             }
 
             if self.peek_is("//") {
+                let start = self.mark();
                 self.bump_str("//");
                 while let Some(ch) = self.peek_char() {
                     if ch == '\n' {
@@ -209,6 +280,7 @@ impl<'a> Lexer<'a> {
                     }
                     self.bump_char();
                 }
+                self.push_comment(start);
                 continue;
             }
 
@@ -220,8 +292,9 @@ impl<'a> Lexer<'a> {
                 }
                 if self.peek_is("*/") {
                     self.bump_str("*/");
+                    self.push_comment(start);
                 } else {
-                    return Err(self.err_at("UnterminatedBlockComment", start));
+                    return Err(self.err_at(ErrorCode::UnterminatedBlockComment, start));
                 }
                 continue;
             }
@@ -234,9 +307,41 @@ impl<'a> Lexer<'a> {
         Ok(())
     }
 
-    /// 读取连续数字，解析为 i32。
+    /// 记下从 `start` 到当前扫描指针的注释。
+    fn push_comment(&mut self, start: Mark) {
+        let end = self.mark();
+        self.comments.push(Comment {
+            text: self.src[start.offset..end.offset].to_string(),
+            span: Span::new_with_line_col(start.offset, end.offset, start.line, start.col, end.line, end.col),
+        });
+    }
+
+    /// 读取连续数字，解析为 i32；后面跟着 `.` 和数字时是小数，例如 `0.5`。
+    /// 可以带指数部分（`1e3`、`2.5E-4`、`1e+21`）：值是 i32 范围内的整数时按整数返回，否则按 `Float` 保存原来的写法。
+    ///
+    /// `0x` 开头的是十六进制整数（hilog 的 domain 常写成 `0xFF00`）。
+    ///
+    /// 超出 i32 范围的整数（`3000000000`）按 `Float` 返回，保存十进制的写法：f64 数字模式下是普通的数字，
+    /// i32 模式下由 CodeGen 报 `InvalidNumber`。十六进制超出 u64 时仍然在这里报 `InvalidNumber`。
     fn lex_number(&mut self) -> Result<TokenKind, Error> {
         let start = self.mark();
+        let rest = &self.src[self.byte_pos..];
+        if (rest.starts_with("0x") || rest.starts_with("0X"))
+            && rest[2..].starts_with(|c: char| c.is_ascii_hexdigit())
+        {
+            self.bump_char();
+            self.bump_char();
+            let mut digits = String::new();
+            while let Some(ch) = self.peek_char().filter(|c| c.is_ascii_hexdigit()) {
+                digits.push(ch);
+                self.bump_char();
+            }
+            return match (i32::from_str_radix(&digits, 16), u64::from_str_radix(&digits, 16)) {
+                (Ok(n), _) => Ok(TokenKind::Number(n)),
+                (_, Ok(n)) => Ok(TokenKind::Float(n.to_string())),
+                _ => Err(self.err_at(ErrorCode::InvalidNumber, start)),
+            };
+        }
         let mut s = String::new();
         while let Some(ch) = self.peek_char() {
             if ch.is_ascii_digit() {
@@ -246,38 +351,70 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
+        let rest = &self.src[self.byte_pos..];
+        if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            s.push('.');
+            self.bump_char();
+            while let Some(ch) = self.peek_char().filter(|c| c.is_ascii_digit()) {
+                s.push(ch);
+                self.bump_char();
+            }
+        }
+        // 指数部分：`e` / `E`，可以带符号，后面必须有数字
+        let rest = &self.src[self.byte_pos..];
+        let exponent = rest.strip_prefix(['e', 'E']).map(|r| r.strip_prefix(['+', '-']).unwrap_or(r));
+        if let Some(digits) = exponent.filter(|r| r.starts_with(|c: char| c.is_ascii_digit())) {
+            let marker = &rest[..rest.len() - digits.len()];
+            s.push_str(marker);
+            for _ in marker.chars() {
+                self.bump_char();
+            }
+            while let Some(ch) = self.peek_char().filter(|c| c.is_ascii_digit()) {
+                s.push(ch);
+                self.bump_char();
+            }
+            // 值是 i32 范围内的整数时（`1e3`）和直接写出来的整数相同
+            return Ok(match s.parse::<f64>() {
+                Ok(v) if v.fract() == 0.0 && v >= f64::from(i32::MIN) && v <= f64::from(i32::MAX) => TokenKind::Number(v as i32),
+                _ => TokenKind::Float(s),
+            });
+        }
+        if s.contains('.') {
+            return Ok(TokenKind::Float(s));
+        }
         match s.parse::<i32>() {
             Ok(n) => Ok(TokenKind::Number(n)),
-            Err(_) => Err(self.err_at("InvalidNumber", start)),
+            Err(_) => Ok(TokenKind::Float(s)),
         }
     }
 
-    /// 读取双引号字符串：`"..."`。
+    /// 读取字符串：`"..."` 或 `'...'`（import 路径常用单引号）。
     ///
-    /// 支持少量转义：`\"`, `\\`, `\n`, `\t`, `\r`。
+    /// 支持少量转义：`\"`, `\'`, `\\`, `\n`, `\t`, `\r`。
     /// 如果遇到换行或 EOF 还没闭合，则报 `UnterminatedString`。
     fn lex_string(&mut self) -> Result<TokenKind, Error> {
         let start = self.mark();
-        // 消费开头的 `"`
+        // 消费开头的引号，结尾必须是同一种引号
+        let quote = self.peek_char().unwrap_or('"');
         self.bump_char();
 
         let mut out = String::new();
         while let Some(ch) = self.peek_char() {
             match ch {
-                '"' => {
-                    // 消费结尾的 `"`
+                c if c == quote => {
+                    // 消费结尾的引号
                     self.bump_char();
                     return Ok(TokenKind::String(out));
                 }
                 '\n' => {
-                    return Err(self.err_at("UnterminatedString", start));
+                    return Err(self.err_at(ErrorCode::UnterminatedString, start));
                 }
                 '\\' => {
                     // 处理转义序列：先吃掉 `\`，再读一个字符作为转义目标
                     self.bump_char();
                     let esc = self
                         .peek_char()
-                        .ok_or_else(|| self.err_at("UnterminatedString", start))?;
+                        .ok_or_else(|| self.err_at(ErrorCode::UnterminatedString, start))?;
                     match esc {
                         '"' => {
                             out.push('"');
@@ -312,7 +449,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        Err(self.err_at("UnterminatedString", start))
+        Err(self.err_at(ErrorCode::UnterminatedString, start))
     }
 
     /// 读取标识符，并在此处做“关键字识别”。
@@ -335,8 +472,19 @@ impl<'a> Lexer<'a> {
             "else" => TokenKind::KwElse,
             "while" => TokenKind::KwWhile,
             "return" => TokenKind::KwReturn,
+            "try" => TokenKind::KwTry,
+            "catch" => TokenKind::KwCatch,
+            "finally" => TokenKind::KwFinally,
+            "throw" => TokenKind::KwThrow,
+            "class" => TokenKind::KwClass,
+            "extends" => TokenKind::KwExtends,
+            "new" => TokenKind::KwNew,
+            "import" => TokenKind::KwImport,
+            "export" => TokenKind::KwExport,
             "true" => TokenKind::KwTrue,
             "false" => TokenKind::KwFalse,
+            "null" => TokenKind::KwNull,
+            "undefined" => TokenKind::KwUndefined,
             _ => TokenKind::Ident(s),
         }
     }
@@ -389,7 +537,7 @@ impl<'a> Lexer<'a> {
     }
 
     /// 在某个位置构造一个错误（Span 的起止点都指向该位置）。
-    fn err_at(&self, code: &'static str, pos: Mark) -> Error {
+    fn err_at(&self, code: ErrorCode, pos: Mark) -> Error {
         let span = Span::new_with_line_col(
             pos.offset,
             pos.offset,
@@ -419,12 +567,12 @@ struct Mark {
     col: usize,
 }
 
-/// 标识符首字符规则：字母或 `_`。
+/// 标识符首字符规则：字母（包括中文等非 ASCII 的字母）、`_` 或 `$`（资源引用 `$r(..)` / `$rawfile(..)`）。
 fn is_ident_start(ch: char) -> bool {
-    ch == '_' || ch.is_ascii_alphabetic()
+    ch == '_' || ch == '$' || ch.is_alphabetic()
 }
 
-/// 标识符后续字符规则：字母/数字/`_`。
+/// 标识符后续字符规则：字母/数字/`_`/`$`。
 fn is_ident_continue(ch: char) -> bool {
-    is_ident_start(ch) || ch.is_ascii_digit()
+    is_ident_start(ch) || ch.is_alphanumeric()
 }
@@ -1,8 +1,9 @@
 /// Lexer 模块：负责把源代码字符串切成 Token 序列。
+#[allow(clippy::module_inception)]
 pub mod lexer;
 pub mod token;
 
-/// 对外导出：`lex(src)` 入口函数。
-pub use lexer::lex;
-/// 对外导出：Token 数据结构。
-pub use token::{Token, TokenKind};
+/// 对外导出：`lex(src)` 入口函数，以及带错误恢复的 `lex_recovering(src)`。
+pub use lexer::{error_of, lex, lex_recovering};
+/// 对外导出：Token 数据结构和挂在 token 上的注释。
+pub use token::{Comment, Token, TokenKind};
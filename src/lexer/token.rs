@@ -1,3 +1,4 @@
+use crate::error::ErrorCode;
 use crate::span::Span;
 
 /// 一个 Token = 词法分析后的最小“语法积木”。
@@ -10,6 +11,58 @@ pub struct Token {
     pub kind: TokenKind,
     /// Token 在源代码中的位置
     pub span: Span,
+    /// 写在这个 token 前面的注释（不在前一个 token 的同一行）
+    pub leading: Vec<Comment>,
+    /// 和这个 token 在同一行、跟在它后面的注释：`foo(); // 说明`
+    pub trailing: Vec<Comment>,
+}
+
+/// 源码里的一条注释：`// ..` 或 `/* .. */`。
+///
+/// 词法分析不把注释当作 token，而是挂在相邻的 token 上（见 `Token::leading` / `Token::trailing`），
+/// Parser 再把它们记到语句上（见 `ast::Comments`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    /// 注释原文，包括 `//`、`/*` `*/`
+    pub text: String,
+    /// 注释在源代码中的位置
+    pub span: Span,
+}
+
+impl Comment {
+    /// 是否是块注释 `/* .. */`
+    pub fn is_block(&self) -> bool {
+        self.text.starts_with("/*")
+    }
+
+    /// 是否是 JSDoc 注释 `/** .. */`（`/**/` 不算）
+    pub fn is_doc(&self) -> bool {
+        self.text.starts_with("/**") && self.text != "/**/"
+    }
+
+    /// 注释的内容，一行一项：去掉 `//`、`/*` `*/`；块注释去掉每行开头的空白和 `*`，
+    /// 首尾的空行也去掉。
+    pub fn lines(&self) -> Vec<&str> {
+        if let Some(line) = self.text.strip_prefix("//") {
+            return vec![line.trim_end()];
+        }
+        let body = self.text.trim_start_matches("/*").trim_start_matches('*');
+        let body = body.strip_suffix("*/").unwrap_or(body);
+        let mut lines: Vec<&str> = body
+            .lines()
+            .map(|l| {
+                let l = l.trim();
+                let l = l.strip_prefix('*').unwrap_or(l);
+                l.strip_prefix(' ').unwrap_or(l).trim_end()
+            })
+            .collect();
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        let blank = lines.iter().take_while(|l| l.is_empty()).count();
+        lines.drain(..blank);
+        lines
+    }
 }
 
 /// Token 的种类枚举。
@@ -25,14 +78,28 @@ pub enum TokenKind {
     KwElse,
     KwWhile,
     KwReturn,
+    KwTry,
+    KwCatch,
+    KwFinally,
+    KwThrow,
+    KwClass,
+    KwExtends,
+    KwNew,
+    KwImport,
+    KwExport,
     KwTrue,
     KwFalse,
+    KwNull,
+    KwUndefined,
 
     // ---------- 语义性 Token（携带值） ----------
     /// 标识符：例如 `abc`、`x1`、`_tmp`
     Ident(String),
     /// 整数字面量（ArkTS number 子集在后续会映射为 Rust i32，所以这里直接存 i32）
     Number(i32),
+    /// 小数字面量：例如 `0.5`，保存源码里的写法（只有 f64 数字模式能用，见 `CodegenOptions::number_mode`）。
+    /// 超出 i32 范围的整数也是它，保存十进制的写法：`3000000000`
+    Float(String),
     /// 字符串字面量（支持少量转义）
     String(String),
 
@@ -41,10 +108,16 @@ pub enum TokenKind {
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
     Comma,
     Dot,
+    /// 展开/剩余运算符：`...`
+    Ellipsis,
     Colon,
     Semicolon,
+    /// 装饰器前缀：`@Component`
+    At,
 
     // ---------- 运算符 ----------
     Plus,
@@ -62,6 +135,84 @@ pub enum TokenKind {
 
     AndAnd,
     OrOr,
+    /// 空值合并：`a ?? b`
+    QuestionQuestion,
+    /// 联合类型分隔符：`string | null`
+    Pipe,
     Not,
     Eq,
+    /// 函数类型/箭头：`=>`
+    FatArrow,
+
+    // ---------- 词法错误 ----------
+    /// 无法识别的一段源码，携带错误代码（例如 `UnexpectedChar`、`UnterminatedString`），span 覆盖这段源码。
+    ///
+    /// 只有 `lex_recovering` 会产生：遇到错误不停下，记一个 Error token 接着扫描，
+    /// 后面的解析器和工具（高亮、编辑器插件）照样能处理出错的文件。
+    Error(ErrorCode),
+}
+
+impl TokenKind {
+    /// 给人看的 token 描述，用在语法错误的说明里（见 `Error::message`）：
+    /// 符号和关键字写成 `` `)` ``、`` `let` ``，携带值的 token 带上它的值，例如 ``identifier `x` ``。
+    pub fn describe(&self) -> String {
+        let text = match self {
+            TokenKind::KwLet => "let",
+            TokenKind::KwConst => "const",
+            TokenKind::KwFunction => "function",
+            TokenKind::KwIf => "if",
+            TokenKind::KwElse => "else",
+            TokenKind::KwWhile => "while",
+            TokenKind::KwReturn => "return",
+            TokenKind::KwTry => "try",
+            TokenKind::KwCatch => "catch",
+            TokenKind::KwFinally => "finally",
+            TokenKind::KwThrow => "throw",
+            TokenKind::KwClass => "class",
+            TokenKind::KwExtends => "extends",
+            TokenKind::KwNew => "new",
+            TokenKind::KwImport => "import",
+            TokenKind::KwExport => "export",
+            TokenKind::KwTrue => "true",
+            TokenKind::KwFalse => "false",
+            TokenKind::KwNull => "null",
+            TokenKind::KwUndefined => "undefined",
+            TokenKind::Ident(s) => return format!("identifier `{s}`"),
+            TokenKind::Number(n) => return format!("number `{n}`"),
+            TokenKind::Float(s) => return format!("number `{s}`"),
+            TokenKind::String(s) => return format!("string {s:?}"),
+            TokenKind::Error(_) => return "invalid token".to_string(),
+            TokenKind::LParen => "(",
+            TokenKind::RParen => ")",
+            TokenKind::LBrace => "{",
+            TokenKind::RBrace => "}",
+            TokenKind::LBracket => "[",
+            TokenKind::RBracket => "]",
+            TokenKind::Comma => ",",
+            TokenKind::Dot => ".",
+            TokenKind::Ellipsis => "...",
+            TokenKind::Colon => ":",
+            TokenKind::Semicolon => ";",
+            TokenKind::At => "@",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::EqEq => "==",
+            TokenKind::NotEq => "!=",
+            TokenKind::LtEq => "<=",
+            TokenKind::GtEq => ">=",
+            TokenKind::Lt => "<",
+            TokenKind::Gt => ">",
+            TokenKind::AndAnd => "&&",
+            TokenKind::OrOr => "||",
+            TokenKind::QuestionQuestion => "??",
+            TokenKind::Pipe => "|",
+            TokenKind::Not => "!",
+            TokenKind::Eq => "=",
+            TokenKind::FatArrow => "=>",
+        };
+        format!("`{text}`")
+    }
 }
@@ -1,9 +1,24 @@
 pub mod ast;
+pub mod cargo;
 pub mod codegen;
+pub mod diagnostics;
 pub mod error;
+pub mod error_code;
+pub mod flow;
+pub mod layout;
 pub mod lexer;
+pub mod lint;
+pub mod messages;
+pub mod module;
 pub mod parser;
+pub mod rename;
+pub mod runtime;
+pub mod sema;
+pub mod source_map;
 pub mod span;
+pub mod typeck;
+pub mod ui;
+pub mod verify;
 
 /// crate 的模块导出。
 ///
@@ -15,12 +30,24 @@ pub mod span;
 /// - 测试更方便：tests/ 更像“外部用户”，只调用 lib 暴露的函数。
 /// - 复用更容易：未来其它 Rust 项目也能直接依赖这个库。
 pub use ast::{
-    Callee, CallExpr, Expr, FuncDecl, Literal, Param, Program, Stmt, TypeAnn, VarDecl,
+    AbilityDecl, Alias, BuilderDecl, Callee, CallExpr, CatchClause, ClassDecl, Comments, ComponentDecl, ConsoleFn, Declarator, Decorator, Expr, FieldDecl,
+    FuncDecl, FuncTypeAnn, ImportClause, ImportDecl, ImportSpec, Literal, Namespace, NamespaceDecl, NewExpr, NullKind, Param,
+    Pattern, Program, PromiseFn, PropPattern, ReExportDecl, Stmt, StyleDecl, SystemImport, ThrowStmt, TryStmt, TypeAnn, UiArg, UiAttr, UiElement,
+    UiHandler, UiIf, UiNode, VarDecl,
 };
-pub use error::Error;
-pub use lexer::{lex, Token, TokenKind};
-pub use parser::parse as parse_tokens;
-pub use span::Span;
+pub use codegen::{ArithMode, CodegenOptions, Edition, ExternBindings, HilogMode, Intrinsics, NanMode, NumberMode, OutputFile, OutputMode, StringMode};
+pub use diagnostics::{CompileResult, Diagnostic, Diagnostics, Severity};
+pub use error::{Error, ErrorCode, Expected, Note, NoteCode, Warning};
+pub use layout::{BraceStyle, Formatter, Indent, LayoutOptions};
+pub use lexer::{lex, lex_recovering, Comment, Token, TokenKind};
+pub use lint::{Level, Lint, LintConfig, LintContext};
+pub use messages::Lang;
+pub use module::{starter_system_modules, ResolveConfig, SourceFiles, SystemModules, Unit, Use};
+pub use parser::{parse as parse_tokens, parse_recovering, parse_with as parse_tokens_with, ParseOptions};
+pub use rename::NameMap;
+pub use source_map::{Mapping, SourceMap};
+pub use span::{NodeId, NodeSpan, Span};
+pub use ui::{BuilderBackend, EguiBackend, UiBackend, UiBackendRef, UiCall, UiCode};
 
 /// 辅助函数：直接从源代码解析出 Program AST。
 ///
@@ -30,6 +57,12 @@ pub fn parse_program(src: &str) -> Result<Program, Error> {
     parse_tokens(&tokens)
 }
 
+/// `parse_program` 的指定解析选项版本，警告收集在 `Program::warnings` 里。
+pub fn parse_program_with(src: &str, options: &ParseOptions) -> Result<Program, Error> {
+    let tokens = lex(src)?;
+    parse_tokens_with(&tokens, options)
+}
+
 /// 编译入口：把 ArkTS 子集源码编译成 Rust 源码字符串。
 ///
 /// 目前 Step3 的流水线是：
@@ -38,8 +71,195 @@ pub fn parse_program(src: &str) -> Result<Program, Error> {
 /// 3. CodeGen：`Program` -> Rust 源码字符串
 ///
 /// 注意：这一步的“compile”只生成 Rust 源码，不会自动调用 rustc 去编译。
+///
+/// 出错时只返回第一个错误；要一次拿到全部错误和警告用 `compile_with_diagnostics`。
 pub fn compile(src: &str) -> Result<String, Error> {
+    compile_with_options(src, &CompileOptions::default())
+}
+
+/// 单文件编译的全部配置（见 `compile_with_options`）。默认值就是 `compile` 的行为。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// 输出形式：可执行程序、库还是原生模块
+    pub mode: OutputMode,
+    /// 解析选项（自动分号插入等）
+    pub parse: ParseOptions,
+    /// 代码生成选项：number 的映射方式、truthy 条件、UI 后端、snake_case 改名、加在文件开头的代码等
+    pub codegen: CodegenOptions,
+    /// `declare function` 的调用转发到的 Rust 路径（见 `ExternBindings`）
+    pub externs: ExternBindings,
+    /// lint 规则的级别和登记的自定义规则：设成 `Deny` 的规则报告成错误，编译失败（见 `LintConfig`）
+    pub lints: LintConfig,
+}
+
+/// 按 `options` 编译单个源文件：解析 -> 全部检查（`check_program_typed`）-> CodeGen。
+///
+/// 出错时返回第一个错误；警告不影响结果，要拿到警告用 `compile_with_diagnostics`。
+pub fn compile_with_options(src: &str, options: &CompileOptions) -> Result<String, Error> {
     let tokens = lex(src)?;
-    let program = parse_tokens(&tokens)?;
-    codegen::generate(&program)
+    let program = parse_tokens_with(&tokens, &options.parse)?;
+    generate_checked(&program, options.mode, &options.externs, &options.codegen, &options.lints)
+}
+
+/// 对 `program` 做一次全部检查（`check_program_typed`），有错误时返回第一个错误，没有时用检查的结果生成代码，
+/// CodeGen 不再重复检查。`Deny` 的 lint 只在检查里报告，CodeGen 不会因为它失败。
+fn generate_checked(
+    program: &Program,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    options: &CodegenOptions,
+    lints: &LintConfig,
+) -> Result<String, Error> {
+    let (diagnostics, types) = check_program_typed(program, options, lints);
+    if let Some(err) = diagnostics.first_error() {
+        return Err(err);
+    }
+    codegen::generate_checked(program, &types, mode, externs, options)
+}
+
+/// `compile` 的“报告全部问题”版本：不在第一个错误处停下，返回生成的代码（有错误时为 None）
+/// 和这次编译的全部诊断信息（警告、各个检查找到的所有错误，见 `check_program`）。
+///
+/// 有词法、语法错误时报告全部这些错误（见 `lex_recovering`、`parse_recovering`），不再做后面的检查。
+pub fn compile_with_diagnostics(src: &str) -> CompileResult {
+    let tokens = lex_recovering(src);
+    let (program, errors) = parse_recovering(&tokens, &ParseOptions::default());
+    if !errors.is_empty() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.extend(program.warnings);
+        diagnostics.extend(errors);
+        return CompileResult {
+            output: None,
+            diagnostics,
+        };
+    }
+    let options = CodegenOptions::default();
+    let (diagnostics, types) = check_program_typed(&program, &options, &LintConfig::default());
+    if diagnostics.has_errors() {
+        return CompileResult {
+            output: None,
+            diagnostics,
+        };
+    }
+    match codegen::generate_checked(&program, &types, OutputMode::Binary, &ExternBindings::new(), &options) {
+        Ok(code) => CompileResult {
+            output: Some(code),
+            diagnostics,
+        },
+        Err(e) => CompileResult {
+            output: None,
+            diagnostics: diagnostics.or_error(e),
+        },
+    }
+}
+
+/// 对解析（或链接）好的程序运行 CodeGen 之前的全部检查，收集所有问题：
+/// 1. 解析时的警告（`Program::warnings`）
+/// 2. 语义检查 `sema`：有错误时报告全部错误，没有错误时报告它的警告（没有使用的变量等）
+/// 3. 类型检查 `typeck` 的全部错误和警告（truthy 条件），数据流检查 `flow` 的全部错误
+/// 4. 前面都没有错误时，按默认级别运行内置的 lint 规则（见 `lint::builtin_lints`）和 `LintConfig::register` 登记的自定义规则
+///
+/// `truthy_conditions` 同 `CodegenOptions::truthy_conditions`。CodeGen 本身只报告第一个错误，
+/// CLI 和 `compile_with_diagnostics` 用这里的结果一次报告全部问题。
+pub fn check_program(program: &Program, truthy_conditions: bool) -> Diagnostics {
+    check_program_with(program, truthy_conditions, &LintConfig::default())
+}
+
+/// `check_program` 的指定 lint 级别版本：`lints` 里设成 `Deny` 的规则报告成错误，`Allow` 的不运行。
+pub fn check_program_with(program: &Program, truthy_conditions: bool, lints: &LintConfig) -> Diagnostics {
+    check_program_typed(program, &CodegenOptions { truthy_conditions, ..CodegenOptions::default() }, lints).0
+}
+
+/// `check_program_with` 的完整版本：按 CodeGen 的全部选项做类型检查（数字模式决定哪些表达式是小数，见 `typeck::check_with`），
+/// 同时返回类型检查的结果。没有错误时把结果交给 `codegen::generate_checked`，CodeGen 不再重复检查。
+pub fn check_program_typed(program: &Program, options: &CodegenOptions, lints: &LintConfig) -> (Diagnostics, typeck::Types) {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.extend(program.warnings.iter().cloned());
+    let errors = sema::errors_with(program, &options.intrinsics);
+    if errors.is_empty() {
+        diagnostics.extend(sema::warnings_with(program, &options.intrinsics));
+    }
+    diagnostics.extend(errors);
+    let (types, errors, warnings) = typeck::run(program, options);
+    diagnostics.extend(warnings);
+    diagnostics.extend(errors);
+    diagnostics.extend(flow::errors(program));
+    // lint 只看能通过检查的程序：有错误的代码先修错误
+    if !diagnostics.has_errors() {
+        diagnostics.extend(lint::run(program, &types, &lints.lints(), lints));
+    }
+    (diagnostics, types)
+}
+
+/// 按指定输出形式编译：`OutputMode::Library` 生成不带 `main` 的库代码。
+pub fn compile_as(src: &str, mode: OutputMode) -> Result<String, Error> {
+    compile_with_options(src, &CompileOptions { mode, ..CompileOptions::default() })
+}
+
+/// 多文件编译入口：从入口 `.ets` 文件出发，加载它 import 的所有模块，
+/// 合并后生成一个 Rust 源码字符串。
+///
+/// 流水线：`module::load_modules` -> `module::link` -> 全部检查（合并后的程序上做一次，见 `check_program_typed`）-> CodeGen。
+/// 出错时 `Error::file` 指出是哪个文件（合并后才发现的问题按位置找回所在的模块，见 `SourceFiles`）。
+pub fn compile_file(entry: impl AsRef<std::path::Path>) -> Result<String, Error> {
+    compile_file_as(entry, OutputMode::Binary)
+}
+
+/// `compile_file` 的指定输出形式版本。
+pub fn compile_file_as(
+    entry: impl AsRef<std::path::Path>,
+    mode: OutputMode,
+) -> Result<String, Error> {
+    compile_file_with(
+        entry,
+        mode,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &CodegenOptions::default(),
+    )
+}
+
+/// `compile_file_as` 的完整配置版本：
+/// - `externs`：`declare function`（通常写在 `.d.ets` 声明文件里）的调用转发到配置的 Rust 路径
+/// - `resolve`：模块路径的解析方式（根目录、`@common/*` 之类的路径别名、尝试的后缀）
+/// - `options`：代码生成选项（例如 console 输出是否保持 `{:?}` 调试格式）
+pub fn compile_file_with(
+    entry: impl AsRef<std::path::Path>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    resolve: &ResolveConfig,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    let modules = module::load_modules_with(entry.as_ref(), resolve).map_err(first_error)?;
+    let files = SourceFiles::of(&modules);
+    let located = |e: Error| e.locate(&files);
+    let program = module::link(modules).map_err(|d| first_error(d.locate(&files)))?;
+    generate_checked(&program, mode, externs, options, &LintConfig::default()).map_err(located)
+}
+
+/// 只返回一个错误的接口报告的错误：诊断信息里的第一个错误。
+fn first_error(diagnostics: Diagnostics) -> Error {
+    diagnostics.first_error().unwrap_or_else(|| Error::new(ErrorCode::CompileFailed, Span::default()))
+}
+
+/// 模块树编译入口：每个 `.ets` 文件生成一个 Rust 模块文件，而不是拼成一个文件。
+///
+/// 返回的第一个文件是 crate 根（`main.rs` / 库模式下 `lib.rs`），其余文件的路径相对于它所在的目录，
+/// 例如 `util.ets` -> `util.rs`，根文件里有对应的 `mod util;`。
+pub fn compile_tree(
+    entry: impl AsRef<std::path::Path>,
+    mode: OutputMode,
+    externs: &ExternBindings,
+    resolve: &ResolveConfig,
+    options: &CodegenOptions,
+) -> Result<Vec<OutputFile>, Error> {
+    let modules = module::load_modules_with(entry.as_ref(), resolve).map_err(first_error)?;
+    let files = SourceFiles::of(&modules);
+    let located = |e: Error| e.locate(&files);
+    let (program, units) = module::link_tree(modules).map_err(|d| first_error(d.locate(&files)))?;
+    let (diagnostics, types) = check_program_typed(&program, options, &LintConfig::default());
+    if let Some(err) = diagnostics.first_error() {
+        return Err(located(err));
+    }
+    codegen::generate_tree_checked(&program, &units, &types, mode, externs, options).map_err(located)
 }
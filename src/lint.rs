@@ -0,0 +1,546 @@
+use crate::ast::{AssignTarget, BinaryOp, Callee, Expr, FuncDecl, Literal, Program, Stmt, TypeAnn, UnaryOp};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::error::ErrorCode;
+use crate::flow;
+use crate::span::Span;
+use crate::typeck::Types;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// lint 规则的级别：不报、报警告（默认），还是当成错误（编译失败）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// 一条 lint 规则：找出能编译、但很可能写错了的代码（例如 `x = x;`），报告成警告。
+///
+/// 和 `sema` / `typeck` 不同，lint 不影响编译能不能通过，可以按名字关掉或者升级成错误（见 `LintConfig`）。
+/// 规则只需要实现关心的钩子：`run` 按源码顺序遍历整个程序（顶层语句、各种函数的函数体、嵌套函数），
+/// 对每个函数、语句、表达式调用对应的钩子。`LintContext::type_of` 可以查到 `typeck` 推断出来的类型。
+pub trait Lint {
+    /// 规则名：snake_case，CLI 的 `--allow` / `--warn` / `--deny` 用它，例如 `self_assignment`。
+    fn name(&self) -> &'static str;
+
+    /// 报告时用的错误码：内置规则有各自的错误码，自定义规则默认是 `ErrorCode::CustomLint`，
+    /// 诊断信息的参数里带上规则名（`lint`）和 `message`。
+    fn code(&self) -> ErrorCode {
+        ErrorCode::CustomLint
+    }
+
+    /// 自定义规则报告的说明文字（内置规则的说明在消息表里，不用这个）。
+    fn message(&self) -> String {
+        format!("lint `{}`", self.name())
+    }
+
+    /// 没有配置时的级别。
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    /// 开始遍历之前，对整个程序调用一次。
+    fn check_program(&self, _program: &Program, _cx: &mut LintContext) {}
+
+    /// 每个函数（顶层函数、嵌套函数、namespace 函数、组件和 Ability 的方法）。
+    fn check_func(&self, _f: &FuncDecl, _cx: &mut LintContext) {}
+
+    /// 每条语句（包括代码块、分支里的语句）。
+    fn check_stmt(&self, _s: &Stmt, _cx: &mut LintContext) {}
+
+    /// 每个表达式（包括子表达式）。
+    fn check_expr(&self, _e: &Expr, _cx: &mut LintContext) {}
+}
+
+/// 规则在钩子里用到的上下文：查类型、报告问题。
+pub struct LintContext<'a> {
+    types: &'a Types,
+    /// 最内层有位置的语句/表达式，没有位置的表达式（手写的 AST）用它报告
+    span: Span,
+    found: Vec<Span>,
+}
+
+impl LintContext<'_> {
+    /// `typeck` 推断出来的表达式类型（见 `typeck::Types`），推断不出来时为 None。
+    pub fn type_of(&self, e: &Expr) -> Option<&TypeAnn> {
+        self.types.get(e)
+    }
+
+    /// 报告一处问题；`span` 是 `Span::default()` 时用包含它的最内层语句/表达式的位置。
+    pub fn report(&mut self, span: Span) {
+        let span = if span == Span::default() { self.span } else { span };
+        self.found.push(span);
+    }
+}
+
+/// 各条规则的级别配置：没有配置的规则用它的 `default_level`。
+///
+/// 自定义规则也登记在这里（见 `register`），`check_program_typed` / `compile_with_options` 在内置规则之后运行它们。
+#[derive(Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<String, Level>,
+    custom: Vec<Arc<dyn Lint>>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置规则的级别，`name` 是 `Lint::name`。
+    pub fn set(&mut self, name: impl Into<String>, level: Level) {
+        self.levels.insert(name.into(), level);
+    }
+
+    /// 规则实际使用的级别。
+    pub fn level(&self, lint: &dyn Lint) -> Level {
+        self.levels.get(lint.name()).copied().unwrap_or_else(|| lint.default_level())
+    }
+
+    /// 登记一条自定义规则，级别同样用 `set` 按规则名配置。
+    pub fn register(&mut self, lint: impl Lint + 'static) {
+        self.custom.push(Arc::new(lint));
+    }
+
+    /// 登记的自定义规则，按登记的顺序。
+    pub fn custom_lints(&self) -> &[Arc<dyn Lint>] {
+        &self.custom
+    }
+
+    /// 要运行的全部规则：内置规则加上登记的自定义规则。
+    pub(crate) fn lints(&self) -> Vec<Arc<dyn Lint>> {
+        builtin_lints().into_iter().map(Arc::from).chain(self.custom.iter().cloned()).collect()
+    }
+
+    fn custom_names(&self) -> Vec<&'static str> {
+        self.custom.iter().map(|lint| lint.name()).collect()
+    }
+}
+
+/// 规则本身没法比较和打印，按规则名处理。
+impl fmt::Debug for LintConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LintConfig").field("levels", &self.levels).field("custom", &self.custom_names()).finish()
+    }
+}
+
+impl PartialEq for LintConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.levels == other.levels && self.custom_names() == other.custom_names()
+    }
+}
+
+impl Eq for LintConfig {}
+
+/// 内置的 lint 规则。
+pub fn builtin_lints() -> Vec<Box<dyn Lint>> {
+    vec![
+        Box::new(SelfAssignment),
+        Box::new(EmptyBranch),
+        Box::new(SelfComparison),
+        Box::new(ConstantCondition),
+        Box::new(InfiniteLoop),
+        Box::new(DeadStore),
+    ]
+}
+
+/// 对程序运行 `lints` 里的规则，按 `config` 的级别报告：`Warn` 是警告，`Deny` 是错误，`Allow` 的规则不运行。
+///
+/// `types` 是 `typeck` 对同一个 `program` 推断出来的类型。结果按位置排序，同一条规则在同一个位置只报一次。
+/// 错误码是 `ErrorCode::CustomLint` 的规则，诊断信息的参数是规则名 `lint` 和说明 `message`。
+pub fn run<L: AsRef<dyn Lint>>(program: &Program, types: &Types, lints: &[L], config: &LintConfig) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    for lint in lints {
+        let lint = lint.as_ref();
+        let severity = match config.level(lint) {
+            Level::Allow => continue,
+            Level::Warn => Severity::Warning,
+            Level::Deny => Severity::Error,
+        };
+        let mut cx = LintContext {
+            types,
+            span: Span::default(),
+            found: Vec::new(),
+        };
+        lint.check_program(program, &mut cx);
+        let mut walker = Walker { lint, cx };
+        walker.program(program);
+        let mut found = walker.cx.found;
+        found.sort_by_key(|span| span.start);
+        found.dedup();
+        let code = lint.code();
+        let args = match code {
+            ErrorCode::CustomLint => vec![("lint", lint.name().to_string()), ("message", lint.message())],
+            _ => Vec::new(),
+        };
+        out.extend(found.into_iter().map(|span| Diagnostic {
+            args: args.clone(),
+            ..Diagnostic::new(severity, code, span)
+        }));
+    }
+    out.sort_by_key(|d| (d.span.start, d.code.as_str()));
+    out
+}
+
+/// 按源码顺序遍历程序，对每个节点调用一条规则的钩子。
+struct Walker<'l, 'a> {
+    lint: &'l dyn Lint,
+    cx: LintContext<'a>,
+}
+
+impl Walker<'_, '_> {
+    fn program(&mut self, program: &Program) {
+        self.stmts(&program.stmts);
+        let funcs = program
+            .funcs
+            .iter()
+            .chain(program.namespace_decls.iter().flat_map(|ns| &ns.funcs))
+            .chain(program.components.iter().flat_map(|c| &c.methods))
+            .chain(program.abilities.iter().flat_map(|a| &a.methods));
+        for f in funcs {
+            self.func(f);
+        }
+    }
+
+    fn func(&mut self, f: &FuncDecl) {
+        self.at(f.span.0, |w| {
+            w.lint.check_func(f, &mut w.cx);
+            w.stmts(&f.body.stmts);
+        });
+    }
+
+    /// 在 `span`（有位置时）里遍历：钩子报告没有位置的问题时用它。
+    fn at(&mut self, span: Span, walk: impl FnOnce(&mut Self)) {
+        let outer = self.cx.span;
+        if span != Span::default() {
+            self.cx.span = span;
+        }
+        walk(self);
+        self.cx.span = outer;
+    }
+
+    fn stmts(&mut self, list: &[Stmt]) {
+        for s in list {
+            self.stmt(s);
+        }
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        self.at(s.span(), |w| {
+            w.lint.check_stmt(s, &mut w.cx);
+            match s {
+                Stmt::VarDecl(v) => {
+                    for init in v.decls.iter().filter_map(|d| d.init.as_ref()) {
+                        w.expr(init);
+                    }
+                }
+                Stmt::Assign(a) => w.expr(&a.value),
+                Stmt::ExprStmt(e) => w.expr(e),
+                Stmt::Block(b) => w.stmts(&b.stmts),
+                Stmt::If(i) => {
+                    w.expr(&i.cond);
+                    w.stmt(&i.then_branch);
+                    if let Some(e) = &i.else_branch {
+                        w.stmt(e);
+                    }
+                }
+                Stmt::While(wh) => {
+                    w.expr(&wh.cond);
+                    w.stmt(&wh.body);
+                }
+                Stmt::Return(r) => {
+                    if let Some(v) = &r.value {
+                        w.expr(v);
+                    }
+                }
+                Stmt::FuncDecl(f) => w.func(f),
+                Stmt::Throw(t) => w.expr(&t.value),
+                Stmt::Try(t) => {
+                    w.stmts(&t.block.stmts);
+                    if let Some(c) = &t.catch {
+                        w.stmts(&c.body.stmts);
+                    }
+                    if let Some(f) = &t.finally {
+                        w.stmts(&f.stmts);
+                    }
+                }
+            }
+        });
+    }
+
+    fn expr(&mut self, e: &Expr) {
+        self.at(e.span(), |w| {
+            w.lint.check_expr(e, &mut w.cx);
+            match e {
+                Expr::Literal(_, _) | Expr::Ident(_, _) => {}
+                Expr::Unary(u) => w.expr(&u.expr),
+                Expr::Binary(b) => {
+                    w.expr(&b.left);
+                    w.expr(&b.right);
+                }
+                Expr::Group(inner) | Expr::Spread(inner) => w.expr(inner),
+                Expr::Member(m) => w.expr(&m.object),
+                Expr::Index(ix) => {
+                    w.expr(&ix.object);
+                    w.expr(&ix.index);
+                }
+                Expr::Call(c) => {
+                    if let Callee::Expr(inner) | Callee::Method { object: inner, .. } = &c.callee {
+                        w.expr(inner);
+                    }
+                    for a in &c.args {
+                        w.expr(a);
+                    }
+                }
+                Expr::New(n) => {
+                    for a in &n.args {
+                        w.expr(a);
+                    }
+                }
+                Expr::Assign(a) => {
+                    if let AssignTarget::Index { index, .. } = &a.target {
+                        w.expr(index);
+                    }
+                    w.expr(&a.value);
+                }
+            }
+        });
+    }
+}
+
+/// `self_assignment`：把变量（或字段）赋值给它自己，`x = x;`、`this.n = this.n;`。
+/// 多半是想写 `x = y` 或者 `this.x = x`，移植代码时改名改漏了。
+pub struct SelfAssignment;
+
+impl Lint for SelfAssignment {
+    fn name(&self) -> &'static str {
+        "self_assignment"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::SelfAssignment
+    }
+
+    fn check_stmt(&self, s: &Stmt, cx: &mut LintContext) {
+        if let Stmt::Assign(a) = s {
+            if matches!(&a.value, Expr::Ident(v, _) if *v == a.name) {
+                cx.report(a.span.0);
+            }
+        }
+    }
+
+    fn check_expr(&self, e: &Expr, cx: &mut LintContext) {
+        let Expr::Assign(a) = e else {
+            return;
+        };
+        let same = match (&a.target, a.value.as_ref()) {
+            (AssignTarget::Ident(name), Expr::Ident(v, _)) => name == v,
+            (AssignTarget::Member { object, property }, Expr::Member(m)) => {
+                m.property == *property && matches!(m.object.as_ref(), Expr::Ident(o, _) if o == object)
+            }
+            _ => false,
+        };
+        if same {
+            cx.report(a.span.0);
+        }
+    }
+}
+
+/// `empty_branch`：`if` 分支或 `while` 循环体是空代码块 `{}`。
+/// 里面的代码多半是移植时漏掉了；确实什么都不用做时应该改写条件，或者加 `--allow empty_branch`。
+///
+/// 空的 `else {}` 不报：子集要求 `if` 必须带 `else`，只有一个分支时就写成空的 `else {}`。
+pub struct EmptyBranch;
+
+impl Lint for EmptyBranch {
+    fn name(&self) -> &'static str {
+        "empty_branch"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::EmptyBranch
+    }
+
+    fn check_stmt(&self, s: &Stmt, cx: &mut LintContext) {
+        let empty = |s: &Stmt| matches!(s, Stmt::Block(b) if b.stmts.is_empty());
+        match s {
+            Stmt::If(i) if empty(&i.then_branch) => cx.report(i.span.0),
+            Stmt::While(w) if empty(&w.body) => cx.report(w.span.0),
+            _ => {}
+        }
+    }
+}
+
+/// `self_comparison`：比较一个变量和它自己，`x == x`、`i < i`，结果总是一样的。
+///
+/// number 的 `x != x` / `x == x` 不报：f64 数字模式下这是判断 NaN 的常见写法。
+pub struct SelfComparison;
+
+impl Lint for SelfComparison {
+    fn name(&self) -> &'static str {
+        "self_comparison"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::SelfComparison
+    }
+
+    fn check_expr(&self, e: &Expr, cx: &mut LintContext) {
+        let Expr::Binary(b) = e else {
+            return;
+        };
+        let (Expr::Ident(l, _), Expr::Ident(r, _)) = (b.left.as_ref(), b.right.as_ref()) else {
+            return;
+        };
+        let nan_check = matches!(b.op, BinaryOp::EqEq | BinaryOp::NotEq) && cx.type_of(&b.left) == Some(&TypeAnn::Number);
+        let comparison = matches!(
+            b.op,
+            BinaryOp::EqEq | BinaryOp::NotEq | BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq
+        );
+        if l == r && comparison && !nan_check {
+            cx.report(b.span.0);
+        }
+    }
+}
+
+/// `constant_condition`：`if` 的条件是常量（`if (false)`、`if (1 > 2)`），总是走同一个分支；
+/// 或者 `while (false)`，循环体一次都不执行。多半是调试时临时改的条件忘了改回来。
+///
+/// 条件的值见 `const_bool`。`while (true)` 是常见写法，不报（没有出口时见 `infinite_loop`）。
+pub struct ConstantCondition;
+
+impl Lint for ConstantCondition {
+    fn name(&self) -> &'static str {
+        "constant_condition"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::ConstantCondition
+    }
+
+    fn check_stmt(&self, s: &Stmt, cx: &mut LintContext) {
+        match s {
+            Stmt::If(i) if const_bool(&i.cond).is_some() => cx.report(i.span.0),
+            Stmt::While(w) if const_bool(&w.cond) == Some(false) => cx.report(w.span.0),
+            _ => {}
+        }
+    }
+}
+
+/// `infinite_loop`：条件总是 true 的 `while` 循环（见 `const_bool`），循环体里没有 `return` / `throw`，
+/// 子集里也没有 `break`，所以循环永远不会结束。
+///
+/// 嵌套函数里的 `return` 不算出口；`try` 里的 `throw` 被 catch 接住时其实也出不去，这种情况不区分。
+pub struct InfiniteLoop;
+
+impl Lint for InfiniteLoop {
+    fn name(&self) -> &'static str {
+        "infinite_loop"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::InfiniteLoop
+    }
+
+    fn check_stmt(&self, s: &Stmt, cx: &mut LintContext) {
+        if let Stmt::While(w) = s {
+            if const_bool(&w.cond) == Some(true) && !has_exit(&w.body) {
+                cx.report(w.span.0);
+            }
+        }
+    }
+}
+
+/// 语句里有没有能离开循环的 `return` / `throw`（不看嵌套函数）。
+fn has_exit(s: &Stmt) -> bool {
+    match s {
+        Stmt::Return(_) | Stmt::Throw(_) => true,
+        Stmt::Block(b) => b.stmts.iter().any(has_exit),
+        Stmt::If(i) => has_exit(&i.then_branch) || i.else_branch.as_deref().is_some_and(has_exit),
+        Stmt::While(w) => has_exit(&w.body),
+        Stmt::Try(t) => {
+            let blocks = [Some(&t.block), t.catch.as_ref().map(|c| &c.body), t.finally.as_ref()];
+            blocks.into_iter().flatten().any(|b| b.stmts.iter().any(has_exit))
+        }
+        Stmt::VarDecl(_) | Stmt::Assign(_) | Stmt::ExprStmt(_) | Stmt::FuncDecl(_) => false,
+    }
+}
+
+/// 常量折叠：只由字面量组成的条件的值，例如 `true`、`!false`、`1 > 2`、`(2 * 3 == 6)`；
+/// `false && x` / `true || x` 短路，右边不是常量也有值。条件里有变量、调用时为 None。
+pub fn const_bool(e: &Expr) -> Option<bool> {
+    match e {
+        Expr::Literal(Literal::Bool(b), _) => Some(*b),
+        Expr::Group(inner) => const_bool(inner),
+        Expr::Unary(u) if u.op == UnaryOp::Not => const_bool(&u.expr).map(|b| !b),
+        Expr::Binary(b) => match b.op {
+            BinaryOp::AndAnd => match const_bool(&b.left)? {
+                false => Some(false),
+                true => const_bool(&b.right),
+            },
+            BinaryOp::OrOr => match const_bool(&b.left)? {
+                true => Some(true),
+                false => const_bool(&b.right),
+            },
+            BinaryOp::EqEq | BinaryOp::NotEq => {
+                let same = match (const_bool(&b.left), const_bool(&b.right)) {
+                    (Some(l), Some(r)) => l == r,
+                    _ => const_number(&b.left)? == const_number(&b.right)?,
+                };
+                Some(same == (b.op == BinaryOp::EqEq))
+            }
+            BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+                let (l, r) = (const_number(&b.left)?, const_number(&b.right)?);
+                Some(match b.op {
+                    BinaryOp::Lt => l < r,
+                    BinaryOp::LtEq => l <= r,
+                    BinaryOp::Gt => l > r,
+                    _ => l >= r,
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 整数字面量组成的表达式的值（`const_bool` 里比较用，CodeGen 用它找出整数除以 0）；溢出、除法等不折叠。
+pub(crate) fn const_number(e: &Expr) -> Option<i64> {
+    match e {
+        Expr::Literal(Literal::Number(n), _) => Some(i64::from(*n)),
+        Expr::Group(inner) => const_number(inner),
+        Expr::Unary(u) if u.op == UnaryOp::Neg => const_number(&u.expr)?.checked_neg(),
+        Expr::Binary(b) => {
+            let (l, r) = (const_number(&b.left)?, const_number(&b.right)?);
+            match b.op {
+                BinaryOp::Add => l.checked_add(r),
+                BinaryOp::Sub => l.checked_sub(r),
+                BinaryOp::Mul => l.checked_mul(r),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// `dead_store`：赋给变量的值在下一次赋值之前、或者离开作用域之前都没有被读取（`x = 1; x = 2;` 的第一次赋值）。
+/// 多半是移植时留下的多余代码，或者本来要读取的是这个变量却写成了别的变量。分析见 `flow::dead_stores`。
+pub struct DeadStore;
+
+impl Lint for DeadStore {
+    fn name(&self) -> &'static str {
+        "dead_store"
+    }
+
+    fn code(&self) -> ErrorCode {
+        ErrorCode::DeadStore
+    }
+
+    fn check_program(&self, program: &Program, cx: &mut LintContext) {
+        for span in flow::dead_stores(program) {
+            cx.report(span);
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 
+const USAGE: &str = "Usage: arkts2rust <input.ets> [-o <output.rs>] [--cargo-project <dir>] [--lib] [--skip-top-level] [--native-module] [--single-file] \
+[--bind <name>=<rust::path>]... [--intrinsic <callee>=<template>]... [--base-dir <dir>] [--path <pattern>=<target>]... [--ext <.ext>]... [--system-module <module>=<rust::path>]... \
+[--debug-format] [--use-rand] [--serde-json] [--chrono] [--debug-assert] [--nan zero|panic|throw] [--arith plain|wrapping|checked] [--string-mode owned|static|shared] [--number-mode i32|f64] [--js-division] [--truthy] [--asi] [--ui-backend builder|egui] [--ability-runtime <rust::path>] [--runtime <rust::path>] [--prelude <file.rs>] [--comments] [--doc-comments] [--provenance] [--source-map] [--verify] [--emit-runtime <dir>] [--hilog println|log|tracing] [--resource-lookup <rust::path>] [--snake-case] [--name-map <file.json>] [--indent <n>|tab] [--brace-style same-line|next-line] [--no-trailing-newline] [--format prettyplease|rustfmt] [--edition 2015|2018|2021|2024] [--lang en|zh] [--allow|--warn|--deny <lint>]...";
+
 /// CLI 程序入口。
 ///
 /// 它做的事情非常“薄”：
-/// 1) 读入 ArkTS 源文件（.ets）
-/// 2) 调用库函数 `arkts2rust::compile` 得到 Rust 源码字符串
-/// 3) 把 Rust 源码写到输出文件（默认 output.rs）
+/// 1) 拿到入口 ArkTS 源文件（.ets）的路径
+/// 2) 调用库函数（`module::load_modules_with` -> `module::link_tree` -> `codegen::generate_tree`，
+///    和 `arkts2rust::compile_tree` 相同）得到 Rust 源码，会顺带加载 import 的文件；
+///    解析和语义检查的警告打印到 stderr，出错时一次打印全部错误（见 `check`）
+/// 3) 把 Rust 源码写到输出文件（默认 output.rs）；import 的每个文件生成一个模块文件，
+///    放在输出文件旁边（`util.ets` -> `util.rs`）。`--single-file` 时所有代码合并进输出文件
 ///
 /// 语法/编译逻辑都在 `src/lib.rs` 以及内部模块里，这里只负责 I/O 和参数解析。
 fn main() {
@@ -16,23 +24,308 @@ fn main() {
     let input_path = match args.next() {
         Some(p) => p,
         None => {
-            eprintln!("Usage: arkts2rust <input.ets> [-o <output.rs>]");
+            eprintln!("{USAGE}");
             process::exit(2);
         }
     };
 
     // 解析可选参数：
     // -o / --output <path>
+    // --cargo-project <dir>：生成完整的 Cargo 项目（Cargo.toml、src/main.rs 或 src/lib.rs，依赖按选项和代码写好），
+    //   写到 dir 下，不再使用 -o（见 `arkts2rust::cargo::project`）
+    // --lib：生成库代码（没有 main，export 的项是 pub）
+    // --skip-top-level：库代码里跳过 const 声明以外的顶层语句并给出警告（默认报错）
+    // --native-module：在库代码的基础上给导出函数生成 napi 包装，编译成 HarmonyOS 原生模块（.so）
+    // --single-file：把所有模块合并成一个 Rust 文件（默认每个 .ets 文件一个 Rust 模块文件）
+    // --bind name=path：`declare function name` 的调用转发到 Rust 函数 `path`（可以写多次）
+    // --intrinsic 'Math.floor=libm::floor($0)'：内置函数调用按模板生成代码（可以写多次，见 `arkts2rust::Intrinsics`）
+    // --base-dir / --path / --ext：模块路径解析配置（见 `arkts2rust::ResolveConfig`）
+    //   --path '@common/*=src/common/*' 可以写多次，同一个模式写多次表示多个候选目标
+    //   --ext 写了就替换默认的 `.ets`、`.d.ets` 后缀列表
+    // --system-module '@ohos.data.preferences=my_rt::prefs'：添加或覆盖系统模块的映射（可以写多次），
+    //   `=` 后面留空表示由转换器内置处理（见 `arkts2rust::SystemModules`）
+    // --debug-format：console 输出保持 Rust 的 `{:?}` 调试格式（默认按 JS 的样子输出）
+    // --use-rand：`Math.random()` 使用 `rand` crate（默认在输出里附带一个 xorshift 函数）
+    // --serde-json：`JSON.stringify` / `JSON.parse` 生成 `serde_json` 调用
+    // --chrono：`Date` 生成 chrono 代码（默认用 `std::time::SystemTime`）
+    // --debug-assert：`console.assert` / `assert` 生成 `debug_assert!`（release 构建里不检查）
+    // --nan：`parseInt` 等数字转换失败（JS 里的 NaN）时得到 0（默认）、panic 还是抛异常
+    // --arith：整数运算溢出时用普通运算符（默认，debug 构建 panic）、`wrapping_*` 回绕，还是 `checked_*` 抛异常
+    // --string-mode：string 映射为 String（默认）、&'static str（只用字面量的程序）还是 Rc<str>（共享的字符串）
+    // --number-mode：number 映射为 i32（默认）还是 f64（和 ArkTS 一样的浮点数语义）
+    // --js-division：number 是 i32 时 `/` 也按 JS 的语义得到小数（`7 / 2` 是 3.5）
+    // --truthy：允许 `if (n)` 之类的 truthy 条件（转成 `n != 0` 等），并给出警告
+    // --asi：语句末尾可以不写分号（按 JS 的规则自动插入），并给出警告
+    // --ui-backend：组件 `build()` 里的 UI 用哪个后端生成代码（默认只作为注释列出）
+    // --ability-runtime：Ability 类使用的 `UIAbility` trait 所在的模块（默认在输出里附带一个最小实现）
+    // --runtime：数字转换、`Date` 等辅助函数和组件状态类型从这个模块 `use`，例如 `arkts_runtime`
+    //   （默认在输出里附带用到的定义，见 `arkts2rust::runtime`）；配合 --cargo-project 时运行时 crate 会放进项目
+    // --prelude <file.rs>：把文件内容原样加在生成的代码最开头（`#![allow(..)]`、`use` 语句等，见 `CodegenOptions::prelude`）
+    // --comments：保留源码里的注释，输出到对应的语句旁边（见 `CodegenOptions::comments`）
+    // --doc-comments：函数、组件和 class 前面的 JSDoc 注释生成 `///` 文档注释（见 `CodegenOptions::doc_comments`）
+    // --provenance：每条语句、每个函数前面加 `// 文件:行号` 注释，对照原来的 ArkTS（见 `CodegenOptions::provenance`）
+    // --source-map：每个输出文件旁边写一个 `<file>.map.json`，记录生成的代码和源码位置的对应关系（见 `arkts2rust::SourceMap`）
+    // --verify：写完输出以后用 rustc 编译生成的代码（--cargo-project 时是 `cargo check`），rustc 的报错按源码映射
+    //   换回 .ets 文件里的位置报告（见 `arkts2rust::verify`）；有错误时退出码为 1
+    // --emit-runtime <dir>：把运行时 crate（Cargo.toml 和 src/lib.rs）写到 dir 下
+    // --hilog：`hilog.info(..)` 等生成 `println!`（默认）、`log` 还是 `tracing` 的宏
+    // --resource-lookup：`$r(..)` / `$rawfile(..)` 调用的资源查找函数（默认生成资源名字符串）
+    // --snake-case：函数名、变量名、参数名改成 snake_case（见 `arkts2rust::rename`）
+    // --name-map：把改名的映射表（原名 -> 新名）写成 JSON 文件，配合 --snake-case 使用
+    // --indent / --brace-style / --no-trailing-newline：输出代码的缩进（空格数或 tab）、左大括号的位置、
+    //   文件末尾不要换行（见 `arkts2rust::LayoutOptions`）
+    // --format：写文件之前用 prettyplease（要打开 `prettyplease` feature）或者 rustfmt 格式化生成的代码
+    // --edition：生成的代码使用的 Rust edition（默认 2021，见 `arkts2rust::Edition`）
+    // --allow / --warn / --deny <lint>：lint 规则的级别（可以写多次，见 `arkts2rust::lint::builtin_lints`），
+    //   deny 的规则报告成错误，编译失败
+    // --lang：错误信息用英文还是中文（默认看环境变量 `ARKTS2RUST_LANG` / `LANG`，见 `arkts2rust::Lang::from_env`）
     let mut output_path: Option<String> = None;
+    let mut mode = arkts2rust::OutputMode::Binary;
+    let mut externs = arkts2rust::ExternBindings::new();
+    let mut resolve = arkts2rust::ResolveConfig::default();
+    let mut custom_exts = false;
+    let mut single_file = false;
+    let mut cargo_project: Option<String> = None;
+    let mut name_map: Option<String> = None;
+    let mut source_map = false;
+    let mut verify = false;
+    let mut emit_runtime: Option<String> = None;
+    let mut options = arkts2rust::CodegenOptions::default();
+    let mut lang = arkts2rust::Lang::from_env();
+    let mut lints = arkts2rust::LintConfig::new();
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "-o" | "--output" => {
                 // 下一个参数就是输出路径
                 output_path = args.next();
             }
+            "--lib" => mode = arkts2rust::OutputMode::Library,
+            "--native-module" => mode = arkts2rust::OutputMode::NativeModule,
+            "--single-file" => single_file = true,
+            "--cargo-project" => match args.next() {
+                Some(dir) => cargo_project = Some(dir),
+                None => {
+                    eprintln!("--cargo-project expects <dir>");
+                    process::exit(2);
+                }
+            },
+            "--snake-case" => options.snake_case = true,
+            "--name-map" => match args.next() {
+                Some(path) => name_map = Some(path),
+                None => {
+                    eprintln!("--name-map expects <file.json>");
+                    process::exit(2);
+                }
+            },
+            "--indent" => match args.next().as_deref().map(|n| (n, n.parse::<usize>())) {
+                Some(("tab", _)) => options.layout.indent = arkts2rust::Indent::Tabs,
+                Some((_, Ok(n))) => options.layout.indent = arkts2rust::Indent::Spaces(n),
+                _ => {
+                    eprintln!("--indent expects a number of spaces or tab");
+                    process::exit(2);
+                }
+            },
+            "--brace-style" => match args.next().as_deref() {
+                Some("same-line") => options.layout.brace_style = arkts2rust::BraceStyle::SameLine,
+                Some("next-line") => options.layout.brace_style = arkts2rust::BraceStyle::NextLine,
+                _ => {
+                    eprintln!("--brace-style expects same-line or next-line");
+                    process::exit(2);
+                }
+            },
+            "--skip-top-level" => options.skip_top_level_code = true,
+            "--no-trailing-newline" => options.layout.trailing_newline = false,
+            "--edition" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(edition)) => options.edition = edition,
+                _ => {
+                    eprintln!("--edition expects 2015, 2018, 2021 or 2024");
+                    process::exit(2);
+                }
+            },
+            "--format" => match args.next().as_deref() {
+                Some("prettyplease") => options.formatter = arkts2rust::Formatter::Prettyplease,
+                Some("rustfmt") => options.formatter = arkts2rust::Formatter::Rustfmt,
+                _ => {
+                    eprintln!("--format expects prettyplease or rustfmt");
+                    process::exit(2);
+                }
+            },
+            "--debug-format" => options.debug_format = true,
+            "--comments" => options.comments = true,
+            "--doc-comments" => options.doc_comments = true,
+            "--provenance" => options.provenance = true,
+            "--source-map" => source_map = true,
+            "--verify" => verify = true,
+            "--use-rand" => options.use_rand = true,
+            "--serde-json" => options.use_serde_json = true,
+            "--chrono" => options.use_chrono = true,
+            "--debug-assert" => options.debug_assert = true,
+            "--js-division" => options.js_division = true,
+            "--asi" => resolve.parse.asi = true,
+            "--truthy" => options.truthy_conditions = true,
+            "--number-mode" => match args.next().as_deref() {
+                Some("i32") => options.number_mode = arkts2rust::NumberMode::I32,
+                Some("f64") => options.number_mode = arkts2rust::NumberMode::F64,
+                _ => {
+                    eprintln!("--number-mode expects i32 or f64");
+                    process::exit(2);
+                }
+            },
+            "--nan" => match args.next().as_deref() {
+                Some("zero") => options.nan = arkts2rust::NanMode::Zero,
+                Some("panic") => options.nan = arkts2rust::NanMode::Panic,
+                Some("throw") => options.nan = arkts2rust::NanMode::Throw,
+                _ => {
+                    eprintln!("--nan expects zero, panic or throw");
+                    process::exit(2);
+                }
+            },
+            "--arith" => match args.next().as_deref() {
+                Some("plain") => options.arith = arkts2rust::ArithMode::Plain,
+                Some("wrapping") => options.arith = arkts2rust::ArithMode::Wrapping,
+                Some("checked") => options.arith = arkts2rust::ArithMode::Checked,
+                _ => {
+                    eprintln!("--arith expects plain, wrapping or checked");
+                    process::exit(2);
+                }
+            },
+            "--string-mode" => match args.next().as_deref() {
+                Some("owned") => options.string_mode = arkts2rust::StringMode::Owned,
+                Some("static") => options.string_mode = arkts2rust::StringMode::Static,
+                Some("shared") => options.string_mode = arkts2rust::StringMode::Shared,
+                _ => {
+                    eprintln!("--string-mode expects owned, static or shared");
+                    process::exit(2);
+                }
+            },
+            "--ui-backend" => match args.next().as_deref().and_then(arkts2rust::UiBackendRef::by_name) {
+                Some(backend) => options.ui_backend = Some(backend),
+                None => {
+                    eprintln!("--ui-backend expects builder or egui");
+                    process::exit(2);
+                }
+            },
+            "--hilog" => match args.next().as_deref() {
+                Some("println") => options.hilog = arkts2rust::HilogMode::Println,
+                Some("log") => options.hilog = arkts2rust::HilogMode::Log,
+                Some("tracing") => options.hilog = arkts2rust::HilogMode::Tracing,
+                _ => {
+                    eprintln!("--hilog expects println, log or tracing");
+                    process::exit(2);
+                }
+            },
+            "--resource-lookup" => match args.next() {
+                Some(path) => options.resource_lookup = Some(path),
+                None => {
+                    eprintln!("--resource-lookup expects <rust::path>");
+                    process::exit(2);
+                }
+            },
+            "--ability-runtime" => match args.next() {
+                Some(path) => options.ability_runtime = Some(path),
+                None => {
+                    eprintln!("--ability-runtime expects <rust::path>");
+                    process::exit(2);
+                }
+            },
+            "--runtime" => match args.next() {
+                Some(path) => options.runtime = Some(path),
+                None => {
+                    eprintln!("--runtime expects <rust::path>");
+                    process::exit(2);
+                }
+            },
+            "--prelude" => match args.next().map(|path| fs::read_to_string(&path).map_err(|e| (path, e))) {
+                Some(Ok(code)) => options.prelude = Some(code),
+                Some(Err((path, e))) => {
+                    eprintln!("Failed to read prelude file {path}: {e}");
+                    process::exit(2);
+                }
+                None => {
+                    eprintln!("--prelude expects <file.rs>");
+                    process::exit(2);
+                }
+            },
+            "--emit-runtime" => match args.next() {
+                Some(dir) => emit_runtime = Some(dir),
+                None => {
+                    eprintln!("--emit-runtime expects <dir>");
+                    process::exit(2);
+                }
+            },
+            "--intrinsic" => match args.next().as_deref().and_then(|b| b.split_once('=')) {
+                Some((callee, template)) => {
+                    options.intrinsics.insert(callee.to_string(), template.to_string());
+                }
+                None => {
+                    eprintln!("--intrinsic expects <callee>=<template>");
+                    process::exit(2);
+                }
+            },
+            "--bind" => match args.next().as_deref().and_then(|b| b.split_once('=')) {
+                Some((name, path)) => {
+                    externs.insert(name.to_string(), path.to_string());
+                }
+                None => {
+                    eprintln!("--bind expects <name>=<rust::path>");
+                    process::exit(2);
+                }
+            },
+            "--base-dir" => resolve.base_dir = args.next().map(Into::into),
+            "--path" => match args.next().as_deref().and_then(|p| p.split_once('=')) {
+                Some((pattern, target)) => {
+                    match resolve.paths.iter_mut().find(|(p, _)| p == pattern) {
+                        Some((_, targets)) => targets.push(target.to_string()),
+                        None => resolve.paths.push((pattern.to_string(), vec![target.to_string()])),
+                    }
+                }
+                None => {
+                    eprintln!("--path expects <pattern>=<target>");
+                    process::exit(2);
+                }
+            },
+            "--system-module" => match args.next().as_deref().and_then(|m| m.split_once('=')) {
+                Some((module, path)) => {
+                    let path = (!path.is_empty()).then(|| path.to_string());
+                    resolve.system_modules.insert(module.to_string(), path);
+                }
+                None => {
+                    eprintln!("--system-module expects <module>=<rust::path>");
+                    process::exit(2);
+                }
+            },
+            "--allow" | "--warn" | "--deny" => {
+                let level = match arg.as_str() {
+                    "--allow" => arkts2rust::Level::Allow,
+                    "--warn" => arkts2rust::Level::Warn,
+                    _ => arkts2rust::Level::Deny,
+                };
+                match args.next() {
+                    Some(name) if arkts2rust::lint::builtin_lints().iter().any(|l| l.name() == name) => lints.set(name, level),
+                    _ => {
+                        let names: Vec<&str> = arkts2rust::lint::builtin_lints().iter().map(|l| l.name()).collect();
+                        eprintln!("{arg} expects one of: {}", names.join(", "));
+                        process::exit(2);
+                    }
+                }
+            }
+            "--lang" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(l)) => lang = l,
+                _ => {
+                    eprintln!("--lang expects en or zh");
+                    process::exit(2);
+                }
+            },
+            "--ext" => {
+                if !custom_exts {
+                    resolve.extensions.clear();
+                    custom_exts = true;
+                }
+                resolve.extensions.extend(args.next());
+            }
             _ => {
                 eprintln!("Unknown argument: {arg}");
-                eprintln!("Usage: arkts2rust <input.ets> [-o <output.rs>]");
+                eprintln!("{USAGE}");
                 process::exit(2);
             }
         }
@@ -41,28 +334,195 @@ fn main() {
     // 不传 -o 时，默认输出到当前目录下的 output.rs
     let output_path = output_path.unwrap_or_else(|| "output.rs".to_string());
 
-    // 读取输入源文件
-    let src = match fs::read_to_string(&input_path) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Failed to read input file {input_path}: {e}");
-            process::exit(2);
+    // 调用库函数进行编译（返回 Rust 源码）。
+    // 入口文件 import 的其它 .ets 文件会被一起加载。
+    let mut names = arkts2rust::NameMap::default();
+    let mut maps: Vec<arkts2rust::SourceMap> = Vec::new();
+    let files = arkts2rust::module::load_modules_with(Path::new(&input_path), &resolve)
+        .and_then(|modules| {
+            // 检查和 CodeGen 在合并后的程序上进行，诊断信息按位置找回所在的文件
+            let files = arkts2rust::SourceFiles::of(&modules);
+            if single_file {
+                let program = arkts2rust::module::link(modules).map_err(|e| e.locate(&files))?;
+                let (diagnostics, types) = check(&program, mode, &options, &lints, &files, lang)?;
+                if options.snake_case {
+                    names = arkts2rust::rename::snake_case_map(&program);
+                }
+                let code = match source_map || verify {
+                    true => arkts2rust::codegen::generate_checked_mapped(&program, &types, mode, &externs, &options).map(
+                        |(code, map)| {
+                            maps.push(map);
+                            code
+                        },
+                    ),
+                    false => arkts2rust::codegen::generate_checked(&program, &types, mode, &externs, &options),
+                };
+                code.map(|code| {
+                    vec![arkts2rust::OutputFile {
+                        path: output_path.clone().into(),
+                        code,
+                    }]
+                })
+                .map_err(|e| diagnostics.or_error(e).locate(&files))
+            } else {
+                let (program, units) = arkts2rust::module::link_tree(modules).map_err(|e| e.locate(&files))?;
+                let (diagnostics, types) = check(&program, mode, &options, &lints, &files, lang)?;
+                if options.snake_case {
+                    names = arkts2rust::rename::snake_case_map(&program);
+                }
+                let generated = match source_map || verify {
+                    true => arkts2rust::codegen::generate_tree_checked_mapped(&program, &units, &types, mode, &externs, &options)
+                        .map(|files| {
+                            let (files, found): (Vec<_>, Vec<_>) = files.into_iter().unzip();
+                            maps = found;
+                            files
+                        }),
+                    false => arkts2rust::codegen::generate_tree_checked(&program, &units, &types, mode, &externs, &options),
+                };
+                generated.map_err(|e| diagnostics.or_error(e).locate(&files))
+            }
+        });
+    let mut files = match files {
+        Ok(files) => files,
+        Err(diagnostics) => {
+            // 编译错误：一次打印全部错误，每个错误带上出错的源码行，用 `^^^` 标出位置
+            let count = diagnostics.errors().count();
+            for e in diagnostics.errors() {
+                eprintln!("{}", render(e, lang));
+            }
+            eprintln!("Compile failed: {count} error(s)");
+            process::exit(1);
         }
     };
 
-    // 调用库函数进行编译（返回 Rust 源码字符串）
-    match arkts2rust::compile(&src) {
-        Ok(rust_code) => {
-            // 写出到文件
-            if let Err(e) = fs::write(&output_path, rust_code) {
-                eprintln!("Failed to write output file {output_path}: {e}");
+    // 根文件写到输出路径，其它模块文件放在它所在的目录下；
+    // --cargo-project 时整个项目（Cargo.toml 和 src/ 下的文件）写到项目目录下
+    let out_dir = match &cargo_project {
+        Some(dir) => {
+            let name = Path::new(dir).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            files = arkts2rust::cargo::project(&name, &files, mode, &externs, &options);
+            PathBuf::from(dir)
+        }
+        None => {
+            files[0].path = output_path.clone().into();
+            Path::new(&output_path).parent().unwrap_or(Path::new("")).to_path_buf()
+        }
+    };
+    let paths: Vec<PathBuf> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| if i == 0 && cargo_project.is_none() { file.path.clone() } else { out_dir.join(&file.path) })
+        .collect();
+    for (path, file) in paths.iter().zip(&files) {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Err(e) = fs::write(path, &file.code) {
+            eprintln!("Failed to write output file {}: {e}", path.display());
+            process::exit(2);
+        }
+    }
+    // 源码映射和对应的 Rust 文件放在一起：`main.rs` -> `main.rs.map.json`；Cargo 项目的第一个文件是 Cargo.toml
+    let skip = usize::from(cargo_project.is_some());
+    for (path, map) in paths.iter().skip(skip).zip(&maps).filter(|_| source_map) {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let map_path = PathBuf::from(format!("{}.map.json", path.display()));
+        if let Err(e) = fs::write(&map_path, map.to_json(&name)) {
+            eprintln!("Failed to write source map {}: {e}", map_path.display());
+            process::exit(2);
+        }
+    }
+    if let Some(dir) = emit_runtime {
+        for file in arkts2rust::runtime::project(options.edition) {
+            let path = Path::new(&dir).join(&file.path);
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Err(e) = fs::write(&path, &file.code) {
+                eprintln!("Failed to write runtime file {}: {e}", path.display());
                 process::exit(2);
             }
         }
+    }
+    if let Some(path) = name_map {
+        if let Err(e) = fs::write(&path, names.to_json()) {
+            eprintln!("Failed to write name map {path}: {e}");
+            process::exit(2);
+        }
+    }
+    if verify {
+        let found = match &cargo_project {
+            Some(dir) => arkts2rust::verify::cargo_check(Path::new(dir)),
+            None => arkts2rust::verify::rustc(&paths[0], mode, options.edition),
+        };
+        let mapped: Vec<(&PathBuf, &arkts2rust::SourceMap)> = paths.iter().skip(skip).zip(&maps).collect();
+        verify_output(found, &mapped, cargo_project.as_deref().map(Path::new), lang);
+    }
+}
+
+/// `--verify`：打印 rustc 对生成代码的报错，位置按源码映射换回 .ets 文件（见 `arkts2rust::verify::map_back`），
+/// 有错误时退出。`files` 是写出去的 Rust 文件和它们的源码映射；`cargo check` 报告的路径相对于项目目录 `project`。
+fn verify_output(
+    found: Result<Vec<arkts2rust::verify::RustcDiagnostic>, arkts2rust::Error>,
+    files: &[(&PathBuf, &arkts2rust::SourceMap)],
+    project: Option<&Path>,
+    lang: arkts2rust::Lang,
+) {
+    let found = match found {
+        Ok(found) => found,
         Err(e) => {
-            // 编译错误：错误中包含 code 和 span（行列号）方便定位
-            eprintln!("Compile failed: {e}");
-            process::exit(1);
+            eprintln!("{}", render(&e.into(), lang));
+            process::exit(2);
         }
+    };
+    let same_file = |a: &Path, b: &Path| match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    };
+    let errors: Vec<_> = found
+        .iter()
+        .filter(|d| d.is_error() && !d.message.starts_with("aborting due to"))
+        .collect();
+    for d in &errors {
+        let path = d.file.as_deref().map(|f| project.map_or_else(|| PathBuf::from(f), |dir| dir.join(f)));
+        let map = path.and_then(|p| files.iter().find(|(f, _)| same_file(f, &p))).map(|(_, map)| *map);
+        eprintln!("{}", render(&arkts2rust::verify::map_back(d, map), lang));
+    }
+    if !errors.is_empty() {
+        eprintln!("Verify failed: {} error(s) in the generated Rust code", errors.len());
+        process::exit(1);
     }
 }
+
+/// 运行 CodeGen 之前的全部检查（见 `arkts2rust::check_program_typed`），只做这一次：CodeGen 直接用返回的类型检查结果。
+/// 警告不影响编译结果，直接打印到 stderr：
+/// 解析时的警告、语义检查的警告（没有使用的变量 / 函数）、CodeGen 的警告（库代码里跳过的顶层语句、改了名的 `main`，
+/// 见 `codegen::warnings`）和 lint 的警告。有错误时返回 Err，一次报告全部错误（`--deny` 的 lint 只在这里报告，CodeGen 不会失败）；
+/// 没有错误时返回的诊断信息在 CodeGen 失败时一起报告。
+///
+/// 诊断信息按 `files` 补上所在的文件，打印时显示在 `-->` 后面；`lang` 是警告说明的语言。
+fn check(
+    program: &arkts2rust::Program,
+    mode: arkts2rust::OutputMode,
+    options: &arkts2rust::CodegenOptions,
+    lints: &arkts2rust::LintConfig,
+    files: &arkts2rust::SourceFiles,
+    lang: arkts2rust::Lang,
+) -> Result<(arkts2rust::Diagnostics, arkts2rust::typeck::Types), arkts2rust::Diagnostics> {
+    let (mut diagnostics, types) = arkts2rust::check_program_typed(program, options, lints);
+    diagnostics.extend(arkts2rust::codegen::warnings(program, mode, options));
+    let diagnostics = diagnostics.locate(files);
+    for w in diagnostics.warnings() {
+        eprintln!("{}", render(w, lang));
+    }
+    if diagnostics.has_errors() {
+        return Err(diagnostics);
+    }
+    Ok((diagnostics, types))
+}
+
+/// 按 rustc 的格式用 `lang` 打印一条诊断信息（见 `Diagnostic::render_in`），源码从诊断信息所在的文件读取。
+fn render(d: &arkts2rust::Diagnostic, lang: arkts2rust::Lang) -> String {
+    let src = d.file.as_deref().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+    d.render_in(&src, lang)
+}
@@ -0,0 +1,313 @@
+use crate::error::{ErrorCode, Expected, NoteCode};
+use std::str::FromStr;
+
+/// 诊断信息使用的语言。
+///
+/// 错误码（`ErrorCode`）是机器可读的，不随语言变化；给人看的说明文字放在这里的消息表里，
+/// 按错误码查出英文或中文（见 `message`）。CLI 用 `--lang en|zh` 或环境变量选择（见 `Lang::from_env`）。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Lang {
+    /// 英文（默认）
+    #[default]
+    En,
+    /// 中文
+    Zh,
+}
+
+impl Lang {
+    /// 从环境变量选择语言：`ARKTS2RUST_LANG=zh|en` 优先，没有时看 `LANG`（`zh_CN.UTF-8` 等以 `zh` 开头的是中文），
+    /// 都没有或不认识时是英文。
+    pub fn from_env() -> Self {
+        if let Some(lang) = std::env::var("ARKTS2RUST_LANG").ok().and_then(|v| v.parse().ok()) {
+            return lang;
+        }
+        match std::env::var("LANG") {
+            Ok(v) if v.starts_with("zh") => Lang::Zh,
+            _ => Lang::En,
+        }
+    }
+}
+
+/// 解析 `en` / `zh`；不认识的字符串返回 `Err(())`。
+impl FromStr for Lang {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "en" => Ok(Lang::En),
+            "zh" => Ok(Lang::Zh),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 错误码对应的说明文字，例如 `UndeclaredVariable` 是 “use of undeclared variable `{name}`” /
+/// “使用了没有声明的变量 `{name}`”；`{name}` 这样的参数由 `format` 用 `Error::args` 填上。
+pub fn message(code: ErrorCode, lang: Lang) -> &'static str {
+    let (en, zh) = catalog(code);
+    match lang {
+        Lang::En => en,
+        Lang::Zh => zh,
+    }
+}
+
+/// 语法错误的说明（见 `Expected`）：英文和 `Expected` 的 Display 相同，
+/// 中文是 “应为 `,` 或 `)`，实际是 `;`”。
+pub fn expected(e: &Expected, lang: Lang) -> String {
+    if lang == Lang::En {
+        return e.to_string();
+    }
+    let tokens: Vec<String> = e.tokens.iter().map(|t| token(t)).collect();
+    let list = match tokens.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} 或 {last}", rest.join("、")),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    };
+    format!("应为 {list}，实际是 {}", token(&e.found))
+}
+
+/// 补充说明的文字（见 `Note`）：按种类查出说明，填上参数。
+pub fn note(code: NoteCode, args: &[(&'static str, String)], lang: Lang) -> String {
+    let (en, zh) = note_catalog(code);
+    format(if lang == Lang::En { en } else { zh }, args)
+}
+
+/// 把说明文字里的 `{name}` 换成 `args` 里同名参数的值；没有这个参数时原样保留。
+pub fn format(template: &str, args: &[(&'static str, String)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let param = rest[start + 1..].find('}').map(|end| &rest[start + 1..start + 1 + end]);
+        match param.and_then(|p| Some((p, &args.iter().find(|(k, _)| *k == p)?.1))) {
+            Some((p, value)) => {
+                out.push_str(value);
+                rest = &rest[start + p.len() + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `TokenKind::describe` 的描述换成中文：`` `)` `` 这样的原样保留，只翻译前面的类别词。
+fn token(text: &str) -> String {
+    const WORDS: [(&str, &str); 8] = [
+        ("identifier", "标识符"),
+        ("number", "数字"),
+        ("string", "字符串"),
+        ("expression", "表达式"),
+        ("literal", "字面量"),
+        ("module path string", "模块路径字符串"),
+        ("end of file", "文件末尾"),
+        ("invalid token", "无效的 token"),
+    ];
+    for (en, zh) in WORDS {
+        if text == en {
+            return zh.to_string();
+        }
+        if let Some(rest) = text.strip_prefix(en).and_then(|r| r.strip_prefix(' ')) {
+            return format!("{zh} {rest}");
+        }
+    }
+    text.to_string()
+}
+
+/// 补充说明的消息表：每种说明的（英文，中文）文字。
+fn note_catalog(code: NoteCode) -> (&'static str, &'static str) {
+    use NoteCode::*;
+    match code {
+        DeclaredHere => ("declared here", "在这里声明"),
+        FirstDeclaredHere => ("first declared here", "第一次在这里声明"),
+        DeclaredConstHere => ("declared as const here", "在这里声明为 const"),
+        FunctionDeclaredHere => ("function declared here", "函数在这里声明"),
+        ParameterDeclaredHere => ("parameter declared here", "参数在这里声明"),
+        TopLevelLet => ("top-level `let` variables are local to `main`", "顶层的 `let` 变量是 `main` 的局部变量"),
+        DidYouMean => ("did you mean `{name}`?", "是不是想写 `{name}`？"),
+        Imports => ("imports {file}", "导入了 {file}"),
+        Rustc => ("{message}", "{message}"),
+    }
+}
+
+/// 消息表：每个错误码的（英文，中文）说明，`{name}` 这样的参数见 `format`。
+/// `match` 没有通配分支，新增错误码时编译器会提醒在这里补上。
+fn catalog(code: ErrorCode) -> (&'static str, &'static str) {
+    use ErrorCode::*;
+    match code {
+        // ---------- 词法分析 ----------
+        UnexpectedChar => ("unexpected character", "不支持的字符"),
+        UnterminatedBlockComment => ("unterminated block comment", "块注释 `/* ..` 没有闭合"),
+        InvalidNumber => ("number literal out of range", "数字超出范围"),
+        UnterminatedString => ("unterminated string literal", "字符串没有闭合"),
+
+        // ---------- 语法分析 ----------
+        UnsupportedExport => ("unsupported export form", "不支持的 export 写法"),
+        MisplacedDecorator => ("decorator must precede a function, class or component", "装饰器后面应该是函数、class 或组件"),
+        DuplicateBuild => ("component has more than one `build()`", "组件里有多个 `build()`"),
+        UnsupportedComponentMember => ("unsupported component member", "组件里不支持的成员"),
+        MissingRBrace => ("missing `}`", "缺少 `}`"),
+        UnsupportedClassMember => ("unsupported class member", "class 里不支持的成员"),
+        UnexpectedToken => ("unexpected token", "这里不能出现这个 token"),
+        UnexpectedEof => ("unexpected end of file", "意外到达文件末尾"),
+        InvalidDecoratorArgs => ("invalid decorator arguments", "装饰器的参数不对"),
+        ExpectedUiAttrCall => ("expected a `.attr(..)` call after a UI component", "UI 组件后面应该是 `.attr(..)` 属性方法"),
+        UnsupportedNamespaceMember => ("unsupported namespace member", "namespace 里不支持的成员"),
+        ExpectedModulePath => ("expected a module path string", "应该是模块路径字符串"),
+        ClassNotAllowedHere => ("classes can only be declared at the top level", "class 只能在顶层声明"),
+        ImportNotAllowedHere => ("imports are only allowed at the top level", "import 只能在顶层"),
+        NamespaceNotAllowedHere => ("namespaces can only be declared at the top level", "namespace 只能在顶层声明"),
+        DeclareNotAllowedHere => ("`declare` is only allowed at the top level", "`declare` 只能在顶层"),
+        ExportNotAllowedHere => ("exports are only allowed at the top level", "export 只能在顶层"),
+        MissingInitializer => ("variable declaration needs an initializer", "变量声明缺少初始值"),
+        MissingRBracket => ("missing `]`", "缺少 `]`"),
+        RestParamMustBeLast => ("rest parameter must be the last parameter", "剩余参数必须是最后一个参数"),
+        RestParamMustBeArray => ("rest parameter must have an array type", "剩余参数的类型必须是数组"),
+        UnsupportedUnionType => ("unsupported union type", "不支持的联合类型（只支持 `T | null` / `T | undefined`）"),
+        UnknownType => ("unknown type", "不认识的类型名"),
+        MissingGt => ("missing `>`", "缺少 `>`"),
+        ExpectedColon => ("missing `:`", "缺少 `:`"),
+        MissingRParen => ("missing `)`", "缺少 `)`"),
+        ExpectedArrow => ("missing `=>` in function type", "函数类型标注缺少 `=>`"),
+        ExpectedBlock => ("expected a block `{ .. }`", "应该是代码块 `{ .. }`"),
+        MissingElse => ("`if` expression needs an `else` branch", "`if` 缺少 `else` 分支"),
+        MissingCatchOrFinally => ("`try` needs a `catch` or `finally` block", "`try` 既没有 `catch` 也没有 `finally`"),
+        ConditionMustBeBool => ("condition must be a boolean", "条件必须是 boolean"),
+        UnknownStructure => ("unsupported construct", "不支持的写法"),
+        MixedNullishAndLogical => ("`??` mixed with `&&` or `||` needs parentheses", "`??` 和 `&&` / `||` 混用时要加括号"),
+        ExpectedExpr => ("expected an expression", "应该是表达式"),
+        ExpectedLiteral => ("expected a literal", "应该是字面量"),
+        ExpectedIdentifier => ("expected an identifier", "应该是标识符"),
+        MissingSemicolon => ("missing `;`", "缺少 `;`"),
+        ExpectedDot => ("missing `.`", "缺少 `.`"),
+        ExpectedAs => ("missing `as`", "缺少 `as`"),
+        ExpectedFrom => ("missing `from`", "缺少 `from`"),
+
+        // ---------- 模块 ----------
+        ModuleNotFound => ("imported module not found", "找不到 import 的文件"),
+        CodeInDeclarationFile => ("declaration files may only contain declarations", "`.d.ets` 声明文件里只能有声明"),
+        UnknownSystemModule => ("unknown system module", "不认识的系统模块"),
+        CircularImport => ("circular import", "循环 import"),
+        UnsupportedModulePath => ("unsupported module path", "不支持的模块路径"),
+        UnsupportedClassAlias => ("imported classes cannot be renamed", "import 的 class 不能改名"),
+        UnsupportedNamespaceAlias => ("imported namespaces cannot be renamed", "import 的 namespace 不能改名"),
+        DuplicateSymbol => ("top-level name `{name}` is defined more than once across modules", "合并模块后顶层名字 `{name}` 重复"),
+        UnsupportedConstImport => ("only consts initialized with a number or boolean constant expression can be imported, without renaming", "只能 import 初始值是 number/boolean 常量表达式的 const，而且不能改名"),
+        UnsupportedDefaultImport => ("default imports are not supported", "不支持默认导入"),
+        DuplicateModuleName => ("two modules map to the same Rust module name", "两个模块生成的 Rust 模块名相同"),
+        ImportNotExported => ("imported name `{name}` is not exported", "import 的名字 `{name}` 没有被导出"),
+
+        // ---------- 语义检查 ----------
+        DuplicateDeclaration => ("`{name}` is declared more than once in the same scope", "同一个作用域里重复声明了 `{name}`"),
+        UsedBeforeDeclaration => ("variable `{name}` used before its declaration", "变量 `{name}` 在声明之前使用"),
+        UndeclaredVariable => ("use of undeclared variable `{name}`", "使用了没有声明的变量 `{name}`"),
+        UnknownFunction => ("call to undeclared function `{name}`", "调用了没有声明的函数 `{name}`"),
+        ConstReassignment => ("cannot assign to const `{name}`", "不能给 const `{name}` 赋值"),
+
+        // ---------- 类型检查 ----------
+        MissingReturn => ("not all code paths return a value", "有返回值的函数在某条路径上没有 return"),
+        TypeMismatch => ("mismatched types", "类型不匹配"),
+        WrongArgCount => ("wrong number of arguments", "实参个数和形参个数不同"),
+        ArgTypeMismatch => ("argument type does not match the parameter", "实参的类型和形参不匹配"),
+
+        // ---------- 数据流检查 ----------
+        UseOfUninitialized => ("use of possibly unassigned variable `{name}`", "读取还没有明确赋值的变量 `{name}`"),
+
+        // ---------- 代码生成 ----------
+        NullNotAllowed => ("`null` is not allowed here", "这里不能是 null"),
+        UnresolvedImport => ("imports cannot be resolved when compiling a single source", "单份源码里不能处理 import / re-export"),
+        UnknownExternBinding => ("binding has no matching `declare function`", "外部绑定的名字没有对应的 `declare function`"),
+        UnsupportedComponentThrow => ("cannot throw inside a component method", "组件方法里不能 throw"),
+        UnsupportedAbilityThrow => ("cannot throw inside an Ability method", "Ability 方法里不能 throw"),
+        InvalidLifecycleParams => ("invalid Ability lifecycle parameters", "Ability 生命周期方法的参数不对"),
+        MissingFieldType => ("component field needs a type annotation", "组件字段没有类型标注，也推断不出类型"),
+        StateFieldNeedsInitializer => ("state field needs an initializer", "状态字段缺少初始值"),
+        StateFieldHasInitializer => ("`@Link` / `@Consume` fields cannot have an initializer", "`@Link` / `@Consume` 字段不能有初始值"),
+        InvalidBuilderArgs => ("invalid `@Builder` arguments", "`@Builder` 的实参不对"),
+        ExtendTargetMismatch => ("`@Extend` applied to a different component", "`@Extend` 的组件和使用的组件不同"),
+        InvalidStyleArgs => ("invalid `@Styles` / `@Extend` arguments", "`@Styles` / `@Extend` 的实参不对"),
+        InvalidForEach => ("invalid `ForEach` arguments", "`ForEach` 的实参不对"),
+        InvalidComponentArgs => ("invalid component arguments", "自定义组件的实参不对"),
+        UnknownComponentProp => ("component has no such property", "自定义组件没有这个属性"),
+        MissingComponentProp => ("missing required component property", "自定义组件缺少必须传的属性"),
+        ConsumeWithoutProvide => ("`@Consume` has no matching `@Provide`", "`@Consume` 找不到对应的 `@Provide`"),
+        LinkNeedsSharedSource => ("`@Link` source must be shareable state", "`@Link` 的来源必须是能共享的状态"),
+        TopLevelCodeInLibrary => ("top-level code is not allowed in library mode", "库模式下不能有顶层代码"),
+        SharedConstNotConstant => (
+            "a top-level constant used inside functions must be initialized with a constant expression",
+            "函数里用到的顶层常量必须用常量表达式初始化",
+        ),
+        RecursiveClosureUnsupported => ("closures cannot call themselves or each other recursively", "闭包不能递归调用自己，也不能互相调用"),
+        NullRequiresTypeAnnotation => ("`null` initializer needs a type annotation", "`null` 初始值需要类型标注"),
+        UnsupportedIndexAccess => ("unsupported index access", "不支持的下标访问"),
+        DuplicateClass => ("duplicate class declaration", "class 重复声明"),
+        UnsupportedClass => ("unsupported class (only subclasses of `Error` are supported)", "不支持的 class（只支持继承 `Error` 的异常类）"),
+        UnknownErrorClass => ("unknown error class", "不认识的异常类"),
+        TooManyConstructorArgs => ("too many constructor arguments", "构造异常对象的实参太多"),
+        ReturnValueRequired => ("`return` needs a value in this function", "有返回值的函数里 `return` 没有值"),
+        UnsupportedNativeType => ("type not supported in a native module interface", "原生模块接口不支持的类型"),
+        UnsupportedNativeSignature => ("signature not supported in a native module interface", "原生模块接口不支持的函数签名"),
+        UnsupportedSpreadArg => ("unsupported spread argument", "不支持的展开实参"),
+        UnsupportedConsoleArgs => ("unsupported `console` arguments", "不支持的 `console` 实参"),
+        AssertArgCount => ("wrong number of arguments to `assert`", "`assert` 的实参个数不对"),
+        UnknownMember => ("unknown member", "不认识的成员"),
+        ConversionArgCount => ("wrong number of arguments to a conversion function", "类型转换函数的实参个数不对"),
+        UnknownMethod => ("unknown method", "不认识的方法"),
+        UnknownStringMethod => ("unknown string method", "不认识的字符串方法"),
+        MathConstantNeedsFloat => ("`Math.PI` / `Math.E` need the f64 number mode", "`Math.PI` / `Math.E` 需要 f64 数字模式"),
+        FractionalNumberNeedsF64 => ("fractional result does not fit in i32", "小数放不进 i32：需要 f64 数字模式，或者先取整"),
+        IntegerDivisionByZero => ("integer division by zero: the NaN or Infinity result needs the f64 number mode", "整数除以 0：结果 NaN / Infinity 需要 f64 数字模式"),
+        FloatLiteralNeedsF64 => ("fractional literal needs the f64 number mode", "小数字面量需要 f64 数字模式"),
+        UnsupportedMemberAccess => ("unsupported member access", "不支持的成员访问"),
+        UnknownMathFunction => ("unknown `Math` function", "不认识的 `Math` 函数"),
+        MathArgCount => ("wrong number of arguments to a `Math` function", "`Math` 函数的实参个数不对"),
+        StringMethodArgCount => ("wrong number of arguments to a string method", "字符串方法的实参个数不对"),
+        InvalidResourceRef => ("invalid resource reference", "`$r(..)` / `$rawfile(..)` 的资源名不对"),
+        UnknownHilogFunction => ("unknown hilog function", "不认识的 hilog 函数"),
+        HilogArgCount => ("wrong number of arguments to a hilog function", "hilog 函数的实参个数不对"),
+        HilogFormatNotLiteral => ("hilog format must be a string literal", "hilog 的格式串必须是字面量"),
+        UnknownJsonFunction => ("unknown `JSON` function", "不认识的 `JSON` 函数"),
+        JsonNeedsSerde => ("`JSON` needs serde support (`--serde-json`)", "`JSON` 需要启用 serde（`--serde-json`）"),
+        JsonArgCount => ("wrong number of arguments to a `JSON` function", "`JSON` 函数的实参个数不对"),
+        UnknownDateFunction => ("unknown `Date` function", "不认识的 `Date` 函数"),
+        DateArgCount => ("wrong number of arguments to a `Date` function", "`Date` 函数的实参个数不对"),
+        UnsupportedDateArgs => ("unsupported `Date` arguments", "不支持的 `Date` 实参"),
+        UnknownDateMethod => ("unknown `Date` method", "不认识的 `Date` 方法"),
+        UnsupportedConversion => ("unsupported type conversion", "不支持的类型转换"),
+        TimerCallbackNotFunction => ("timer callback must be a function", "定时器的回调必须是函数"),
+        PromiseArgCount => ("wrong number of arguments to a `Promise` function", "`Promise` 函数的实参个数不对"),
+        SpreadRequiresRestParam => ("spread arguments can only be passed to a rest parameter", "展开实参只能传给剩余参数"),
+        NewOnlyInThrow => ("`new` is only supported in `throw`", "`new` 只能出现在 `throw` 里"),
+        DuplicateEntry => ("more than one `@Entry` component", "有多个 `@Entry` 组件"),
+        EntryFieldNeedsInitializer => ("`@Entry` component fields need an initializer", "`@Entry` 组件的字段缺少初始值"),
+        ConflictingStateDecorators => ("field has more than one state decorator", "一个字段上有多个状态装饰器"),
+        StaticStringNotLiteral => ("only string literals can be stored as `&'static str`", "`&'static str` 只能保存字符串字面量"),
+        AsyncNeedsEdition2018 => ("`Promise.all` needs Rust edition 2018 or later", "`Promise.all` 需要 Rust 2018 及以上的 edition"),
+        FormatterUnavailable => ("code formatter is not available", "找不到代码格式化工具"),
+        FormatFailed => ("failed to format the generated code", "格式化生成的代码失败"),
+        IntrinsicArgCount => ("not enough arguments for the intrinsic template", "实参个数少于内置函数映射模板用到的个数"),
+        SourceMapFormatter => ("source maps cannot be generated with the prettyplease formatter", "使用 prettyplease 格式化时不能生成源码映射"),
+        RustcUnavailable => ("rustc is not available to check the generated code", "找不到 rustc，不能检查生成的代码"),
+        GeneratedCodeError => ("the generated Rust code does not compile", "生成的 Rust 代码编译失败"),
+        CompileFailed => ("compilation failed", "编译失败"),
+
+        // ---------- 警告 ----------
+        IgnoredDecorator => ("decorator is ignored", "装饰器被忽略"),
+        TruthyCondition => ("non-boolean condition converted with JS truthiness", "条件不是 boolean，按 JS 的 truthy 规则转换"),
+        InsertedSemicolon => ("semicolon inserted automatically", "自动插入了分号"),
+        TopLevelCodeSkipped => ("top-level code is skipped in library mode", "库模式下跳过了顶层代码"),
+        MainFunctionRenamed => ("function `main` is renamed; the generated `fn main` runs the top-level code", "函数 `main` 改了名，生成的 `fn main` 执行顶层代码"),
+        UnusedVariable => ("unused variable `{name}`", "没有使用的变量 `{name}`"),
+        UnusedFunction => ("unused function `{name}`", "没有使用的函数 `{name}`"),
+        SelfAssignment => ("variable is assigned to itself", "变量赋值给了它自己"),
+        EmptyBranch => ("branch is an empty block", "分支是空代码块"),
+        SelfComparison => ("value is compared with itself", "变量和它自己比较"),
+        ConstantCondition => ("condition is always the same", "条件的值总是一样的"),
+        InfiniteLoop => ("loop never ends", "循环永远不会结束"),
+        DeadStore => ("value assigned is never read", "赋的值没有被读取"),
+        CustomLint => ("{message} (lint `{lint}`)", "{message}（lint `{lint}`）"),
+    }
+}
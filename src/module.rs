@@ -0,0 +1,1158 @@
+use crate::ast::{
+    Alias, BinaryOp, BlockStmt, Expr, ImportClause, Literal, Namespace, Pattern, Program, Stmt, SystemImport, VarDecl,
+};
+use crate::diagnostics::Diagnostics;
+use crate::error::{Error, ErrorCode, NoteCode};
+use crate::lexer::Token;
+use crate::parser::ParseOptions;
+use crate::rename;
+use crate::span::{NodeSpan, Span};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 多文件编译里的一个模块：一个 `.ets` 文件解析出来的 Program。
+///
+/// `deps[i]` 是 `program.imports[i]` 解析后的文件路径（已经规范化，可以直接比较），
+/// `reexport_deps[i]` 对应 `program.reexports[i]`。
+#[derive(Clone, Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    /// 源文件编号：这个模块里所有位置的 `Span::file`（见 `SourceFiles`）
+    pub file: u32,
+    pub program: Program,
+    pub deps: Vec<PathBuf>,
+    pub reexport_deps: Vec<PathBuf>,
+}
+
+/// 模块路径的解析配置，作用类似 tsconfig 里的 `baseUrl` / `paths`。
+///
+/// - `base_dir`：非相对路径（不以 `./`、`../` 开头）的根目录，`paths` 里的目标也相对它
+///   （它自己是相对路径时按当前工作目录解析）；`None` 时 `paths` 相对入口文件所在目录，
+///   且不匹配任何别名的非相对路径报错
+/// - `paths`：路径别名，`("@common/*", ["src/common/*"])` 把 `'@common/log'` 映射到
+///   `<base_dir>/src/common/log`。模式里最多一个 `*`；多个模式都匹配时取 `*` 前缀最长的，
+///   同一个模式的多个目标按顺序尝试
+/// - `extensions`：模块路径没写后缀时依次尝试的后缀，默认 `[".ets", ".d.ets"]`
+/// - `parse`：解析加载到的每个文件时用的选项（见 `ParseOptions`）
+/// - `system_modules`：系统模块（`@ohos.data.preferences` 等）映射到的 Rust 路径，
+///   默认是 `starter_system_modules()`（见 `SystemModules`）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolveConfig {
+    pub base_dir: Option<PathBuf>,
+    pub paths: Vec<(String, Vec<String>)>,
+    pub extensions: Vec<String>,
+    pub parse: ParseOptions,
+    pub system_modules: SystemModules,
+}
+
+impl Default for ResolveConfig {
+    fn default() -> Self {
+        Self {
+            base_dir: None,
+            paths: Vec::new(),
+            extensions: vec![".ets".to_string(), ".d.ets".to_string()],
+            parse: ParseOptions::default(),
+            system_modules: starter_system_modules(),
+        }
+    }
+}
+
+/// 系统模块的映射：模块路径原文 -> Rust 路径。
+///
+/// 平台 API 没有对应的 `.ets` 文件可以加载，import 它们时按这张表处理（见 `SystemImport`）：
+/// - `Some(path)`：import 的名字变成 `path` 下的 Rust 项，
+///   `import preferences from '@ohos.data.preferences'` -> `use ohos_runtime::data::preferences;`，
+///   `preferences.getPreferences(ctx, 'store')` -> `preferences::getPreferences(ctx, String::from("store"))`
+/// - `None`：API 由转换器内置处理（`hilog`、`UIAbility` 等），import 直接忽略
+///
+/// 表里没有的 `@ohos.*` / `@kit.*` 模块报 `UnknownSystemModule`。
+pub type SystemModules = BTreeMap<String, Option<String>>;
+
+/// 常用系统模块的默认映射。
+///
+/// 有 Rust 路径的模块都映射到 `ohos_runtime` crate 里同名（snake_case）的模块，
+/// 生成的代码需要一个提供这些函数的运行时 crate；换成别的实现时改 `ResolveConfig::system_modules`。
+pub fn starter_system_modules() -> SystemModules {
+    let builtin = [
+        "@ohos.hilog",
+        "@ohos.app.ability.UIAbility",
+        "@ohos.app.ability.Want",
+        "@ohos.app.ability.AbilityConstant",
+        "@kit.AbilityKit",
+        "@kit.ArkUI",
+        "@kit.PerformanceAnalysisKit",
+    ];
+    let mapped = [
+        ("@ohos.window", "ohos_runtime::window"),
+        ("@ohos.router", "ohos_runtime::router"),
+        ("@ohos.promptAction", "ohos_runtime::prompt_action"),
+        ("@ohos.data.preferences", "ohos_runtime::data::preferences"),
+        ("@ohos.file.fs", "ohos_runtime::file::fs"),
+        ("@ohos.net.http", "ohos_runtime::net::http"),
+        ("@ohos.util", "ohos_runtime::util"),
+        ("@ohos.deviceInfo", "ohos_runtime::device_info"),
+        ("@kit.ArkData", "ohos_runtime::data"),
+        ("@kit.CoreFileKit", "ohos_runtime::file"),
+        ("@kit.NetworkKit", "ohos_runtime::net"),
+    ];
+    builtin
+        .iter()
+        .map(|m| (m.to_string(), None))
+        .chain(mapped.iter().map(|(m, path)| (m.to_string(), Some(path.to_string()))))
+        .collect()
+}
+
+/// 看起来是系统模块的路径：`@ohos.` / `@kit.` 开头
+fn is_system_source(source: &str) -> bool {
+    source.starts_with("@ohos.") || source.starts_with("@kit.")
+}
+
+impl ResolveConfig {
+    /// 在 `paths` 里查找匹配 `source` 的别名，返回替换 `*` 之后的候选路径（按优先级排列）。
+    fn alias_targets(&self, source: &str) -> Option<Vec<String>> {
+        // (优先级, 目标)：没有 `*` 的精确匹配优先级最高，其次 `*` 前缀越长越优先
+        let mut best: Option<(usize, Vec<String>)> = None;
+        for (pattern, targets) in &self.paths {
+            let (rank, captured) = match pattern.split_once('*') {
+                Some((prefix, suffix))
+                    if source.len() >= prefix.len() + suffix.len()
+                        && source.starts_with(prefix)
+                        && source.ends_with(suffix) =>
+                {
+                    (prefix.len(), &source[prefix.len()..source.len() - suffix.len()])
+                }
+                None if pattern == source => (usize::MAX, ""),
+                _ => continue,
+            };
+            if best.as_ref().is_some_and(|(r, _)| *r >= rank) {
+                continue;
+            }
+            let replaced = targets.iter().map(|t| t.replacen('*', captured, 1)).collect();
+            best = Some((rank, replaced));
+        }
+        best.map(|(_, targets)| targets)
+    }
+}
+
+/// 从入口文件出发，按默认配置加载它 import 的所有 `.ets` 文件。
+///
+/// 返回的模块按“依赖在前”的顺序排列，入口文件总是最后一个。
+/// 同一个文件被多处 import 时只加载一次。
+pub fn load_modules(entry: &Path) -> Result<Vec<Module>, Diagnostics> {
+    load_modules_with(entry, &ResolveConfig::default())
+}
+
+/// 按指定的路径解析配置加载模块（见 `ResolveConfig`）。
+///
+/// 错误会带上出错的文件名（见 `Error::file`）：
+/// - 文件里有词法、语法错误：报告这个文件的全部这些错误（见 `lex_recovering`、`parse_recovering`）
+/// - 文件读不到：`ModuleNotFound`
+/// - 非相对路径既没有匹配的别名，也没有配置 `base_dir`：`UnsupportedModulePath`
+/// - `@ohos.*` / `@kit.*` 模块不在 `system_modules` 里（也没有匹配的别名）：`UnknownSystemModule`
+/// - `.d.ets` 声明文件里出现了声明以外的代码：`CodeInDeclarationFile`
+/// - import 形成了环（`a` import `b`，`b` 又 import `a`）：`CircularImport`，
+///   位置是把环闭合的那条 import，`notes` 按顺序列出环上的每一条 import
+///
+/// 每个文件按开始加载的顺序从 1 编号，解析出来的位置都带着这个编号（`Span::file`），
+/// 合并之后检查出来的问题用 `SourceFiles` 找回所在的文件。
+pub fn load_modules_with(entry: &Path, config: &ResolveConfig) -> Result<Vec<Module>, Diagnostics> {
+    let entry = fs::canonicalize(entry)
+        .map_err(|_| Error::new(ErrorCode::ModuleNotFound, Span::default()).in_file(display(entry)))?;
+    let root = entry.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+    let mut loader = Loader {
+        config,
+        root,
+        modules: Vec::new(),
+        visited: HashSet::new(),
+        files: 0,
+        path: Vec::new(),
+    };
+    loader.visit(entry)?;
+    Ok(loader.modules)
+}
+
+/// 深度优先加载：先加载所有依赖，再把自己放进列表（后序），这样依赖总在前面。
+///
+/// `path` 记录当前正在加载的 import 链：每一项是 `(import 所在文件, 模块路径的位置)`，
+/// 要加载的文件如果已经在链上，说明出现了循环 import。
+struct Loader<'a> {
+    config: &'a ResolveConfig,
+    /// 入口文件所在目录（没有配置 `base_dir` 时的别名根目录）
+    root: PathBuf,
+    modules: Vec<Module>,
+    visited: HashSet<PathBuf>,
+    /// 已经编号的文件数
+    files: u32,
+    path: Vec<(PathBuf, Span)>,
+}
+
+impl Loader<'_> {
+    fn visit(&mut self, path: PathBuf) -> Result<(), Diagnostics> {
+        if !self.visited.insert(path.clone()) {
+            return Ok(());
+        }
+        let file = display(&path);
+        let src = fs::read_to_string(&path)
+            .map_err(|_| Error::new(ErrorCode::ModuleNotFound, Span::default()).in_file(&file))?;
+        self.files += 1;
+        let id = self.files;
+        let mut tokens = crate::lex_recovering(&src);
+        in_source_file(&mut tokens, id);
+        let (mut program, errors) = crate::parse_recovering(&tokens, &self.config.parse);
+        if !errors.is_empty() {
+            let mut diagnostics = Diagnostics::new();
+            diagnostics.extend(errors.into_iter().map(|e| e.in_file(&file)));
+            return Err(diagnostics);
+        }
+        if let Some(span) = file.ends_with(".d.ets").then(|| declaration_file_code(&program)).flatten() {
+            return Err(Error::new(ErrorCode::CodeInDeclarationFile, span).in_file(file).into());
+        }
+
+        // 系统模块没有文件可以加载，单独放进 `system_imports`
+        let mut imports = Vec::new();
+        for import in std::mem::take(&mut program.imports) {
+            if let Some(rust_path) = self.config.system_modules.get(&import.source) {
+                program.system_imports.push(SystemImport {
+                    clause: import.clause,
+                    source: import.source,
+                    rust_path: rust_path.clone(),
+                    span: import.span,
+                });
+            } else if is_system_source(&import.source) && self.config.alias_targets(&import.source).is_none() {
+                let err = Error::new(ErrorCode::UnknownSystemModule, import.source_span);
+                return Err(err.in_file(file).into());
+            } else {
+                imports.push(import);
+            }
+        }
+        program.imports = imports;
+
+        let mut deps = Vec::new();
+        for import in &program.imports {
+            deps.push(self.visit_dep(&path, &import.source, import.source_span)?);
+        }
+        let mut reexport_deps = Vec::new();
+        for reexport in &program.reexports {
+            reexport_deps.push(self.visit_dep(&path, &reexport.source, reexport.source_span)?);
+        }
+        self.modules.push(Module {
+            path,
+            file: id,
+            program,
+            deps,
+            reexport_deps,
+        });
+        Ok(())
+    }
+
+    fn visit_dep(&mut self, importer: &Path, source: &str, span: Span) -> Result<PathBuf, Diagnostics> {
+        let dep = self
+            .resolve_import(importer, source)
+            .map_err(|e| Error::new(e.code, span).in_file(display(importer)))?;
+        // import 自己也算环（此时 importer 还不在链上）
+        let start = if dep == importer {
+            Some(self.path.len())
+        } else {
+            self.path.iter().position(|(file, _)| *file == dep)
+        };
+        if let Some(start) = start {
+            return Err(self.cycle_error(start, importer, span).into());
+        }
+        self.path.push((importer.to_path_buf(), span));
+        let result = self.visit(dep.clone());
+        self.path.pop();
+        result?;
+        Ok(dep)
+    }
+
+    /// 构造 `CircularImport`：环从 `self.path[start]` 开始，由 `importer` 里位于 `span` 的 import 闭合。
+    fn cycle_error(&self, start: usize, importer: &Path, span: Span) -> Error {
+        let mut cycle: Vec<(&Path, Span)> =
+            self.path[start..].iter().map(|(file, span)| (file.as_path(), *span)).collect();
+        cycle.push((importer, span));
+        let mut err = Error::new(ErrorCode::CircularImport, span).in_file(display(importer));
+        for (i, (file, span)) in cycle.iter().enumerate() {
+            let next = cycle.get(i + 1).map_or(cycle[0].0, |(f, _)| f);
+            err = err.with_note_args(display(file), *span, NoteCode::Imports, &[("file", &display(next))]);
+        }
+        err
+    }
+
+    /// 把 import 里的模块路径解析成文件路径：
+    /// - `./`、`../` 开头：相对于 import 所在文件的目录
+    /// - 匹配 `paths` 里的别名：替换后相对于 `base_dir`（没有配置时是入口文件所在目录）
+    /// - 其它非相对路径：相对于 `base_dir`
+    ///
+    /// 没写后缀时依次补上 `extensions` 里的后缀。
+    ///
+    /// 例：`/src/main.ets` 里的 `'./util'` -> `/src/util.ets`（或 `/src/util.d.ets`）
+    fn resolve_import(&self, importer: &Path, source: &str) -> Result<PathBuf, Error> {
+        let base = self.config.base_dir.clone();
+        let bases: Vec<PathBuf> = if source.starts_with("./") || source.starts_with("../") {
+            let dir = importer.parent().unwrap_or_else(|| Path::new("."));
+            vec![dir.join(source)]
+        } else if let Some(targets) = self.config.alias_targets(source) {
+            let base = base.unwrap_or_else(|| self.root.clone());
+            targets.iter().map(|t| base.join(t)).collect()
+        } else if let Some(base) = base {
+            vec![base.join(source)]
+        } else {
+            return Err(Error::new(ErrorCode::UnsupportedModulePath, Span::default()));
+        };
+
+        let exts = &self.config.extensions;
+        let has_ext = exts.iter().any(|ext| source.ends_with(ext.as_str())) || source.ends_with(".ets");
+        bases
+            .iter()
+            .flat_map(|b| {
+                let mut candidates = Vec::new();
+                if has_ext {
+                    candidates.push(b.clone());
+                } else {
+                    for ext in exts {
+                        let mut file = b.clone().into_os_string();
+                        file.push(ext);
+                        candidates.push(PathBuf::from(file));
+                    }
+                }
+                candidates
+            })
+            .find(|file| file.is_file())
+            .and_then(|file| fs::canonicalize(file).ok())
+            .ok_or_else(|| Error::new(ErrorCode::ModuleNotFound, Span::default()))
+    }
+}
+
+/// 把 token（和挂在上面的注释）的位置登记到编号为 `file` 的源文件。
+fn in_source_file(tokens: &mut [Token], file: u32) {
+    for token in tokens {
+        token.span.file = file;
+        for comment in token.leading.iter_mut().chain(&mut token.trailing) {
+            comment.span.file = file;
+        }
+    }
+}
+
+/// 多文件编译的源文件表：`Span::file` 编号 -> 文件名（见 `load_modules_with`）。
+///
+/// 检查和 CodeGen 在合并后的程序上进行，报告的位置不知道来自哪个文件，打印之前用它补上（见 `Diagnostics::locate`）。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceFiles(BTreeMap<u32, String>);
+
+impl SourceFiles {
+    pub fn of(modules: &[Module]) -> Self {
+        SourceFiles(modules.iter().map(|m| (m.file, display(&m.path))).collect())
+    }
+
+    /// `span` 所在文件的文件名；没有登记的编号返回 None。
+    pub fn name(&self, span: Span) -> Option<&str> {
+        self.0.get(&span.file).map(String::as_str)
+    }
+}
+
+/// 声明文件只能包含 import/re-export、`declare function` 和 class 声明；返回第一个不允许的函数、namespace 或语句的位置。
+fn declaration_file_code(program: &Program) -> Option<Span> {
+    let funcs = program.funcs.iter().map(|f| f.span.0);
+    let decls = program.namespace_decls.iter().map(|n| n.span.0);
+    funcs.chain(decls).chain(program.stmts.iter().map(Stmt::span)).min_by_key(|s| s.start)
+}
+
+/// 把多个模块合并成一个 Program，交给 CodeGen 生成单个 Rust 文件。
+///
+/// 合并前先做跨模块的符号检查，有错误时一起返回找到的全部错误（出错的那一项跳过，接着检查其它的）：
+/// - import 的名字必须是目标模块 `export` 的函数或 class（或者它 re-export 的名字），
+///   否则 `ImportNotExported`
+/// - import 的 const 必须是 `export const` 声明、初始值是 number/boolean 常量表达式的名字（见 `importable_consts`），
+///   或者不改名地 re-export 这样的名字（`export { PI } from './a'`），而且不能改名；其它情况（`export let`、
+///   改了名的 re-export、`as` 改名）报 `UnsupportedConstImport`，指向 import 的那一项
+/// - 每个模块有自己的作用域：模块自己的顶层函数等和它 import 进来的名字（包括映射到 Rust 路径的系统模块 import）
+///   重名报 `DuplicateSymbol`，指向 import 的那一项。不同模块的私有函数可以同名。同一个文件里重复声明的顶层函数等
+///   留给合并后的语义检查，和单文件编译一样报 `DuplicateDeclaration`，不影响其它错误的报告
+/// - 默认导入只能用于系统模块：`import util from './util'` 报 `UnsupportedDefaultImport`
+///
+/// 重命名的处理：合并后只剩一个作用域，所有名字最终都追到定义它的顶层函数上：
+/// - `import { a as b }` -> 别名 `b -> a`（CodeGen 生成 `use crate::a as b;`）
+/// - `import * as util` -> 命名空间 `util`，成员是目标模块的全部导出函数
+/// - `export { a as b } from './m'` -> 本模块的导出表里 `b` 指向 `a`；改了名字的同时记成
+///   导出别名（库模式下生成 `pub use crate::a as b;`）
+/// - class 是 `ArkError` 的变体，不能改名（`UnsupportedClassAlias`），也不会进入命名空间
+/// - `namespace` 声明生成的是 Rust 模块，同样不能改名（`UnsupportedNamespaceAlias`），
+///   也不会进入 `import * as` 的命名空间
+///
+/// 只在一个模块里可见的名字（私有函数、import 的别名和命名空间）和合并后别的顶层名字冲突时改名
+/// （`helper` -> `helper_2`，见 `rename::rename_apart`），模块里的引用跟着改；入口模块先分配，保留原来的名字。
+/// 其它顶层名字（导出的函数、外部函数声明、namespace 声明、`@Builder` / `@Styles`、re-export 的别名、
+/// 被 import 的 const、系统模块 import）不改名，在不同模块里重名报 `DuplicateSymbol`。
+///
+/// 合并规则：
+/// - 函数、外部函数声明、class 和 namespace 声明按模块顺序拼接
+/// - 依赖模块的顶层语句各自包进一个块里（保持模块之间的局部变量互不可见），
+///   按加载顺序放在入口模块的语句之前，效果接近 JS 里“被 import 的模块先执行”。
+///   被 import 的 const 从块里提到顶层，放在块前面（初始值是常量，提前不影响结果），CodeGen 生成模块级的 `const`
+/// - 注释按文件里的位置记录，只有一个模块时才保留（见 `ast::Comments`）；文件名（`Program::file`）同样只在只有一个模块时保留
+pub fn link(mut modules: Vec<Module>) -> Result<Program, Diagnostics> {
+    let linked = resolve(&mut modules)?;
+    Ok(merge(modules, linked))
+}
+
+/// `resolve` 的结果：合并后的程序里的系统模块 import、别名和命名空间，以及每个模块里被 import、要提到顶层的 const。
+struct Linked {
+    system_imports: Vec<SystemImport>,
+    aliases: Vec<Alias>,
+    namespaces: Vec<Namespace>,
+    lifted: HashMap<PathBuf, HashSet<String>>,
+    /// 每个模块里可以被 import 的 const：名字 -> 声明它的模块
+    consts: HashMap<PathBuf, HashMap<String, PathBuf>>,
+}
+
+/// 模块 import 进来、合并后要占一个顶层名字的东西：改了名字的 import 和 `import * as`。
+enum Imported {
+    Alias(Alias),
+    Namespace(Namespace),
+}
+
+impl Imported {
+    fn name(&self) -> &str {
+        match self {
+            Imported::Alias(a) => &a.name,
+            Imported::Namespace(n) => &n.name,
+        }
+    }
+}
+
+/// `link` 的检查部分：检查 import、re-export 和各个模块的作用域，给和别的模块冲突的私有名字改名（直接改写 `modules`）。
+fn resolve(modules: &mut [Module]) -> Result<Linked, Diagnostics> {
+    let classes: HashSet<&str> = modules
+        .iter()
+        .flat_map(|m| m.program.classes.iter().map(|c| c.name.as_str()))
+        .collect();
+    let namespace_decls: HashSet<&str> = modules
+        .iter()
+        .flat_map(|m| m.program.namespace_decls.iter().map(|n| n.name.as_str()))
+        .collect();
+    let check_alias = |target: &str| -> Result<(), Error> {
+        if classes.contains(target) {
+            Err(Error::new(ErrorCode::UnsupportedClassAlias, Span::default()))
+        } else if namespace_decls.contains(target) {
+            Err(Error::new(ErrorCode::UnsupportedNamespaceAlias, Span::default()))
+        } else {
+            Ok(())
+        }
+    };
+
+    // 每个模块的导出表：导出名 -> 定义它的顶层函数/class 名。
+    // 依赖总在前面加载，所以处理 re-export 时目标模块的导出表已经建好。
+    let mut exports: HashMap<&Path, HashMap<String, String>> = HashMap::new();
+    let mut const_exports: HashMap<&Path, HashSet<String>> = HashMap::new();
+    // 可以被 import 的 const：模块 -> 名字 -> 声明它的模块（不改名的 re-export 追到声明它的模块）
+    let mut importable: HashMap<PathBuf, HashMap<String, PathBuf>> = HashMap::new();
+    let mut aliases: Vec<Alias> = Vec::new();
+    // 找到的全部错误：出错的 import/re-export 跳过，接着检查其它的
+    let mut errors = Diagnostics::new();
+    for m in modules.iter() {
+        let file = display(&m.path);
+        let mut table = own_exports(&m.program);
+        let mut origins: HashMap<String, PathBuf> =
+            importable_consts(&m.program).into_iter().map(|name| (name.to_string(), m.path.clone())).collect();
+        let mut consts: HashSet<String> = m
+            .program
+            .stmts
+            .iter()
+            .filter_map(|s| match s {
+                Stmt::VarDecl(v) if v.is_export => Some(v.names()),
+                _ => None,
+            })
+            .flatten()
+            .map(String::from)
+            .collect();
+
+        for (reexport, dep) in m.program.reexports.iter().zip(&m.reexport_deps) {
+            for spec in &reexport.specs {
+                let name = spec.local_name();
+                if const_exports.get(dep.as_path()).is_some_and(|c| c.contains(&spec.name)) {
+                    consts.insert(name.to_string());
+                    let origin = importable.get(dep).and_then(|c| c.get(&spec.name)).filter(|_| spec.alias.is_none());
+                    if let Some(origin) = origin {
+                        origins.insert(name.to_string(), origin.clone());
+                    }
+                    continue;
+                }
+                let located = |e: Error| e.at(spec.span.0).in_file(&file);
+                let target = match lookup_export(&exports, dep, &spec.name) {
+                    Ok(target) => target,
+                    Err(e) => {
+                        errors.push(located(e));
+                        continue;
+                    }
+                };
+                if name != target {
+                    if let Err(e) = check_alias(&target).and_then(|_| push_alias(&mut aliases, name, &target, true)) {
+                        errors.push(located(e));
+                        continue;
+                    }
+                }
+                table.insert(name.to_string(), target);
+            }
+        }
+        exports.insert(&m.path, table);
+        const_exports.insert(&m.path, consts);
+        importable.insert(m.path.clone(), origins);
+    }
+
+    // 每个模块自己的顶层名字 -> 第一次声明的位置，以及其中重复声明的名字。
+    // 同一个文件里的重复声明不在这里报：合并后的语义检查和单文件编译一样报 `DuplicateDeclaration`（补充说明指向第一个声明），
+    // 同时还能报告其它错误
+    let mut own: Vec<HashMap<&str, Span>> = Vec::new();
+    let mut repeated: Vec<HashSet<&str>> = Vec::new();
+    for m in modules.iter() {
+        let mut names: HashMap<&str, Span> = HashMap::new();
+        let mut again = HashSet::new();
+        for (name, span) in top_level_names(&m.program) {
+            if names.contains_key(name) {
+                again.insert(name);
+            }
+            names.entry(name).or_insert(span);
+        }
+        own.push(names);
+        repeated.push(again);
+    }
+
+    // 被 import 的 const：模块 -> 名字，合并时提到顶层
+    let mut lifted: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+    // 每个模块 import 进来的别名和命名空间（按模块顺序）
+    let mut imported: Vec<Vec<Imported>> = Vec::new();
+    for (m, own) in modules.iter().zip(&own) {
+        let file = display(&m.path);
+        // 模块作用域里 import 进来的名字 -> 指向的东西（目标函数名、`<文件>::<const>`、`* <文件>`），
+        // 同一个名字指向同一个东西的重复 import 不算冲突
+        let mut locals: HashMap<String, String> = HashMap::new();
+        let mut bind = |name: &str, what: String, span: Span| -> Result<(), Error> {
+            if own.contains_key(name) || locals.get(name).is_some_and(|w| *w != what) {
+                return Err(Error::new(ErrorCode::DuplicateSymbol, span).with_arg("name", name).in_file(&file));
+            }
+            locals.insert(name.to_string(), what);
+            Ok(())
+        };
+        let mut items = Vec::new();
+        for (import, dep) in m.program.imports.iter().zip(&m.deps) {
+            match &import.clause {
+                ImportClause::Named(specs) => {
+                    for spec in specs {
+                        if const_exports[dep.as_path()].contains(&spec.name) {
+                            let origin = importable[dep].get(&spec.name).filter(|_| spec.alias.is_none());
+                            let Some(origin) = origin else {
+                                errors.push(Error::new(ErrorCode::UnsupportedConstImport, spec.span.0).in_file(&file));
+                                continue;
+                            };
+                            match bind(&spec.name, format!("{}::{}", origin.display(), spec.name), spec.span.0) {
+                                Ok(()) => {
+                                    lifted.entry(origin.clone()).or_default().insert(spec.name.clone());
+                                }
+                                Err(e) => errors.push(e),
+                            }
+                            continue;
+                        }
+                        let located = |e: Error| e.at(spec.span.0).in_file(&file);
+                        let target = match lookup_export(&exports, dep, &spec.name) {
+                            Ok(target) => target,
+                            Err(e) => {
+                                errors.push(located(e));
+                                continue;
+                            }
+                        };
+                        let name = spec.local_name();
+                        if let Err(e) = bind(name, target.clone(), spec.span.0) {
+                            errors.push(e);
+                            continue;
+                        }
+                        if name != target {
+                            if let Err(e) = check_alias(&target) {
+                                errors.push(located(e));
+                                continue;
+                            }
+                            items.push(Imported::Alias(Alias {
+                                name: name.to_string(),
+                                target,
+                                is_export: false,
+                            }));
+                        }
+                    }
+                }
+                ImportClause::Namespace(name) => {
+                    if let Err(e) = bind(name, format!("* {}", dep.display()), import.span.0) {
+                        errors.push(e);
+                        continue;
+                    }
+                    let mut members: Vec<Alias> = exports[dep.as_path()]
+                        .iter()
+                        .filter(|(_, target)| {
+                            !classes.contains(target.as_str()) && !namespace_decls.contains(target.as_str())
+                        })
+                        .map(|(member, target)| Alias {
+                            name: member.clone(),
+                            target: target.clone(),
+                            is_export: false,
+                        })
+                        .collect();
+                    members.sort_by(|a, b| a.name.cmp(&b.name));
+                    items.push(Imported::Namespace(Namespace {
+                        name: name.clone(),
+                        members,
+                    }));
+                }
+                ImportClause::Default(_) => {
+                    errors.push(Error::new(ErrorCode::UnsupportedDefaultImport, import.source_span).in_file(&file));
+                }
+            }
+        }
+        for import in m.program.system_imports.iter().filter(|i| i.rust_path.is_some()) {
+            for name in import.local_names() {
+                if let Err(e) = bind(name, format!("{} {}", import.source, name), import.span.0) {
+                    errors.push(e);
+                }
+            }
+        }
+        imported.push(items);
+    }
+
+    // 不改名的顶层名字 -> (定义它的文件, 定义的位置)：不同模块里重名报 `DuplicateSymbol`
+    let mut fixed: HashMap<&str, (&Path, Span)> = HashMap::new();
+    for m in modules.iter() {
+        for (name, span) in top_level_names(&m.program) {
+            if m.program.funcs.iter().any(|f| f.name == name && !f.is_export) {
+                continue;
+            }
+            match fixed.get(name) {
+                Some((path, _)) if *path != m.path => {
+                    errors.push(Error::new(ErrorCode::DuplicateSymbol, span).with_arg("name", name).in_file(display(&m.path)));
+                }
+                Some(_) => {}
+                None => {
+                    fixed.insert(name, (&m.path, span));
+                }
+            }
+        }
+    }
+    // re-export 的别名和顶层函数共用一个作用域
+    for alias in &aliases {
+        if let Some((path, span)) = fixed.get(alias.name.as_str()) {
+            let err = Error::new(ErrorCode::DuplicateSymbol, *span).with_arg("name", alias.name.as_str());
+            errors.push(err.in_file(display(path)));
+        }
+    }
+
+    // 各模块相同的系统模块 import 只保留一条；同一个名字指向不同的东西时报错
+    let mut system_imports: Vec<SystemImport> = Vec::new();
+    for m in modules.iter() {
+        for import in &m.program.system_imports {
+            if system_imports.contains(import) {
+                continue;
+            }
+            let names = match import.rust_path {
+                Some(_) => import.local_names(),
+                None => Vec::new(),
+            };
+            let taken = names.into_iter().find(|name| {
+                system_imports
+                    .iter()
+                    .filter(|i| i.rust_path.is_some())
+                    .any(|i| i.local_names().contains(name))
+                    || fixed.contains_key(name)
+                    || aliases.iter().any(|a| a.name == *name)
+            });
+            match taken {
+                Some(name) => {
+                    let err = Error::new(ErrorCode::DuplicateSymbol, import.span.0).with_arg("name", name);
+                    errors.push(err.in_file(display(&m.path)));
+                }
+                None => system_imports.push(import.clone()),
+            }
+        }
+    }
+
+    // 提到顶层的 const 同样不改名（按模块顺序检查，报告的顺序固定）
+    let mut lifted_names: HashSet<&str> = HashSet::new();
+    for m in modules.iter() {
+        let mut names: Vec<&String> = lifted.get(&m.path).into_iter().flatten().collect();
+        names.sort();
+        for name in names {
+            let taken = fixed.contains_key(name.as_str())
+                || aliases.iter().any(|a| &a.name == name)
+                || system_imports.iter().any(|i| i.rust_path.is_some() && i.local_names().contains(&name.as_str()));
+            if taken || !lifted_names.insert(name) {
+                let err = Error::new(ErrorCode::DuplicateSymbol, const_span(&m.program, name)).with_arg("name", name.as_str());
+                errors.push(err.in_file(display(&m.path)));
+            }
+        }
+    }
+    if errors.has_errors() {
+        return Err(errors);
+    }
+
+    // 合并后已经占用的顶层名字
+    let mut taken: BTreeSet<String> = fixed.keys().map(|n| n.to_string()).collect();
+    taken.extend(aliases.iter().map(|a| a.name.clone()));
+    taken.extend(lifted_names.iter().map(|n| n.to_string()));
+    taken.extend(system_imports.iter().filter(|i| i.rust_path.is_some()).flat_map(|i| i.local_names()).map(String::from));
+    // 重复声明的名字交给语义检查报告，保持原来的名字
+    taken.extend(repeated.iter().flatten().map(|n| n.to_string()));
+
+    // 只在一个模块里可见的名字：入口模块先分配，冲突的改名
+    let entry_idx = modules.len().saturating_sub(1);
+    let order = std::iter::once(entry_idx).chain(0..entry_idx);
+    let mut namespaces: Vec<Namespace> = Vec::new();
+    let mut renames: Vec<Vec<String>> = vec![Vec::new(); modules.len()];
+    for i in order {
+        let private = modules[i].program.funcs.iter().filter(|f| !f.is_export).map(|f| f.name.as_str());
+        let private = private.filter(|name| !repeated[i].contains(name));
+        for name in private {
+            if !taken.insert(name.to_string()) {
+                renames[i].push(name.to_string());
+            }
+        }
+        for item in std::mem::take(&mut imported[i]) {
+            let shared = match &item {
+                Imported::Alias(a) => aliases.iter().any(|b| b.name == a.name && b.target == a.target),
+                Imported::Namespace(n) => namespaces.contains(n),
+            };
+            if shared {
+                continue;
+            }
+            if taken.insert(item.name().to_string()) {
+                match item {
+                    Imported::Alias(a) => aliases.push(a),
+                    Imported::Namespace(n) => namespaces.push(n),
+                }
+                continue;
+            }
+            // 改名以后再记进别名、命名空间
+            renames[i].push(item.name().to_string());
+            imported[i].push(item);
+        }
+    }
+
+    // 改名时避开合并后的顶层名字和所有模块里声明过的名字
+    let mut avoid: BTreeSet<String> = modules.iter().flat_map(|m| rename::declared(&m.program)).collect();
+    avoid.extend(taken);
+    for (i, (m, names)) in modules.iter_mut().zip(renames).enumerate() {
+        let map = rename::rename_apart(&mut m.program, &names, &mut avoid);
+        let rename = |name: &mut String| {
+            if let Some(to) = map.get(name) {
+                *name = to.to_string();
+            }
+        };
+        for import in &mut m.program.imports {
+            match &mut import.clause {
+                ImportClause::Named(specs) => specs.iter_mut().filter_map(|s| s.alias.as_mut()).for_each(rename),
+                ImportClause::Namespace(name) => rename(name),
+                ImportClause::Default(_) => {}
+            }
+        }
+        for item in std::mem::take(&mut imported[i]) {
+            match item {
+                Imported::Alias(mut a) => {
+                    rename(&mut a.name);
+                    aliases.push(a);
+                }
+                Imported::Namespace(mut n) => {
+                    rename(&mut n.name);
+                    namespaces.push(n);
+                }
+            }
+        }
+    }
+
+    Ok(Linked {
+        system_imports,
+        aliases,
+        namespaces,
+        lifted,
+        consts: importable,
+    })
+}
+
+/// 模块自己的顶层名字和声明的位置：函数、外部函数声明、namespace 声明、`@Builder` / `@Styles` 函数。
+fn top_level_names(program: &Program) -> impl Iterator<Item = (&str, Span)> {
+    let funcs = program.funcs.iter().chain(&program.declare_funcs).map(|f| (f.name.as_str(), f.span.0));
+    let decls = program.namespace_decls.iter().map(|n| (n.name.as_str(), n.span.0));
+    let builders = program.builders.iter().map(|b| (b.name.as_str(), b.span.0));
+    let styles = program.styles.iter().map(|s| (s.name.as_str(), s.span.0));
+    funcs.chain(decls).chain(builders).chain(styles)
+}
+
+/// `link` 的合并部分（见 `link` 的合并规则）。
+fn merge(modules: Vec<Module>, linked: Linked) -> Program {
+    let Linked {
+        system_imports,
+        aliases,
+        namespaces,
+        lifted,
+        ..
+    } = linked;
+    let entry_idx = modules.len().saturating_sub(1);
+    let single = modules.len() == 1;
+    let mut program = Program {
+        system_imports,
+        aliases,
+        namespaces,
+        ..Default::default()
+    };
+    for (i, m) in modules.into_iter().enumerate() {
+        program.funcs.extend(m.program.funcs);
+        program.declare_funcs.extend(m.program.declare_funcs);
+        program.classes.extend(m.program.classes);
+        program.abilities.extend(m.program.abilities);
+        program.namespace_decls.extend(m.program.namespace_decls);
+        program.components.extend(m.program.components);
+        program.builders.extend(m.program.builders);
+        program.styles.extend(m.program.styles);
+        let file = display(&m.path);
+        program.warnings.extend(m.program.warnings.into_iter().map(|w| w.in_file(&file)));
+        if single {
+            program.comments = m.program.comments;
+            program.file = Some(file.clone());
+        }
+        if i == entry_idx {
+            program.stmts.extend(m.program.stmts);
+            continue;
+        }
+        let (top, stmts) = match lifted.get(&m.path) {
+            Some(names) => lift_consts(m.program.stmts, names),
+            None => (Vec::new(), m.program.stmts),
+        };
+        program.stmts.extend(top);
+        if !stmts.is_empty() {
+            program.stmts.push(Stmt::Block(BlockStmt {
+                stmts,
+                span: NodeSpan::default(),
+            }));
+        }
+    }
+    program
+}
+
+/// 可以被其它模块 import 的 const（见 `link`）：模块自己 `export const` 声明的名字，初始值是 number/boolean 的常量表达式
+/// （字面量和它们的运算）。CodeGen 一定能把它们生成为模块级的 `const`。
+fn importable_consts(program: &Program) -> HashSet<&str> {
+    program
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::VarDecl(v) if v.is_export && v.is_const => Some(&v.decls),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|d| match (&d.pattern, &d.init) {
+            (Pattern::Ident(name), Some(init)) if is_literal_const(init) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 顶层 const `name` 的声明位置（在 `importable_consts` 里的才会被查）。
+fn const_span(program: &Program, name: &str) -> Span {
+    program
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::VarDecl(v) => Some(&v.decls),
+            _ => None,
+        })
+        .flatten()
+        .find(|d| matches!(&d.pattern, Pattern::Ident(n) if n == name))
+        .map_or_else(Span::default, |d| d.span.0)
+}
+
+/// number/boolean 字面量和它们的运算。
+fn is_literal_const(e: &Expr) -> bool {
+    match e {
+        Expr::Literal(Literal::Number(_) | Literal::Float(_) | Literal::Bool(_), _) => true,
+        Expr::Unary(u) => is_literal_const(&u.expr),
+        Expr::Binary(b) => b.op != BinaryOp::NullishCoalesce && is_literal_const(&b.left) && is_literal_const(&b.right),
+        Expr::Group(inner) => is_literal_const(inner),
+        _ => false,
+    }
+}
+
+/// 把 `names` 里的 const 声明项从模块的顶层语句里拿出来：返回拿出来的声明和剩下的语句。
+fn lift_consts(stmts: Vec<Stmt>, names: &HashSet<String>) -> (Vec<Stmt>, Vec<Stmt>) {
+    let mut top = Vec::new();
+    let mut rest = Vec::new();
+    for s in stmts {
+        let Stmt::VarDecl(v) = s else {
+            rest.push(s);
+            continue;
+        };
+        let (lifted, kept): (Vec<_>, Vec<_>) = v
+            .decls
+            .iter()
+            .cloned()
+            .partition(|d| matches!(&d.pattern, Pattern::Ident(name) if names.contains(name)));
+        if !lifted.is_empty() {
+            top.push(Stmt::VarDecl(VarDecl { decls: lifted, ..v.clone() }));
+        }
+        if !kept.is_empty() {
+            rest.push(Stmt::VarDecl(VarDecl { decls: kept, ..v }));
+        }
+    }
+    (top, rest)
+}
+
+/// 模块树输出里的一个 Rust 模块，对应一个 `.ets` 文件（见 `link_tree`）。
+#[derive(Clone, Debug)]
+pub struct Unit {
+    /// `crate::` 之后的模块路径，例如 `common/log.ets` -> `["common", "log"]`；入口文件是空路径（crate 根）
+    pub path: Vec<String>,
+    /// 这个文件自己的函数、外部函数声明、namespace 声明和顶层语句。
+    ///
+    /// import/re-export 已经换成了 `uses`；class 统一放在合并后的 Program 里（它们是 `ArkError` 的变体）。
+    pub program: Program,
+    pub uses: Vec<Use>,
+}
+
+/// 模块树里一个文件引用其它文件的方式。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Use {
+    /// `import { target as name }` -> `use crate::<module>::<target> as <name>;`
+    ///
+    /// `is_export` 表示 re-export（`export { .. } from`），其它模块可以经过这个文件再 import 它。
+    Item {
+        module: Vec<String>,
+        target: String,
+        name: String,
+        is_export: bool,
+    },
+    /// `import * as name` -> `use crate::<module> as <name>;`
+    Module { module: Vec<String>, name: String },
+}
+
+/// 不合并文件的链接方式：每个 `.ets` 文件对应一个 Rust 模块。
+///
+/// 返回值：
+/// - 合并后的 Program（和 `link` 的结果一样，CodeGen 用它做全局的名字查找和异常分析）
+/// - 每个文件的 `Unit`，按加载顺序排列（依赖在前，入口文件最后）
+///
+/// 模块路径取自文件相对于“所有文件共同的上级目录”的路径，文件名去掉后缀并转成合法的
+/// Rust 标识符（`math-utils.d.ets` -> `math_utils`）。两个文件得到同一个模块路径时
+/// 报 `DuplicateModuleName`，和 `link` 的错误一起返回。
+pub fn link_tree(mut modules: Vec<Module>) -> Result<(Program, Vec<Unit>), Diagnostics> {
+    let entry_idx = modules.len().saturating_sub(1);
+    let dirs: Vec<&Path> = modules.iter().filter_map(|m| m.path.parent()).collect();
+    let root = dirs.iter().skip(1).fold(dirs.first().copied().unwrap_or(Path::new("")), |acc, d| {
+        acc.ancestors().find(|a| d.starts_with(a)).unwrap_or(Path::new(""))
+    });
+    let mut errors = Diagnostics::new();
+    let mut paths: Vec<Vec<String>> = Vec::new();
+    for (i, m) in modules.iter().enumerate() {
+        let path = if i == entry_idx {
+            Vec::new()
+        } else {
+            module_path(m.path.strip_prefix(root).unwrap_or(&m.path))
+        };
+        if paths.contains(&path) {
+            errors.push(Error::new(ErrorCode::DuplicateModuleName, Span::default()).in_file(display(&m.path)));
+        }
+        paths.push(path);
+    }
+
+    // 模块树里各个文件的名字用 `resolve` 改过名的，和合并后的 Program 一致
+    let mut linked = match resolve(&mut modules) {
+        Ok(linked) if !errors.has_errors() => linked,
+        Ok(_) => return Err(errors),
+        Err(found) => {
+            errors.extend(found);
+            return Err(errors);
+        }
+    };
+    let consts = std::mem::take(&mut linked.consts);
+    let program = merge(modules.clone(), linked);
+
+    // 顶层名字 -> 定义它的模块路径（class 不需要 use：它们是根模块里 `ArkError` 的变体）
+    let mut owner: HashMap<&str, &Vec<String>> = HashMap::new();
+    for (m, path) in modules.iter().zip(&paths) {
+        let funcs = m.program.funcs.iter().chain(&m.program.declare_funcs).map(|f| f.name.as_str());
+        let decls = m.program.namespace_decls.iter().map(|n| n.name.as_str());
+        for name in funcs.chain(decls) {
+            owner.insert(name, path);
+        }
+    }
+
+    // 记录一条 `use`；同一个名字既 import 又 re-export 时只保留一条（re-export）
+    let push_item = |uses: &mut Vec<Use>, target: &str, name: &str, is_export: bool| {
+        let Some(module) = owner.get(target) else {
+            return;
+        };
+        match uses.iter_mut().find(|u| matches!(u, Use::Item { name: n, .. } if n == name)) {
+            Some(Use::Item { is_export: e, .. }) => *e |= is_export,
+            _ => uses.push(Use::Item {
+                module: (*module).clone(),
+                target: target.to_string(),
+                name: name.to_string(),
+                is_export,
+            }),
+        }
+    };
+
+    // 可以被 import 的 const 所在模块的路径（`link` 检查过，import/re-export 里找不到导出的名字就是 const）
+    let const_module = |dep: &Path, name: &str| -> Option<Vec<String>> {
+        let origin = consts.get(dep)?.get(name)?;
+        modules.iter().position(|m| m.path == *origin).map(|i| paths[i].clone())
+    };
+
+    let mut exports: HashMap<&Path, HashMap<String, String>> = HashMap::new();
+    let mut units = Vec::new();
+    for (m, path) in modules.iter().zip(&paths) {
+        let mut table = own_exports(&m.program);
+        let mut uses = Vec::new();
+        for (reexport, dep) in m.program.reexports.iter().zip(&m.reexport_deps) {
+            for spec in &reexport.specs {
+                if let Ok(target) = lookup_export(&exports, dep, &spec.name) {
+                    push_item(&mut uses, &target, spec.local_name(), true);
+                    table.insert(spec.local_name().to_string(), target);
+                } else if let Some(module) = const_module(dep, &spec.name) {
+                    // re-export 的 const：`pub use` 声明它的模块里模块级的 `const`
+                    uses.push(Use::Item {
+                        module,
+                        target: spec.name.clone(),
+                        name: spec.local_name().to_string(),
+                        is_export: true,
+                    });
+                }
+            }
+        }
+        for (import, dep) in m.program.imports.iter().zip(&m.deps) {
+            match &import.clause {
+                ImportClause::Named(specs) => {
+                    for spec in specs {
+                        if let Ok(target) = lookup_export(&exports, dep, &spec.name) {
+                            push_item(&mut uses, &target, spec.local_name(), false);
+                        } else if let Some(module) = const_module(dep, &spec.name) {
+                            // import 的 const 是声明它的模块里模块级的 `const`，同样 `use`
+                            uses.push(Use::Item {
+                                module,
+                                target: spec.name.clone(),
+                                name: spec.name.clone(),
+                                is_export: false,
+                            });
+                        }
+                    }
+                }
+                ImportClause::Default(_) => {}
+                ImportClause::Namespace(name) => {
+                    let dep_idx = modules.iter().position(|d| d.path == *dep).unwrap_or(entry_idx);
+                    uses.push(Use::Module {
+                        module: paths[dep_idx].clone(),
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+        exports.insert(&m.path, table);
+        units.push(Unit {
+            path: path.clone(),
+            program: Program {
+                system_imports: m.program.system_imports.clone(),
+                funcs: m.program.funcs.clone(),
+                declare_funcs: m.program.declare_funcs.clone(),
+                abilities: m.program.abilities.clone(),
+                namespace_decls: m.program.namespace_decls.clone(),
+                components: m.program.components.clone(),
+                builders: m.program.builders.clone(),
+                styles: m.program.styles.clone(),
+                stmts: m.program.stmts.clone(),
+                comments: m.program.comments.clone(),
+                file: Some(display(&m.path)),
+                ..Default::default()
+            },
+            uses,
+        });
+    }
+    Ok((program, units))
+}
+
+/// `common/math-utils.ets` -> `["common", "math_utils"]`
+fn module_path(rel: &Path) -> Vec<String> {
+    let mut parts: Vec<String> = rel
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    if let Some(file) = parts.last_mut() {
+        // 去掉 `.ets`、`.d.ets` 之类的后缀
+        *file = file.split('.').next().unwrap_or_default().to_string();
+    }
+    parts.iter().map(|p| rust_ident(p)).collect()
+}
+
+/// 把文件/目录名转成合法的 Rust 模块名：小写，非字母数字换成 `_`，数字开头时前面补 `_`。
+fn rust_ident(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// 模块自己定义并导出的函数（包括 `@Builder` / `@Styles` / `@Extend` 函数）、class 和 namespace：`name -> name`（不含 re-export 和 const）。
+fn own_exports(program: &Program) -> HashMap<String, String> {
+    let funcs = program
+        .funcs
+        .iter()
+        .chain(&program.declare_funcs)
+        .filter(|f| f.is_export)
+        .map(|f| &f.name);
+    let classes = program.classes.iter().filter(|c| c.is_export).map(|c| &c.name);
+    let namespaces = program.namespace_decls.iter().filter(|n| n.is_export).map(|n| &n.name);
+    let builders = program.builders.iter().filter(|b| b.is_export).map(|b| &b.name);
+    let styles = program.styles.iter().filter(|s| s.is_export).map(|s| &s.name);
+    funcs
+        .chain(classes)
+        .chain(namespaces)
+        .chain(builders)
+        .chain(styles)
+        .map(|n| (n.clone(), n.clone()))
+        .collect()
+}
+
+/// 在模块 `dep` 的导出表里查 `name`，找不到报 `ImportNotExported`（没有位置，由调用方补上 import 的那一项）。
+fn lookup_export(
+    exports: &HashMap<&Path, HashMap<String, String>>,
+    dep: &Path,
+    name: &str,
+) -> Result<String, Error> {
+    exports
+        .get(dep)
+        .and_then(|table| table.get(name))
+        .cloned()
+        .ok_or_else(|| Error::new(ErrorCode::ImportNotExported, Span::default()).with_arg("name", name))
+}
+
+/// 记录一个别名。同名同目标的别名（多个模块写了同样的 import）只记一次；
+/// 同名不同目标报 `DuplicateSymbol`。
+fn push_alias(aliases: &mut Vec<Alias>, name: &str, target: &str, is_export: bool) -> Result<(), Error> {
+    match aliases.iter_mut().find(|a| a.name == name) {
+        Some(a) if a.target == target => {
+            a.is_export |= is_export;
+            Ok(())
+        }
+        Some(_) => Err(Error::new(ErrorCode::DuplicateSymbol, Span::default()).with_arg("name", name)),
+        None => {
+            aliases.push(Alias {
+                name: name.to_string(),
+                target: target.to_string(),
+                is_export,
+            });
+            Ok(())
+        }
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod parser;
 
-pub use parser::parse;
+pub use parser::{parse, parse_recovering, parse_with, ParseOptions};
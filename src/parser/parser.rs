@@ -1,15 +1,66 @@
 use crate::ast::{
-    AssignStmt, BinaryExpr, BinaryOp, BlockStmt, Callee, CallExpr, Expr, FuncDecl, IfStmt, Literal,
-    Param, Program, ReturnStmt, Stmt, TypeAnn, UnaryExpr, UnaryOp, VarDecl, WhileStmt,
+    AbilityDecl, AssignExpr, AssignStmt, AssignTarget, BinaryExpr, BinaryOp, BlockStmt, BuilderDecl, Callee, CallExpr, CatchClause, ClassDecl, Comments, ComponentDecl, ConsoleFn, Declarator, Decorator, Expr, FieldDecl, IndexExpr, MemberExpr,
+    FuncDecl, FuncTypeAnn, NewExpr, NullKind, IfStmt, ImportClause, ImportDecl, ImportSpec, NamespaceDecl, Literal, Param, Pattern, Program, PromiseFn, PropPattern, ReExportDecl, ReturnStmt, Stmt, StyleDecl, ThrowStmt,
+    TryStmt, TypeAnn, UiArg, UiAttr, UiElement, UiHandler, UiIf, UiNode, UnaryExpr, UnaryOp, VarDecl, WhileStmt,
+    ABILITY_LIFECYCLE,
 };
-use crate::error::Error;
+use std::collections::BTreeSet;
+
+use crate::diagnostics::did_you_mean;
+use crate::error::{Error, ErrorCode, Warning};
+use crate::lexer::error_of;
 use crate::lexer::token::Token;
 use crate::lexer::token::TokenKind;
-use crate::span::Span;
+use crate::span::{NodeSpan, Span};
+
+/// 类型标注里能写的类型名（见 `Parser::parse_type_atom`），`UnknownType` 的拼写建议用。
+const TYPE_NAMES: [&str; 6] = ["number", "string", "boolean", "void", "Date", "Promise"];
 
 /// 解析器入口：将 Token 列表解析为 Program AST。
 pub fn parse(tokens: &[Token]) -> Result<Program, Error> {
-    Parser::new(tokens).parse_program()
+    parse_with(tokens, &ParseOptions::default())
+}
+
+/// 按指定选项解析，警告收集在 `Program::warnings` 里。有语法错误时返回第一个。
+pub fn parse_with(tokens: &[Token], options: &ParseOptions) -> Result<Program, Error> {
+    let (program, errors) = parse_recovering(tokens, options);
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(program),
+    }
+}
+
+/// 带错误恢复的解析：一条语句出错后跳到下一条语句（见 `Parser::synchronize`）接着解析，
+/// 一次找出所有语法错误，而不是只报第一个。
+///
+/// 返回解析出来的 Program 和全部错误（按出现的顺序）。出错的语句 / 声明直接丢掉，AST 里没有占位节点：
+/// 有语法错误时后面的检查和代码生成都不再运行，不完整的 Program 只给需要“尽量解析”的工具使用。
+///
+/// `tokens` 可以来自 `lex_recovering`：Error token 当作解析不了的 token，同样跳到下一条语句。
+pub fn parse_recovering(tokens: &[Token], options: &ParseOptions) -> (Program, Vec<Error>) {
+    let mut parser = Parser::new(tokens, options.clone());
+    let program = parser.parse_program();
+    // 词法错误（`lex_recovering` 产生的 Error token）也报告出来；解析器正好停在 Error token 上报的错误
+    // 是同一个问题，只保留词法错误
+    let mut errors: Vec<Error> = tokens.iter().filter_map(error_of).collect();
+    let lexical = errors.len();
+    for err in parser.errors {
+        if !errors[..lexical].iter().any(|e| e.span == err.span) {
+            errors.push(err);
+        }
+    }
+    errors.sort_by_key(|e| e.span.start);
+    (program, errors)
+}
+
+/// 解析选项：放宽一些默认会报错的写法，让没有改过的 ArkTS 文件也能编译，同时给出警告。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// 自动分号插入（ASI）：语句末尾没写 `;` 时，如果下一个 token 在新的一行、是 `}` 或者已经到文件末尾，
+    /// 就当作这里有一个分号，并记一个 `InsertedSemicolon` 警告，而不是报 `MissingSemicolon`。
+    ///
+    /// 和 JS 一样，`return` 后面直接换行时是 `return;`，下一行不会被当作返回值。
+    pub asi: bool,
 }
 
 /// 递归下降解析器结构体。
@@ -22,26 +73,914 @@ pub fn parse(tokens: &[Token]) -> Result<Program, Error> {
 struct Parser<'a> {
     tokens: &'a [Token], // Token 流
     i: usize,            // 当前扫描位置
+    options: ParseOptions,
+    warnings: Vec<Warning>,
+    /// 出错后恢复时记下的语法错误（见 `recover`）
+    errors: Vec<Error>,
+    /// 已经解析、还没交给后面声明的装饰器（见 `parse_decorators`）
+    decorators: Vec<Decorator>,
+    /// 已经记到节点上的注释（见 `note_leading`）
+    comments: Comments,
+    /// 这个下标之前的 token 前面的注释都已经记下了
+    comment_pos: usize,
+    /// 后面的注释已经记成语句行尾注释的 token 下标
+    claimed: BTreeSet<usize>,
+}
+
+/// `declare` 后面可以跟的两种声明。
+enum Declare {
+    Func(FuncDecl),
+    Class(ClassDecl),
 }
 
 impl<'a> Parser<'a> {
-    fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, i: 0 }
+    fn new(tokens: &'a [Token], options: ParseOptions) -> Self {
+        Self {
+            tokens,
+            i: 0,
+            options,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            decorators: Vec::new(),
+            comments: Comments::default(),
+            comment_pos: 0,
+            claimed: BTreeSet::new(),
+        }
     }
 
     /// 解析整个程序（Program = { Stmt }）
     ///
     /// 规则：一直解析语句直到 token 用完（EOF）。
-    fn parse_program(&mut self) -> Result<Program, Error> {
-        let mut funcs = Vec::new();
-        let mut stmts = Vec::new();
+    ///
+    /// `import` / `export` 只能出现在顶层；可以导出 function、class 和 const，
+    /// 也可以用 `export { .. } from '..'` 转发其它模块的导出。
+    ///
+    /// 一个顶层声明 / 语句出错时记下错误，跳过它接着解析（见 `recover`）。
+    fn parse_program(&mut self) -> Program {
+        let mut program = Program::default();
         while !self.is_eof() {
+            let start = self.i;
+            self.note_top_level();
+            if let Err(e) = self.parse_top_level(&mut program) {
+                self.recover(e, start);
+            }
+        }
+        program.warnings = std::mem::take(&mut self.warnings);
+        program.comments = std::mem::take(&mut self.comments);
+        program
+    }
+
+    /// 顶层声明前面的注释记在 `export` / `default` / `declare` 后面的那个 token 上，
+    /// 也就是声明节点的起始位置。装饰器前面的注释留给装饰器后面的声明。
+    fn note_top_level(&mut self) {
+        if self.peek_kind() == Some(&TokenKind::At) {
+            return;
+        }
+        let modifiers = self.tokens[self.i..]
+            .iter()
+            .take_while(|t| match &t.kind {
+                TokenKind::KwExport => true,
+                TokenKind::Ident(s) => s == "default" || s == "declare",
+                _ => false,
+            })
+            .count();
+        self.note_leading_at(self.i + modifiers);
+    }
+
+    /// 把还没记下的注释（包括当前 token 前面的）记成从当前 token 开始的节点前面的注释。
+    fn note_leading(&mut self) {
+        self.note_leading_at(self.i);
+    }
+
+    /// 把第 `i` 个 token 和它之前还没记下的注释记成从这个 token 开始的节点前面的注释。
+    ///
+    /// 前一个 token 的行尾注释没有被语句认领时（例如跟在 `{` 后面），也当作下一条语句前面的注释。
+    fn note_leading_at(&mut self, i: usize) {
+        let Some(tok) = self.tokens.get(i) else {
+            return;
+        };
+        let mut found = Vec::new();
+        for j in self.comment_pos..=i {
+            if j > 0 && !self.claimed.contains(&(j - 1)) {
+                found.extend(self.tokens[j - 1].trailing.iter().cloned());
+            }
+            found.extend(self.tokens[j].leading.iter().cloned());
+        }
+        self.comment_pos = self.comment_pos.max(i + 1);
+        if !found.is_empty() {
+            self.comments.leading.entry(tok.span.start).or_default().extend(found);
+        }
+    }
+
+    /// 刚解析完的语句（结束位置是 `end`）认领写在它中间、还没记下的注释（`let a = 1 /* 一 */ + 2;`）
+    /// 和它最后一个 token 的行尾注释，都记成这条语句的行尾注释。
+    ///
+    /// 嵌套的语句已经记下了块里的注释，所以 `if (..)` / `while (..)` 条件里的注释归块里的第一条语句。
+    fn note_trailing(&mut self, end: usize) {
+        let Some(last) = self.i.checked_sub(1) else {
+            return;
+        };
+        let mut found = Vec::new();
+        for j in self.comment_pos..=last {
+            if j > 0 && !self.claimed.contains(&(j - 1)) {
+                found.extend(self.tokens[j - 1].trailing.iter().cloned());
+            }
+            found.extend(self.tokens[j].leading.iter().cloned());
+        }
+        if self.comment_pos <= last + 1 && self.claimed.insert(last) {
+            found.extend(self.tokens[last].trailing.iter().cloned());
+        }
+        self.comment_pos = self.comment_pos.max(last + 1);
+        if !found.is_empty() {
+            self.comments.trailing.entry(end).or_default().extend(found);
+        }
+    }
+
+    /// 解析一个顶层声明或语句，放进 `program` 对应的列表里。
+    fn parse_top_level(&mut self, program: &mut Program) -> Result<(), Error> {
+        match self.peek_kind() {
+            Some(TokenKind::At) => self.parse_decorators()?,
+            Some(TokenKind::KwImport) => program.imports.push(self.parse_import_decl()?),
+            Some(TokenKind::KwExport) => {
+                let _ = self.bump(); // 吃掉 'export'
+                match self.peek_kind() {
+                    Some(TokenKind::LBrace) => program.reexports.push(self.parse_reexport_decl()?),
+                    Some(TokenKind::KwFunction) if self.has_decorator("Builder") => {
+                        let _ = self.bump(); // 吃掉 'function'
+                        let mut b = self.parse_builder_decl()?;
+                        b.is_export = true;
+                        program.builders.push(b);
+                    }
+                    Some(TokenKind::KwFunction) if self.at_style_decl() => {
+                        let _ = self.bump(); // 吃掉 'function'
+                        let mut s = self.parse_style_decl()?;
+                        s.is_export = true;
+                        program.styles.push(s);
+                    }
+                    Some(TokenKind::KwFunction) => {
+                        let mut f = self.parse_func_decl()?;
+                        f.is_export = true;
+                        program.funcs.push(f);
+                    }
+                    // `export default` 只用于 Ability 类，当作普通导出处理
+                    Some(TokenKind::Ident(s)) if s == "default" && self.at_ability_decl(1) => {
+                        let _ = self.bump(); // 吃掉 'default'
+                        let mut a = self.parse_ability_decl()?;
+                        a.is_export = true;
+                        program.abilities.push(a);
+                    }
+                    _ if self.at_ability_decl(0) => {
+                        let mut a = self.parse_ability_decl()?;
+                        a.is_export = true;
+                        program.abilities.push(a);
+                    }
+                    Some(TokenKind::KwClass) => {
+                        let mut c = self.parse_class_decl(false)?;
+                        c.is_export = true;
+                        program.classes.push(c);
+                    }
+                    _ if self.at_namespace_decl() => {
+                        let mut ns = self.parse_namespace_decl()?;
+                        ns.is_export = true;
+                        program.namespace_decls.push(ns);
+                    }
+                    _ if self.at_struct_decl(0) => {
+                        let mut c = self.parse_component_decl()?;
+                        c.is_export = true;
+                        program.components.push(c);
+                    }
+                    _ if self.at_declare() => match self.parse_declare()? {
+                        Declare::Func(mut f) => {
+                            f.is_export = true;
+                            program.declare_funcs.push(f);
+                        }
+                        Declare::Class(mut c) => {
+                            c.is_export = true;
+                            program.classes.push(c);
+                        }
+                    },
+                    Some(TokenKind::KwConst) => {
+                        let mut stmt = self.parse_var_decl(true)?;
+                        if let Stmt::VarDecl(v) = &mut stmt {
+                            v.is_export = true;
+                        }
+                        program.stmts.push(stmt);
+                    }
+                    // `export let` 之类：导出可变绑定暂不支持
+                    _ => return Err(self.err_here(ErrorCode::UnsupportedExport)),
+                }
+            }
+            Some(TokenKind::KwFunction) if self.has_decorator("Builder") => {
+                let _ = self.bump(); // 吃掉 'function'
+                program.builders.push(self.parse_builder_decl()?);
+            }
+            Some(TokenKind::KwFunction) if self.at_style_decl() => {
+                let _ = self.bump(); // 吃掉 'function'
+                program.styles.push(self.parse_style_decl()?);
+            }
+            Some(TokenKind::KwFunction) => program.funcs.push(self.parse_func_decl()?),
+            _ if self.at_ability_decl(0) => program.abilities.push(self.parse_ability_decl()?),
+            Some(TokenKind::KwClass) => program.classes.push(self.parse_class_decl(false)?),
+            _ if self.at_namespace_decl() => program.namespace_decls.push(self.parse_namespace_decl()?),
+            _ if self.at_struct_decl(0) => program.components.push(self.parse_component_decl()?),
+            _ if self.at_declare() => match self.parse_declare()? {
+                Declare::Func(f) => program.declare_funcs.push(f),
+                Declare::Class(c) => program.classes.push(c),
+            },
+            _ => program.stmts.push(self.parse_stmt()?),
+        }
+        Ok(())
+    }
+
+    /// 当前位置是否是 `namespace Name {`。
+    ///
+    /// `namespace` 不是关键字（它也可以当普通变量名），所以要多看两个 token 才能确定。
+    fn at_namespace_decl(&self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if s == "namespace")
+            && matches!(self.peek_kind_n(1), Some(TokenKind::Ident(_)))
+            && matches!(self.peek_kind_n(2), Some(TokenKind::LBrace))
+    }
+
+    /// 往后第 `n` 个 token 开始是否是 `struct Name {`（`struct` 也不是关键字）。
+    fn at_struct_decl(&self, n: usize) -> bool {
+        matches!(self.peek_kind_n(n), Some(TokenKind::Ident(s)) if s == "struct")
+            && matches!(self.peek_kind_n(n + 1), Some(TokenKind::Ident(_)))
+            && matches!(self.peek_kind_n(n + 2), Some(TokenKind::LBrace))
+    }
+
+    /// 解析 ArkUI 自定义组件：`struct Name { 字段和方法 }`（前面的装饰器已由 `parse_decorators` 解析）。
+    ///
+    /// - 字段：`@State count: number = 0;`，可以带 `private` 之类的修饰符（忽略）
+    /// - 方法：`aboutToAppear(): void { .. }`，和函数一样但不写 `function`
+    /// - `build() { .. }`：函数体按声明式 UI 语法解析（见 `parse_ui_block`），只能有一个，否则报 `DuplicateBuild`
+    /// - `@Builder item(x: number) { .. }`：函数体同样按 UI 语法解析（见 `parse_builder_decl`）
+    /// - `@Styles card() { .. }`：函数体是一串属性方法（见 `parse_style_decl`）
+    ///
+    /// 组件上认识 `@Component` / `@Entry`，其它装饰器记 `IgnoredDecorator` 警告；
+    /// 其它成员报 `UnsupportedComponentMember`。
+    fn parse_component_decl(&mut self) -> Result<ComponentDecl, Error> {
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &["Component", "Entry"]);
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'struct'
+        let name = self.expect_ident()?;
+        self.expect_simple(TokenKind::LBrace)?;
+        let mut fields = Vec::new();
+        let mut build = None;
+        let mut methods = Vec::new();
+        let mut builders = Vec::new();
+        let mut styles = Vec::new();
+        loop {
+            self.parse_decorator_list()?;
+            while matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if is_member_modifier(s))
+                && matches!(self.peek_kind_n(1), Some(TokenKind::Ident(_)))
+            {
+                let _ = self.bump();
+            }
+            self.note_leading();
+            match (self.peek_kind(), self.peek_kind_n(1)) {
+                (Some(TokenKind::RBrace), _) if self.decorators.is_empty() => {
+                    let _ = self.bump();
+                    break;
+                }
+                (Some(TokenKind::RBrace), _) => return Err(self.err_here(ErrorCode::MisplacedDecorator)),
+                (Some(TokenKind::Ident(s)), Some(TokenKind::LParen)) if s == "build" => {
+                    if build.is_some() {
+                        return Err(self.err_here(ErrorCode::DuplicateBuild));
+                    }
+                    let decorators = std::mem::take(&mut self.decorators);
+                    self.warn_ignored_decorators(&decorators, &[]);
+                    let _ = self.parse_signature()?;
+                    build = Some(self.parse_ui_block()?);
+                }
+                (Some(TokenKind::Ident(_)), Some(TokenKind::LParen)) if self.has_decorator("Builder") => {
+                    builders.push(self.parse_builder_decl()?);
+                }
+                (Some(TokenKind::Ident(_)), Some(TokenKind::LParen)) if self.has_decorator("Styles") => {
+                    styles.push(self.parse_style_decl()?);
+                }
+                (Some(TokenKind::Ident(_)), Some(TokenKind::LParen)) => {
+                    let decorators = std::mem::take(&mut self.decorators);
+                    self.warn_ignored_decorators(&decorators, &[]);
+                    let start = self.start_span();
+                    let (name, params, ret_type) = self.parse_signature()?;
+                    let body = self.parse_block_only()?;
+                    methods.push(FuncDecl {
+                        is_export: false,
+                        name,
+                        params,
+                        ret_type,
+                        body,
+                        decorators,
+                        span: self.node_span(start),
+                    });
+                }
+                (Some(TokenKind::Ident(_)), _) => fields.push(self.parse_field_decl()?),
+                (Some(_), _) => return Err(self.err_here(ErrorCode::UnsupportedComponentMember)),
+                (None, _) => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"])),
+            }
+        }
+        Ok(ComponentDecl {
+            is_export: false,
+            name,
+            decorators,
+            fields,
+            build,
+            methods,
+            builders,
+            styles,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 往后第 `n` 个 token 开始是否是 `class Name extends UIAbility`。
+    fn at_ability_decl(&self, n: usize) -> bool {
+        matches!(self.peek_kind_n(n), Some(TokenKind::KwClass))
+            && matches!(self.peek_kind_n(n + 1), Some(TokenKind::Ident(_)))
+            && matches!(self.peek_kind_n(n + 2), Some(TokenKind::KwExtends))
+            && matches!(self.peek_kind_n(n + 3), Some(TokenKind::Ident(s)) if s == "UIAbility")
+    }
+
+    /// 解析 Ability 类：`class Name extends UIAbility { 方法 }`（见 `AbilityDecl`）。
+    ///
+    /// - 生命周期方法（`ABILITY_LIFECYCLE`）的参数见 `parse_lifecycle_params`，返回类型标注（`: void`）忽略
+    /// - 其它方法和组件方法一样：`name(params): T { .. }`
+    /// - 方法前可以写 `private` 之类的修饰符（忽略）；字段等其它成员报 `UnsupportedClassMember`
+    fn parse_ability_decl(&mut self) -> Result<AbilityDecl, Error> {
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &[]);
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'class'
+        let name = self.expect_ident()?;
+        let _ = self.bump(); // 吃掉 'extends'
+        let _ = self.bump(); // 吃掉 'UIAbility'
+        self.expect_simple(TokenKind::LBrace)?;
+        let mut methods = Vec::new();
+        loop {
+            while matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if is_member_modifier(s))
+                && matches!(self.peek_kind_n(1), Some(TokenKind::Ident(_)))
+            {
+                let _ = self.bump();
+            }
+            self.note_leading();
+            match (self.peek_kind(), self.peek_kind_n(1)) {
+                (Some(TokenKind::RBrace), _) => {
+                    let _ = self.bump();
+                    break;
+                }
+                (Some(TokenKind::Ident(s)), Some(TokenKind::LParen)) if ABILITY_LIFECYCLE.contains(&s.as_str()) => {
+                    let start = self.start_span();
+                    let name = self.expect_ident()?;
+                    let params = self.parse_lifecycle_params()?;
+                    if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+                        let _ = self.bump();
+                        let _ = self.parse_type_ann()?;
+                    }
+                    let body = self.parse_block_only()?;
+                    methods.push(FuncDecl {
+                        is_export: false,
+                        name,
+                        params,
+                        ret_type: None,
+                        body,
+                        decorators: Vec::new(),
+                        span: self.node_span(start),
+                    });
+                }
+                (Some(TokenKind::Ident(_)), Some(TokenKind::LParen)) => {
+                    let start = self.start_span();
+                    let (name, params, ret_type) = self.parse_signature()?;
+                    let body = self.parse_block_only()?;
+                    methods.push(FuncDecl {
+                        is_export: false,
+                        name,
+                        params,
+                        ret_type,
+                        body,
+                        span: self.node_span(start),
+                        decorators: Vec::new(),
+                    });
+                }
+                (Some(_), _) => return Err(self.err_here(ErrorCode::UnsupportedClassMember)),
+                (None, _) => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"])),
+            }
+        }
+        Ok(AbilityDecl {
+            is_export: false,
+            name,
+            decorators,
+            methods,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析生命周期方法的参数列表：`(want: Want, launchParam: AbilityConstant.LaunchParam)`。
+    ///
+    /// 参数类型是运行时提供的类型（见 `codegen::lifecycle_params`），这里只接受 `Name` 或 `A.B` 形式的标注，不保存。
+    fn parse_lifecycle_params(&mut self) -> Result<Vec<Param>, Error> {
+        self.expect_simple(TokenKind::LParen)?;
+        let mut params = Vec::new();
+        while !matches!(self.peek_kind(), Some(TokenKind::RParen)) {
+            let start = self.start_span();
+            let name = self.expect_ident()?;
+            if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+                let _ = self.bump();
+                self.expect_ident()?;
+                while matches!(self.peek_kind(), Some(TokenKind::Dot)) {
+                    let _ = self.bump();
+                    self.expect_ident()?;
+                }
+            }
+            params.push(Param {
+                name,
+                ty: None,
+                is_rest: false,
+                span: self.node_span(start),
+            });
+            match self.peek_kind() {
+                Some(TokenKind::Comma) => {
+                    let _ = self.bump();
+                }
+                Some(TokenKind::RParen) => {}
+                Some(_) => return Err(self.err_expected(ErrorCode::UnexpectedToken, &["`,`", "`)`"])),
+                None => return Err(self.err_expected(ErrorCode::UnexpectedEof, &["`,`", "`)`"])),
+            }
+        }
+        self.expect_rparen()?;
+        Ok(params)
+    }
+
+    /// 已经解析、还没被取走的装饰器里有没有 `@name`。
+    fn has_decorator(&self, name: &str) -> bool {
+        self.decorators.iter().any(|d| d.name == name)
+    }
+
+    /// 已经解析的装饰器里有 `@Styles` 或 `@Extend`（后面是属性方法组成的函数，见 `parse_style_decl`）。
+    fn at_style_decl(&self) -> bool {
+        self.has_decorator("Styles") || self.has_decorator("Extend")
+    }
+
+    /// 解析 `@Styles` / `@Extend(Name)` 函数和 `@Styles` 方法的 `name(params) { .attr(..) .. }`（`function` 已经吃掉）。
+    ///
+    /// `@Extend` 的参数必须是一个 UI 组件名，否则报 `InvalidDecoratorArgs`；
+    /// 函数体里不是属性方法时报 `ExpectedUiAttrCall`。
+    fn parse_style_decl(&mut self) -> Result<StyleDecl, Error> {
+        let start = self.start_span();
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &["Styles", "Extend"]);
+        let extend = match decorators.iter().find(|d| d.name == "Extend") {
+            Some(d) => match d.args.as_slice() {
+                [Expr::Ident(name, _)] => Some(name.clone()),
+                _ => return Err(Error::new(ErrorCode::InvalidDecoratorArgs, d.span)),
+            },
+            None => None,
+        };
+        let (name, params, _) = self.parse_signature()?;
+        self.expect_simple(TokenKind::LBrace)?;
+        let attrs = self.parse_ui_attrs()?;
+        match self.peek_kind() {
+            Some(TokenKind::RBrace) => {
+                let _ = self.bump();
+            }
+            Some(_) => return Err(self.err_here(ErrorCode::ExpectedUiAttrCall)),
+            None => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"])),
+        }
+        Ok(StyleDecl {
+            is_export: false,
+            name,
+            params,
+            decorators,
+            extend,
+            attrs,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析 `@Builder` 函数 / 方法的 `name(params) { UI }`（`function` 已经吃掉）。
+    ///
+    /// 返回类型标注可以写（`: void`），但会被忽略；`@Builder` 以外的装饰器记 `IgnoredDecorator` 警告。
+    fn parse_builder_decl(&mut self) -> Result<BuilderDecl, Error> {
+        let start = self.start_span();
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &["Builder"]);
+        let (name, params, _) = self.parse_signature()?;
+        let body = self.parse_ui_block()?;
+        Ok(BuilderDecl {
+            is_export: false,
+            name,
+            params,
+            decorators,
+            body,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析声明式 UI 的 `{ .. }`（见 `UiNode`）：
+    /// - `if (cond) { .. } else { .. }`：条件渲染，else 可以省略
+    /// - 大写字母开头、后面紧跟 `(` 的名字：UI 组件（见 `parse_ui_element`）
+    /// - 其它：普通语句
+    fn parse_ui_block(&mut self) -> Result<Vec<UiNode>, Error> {
+        self.expect_simple(TokenKind::LBrace)?;
+        let mut nodes = Vec::new();
+        while !matches!(self.peek_kind(), Some(TokenKind::RBrace)) {
+            if self.is_eof() {
+                return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"]));
+            }
+            nodes.push(self.parse_ui_node()?);
+        }
+        let _ = self.bump(); // 吃掉 '}'
+        Ok(nodes)
+    }
+
+    fn parse_ui_node(&mut self) -> Result<UiNode, Error> {
+        if matches!(self.peek_kind(), Some(TokenKind::KwIf)) {
+            return Ok(UiNode::If(self.parse_ui_if()?));
+        }
+        if self.at_ui_element() {
+            return Ok(UiNode::Element(self.parse_ui_element()?));
+        }
+        // `footer()` / `this.item(1)`（`@Builder` 调用）和 UI 组件一样，末尾的分号可以省略
+        let call_start = match (self.peek_kind(), self.peek_kind_n(1), self.peek_kind_n(3)) {
+            (Some(TokenKind::Ident(_)), Some(TokenKind::LParen), _) => true,
+            (Some(TokenKind::Ident(_)), Some(TokenKind::Dot), Some(TokenKind::LParen)) => {
+                matches!(self.peek_kind_n(2), Some(TokenKind::Ident(_)))
+            }
+            _ => false,
+        };
+        if call_start {
+            let expr = self.parse_expr_bp(0)?;
+            if matches!(self.peek_kind(), Some(TokenKind::Semicolon)) || !matches!(expr, Expr::Call(_)) {
+                self.expect_semicolon()?;
+            }
+            return Ok(UiNode::Stmt(Stmt::ExprStmt(expr)));
+        }
+        Ok(UiNode::Stmt(self.parse_stmt()?))
+    }
+
+    fn at_ui_element(&self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if s.starts_with(|c: char| c.is_ascii_uppercase()))
+            && matches!(self.peek_kind_n(1), Some(TokenKind::LParen))
+    }
+
+    fn parse_ui_if(&mut self) -> Result<UiIf, Error> {
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'if'
+        self.expect_simple(TokenKind::LParen)?;
+        let cond = self.parse_expr_bp(0)?;
+        self.expect_rparen()?;
+        let then_branch = self.parse_ui_block()?;
+        let else_branch = if matches!(self.peek_kind(), Some(TokenKind::KwElse)) {
+            let _ = self.bump(); // 吃掉 'else'
+            if matches!(self.peek_kind(), Some(TokenKind::KwIf)) {
+                vec![UiNode::If(self.parse_ui_if()?)]
+            } else {
+                self.parse_ui_block()?
+            }
+        } else {
+            Vec::new()
+        };
+        Ok(UiIf {
+            cond,
+            then_branch,
+            else_branch,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析 UI 组件：`Name(args) { children }.attr(args)...`，子节点和属性方法都可以省略。
+    ///
+    /// 属性方法通常换行书写，末尾的 `;` 可写可不写。
+    fn parse_ui_element(&mut self) -> Result<UiElement, Error> {
+        let start = self.start_span();
+        let name = self.expect_ident()?;
+        let args = self.parse_ui_args()?;
+        let children = if matches!(self.peek_kind(), Some(TokenKind::LBrace)) {
+            self.parse_ui_block()?
+        } else {
+            Vec::new()
+        };
+        let attrs = self.parse_ui_attrs()?;
+        Ok(UiElement {
+            name,
+            args,
+            children,
+            attrs,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析连续的属性方法 `.fontSize(20).fontColor(Color.Red)`，末尾的分号可以省略。
+    fn parse_ui_attrs(&mut self) -> Result<Vec<UiAttr>, Error> {
+        let mut attrs = Vec::new();
+        while matches!(self.peek_kind(), Some(TokenKind::Dot)) {
+            let start = self.start_span();
+            let _ = self.bump(); // 吃掉 '.'
+            let name = self.expect_ident()?;
+            if !matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+                return Err(self.err_here(ErrorCode::ExpectedUiAttrCall));
+            }
+            let args = self.parse_ui_args()?;
+            attrs.push(UiAttr {
+                name,
+                args,
+                span: self.node_span(start),
+            });
+        }
+        if matches!(self.peek_kind(), Some(TokenKind::Semicolon)) {
+            let _ = self.bump();
+        }
+        Ok(attrs)
+    }
+
+    /// 解析 UI 组件 / 属性方法的实参列表 `( .. )`（见 `UiArg`）。
+    fn parse_ui_args(&mut self) -> Result<Vec<UiArg>, Error> {
+        self.expect_simple(TokenKind::LParen)?;
+        let mut args = Vec::new();
+        while !matches!(self.peek_kind(), Some(TokenKind::RParen)) {
+            args.push(self.parse_ui_arg()?);
+            match self.peek_kind() {
+                Some(TokenKind::Comma) => {
+                    let _ = self.bump();
+                }
+                Some(TokenKind::RParen) => {}
+                Some(_) => return Err(self.err_expected(ErrorCode::UnexpectedToken, &["`,`", "`)`"])),
+                None => return Err(self.err_expected(ErrorCode::UnexpectedEof, &["`,`", "`)`"])),
+            }
+        }
+        self.expect_rparen()?;
+        Ok(args)
+    }
+
+    fn parse_ui_arg(&mut self) -> Result<UiArg, Error> {
+        match self.peek_kind() {
+            Some(TokenKind::LBrace) => {
+                let _ = self.bump(); // 吃掉 '{'
+                let mut props = Vec::new();
+                while !matches!(self.peek_kind(), Some(TokenKind::RBrace)) {
+                    let key = match self.peek_kind() {
+                        Some(TokenKind::String(s)) => {
+                            let key = s.clone();
+                            let _ = self.bump();
+                            key
+                        }
+                        _ => self.expect_ident()?,
+                    };
+                    self.expect_simple(TokenKind::Colon)?;
+                    props.push((key, self.parse_expr_bp(0)?));
+                    match self.peek_kind() {
+                        Some(TokenKind::Comma) => {
+                            let _ = self.bump();
+                        }
+                        Some(TokenKind::RBrace) => {}
+                        Some(_) => return Err(self.err_expected(ErrorCode::UnexpectedToken, &["`,`", "`}`"])),
+                        None => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`,`", "`}`"])),
+                    }
+                }
+                let _ = self.bump(); // 吃掉 '}'
+                Ok(UiArg::Object(props))
+            }
+            Some(TokenKind::Ident(_)) if matches!(self.peek_kind_n(1), Some(TokenKind::FatArrow)) => {
+                let start = self.start_span();
+                let name = self.expect_ident()?;
+                let params = vec![Param {
+                    name,
+                    ty: None,
+                    is_rest: false,
+                    span: self.node_span(start),
+                }];
+                self.parse_ui_handler(start, params)
+            }
+            Some(TokenKind::LParen) if self.at_arrow_params() => {
+                let start = self.start_span();
+                let params = self.parse_param_list()?;
+                self.parse_ui_handler(start, params)
+            }
+            _ => Ok(UiArg::Expr(self.parse_expr_bp(0)?)),
+        }
+    }
+
+    /// 当前的 `(` 和与它配对的 `)` 之后是不是 `=>`（箭头函数的参数列表）。
+    fn at_arrow_params(&self) -> bool {
+        let mut depth = 0usize;
+        let mut n = 0;
+        while let Some(kind) = self.peek_kind_n(n) {
+            match kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return matches!(self.peek_kind_n(n + 1), Some(TokenKind::FatArrow));
+                    }
+                }
+                _ => {}
+            }
+            n += 1;
+        }
+        false
+    }
+
+    /// 箭头函数的 `=> { .. }` / `=> expr` 部分（参数已经解析，`start` 是参数的开头）。
+    fn parse_ui_handler(&mut self, start: Span, params: Vec<Param>) -> Result<UiArg, Error> {
+        self.expect_simple(TokenKind::FatArrow)?;
+        let body = if matches!(self.peek_kind(), Some(TokenKind::LBrace)) {
+            self.parse_ui_block()?
+        } else if self.at_ui_element() {
+            vec![UiNode::Element(self.parse_ui_element()?)]
+        } else {
+            vec![UiNode::Stmt(Stmt::ExprStmt(self.parse_expr_bp(0)?))]
+        };
+        Ok(UiArg::Handler(UiHandler {
+            params,
+            body,
+            span: self.node_span(start),
+        }))
+    }
+
+    /// 解析组件字段：`name: T = init;`，类型标注和初始值都可以省略。
+    fn parse_field_decl(&mut self) -> Result<FieldDecl, Error> {
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &["State", "Prop", "Link", "Provide", "Consume"]);
+        let start = self.start_span();
+        let name = self.expect_ident()?;
+        let ty = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+            let _ = self.bump();
+            Some(self.parse_type_ann()?)
+        } else {
+            None
+        };
+        let init = if matches!(self.peek_kind(), Some(TokenKind::Eq)) {
+            let _ = self.bump();
+            Some(self.parse_expr_bp(0)?)
+        } else {
+            None
+        };
+        self.expect_semicolon()?;
+        Ok(FieldDecl {
+            decorators,
+            name,
+            ty,
+            init,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 当前位置是否是 `declare function` / `declare class`（`declare` 同样不是关键字）。
+    fn at_declare(&self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if s == "declare")
+            && matches!(
+                self.peek_kind_n(1),
+                Some(TokenKind::KwFunction) | Some(TokenKind::KwClass)
+            )
+    }
+
+    /// 解析外部声明（ambient declaration）：
+    /// - `declare function now(): number;`：只有签名，以分号结尾
+    /// - `declare class NotFound extends Error {}`：和普通 class 声明一样，类体里还可以写成员的签名
+    ///   （`bar(x: number): string;`、`name: string;`，见 `parse_ambient_member`）
+    fn parse_declare(&mut self) -> Result<Declare, Error> {
+        let _ = self.bump(); // 吃掉 'declare'
+        if matches!(self.peek_kind(), Some(TokenKind::KwClass)) {
+            return Ok(Declare::Class(self.parse_class_decl(true)?));
+        }
+        let start = self.start_span();
+        let (name, params, ret_type) = self.parse_func_signature()?;
+        self.expect_semicolon()?;
+        Ok(Declare::Func(FuncDecl {
+            is_export: false,
+            name,
+            params,
+            ret_type,
+            body: BlockStmt { stmts: Vec::new(), span: NodeSpan::default() },
+            decorators: Vec::new(),
+            span: self.node_span(start),
+        }))
+    }
+
+    /// 解析 namespace 声明：`namespace Utils { export function f() {..} function g() {..} }`
+    ///
+    /// 成员只能是（可以带 `export` 的）函数，其它成员报 `UnsupportedNamespaceMember`。
+    fn parse_namespace_decl(&mut self) -> Result<NamespaceDecl, Error> {
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'namespace'
+        let name = self.expect_ident()?;
+        self.expect_simple(TokenKind::LBrace)?;
+        let mut funcs = Vec::new();
+        loop {
             match self.peek_kind() {
+                Some(TokenKind::RBrace) => {
+                    let _ = self.bump();
+                    break;
+                }
+                Some(TokenKind::At) => self.parse_decorators()?,
                 Some(TokenKind::KwFunction) => funcs.push(self.parse_func_decl()?),
-                _ => stmts.push(self.parse_stmt()?),
+                Some(TokenKind::KwExport)
+                    if matches!(self.peek_kind_n(1), Some(TokenKind::KwFunction)) =>
+                {
+                    let _ = self.bump();
+                    let mut f = self.parse_func_decl()?;
+                    f.is_export = true;
+                    funcs.push(f);
+                }
+                Some(_) => return Err(self.err_here(ErrorCode::UnsupportedNamespaceMember)),
+                None => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"])),
             }
         }
-        Ok(Program { funcs, stmts })
+        Ok(NamespaceDecl {
+            is_export: false,
+            name,
+            funcs,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析 import 声明：
+    /// - `import { a, b as c } from './util';`
+    /// - `import * as util from './util';`
+    /// - `import hilog from '@ohos.hilog';`（默认导入，只能用于系统模块，见 `module::link`）
+    fn parse_import_decl(&mut self) -> Result<ImportDecl, Error> {
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'import'
+        let clause = if matches!(self.peek_kind(), Some(TokenKind::Star)) {
+            let _ = self.bump();
+            self.expect_contextual("as", ErrorCode::ExpectedAs)?;
+            ImportClause::Namespace(self.expect_ident()?)
+        } else if matches!(self.peek_kind(), Some(TokenKind::Ident(_))) {
+            ImportClause::Default(self.expect_ident()?)
+        } else {
+            ImportClause::Named(self.parse_import_specs()?)
+        };
+        let (source, source_span) = self.parse_from_source()?;
+        Ok(ImportDecl {
+            clause,
+            source,
+            source_span,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析 re-export 声明：`export { a, b as c } from './m';`（`export` 已被吃掉）
+    fn parse_reexport_decl(&mut self) -> Result<ReExportDecl, Error> {
+        let specs = self.parse_import_specs()?;
+        let (source, source_span) = self.parse_from_source()?;
+        Ok(ReExportDecl {
+            specs,
+            source,
+            source_span,
+        })
+    }
+
+    /// 解析花括号里的名字列表：`{ a, b as c }`，至少一个名字，允许尾随逗号。
+    fn parse_import_specs(&mut self) -> Result<Vec<ImportSpec>, Error> {
+        self.expect_simple(TokenKind::LBrace)?;
+        let mut specs = vec![self.parse_import_spec()?];
+        while matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+            let _ = self.bump();
+            if matches!(self.peek_kind(), Some(TokenKind::RBrace)) {
+                break;
+            }
+            specs.push(self.parse_import_spec()?);
+        }
+        match self.peek_kind() {
+            Some(TokenKind::RBrace) => {
+                let _ = self.bump();
+            }
+            _ => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`,`", "`}`"])),
+        }
+        Ok(specs)
+    }
+
+    /// `a` 或 `a as b`
+    fn parse_import_spec(&mut self) -> Result<ImportSpec, Error> {
+        let start = self.start_span();
+        let name = self.expect_ident()?;
+        let alias = match self.peek_kind() {
+            Some(TokenKind::Ident(s)) if s == "as" => {
+                let _ = self.bump();
+                Some(self.expect_ident()?)
+            }
+            _ => None,
+        };
+        Ok(ImportSpec {
+            name,
+            alias,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析 `from '<path>';`，返回路径和它的位置。`from`/`as` 不是关键字，按标识符识别。
+    fn parse_from_source(&mut self) -> Result<(String, Span), Error> {
+        self.expect_contextual("from", ErrorCode::ExpectedFrom)?;
+        let span = self.peek_span().unwrap_or_default();
+        let source = match self.peek_kind() {
+            Some(TokenKind::String(s)) => {
+                let s = s.clone();
+                let _ = self.bump();
+                s
+            }
+            _ => return Err(self.err_expected(ErrorCode::ExpectedModulePath, &["module path string"])),
+        };
+        self.expect_semicolon()?;
+        Ok((source, span))
     }
 
     /// 解析单条语句（Stmt）
@@ -51,26 +990,56 @@ impl<'a> Parser<'a> {
     /// - `if (...) ... else ...` -> If
     /// - `while (...) ...` -> While
     /// - `return expr?;` -> Return
+    /// - `function ...` -> 块内函数声明（FuncDecl）
     /// - 其它 -> 表达式语句（ExprStmt，必须以分号结尾）
     ///
     /// 说明（很重要）：
     /// - 不是所有语句都需要分号：Block/If/While 不需要。
     /// - 需要分号的语句：变量声明、赋值、return、表达式语句。
+    ///
+    /// 语句前后的注释记到 `Program::comments` 里（见 `note_leading` / `note_trailing`）。
     fn parse_stmt(&mut self) -> Result<Stmt, Error> {
+        // 代码块前后的注释留给块里、块后的语句
+        if self.peek_kind() == Some(&TokenKind::LBrace) {
+            return self.parse_stmt_kind();
+        }
+        self.note_leading();
+        let stmt = self.parse_stmt_kind()?;
+        self.note_trailing(stmt.span().end);
+        Ok(stmt)
+    }
+
+    fn parse_stmt_kind(&mut self) -> Result<Stmt, Error> {
         match self.peek_kind() {
             Some(TokenKind::KwLet) => self.parse_var_decl(false),
             Some(TokenKind::KwConst) => self.parse_var_decl(true),
-            Some(TokenKind::KwFunction) => Err(self.err_here("FunctionNotAllowedHere")),
+            Some(TokenKind::KwFunction) => Ok(Stmt::FuncDecl(self.parse_func_decl()?)),
+            Some(TokenKind::At) => {
+                self.parse_decorators()?;
+                self.parse_stmt()
+            }
+            Some(TokenKind::KwClass) => Err(self.err_here(ErrorCode::ClassNotAllowedHere)),
+            Some(TokenKind::KwImport) => Err(self.err_here(ErrorCode::ImportNotAllowedHere)),
+            _ if self.at_namespace_decl() => Err(self.err_here(ErrorCode::NamespaceNotAllowedHere)),
+            _ if self.at_declare() => Err(self.err_here(ErrorCode::DeclareNotAllowedHere)),
+            Some(TokenKind::KwExport) => Err(self.err_here(ErrorCode::ExportNotAllowedHere)),
             Some(TokenKind::LBrace) => self.parse_block_stmt(),
             Some(TokenKind::KwIf) => self.parse_if_stmt(),
             Some(TokenKind::KwWhile) => self.parse_while_stmt(),
             Some(TokenKind::KwReturn) => self.parse_return_stmt(),
+            Some(TokenKind::KwThrow) => self.parse_throw_stmt(),
+            Some(TokenKind::KwTry) => self.parse_try_stmt(),
             Some(TokenKind::Ident(_)) if matches!(self.peek_kind_n(1), Some(TokenKind::Eq)) => {
+                let start = self.start_span();
                 let name = self.expect_ident()?;
                 self.expect_simple(TokenKind::Eq)?;
                 let value = self.parse_expr_bp(0)?;
                 self.expect_semicolon()?;
-                Ok(Stmt::Assign(AssignStmt { name, value }))
+                Ok(Stmt::Assign(AssignStmt {
+                    name,
+                    value,
+                    span: self.node_span(start),
+                }))
             }
             _ => {
                 let expr = self.parse_expr_bp(0)?;
@@ -80,104 +1049,401 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// 解析变量声明（let x = ...;）
+    /// 解析变量声明（let x = ...;）
+    ///
+    /// 产生式（简化写法）：
+    /// - `("let" | "const") Declarator ("," Declarator)* ";"`（分号在 parse_stmt 里检查，这里也会检查一次以更直观）
+    /// - `Declarator := Pattern (":" Type)? ("=" Expr)?`
+    ///
+    /// 只有 `let` 声明的普通变量可以省略初始值；`const` 和解构必须有初始值，否则报 `MissingInitializer`。
+    fn parse_var_decl(&mut self, is_const: bool) -> Result<Stmt, Error> {
+        let start = self.start_span();
+        if is_const {
+            self.expect_simple(TokenKind::KwConst)?;
+        } else {
+            self.expect_simple(TokenKind::KwLet)?;
+        }
+
+        let mut decls = vec![self.parse_declarator(is_const)?];
+        while matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+            let _ = self.bump(); // 吃掉 ','
+            decls.push(self.parse_declarator(is_const)?);
+        }
+        self.expect_semicolon()?; // 分号
+        Ok(Stmt::VarDecl(VarDecl {
+            is_export: false,
+            is_const,
+            decls,
+            span: self.node_span(start),
+        }))
+    }
+
+    /// 解析一个声明项：`x`、`x: number`、`x = 1`、`[a, b] = pair`。
+    fn parse_declarator(&mut self, is_const: bool) -> Result<Declarator, Error> {
+        let start = self.start_span();
+        let pattern = self.parse_pattern()?; // 变量名或解构模式
+        let ty = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+            let _ = self.bump();
+            Some(self.parse_type_ann()?) // 可选的类型标注
+        } else {
+            None
+        };
+        let init = if matches!(self.peek_kind(), Some(TokenKind::Semicolon) | Some(TokenKind::Comma)) {
+            if is_const || !matches!(pattern, Pattern::Ident(_)) {
+                return Err(self.err_here(ErrorCode::MissingInitializer));
+            }
+            None
+        } else {
+            self.expect_simple(TokenKind::Eq)?; // 等号
+            Some(self.parse_expr_bp(0)?) // 初始值
+        };
+        Ok(Declarator {
+            pattern,
+            ty,
+            init,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析绑定模式：
+    /// - `Ident`
+    /// - `"[" (Pattern ("," Pattern)*)? "]"`
+    /// - `"{" (Prop ("," Prop)*)? "}"`，其中 `Prop := Ident (":" Pattern)?`
+    fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        match self.peek_kind() {
+            Some(TokenKind::LBracket) => {
+                let _ = self.bump();
+                let mut items = Vec::new();
+                while !matches!(self.peek_kind(), Some(TokenKind::RBracket)) {
+                    items.push(self.parse_pattern()?);
+                    if matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+                        let _ = self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                match self.peek_kind() {
+                    Some(TokenKind::RBracket) => {
+                        let _ = self.bump();
+                        Ok(Pattern::Array(items))
+                    }
+                    _ => Err(self.err_expected(ErrorCode::MissingRBracket, &["`,`", "`]`"])),
+                }
+            }
+            Some(TokenKind::LBrace) => {
+                let _ = self.bump();
+                let mut props = Vec::new();
+                while !matches!(self.peek_kind(), Some(TokenKind::RBrace)) {
+                    let key = self.expect_ident()?;
+                    let value = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+                        let _ = self.bump();
+                        self.parse_pattern()?
+                    } else {
+                        Pattern::Ident(key.clone())
+                    };
+                    props.push(PropPattern { key, value });
+                    if matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+                        let _ = self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                match self.peek_kind() {
+                    Some(TokenKind::RBrace) => {
+                        let _ = self.bump();
+                        Ok(Pattern::Object(props))
+                    }
+                    _ => Err(self.err_expected(ErrorCode::MissingRBrace, &["`,`", "`}`"])),
+                }
+            }
+            _ => Ok(Pattern::Ident(self.expect_ident()?)),
+        }
+    }
+
+    /// 解析函数声明（Step6 起支持顶层，之后也允许出现在任意代码块内）。
+    ///
+    /// 语法（类型标注可选）：
+    /// ```text
+    /// function name(a: number, b: number): number { ... }
+    /// function name(a, b) { ... }
+    /// ```
+    fn parse_func_decl(&mut self) -> Result<FuncDecl, Error> {
+        self.note_leading();
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &[]);
+        let start = self.start_span();
+        let (name, params, ret_type) = self.parse_func_signature()?;
+        let body = self.parse_block_only()?;
+        Ok(FuncDecl {
+            is_export: false,
+            name,
+            params,
+            ret_type,
+            body,
+            decorators,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析连续的装饰器：`@Entry @Component`、`@Watch('onChange')`，
+    /// 结果先放在 `self.decorators` 里，由紧跟着的声明取走。
+    ///
+    /// 装饰器后面（可以隔着 `export`）必须是函数、class 或 struct 声明，否则报 `MisplacedDecorator`。
+    fn parse_decorators(&mut self) -> Result<(), Error> {
+        self.parse_decorator_list()?;
+        let n = usize::from(matches!(self.peek_kind(), Some(TokenKind::KwExport)));
+        match self.peek_kind_n(n) {
+            Some(TokenKind::KwFunction) | Some(TokenKind::KwClass) => Ok(()),
+            _ if self.at_struct_decl(n) => Ok(()),
+            Some(_) => Err(self.err_here(ErrorCode::MisplacedDecorator)),
+            None => Err(self.err_eof(ErrorCode::MisplacedDecorator)),
+        }
+    }
+
+    /// 只解析装饰器本身（见 `parse_decorators`），不检查后面跟的是什么。
+    fn parse_decorator_list(&mut self) -> Result<(), Error> {
+        while let Some(TokenKind::At) = self.peek_kind() {
+            let start = self.peek_span().unwrap_or_default();
+            let _ = self.bump(); // 吃掉 '@'
+            let name = self.expect_ident()?;
+            let args = if matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+                self.parse_call_args()?
+            } else {
+                Vec::new()
+            };
+            let span = start.to(self.tokens[self.i - 1].span);
+            self.decorators.push(Decorator { name, args, span });
+        }
+        Ok(())
+    }
+
+    /// 代码生成不认识（不在 `known` 里）的装饰器会被忽略，各记一个 `IgnoredDecorator` 警告。
+    fn warn_ignored_decorators(&mut self, decorators: &[Decorator], known: &[&str]) {
+        for d in decorators {
+            if !known.contains(&d.name.as_str()) {
+                self.warnings.push(Warning::new(ErrorCode::IgnoredDecorator, d.span));
+            }
+        }
+    }
+
+    /// 解析函数签名：`function name(params): Ret`（返回类型可选），不含函数体。
+    fn parse_func_signature(&mut self) -> Result<(String, Vec<Param>, Option<TypeAnn>), Error> {
+        let _ = self.bump(); // 吃掉 'function'
+        self.parse_signature()
+    }
+
+    /// 解析 `name(params): Ret`：函数签名去掉 `function` 的部分（组件的方法也是这样写的）。
+    fn parse_signature(&mut self) -> Result<(String, Vec<Param>, Option<TypeAnn>), Error> {
+        let name = self.expect_ident()?;
+        let params = self.parse_param_list()?;
+        let ret_type = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+            let _ = self.bump();
+            Some(self.parse_type_ann()?)
+        } else {
+            None
+        };
+        Ok((name, params, ret_type))
+    }
+
+    /// 解析参数列表 `(a: number, ...rest: number[])`。
+    fn parse_param_list(&mut self) -> Result<Vec<Param>, Error> {
+        self.expect_simple(TokenKind::LParen)?;
+
+        let mut params = Vec::new();
+        if !matches!(self.peek_kind(), Some(TokenKind::RParen)) {
+            loop {
+                let param = self.parse_param()?;
+                let is_rest = param.is_rest;
+                params.push(param);
+                match self.peek_kind() {
+                    // 剩余参数会“吃掉”所有剩下的实参，所以它后面不能再有参数。
+                    Some(TokenKind::Comma) if is_rest => {
+                        return Err(self.err_here(ErrorCode::RestParamMustBeLast));
+                    }
+                    Some(TokenKind::Comma) => {
+                        let _ = self.bump();
+                    }
+                    Some(TokenKind::RParen) => break,
+                    Some(_) => return Err(self.err_expected(ErrorCode::UnexpectedToken, &["`,`", "`)`"])),
+                    None => return Err(self.err_expected(ErrorCode::UnexpectedEof, &["`,`", "`)`"])),
+                }
+            }
+        }
+        self.expect_rparen()?;
+        Ok(params)
+    }
+
+    /// 解析单个参数：`name`、`name: T` 或剩余参数 `...name: T[]`。
+    fn parse_param(&mut self) -> Result<Param, Error> {
+        let start = self.start_span();
+        let is_rest = matches!(self.peek_kind(), Some(TokenKind::Ellipsis));
+        if is_rest {
+            let _ = self.bump(); // 吃掉 '...'
+        }
+
+        let name = self.expect_ident()?;
+        let ty = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+            let _ = self.bump();
+            let ty_span = self.peek_span().unwrap_or_default();
+            let ty = self.parse_type_ann()?;
+            if is_rest && !matches!(ty, TypeAnn::Array(_)) {
+                return Err(Error::new(ErrorCode::RestParamMustBeArray, ty_span));
+            }
+            Some(ty)
+        } else {
+            None
+        };
+        Ok(Param {
+            name,
+            ty,
+            is_rest,
+            span: self.node_span(start),
+        })
+    }
+
+    /// 解析类型标注，支持与 `null`/`undefined` 组成联合类型。
+    ///
+    /// 例：`string | null`、`null | number`、`number[] | undefined`
+    ///
+    /// 只支持“一个具体类型 + null/undefined”的联合（即可空类型），
+    /// 其它联合类型（例如 `string | number`）在 Rust 里没有直接对应，报 `UnsupportedUnionType`。
+    fn parse_type_ann(&mut self) -> Result<TypeAnn, Error> {
+        let start = self.peek_span().unwrap_or_default();
+        // 出现过 `null` 就记为 `Null`，只写了 `undefined` 才是 `Undefined`
+        let mut nullable: Option<NullKind> = None;
+        let mut inner: Option<TypeAnn> = None;
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::KwNull) => {
+                    let _ = self.bump();
+                    nullable = Some(NullKind::Null);
+                }
+                Some(TokenKind::KwUndefined) => {
+                    let _ = self.bump();
+                    nullable = nullable.or(Some(NullKind::Undefined));
+                }
+                _ => {
+                    let member_span = self.peek_span().unwrap_or_default();
+                    let ty = self.parse_type_atom()?;
+                    if inner.is_some() {
+                        return Err(Error::new(ErrorCode::UnsupportedUnionType, member_span));
+                    }
+                    inner = Some(ty);
+                }
+            }
+            if matches!(self.peek_kind(), Some(TokenKind::Pipe)) {
+                let _ = self.bump();
+            } else {
+                break;
+            }
+        }
+
+        match inner {
+            Some(ty) => Ok(match nullable {
+                Some(kind) => TypeAnn::Nullable(Box::new(ty), kind),
+                None => ty,
+            }),
+            // 只有 `null`/`undefined`，没有具体类型
+            None => Err(Error::new(ErrorCode::UnknownType, start)),
+        }
+    }
+
+    /// 解析单个（非联合）类型：基础类型名，后面可以跟任意个 `[]` 表示数组；
+    /// 以 `(` 开头时解析函数类型；`Promise<T>` 带一个类型参数。
     ///
-    /// 产生式（简化写法）：
-    /// - `("let" | "const") Ident "=" Literal ";"`（分号在 parse_stmt 里检查，这里也会检查一次以更直观）
-    fn parse_var_decl(&mut self, is_const: bool) -> Result<Stmt, Error> {
-        if is_const {
-            self.expect_simple(TokenKind::KwConst)?;
-        } else {
-            self.expect_simple(TokenKind::KwLet)?;
+    /// 例：`number`、`string[]`、`number[][]`、`(x: number) => number`、`Promise<number>`
+    fn parse_type_atom(&mut self) -> Result<TypeAnn, Error> {
+        if matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+            return self.parse_func_type_ann();
         }
 
-        let name = self.expect_ident()?; // 变量名
-        self.expect_simple(TokenKind::Eq)?; // 等号
-        let init = self.parse_expr_bp(0)?; // 初始值
-        self.expect_semicolon()?; // 分号
-        Ok(Stmt::VarDecl(VarDecl {
-            is_const,
-            name,
-            init,
-        }))
+        let start = self.peek_span().unwrap_or_default();
+        let s = self.expect_ident()?;
+        let mut ty = match s.as_str() {
+            "number" => TypeAnn::Number,
+            "string" => TypeAnn::String,
+            "boolean" => TypeAnn::Boolean,
+            "void" => TypeAnn::Void,
+            "Date" => TypeAnn::Date,
+            "Promise" => {
+                self.expect_simple(TokenKind::Lt)?;
+                let inner = self.parse_type_ann()?;
+                match self.peek_kind() {
+                    Some(TokenKind::Gt) => {
+                        let _ = self.bump();
+                    }
+                    _ => return Err(self.err_expected(ErrorCode::MissingGt, &["`>`"])),
+                }
+                TypeAnn::Promise(Box::new(inner))
+            }
+            _ => {
+                let known = TYPE_NAMES.iter().map(|t| (t.to_string(), Span::default()));
+                return Err(did_you_mean(Error::new(ErrorCode::UnknownType, start), &s, known));
+            }
+        };
+
+        while matches!(self.peek_kind(), Some(TokenKind::LBracket)) {
+            let _ = self.bump(); // 吃掉 '['
+            match self.peek_kind() {
+                Some(TokenKind::RBracket) => {
+                    let _ = self.bump();
+                }
+                _ => return Err(self.err_expected(ErrorCode::MissingRBracket, &["`]`"])),
+            }
+            ty = TypeAnn::Array(Box::new(ty));
+        }
+        Ok(ty)
     }
 
-    /// 解析顶层函数声明（Step6）。
+    /// 解析函数类型标注：`(a: A, b: B) => R`
     ///
-    /// 语法（类型标注可选）：
-    /// ```text
-    /// function name(a: number, b: number): number { ... }
-    /// function name(a, b) { ... }
-    /// ```
-    fn parse_func_decl(&mut self) -> Result<FuncDecl, Error> {
-        let _ = self.bump(); // 吃掉 'function'
-
-        let name = self.expect_ident()?;
+    /// 进入本函数时，当前 token 必须是 `(`。参数名必须写，但只有类型会被保留。
+    fn parse_func_type_ann(&mut self) -> Result<TypeAnn, Error> {
         self.expect_simple(TokenKind::LParen)?;
 
         let mut params = Vec::new();
         if !matches!(self.peek_kind(), Some(TokenKind::RParen)) {
             loop {
-                params.push(self.parse_param()?);
+                let _name = self.expect_ident()?;
+                match self.peek_kind() {
+                    Some(TokenKind::Colon) => {
+                        let _ = self.bump();
+                    }
+                    _ => return Err(self.err_expected(ErrorCode::ExpectedColon, &["`:`"])),
+                }
+                params.push(self.parse_type_ann()?);
                 match self.peek_kind() {
                     Some(TokenKind::Comma) => {
                         let _ = self.bump();
                     }
                     Some(TokenKind::RParen) => break,
-                    Some(_) => return Err(self.err_here("UnexpectedToken")),
-                    None => return Err(self.err_eof("UnexpectedEof")),
+                    _ => return Err(self.err_expected(ErrorCode::MissingRParen, &["`,`", "`)`"])),
                 }
             }
         }
         self.expect_rparen()?;
 
-        let ret_type = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
-            let _ = self.bump();
-            Some(self.parse_type_ann()?)
-        } else {
-            None
-        };
-
-        let body = self.parse_block_only()?;
-        Ok(FuncDecl {
-            name,
-            params,
-            ret_type,
-            body,
-        })
-    }
-
-    fn parse_param(&mut self) -> Result<Param, Error> {
-        let name = self.expect_ident()?;
-        let ty = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
-            let _ = self.bump();
-            Some(self.parse_type_ann()?)
-        } else {
-            None
-        };
-        Ok(Param { name, ty })
-    }
-
-    fn parse_type_ann(&mut self) -> Result<TypeAnn, Error> {
-        let start = self.peek_span().unwrap_or_default();
-        let s = self.expect_ident()?;
-        match s.as_str() {
-            "number" => Ok(TypeAnn::Number),
-            "string" => Ok(TypeAnn::String),
-            "boolean" => Ok(TypeAnn::Boolean),
-            "void" => Ok(TypeAnn::Void),
-            _ => Err(Error::new("UnknownType", start)),
+        match self.peek_kind() {
+            Some(TokenKind::FatArrow) => {
+                let _ = self.bump();
+            }
+            _ => return Err(self.err_expected(ErrorCode::ExpectedArrow, &["`=>`"])),
         }
+        let ret = self.parse_type_ann()?;
+        Ok(TypeAnn::Function(FuncTypeAnn {
+            params,
+            ret: Box::new(ret),
+        }))
     }
 
     /// 只解析一个 block，并返回 BlockStmt（用于函数体）。
     fn parse_block_only(&mut self) -> Result<BlockStmt, Error> {
         if !matches!(self.peek_kind(), Some(TokenKind::LBrace)) {
-            return Err(self.err_here("ExpectedBlock"));
+            return Err(self.err_expected(ErrorCode::ExpectedBlock, &["`{`"]));
         }
         match self.parse_block_stmt()? {
             Stmt::Block(b) => Ok(b),
-            _ => Err(self.err_here("UnexpectedToken")),
+            _ => Err(self.err_here(ErrorCode::UnexpectedToken)),
         }
     }
 
@@ -185,88 +1451,241 @@ impl<'a> Parser<'a> {
     ///
     /// 进入本函数时，当前 token 必须是 `{`。
     fn parse_block_stmt(&mut self) -> Result<Stmt, Error> {
+        let block_start = self.start_span();
         let _ = self.bump(); // 吃掉 '{'
         let mut stmts = Vec::new();
 
         while !matches!(self.peek_kind(), Some(TokenKind::RBrace)) {
             if self.is_eof() {
-                return Err(self.err_eof("MissingRBrace"));
+                return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"]));
+            }
+            // 出错的语句跳过，接着解析块里后面的语句
+            let start = self.i;
+            match self.parse_stmt() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(e) => self.recover(e, start),
             }
-            stmts.push(self.parse_stmt()?);
         }
 
         let _ = self.bump(); // 吃掉 '}'
-        Ok(Stmt::Block(BlockStmt { stmts }))
+        Ok(Stmt::Block(BlockStmt {
+            stmts,
+            span: self.node_span(block_start),
+        }))
     }
 
     /// 解析 if 语句：`if (cond) stmt else stmt`
     fn parse_if_stmt(&mut self) -> Result<Stmt, Error> {
+        let start = self.start_span();
         let _ = self.bump(); // 吃掉 'if'
         self.expect_simple(TokenKind::LParen)?;
-        let cond_span = self.peek_span().unwrap_or_default();
         let cond = self.parse_expr_bp(0)?;
         self.expect_rparen()?;
-        self.ensure_bool_condition(&cond, cond_span)?;
 
         let then_branch = self.parse_stmt()?;
 
-        let else_branch = if matches!(self.peek_kind(), Some(TokenKind::KwElse)) {
-            let _ = self.bump();
-            Some(Box::new(self.parse_stmt()?))
-        } else {
-            None
-        };
+        // Step5 约定：if 必须带 else 分支。
+        if !matches!(self.peek_kind(), Some(TokenKind::KwElse)) {
+            return Err(self.err_expected(ErrorCode::MissingElse, &["`else`"]));
+        }
+        let _ = self.bump(); // 吃掉 'else'
+        let else_branch = Some(Box::new(self.parse_stmt()?));
 
         Ok(Stmt::If(IfStmt {
             cond,
             then_branch: Box::new(then_branch),
             else_branch,
+            span: self.node_span(start),
         }))
     }
 
     /// 解析 while 语句：`while (cond) stmt`
     fn parse_while_stmt(&mut self) -> Result<Stmt, Error> {
+        let start = self.start_span();
         let _ = self.bump(); // 吃掉 'while'
         self.expect_simple(TokenKind::LParen)?;
-        let cond_span = self.peek_span().unwrap_or_default();
         let cond = self.parse_expr_bp(0)?;
         self.expect_rparen()?;
-        self.ensure_bool_condition(&cond, cond_span)?;
 
         let body = self.parse_stmt()?;
         Ok(Stmt::While(WhileStmt {
             cond,
             body: Box::new(body),
+            span: self.node_span(start),
         }))
     }
 
     /// 解析 return 语句：`return expr?;`
     fn parse_return_stmt(&mut self) -> Result<Stmt, Error> {
+        let start = self.start_span();
         let _ = self.bump(); // 吃掉 'return'
 
-        if matches!(self.peek_kind(), Some(TokenKind::Semicolon)) {
+        if matches!(self.peek_kind(), Some(TokenKind::Semicolon)) || self.can_insert_semicolon() {
             self.expect_semicolon()?;
-            return Ok(Stmt::Return(ReturnStmt { value: None }));
+            return Ok(Stmt::Return(ReturnStmt {
+                value: None,
+                span: self.node_span(start),
+            }));
         }
 
         let value = self.parse_expr_bp(0)?;
         self.expect_semicolon()?;
-        Ok(Stmt::Return(ReturnStmt { value: Some(value) }))
+        Ok(Stmt::Return(ReturnStmt {
+            value: Some(value),
+            span: self.node_span(start),
+        }))
+    }
+
+    /// 解析 class 声明：`class Ident ("extends" Ident)? "{" "}"`
+    ///
+    /// 目前类只用来声明自定义异常，类体必须为空；`ambient`（`declare class`）时类体里可以有成员的签名。
+    fn parse_class_decl(&mut self, ambient: bool) -> Result<ClassDecl, Error> {
+        let decorators = std::mem::take(&mut self.decorators);
+        self.warn_ignored_decorators(&decorators, &[]);
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'class'
+        let name = self.expect_ident()?;
+        let extends = if matches!(self.peek_kind(), Some(TokenKind::KwExtends)) {
+            let _ = self.bump();
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+        self.expect_simple(TokenKind::LBrace)?;
+        let (mut methods, mut fields) = (Vec::new(), Vec::new());
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::RBrace) => {
+                    let _ = self.bump();
+                    break;
+                }
+                Some(_) if ambient => match self.parse_ambient_member()? {
+                    Ok(method) => methods.push(method),
+                    Err(field) => fields.push(field),
+                },
+                Some(_) => return Err(self.err_here(ErrorCode::UnsupportedClassMember)),
+                None => return Err(self.err_expected(ErrorCode::MissingRBrace, &["`}`"])),
+            }
+        }
+        Ok(ClassDecl {
+            is_export: false,
+            name,
+            extends,
+            decorators,
+            is_declare: ambient,
+            methods,
+            fields,
+            span: self.node_span(start),
+        })
     }
 
-    /// 检查 if/while 的条件表达式是否“看起来像 bool”。
+    /// 解析 `declare class` 里的一个成员签名，以分号结尾：
+    /// - 方法：`bar(x: number): string;` -> `Ok`，函数体为空
+    /// - 字段：`name: string;` -> `Err`，没有初始值
     ///
-    /// 限制（写入 docs）：不支持 JS truthy，条件必须是 bool。
-    /// 因为我们没有做完整类型系统，所以这里采取“保守拒绝”的策略：
-    /// - 明确是 number/string 的 literal：直接报错
-    /// - 明确是算术表达式（+ - * / %）或一元负号：直接报错
-    /// - 其它（比较、相等、逻辑、标识符、函数调用）：允许
-    fn ensure_bool_condition(&self, expr: &Expr, span: Span) -> Result<(), Error> {
-        if is_bool_like_expr(expr) {
-            Ok(())
+    /// 前面可以写 `static`、`readonly`、`private` 之类的修饰符（忽略）；其它写法报 `UnsupportedClassMember`。
+    fn parse_ambient_member(&mut self) -> Result<Result<FuncDecl, FieldDecl>, Error> {
+        while matches!(self.peek_kind(), Some(TokenKind::Ident(s)) if is_member_modifier(s) || s == "static" || s == "readonly")
+            && matches!(self.peek_kind_n(1), Some(TokenKind::Ident(_)))
+        {
+            let _ = self.bump();
+        }
+        let start = self.start_span();
+        match (self.peek_kind(), self.peek_kind_n(1)) {
+            (Some(TokenKind::Ident(_)), Some(TokenKind::LParen)) => {
+                let (name, params, ret_type) = self.parse_signature()?;
+                self.expect_semicolon()?;
+                Ok(Ok(FuncDecl {
+                    is_export: false,
+                    name,
+                    params,
+                    ret_type,
+                    body: BlockStmt { stmts: Vec::new(), span: NodeSpan::default() },
+                    decorators: Vec::new(),
+                    span: self.node_span(start),
+                }))
+            }
+            (Some(TokenKind::Ident(_)), Some(TokenKind::Colon)) => {
+                let name = self.expect_ident()?;
+                let _ = self.bump(); // 吃掉 ':'
+                let ty = self.parse_type_ann()?;
+                self.expect_semicolon()?;
+                Ok(Err(FieldDecl {
+                    decorators: Vec::new(),
+                    name,
+                    ty: Some(ty),
+                    init: None,
+                    span: self.node_span(start),
+                }))
+            }
+            _ => Err(self.err_here(ErrorCode::UnsupportedClassMember)),
+        }
+    }
+
+    /// 解析 throw 语句：`throw expr;`
+    fn parse_throw_stmt(&mut self) -> Result<Stmt, Error> {
+        let start = self.start_span();
+        let _ = self.bump(); // 吃掉 'throw'
+        let value = self.parse_expr_bp(0)?;
+        self.expect_semicolon()?;
+        Ok(Stmt::Throw(ThrowStmt {
+            value,
+            span: self.node_span(start),
+        }))
+    }
+
+    /// 解析 try 语句：
+    /// - `try Block catch ("(" Ident (":" Ident)? ")")? Block (finally Block)?`
+    /// - `try Block finally Block`
+    fn parse_try_stmt(&mut self) -> Result<Stmt, Error> {
+        let try_span = self.peek_span().unwrap_or_default();
+        let _ = self.bump(); // 吃掉 'try'
+        let block = self.parse_block_only()?;
+
+        let catch = if matches!(self.peek_kind(), Some(TokenKind::KwCatch)) {
+            let start = self.start_span();
+            let _ = self.bump();
+            let (param, ty) = if matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+                let _ = self.bump();
+                let name = self.expect_ident()?;
+                let ty = if matches!(self.peek_kind(), Some(TokenKind::Colon)) {
+                    let _ = self.bump();
+                    Some(self.expect_ident()?) // 异常类名
+                } else {
+                    None
+                };
+                self.expect_rparen()?;
+                (Some(name), ty)
+            } else {
+                (None, None)
+            };
+            let body = self.parse_block_only()?;
+            Some(CatchClause {
+                param,
+                ty,
+                body,
+                span: self.node_span(start),
+            })
+        } else {
+            None
+        };
+
+        let finally = if matches!(self.peek_kind(), Some(TokenKind::KwFinally)) {
+            let _ = self.bump();
+            Some(self.parse_block_only()?)
         } else {
-            Err(Error::new("ConditionMustBeBool", span))
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(Error::new(ErrorCode::MissingCatchOrFinally, try_span));
         }
+        Ok(Stmt::Try(TryStmt {
+            block,
+            catch,
+            finally,
+            span: self.node_span(try_span),
+        }))
     }
 
     /// 解析表达式（Pratt Parser / 运算符优先级解析）。
@@ -275,22 +1694,39 @@ impl<'a> Parser<'a> {
     /// - 数值越大，绑定越紧（优先级越高）。
     /// - 在 while 循环里不断吃掉可以绑定到左侧的运算符，从而构建正确的 AST 结构。
     ///
-    /// Step4 支持的优先级（从低到高，简化版）：
-    /// 1) `||`
-    /// 2) `&&`
-    /// 3) `==` `!=`
-    /// 4) `<` `<=` `>` `>=`
-    /// 5) `+` `-`
-    /// 6) `*` `/` `%`
-    /// 7) 前缀 `!` `-`
-    /// 8) 调用 `f(...)`（后缀，绑定最紧）
+    /// 支持的优先级（从低到高，简化版）：
+    /// 1) `??`
+    /// 2) `||`
+    /// 3) `&&`
+    /// 4) `==` `!=`
+    /// 5) `<` `<=` `>` `>=`
+    /// 6) `+` `-`
+    /// 7) `*` `/` `%`
+    /// 8) 前缀 `!` `-`
+    /// 9) 调用 `f(...)`（后缀，绑定最紧）
+    ///
+    /// 赋值 `target = value` 比上面所有运算符都低，并且是右结合的（`a = b = 0` 是 `a = (b = 0)`），
+    /// 所以只在 `min_bp == 0`（一个完整表达式的开头）时识别。
     fn parse_expr_bp(&mut self, min_bp: u8) -> Result<Expr, Error> {
+        // ---------- 处理赋值表达式：target = value ----------
+        let start = self.start_span();
+        if min_bp == 0 {
+            if let Some(target) = self.parse_assign_target()? {
+                let value = self.parse_expr_bp(0)?;
+                return Ok(Expr::Assign(AssignExpr {
+                    target,
+                    value: Box::new(value),
+                    span: self.node_span(start),
+                }));
+            }
+        }
+
         let mut lhs = self.parse_prefix()?;
 
         loop {
-            // ---------- 处理函数调用：ident(expr, expr, ...) ----------
+            // ---------- 处理函数调用：ident(expr, expr, ...)，以及连续调用 f(x)(y) ----------
             if matches!(self.peek_kind(), Some(TokenKind::LParen)) {
-                let (l_bp, _r_bp) = (15u8, 16u8);
+                let (l_bp, _r_bp) = (17u8, 18u8);
                 if l_bp < min_bp {
                     break;
                 }
@@ -298,25 +1734,81 @@ impl<'a> Parser<'a> {
                 let lparen_span = self.peek_span().unwrap_or_default();
 
                 match lhs {
-                    Expr::Ident(name) => {
+                    Expr::Ident(name, _) => {
                         let args = self.parse_call_args()?;
                         lhs = Expr::Call(CallExpr {
                             callee: Callee::Ident(name),
                             args,
+                            span: self.node_span(start),
                         });
                         continue;
                     }
-                    Expr::Call(_) => {
-                        return Err(Error::new("UnknownStructure", lparen_span));
+                    // `f(x)(y)`：前一个调用返回的是函数
+                    call @ Expr::Call(_) => {
+                        let args = self.parse_call_args()?;
+                        lhs = Expr::Call(CallExpr {
+                            callee: Callee::Expr(Box::new(call)),
+                            args,
+                            span: self.node_span(start),
+                        });
+                        continue;
                     }
                     _ => {
-                        return Err(Error::new("UnknownStructure", lparen_span));
+                        return Err(Error::new(ErrorCode::UnknownStructure, lparen_span));
+                    }
+                }
+            }
+
+            // ---------- 处理成员访问：expr.prop 与方法调用 expr.method(args) ----------
+            if matches!(self.peek_kind(), Some(TokenKind::Dot)) {
+                if 17 < min_bp {
+                    break;
+                }
+                let _ = self.bump();
+                let property = self.expect_ident()?;
+                lhs = if matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+                    let args = self.parse_call_args()?;
+                    Expr::Call(CallExpr {
+                        callee: Callee::Method {
+                            object: Box::new(lhs),
+                            method: property,
+                        },
+                        args,
+                        span: self.node_span(start),
+                    })
+                } else {
+                    Expr::Member(MemberExpr {
+                        object: Box::new(lhs),
+                        property,
+                        span: self.node_span(start),
+                    })
+                };
+                continue;
+            }
+
+            // ---------- 处理下标读取：expr[index] ----------
+            if matches!(self.peek_kind(), Some(TokenKind::LBracket)) {
+                if 17 < min_bp {
+                    break;
+                }
+                let _ = self.bump();
+                let index = self.parse_expr_bp(0)?;
+                match self.peek_kind() {
+                    Some(TokenKind::RBracket) => {
+                        let _ = self.bump();
                     }
+                    _ => return Err(self.err_expected(ErrorCode::MissingRBracket, &["`]`"])),
                 }
+                lhs = Expr::Index(IndexExpr {
+                    object: Box::new(lhs),
+                    index: Box::new(index),
+                    span: self.node_span(start),
+                });
+                continue;
             }
 
             // ---------- 处理二元运算 ----------
-            let (l_bp, r_bp, op) = match self.peek_kind().and_then(|k| infix_bp(k)) {
+            let (l_bp, r_bp, op) = match self.peek_kind().and_then(infix_bp) {
                 Some(x) => x,
                 None => break,
             };
@@ -325,35 +1817,93 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            let _op_tok = self.bump();
+            let op_tok = self.bump();
             let rhs = self.parse_expr_bp(r_bp)?;
+            // 与 TS 一致：`??` 不能和 `&&`/`||` 直接混用，必须加括号表明意图
+            if mixes_nullish_and_logical(op, &lhs) || mixes_nullish_and_logical(op, &rhs) {
+                let span = op_tok.map(|t| t.span).unwrap_or_default();
+                return Err(Error::new(ErrorCode::MixedNullishAndLogical, span));
+            }
             lhs = Expr::Binary(BinaryExpr {
                 op,
                 left: Box::new(lhs),
                 right: Box::new(rhs),
+                span: self.node_span(start),
             });
         }
 
         Ok(lhs)
     }
 
+    /// 如果接下来是“赋值目标 =”，吃掉它们并返回目标；否则不前进，返回 None。
+    ///
+    /// - `x =` -> 变量
+    /// - `obj.x =` -> 字段
+    /// - `arr[i] =` -> 下标（目前还不支持读取下标，所以 `arr[i]` 后面必须是 `=`）
+    fn parse_assign_target(&mut self) -> Result<Option<AssignTarget>, Error> {
+        let object = match self.peek_kind() {
+            Some(TokenKind::Ident(s)) => s.clone(),
+            _ => return Ok(None),
+        };
+        let target = match (self.peek_kind_n(1), self.peek_kind_n(2), self.peek_kind_n(3)) {
+            (Some(TokenKind::Eq), _, _) => {
+                let _ = self.bump();
+                AssignTarget::Ident(object)
+            }
+            (Some(TokenKind::Dot), Some(TokenKind::Ident(property)), Some(TokenKind::Eq)) => {
+                let property = property.clone();
+                let _ = self.bump(); // 吃掉对象名
+                let _ = self.bump(); // 吃掉 '.'
+                let _ = self.bump(); // 吃掉字段名
+                AssignTarget::Member { object, property }
+            }
+            (Some(TokenKind::LBracket), _, _) => {
+                let start = self.i;
+                let _ = self.bump(); // 吃掉对象名
+                let _ = self.bump(); // 吃掉 '['
+                let index = self.parse_expr_bp(0)?;
+                match self.peek_kind() {
+                    Some(TokenKind::RBracket) => {
+                        let _ = self.bump();
+                    }
+                    _ => return Err(self.err_expected(ErrorCode::MissingRBracket, &["`]`"])),
+                }
+                // 后面不是 `=`：这是下标读取，退回去按普通表达式解析
+                if !matches!(self.peek_kind(), Some(TokenKind::Eq)) {
+                    self.i = start;
+                    return Ok(None);
+                }
+                AssignTarget::Index {
+                    object,
+                    index: Box::new(index),
+                }
+            }
+            _ => return Ok(None),
+        };
+        self.expect_simple(TokenKind::Eq)?;
+        Ok(Some(target))
+    }
+
     /// 解析前缀表达式（primary / unary）。
     fn parse_prefix(&mut self) -> Result<Expr, Error> {
+        let start = self.start_span();
         match self.peek_kind() {
             Some(TokenKind::Not) => {
                 let _ = self.bump();
-                let rhs = self.parse_expr_bp(13)?;
+                let rhs = self.parse_expr_bp(15)?;
                 Ok(Expr::Unary(UnaryExpr {
                     op: UnaryOp::Not,
                     expr: Box::new(rhs),
+                    span: self.node_span(start),
                 }))
             }
             Some(TokenKind::Minus) => {
                 let _ = self.bump();
-                let rhs = self.parse_expr_bp(13)?;
+                let rhs = self.parse_expr_bp(15)?;
                 Ok(Expr::Unary(UnaryExpr {
                     op: UnaryOp::Neg,
                     expr: Box::new(rhs),
+                    span: self.node_span(start),
                 }))
             }
             _ => self.parse_primary(),
@@ -366,67 +1916,152 @@ impl<'a> Parser<'a> {
     /// - literal：number/string/boolean
     /// - ident：标识符引用
     /// - 括号：`(expr)`
-    /// - console.log(literal)：为了兼容 Step2/Step3（保持 console.log 参数仍是 literal）
+    /// - console.log(...) / console.error(...) 等：内置的输出函数
     fn parse_primary(&mut self) -> Result<Expr, Error> {
         match self.peek_kind() {
             Some(TokenKind::Number(_))
+            | Some(TokenKind::Float(_))
             | Some(TokenKind::String(_))
             | Some(TokenKind::KwTrue)
-            | Some(TokenKind::KwFalse) => Ok(Expr::Literal(self.parse_literal()?)),
-            Some(TokenKind::Ident(s)) if s == "console.log" => self.parse_console_log_call(),
+            | Some(TokenKind::KwFalse)
+            | Some(TokenKind::KwNull)
+            | Some(TokenKind::KwUndefined) => {
+                let start = self.start_span();
+                let lit = self.parse_literal()?;
+                Ok(Expr::Literal(lit, self.node_span(start)))
+            }
+            Some(TokenKind::Ident(s)) if s == "console.log" => self.parse_console_call(),
             Some(TokenKind::Ident(s)) if s == "console" => {
                 if matches!(self.peek_kind_n(1), Some(TokenKind::Dot))
                     && matches!(self.peek_kind_n(2), Some(TokenKind::Ident(_)))
                 {
-                    self.parse_console_log_call()
+                    self.parse_console_call()
                 } else {
-                    Ok(Expr::Ident(self.expect_ident()?))
+                    self.parse_ident_expr()
                 }
             }
-            Some(TokenKind::Ident(_)) => Ok(Expr::Ident(self.expect_ident()?)),
+            Some(TokenKind::Ident(s))
+                if s == "Promise" && matches!(self.peek_kind_n(1), Some(TokenKind::Dot)) =>
+            {
+                self.parse_promise_call()
+            }
+            Some(TokenKind::Ident(_)) if matches!(self.peek_kind_n(1), Some(TokenKind::Dot)) => {
+                self.parse_member_call()
+            }
+            Some(TokenKind::Ident(_)) => self.parse_ident_expr(),
             Some(TokenKind::LParen) => {
                 let _ = self.bump();
                 let inner = self.parse_expr_bp(0)?;
                 self.expect_rparen()?;
                 Ok(Expr::Group(Box::new(inner)))
             }
-            Some(_) => Err(self.err_here("ExpectedExpr")),
-            None => Err(self.err_eof("ExpectedExpr")),
+            Some(TokenKind::KwNew) => {
+                let start = self.start_span();
+                let _ = self.bump();
+                let class = self.expect_ident()?;
+                let args = self.parse_call_args()?;
+                Ok(Expr::New(NewExpr {
+                    class,
+                    args,
+                    span: self.node_span(start),
+                }))
+            }
+            _ => Err(self.err_expected(ErrorCode::ExpectedExpr, &["expression"])),
         }
     }
 
-    /// 解析 console.log(literal) 调用（兼容 Step2/Step3）。
+    /// 解析 `console.log(...)` / `console.error(...)` / `warn` / `info` / `debug` 调用。
     ///
-    /// 注意：为了不破坏原来的 Step2 测试，这里仍然严格要求参数是 literal。
-    fn parse_console_log_call(&mut self) -> Result<Expr, Error> {
+    /// 其它方法名报 `UnknownStructure`。实参和普通函数调用一样由 `parse_call_args` 解析：可以是任意表达式，也可以没有实参。
+    fn parse_console_call(&mut self) -> Result<Expr, Error> {
         let start_span = self.peek_span().unwrap_or_default();
 
         let callee = match self.peek_kind() {
             Some(TokenKind::Ident(s)) if s == "console.log" => {
                 let _ = self.bump();
-                Callee::ConsoleLog
+                Callee::Console(ConsoleFn::Log)
             }
             Some(TokenKind::Ident(s)) if s == "console" => {
                 let _ = self.bump();
                 self.expect_dot()?;
-                let ident = self.expect_ident()?;
-                if ident != "log" {
-                    return Err(self.err_span("UnknownStructure", start_span));
-                }
-                Callee::ConsoleLog
+                let method = match self.expect_ident()?.as_str() {
+                    "log" => ConsoleFn::Log,
+                    "error" => ConsoleFn::Error,
+                    "warn" => ConsoleFn::Warn,
+                    "info" => ConsoleFn::Info,
+                    "debug" => ConsoleFn::Debug,
+                    "assert" => ConsoleFn::Assert,
+                    _ => return Err(self.err_span(ErrorCode::UnknownStructure, start_span)),
+                };
+                Callee::Console(method)
             }
-            _ => return Err(self.err_here("UnknownStructure")),
+            _ => return Err(self.err_here(ErrorCode::UnknownStructure)),
         };
 
-        self.expect_simple(TokenKind::LParen)?;
-        let arg = self.parse_expr_bp(0)?;
-        let args = vec![arg];
-        self.expect_rparen()?;
-        Ok(Expr::Call(CallExpr { callee, args }))
+        let args = self.parse_call_args()?;
+        Ok(Expr::Call(CallExpr {
+            callee,
+            args,
+            span: self.node_span(start_span),
+        }))
+    }
+
+    /// 解析 `Promise.resolve(..)` / `Promise.reject(..)` / `Promise.all(..)`。
+    ///
+    /// 其它方法名报 `UnknownStructure`；参数个数留给 CodeGen 检查。
+    fn parse_promise_call(&mut self) -> Result<Expr, Error> {
+        let start_span = self.peek_span().unwrap_or_default();
+        let _ = self.bump(); // 吃掉 `Promise`
+        self.expect_dot()?;
+        let method = match self.expect_ident()?.as_str() {
+            "resolve" => PromiseFn::Resolve,
+            "reject" => PromiseFn::Reject,
+            "all" => PromiseFn::All,
+            _ => return Err(self.err_span(ErrorCode::UnknownStructure, start_span)),
+        };
+        let args = self.parse_call_args()?;
+        Ok(Expr::Call(CallExpr {
+            callee: Callee::Promise(method),
+            args,
+            span: self.node_span(start_span),
+        }))
+    }
+
+    /// 解析标识符引用 `x`，记下它自己的位置。
+    fn parse_ident_expr(&mut self) -> Result<Expr, Error> {
+        let start = self.start_span();
+        let name = self.expect_ident()?;
+        Ok(Expr::Ident(name, self.node_span(start)))
+    }
+
+    /// 解析 `ns.f(args)`：通过命名空间调用函数（也可能是 `s.trim()` 这样的方法调用，由 CodeGen 区分）。
+    ///
+    /// 后面不是调用时是属性读取 `s.length`。
+    fn parse_member_call(&mut self) -> Result<Expr, Error> {
+        let start = self.start_span();
+        let object = self.expect_ident()?;
+        let object_span = self.node_span(start);
+        self.expect_dot()?;
+        let property = self.expect_ident()?;
+        if !matches!(self.peek_kind(), Some(TokenKind::LParen)) {
+            return Ok(Expr::Member(MemberExpr {
+                object: Box::new(Expr::Ident(object, object_span)),
+                property,
+                span: self.node_span(start),
+            }));
+        }
+        let args = self.parse_call_args()?;
+        Ok(Expr::Call(CallExpr {
+            callee: Callee::Member { object, property },
+            args,
+            span: self.node_span(start),
+        }))
     }
 
     /// 解析函数调用参数列表（用于 ident(expr, expr, ...)）。
     ///
+    /// 实参可以是展开表达式 `...xs`，对应被调函数的剩余参数。
+    ///
     /// 进入本函数时，当前 token 必须是 `(`。
     fn parse_call_args(&mut self) -> Result<Vec<Expr>, Error> {
         self.expect_simple(TokenKind::LParen)?;
@@ -438,7 +2073,12 @@ impl<'a> Parser<'a> {
         }
 
         loop {
-            let expr = self.parse_expr_bp(0)?;
+            let expr = if matches!(self.peek_kind(), Some(TokenKind::Ellipsis)) {
+                let _ = self.bump(); // 吃掉 '...'
+                Expr::Spread(Box::new(self.parse_expr_bp(0)?))
+            } else {
+                self.parse_expr_bp(0)?
+            };
             args.push(expr);
 
             match self.peek_kind() {
@@ -449,8 +2089,7 @@ impl<'a> Parser<'a> {
                     let _ = self.bump();
                     break;
                 }
-                Some(_) => return Err(self.err_here("MissingRParen")),
-                None => return Err(self.err_eof("MissingRParen")),
+                _ => return Err(self.err_expected(ErrorCode::MissingRParen, &["`,`", "`)`"])),
             }
         }
 
@@ -467,6 +2106,11 @@ impl<'a> Parser<'a> {
                 let _ = self.bump();
                 Ok(Literal::Number(n))
             }
+            Some(TokenKind::Float(s)) => {
+                let s = s.clone();
+                let _ = self.bump();
+                Ok(Literal::Float(s))
+            }
             Some(TokenKind::String(s)) => {
                 let s = s.clone();
                 let _ = self.bump();
@@ -480,8 +2124,15 @@ impl<'a> Parser<'a> {
                 let _ = self.bump();
                 Ok(Literal::Bool(false))
             }
-            Some(_) => Err(self.err_here("ExpectedLiteral")),
-            None => Err(self.err_eof("ExpectedLiteral")),
+            Some(TokenKind::KwNull) => {
+                let _ = self.bump();
+                Ok(Literal::Null)
+            }
+            Some(TokenKind::KwUndefined) => {
+                let _ = self.bump();
+                Ok(Literal::Undefined)
+            }
+            _ => Err(self.err_expected(ErrorCode::ExpectedLiteral, &["literal"])),
         }
     }
 
@@ -493,20 +2144,50 @@ impl<'a> Parser<'a> {
                 let _ = self.bump();
                 Ok(s)
             }
-            Some(_) => Err(self.err_here("ExpectedIdentifier")),
-            None => Err(self.err_eof("ExpectedIdentifier")),
+            _ => Err(self.err_expected(ErrorCode::ExpectedIdentifier, &["identifier"])),
+        }
+    }
+
+    /// 期望下一个 token 是某个上下文关键字（例如 `from`、`as`，词法上只是普通标识符），否则报 `code`。
+    fn expect_contextual(&mut self, word: &str, code: ErrorCode) -> Result<(), Error> {
+        match self.peek_kind() {
+            Some(TokenKind::Ident(s)) if s == word => {
+                let _ = self.bump();
+                Ok(())
+            }
+            _ => Err(self.err_expected(code, &[&format!("`{word}`")])),
         }
     }
 
     /// 期望下一个 token 是分号 `;`，否则报 `MissingSemicolon`。
+    ///
+    /// `ParseOptions::asi` 时能自动插入分号的地方不报错，改成记一个 `InsertedSemicolon` 警告，
+    /// 位置是前一个 token 的末尾（分号本来应该写的地方）。
     fn expect_semicolon(&mut self) -> Result<(), Error> {
         match self.peek_kind() {
             Some(TokenKind::Semicolon) => {
                 let _ = self.bump();
                 Ok(())
             }
-            Some(_) => Err(self.err_here("MissingSemicolon")),
-            None => Err(self.err_eof("MissingSemicolon")),
+            _ if self.can_insert_semicolon() => {
+                let prev = self.tokens[self.i - 1].span;
+                let span = Span { start: prev.end, start_line: prev.end_line, start_col: prev.end_col, ..prev };
+                self.warnings.push(Warning::new(ErrorCode::InsertedSemicolon, span));
+                Ok(())
+            }
+            _ => Err(self.err_expected(ErrorCode::MissingSemicolon, &["`;`"])),
+        }
+    }
+
+    /// 当前位置能不能自动插入分号（见 `ParseOptions::asi`）：
+    /// 下一个 token 是 `}`、已经到文件末尾，或者和前一个 token 之间有换行。
+    fn can_insert_semicolon(&self) -> bool {
+        if !self.options.asi || self.i == 0 {
+            return false;
+        }
+        match self.tokens.get(self.i) {
+            None => true,
+            Some(tok) => tok.kind == TokenKind::RBrace || tok.span.start_line > self.tokens[self.i - 1].span.end_line,
         }
     }
 
@@ -517,8 +2198,7 @@ impl<'a> Parser<'a> {
                 let _ = self.bump();
                 Ok(())
             }
-            Some(_) => Err(self.err_here("MissingRParen")),
-            None => Err(self.err_eof("MissingRParen")),
+            _ => Err(self.err_expected(ErrorCode::MissingRParen, &["`)`"])),
         }
     }
 
@@ -529,27 +2209,67 @@ impl<'a> Parser<'a> {
                 let _ = self.bump();
                 Ok(())
             }
-            Some(_) => Err(self.err_here("ExpectedDot")),
-            None => Err(self.err_eof("ExpectedDot")),
+            _ => Err(self.err_expected(ErrorCode::ExpectedDot, &["`.`"])),
         }
     }
 
-    /// 期望下一个 token 是某些“固定符号/关键字”。
-    ///
-    /// 这里没有做一个通用的 token 比较函数，而是仅覆盖 Step2 会用到的那几个 token，
-    /// 让实现保持最小且更直观。
+    /// 期望下一个 token 是某个“固定符号/关键字”（不携带值的 token），否则报 `UnexpectedToken`。
     fn expect_simple(&mut self, kind: TokenKind) -> Result<(), Error> {
-        match (self.peek_kind(), &kind) {
-            (Some(TokenKind::KwLet), TokenKind::KwLet)
-            | (Some(TokenKind::KwConst), TokenKind::KwConst)
-            | (Some(TokenKind::LParen), TokenKind::LParen)
-            | (Some(TokenKind::RParen), TokenKind::RParen)
-            | (Some(TokenKind::Eq), TokenKind::Eq) => {
+        match self.peek_kind() {
+            Some(k) if *k == kind => {
                 let _ = self.bump();
                 Ok(())
             }
-            (Some(_), _) => Err(self.err_here("UnexpectedToken")),
-            (None, _) => Err(self.err_eof("UnexpectedEof")),
+            Some(_) => Err(self.err_expected(ErrorCode::UnexpectedToken, &[&kind.describe()])),
+            None => Err(self.err_expected(ErrorCode::UnexpectedEof, &[&kind.describe()])),
+        }
+    }
+
+    /// 从第 `start` 个 token 开始的语句解析出错：记下错误，跳到可以接着解析的位置（见 `synchronize`）。
+    ///
+    /// 没有闭合的代码块会让每一层外层代码块都报一次同样的 `MissingRBrace`，只记一次。
+    fn recover(&mut self, err: Error, start: usize) {
+        if self.errors.last() != Some(&err) {
+            self.errors.push(err);
+        }
+        self.decorators.clear();
+        self.synchronize(start);
+    }
+
+    /// 出错后跳过 token，直到可以重新开始解析一条语句：
+    /// - 吃掉语句末尾的 `;`
+    /// - 出错的语句里已经打开的代码块（`function f() { ..`）跳到它们闭合的 `}` 为止，吃掉这个 `}`
+    /// - 停在外层代码块的 `}` 前面（由外层吃掉）
+    /// - 停在下一个语句关键字（`let`、`if`、`function` 等）或装饰器 `@` 前面
+    ///
+    /// 中间遇到的 `{ .. }` 整个跳过。停下的位置一定在 `start` 之后，保证解析一直在往前走。
+    fn synchronize(&mut self, start: usize) {
+        let opened = self.tokens[start..self.i].iter().fold(0isize, |depth, t| match t.kind {
+            TokenKind::LBrace => depth + 1,
+            TokenKind::RBrace => depth - 1,
+            _ => depth,
+        });
+        let mut depth = opened.max(0) as usize;
+        while let Some(kind) = self.peek_kind() {
+            let moved = self.i > start;
+            match kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let _ = self.bump(); // 吃掉出错语句的最后一个 '}'
+                        return;
+                    }
+                }
+                TokenKind::RBrace if moved => return,
+                TokenKind::Semicolon if depth == 0 => {
+                    let _ = self.bump(); // 吃掉 ';'
+                    return;
+                }
+                kind if depth == 0 && moved && starts_stmt(kind) => return,
+                _ => {}
+            }
+            let _ = self.bump();
         }
     }
 
@@ -583,20 +2303,38 @@ impl<'a> Parser<'a> {
         self.i >= self.tokens.len()
     }
 
+    /// 当前 token 的位置（EOF 时是最后一个 token），作为 AST 节点的起点（见 `node_span`）。
+    fn start_span(&self) -> Span {
+        self.peek_span().unwrap_or_else(|| self.eof_span())
+    }
+
+    /// AST 节点的位置：从 `start` 到刚吃掉的那个 token 的结尾。
+    fn node_span(&self, start: Span) -> NodeSpan {
+        let end = self.i.checked_sub(1).and_then(|i| self.tokens.get(i)).map_or(start, |t| t.span);
+        NodeSpan::from(start.to(end))
+    }
+
     /// 构造一个错误：定位到“当前 token”的 span。
     ///
     /// 如果已经没有 token（EOF），就退化为使用最后一个 token 的 span（见 eof_span）。
-    fn err_here(&self, code: &'static str) -> Error {
+    fn err_here(&self, code: ErrorCode) -> Error {
         Error::new(code, self.peek_span().unwrap_or_else(|| self.eof_span()))
     }
 
+    /// 构造一个语法错误（定位同 `err_here`），记下这里可以接受的 token 和实际遇到的 token，
+    /// 错误信息里会说明 “expected `)` or `,`, found `;`”（见 `Error::message`）。
+    fn err_expected(&self, code: ErrorCode, expected: &[&str]) -> Error {
+        let found = self.peek_kind().map_or_else(|| "end of file".to_string(), TokenKind::describe);
+        self.err_here(code).with_expected(expected, found)
+    }
+
     /// 构造一个错误：定位到 EOF（使用最后一个 token 的 span）。
-    fn err_eof(&self, code: &'static str) -> Error {
+    fn err_eof(&self, code: ErrorCode) -> Error {
         Error::new(code, self.eof_span())
     }
 
     /// 构造一个错误：定位到指定 span。
-    fn err_span(&self, code: &'static str, span: Span) -> Error {
+    fn err_span(&self, code: ErrorCode, span: Span) -> Error {
         Error::new(code, span)
     }
 
@@ -609,6 +2347,25 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// 一定是一条语句 / 声明开头的 token（错误恢复时停在这里，见 `Parser::synchronize`）。
+fn starts_stmt(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::KwLet
+            | TokenKind::KwConst
+            | TokenKind::KwFunction
+            | TokenKind::KwIf
+            | TokenKind::KwWhile
+            | TokenKind::KwReturn
+            | TokenKind::KwTry
+            | TokenKind::KwThrow
+            | TokenKind::KwClass
+            | TokenKind::KwImport
+            | TokenKind::KwExport
+            | TokenKind::At
+    )
+}
+
 fn infix_bp(kind: &TokenKind) -> Option<(u8, u8, BinaryOp)> {
     // 这里返回 (left_bp, right_bp, op)：
     // - left_bp 越大，表示该运算符越“紧密地绑定”左侧
@@ -618,44 +2375,35 @@ fn infix_bp(kind: &TokenKind) -> Option<(u8, u8, BinaryOp)> {
     // - 对左结合运算符（本 Step 的所有二元运算符都是左结合），使用 (p, p+1)
     //   能确保 `1-2-3` 解析为 `(1-2)-3`，而不是 `1-(2-3)`。
     match kind {
-        TokenKind::OrOr => Some((1, 2, BinaryOp::OrOr)),
-        TokenKind::AndAnd => Some((3, 4, BinaryOp::AndAnd)),
-        TokenKind::EqEq => Some((5, 6, BinaryOp::EqEq)),
-        TokenKind::NotEq => Some((5, 6, BinaryOp::NotEq)),
-        TokenKind::Lt => Some((7, 8, BinaryOp::Lt)),
-        TokenKind::LtEq => Some((7, 8, BinaryOp::LtEq)),
-        TokenKind::Gt => Some((7, 8, BinaryOp::Gt)),
-        TokenKind::GtEq => Some((7, 8, BinaryOp::GtEq)),
-        TokenKind::Plus => Some((9, 10, BinaryOp::Add)),
-        TokenKind::Minus => Some((9, 10, BinaryOp::Sub)),
-        TokenKind::Star => Some((11, 12, BinaryOp::Mul)),
-        TokenKind::Slash => Some((11, 12, BinaryOp::Div)),
-        TokenKind::Percent => Some((11, 12, BinaryOp::Mod)),
+        TokenKind::QuestionQuestion => Some((1, 2, BinaryOp::NullishCoalesce)),
+        TokenKind::OrOr => Some((3, 4, BinaryOp::OrOr)),
+        TokenKind::AndAnd => Some((5, 6, BinaryOp::AndAnd)),
+        TokenKind::EqEq => Some((7, 8, BinaryOp::EqEq)),
+        TokenKind::NotEq => Some((7, 8, BinaryOp::NotEq)),
+        TokenKind::Lt => Some((9, 10, BinaryOp::Lt)),
+        TokenKind::LtEq => Some((9, 10, BinaryOp::LtEq)),
+        TokenKind::Gt => Some((9, 10, BinaryOp::Gt)),
+        TokenKind::GtEq => Some((9, 10, BinaryOp::GtEq)),
+        TokenKind::Plus => Some((11, 12, BinaryOp::Add)),
+        TokenKind::Minus => Some((11, 12, BinaryOp::Sub)),
+        TokenKind::Star => Some((13, 14, BinaryOp::Mul)),
+        TokenKind::Slash => Some((13, 14, BinaryOp::Div)),
+        TokenKind::Percent => Some((13, 14, BinaryOp::Mod)),
         _ => None,
     }
 }
 
-fn is_bool_like_expr(expr: &Expr) -> bool {
-    match expr {
-        Expr::Literal(Literal::Bool(_)) => true,
-        Expr::Literal(Literal::Number(_)) | Expr::Literal(Literal::String(_)) => false,
-        Expr::Ident(_) => true,
-        Expr::Call(_) => true,
-        Expr::Group(inner) => is_bool_like_expr(inner),
-        Expr::Unary(u) => match u.op {
-            UnaryOp::Not => true,
-            UnaryOp::Neg => false,
-        },
-        Expr::Binary(b) => match b.op {
-            BinaryOp::EqEq
-            | BinaryOp::NotEq
-            | BinaryOp::Lt
-            | BinaryOp::LtEq
-            | BinaryOp::Gt
-            | BinaryOp::GtEq
-            | BinaryOp::AndAnd
-            | BinaryOp::OrOr => true,
-            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => false,
-        },
-    }
+/// `op` 与未加括号的操作数 `operand` 是否构成 `??` 与 `&&`/`||` 的混用。
+fn mixes_nullish_and_logical(op: BinaryOp, operand: &Expr) -> bool {
+    let Expr::Binary(inner) = operand else {
+        return false;
+    };
+    let is_logical = |o: BinaryOp| matches!(o, BinaryOp::AndAnd | BinaryOp::OrOr);
+    (op == BinaryOp::NullishCoalesce && is_logical(inner.op))
+        || (is_logical(op) && inner.op == BinaryOp::NullishCoalesce)
+}
+
+/// 组件成员前面可以写、但翻译时忽略的访问修饰符。
+fn is_member_modifier(word: &str) -> bool {
+    matches!(word, "private" | "public" | "protected")
 }
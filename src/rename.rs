@@ -0,0 +1,585 @@
+use crate::ast::{
+    AssignTarget, Callee, Expr, FuncDecl, Param, Pattern, Program, Stmt, UiArg, UiAttr, UiNode,
+};
+use crate::codegen::Edition;
+use crate::module::{Unit, Use};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 把 ArkTS 的 camelCase 名字转成 Rust 习惯的 snake_case：`fooBar` -> `foo_bar`。
+///
+/// 连续的大写字母当作一个词（`parseHTTPResponse` -> `parse_http_response`），数字后面的大写字母另起一个词
+/// （`x1Value` -> `x1_value`），开头的 `_` 保留。没有小写字母的名字（`MAX_SIZE` 这样的常量）保持不变。
+pub fn snake_case(name: &str) -> String {
+    if !name.chars().any(|c| c.is_ascii_lowercase()) {
+        return name.to_string();
+    }
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev = i.checked_sub(1).map(|j| chars[j]);
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            let new_word = match prev {
+                Some(p) if p.is_ascii_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_ascii_uppercase() => next_lower,
+                _ => false,
+            };
+            if new_word {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(*c);
+        }
+    }
+    out
+}
+
+/// 名字映射表：原来的名字 -> 生成代码里用的名字（只记改了名的）。
+///
+/// 表是全局的：同一个名字不管在哪个作用域、哪个模块里声明，都改成同一个新名字，
+/// 所以改名不用关心作用域，CodeGen 里按名字查找的地方（函数签名、闭包捕获、`mut` 分析）照样对得上。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameMap {
+    names: BTreeMap<String, String>,
+}
+
+impl NameMap {
+    /// 名字改成了什么；没有改名时为 None。
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.names.get(name).map(String::as_str)
+    }
+
+    /// 反查：生成代码里的名字原来叫什么（例如原生模块给 JS 用的导出名要保持原样）。
+    pub fn original(&self, renamed: &str) -> Option<&str> {
+        self.names.iter().find(|(_, to)| to.as_str() == renamed).map(|(from, _)| from.as_str())
+    }
+
+    /// 按原来的名字排序的 `(原名, 新名)`。
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.names.iter().map(|(from, to)| (from.as_str(), to.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// 写成 JSON 对象（CLI 的 `--name-map` 输出这个）：`{"fooBar": "foo_bar"}`，按原名排序。
+    pub fn to_json(&self) -> String {
+        let items: Vec<String> = self.iter().map(|(from, to)| format!("  \"{from}\": \"{to}\"")).collect();
+        if items.is_empty() {
+            return "{}\n".to_string();
+        }
+        format!("{{\n{}\n}}\n", items.join(",\n"))
+    }
+
+    fn rename(&self, name: &mut String) {
+        if let Some(to) = self.names.get(name.as_str()) {
+            *name = to.clone();
+        }
+    }
+}
+
+/// 给程序里的函数名、变量名、参数名建立 snake_case 的映射表（见 `NameMap`）。
+///
+/// 不改名的：
+/// - `declare function`：`--bind` 按原名配置外部绑定
+/// - class、组件、Ability、namespace、`@Builder` / `@Styles` 的名字，以及组件和 Ability 的方法、字段
+///   （它们是类型名、UI 里的组件名或者通过 `this.x` 访问的成员）
+/// - 转换后和 Rust 关键字相同的名字（`Type` -> `type`，见 `is_keyword`）
+///
+/// 转换后的名字和别的名字冲突时（`fooBar` 和 `foo_bar` 都有）依次加上 `_2`、`_3`……，按原名排序决定谁加后缀。
+pub fn snake_case_map(program: &Program) -> NameMap {
+    let (declared, keep) = declared_names(program);
+    // 已经占用的名字：所有原名（包括不改名的）和已经分配出去的新名字
+    let mut taken: BTreeSet<String> = declared.iter().cloned().chain(keep.iter().map(|s| s.to_string())).collect();
+    let mut names = BTreeMap::new();
+    for name in &declared {
+        let snake = snake_case(name);
+        if snake == *name || keep.contains(name.as_str()) || is_keyword(&snake, Edition::E2024) {
+            continue;
+        }
+        names.insert(name.clone(), fresh_name(&snake, &mut taken));
+    }
+    NameMap { names }
+}
+
+/// 把一个 ArkTS 标识符变成合法的 Rust 标识符（不保证唯一，见 `sanitize`）：
+/// - 和 Rust 关键字（见 `is_keyword`）相同：原始标识符 `match` -> `r#match`；`self`、`Self`、`super`、`crate`
+///   不能写成原始标识符，加后缀 `self` -> `self_`
+/// - `$` 换成 `_`，非 ASCII 字符换成 `_u` 加十六进制码点：`$count` -> `_count`，`计数` -> `_u8ba1_u6570`
+/// - 结果是 `_` 或者以 `__` 开头（`__` 留给生成的临时变量和辅助函数，例如 `__ark_random`）时加前缀 `x`：`__tmp` -> `x__tmp`
+///
+/// 已经合法的名字原样返回。
+pub fn rust_ident(name: &str, edition: Edition) -> Cow<'_, str> {
+    if is_keyword(name, edition) {
+        return match name {
+            "self" | "Self" | "super" | "crate" => Cow::Owned(format!("{name}_")),
+            _ => Cow::Owned(format!("r#{name}")),
+        };
+    }
+    let mut out = String::new();
+    for c in name.chars() {
+        match c {
+            '$' => out.push('_'),
+            c if c.is_ascii() => out.push(c),
+            c => out.push_str(&format!("_u{:x}", c as u32)),
+        }
+    }
+    if out == "_" || out.starts_with("__") {
+        out.insert(0, 'x');
+    }
+    match out == name {
+        true => Cow::Borrowed(name),
+        false => Cow::Owned(out),
+    }
+}
+
+/// 名字清理：把函数名、变量名、参数名都变成合法、不重复的 Rust 标识符（见 `rust_ident`），结果合并进 `map`。
+/// 改出来的名字和别的名字冲突时依次试 `_2`、`_3`……（`$a` 和 `_a` 都有时，`$a` 改成 `_a_2`）。
+///
+/// CodeGen 生成代码之前总是按这里的结果改名（见 `codegen::renamed`），所有用到这些名字的地方都跟着改。
+/// `map` 里已有的映射（例如 `snake_case_map` 的结果）按改名之后的名字判断；不改名的名字和 `snake_case_map` 相同。
+pub fn sanitize(program: &Program, map: &mut NameMap, edition: Edition) {
+    let (declared, keep) = declared_names(program);
+    let mut taken = taken_names(&declared, &keep, map);
+    for name in &declared {
+        let current = map.get(name).unwrap_or(name).to_string();
+        if keep.contains(name.as_str()) {
+            continue;
+        }
+        let to = match rust_ident(&current, edition) {
+            Cow::Borrowed(_) => continue,
+            // 原始标识符不会和别的名字冲突
+            Cow::Owned(to) if to.starts_with("r#") => to,
+            Cow::Owned(to) => fresh_suffixed(to.trim_end_matches('_'), &to, &mut taken),
+        };
+        map.names.insert(name.clone(), to);
+    }
+}
+
+/// 顶层函数 `main` 改成 `main_`（被占用时依次试 `main_2`、`main_3`……），把 `fn main` 留给可执行程序生成的入口函数，
+/// 调用处跟着改。结果合并进 `map`；没有顶层函数 `main` 时不变，返回 false。
+pub fn rename_main(program: &Program, map: &mut NameMap) -> bool {
+    if !program.funcs.iter().any(|f| f.name == "main") || map.names.contains_key("main") {
+        return false;
+    }
+    let (declared, keep) = declared_names(program);
+    let mut taken = taken_names(&declared, &keep, map);
+    let to = fresh_suffixed("main", "main_", &mut taken);
+    map.names.insert("main".to_string(), to);
+    true
+}
+
+/// 多文件合并时给一个模块里和别的模块冲突的名字改名（见 `module::link`）：`names` 里的名字依次改成
+/// `{name}_2`、`{name}_3`……里第一个不在 `taken` 里的，新名字记进 `taken`。模块里的声明和引用按结果改写，返回映射表。
+pub(crate) fn rename_apart(program: &mut Program, names: &[String], taken: &mut BTreeSet<String>) -> NameMap {
+    let mut map = NameMap::default();
+    for name in names {
+        let to = fresh_name(name, taken);
+        map.names.insert(name.clone(), to);
+    }
+    if !map.is_empty() {
+        apply(program, &map);
+    }
+    map
+}
+
+/// 程序里声明的函数名、变量名、参数名（见 `collect_program`）。
+pub(crate) fn declared(program: &Program) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    collect_program(program, &mut out);
+    out
+}
+
+/// 已经占用的名字：所有原名（包括不改名的）和 `map` 里已经分配出去的新名字。
+fn taken_names(declared: &BTreeSet<String>, keep: &BTreeSet<&str>, map: &NameMap) -> BTreeSet<String> {
+    declared
+        .iter()
+        .cloned()
+        .chain(keep.iter().map(|s| s.to_string()))
+        .chain(map.names.values().cloned())
+        .collect()
+}
+
+/// 先试 `first`，被占用时按 `fresh_name(base)` 从 `{base}_2` 开始试；结果记进 `taken`。
+fn fresh_suffixed(base: &str, first: &str, taken: &mut BTreeSet<String>) -> String {
+    match taken.insert(first.to_string()) {
+        true => first.to_string(),
+        false => fresh_name(base, taken),
+    }
+}
+
+/// `base` 没有被占用时就用它，否则依次加上 `_2`、`_3`……；结果记进 `taken`。
+fn fresh_name(base: &str, taken: &mut BTreeSet<String>) -> String {
+    let mut to = base.to_string();
+    let mut n = 2;
+    while taken.contains(&to) {
+        to = format!("{base}_{n}");
+        n += 1;
+    }
+    taken.insert(to.clone());
+    to
+}
+
+/// 程序里声明的函数名、变量名、参数名，以及其中不能改名的名字（见 `snake_case_map`）。
+fn declared_names(program: &Program) -> (BTreeSet<String>, BTreeSet<&str>) {
+    let mut declared = BTreeSet::new();
+    collect_program(program, &mut declared);
+    let keep: BTreeSet<&str> = program
+        .declare_funcs
+        .iter()
+        .map(|f| f.name.as_str())
+        .chain(program.classes.iter().map(|c| c.name.as_str()))
+        .chain(program.components.iter().map(|c| c.name.as_str()))
+        .chain(program.abilities.iter().map(|a| a.name.as_str()))
+        .chain(program.namespace_decls.iter().map(|ns| ns.name.as_str()))
+        .chain(program.namespaces.iter().map(|ns| ns.name.as_str()))
+        .chain(program.builders.iter().map(|b| b.name.as_str()))
+        .chain(program.styles.iter().map(|s| s.name.as_str()))
+        .collect();
+    (declared, keep)
+}
+
+/// 按映射表改写程序里所有的声明和引用（`snake_case_map` 得到的表，或者自己构造的表）。
+pub fn apply(program: &mut Program, map: &NameMap) {
+    let namespaces: BTreeSet<String> = program
+        .namespace_decls
+        .iter()
+        .map(|ns| ns.name.clone())
+        .chain(program.namespaces.iter().map(|ns| ns.name.clone()))
+        .collect();
+    let r = Renamer { map, namespaces };
+    for alias in program.aliases.iter_mut().chain(program.namespaces.iter_mut().flat_map(|ns| &mut ns.members)) {
+        map.rename(&mut alias.name);
+        map.rename(&mut alias.target);
+    }
+    for f in program.funcs.iter_mut().chain(program.namespace_decls.iter_mut().flat_map(|ns| &mut ns.funcs)) {
+        map.rename(&mut f.name);
+        r.func_body(f);
+    }
+    for f in program.components.iter_mut().flat_map(|c| &mut c.methods).chain(program.abilities.iter_mut().flat_map(|a| &mut a.methods)) {
+        r.func_body(f);
+    }
+    for c in &mut program.components {
+        for field in &mut c.fields {
+            if let Some(init) = &mut field.init {
+                r.expr(init);
+            }
+        }
+        if let Some(build) = &mut c.build {
+            r.ui_nodes(build);
+        }
+        for b in &mut c.builders {
+            r.params(&mut b.params);
+            r.ui_nodes(&mut b.body);
+        }
+        for s in &mut c.styles {
+            r.params(&mut s.params);
+            r.ui_attrs(&mut s.attrs);
+        }
+    }
+    for b in &mut program.builders {
+        r.params(&mut b.params);
+        r.ui_nodes(&mut b.body);
+    }
+    for s in &mut program.styles {
+        r.params(&mut s.params);
+        r.ui_attrs(&mut s.attrs);
+    }
+    r.stmts(&mut program.stmts);
+}
+
+/// 模块树（见 `module::link_tree`）的每个文件按同一张表改名，`use` 里的名字也一起改。
+pub fn apply_units(units: &mut [Unit], map: &NameMap) {
+    for unit in units {
+        apply(&mut unit.program, map);
+        for u in &mut unit.uses {
+            if let Use::Item { target, name, .. } = u {
+                map.rename(target);
+                map.rename(name);
+            }
+        }
+    }
+}
+
+/// `name` 在 `edition` 里是不是 Rust 关键字（包括保留的关键字）：
+/// `async`、`await`、`dyn`、`try` 从 2018 开始是关键字，`gen` 从 2024 开始。
+pub fn is_keyword(name: &str, edition: Edition) -> bool {
+    const KEYWORDS: [&str; 47] = [
+        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+        "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+        "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+        "override", "priv", "typeof", "unsized", "virtual", "yield",
+    ];
+    match name {
+        "async" | "await" | "dyn" | "try" => edition != Edition::E2015,
+        "gen" => edition == Edition::E2024,
+        _ => KEYWORDS.contains(&name),
+    }
+}
+
+/// 程序里声明的函数名、变量名、参数名（包括各种函数体、UI 回调里的）。
+fn collect_program(program: &Program, out: &mut BTreeSet<String>) {
+    for f in program.funcs.iter().chain(program.namespace_decls.iter().flat_map(|ns| &ns.funcs)) {
+        out.insert(f.name.clone());
+        collect_func_body(f, out);
+    }
+    for f in program.components.iter().flat_map(|c| &c.methods).chain(program.abilities.iter().flat_map(|a| &a.methods)) {
+        collect_func_body(f, out);
+    }
+    // 别名指向改了名的函数时，别名本身也是一个函数名
+    for alias in &program.aliases {
+        if out.contains(&alias.target) {
+            out.insert(alias.name.clone());
+        }
+    }
+    for c in &program.components {
+        if let Some(build) = &c.build {
+            collect_ui(build, out);
+        }
+        for b in &c.builders {
+            collect_params(&b.params, out);
+            collect_ui(&b.body, out);
+        }
+        c.styles.iter().for_each(|s| collect_params(&s.params, out));
+    }
+    for b in &program.builders {
+        collect_params(&b.params, out);
+        collect_ui(&b.body, out);
+    }
+    program.styles.iter().for_each(|s| collect_params(&s.params, out));
+    collect_stmts(&program.stmts, out);
+}
+
+fn collect_func_body(f: &FuncDecl, out: &mut BTreeSet<String>) {
+    collect_params(&f.params, out);
+    collect_stmts(&f.body.stmts, out);
+}
+
+fn collect_params(params: &[Param], out: &mut BTreeSet<String>) {
+    out.extend(params.iter().map(|p| p.name.clone()));
+}
+
+fn collect_stmts(stmts: &[Stmt], out: &mut BTreeSet<String>) {
+    for s in stmts {
+        match s {
+            Stmt::VarDecl(v) => out.extend(v.names().into_iter().map(String::from)),
+            Stmt::Block(b) => collect_stmts(&b.stmts, out),
+            Stmt::If(i) => {
+                collect_stmts(std::slice::from_ref(&i.then_branch), out);
+                if let Some(e) = &i.else_branch {
+                    collect_stmts(std::slice::from_ref(e), out);
+                }
+            }
+            Stmt::While(w) => collect_stmts(std::slice::from_ref(&w.body), out),
+            Stmt::FuncDecl(f) => {
+                out.insert(f.name.clone());
+                collect_func_body(f, out);
+            }
+            Stmt::Try(t) => {
+                collect_stmts(&t.block.stmts, out);
+                if let Some(c) = &t.catch {
+                    out.extend(c.param.clone());
+                    collect_stmts(&c.body.stmts, out);
+                }
+                if let Some(f) = &t.finally {
+                    collect_stmts(&f.stmts, out);
+                }
+            }
+            Stmt::Assign(_) | Stmt::ExprStmt(_) | Stmt::Return(_) | Stmt::Throw(_) => {}
+        }
+    }
+}
+
+fn collect_ui(nodes: &[UiNode], out: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            UiNode::Element(el) => {
+                for arg in el.args.iter().chain(el.attrs.iter().flat_map(|a| &a.args)) {
+                    if let UiArg::Handler(h) = arg {
+                        collect_params(&h.params, out);
+                        collect_ui(&h.body, out);
+                    }
+                }
+                collect_ui(&el.children, out);
+            }
+            UiNode::If(i) => {
+                collect_ui(&i.then_branch, out);
+                collect_ui(&i.else_branch, out);
+            }
+            UiNode::Stmt(s) => collect_stmts(std::slice::from_ref(s), out),
+        }
+    }
+}
+
+/// 按映射表改写声明和引用。
+struct Renamer<'a> {
+    map: &'a NameMap,
+    /// namespace 的名字：`Utils.fooBar()` 里的 `fooBar` 是函数名，要跟着改
+    namespaces: BTreeSet<String>,
+}
+
+impl Renamer<'_> {
+    fn func_body(&self, f: &mut FuncDecl) {
+        self.params(&mut f.params);
+        self.stmts(&mut f.body.stmts);
+    }
+
+    fn params(&self, params: &mut [Param]) {
+        for p in params {
+            self.map.rename(&mut p.name);
+        }
+    }
+
+    fn pattern(&self, p: &mut Pattern) {
+        match p {
+            Pattern::Ident(name) => self.map.rename(name),
+            Pattern::Array(items) => items.iter_mut().for_each(|p| self.pattern(p)),
+            // 字段名 `key` 不变，`{fooBar}` 变成 `{fooBar: foo_bar}`
+            Pattern::Object(props) => props.iter_mut().for_each(|p| self.pattern(&mut p.value)),
+        }
+    }
+
+    fn stmts(&self, stmts: &mut [Stmt]) {
+        for s in stmts {
+            self.stmt(s);
+        }
+    }
+
+    fn stmt(&self, s: &mut Stmt) {
+        match s {
+            Stmt::VarDecl(v) => {
+                for d in &mut v.decls {
+                    self.pattern(&mut d.pattern);
+                    if let Some(init) = &mut d.init {
+                        self.expr(init);
+                    }
+                }
+            }
+            Stmt::Assign(a) => {
+                self.map.rename(&mut a.name);
+                self.expr(&mut a.value);
+            }
+            Stmt::ExprStmt(e) => self.expr(e),
+            Stmt::Block(b) => self.stmts(&mut b.stmts),
+            Stmt::If(i) => {
+                self.expr(&mut i.cond);
+                self.stmt(&mut i.then_branch);
+                if let Some(e) = &mut i.else_branch {
+                    self.stmt(e);
+                }
+            }
+            Stmt::While(w) => {
+                self.expr(&mut w.cond);
+                self.stmt(&mut w.body);
+            }
+            Stmt::Return(r) => {
+                if let Some(v) = &mut r.value {
+                    self.expr(v);
+                }
+            }
+            Stmt::FuncDecl(f) => {
+                self.map.rename(&mut f.name);
+                self.func_body(f);
+            }
+            Stmt::Throw(t) => self.expr(&mut t.value),
+            Stmt::Try(t) => {
+                self.stmts(&mut t.block.stmts);
+                if let Some(c) = &mut t.catch {
+                    if let Some(param) = &mut c.param {
+                        self.map.rename(param);
+                    }
+                    self.stmts(&mut c.body.stmts);
+                }
+                if let Some(f) = &mut t.finally {
+                    self.stmts(&mut f.stmts);
+                }
+            }
+        }
+    }
+
+    fn expr(&self, e: &mut Expr) {
+        match e {
+            Expr::Literal(_, _) => {}
+            Expr::Ident(name, _) => self.map.rename(name),
+            Expr::Unary(u) => self.expr(&mut u.expr),
+            Expr::Binary(b) => {
+                self.expr(&mut b.left);
+                self.expr(&mut b.right);
+            }
+            Expr::Group(inner) | Expr::Spread(inner) => self.expr(inner),
+            // 字段名、方法名不变
+            Expr::Member(m) => self.expr(&mut m.object),
+            Expr::Index(ix) => {
+                self.expr(&mut ix.object);
+                self.expr(&mut ix.index);
+            }
+            Expr::Call(c) => {
+                match &mut c.callee {
+                    Callee::Ident(name) => self.map.rename(name),
+                    Callee::Member { object, property } => {
+                        if self.namespaces.contains(object.as_str()) {
+                            self.map.rename(property);
+                        } else {
+                            self.map.rename(object);
+                        }
+                    }
+                    Callee::Expr(inner) | Callee::Method { object: inner, .. } => self.expr(inner),
+                    Callee::Console(_) | Callee::Promise(_) => {}
+                }
+                c.args.iter_mut().for_each(|a| self.expr(a));
+            }
+            Expr::New(n) => n.args.iter_mut().for_each(|a| self.expr(a)),
+            Expr::Assign(a) => {
+                match &mut a.target {
+                    AssignTarget::Ident(name) | AssignTarget::Member { object: name, .. } => self.map.rename(name),
+                    AssignTarget::Index { object, index } => {
+                        self.map.rename(object);
+                        self.expr(index);
+                    }
+                }
+                self.expr(&mut a.value);
+            }
+        }
+    }
+
+    fn ui_nodes(&self, nodes: &mut [UiNode]) {
+        for node in nodes {
+            match node {
+                UiNode::Element(el) => {
+                    self.ui_args(&mut el.args);
+                    self.ui_nodes(&mut el.children);
+                    self.ui_attrs(&mut el.attrs);
+                }
+                UiNode::If(i) => {
+                    self.expr(&mut i.cond);
+                    self.ui_nodes(&mut i.then_branch);
+                    self.ui_nodes(&mut i.else_branch);
+                }
+                UiNode::Stmt(s) => self.stmt(s),
+            }
+        }
+    }
+
+    fn ui_attrs(&self, attrs: &mut [UiAttr]) {
+        for attr in attrs {
+            self.ui_args(&mut attr.args);
+        }
+    }
+
+    fn ui_args(&self, args: &mut [UiArg]) {
+        for arg in args {
+            match arg {
+                UiArg::Expr(e) => self.expr(e),
+                // 对象的键是组件的属性名，不变
+                UiArg::Object(props) => props.iter_mut().for_each(|(_, v)| self.expr(v)),
+                UiArg::Handler(h) => {
+                    self.params(&mut h.params);
+                    self.ui_nodes(&mut h.body);
+                }
+            }
+        }
+    }
+}
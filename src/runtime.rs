@@ -0,0 +1,650 @@
+use crate::codegen::{Edition, OutputFile};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// 运行时 crate 的名字。`CodegenOptions::runtime` 配置成这个名字时，`cargo::project` 把 `project` 生成的 crate
+/// 放进项目目录，作为 path 依赖。
+pub const CRATE_NAME: &str = "arkts_runtime";
+
+/// 生成的代码用到的一个 JS 语义辅助项（类型或函数）。
+pub(crate) struct Helper {
+    /// 生成的代码里引用它的名字
+    pub(crate) name: &'static str,
+    /// 定义里用到的其它辅助项；`ArkDate` 是生成的代码自己定义的类型别名（见 `CodeGen::with_helpers`）
+    pub(crate) deps: &'static [&'static str],
+    /// 定义。运行时 crate 里要公开的位置写成 `{vis}`：顶层的类型和函数、struct 的字段、固有 impl 里的方法
+    def: &'static str,
+}
+
+impl Helper {
+    /// 写进输出文件的定义（私有），或者运行时 crate 里的定义（`public`，`{vis}` 换成 `pub `）。
+    fn render(&self, public: bool) -> String {
+        self.def.replace("{vis}", if public { "pub " } else { "" })
+    }
+}
+
+/// 全部辅助项，用到的在前面：组件状态类型（`STATE_HELPERS`）、数字转换（`CONVERSION_HELPERS`）、
+/// 值转成文字（`FORMAT_HELPERS`）、`Date`（`DATE_HELPERS`）、字符串方法（`STRING_HELPERS`）、`Math.random()`（`RANDOM_HELPER`）、
+/// 异常类型（`ERROR_HELPER`）、Ability 运行时（`ABILITY_HELPERS`）和 builder UI 后端的 `ArkUi`（`UI_HELPER`）。
+///
+/// CodeGen 生成代码时登记用到了哪些（见 `CodeGen::helper`）。没有配置 `CodegenOptions::runtime` 时
+/// 把它们的定义写进输出文件（见 `inline`），配置了时从运行时 crate 里 `use`（见 `imports`）。
+fn helpers() -> impl DoubleEndedIterator<Item = &'static Helper> {
+    STATE_HELPERS
+        .iter()
+        .chain(&CONVERSION_HELPERS)
+        .chain(&FORMAT_HELPERS)
+        .chain(&DATE_HELPERS)
+        .chain(&STRING_HELPERS)
+        .chain([&RANDOM_HELPER, &ERROR_HELPER])
+        .chain(&ABILITY_HELPERS)
+        .chain([&UI_HELPER])
+}
+
+/// `used` 加上它们的定义里用到的辅助项（可能有 `ArkDate`）。
+pub(crate) fn required(used: &BTreeSet<&'static str>) -> BTreeSet<&'static str> {
+    let mut out = used.clone();
+    // 定义只用到排在前面的辅助项，倒着走一遍就够了
+    for helper in helpers().rev() {
+        if out.contains(helper.name) {
+            out.extend(helper.deps);
+        }
+    }
+    out
+}
+
+/// 在 `out` 开头加上 `required` 里的辅助项的定义。
+pub(crate) fn inline(mut out: String, required: &BTreeSet<&'static str>) -> String {
+    let defs: String = helpers().filter(|h| required.contains(h.name)).map(|h| h.render(false)).collect();
+    out.insert_str(0, &defs);
+    out
+}
+
+/// 名为 `name` 的辅助项的定义，`public` 时是公开的（见 `Helper::render`）。
+///
+/// 给不经过 `CodeGen::helper` 登记的项用：`ArkError` 只在 crate 根定义一次，库里还要公开。
+pub(crate) fn definition(name: &str, public: bool) -> String {
+    helpers().find(|h| h.name == name).map(|h| h.render(public)).unwrap_or_default()
+}
+
+/// 在 `out` 开头加上 `use path::{..};`（只有一项时是 `use path::name;`），引入 `used` 里的辅助项；没有用到时原样返回。
+pub(crate) fn imports(mut out: String, path: &str, used: &BTreeSet<&'static str>) -> String {
+    let names: Vec<&str> = helpers().map(|h| h.name).filter(|name| used.contains(name)).collect();
+    match names.as_slice() {
+        [] => {}
+        [name] => out.insert_str(0, &format!("use {path}::{name};\n\n")),
+        _ => out.insert_str(0, &format!("use {path}::{{{}}};\n\n", names.join(", "))),
+    }
+    out
+}
+
+/// 运行时 crate 的 `src/lib.rs`：全部辅助项，类型、函数、方法和字段都是 `pub`。
+///
+/// `Date` 的辅助函数用 `std::time::SystemTime`（`ArkDate`）；`--chrono` 时生成的代码不用这些函数。
+/// Ability 的生命周期方法和 `loadContent` 保持 ArkTS 的名字，所以开头关掉 `non_snake_case` 警告。
+pub fn source() -> String {
+    let mut out = String::from("#![allow(non_snake_case)]\n\npub type ArkDate = std::time::SystemTime;\n\n");
+    for helper in helpers() {
+        out.push_str(&helper.render(true));
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// 运行时 crate：`Cargo.toml`（包名是 `CRATE_NAME`，没有依赖）和 `src/lib.rs`（见 `source`）。
+pub fn project(edition: Edition) -> Vec<OutputFile> {
+    vec![
+        OutputFile {
+            path: PathBuf::from("Cargo.toml"),
+            code: format!(
+                "[package]\nname = \"{CRATE_NAME}\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n[dependencies]\n",
+                edition.as_str()
+            ),
+        },
+        OutputFile {
+            path: PathBuf::from("src/lib.rs"),
+            code: source(),
+        },
+    ]
+}
+
+/// `Math.random()` 默认使用的随机数函数：xorshift64*，第一次调用时用当前时间做种子。
+///
+/// 只依赖 std，生成的程序不需要额外的 crate；不适合用在密码学场景。
+pub(crate) const RANDOM_HELPER: Helper = Helper {
+    name: "__ark_random",
+    deps: &[],
+    def: "\
+{vis}fn __ark_random() -> f64 {
+    thread_local! {
+        static STATE: std::cell::Cell<u64> = std::cell::Cell::new(0);
+    }
+    STATE.with(|s| {
+        let mut x = s.get();
+        if x == 0 {
+            x = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0)
+                | 1;
+        }
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        s.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+",
+};
+
+/// 组件状态字段的类型（见 `gen_component`、`StateKind`）。
+/// `set` 时版本号加一，用来判断要不要重新 build。
+pub(crate) const STATE_HELPERS: [Helper; 4] = [
+    Helper {
+        name: "ArkState",
+        deps: &[],
+        def: "\
+{vis}struct ArkState<T> {
+    {vis}value: T,
+    {vis}version: u64,
+}
+
+impl<T> ArkState<T> {
+    {vis}fn new(value: T) -> Self {
+        ArkState { value, version: 0 }
+    }
+
+    {vis}fn set(&mut self, value: T) {
+        self.value = value;
+        self.version += 1;
+    }
+}
+
+",
+    },
+    Helper {
+        name: "ArkProp",
+        deps: &[],
+        def: "\
+{vis}struct ArkProp<T> {
+    {vis}value: T,
+    {vis}version: u64,
+}
+
+impl<T> ArkProp<T> {
+    {vis}fn new(value: T) -> Self {
+        ArkProp { value, version: 0 }
+    }
+
+    {vis}fn set(&mut self, value: T) {
+        self.value = value;
+        self.version += 1;
+    }
+}
+
+",
+    },
+    Helper {
+        name: "ArkLink",
+        deps: &["ArkState"],
+        def: "\
+{vis}struct ArkLink<T>(std::rc::Rc<std::cell::RefCell<ArkState<T>>>);
+
+impl<T> Clone for ArkLink<T> {
+    fn clone(&self) -> Self {
+        ArkLink(self.0.clone())
+    }
+}
+
+impl<T: Clone> ArkLink<T> {
+    {vis}fn new(value: T) -> Self {
+        ArkLink(std::rc::Rc::new(std::cell::RefCell::new(ArkState::new(value))))
+    }
+
+    {vis}fn get(&self) -> T {
+        self.0.borrow().value.clone()
+    }
+
+    {vis}fn set(&self, value: T) {
+        self.0.borrow_mut().set(value);
+    }
+
+    {vis}fn version(&self) -> u64 {
+        self.0.borrow().version
+    }
+}
+
+",
+    },
+    Helper {
+        name: "ArkContext",
+        deps: &["ArkLink"],
+        def: "\
+{vis}struct ArkContext {
+    {vis}values: std::collections::HashMap<String, Box<dyn std::any::Any>>,
+}
+
+impl ArkContext {
+    {vis}fn new() -> Self {
+        ArkContext { values: std::collections::HashMap::new() }
+    }
+
+    {vis}fn provide<T: 'static>(&mut self, key: &str, link: ArkLink<T>) {
+        self.values.insert(key.to_string(), Box::new(link));
+    }
+
+    {vis}fn consume<T: 'static>(&self, key: &str) -> ArkLink<T> {
+        match self.values.get(key).and_then(|v| v.downcast_ref::<ArkLink<T>>()) {
+            Some(link) => link.clone(),
+            None => panic!(\"no @Provide for {}\", key),
+        }
+    }
+}
+
+",
+    },
+];
+
+/// 数字转换函数（见 `gen_conversion`）用到的辅助函数。转换失败时返回 `None`。
+///
+/// 最后一个反过来把 f64 转成 JS 的文字（`console.log`、`String(x)` 用），按 ECMAScript 的 Number::toString：
+/// 非有限值是 `Infinity`/`-Infinity`/`NaN`（Rust 的 Display 会输出 `inf`/`-inf`/`NaN`），`-0` 是 `0`，
+/// 绝对值不小于 1e21 或者小于 1e-6 时用指数形式（`1e+21`、`1.5e-7`），其余和 Rust 的 Display 相同（最短的能还原的数字）。
+pub(crate) const CONVERSION_HELPERS: [Helper; 4] = [
+    Helper {
+        name: "__ark_parse_int",
+        deps: &[],
+        def: "\
+{vis}fn __ark_parse_int(s: &str, radix: i32) -> Option<i32> {
+    let s = s.trim_start();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let mut radix = radix as u32;
+    let mut s = s;
+    if radix == 0 || radix == 16 {
+        if let Some(rest) = s.strip_prefix(\"0x\").or_else(|| s.strip_prefix(\"0X\")) {
+            s = rest;
+            radix = 16;
+        }
+    }
+    if radix == 0 {
+        radix = 10;
+    }
+    if !(2..=36).contains(&radix) {
+        return None;
+    }
+    let digits: String = s.chars().take_while(|c| c.is_digit(radix)).collect();
+    let n = i64::from_str_radix(&digits, radix).ok()?;
+    Some(if negative { -n } else { n } as i32)
+}
+
+",
+    },
+    Helper {
+        name: "__ark_parse_float",
+        deps: &[],
+        def: "\
+{vis}fn __ark_parse_float(s: &str) -> Option<f64> {
+    let s = s.trim_start();
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+        .unwrap_or(s.len());
+    (1..=end).rev().find_map(|i| s[..i].parse::<f64>().ok())
+}
+
+",
+    },
+    Helper {
+        name: "__ark_number",
+        deps: &[],
+        def: "\
+{vis}fn __ark_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(0.0);
+    }
+    s.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+",
+    },
+    Helper {
+        name: "__ark_number_to_string",
+        deps: &[],
+        def: "\
+{vis}fn __ark_number_to_string(n: f64) -> String {
+    if n.is_nan() {
+        String::from(\"NaN\")
+    } else if n.is_infinite() {
+        String::from(if n > 0.0 { \"Infinity\" } else { \"-Infinity\" })
+    } else if n == 0.0 {
+        String::from(\"0\")
+    } else if n.abs() >= 1e21 || n.abs() < 1e-6 {
+        let s = format!(\"{:e}\", n);
+        if s.contains(\"e-\") {
+            s
+        } else {
+            s.replacen('e', \"e+\", 1)
+        }
+    } else {
+        n.to_string()
+    }
+}
+
+",
+    },
+];
+
+/// `console.log`、`String(x)` 和字符串拼接把值转成 JS 文字时用到的辅助项（见 `gen_console_arg`、`gen_to_string`）：
+/// `ArkToString` 按 JS 的规则转换基础类型（f64 经过 `__ark_number_to_string`），`__ark_fmt_nullable` 在没有值时
+/// 输出 `null` 或 `undefined`，`__ark_join` 把数组元素用逗号连接。
+pub(crate) const FORMAT_HELPERS: [Helper; 3] = [
+    Helper {
+        name: "ArkToString",
+        deps: &["__ark_number_to_string"],
+        def: "\
+{vis}trait ArkToString {
+    fn ark_to_string(&self) -> String;
+}
+
+impl ArkToString for i32 {
+    fn ark_to_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArkToString for f64 {
+    fn ark_to_string(&self) -> String {
+        __ark_number_to_string(*self)
+    }
+}
+
+impl ArkToString for bool {
+    fn ark_to_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArkToString for String {
+    fn ark_to_string(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ArkToString for &str {
+    fn ark_to_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ArkToString for std::rc::Rc<str> {
+    fn ark_to_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+",
+    },
+    Helper {
+        name: "__ark_fmt_nullable",
+        deps: &["ArkToString"],
+        def: "\
+{vis}fn __ark_fmt_nullable<T: ArkToString>(v: &Option<T>, absent: &str) -> String {
+    match v {
+        Some(v) => v.ark_to_string(),
+        None => String::from(absent),
+    }
+}
+
+",
+    },
+    Helper {
+        name: "__ark_join",
+        deps: &["ArkToString"],
+        def: "\
+{vis}fn __ark_join<T: ArkToString>(xs: &[T]) -> String {
+    xs.iter().map(|x| x.ark_to_string()).collect::<Vec<_>>().join(\",\")
+}
+
+",
+    },
+];
+
+/// `Date` 默认（不用 chrono）时用到的辅助函数，按输出顺序排列。
+///
+/// 只依赖 std；毫秒时间戳用 f64 表示（和 JS 一样），早于 1970 年的时间是负数。
+pub(crate) const DATE_HELPERS: [Helper; 3] = [
+    Helper {
+        name: "__ark_millis",
+        deps: &["ArkDate"],
+        def: "\
+{vis}fn __ark_millis(t: ArkDate) -> f64 {
+    match t.duration_since(std::time::UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as f64,
+        Err(e) => -(e.duration().as_millis() as f64),
+    }
+}
+
+",
+    },
+    Helper {
+        name: "__ark_from_millis",
+        deps: &["ArkDate"],
+        def: "\
+{vis}fn __ark_from_millis(ms: f64) -> ArkDate {
+    let d = std::time::Duration::from_millis(ms.abs() as u64);
+    if ms < 0.0 {
+        std::time::UNIX_EPOCH - d
+    } else {
+        std::time::UNIX_EPOCH + d
+    }
+}
+
+",
+    },
+    Helper {
+        name: "__ark_iso_string",
+        deps: &["ArkDate", "__ark_millis"],
+        def: "\
+{vis}fn __ark_iso_string(t: ArkDate) -> String {
+    let ms = __ark_millis(t) as i64;
+    let (days, ms) = (ms.div_euclid(86_400_000), ms.rem_euclid(86_400_000));
+    // 公历日期换算：从 0000-03-01 起按 400 年周期计算
+    let z = days + 719_468;
+    let (era, doe) = (z.div_euclid(146_097), z.rem_euclid(146_097));
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        \"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z\",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+",
+    },
+];
+
+/// 字符串方法（见 `gen_string_method`）用到的辅助函数。下标按字符（`char`）计算，
+/// 返回的下标、长度是 i32，f64 模式下由调用处转换。
+pub(crate) const STRING_HELPERS: [Helper; 3] = [
+    Helper {
+        name: "__ark_index_of",
+        deps: &[],
+        def: "\
+{vis}fn __ark_index_of(s: &str, pat: &str) -> i32 {
+    s.find(pat).map_or(-1, |i| s[..i].chars().count() as i32)
+}
+
+",
+    },
+    Helper {
+        name: "__ark_slice",
+        deps: &[],
+        def: "\
+{vis}fn __ark_slice(s: &str, start: i32, end: Option<i32>) -> String {
+    let n = s.chars().count() as i32;
+    let at = |k: i32| if k < 0 { (n + k).max(0) } else { k.min(n) };
+    let (a, b) = (at(start), end.map_or(n, at));
+    s.chars().skip(a as usize).take((b - a).max(0) as usize).collect()
+}
+
+",
+    },
+    Helper {
+        name: "__ark_substring",
+        deps: &[],
+        def: "\
+{vis}fn __ark_substring(s: &str, start: i32, end: Option<i32>) -> String {
+    let n = s.chars().count() as i32;
+    let at = |k: i32| k.max(0).min(n);
+    let (a, b) = (at(start), end.map_or(n, at));
+    let (a, b) = (a.min(b), a.max(b));
+    s.chars().skip(a as usize).take((b - a) as usize).collect()
+}
+
+",
+    },
+];
+
+/// 只有内置 `Error` 时的异常类型（见 `gen_throw`）：变体携带异常消息，实现 `Display` 和 `std::error::Error`。
+/// 有自定义异常类时每个类一个变体，由 CodeGen 按程序生成（见 `gen_error_enum`）。
+pub(crate) const ERROR_HELPER: Helper = Helper {
+    name: "ArkError",
+    deps: &[],
+    def: "\
+#[derive(Debug, Clone, PartialEq)]
+{vis}enum ArkError {
+    Error(String),
+}
+
+impl std::fmt::Display for ArkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArkError::Error(m) => write!(f, \"Error: {m}\"),
+        }
+    }
+}
+
+impl std::error::Error for ArkError {}
+
+",
+};
+
+/// 没有配置 `CodegenOptions::ability_runtime` 时用的 Ability 运行时（见 `gen_ability`）：
+/// `UIAbility` trait 的生命周期方法默认什么都不做，`WindowStage::loadContent` 只打印页面路径。
+pub(crate) const ABILITY_HELPERS: [Helper; 4] = [
+    Helper {
+        name: "Want",
+        deps: &[],
+        def: "\
+{vis}struct Want;
+
+impl Want {
+    {vis}fn new() -> Self {
+        Want
+    }
+}
+
+",
+    },
+    Helper {
+        name: "LaunchParam",
+        deps: &[],
+        def: "\
+{vis}struct LaunchParam;
+
+impl LaunchParam {
+    {vis}fn new() -> Self {
+        LaunchParam
+    }
+}
+
+",
+    },
+    Helper {
+        name: "WindowStage",
+        deps: &[],
+        def: "\
+{vis}struct WindowStage;
+
+impl WindowStage {
+    {vis}fn new() -> Self {
+        WindowStage
+    }
+
+    {vis}fn loadContent(&mut self, path: String) {
+        println!(\"loadContent {}\", path);
+    }
+}
+
+",
+    },
+    Helper {
+        name: "UIAbility",
+        deps: &["Want", "LaunchParam", "WindowStage"],
+        def: "\
+{vis}trait UIAbility {
+    fn onCreate(&mut self, _want: &Want, _launch_param: &LaunchParam) {}
+    fn onWindowStageCreate(&mut self, _window_stage: &mut WindowStage) {}
+    fn onForeground(&mut self) {}
+    fn onBackground(&mut self) {}
+    fn onWindowStageDestroy(&mut self) {}
+    fn onDestroy(&mut self) {}
+}
+
+",
+    },
+];
+
+/// builder UI 后端（见 `ui::BuilderBackend`）的运行时：把组件记成一行行文本，最外层的组件结束时打印整棵树。
+pub(crate) const UI_HELPER: Helper = Helper {
+    name: "ArkUi",
+    deps: &[],
+    def: "\
+{vis}struct ArkUi {
+    lines: Vec<String>,
+    depth: usize,
+}
+
+impl ArkUi {
+    {vis}fn new() -> Self {
+        ArkUi { lines: Vec::new(), depth: 0 }
+    }
+
+    {vis}fn open(&mut self, name: &str, args: Vec<String>) {
+        self.lines.push(format!(\"{}{}({})\", \"  \".repeat(self.depth), name, args.join(\", \")));
+        self.depth += 1;
+    }
+
+    {vis}fn attr(&mut self, name: &str, args: Vec<String>) {
+        if let Some(line) = self.lines.last_mut() {
+            line.push_str(&format!(\".{}({})\", name, args.join(\", \")));
+        }
+    }
+
+    {vis}fn close(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            println!(\"{}\", self.lines.join(\"\\n\"));
+            self.lines.clear();
+        }
+    }
+}
+
+",
+};
@@ -0,0 +1,721 @@
+use crate::ast::{
+    AssignTarget, BlockStmt, BuilderDecl, Callee, ComponentDecl, Expr, FuncDecl, ImportClause, Param, Program, Stmt,
+    StyleDecl, UiArg, UiAttr, UiNode,
+};
+use crate::codegen::{is_global_builtin, is_resource_builtin, member_key, Intrinsics};
+use crate::diagnostics::did_you_mean;
+use crate::error::{Error, ErrorCode, NoteCode, Warning};
+use crate::span::Span;
+use std::collections::{HashMap, HashSet};
+
+/// 语义检查的入口：在 CodeGen 之前按作用域解析程序里用到的名字。
+///
+/// 目前检查：
+/// - 读取、赋值的变量名（以及 `x.f()` 里的 `x`）必须在某一层作用域里声明过，否则报 `UndeclaredVariable`，
+///   位置是包含这个名字的最内层语句/表达式。没有这一步时 `x = 1;` 会生成 Rust 代码，等到 rustc 才报一个看不懂的错。
+///   有拼写相近的名字时同样附带 `did you mean` 的补充说明。
+/// - 不能给 `const` 变量重新赋值：报 `ConstReassignment`，位置是赋值语句，补充说明（`Error::notes`）指向声明。
+/// - 同一个作用域里不能重复声明同名的变量 / 函数 / 参数：报 `DuplicateDeclaration`，位置是后一个声明，
+///   补充说明指向前一个。函数的参数和函数体最外层的声明算同一个作用域（`function f(a) { let a = 1; }` 也算重复）。
+/// - `let` / `const` 变量在声明语句之前不能使用（暂时性死区，TDZ）：报 `UsedBeforeDeclaration`，补充说明指向声明。
+///   嵌套函数、UI 事件回调里的使用不算：它们要等被调用时才执行。
+///
+/// - 被调用的函数名（`f()` 里的 `f`）必须是声明过的函数、变量（回调）、参数或内置函数（`parseInt`、`setTimeout` 等），
+///   否则报 `UnknownFunction`；有拼写相近的名字时附带一条 `did you mean `..`?` 的补充说明。
+///   `CodegenOptions::intrinsics` 里配置了的被调用者也算内置函数（见 `check_with`）。
+///
+/// 和 JS 一样，一个名字在它所在的整个代码块里都可见：内层代码块里的同名变量从块的开头起就遮蔽外层的变量。
+///
+/// 顶层语句生成在 `fn main` 里，顶层的 `let` 变量是 `main` 的局部变量：函数、组件等顶层项里使用它们报
+/// `UndeclaredVariable`，补充说明指向声明。顶层的 `const` 可以使用，CodeGen 把它们生成为模块级的 `const`（见 `shared_consts`）。
+pub fn check(program: &Program) -> Result<(), Error> {
+    check_with(program, &Intrinsics::new())
+}
+
+/// `check` 的带内置函数映射版本：`intrinsics` 里的被调用者（`foo`、`obj.f`，没有被声明遮蔽时）不需要声明就能调用。
+pub fn check_with(program: &Program, intrinsics: &Intrinsics) -> Result<(), Error> {
+    match errors_with(program, intrinsics).into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// `check` 的“报告全部错误”版本（见 `Diagnostics`）。
+///
+/// 每个顶层项（函数、namespace、组件、Ability 方法、`@Builder`、顶层语句）单独检查：
+/// 一项里遇到错误就跳过这一项剩下的部分，接着检查下一项，所以互不相关的错误一次都能报告出来。
+pub fn errors(program: &Program) -> Vec<Error> {
+    errors_with(program, &Intrinsics::new())
+}
+
+/// `errors` 的带内置函数映射版本（见 `check_with`）。
+pub fn errors_with(program: &Program, intrinsics: &Intrinsics) -> Vec<Error> {
+    resolve_program(program, intrinsics).errors
+}
+
+/// 语义检查产生的警告，按位置排序：
+/// - 声明了但从来没有被读取的 `let` / `const` 变量：`UnusedVariable`（只被赋值也算没有读取）
+/// - 从来没有被调用（或者当作回调传出去）的函数：`UnusedFunction`
+///
+/// 导出的变量 / 函数、以 `_` 开头的名字不报。程序有语义错误时返回空列表（错误由 `check` 报告）。
+pub fn warnings(program: &Program) -> Vec<Warning> {
+    warnings_with(program, &Intrinsics::new())
+}
+
+/// `warnings` 的带内置函数映射版本（见 `check_with`）。
+pub fn warnings_with(program: &Program, intrinsics: &Intrinsics) -> Vec<Warning> {
+    let r = resolve_program(program, intrinsics);
+    if !r.errors.is_empty() {
+        return Vec::new();
+    }
+    let mut warnings = r.warnings;
+    warnings.sort_by_key(|w| (w.span.start, w.code.as_str()));
+    warnings
+}
+
+/// 函数、组件等顶层项里用到的顶层 `const` 变量：CodeGen 把它们生成为模块级的 `const`，而不是 `main` 的局部变量。
+///
+/// 这些常量的初始值里用到的顶层 `const` 也算（`const TWICE = K * 2;` 里的 `K`）。
+pub fn shared_consts(program: &Program) -> HashSet<String> {
+    with_const_deps(program, resolve_program(program, &Intrinsics::new()).shared)
+}
+
+/// `shared` 加上这些顶层 `const` 的初始值里用到的顶层 `const`。
+pub(crate) fn with_const_deps(program: &Program, mut shared: HashSet<String>) -> HashSet<String> {
+    let consts: Vec<(Vec<&str>, &Expr)> = program
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::VarDecl(v) if v.is_const => Some(v),
+            _ => None,
+        })
+        .flat_map(|v| &v.decls)
+        .filter_map(|d| Some((d.pattern.names(), d.init.as_ref()?)))
+        .collect();
+    let top: HashSet<&str> = consts.iter().flat_map(|(names, _)| names.iter().copied()).collect();
+    // 倒着看一遍就够了：初始值只能使用前面声明的常量
+    for (names, init) in consts.iter().rev() {
+        if names.iter().any(|n| shared.contains(*n)) {
+            let mut used = HashSet::new();
+            idents(init, &mut used);
+            shared.extend(used.into_iter().filter(|n| top.contains(n)).map(str::to_string));
+        }
+    }
+    shared
+}
+
+/// 表达式里读取的变量名。
+fn idents<'a>(e: &'a Expr, out: &mut HashSet<&'a str>) {
+    match e {
+        Expr::Ident(name, _) => {
+            out.insert(name);
+        }
+        Expr::Unary(u) => idents(&u.expr, out),
+        Expr::Binary(b) => {
+            idents(&b.left, out);
+            idents(&b.right, out);
+        }
+        Expr::Group(inner) | Expr::Spread(inner) => idents(inner, out),
+        Expr::Member(m) => idents(&m.object, out),
+        Expr::Index(ix) => {
+            idents(&ix.object, out);
+            idents(&ix.index, out);
+        }
+        Expr::Call(c) => c.args.iter().for_each(|a| idents(a, out)),
+        Expr::New(n) => n.args.iter().for_each(|a| idents(a, out)),
+        Expr::Assign(a) => idents(&a.value, out),
+        Expr::Literal(_, _) => {}
+    }
+}
+
+/// 解析整个程序，错误记在 `Resolver::errors` 里。重复的顶层声明也只是记下错误：全局作用域保留第一个声明，接着检查。
+fn resolve_program(program: &Program, intrinsics: &Intrinsics) -> Resolver {
+    let mut r = Resolver {
+        in_items: true,
+        intrinsics: intrinsics.keys().cloned().collect(),
+        ..Resolver::default()
+    };
+    let (globals, duplicates) = scope(globals(program));
+    r.scopes.push(globals);
+    r.errors.extend(duplicates);
+    for f in program.funcs.iter().chain(&program.declare_funcs) {
+        r.item(|r| r.func(f, &[]));
+    }
+    for ns in &program.namespace_decls {
+        let members: Vec<(&str, Symbol)> = ns.funcs.iter().map(|f| (f.name.as_str(), Symbol::func(f))).collect();
+        r.item(|r| {
+            r.push(members).map_err(|e| e.at(ns.span.0))?;
+            for f in &ns.funcs {
+                r.item(|r| r.func(f, &[]));
+            }
+            r.pop();
+            Ok(())
+        });
+    }
+    for c in &program.components {
+        r.item(|r| r.component(c));
+    }
+    for a in &program.abilities {
+        for m in &a.methods {
+            r.item(|r| r.func(m, &[("this", Symbol::new(SymbolKind::This, Span::default()))]));
+        }
+    }
+    for b in &program.builders {
+        r.item(|r| r.builder(b));
+    }
+    for s in &program.styles {
+        r.item(|r| r.style(s));
+    }
+    // 顶层语句声明的变量已经在全局作用域里
+    r.in_items = false;
+    for s in &program.stmts {
+        r.item(|r| r.stmt(s));
+        // 出错的声明语句也算执行过了：后面使用这些变量不再报 `UsedBeforeDeclaration`
+        if let Stmt::VarDecl(v) = s {
+            r.declared(v.names());
+        }
+    }
+    // 别名（`import { f as g }`）、命名空间导入的成员指向的函数通过别名使用
+    for target in program.aliases.iter().chain(program.namespaces.iter().flat_map(|ns| &ns.members)) {
+        r.mark_read(&target.target);
+    }
+    r.pop();
+    r
+}
+
+/// 不需要声明就能使用的内置对象：`Math.max(..)`、`JSON.parse(..)`、`Date.now()`、`hilog.info(..)`。
+const BUILTIN_OBJECTS: [&str; 4] = ["Math", "JSON", "Date", "hilog"];
+
+/// 不需要声明就能调用的内置函数（`UnknownFunction` 的拼写建议用，判断见 `codegen::is_global_builtin`）。
+const BUILTIN_FUNCTIONS: [&str; 9] =
+    ["parseInt", "parseFloat", "Number", "String", "setTimeout", "setInterval", "assert", "$r", "$rawfile"];
+
+/// 符号表里的一个名字。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// 声明所在的位置（catch 的异常变量是整个 catch 子句的位置，顶层的类、组件等没有位置）
+    pub span: Span,
+    /// 带 `export` 的声明：没有在本文件里使用也不报警告
+    pub exported: bool,
+}
+
+impl Symbol {
+    /// 变量、参数、函数：同一个作用域里不能重名的声明。
+    fn is_binding(&self) -> bool {
+        matches!(self.kind, SymbolKind::Let | SymbolKind::Const | SymbolKind::Param | SymbolKind::Func)
+    }
+
+    fn new(kind: SymbolKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            exported: false,
+        }
+    }
+
+    fn func(f: &FuncDecl) -> Self {
+        Self {
+            exported: f.is_export,
+            ..Self::new(SymbolKind::Func, f.span.0)
+        }
+    }
+}
+
+/// 名字是怎么声明的。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Let,
+    Const,
+    /// 函数参数、catch 的异常变量
+    Param,
+    /// 函数声明（顶层、块内或 namespace 里的函数）
+    Func,
+    /// 其它顶层声明：类、组件、namespace、import 引入的名字等
+    Item,
+    /// 组件 / Ability 方法里的 `this`
+    This,
+}
+
+/// 作用域栈：最外层是全局作用域，每进入一个函数体 / 代码块压入一层。
+#[derive(Default)]
+struct Resolver {
+    scopes: Vec<Scope>,
+    /// 当前函数（组件、UI 回调）最外层作用域在 `scopes` 里的下标：更外层的变量在函数执行时已经声明过了
+    fn_start: usize,
+    /// 弹出作用域时发现的没有使用的变量 / 函数（见 `warnings`）
+    warnings: Vec<Warning>,
+    /// 各个顶层项里遇到的错误（见 `item`）
+    errors: Vec<Error>,
+    /// 正在检查顶层语句以外的顶层项（函数、组件等）：这时顶层的 `let` 变量不可见
+    in_items: bool,
+    /// 顶层项里用到的顶层 `const` 变量（见 `shared_consts`）
+    shared: HashSet<String>,
+    /// `CodegenOptions::intrinsics` 里配置了的被调用者：没有被遮蔽时和内置函数一样不需要声明
+    intrinsics: HashSet<String>,
+}
+
+#[derive(Default)]
+struct Scope {
+    symbols: HashMap<String, Symbol>,
+    /// 还没执行到声明语句的 `let` / `const` 变量
+    pending: HashSet<String>,
+    /// 被读取过的名字
+    read: HashSet<String>,
+}
+
+impl Resolver {
+    /// 压入一层作用域。同名的变量 / 参数 / 函数报 `DuplicateDeclaration`（这时不压入）。
+    fn push<'a>(&mut self, symbols: impl IntoIterator<Item = (&'a str, Symbol)>) -> Result<(), Error> {
+        let (scope, duplicates) = scope(symbols);
+        if let Some(err) = duplicates.into_iter().next() {
+            return Err(err);
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    /// 解析一个顶层项里的名字；失败时记下错误，退回到这一项之前的作用域和函数边界（`fn_start`）。
+    fn item(&mut self, check: impl FnOnce(&mut Self) -> Result<(), Error>) {
+        let (depth, fn_start) = (self.scopes.len(), self.fn_start);
+        if let Err(err) = check(self) {
+            self.scopes.truncate(depth);
+            self.fn_start = fn_start;
+            self.errors.push(err);
+        }
+    }
+
+    /// 进入一个函数（组件、UI 回调）：压入 `symbols`（参数等），在里面检查 `body`。
+    fn in_fn<'a>(
+        &mut self,
+        symbols: impl IntoIterator<Item = (&'a str, Symbol)>,
+        body: impl FnOnce(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let outer_start = std::mem::replace(&mut self.fn_start, self.scopes.len());
+        self.push(symbols)?;
+        let result = body(self);
+        self.pop();
+        self.fn_start = outer_start;
+        result
+    }
+
+    /// 执行到了声明语句：之后可以使用这些变量了。
+    fn declared(&mut self, names: Vec<&str>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            for name in names {
+                scope.pending.remove(name);
+            }
+        }
+    }
+
+    /// 弹出一层作用域，没有被读取过的变量 / 函数记一条警告。
+    fn pop(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for (name, sym) in &scope.symbols {
+            if scope.read.contains(name) || sym.exported || name.starts_with('_') {
+                continue;
+            }
+            let code = match sym.kind {
+                SymbolKind::Let | SymbolKind::Const => ErrorCode::UnusedVariable,
+                SymbolKind::Func => ErrorCode::UnusedFunction,
+                _ => continue,
+            };
+            self.warnings.push(Warning::new(code, sym.span).with_arg("name", name.clone()));
+        }
+    }
+
+    /// 记下名字被读取过（记在声明它的那一层作用域里）。
+    fn mark_read(&mut self, name: &str) {
+        let Some(i) = self.scopes.iter().rposition(|s| s.symbols.contains_key(name)) else {
+            return;
+        };
+        if i == 0 && self.in_items && self.scopes[0].symbols[name].kind == SymbolKind::Const {
+            self.shared.insert(name.to_string());
+        }
+        self.scopes[i].read.insert(name.to_string());
+    }
+
+    /// 从内到外查找名字。
+    fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|s| s.symbols.get(name))
+    }
+
+    /// 使用一个名字：必须已经声明（或者是内置对象），在当前函数里声明的 `let` / `const` 还要已经执行过声明语句。
+    /// 返回名字对应的符号（内置对象为 None）。
+    fn resolve(&self, name: &str) -> Result<Option<&Symbol>, Error> {
+        for (i, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(sym) = scope.symbols.get(name) {
+                if i == 0 && self.in_items && sym.kind == SymbolKind::Let {
+                    let err = Error::new(ErrorCode::UndeclaredVariable, Span::default()).with_arg("name", name);
+                    return Err(err.with_note("", sym.span, NoteCode::TopLevelLet));
+                }
+                if i >= self.fn_start && scope.pending.contains(name) {
+                    let err = Error::new(ErrorCode::UsedBeforeDeclaration, Span::default()).with_arg("name", name);
+                    return Err(err.with_note("", sym.span, NoteCode::DeclaredHere));
+                }
+                return Ok(Some(sym));
+            }
+        }
+        if BUILTIN_OBJECTS.contains(&name) {
+            return Ok(None);
+        }
+        let builtins = BUILTIN_OBJECTS.iter().map(|b| (b.to_string(), Span::default()));
+        let err = Error::new(ErrorCode::UndeclaredVariable, Span::default()).with_arg("name", name);
+        Err(did_you_mean(err, name, self.declared_names().chain(builtins)))
+    }
+
+    fn use_name(&mut self, name: &str) -> Result<(), Error> {
+        self.resolve(name)?;
+        self.mark_read(name);
+        Ok(())
+    }
+
+    /// 调用 `name(..)`：名字要能使用（见 `resolve`），或者是没有被遮蔽的内置函数（包括配置的 `intrinsics`）。
+    fn call_name(&mut self, name: &str) -> Result<(), Error> {
+        if self.lookup(name).is_none()
+            && (is_global_builtin(name) || is_resource_builtin(name) || self.intrinsics.contains(name))
+        {
+            return Ok(());
+        }
+        match self.resolve(name) {
+            Err(e) if e.code == ErrorCode::UndeclaredVariable => {
+                let builtins = BUILTIN_FUNCTIONS.iter().map(|b| (b.to_string(), Span::default()));
+                let err = Error::new(ErrorCode::UnknownFunction, Span::default()).with_arg("name", name);
+                Err(did_you_mean(err, name, self.declared_names().chain(builtins)))
+            }
+            Err(e) => Err(e),
+            Ok(_) => {
+                self.mark_read(name);
+                Ok(())
+            }
+        }
+    }
+
+    /// 作用域里声明过的名字及其声明位置（拼写建议的候选，见 `did_you_mean`）。
+    fn declared_names(&self) -> impl Iterator<Item = (String, Span)> + '_ {
+        self.scopes
+            .iter()
+            .flat_map(|s| &s.symbols)
+            .filter(|(_, sym)| sym.kind != SymbolKind::This)
+            .map(|(n, sym)| (n.clone(), sym.span))
+    }
+
+    /// 赋值 `name = ..`：名字必须可以使用（见 `resolve`），并且不是 `const`。
+    fn assign_name(&self, name: &str) -> Result<(), Error> {
+        match self.resolve(name)? {
+            Some(sym) if sym.kind == SymbolKind::Const => {
+                let err = Error::new(ErrorCode::ConstReassignment, Span::default()).with_arg("name", name);
+                Err(err.with_note("", sym.span, NoteCode::DeclaredConstHere))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 函数：参数、`extra`（例如方法里的 `this`）和函数体最外层的声明在同一层作用域。
+    fn func(&mut self, f: &FuncDecl, extra: &[(&str, Symbol)]) -> Result<(), Error> {
+        let mut outer = params(&f.params);
+        outer.extend(extra.iter().copied());
+        let outer_start = std::mem::replace(&mut self.fn_start, self.scopes.len());
+        let result = self.scoped_stmts(outer, &f.body.stmts);
+        self.fn_start = outer_start;
+        result.map_err(|e| e.at(f.span.0))
+    }
+
+    fn builder(&mut self, b: &BuilderDecl) -> Result<(), Error> {
+        let result = self.in_fn(params(&b.params), |r| r.ui_nodes(&b.body));
+        result.map_err(|e| e.at(b.span.0))
+    }
+
+    fn style(&mut self, s: &StyleDecl) -> Result<(), Error> {
+        let result = self.in_fn(params(&s.params), |r| r.attrs(&s.attrs));
+        result.map_err(|e| e.at(s.span.0))
+    }
+
+    fn component(&mut self, c: &ComponentDecl) -> Result<(), Error> {
+        self.in_fn([("this", Symbol::new(SymbolKind::This, Span::default()))], |r| {
+            for field in &c.fields {
+                if let Some(init) = &field.init {
+                    r.expr(init).map_err(|e| e.at(field.span.0))?;
+                }
+            }
+            for m in &c.methods {
+                r.func(m, &[])?;
+            }
+            for b in &c.builders {
+                r.builder(b)?;
+            }
+            for s in &c.styles {
+                r.style(s)?;
+            }
+            match &c.build {
+                Some(build) => r.ui_nodes(build),
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn block(&mut self, b: &BlockStmt) -> Result<(), Error> {
+        self.stmts(&b.stmts)
+    }
+
+    /// 一个代码块：先登记块里直接声明的变量和函数，再检查每条语句。
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<(), Error> {
+        self.scoped_stmts(Vec::new(), stmts)
+    }
+
+    /// 和 `stmts` 相同，`outer`（参数、catch 的异常变量）和块里的声明登记在同一层作用域。
+    fn scoped_stmts<'a>(&mut self, mut outer: Vec<(&'a str, Symbol)>, stmts: &'a [Stmt]) -> Result<(), Error> {
+        outer.extend(block_symbols(stmts));
+        self.push(outer)?;
+        let result = stmts.iter().try_for_each(|s| self.stmt(s));
+        self.pop();
+        result
+    }
+
+    fn stmt(&mut self, s: &Stmt) -> Result<(), Error> {
+        self.stmt_kind(s).map_err(|e| e.at(s.span()))
+    }
+
+    fn stmt_kind(&mut self, s: &Stmt) -> Result<(), Error> {
+        match s {
+            // 每一项的初始值可以使用前面几项：`let a = 1, b = a + 1;`
+            Stmt::VarDecl(v) => v.decls.iter().try_for_each(|d| {
+                if let Some(init) = &d.init {
+                    self.expr(init).map_err(|e| e.at(d.span.0))?;
+                }
+                self.declared(d.pattern.names());
+                Ok(())
+            }),
+            Stmt::Assign(a) => {
+                self.assign_name(&a.name)?;
+                self.expr(&a.value)
+            }
+            Stmt::ExprStmt(e) => self.expr(e),
+            Stmt::Block(b) => self.block(b),
+            Stmt::If(i) => {
+                self.expr(&i.cond)?;
+                self.branch(&i.then_branch)?;
+                match &i.else_branch {
+                    Some(e) => self.branch(e),
+                    None => Ok(()),
+                }
+            }
+            Stmt::While(w) => {
+                self.expr(&w.cond)?;
+                self.branch(&w.body)
+            }
+            Stmt::Return(r) => match &r.value {
+                Some(v) => self.expr(v),
+                None => Ok(()),
+            },
+            Stmt::FuncDecl(f) => self.func(f, &[]),
+            Stmt::Throw(t) => self.expr(&t.value),
+            Stmt::Try(t) => {
+                self.block(&t.block)?;
+                if let Some(c) = &t.catch {
+                    let param = c.param.iter().map(|p| (p.as_str(), Symbol::new(SymbolKind::Param, c.span.0)));
+                    self.scoped_stmts(param.collect(), &c.body.stmts).map_err(|e| e.at(c.span.0))?;
+                }
+                match &t.finally {
+                    Some(b) => self.block(b),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// if/while 的分支：不是代码块的单条语句（`if (c) let x = 1;`）也自成一个作用域。
+    fn branch(&mut self, s: &Stmt) -> Result<(), Error> {
+        match s {
+            Stmt::Block(b) => self.block(b),
+            other => self.stmts(std::slice::from_ref(other)),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<(), Error> {
+        self.expr_kind(e).map_err(|err| err.at(e.span()))
+    }
+
+    fn expr_kind(&mut self, e: &Expr) -> Result<(), Error> {
+        match e {
+            Expr::Literal(_, _) => Ok(()),
+            Expr::Ident(name, _) => self.use_name(name),
+            Expr::Unary(u) => self.expr(&u.expr),
+            Expr::Binary(b) => {
+                self.expr(&b.left)?;
+                self.expr(&b.right)
+            }
+            Expr::Group(inner) | Expr::Spread(inner) => self.expr(inner),
+            Expr::Call(c) => {
+                match &c.callee {
+                    Callee::Member { object, property }
+                        if self.lookup(object).is_none() && self.intrinsics.contains(&member_key(object, property)) => {}
+                    Callee::Member { object, .. } => self.use_name(object)?,
+                    Callee::Method { object: inner, .. } | Callee::Expr(inner) => self.expr(inner)?,
+                    Callee::Ident(name) => self.call_name(name)?,
+                    Callee::Console(_) | Callee::Promise(_) => {}
+                }
+                c.args.iter().try_for_each(|a| self.expr(a))
+            }
+            Expr::New(n) => n.args.iter().try_for_each(|a| self.expr(a)),
+            Expr::Assign(a) => {
+                match &a.target {
+                    AssignTarget::Ident(name) => self.assign_name(name)?,
+                    AssignTarget::Member { object, .. } => self.use_name(object)?,
+                    AssignTarget::Index { object, index } => {
+                        self.use_name(object)?;
+                        self.expr(index)?;
+                    }
+                }
+                self.expr(&a.value)
+            }
+            Expr::Member(m) => self.expr(&m.object),
+            Expr::Index(ix) => {
+                self.expr(&ix.object)?;
+                self.expr(&ix.index)
+            }
+        }
+    }
+
+    /// `build()` / `@Builder` 里的 UI：子节点列表和普通代码块一样是一层作用域。
+    fn ui_nodes(&mut self, nodes: &[UiNode]) -> Result<(), Error> {
+        self.push(block_symbols(nodes.iter().filter_map(|n| match n {
+            UiNode::Stmt(s) => Some(s),
+            _ => None,
+        })))?;
+        let result = nodes.iter().try_for_each(|n| self.ui_node(n));
+        self.pop();
+        result
+    }
+
+    fn ui_node(&mut self, node: &UiNode) -> Result<(), Error> {
+        self.ui_node_kind(node).map_err(|e| e.at(node.span()))
+    }
+
+    fn ui_node_kind(&mut self, node: &UiNode) -> Result<(), Error> {
+        match node {
+            UiNode::Element(e) => {
+                self.ui_args(&e.args)?;
+                self.ui_nodes(&e.children)?;
+                self.attrs(&e.attrs)
+            }
+            UiNode::If(i) => {
+                self.expr(&i.cond)?;
+                self.ui_nodes(&i.then_branch)?;
+                self.ui_nodes(&i.else_branch)
+            }
+            UiNode::Stmt(s) => self.stmt(s),
+        }
+    }
+
+    fn attrs(&mut self, attrs: &[UiAttr]) -> Result<(), Error> {
+        attrs.iter().try_for_each(|a| self.ui_args(&a.args).map_err(|e| e.at(a.span.0)))
+    }
+
+    fn ui_args(&mut self, args: &[UiArg]) -> Result<(), Error> {
+        for arg in args {
+            match arg {
+                UiArg::Expr(e) => self.ui_expr(e)?,
+                UiArg::Object(props) => props.iter().try_for_each(|(_, e)| self.ui_expr(e))?,
+                UiArg::Handler(h) => {
+                    let result = self.in_fn(params(&h.params), |r| r.ui_nodes(&h.body));
+                    result.map_err(|e| e.at(h.span.0))?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// UI 组件的实参：还可以是 ArkUI 的枚举值 `FontWeight.Bold`（首字母大写、没有被同名变量遮蔽的名字的成员）。
+    fn ui_expr(&mut self, e: &Expr) -> Result<(), Error> {
+        if let Expr::Member(m) = e {
+            if let Expr::Ident(name, _) = &*m.object {
+                if name.starts_with(|c: char| c.is_ascii_uppercase()) && self.lookup(name).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        self.expr(e)
+    }
+}
+
+/// 全局作用域：顶层的函数、类、组件、namespace、import 引入的名字，以及顶层语句声明的变量。
+fn globals(program: &Program) -> Vec<(&str, Symbol)> {
+    let item = Symbol::new(SymbolKind::Item, Span::default());
+    let mut out: Vec<(&str, Symbol)> = program
+        .funcs
+        .iter()
+        .chain(&program.declare_funcs)
+        .map(|f| (f.name.as_str(), Symbol::func(f)))
+        .collect();
+    out.extend(program.classes.iter().map(|c| (c.name.as_str(), item)));
+    out.extend(program.abilities.iter().map(|a| (a.name.as_str(), item)));
+    out.extend(program.namespace_decls.iter().map(|ns| (ns.name.as_str(), item)));
+    out.extend(program.components.iter().map(|c| (c.name.as_str(), item)));
+    out.extend(program.builders.iter().map(|b| (b.name.as_str(), item)));
+    out.extend(program.styles.iter().map(|s| (s.name.as_str(), item)));
+    out.extend(program.aliases.iter().map(|a| (a.name.as_str(), item)));
+    out.extend(program.namespaces.iter().map(|ns| (ns.name.as_str(), item)));
+    for i in &program.system_imports {
+        out.extend(i.local_names().into_iter().map(|name| (name, item)));
+    }
+    for i in &program.imports {
+        match &i.clause {
+            ImportClause::Named(specs) => out.extend(specs.iter().map(|s| (s.local_name(), item))),
+            ImportClause::Namespace(name) | ImportClause::Default(name) => out.push((name, item)),
+        }
+    }
+    out.extend(block_symbols(&program.stmts));
+    out
+}
+
+/// 由 `symbols` 组成的一层作用域，以及其中重复声明的变量 / 参数 / 函数（`DuplicateDeclaration`，
+/// 补充说明指向第一个声明；作用域里保留第一个声明）。
+fn scope<'a>(symbols: impl IntoIterator<Item = (&'a str, Symbol)>) -> (Scope, Vec<Error>) {
+    let mut scope = Scope::default();
+    let mut duplicates = Vec::new();
+    for (name, sym) in symbols {
+        if let Some(first) = scope.symbols.get(name) {
+            if first.is_binding() && sym.is_binding() {
+                let err = Error::new(ErrorCode::DuplicateDeclaration, sym.span).with_arg("name", name);
+                duplicates.push(err.with_note("", first.span, NoteCode::FirstDeclaredHere));
+                continue;
+            }
+        }
+        if matches!(sym.kind, SymbolKind::Let | SymbolKind::Const) {
+            scope.pending.insert(name.to_string());
+        }
+        scope.symbols.insert(name.to_string(), sym);
+    }
+    (scope, duplicates)
+}
+
+/// 参数组成的作用域。
+fn params(params: &[Param]) -> Vec<(&str, Symbol)> {
+    params
+        .iter()
+        .map(|p| (p.name.as_str(), Symbol::new(SymbolKind::Param, p.span.0)))
+        .collect()
+}
+
+/// 代码块里直接声明的变量和函数（不包括嵌套代码块里的）。
+fn block_symbols<'a>(stmts: impl IntoIterator<Item = &'a Stmt>) -> Vec<(&'a str, Symbol)> {
+    let mut out = Vec::new();
+    for s in stmts {
+        match s {
+            Stmt::VarDecl(v) => {
+                let kind = if v.is_const { SymbolKind::Const } else { SymbolKind::Let };
+                let sym = Symbol {
+                    exported: v.is_export,
+                    ..Symbol::new(kind, v.span.0)
+                };
+                out.extend(v.names().into_iter().map(|name| (name, sym)));
+            }
+            Stmt::FuncDecl(f) => out.push((f.name.as_str(), Symbol::func(f))),
+            _ => {}
+        }
+    }
+    out
+}
@@ -0,0 +1,141 @@
+use crate::span::Span;
+
+/// 生成的 Rust 代码和 ArkTS 源码之间的位置映射（CLI 的 `--source-map`），见 `codegen::generate_mapped`。
+///
+/// 每条语句、每个函数生成的第一行代码记一项 `Mapping`，可以双向查找：
+/// - `original`：生成代码的某一行（rustc 报错的位置）来自哪段源码
+/// - `generated`：源码的某个位置生成到了哪一行
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// 源文件名；`Mapping::source` 是这里的下标
+    pub sources: Vec<String>,
+    /// 按生成代码的行号排序
+    pub mappings: Vec<Mapping>,
+}
+
+/// 源码映射里的一项：生成代码的 `line:col`（从 1 开始，`col` 是这一行第一个非空白字符）对应源码里的 `original`。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    pub line: usize,
+    pub col: usize,
+    /// 源文件在 `SourceMap::sources` 里的下标；多个文件合并成一个输出文件时不知道来自哪个文件，为 None
+    pub source: Option<usize>,
+    pub original: Span,
+}
+
+impl SourceMap {
+    /// 生成代码第 `line` 行来自哪段源码：这一行或者前面最近的一项映射。
+    pub fn original(&self, line: usize) -> Option<&Mapping> {
+        self.mappings.iter().take_while(|m| m.line <= line).last()
+    }
+
+    /// 源码 `line:col` 位置生成到了哪里：包含这个位置的源码区间最小的一项映射。
+    pub fn generated(&self, line: usize, col: usize) -> Option<&Mapping> {
+        let pos = (line, col);
+        self.mappings
+            .iter()
+            .filter(|m| (m.original.start_line, m.original.start_col) <= pos && pos < (m.original.end_line, m.original.end_col))
+            .min_by_key(|m| m.original.end - m.original.start)
+    }
+
+    /// `Mapping::source` 对应的文件名。
+    pub fn source(&self, mapping: &Mapping) -> Option<&str> {
+        mapping.source.and_then(|i| self.sources.get(i)).map(String::as_str)
+    }
+
+    /// 写成 JSON（CLI 写到输出文件旁边的 `<file>.map.json`），`file` 是生成的 Rust 文件名：
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "file": "main.rs",
+    ///   "sources": ["main.ets"],
+    ///   "mappings": [
+    ///     {"line": 2, "col": 5, "source": 0, "original": {"start": 0, "end": 10, "startLine": 1, "startCol": 1, "endLine": 1, "endCol": 11}}
+    ///   ]
+    /// }
+    /// ```
+    pub fn to_json(&self, file: &str) -> String {
+        let sources: Vec<String> = self.sources.iter().map(|s| json_string(s)).collect();
+        let mappings: Vec<String> = self
+            .mappings
+            .iter()
+            .map(|m| {
+                let o = &m.original;
+                format!(
+                    "    {{\"line\": {}, \"col\": {}, \"source\": {}, \"original\": {{\"start\": {}, \"end\": {}, \"startLine\": {}, \"startCol\": {}, \"endLine\": {}, \"endCol\": {}}}}}",
+                    m.line,
+                    m.col,
+                    m.source.map_or("null".to_string(), |i| i.to_string()),
+                    o.start,
+                    o.end,
+                    o.start_line,
+                    o.start_col,
+                    o.end_line,
+                    o.end_col
+                )
+            })
+            .collect();
+        let mappings = match mappings.is_empty() {
+            true => "[]".to_string(),
+            false => format!("[\n{}\n  ]", mappings.join(",\n")),
+        };
+        format!(
+            "{{\n  \"version\": 1,\n  \"file\": {},\n  \"sources\": [{}],\n  \"mappings\": {mappings}\n}}\n",
+            json_string(file),
+            sources.join(", ")
+        )
+    }
+}
+
+/// 生成代码时记在语句前面的位置标记行，`extract` 把它们换成映射。`index` 是 `extract` 的 `spans` 里的下标。
+pub(crate) fn marker(index: usize) -> String {
+    format!("{MARKER}{index}")
+}
+
+const MARKER: &str = "//@ark-map:";
+
+/// 删掉 `code` 里的位置标记行（见 `marker`），返回删掉之后的代码和源码映射。
+///
+/// 标记对应下一行代码；连着几个标记时（语句没有生成代码）只用最后一个。
+pub(crate) fn extract(code: &str, spans: &[(Span, Option<String>)]) -> (String, SourceMap) {
+    let mut out = String::with_capacity(code.len());
+    let mut map = SourceMap::default();
+    let mut pending = None;
+    let mut line = 0;
+    for text in code.split_inclusive('\n') {
+        if let Some(index) = text.trim().strip_prefix(MARKER).and_then(|i| i.parse::<usize>().ok()) {
+            pending = spans.get(index);
+            continue;
+        }
+        line += 1;
+        out.push_str(text);
+        let Some((original, file)) = pending.take() else { continue };
+        let source = file.as_ref().map(|file| match map.sources.iter().position(|s| s == file) {
+            Some(i) => i,
+            None => {
+                map.sources.push(file.clone());
+                map.sources.len() - 1
+            }
+        });
+        let col = text.chars().take_while(|c| c.is_whitespace()).count() + 1;
+        map.mappings.push(Mapping { line, col, source, original: *original });
+    }
+    (out, map)
+}
+
+/// JSON 字符串字面量。
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
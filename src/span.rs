@@ -1,7 +1,10 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 /// 源码中的一个“区间位置”。
 ///
 /// - `start/end`：byte offset（按 UTF-8 字节计数），更适合做切片/定位。
 /// - `*_line/*_col`：行列号（从 1 开始），更适合给人看的报错信息。
+/// - `file`：所在源文件的编号（见 `module::SourceFiles`），0 表示没有登记的源码（例如单份源码的编译）。
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Span {
     /// 起始 byte offset（包含）
@@ -16,6 +19,8 @@ pub struct Span {
     pub end_line: usize,
     /// 结束列号（从 1 开始）
     pub end_col: usize,
+    /// 源文件编号
+    pub file: u32,
 }
 
 impl Default for Span {
@@ -27,6 +32,7 @@ impl Default for Span {
             start_col: 1,
             end_line: 1,
             end_col: 1,
+            file: 0,
         }
     }
 }
@@ -57,6 +63,58 @@ impl Span {
             start_col,
             end_line,
             end_col,
+            file: 0,
+        }
+    }
+
+    /// 从 `self` 的开头到 `end` 的结尾。
+    pub fn to(self, end: Span) -> Span {
+        Span {
+            end: end.end,
+            end_line: end.end_line,
+            end_col: end.end_col,
+            ..self
         }
     }
 }
+
+/// AST 节点的位置（见 `ast::Program` 的说明）和编号。
+///
+/// 和 `Span` 的区别只在比较上：AST 节点按结构比较，不管写在源码的哪个位置，
+/// 所以任意两个 `NodeSpan` 都相等。这样手写的 AST（`NodeSpan::default()`）
+/// 可以直接和解析出来的 AST 比较，`module::link` 之类按结构去重的地方也不受影响。
+#[derive(Clone, Copy, Debug)]
+pub struct NodeSpan(pub Span, pub NodeId);
+
+/// AST 节点的编号：每次创建 `NodeSpan`（解析出来的、手写的）都分配一个新的编号，克隆的节点保留原来的编号。
+///
+/// 类型检查的结果按它登记（见 `typeck::Types`），所以对克隆出来的程序（`rename` 之后的）一样有效。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(u64);
+
+impl NodeId {
+    fn fresh() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        NodeId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for NodeSpan {
+    fn default() -> Self {
+        NodeSpan(Span::default(), NodeId::fresh())
+    }
+}
+
+impl PartialEq for NodeSpan {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for NodeSpan {}
+
+impl From<Span> for NodeSpan {
+    fn from(span: Span) -> Self {
+        NodeSpan(span, NodeId::fresh())
+    }
+}
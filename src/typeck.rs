@@ -0,0 +1,879 @@
+use crate::ast::{
+    AssignTarget, BinaryExpr, BinaryOp, Callee, CallExpr, ComponentDecl, Declarator, Expr, FieldDecl, FuncDecl, FuncTypeAnn, Literal, Pattern,
+    Program, PromiseFn, Stmt, TypeAnn, UiArg, UiElement, UiNode, UnaryOp,
+};
+use crate::codegen::{
+    effective_ret_type, is_conversion_builtin, is_null_literal, is_resource_builtin, member_key, null_check, param_type,
+    string_method, strip_group, CodegenOptions, NumberMode,
+};
+use crate::error::{Error, ErrorCode, NoteCode, Warning};
+use crate::span::{NodeId, Span};
+use std::collections::{HashMap, HashSet};
+
+/// 类型检查：在 CodeGen 之前推断表达式的类型，检查它们用得对不对（在 `sema::check` 之后运行，名字都已经解析过）。
+///
+/// 检查的地方：
+/// - 变量声明：初始值要能赋给类型标注 `let x: number = 'a';`
+/// - 赋值：值要能赋给变量（没有类型标注的变量取初始值的类型）、数组元素、组件字段
+/// - if/while 的条件要是 boolean，否则报 `ConditionMustBeBool`（`truthy_conditions` 为 true 时改成 `TruthyCondition` 警告，
+///   见 `CodegenOptions::truthy_conditions`、`warnings`）
+/// - return 的值要能赋给函数的返回类型标注
+/// - 运算符的操作数：`-` `*` `/` `%` 和取负只能用于 number，`+` 是 number 相加或者有一边是字符串，
+///   比较两边类型要相同，`&&` `||` `!` 只能用于 boolean
+///
+/// 不对的地方报 `TypeMismatch`，位置是包含它的最内层语句/表达式。
+///
+/// 返回类型不是 void 的函数有执行到末尾的路径时报 `MissingReturn`，位置是函数声明（见 `always_exits`）。
+///
+/// 调用函数声明（顶层函数、块内函数、namespace 函数、组件方法）时实参个数不对报 `WrongArgCount`，
+/// 位置是调用表达式，附带一条指向函数声明的 note；实参的类型和参数对不上报 `ArgTypeMismatch`（见 `check_arg_types`）。
+///
+/// 只在两边的类型都推断得出来时才检查：推断不出来的（`null`、`JSON.parse(..)`、解构出来的变量等）一律放行，
+/// 交给 CodeGen / rustc。可空变量在 `if (x != null)` 这样的分支里收窄成里面的类型（和 CodeGen 的规则相同，见 `codegen::null_check`）。
+///
+/// 检查通过时返回推断出来的各个表达式的类型（见 `Types`），CodeGen 按它决定怎么生成代码。
+pub fn check(program: &Program, truthy_conditions: bool) -> Result<Types, Error> {
+    check_with(program, &CodegenOptions { truthy_conditions, ..CodegenOptions::default() })
+}
+
+/// 按 CodeGen 的选项检查：`truthy_conditions` 见 `check`，数字模式和 `js_division` 决定哪些表达式是小数（见 `Types::is_float`）。
+pub fn check_with(program: &Program, options: &CodegenOptions) -> Result<Types, Error> {
+    let (types, errors, _) = run(program, options);
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(types),
+    }
+}
+
+/// `check` 的“报告全部错误”版本（见 `Diagnostics`）：每条顶层语句、每个函数（组件、`@Builder`）单独检查，
+/// 一项里遇到错误就跳过这一项剩下的部分，接着检查下一项。
+pub fn errors(program: &Program, truthy_conditions: bool) -> Vec<Error> {
+    run(program, &CodegenOptions { truthy_conditions, ..CodegenOptions::default() }).1
+}
+
+/// 类型检查的警告：`truthy_conditions` 时不是 boolean 的 if/while 条件（`TruthyCondition`），按位置排序。
+pub fn warnings(program: &Program, truthy_conditions: bool) -> Vec<Warning> {
+    run(program, &CodegenOptions { truthy_conditions, ..CodegenOptions::default() }).2
+}
+
+/// 同时返回推断出来的类型、全部错误和警告（`check_program` 用：lint 规则要查类型）。
+pub(crate) fn run(program: &Program, options: &CodegenOptions) -> (Types, Vec<Error>, Vec<Warning>) {
+    let mut c = Checker {
+        scopes: vec![globals(program)],
+        ret: None,
+        truthy_conditions: options.truthy_conditions,
+        float_numbers: options.number_mode != NumberMode::F64,
+        js_division: options.js_division,
+        types: Types::default(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+    // 先走一遍顶层语句（变量登记在全局作用域里），函数里用到的顶层变量才有类型
+    for s in &program.stmts {
+        c.item(|c| c.stmt(s));
+    }
+    for f in program.funcs.iter().chain(program.namespace_decls.iter().flat_map(|ns| &ns.funcs)) {
+        c.item(|c| c.func(f));
+    }
+    for comp in &program.components {
+        c.item(|c| c.component(comp));
+    }
+    for a in &program.abilities {
+        for m in &a.methods {
+            // 生命周期方法的参数是运行时的对象，没有类型（见 `codegen::lifecycle_params`）
+            let params = m.params.iter().map(|p| (p.name.clone(), Binding::var(None))).collect();
+            c.item(|c| c.func_in(m, params));
+        }
+    }
+    for b in &program.builders {
+        c.item(|c| {
+            c.scopes.push(params_scope(&b.params));
+            c.ui_nodes(&b.body)
+        });
+    }
+    c.warnings.sort_by_key(|w| w.span.start);
+    (c.types, c.errors, c.warnings)
+}
+
+/// 类型检查的结果：表达式节点 -> 推断出来的类型（推断不出来的表达式不登记）。
+///
+/// 按节点的编号登记（见 `NodeId`）：克隆出来的 `Program`（`rename` 之后的）里的节点一样查得到，
+/// CodeGen 临时构造的表达式查不到。
+#[derive(Clone, Debug, Default)]
+pub struct Types {
+    exprs: HashMap<NodeId, TypeAnn>,
+    floats: HashSet<NodeId>,
+}
+
+impl Types {
+    /// 表达式在它所在位置的类型：被 `x != null` 收窄的可空变量是里面的类型。展开 `...xs` 不是一个值，没有类型。
+    pub fn get(&self, e: &Expr) -> Option<&TypeAnn> {
+        match e {
+            Expr::Spread(_) => None,
+            e => self.exprs.get(&e.id()),
+        }
+    }
+
+    /// i32 数字模式下结果是小数的 number 表达式：`Math.random()`、`Date.now()`、`d.getTime()`、`parseFloat(s)`、
+    /// `CodegenOptions::js_division` 下的除法，和经过四则运算、没有类型标注的变量传下来的（CodeGen 用 f64 表示它们）。
+    pub fn is_float(&self, e: &Expr) -> bool {
+        !matches!(e, Expr::Spread(_)) && self.floats.contains(&e.id())
+    }
+}
+
+/// 作用域里的一个名字。
+#[derive(Clone, Debug, Default)]
+struct Binding<'a> {
+    /// 名字的类型（推断不出来时为 None），函数的类型是 `TypeAnn::Function`
+    ty: Option<TypeAnn>,
+    /// 名字是一个函数声明时指向它，用来检查调用的实参个数（见 `check_arity`）
+    func: Option<&'a FuncDecl>,
+    /// 可空变量在这里已经被判空收窄（见 `narrowed`）
+    narrowed: bool,
+    /// 没有类型标注、初始值是小数的变量（见 `Types::is_float`）
+    float: bool,
+}
+
+impl<'a> Binding<'a> {
+    fn var(ty: Option<TypeAnn>) -> Self {
+        Binding { ty, ..Binding::default() }
+    }
+
+    fn func(f: &'a FuncDecl) -> Self {
+        Binding {
+            ty: Some(func_type(f)),
+            func: Some(f),
+            ..Binding::default()
+        }
+    }
+}
+
+type Scope<'a> = HashMap<String, Binding<'a>>;
+
+struct Checker<'a> {
+    /// 作用域栈。namespace 成员、组件的字段和方法用 `util.f` / `this.x` 这样的名字登记（见 `codegen::member_key`）。
+    scopes: Vec<Scope<'a>>,
+    /// 正在检查的函数的返回类型标注（没有标注、顶层语句为 None）
+    ret: Option<TypeAnn>,
+    truthy_conditions: bool,
+    /// 不是 f64 数字模式：要区分整数和小数（见 `Types::is_float`）
+    float_numbers: bool,
+    js_division: bool,
+    types: Types,
+    /// 各个顶层项里遇到的错误（见 `item`）
+    errors: Vec<Error>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Checker<'a> {
+    fn lookup(&self, name: &str) -> Option<&Binding<'a>> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    /// 名字的类型（声明的类型，不考虑收窄）。
+    fn name_type(&self, name: &str) -> Option<TypeAnn> {
+        self.lookup(name).and_then(|b| b.ty.clone())
+    }
+
+    fn declare(&mut self, name: &str, binding: Binding<'a>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    /// 没有被同名的变量 / namespace 遮蔽的内置对象 `builtin`（`Math`、`JSON`、`Date`）。
+    fn is_builtin(&self, name: &str, builtin: &str) -> bool {
+        name == builtin && self.lookup(name).is_none()
+    }
+
+    /// 推导一个顶层项的类型；不论成败都丢掉它压入的作用域、清掉当前函数的返回类型，错误记下后继续下一项。
+    fn item(&mut self, check: impl FnOnce(&mut Self) -> Result<(), Error>) {
+        let depth = self.scopes.len();
+        if let Err(err) = check(self) {
+            self.errors.push(err);
+        }
+        self.scopes.truncate(depth);
+        self.ret = None;
+    }
+
+    fn func(&mut self, f: &'a FuncDecl) -> Result<(), Error> {
+        self.func_in(f, params_scope(&f.params))
+    }
+
+    /// 检查函数体，`params` 是参数的作用域。
+    fn func_in(&mut self, f: &'a FuncDecl, params: Scope<'a>) -> Result<(), Error> {
+        let outer_ret = std::mem::replace(&mut self.ret, f.ret_type.clone());
+        self.scopes.push(params);
+        let result = self.stmts(&f.body.stmts);
+        self.scopes.pop();
+        self.ret = outer_ret;
+        result.map_err(|e| e.at(f.span.0))?;
+        if effective_ret_type(f) != TypeAnn::Void && !always_exits(&f.body.stmts) {
+            return Err(Error::new(ErrorCode::MissingReturn, f.span.0));
+        }
+        Ok(())
+    }
+
+    /// 组件字段的类型：类型标注，没有时是初始值的类型；初始值要能赋给类型标注。
+    fn field_type(&mut self, field: &'a FieldDecl) -> Result<Option<TypeAnn>, Error> {
+        Ok(match (&field.ty, &field.init) {
+            (Some(t), Some(init)) => {
+                let value = self.expr(init)?;
+                expect_assignable(t, value.as_ref())?;
+                Some(t.clone())
+            }
+            (Some(t), None) => Some(t.clone()),
+            (None, Some(init)) => self.expr(init)?,
+            (None, None) => None,
+        })
+    }
+
+    fn component(&mut self, c: &'a ComponentDecl) -> Result<(), Error> {
+        let mut scope = HashMap::new();
+        for field in &c.fields {
+            let ty = self.field_type(field).map_err(|e| e.at(field.span.0))?;
+            scope.insert(member_key("this", &field.name), Binding::var(ty));
+        }
+        for m in &c.methods {
+            scope.insert(member_key("this", &m.name), Binding::func(m));
+        }
+        self.scopes.push(scope);
+        let result = self.component_body(c);
+        self.scopes.pop();
+        result
+    }
+
+    fn component_body(&mut self, c: &'a ComponentDecl) -> Result<(), Error> {
+        for m in &c.methods {
+            self.func(m)?;
+        }
+        for b in &c.builders {
+            self.scopes.push(params_scope(&b.params));
+            self.ui_nodes(&b.body)?;
+            self.scopes.pop();
+        }
+        match &c.build {
+            Some(build) => self.ui_nodes(build),
+            None => Ok(()),
+        }
+    }
+
+    /// 一个代码块：块里的函数声明提升到块的开头。
+    fn stmts(&mut self, stmts: &'a [Stmt]) -> Result<(), Error> {
+        let funcs = stmts.iter().filter_map(|s| match s {
+            Stmt::FuncDecl(f) => Some((f.name.clone(), Binding::func(f))),
+            _ => None,
+        });
+        self.scopes.push(funcs.collect());
+        let result = stmts.iter().try_for_each(|s| self.stmt(s));
+        self.scopes.pop();
+        result
+    }
+
+    fn stmt(&mut self, s: &'a Stmt) -> Result<(), Error> {
+        self.stmt_kind(s).map_err(|e| e.at(s.span()))
+    }
+
+    /// 一个声明项：初始值要能赋给类型标注，然后登记声明的变量。
+    fn declarator(&mut self, d: &'a Declarator) -> Result<(), Error> {
+        let value = match &d.init {
+            Some(init) => self.expr(init)?,
+            None => None,
+        };
+        if let Some(t) = &d.ty {
+            expect_assignable(t, value.as_ref())?;
+        }
+        match &d.pattern {
+            Pattern::Ident(name) => {
+                let float = d.ty.is_none() && d.init.as_ref().is_some_and(|e| self.types.is_float(e));
+                let ty = d.ty.clone().or(value);
+                self.declare(name, Binding { float, ..Binding::var(ty) });
+            }
+            pattern => pattern.names().into_iter().for_each(|name| self.declare(name, Binding::var(None))),
+        }
+        Ok(())
+    }
+
+    fn stmt_kind(&mut self, s: &'a Stmt) -> Result<(), Error> {
+        match s {
+            Stmt::VarDecl(v) => v.decls.iter().try_for_each(|d| self.declarator(d).map_err(|e| e.at(d.span.0))),
+            Stmt::Assign(a) => {
+                let value = self.expr(&a.value)?;
+                match self.name_type(&a.name) {
+                    Some(t) => expect_assignable(&t, value.as_ref()),
+                    None => Ok(()),
+                }
+            }
+            Stmt::ExprStmt(e) => self.expr(e).map(|_| ()),
+            Stmt::Block(b) => self.stmts(&b.stmts),
+            Stmt::If(i) => {
+                self.condition(&i.cond, i.span.0)?;
+                let check = self.null_check(&i.cond);
+                let then_narrowed = check.as_ref().filter(|c| c.1).map(|c| c.0.as_str());
+                let else_narrowed = check.as_ref().filter(|c| !c.1).map(|c| c.0.as_str());
+                self.narrowed(then_narrowed, |c| c.branch(&i.then_branch))?;
+                match &i.else_branch {
+                    Some(e) => self.narrowed(else_narrowed, |c| c.branch(e)),
+                    None => Ok(()),
+                }
+            }
+            Stmt::While(w) => {
+                self.condition(&w.cond, w.span.0)?;
+                let check = self.null_check(&w.cond);
+                let narrowed = check.as_ref().filter(|c| c.1).map(|c| c.0.as_str());
+                self.narrowed(narrowed, |c| c.branch(&w.body))
+            }
+            Stmt::Return(r) => {
+                let value = match &r.value {
+                    Some(v) => self.expr(v)?,
+                    None => None,
+                };
+                match &self.ret {
+                    // void 函数里 `return x;` 的值会被丢掉（见 `codegen::gen_return`）
+                    Some(TypeAnn::Void) | None => Ok(()),
+                    Some(t) => expect_assignable(t, value.as_ref()),
+                }
+            }
+            Stmt::FuncDecl(f) => self.func(f),
+            Stmt::Throw(t) => self.expr(&t.value).map(|_| ()),
+            Stmt::Try(t) => {
+                self.stmts(&t.block.stmts)?;
+                if let Some(c) = &t.catch {
+                    // 捕获的异常是它的消息（见 `codegen::gen_catch`）
+                    let param = c.param.iter().map(|p| (p.clone(), Binding::var(Some(TypeAnn::String))));
+                    self.scopes.push(param.collect());
+                    let result = self.stmts(&c.body.stmts);
+                    self.scopes.pop();
+                    result?;
+                }
+                match &t.finally {
+                    Some(b) => self.stmts(&b.stmts),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// 判空的条件：`(变量名, 条件为真时非空)`，规则见 `codegen::null_check`。
+    fn null_check(&self, cond: &Expr) -> Option<(String, bool)> {
+        null_check(cond, self.truthy_conditions, |name| {
+            matches!(self.name_type(name), Some(TypeAnn::Nullable(_, _)))
+        })
+    }
+
+    /// 在 `name` 被收窄成非空的作用域里检查一个分支（`name` 为 None 时就是普通的分支）。
+    fn narrowed(&mut self, name: Option<&str>, check: impl FnOnce(&mut Self) -> Result<(), Error>) -> Result<(), Error> {
+        let Some(name) = name else {
+            return check(self);
+        };
+        let binding = Binding {
+            narrowed: true,
+            ..Binding::var(self.name_type(name))
+        };
+        self.scopes.push(HashMap::from([(name.to_string(), binding)]));
+        let result = check(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn branch(&mut self, s: &'a Stmt) -> Result<(), Error> {
+        match s {
+            Stmt::Block(b) => self.stmts(&b.stmts),
+            other => self.stmts(std::slice::from_ref(other)),
+        }
+    }
+
+    /// if/while 的条件要是 boolean：`null`、可空变量（按声明的类型）和推断出来不是 boolean 的条件报 `ConditionMustBeBool`，
+    /// `truthy_conditions` 时改成记一个 `TruthyCondition` 警告。推断不出类型的条件放行。
+    ///
+    /// `truthy_conditions` 时 `!`、`&&`、`||` 的操作数同样按 truthy 规则转换（见 `codegen::gen_condition`），
+    /// 每个被转换的操作数各记一个警告：`while (s && n)` 里的 `s` 和 `n`。
+    ///
+    /// 条件没有位置（手写的 AST）时报告在 `at`（整条 if/while 语句）。
+    fn condition(&mut self, cond: &'a Expr, at: Span) -> Result<(), Error> {
+        self.expr(cond)?;
+        self.truthy_operand(cond, at)
+    }
+
+    /// `condition` 的递归部分：子表达式的类型已经推断过了。
+    fn truthy_operand(&mut self, cond: &Expr, at: Span) -> Result<(), Error> {
+        match strip_group(cond) {
+            Expr::Unary(u) if self.truthy_conditions && u.op == UnaryOp::Not => return self.truthy_operand(&u.expr, at),
+            Expr::Binary(b) if self.truthy_conditions && matches!(b.op, BinaryOp::AndAnd | BinaryOp::OrOr) => {
+                self.truthy_operand(&b.left, at)?;
+                return self.truthy_operand(&b.right, at);
+            }
+            _ => {}
+        }
+        let is_bool = match strip_group(cond) {
+            Expr::Literal(Literal::Null | Literal::Undefined, _) => false,
+            Expr::Ident(name, _) => self.name_type(name).is_none_or(|t| t == TypeAnn::Boolean),
+            _ => self.types.get(cond).is_none_or(|t| *t == TypeAnn::Boolean),
+        };
+        if is_bool {
+            return Ok(());
+        }
+        let span = match cond.span() {
+            span if span == Span::default() => at,
+            span => span,
+        };
+        if self.truthy_conditions {
+            self.warnings.push(Warning::new(ErrorCode::TruthyCondition, span));
+            return Ok(());
+        }
+        Err(Error::new(ErrorCode::ConditionMustBeBool, span))
+    }
+
+    /// 推断表达式的类型（推断不出来时为 None），同时检查子表达式。
+    fn expr(&mut self, e: &'a Expr) -> Result<Option<TypeAnn>, Error> {
+        let ty = self.expr_kind(e).map_err(|err| err.at(e.span()))?;
+        if ty == Some(TypeAnn::Number) && self.is_float(e) {
+            self.types.floats.insert(e.id());
+        }
+        if let Some(t) = &ty {
+            self.types.exprs.insert(e.id(), t.clone());
+        }
+        Ok(ty)
+    }
+
+    /// number 表达式在 i32 数字模式下是不是小数（见 `Types::is_float`），子表达式已经检查过。
+    fn is_float(&self, e: &Expr) -> bool {
+        if !self.float_numbers {
+            return false;
+        }
+        let float = |e: &Expr| self.types.is_float(e);
+        match e {
+            Expr::Ident(name, _) => self.lookup(name).is_some_and(|b| b.float),
+            Expr::Call(call) => match &call.callee {
+                Callee::Member { object, property } => {
+                    (property == "random" && self.is_builtin(object, "Math"))
+                        || (property == "now" && self.is_builtin(object, "Date"))
+                        || (property == "getTime" && self.name_type(object) == Some(TypeAnn::Date))
+                }
+                Callee::Method { object, method } => {
+                    method == "getTime" && self.types.get(object) == Some(&TypeAnn::Date)
+                }
+                Callee::Ident(name) if self.lookup(name).is_none() && is_conversion_builtin(name) => {
+                    name == "parseFloat" || (name == "Number" && call.args.first().is_some_and(float))
+                }
+                _ => false,
+            },
+            Expr::Group(inner) => float(inner),
+            Expr::Unary(u) => u.op == UnaryOp::Neg && float(&u.expr),
+            Expr::Binary(b) if b.op == BinaryOp::Div && self.js_division => true,
+            Expr::Binary(b) => {
+                matches!(b.op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod)
+                    && (float(&b.left) || float(&b.right))
+            }
+            _ => false,
+        }
+    }
+
+    fn expr_kind(&mut self, e: &'a Expr) -> Result<Option<TypeAnn>, Error> {
+        let ty = match e {
+            Expr::Literal(Literal::Number(_) | Literal::Float(_), _) => Some(TypeAnn::Number),
+            Expr::Literal(Literal::String(_), _) => Some(TypeAnn::String),
+            Expr::Literal(Literal::Bool(_), _) => Some(TypeAnn::Boolean),
+            Expr::Literal(Literal::Null | Literal::Undefined, _) => None,
+            Expr::Ident(name, _) => match self.lookup(name) {
+                Some(Binding {
+                    ty: Some(TypeAnn::Nullable(inner, _)),
+                    narrowed: true,
+                    ..
+                }) => Some(inner.as_ref().clone()),
+                other => other.and_then(|b| b.ty.clone()),
+            },
+            Expr::Group(inner) => self.expr(inner)?,
+            Expr::Spread(inner) => {
+                self.expr(inner)?;
+                None
+            }
+            Expr::Unary(u) => {
+                let operand = self.expr(&u.expr)?;
+                match u.op {
+                    UnaryOp::Not => {
+                        if !self.truthy_conditions {
+                            expect_type(&TypeAnn::Boolean, operand.as_ref())?;
+                        }
+                        Some(TypeAnn::Boolean)
+                    }
+                    UnaryOp::Neg => {
+                        expect_type(&TypeAnn::Number, operand.as_ref())?;
+                        Some(TypeAnn::Number)
+                    }
+                }
+            }
+            Expr::Binary(b) => self.binary(b)?,
+            Expr::Call(call) => self.call(call)?,
+            Expr::New(n) => {
+                for a in &n.args {
+                    self.expr(a)?;
+                }
+                self.is_builtin(&n.class, "Date").then_some(TypeAnn::Date)
+            }
+            Expr::Assign(a) => {
+                let value = self.expr(&a.value)?;
+                let target = match &a.target {
+                    AssignTarget::Ident(name) => self.name_type(name),
+                    AssignTarget::Member { object, property } => self.name_type(&member_key(object, property)),
+                    AssignTarget::Index { object, index } => {
+                        self.expr(index)?;
+                        match self.name_type(object) {
+                            Some(TypeAnn::Array(elem)) => Some(*elem),
+                            _ => None,
+                        }
+                    }
+                };
+                match target {
+                    Some(t) => {
+                        expect_assignable(&t, value.as_ref())?;
+                        Some(t)
+                    }
+                    None => value,
+                }
+            }
+            Expr::Member(m) => {
+                if let Expr::Ident(object, _) = &*m.object {
+                    if let Some(field) = self.lookup(&member_key(object, &m.property)) {
+                        return Ok(field.ty.clone());
+                    }
+                    if self.is_builtin(object, "Math") {
+                        return Ok(Some(TypeAnn::Number));
+                    }
+                }
+                match self.expr(&m.object)? {
+                    Some(TypeAnn::String | TypeAnn::Array(_)) if m.property == "length" => Some(TypeAnn::Number),
+                    _ => None,
+                }
+            }
+            Expr::Index(ix) => {
+                let object = self.expr(&ix.object)?;
+                expect_type(&TypeAnn::Number, self.expr(&ix.index)?.as_ref())?;
+                match object {
+                    Some(TypeAnn::String) => Some(TypeAnn::String),
+                    Some(TypeAnn::Array(elem)) => Some(*elem),
+                    _ => None,
+                }
+            }
+        };
+        Ok(ty)
+    }
+
+    fn binary(&mut self, b: &'a BinaryExpr) -> Result<Option<TypeAnn>, Error> {
+        let left = self.expr(&b.left)?;
+        let right = self.expr(&b.right)?;
+        let ty = match b.op {
+            // 没有一边是字符串时是数字相加（有一边推断不出类型时也一样，CodeGen 按数字生成）
+            BinaryOp::Add => match (&left, &right) {
+                (Some(TypeAnn::String), _) | (_, Some(TypeAnn::String)) => Some(TypeAnn::String),
+                _ => {
+                    expect_type(&TypeAnn::Number, left.as_ref())?;
+                    expect_type(&TypeAnn::Number, right.as_ref())?;
+                    Some(TypeAnn::Number)
+                }
+            },
+            BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                expect_type(&TypeAnn::Number, left.as_ref())?;
+                expect_type(&TypeAnn::Number, right.as_ref())?;
+                Some(TypeAnn::Number)
+            }
+            BinaryOp::EqEq | BinaryOp::NotEq | BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+                // `half(4) == 2`：可空的值可以和它的非空类型比较
+                if let (Some(l), Some(r)) = (&left, &right) {
+                    if !is_assignable(l, r) && !is_assignable(r, l) {
+                        return Err(Error::new(ErrorCode::TypeMismatch, Span::default()));
+                    }
+                }
+                Some(TypeAnn::Boolean)
+            }
+            BinaryOp::AndAnd | BinaryOp::OrOr => {
+                if !self.truthy_conditions {
+                    expect_type(&TypeAnn::Boolean, left.as_ref())?;
+                    expect_type(&TypeAnn::Boolean, right.as_ref())?;
+                }
+                Some(TypeAnn::Boolean)
+            }
+            // `x ?? 0`：左边是 `T | null` 时结果是右边的类型（推断不出来时是 T），右边是 `null` 时还是 `T | null`；
+            // 左边不可空时就是左边的类型
+            BinaryOp::NullishCoalesce => match left {
+                _ if is_null_literal(&b.left) => right,
+                Some(TypeAnn::Nullable(inner, kind)) if is_null_literal(&b.right) => Some(TypeAnn::Nullable(inner, kind)),
+                Some(TypeAnn::Nullable(inner, _)) => right.or(Some(*inner)),
+                other => other,
+            },
+        };
+        Ok(ty)
+    }
+
+    fn call(&mut self, call: &'a CallExpr) -> Result<Option<TypeAnn>, Error> {
+        let decl = match &call.callee {
+            Callee::Ident(name) => self.lookup(name).and_then(|b| b.func),
+            Callee::Member { object, property } => self.lookup(&member_key(object, property)).and_then(|b| b.func),
+            _ => None,
+        };
+        let mut arg_types = Vec::new();
+        for a in &call.args {
+            arg_types.push(self.expr(a)?);
+        }
+        if let Some(f) = decl {
+            check_arity(f, &call.args)?;
+            check_arg_types(f, &call.args, &arg_types)?;
+        }
+        let ret = match &call.callee {
+            Callee::Console(_) => Some(TypeAnn::Void),
+            Callee::Ident(name) if self.lookup(name).is_none() && is_conversion_builtin(name) => {
+                Some(if name == "String" { TypeAnn::String } else { TypeAnn::Number })
+            }
+            Callee::Ident(name) if self.lookup(name).is_none() && is_resource_builtin(name) => Some(TypeAnn::String),
+            Callee::Ident(name) => fn_ret(self.name_type(name)),
+            Callee::Promise(PromiseFn::Resolve) => match arg_types.first() {
+                Some(t) => t.clone().map(|t| TypeAnn::Promise(Box::new(t))),
+                None => None,
+            },
+            // `Promise.all(ps)`：`Promise<T>[]` -> `Promise<T[]>`
+            Callee::Promise(PromiseFn::All) => match arg_types.first() {
+                Some(Some(TypeAnn::Array(elem))) => match elem.as_ref() {
+                    TypeAnn::Promise(inner) => Some(TypeAnn::Promise(Box::new(TypeAnn::Array(inner.clone())))),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Callee::Promise(PromiseFn::Reject) => None,
+            Callee::Member { object, property } => match self.lookup(&member_key(object, property)) {
+                Some(member) => fn_ret(member.ty.clone()),
+                None if self.is_builtin(object, "Math") || self.is_builtin(object, "Date") => Some(TypeAnn::Number),
+                None if self.is_builtin(object, "JSON") => (property == "stringify").then_some(TypeAnn::String),
+                None => self.method_type(self.name_type(object), property),
+            },
+            Callee::Method { object, method } => {
+                let object = self.expr(object)?;
+                self.method_type(object, method)
+            }
+            Callee::Expr(inner) => fn_ret(self.expr(inner)?),
+        };
+        Ok(ret)
+    }
+
+    /// `object.method(..)` 的返回类型：`toString()`、字符串的内置方法和 `Date` 的方法。
+    fn method_type(&self, object: Option<TypeAnn>, method: &str) -> Option<TypeAnn> {
+        if method == "toString" {
+            return Some(TypeAnn::String);
+        }
+        match object? {
+            TypeAnn::String => string_method(method).map(|(_, _, ret)| ret),
+            TypeAnn::Date if method == "toISOString" => Some(TypeAnn::String),
+            TypeAnn::Date if method == "getTime" => Some(TypeAnn::Number),
+            _ => None,
+        }
+    }
+
+    /// UI 里的普通语句、条件和回调。
+    fn ui_nodes(&mut self, nodes: &'a [UiNode]) -> Result<(), Error> {
+        self.scopes.push(HashMap::new());
+        let result = nodes.iter().try_for_each(|n| self.ui_node(n));
+        self.scopes.pop();
+        result
+    }
+
+    fn ui_node(&mut self, node: &'a UiNode) -> Result<(), Error> {
+        self.ui_node_kind(node).map_err(|e| e.at(node.span()))
+    }
+
+    fn ui_node_kind(&mut self, node: &'a UiNode) -> Result<(), Error> {
+        match node {
+            UiNode::Element(e) if e.name == "ForEach" => self.for_each(e),
+            UiNode::Element(e) => {
+                self.ui_args(&e.args)?;
+                self.ui_nodes(&e.children)?;
+                e.attrs.iter().try_for_each(|a| self.ui_args(&a.args).map_err(|e| e.at(a.span.0)))
+            }
+            UiNode::If(i) => {
+                self.condition(&i.cond, i.cond.span())?;
+                self.ui_nodes(&i.then_branch)?;
+                self.ui_nodes(&i.else_branch)
+            }
+            UiNode::Stmt(s) => self.stmt(s),
+        }
+    }
+
+    /// UI 元素的实参。回调的参数只有写了类型标注的才有类型。
+    fn ui_args(&mut self, args: &'a [UiArg]) -> Result<(), Error> {
+        for arg in args {
+            match arg {
+                UiArg::Expr(e) => {
+                    self.expr(e)?;
+                }
+                UiArg::Object(fields) => {
+                    for (_, e) in fields {
+                        self.expr(e)?;
+                    }
+                }
+                UiArg::Handler(h) => {
+                    let params = h.params.iter().map(|p| (p.name.clone(), Binding::var(p.ty.clone())));
+                    self.scopes.push(params.collect());
+                    let result = self.ui_nodes(&h.body);
+                    self.scopes.pop();
+                    result.map_err(|e| e.at(h.span.0))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `ForEach(arr, (item, index) => { .. })`：没有类型标注的 `item` 是数组元素的类型，`index` 是 number
+    /// （见 `codegen::gen_for_each`）。不是这种形式时按普通的 UI 元素检查，由 CodeGen 报错。
+    fn for_each(&mut self, e: &'a UiElement) -> Result<(), Error> {
+        let (arr, h, rest) = match e.args.as_slice() {
+            [UiArg::Expr(arr), UiArg::Handler(h), rest @ ..] if (1..=2).contains(&h.params.len()) => (arr, h, rest),
+            _ => return self.ui_args(&e.args),
+        };
+        let elem = match self.expr(arr)? {
+            Some(TypeAnn::Array(elem)) => Some(*elem),
+            _ => None,
+        };
+        let item = &h.params[0];
+        let mut scope = HashMap::from([(item.name.clone(), Binding::var(item.ty.clone().or(elem)))]);
+        if let Some(index) = h.params.get(1) {
+            scope.insert(index.name.clone(), Binding::var(Some(TypeAnn::Number)));
+        }
+        self.scopes.push(scope);
+        let result = self.ui_nodes(&h.body);
+        self.scopes.pop();
+        result?;
+        self.ui_args(rest)
+    }
+}
+
+/// 全局作用域：顶层函数、外部函数和 namespace 里的函数（`util.f`）。
+fn globals(program: &Program) -> Scope<'_> {
+    let mut out: Scope = program
+        .funcs
+        .iter()
+        .chain(&program.declare_funcs)
+        .map(|f| (f.name.clone(), Binding::func(f)))
+        .collect();
+    for ns in &program.namespace_decls {
+        for f in &ns.funcs {
+            out.insert(member_key(&ns.name, &f.name), Binding::func(f));
+            // namespace 里的函数互相调用时不写前缀
+            out.entry(f.name.clone()).or_insert_with(|| Binding::func(f));
+        }
+    }
+    out
+}
+
+fn params_scope(params: &[crate::ast::Param]) -> Scope<'static> {
+    params.iter().map(|p| (p.name.clone(), Binding::var(Some(param_type(p))))).collect()
+}
+
+fn func_type(f: &FuncDecl) -> TypeAnn {
+    TypeAnn::Function(FuncTypeAnn {
+        params: f.params.iter().map(param_type).collect(),
+        ret: Box::new(effective_ret_type(f)),
+    })
+}
+
+/// 实参个数要和函数声明的参数个数相同，有剩余参数 `...xs` 时至少要有它前面的那几个。
+///
+/// 实参里有展开 `f(...xs)` 时个数要到运行时才知道，不检查。
+fn check_arity(f: &FuncDecl, args: &[Expr]) -> Result<(), Error> {
+    if args.iter().any(|a| matches!(a, Expr::Spread(_))) {
+        return Ok(());
+    }
+    let fixed = f.params.iter().filter(|p| !p.is_rest).count();
+    let ok = if f.params.iter().any(|p| p.is_rest) {
+        args.len() >= fixed
+    } else {
+        args.len() == fixed
+    };
+    if ok {
+        return Ok(());
+    }
+    Err(Error::new(ErrorCode::WrongArgCount, Span::default()).with_note("", f.span.0, NoteCode::FunctionDeclaredHere))
+}
+
+/// 实参的类型要能赋给对应参数的类型（省略标注的参数是 number，见 `codegen::param_type`），
+/// 剩余参数 `...xs: T[]` 收下的实参要能赋给 T。展开的实参 `...xs` 之后对不上位置，不再检查。
+///
+/// 报错的位置是实参，附带一条指向参数声明的 note。
+fn check_arg_types(f: &FuncDecl, args: &[Expr], types: &[Option<TypeAnn>]) -> Result<(), Error> {
+    for (i, (arg, ty)) in args.iter().zip(types).enumerate() {
+        if matches!(arg, Expr::Spread(_)) {
+            break;
+        }
+        let Some(param) = f.params.get(i).or_else(|| f.params.last().filter(|p| p.is_rest)) else {
+            continue;
+        };
+        let expected = match param_type(param) {
+            TypeAnn::Array(elem) if param.is_rest => *elem,
+            other => other,
+        };
+        if expect_assignable(&expected, ty.as_ref()).is_err() {
+            return Err(Error::new(ErrorCode::ArgTypeMismatch, arg.span()).with_note(
+                "",
+                param.span.0,
+                NoteCode::ParameterDeclaredHere,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 一串语句是否在每条执行路径上都以 return / throw 结束（不会执行到末尾）。
+///
+/// - `if` 要有 else，并且两个分支都不会执行到末尾
+/// - `while (true)` 没有 break（目前不支持 break），不会正常结束
+/// - try：try 块和 catch 块都不会执行到末尾，或者 finally 块不会执行到末尾
+fn always_exits(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(stmt_exits)
+}
+
+fn stmt_exits(s: &Stmt) -> bool {
+    match s {
+        Stmt::Return(_) | Stmt::Throw(_) => true,
+        Stmt::Block(b) => always_exits(&b.stmts),
+        Stmt::If(i) => stmt_exits(&i.then_branch) && i.else_branch.as_deref().is_some_and(stmt_exits),
+        Stmt::While(w) => matches!(w.cond, Expr::Literal(Literal::Bool(true), _)),
+        Stmt::Try(t) => {
+            let caught = t.catch.as_ref().is_none_or(|c| always_exits(&c.body.stmts));
+            (always_exits(&t.block.stmts) && caught) || t.finally.as_ref().is_some_and(|f| always_exits(&f.stmts))
+        }
+        Stmt::VarDecl(_) | Stmt::Assign(_) | Stmt::ExprStmt(_) | Stmt::FuncDecl(_) => false,
+    }
+}
+
+/// 调用一个函数类型的值得到的类型。
+fn fn_ret(callee: Option<TypeAnn>) -> Option<TypeAnn> {
+    match callee? {
+        TypeAnn::Function(f) => Some(*f.ret),
+        _ => None,
+    }
+}
+
+/// 类型为 `ty` 的值能不能赋给 `target`：类型相同，或者把非空的值赋给可空类型。
+/// 函数类型之间不比较（回调的 void 返回值、参数个数都有宽松的规则）。
+fn is_assignable(target: &TypeAnn, ty: &TypeAnn) -> bool {
+    match (target, ty) {
+        (TypeAnn::Nullable(t, _), TypeAnn::Nullable(v, _)) => is_assignable(t, v),
+        (TypeAnn::Nullable(t, _), v) => is_assignable(t, v),
+        (TypeAnn::Array(t), TypeAnn::Array(v)) | (TypeAnn::Promise(t), TypeAnn::Promise(v)) => is_assignable(t, v),
+        (TypeAnn::Function(_), TypeAnn::Function(_)) => true,
+        (t, v) => t == v,
+    }
+}
+
+/// 值（类型推断不出来时为 None，总是放行）要能赋给 `target`，否则报 `TypeMismatch`。
+fn expect_assignable(target: &TypeAnn, ty: Option<&TypeAnn>) -> Result<(), Error> {
+    match ty {
+        Some(ty) if !is_assignable(target, ty) => Err(Error::new(ErrorCode::TypeMismatch, Span::default())),
+        _ => Ok(()),
+    }
+}
+
+/// 操作数的类型必须正好是 `expected`。
+fn expect_type(expected: &TypeAnn, ty: Option<&TypeAnn>) -> Result<(), Error> {
+    match ty {
+        Some(ty) if ty != expected => Err(Error::new(ErrorCode::TypeMismatch, Span::default())),
+        _ => Ok(()),
+    }
+}
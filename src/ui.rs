@@ -0,0 +1,259 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// 声明式 UI（组件的 `build()`）的代码生成后端：决定 `Text(..)`、`Column() { .. }` 这些内置组件
+/// 生成为哪个 Rust UI 框架的代码。
+///
+/// CodeGen 自己负责 `build` 方法、UI 里的 `if` / `ForEach` / 普通语句和自定义组件，
+/// 遇到内置组件时调用后端：先生成 `open_element` 的代码行，然后是子节点（多缩进一层），
+/// 最后是 `close_element` 的代码行。
+///
+/// 内置了两个后端（见 `UiBackendRef::by_name`）：
+/// - `builder`（`BuilderBackend`）：调用输出里附带的小运行时 `ArkUi`，每次 build 把 UI 树打印成文本
+/// - `egui`（`EguiBackend`）：生成 egui 的调用，`build` 多一个 `ui: &mut egui::Ui` 参数
+///
+/// 也可以自己实现这个 trait，用 `UiBackendRef::new` 放进 `CodegenOptions::ui_backend`。
+pub trait UiBackend: Send + Sync {
+    /// 后端的名字（命令行参数 `--ui-backend <name>`）
+    fn name(&self) -> &str;
+    /// `build` 方法在 `&mut self` 之后的参数：`(参数名, 类型)`，例如 `("ui", "&mut egui::Ui")`
+    fn build_params(&self) -> Vec<(String, String)>;
+    /// 生成的代码用到 UI 时放在文件开头的定义（运行时类型等），默认没有
+    fn prelude(&self) -> String {
+        String::new()
+    }
+    /// 生成的代码用到 UI 时要带上的运行时辅助项（见 `runtime` 模块）的名字，和其它辅助项一样内联，
+    /// 或者从 `CodegenOptions::runtime` 引入。默认没有
+    fn runtime_helpers(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// 组件开始处的代码行（不带缩进）
+    fn open_element(&self, call: &UiCall) -> Vec<String>;
+    /// 组件结束处的代码行（在所有子节点之后）
+    fn close_element(&self, call: &UiCall) -> Vec<String>;
+    /// `@Styles` / `@Extend` 生成的辅助函数里设置一个属性的代码行（不带缩进）。
+    ///
+    /// 辅助函数在 `open_element` 之后、子节点之前调用。默认不生成代码：后端忽略样式属性。
+    fn set_attr(&self, _name: &str, _args: &[UiCode]) -> Vec<String> {
+        Vec::new()
+    }
+    /// main 运行 `@Entry` 组件时，调用 `build` 之前要执行的语句和传给 `build_params` 的实参。
+    ///
+    /// 返回 None 表示 main 不调用 `build`（UI 由框架的事件循环驱动）。
+    fn entry_build(&self) -> Option<(Vec<String>, Vec<String>)>;
+}
+
+/// 交给后端的一个内置 UI 组件，实参都已经生成为 Rust 代码。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiCall {
+    pub name: String,
+    pub args: Vec<UiCode>,
+    /// 属性方法：`(名字, 实参)`，按源码顺序
+    pub attrs: Vec<(String, Vec<UiCode>)>,
+    /// 有没有子节点（`Column() { .. }`）
+    pub has_children: bool,
+}
+
+impl UiCall {
+    /// 名为 `name` 的属性方法的实参（没有这个属性时为 None）
+    pub fn attr(&self, name: &str) -> Option<&[UiCode]> {
+        self.attrs.iter().find(|(n, _)| n == name).map(|(_, args)| args.as_slice())
+    }
+}
+
+/// 生成好的 UI 实参（对应 `ast::UiArg`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UiCode {
+    /// Rust 表达式：`self.message.value.clone()`、`20i32`
+    Expr(String),
+    /// ArkUI 的枚举值 `FontWeight.Bold`：`(枚举名, 成员名)`
+    Enum(String, String),
+    /// 对象字面量：`(键, 值)`
+    Object(Vec<(String, UiCode)>),
+    /// 箭头函数：参数名和函数体的代码行（不带缩进）。
+    ///
+    /// 函数体是在 `build` 方法里就地生成的，可以直接使用 `self`，适合放进 `if clicked { .. }` 这样的位置。
+    Handler { params: Vec<String>, body: Vec<String> },
+}
+
+/// `CodegenOptions::ui_backend` 里保存的后端。两个后端名字相同就认为相等。
+#[derive(Clone)]
+pub struct UiBackendRef(Arc<dyn UiBackend>);
+
+impl UiBackendRef {
+    pub fn new(backend: impl UiBackend + 'static) -> Self {
+        Self(Arc::new(backend))
+    }
+
+    /// 按名字取内置后端：`builder`、`egui`；不认识的名字返回 None。
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "builder" => Some(Self::new(BuilderBackend)),
+            "egui" => Some(Self::new(EguiBackend)),
+            _ => None,
+        }
+    }
+}
+
+impl Deref for UiBackendRef {
+    type Target = dyn UiBackend;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for UiBackendRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UiBackendRef").field(&self.name()).finish()
+    }
+}
+
+impl PartialEq for UiBackendRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for UiBackendRef {}
+
+/// 通用的 builder 后端：
+///
+/// ```text
+/// ui.open("Text", vec![format!("{:?}", self.message.value.clone())]);
+/// ui.attr("fontSize", vec![format!("{:?}", 20i32)]);
+/// ui.close();
+/// ```
+///
+/// `ArkUi`（定义见 `runtime::UI_HELPER`）把组件记成一行行文本，最外层的组件结束时打印整棵树：
+///
+/// ```text
+/// Column()
+///   Text("Hello").fontSize(20)
+/// ```
+///
+/// 箭头函数只打印成 `() => { .. }`，不会执行。main 里会创建 `ArkUi` 并运行 `@Entry` 组件的 build。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuilderBackend;
+
+impl UiBackend for BuilderBackend {
+    fn name(&self) -> &str {
+        "builder"
+    }
+
+    fn build_params(&self) -> Vec<(String, String)> {
+        vec![("ui".to_string(), "&mut ArkUi".to_string())]
+    }
+
+    fn runtime_helpers(&self) -> Vec<&'static str> {
+        vec!["ArkUi"]
+    }
+
+    fn open_element(&self, call: &UiCall) -> Vec<String> {
+        let mut lines = vec![format!("ui.open({:?}, {});", call.name, describe_args(&call.args))];
+        for (name, args) in &call.attrs {
+            lines.extend(self.set_attr(name, args));
+        }
+        lines
+    }
+
+    fn close_element(&self, _call: &UiCall) -> Vec<String> {
+        vec!["ui.close();".to_string()]
+    }
+
+    fn set_attr(&self, name: &str, args: &[UiCode]) -> Vec<String> {
+        vec![format!("ui.attr({name:?}, {});", describe_args(args))]
+    }
+
+    fn entry_build(&self) -> Option<(Vec<String>, Vec<String>)> {
+        Some((vec!["let mut __ui = ArkUi::new();".to_string()], vec!["&mut __ui".to_string()]))
+    }
+}
+
+/// `vec![..]`：每个实参转成一段描述文字（`String`）。
+fn describe_args(args: &[UiCode]) -> String {
+    let parts: Vec<String> = args.iter().map(describe).collect();
+    format!("vec![{}]", parts.join(", "))
+}
+
+/// 生成把实参转成描述文字的 Rust 表达式：值用 `{:?}` 格式（字符串带引号），枚举值写成 `FontWeight.Bold`。
+fn describe(code: &UiCode) -> String {
+    match code {
+        UiCode::Expr(e) => format!("format!(\"{{:?}}\", {e})"),
+        UiCode::Enum(ty, variant) => format!("String::from(\"{ty}.{variant}\")"),
+        UiCode::Object(props) => {
+            let keys: Vec<String> = props.iter().map(|(k, _)| format!("{k}: {{}}")).collect();
+            let values: Vec<String> = props.iter().map(|(_, v)| describe(v)).collect();
+            format!("format!(\"{{{{ {} }}}}\", {})", keys.join(", "), values.join(", "))
+        }
+        UiCode::Handler { params, .. } => format!("String::from(\"({}) => {{ .. }}\")", params.join(", ")),
+    }
+}
+
+/// egui 后端（生成的代码要依赖 `egui` crate）：`build(&mut self, ui: &mut egui::Ui)`。
+///
+/// - `Column() { .. }` -> `ui.vertical(|ui| { .. });`，`Row` -> `ui.horizontal`，其它带子节点的组件 -> `ui.group`
+/// - `Text(x)` -> `ui.label(format!("{}", x));`
+/// - `Button(x).onClick(() => { .. })` -> `if ui.button(format!("{}", x)).clicked() { .. }`
+/// - `Divider()` -> `ui.separator();`
+/// - 其它没有子节点的组件生成一行注释；样式属性（`fontSize` 等）忽略
+///
+/// egui 由应用自己的事件循环每帧调用 `build`，所以 main 不调用 build。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EguiBackend;
+
+impl UiBackend for EguiBackend {
+    fn name(&self) -> &str {
+        "egui"
+    }
+
+    fn build_params(&self) -> Vec<(String, String)> {
+        vec![("ui".to_string(), "&mut egui::Ui".to_string())]
+    }
+
+    fn open_element(&self, call: &UiCall) -> Vec<String> {
+        let text = match call.args.first() {
+            Some(arg) => egui_text(arg),
+            None => "\"\"".to_string(),
+        };
+        match call.name.as_str() {
+            "Column" => vec!["ui.vertical(|ui| {".to_string()],
+            "Row" => vec!["ui.horizontal(|ui| {".to_string()],
+            _ if call.has_children => vec!["ui.group(|ui| {".to_string()],
+            "Text" => vec![format!("ui.label({text});")],
+            "Divider" => vec!["ui.separator();".to_string()],
+            "Button" => match call.attr("onClick") {
+                Some([UiCode::Handler { body, .. }]) => {
+                    let mut lines = vec![format!("if ui.button({text}).clicked() {{")];
+                    lines.extend(body.iter().map(|line| format!("    {line}")));
+                    lines.push("}".to_string());
+                    lines
+                }
+                _ => vec![format!("ui.button({text});")],
+            },
+            name => vec![format!("// {name}(..) is not supported by the egui backend")],
+        }
+    }
+
+    fn close_element(&self, call: &UiCall) -> Vec<String> {
+        if call.has_children || matches!(call.name.as_str(), "Column" | "Row") {
+            vec!["});".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn entry_build(&self) -> Option<(Vec<String>, Vec<String>)> {
+        None
+    }
+}
+
+/// egui 的文字参数：表达式用 `format!("{}", ..)` 转成字符串，枚举值写成 `"FontWeight.Bold"`。
+fn egui_text(code: &UiCode) -> String {
+    match code {
+        UiCode::Expr(e) => format!("format!(\"{{}}\", {e})"),
+        UiCode::Enum(ty, variant) => format!("\"{ty}.{variant}\""),
+        UiCode::Object(_) | UiCode::Handler { .. } => "\"\"".to_string(),
+    }
+}
@@ -0,0 +1,310 @@
+use crate::codegen::{Edition, OutputMode};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::error::{Error, ErrorCode, Note, NoteCode};
+use crate::source_map::SourceMap;
+use crate::span::Span;
+use std::path::Path;
+use std::process::Command;
+
+/// rustc 的一条诊断信息（`--error-format=json` 输出的一行），只保留 CLI 的 `--verify` 用得到的字段。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RustcDiagnostic {
+    /// `error`、`warning`、`note` 等
+    pub level: String,
+    pub message: String,
+    /// `E0308` 这样的错误码
+    pub code: Option<String>,
+    /// 主要位置所在的文件：rustc 看到的路径（`cargo check` 时相对于项目目录）；没有位置时为 None
+    pub file: Option<String>,
+    /// 主要位置在生成代码里的行列号（没有 byte offset）
+    pub span: Span,
+}
+
+impl RustcDiagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// 和 rustc 相同的标题：`error[E0308]: mismatched types`。
+    pub fn title(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{code}]: {}", self.level, self.message),
+            None => format!("{}: {}", self.level, self.message),
+        }
+    }
+}
+
+/// 用 `rustc` 检查 `root`（生成的 crate 根，模块文件在它旁边），返回 rustc 的诊断信息。
+///
+/// 只生成 metadata，不链接；没有 Cargo.toml，生成的代码用到外部 crate（`rand`、`napi-ohos` 等）时会报找不到，
+/// 这时用 `cargo_check` 检查 `cargo::project` 生成的项目。找不到 `rustc` 时报 `RustcUnavailable`。
+pub fn rustc(root: &Path, mode: OutputMode, edition: Edition) -> Result<Vec<RustcDiagnostic>, Error> {
+    let crate_type = match mode {
+        OutputMode::Binary => "bin",
+        OutputMode::Library | OutputMode::NativeModule => "lib",
+    };
+    let out_dir = std::env::temp_dir().join(format!("arkts2rust_verify_{}", std::process::id()));
+    let mut cmd = Command::new("rustc");
+    cmd.args(["--error-format=json", "--emit=metadata", "--crate-name", "arkts2rust_verify"])
+        .args(["--edition", edition.as_str(), "--crate-type", crate_type])
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg(root);
+    let out = cmd.output();
+    let _ = std::fs::remove_dir_all(&out_dir);
+    let out = out.map_err(|_| Error::new(ErrorCode::RustcUnavailable, Span::default()))?;
+    Ok(parse_diagnostics(&String::from_utf8_lossy(&out.stderr)))
+}
+
+/// 在 Cargo 项目目录 `dir` 下运行 `cargo check`，返回 rustc 的诊断信息（文件路径相对于 `dir`）。
+pub fn cargo_check(dir: &Path) -> Result<Vec<RustcDiagnostic>, Error> {
+    let out = Command::new("cargo")
+        .args(["check", "--quiet", "--message-format=json"])
+        .current_dir(dir)
+        .output()
+        .map_err(|_| Error::new(ErrorCode::RustcUnavailable, Span::default()))?;
+    Ok(parse_diagnostics(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// 解析 rustc `--error-format=json`（一行一个 JSON 对象）或者 `cargo --message-format=json`
+/// （`compiler-message` 里的 `message`）的输出。不是诊断信息的行跳过。
+pub fn parse_diagnostics(output: &str) -> Vec<RustcDiagnostic> {
+    output
+        .lines()
+        .filter_map(|line| Json::parse(line.trim()))
+        .filter_map(|json| match json.get("reason") {
+            Some(reason) if reason.as_str() == Some("compiler-message") => json.get("message").cloned(),
+            Some(_) => None,
+            None => Some(json),
+        })
+        .filter_map(|d| rustc_diagnostic(&d))
+        .collect()
+}
+
+fn rustc_diagnostic(d: &Json) -> Option<RustcDiagnostic> {
+    let primary = d
+        .get("spans")
+        .and_then(Json::as_array)
+        .and_then(|spans| spans.iter().find(|s| s.get("is_primary") == Some(&Json::Bool(true))));
+    let num = |s: &Json, key: &str| s.get(key).and_then(Json::as_usize).unwrap_or(1);
+    Some(RustcDiagnostic {
+        level: d.get("level")?.as_str()?.to_string(),
+        message: d.get("message")?.as_str()?.to_string(),
+        code: d.get("code").and_then(|c| c.get("code")).and_then(Json::as_str).map(str::to_string),
+        file: primary.and_then(|s| s.get("file_name")).and_then(Json::as_str).map(str::to_string),
+        span: primary.map_or_else(Span::default, |s| {
+            Span::new_with_line_col(
+                0,
+                0,
+                num(s, "line_start"),
+                num(s, "column_start"),
+                num(s, "line_end"),
+                num(s, "column_end"),
+            )
+        }),
+    })
+}
+
+/// 把 rustc 对生成代码的报错换成源码上的 `GeneratedCodeError`：用 `map`（`d.file` 对应的源码映射，见 `SourceMap::original`）
+/// 找到生成这一行代码的语句，诊断信息指向这条语句；rustc 原来的报错和生成代码里的位置放在补充说明里。
+///
+/// 找不到对应源码的（运行时辅助代码里的报错等）没有位置。`warning` 以外的级别都当作错误。
+pub fn map_back(d: &RustcDiagnostic, map: Option<&SourceMap>) -> Diagnostic {
+    let severity = match d.level.as_str() {
+        "warning" => Severity::Warning,
+        _ => Severity::Error,
+    };
+    let mapping = map.filter(|_| d.file.is_some()).and_then(|map| Some((map, map.original(d.span.start_line)?)));
+    let mut diagnostic = Diagnostic::new(
+        severity,
+        ErrorCode::GeneratedCodeError,
+        mapping.map_or_else(Span::default, |(_, m)| m.original),
+    );
+    diagnostic.file = mapping.and_then(|(map, m)| map.source(m)).map(str::to_string);
+    diagnostic.notes.push(Note {
+        file: d.file.clone().unwrap_or_default(),
+        span: if d.file.is_some() { d.span } else { Span::default() },
+        code: NoteCode::Rustc,
+        args: vec![("message", d.title())],
+    });
+    diagnostic
+}
+
+/// 解析 rustc 输出用的最小 JSON 值。
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// 解析一个完整的 JSON 值；格式不对返回 None。
+    fn parse(text: &str) -> Option<Json> {
+        let mut p = JsonParser { chars: text.chars().collect(), pos: 0 };
+        let value = p.value()?;
+        p.skip_ws();
+        (p.pos == p.chars.len()).then_some(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Obj(items) => items.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Arr(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Num(n) if *n >= 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn skip_ws(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        let found = self.chars.get(self.pos) == Some(&c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Option<Json> {
+        let end = self.pos + word.len();
+        let found = self.chars.get(self.pos..end)?.iter().copied().eq(word.chars());
+        found.then(|| {
+            self.pos = end;
+            value
+        })
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_ws();
+        match *self.chars.get(self.pos)? {
+            '{' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat('}') {
+                    return Some(Json::Obj(items));
+                }
+                loop {
+                    self.skip_ws();
+                    let key = self.string()?;
+                    if !self.eat(':') {
+                        return None;
+                    }
+                    items.push((key, self.value()?));
+                    if self.eat('}') {
+                        return Some(Json::Obj(items));
+                    }
+                    if !self.eat(',') {
+                        return None;
+                    }
+                }
+            }
+            '[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(']') {
+                    return Some(Json::Arr(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.eat(']') {
+                        return Some(Json::Arr(items));
+                    }
+                    if !self.eat(',') {
+                        return None;
+                    }
+                }
+            }
+            '"' => self.string().map(Json::Str),
+            't' => self.keyword("true", Json::Bool(true)),
+            'f' => self.keyword("false", Json::Bool(false)),
+            'n' => self.keyword("null", Json::Null),
+            _ => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                    self.pos += 1;
+                }
+                self.chars[start..self.pos].iter().collect::<String>().parse().ok().map(Json::Num)
+            }
+        }
+    }
+
+    /// 字符串字面量，`\uXXXX` 的代理对合成一个字符。
+    fn string(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                '"' => return Some(out),
+                '\\' => {
+                    let e = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    match e {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let high = self.hex4()?;
+                            let code = match high {
+                                0xD800..=0xDBFF if self.keyword("\\u", Json::Null).is_some() => {
+                                    0x10000 + ((high - 0xD800) << 10) + self.hex4()?.checked_sub(0xDC00)?
+                                }
+                                _ => high,
+                            };
+                            out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        e => out.push(e),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
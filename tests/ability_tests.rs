@@ -0,0 +1,142 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, OutputMode, Param};
+
+mod common;
+
+const ABILITY: &str = r#"
+export default class EntryAbility extends UIAbility {
+  onCreate(want: Want, launchParam: AbilityConstant.LaunchParam): void {
+    console.log("onCreate");
+  }
+
+  onWindowStageCreate(windowStage: window.WindowStage): void {
+    this.trace("window stage");
+    windowStage.loadContent('pages/Index');
+  }
+
+  private trace(msg: string) {
+    console.log("EntryAbility:", msg);
+  }
+
+  onForeground() {
+    console.log("onForeground");
+  }
+
+  onDestroy(): void {
+    console.log("onDestroy");
+  }
+}
+"#;
+
+#[test]
+fn parse_ability_class() {
+    let p = parse_program(ABILITY).unwrap();
+    assert!(p.classes.is_empty());
+    let a = &p.abilities[0];
+    assert_eq!(a.name, "EntryAbility");
+    assert!(a.is_export);
+    let names: Vec<&str> = a.methods.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["onCreate", "onWindowStageCreate", "trace", "onForeground", "onDestroy"]);
+    // 生命周期方法的参数类型由运行时决定，不保存
+    assert_eq!(
+        a.methods[1].params,
+        vec![Param {
+            name: "windowStage".into(),
+            ty: None,
+            is_rest: false,
+            span: Default::default(),
+        }]
+    );
+
+    // 继承其它类的还是普通 class
+    let p = parse_program("class NotFound extends Error {}").unwrap();
+    assert!(p.abilities.is_empty());
+    assert_eq!(p.classes.len(), 1);
+}
+
+#[test]
+fn codegen_ability_struct() {
+    let rust = compile(ABILITY).unwrap();
+    assert!(rust.contains("trait UIAbility {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "struct EntryAbility;\n\nimpl EntryAbility {\n    fn trace(&mut self, msg: String) {\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "impl UIAbility for EntryAbility {\n    \
+             fn onCreate(&mut self, want: &Want, launchParam: &LaunchParam) {\n        \
+             println!(\"{}\", String::from(\"onCreate\"));\n    }\n\n    \
+             fn onWindowStageCreate(&mut self, windowStage: &mut WindowStage) {\n        \
+             self.trace(String::from(\"window stage\"));\n        \
+             windowStage.loadContent(String::from(\"pages/Index\"));\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "fn main() {\n    let mut __ability = EntryAbility;\n    \
+             __ability.onCreate(&Want::new(), &LaunchParam::new());\n    \
+             let mut __stage = WindowStage::new();\n    __ability.onWindowStageCreate(&mut __stage);\n    \
+             __ability.onForeground();\n    __ability.onDestroy();\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+
+    // 省略的参数生成 `_`
+    let rust = compile("class A extends UIAbility { onCreate() {} }").unwrap();
+    assert!(
+        rust.contains("    fn onCreate(&mut self, _: &Want, _: &LaunchParam) {\n    }\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_ability_runtime_option() {
+    let program = parse_program(ABILITY).unwrap();
+    let options = CodegenOptions {
+        ability_runtime: Some("ohos_runtime::ability".into()),
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Library, &ExternBindings::new(), &options).unwrap();
+    assert!(
+        rust.starts_with("use ohos_runtime::ability::{LaunchParam, UIAbility, Want, WindowStage};\n\n"),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("trait UIAbility"), "got:\n{rust}");
+    assert!(rust.contains("pub struct EntryAbility;\n"), "got:\n{rust}");
+    assert!(rust.contains("    pub fn trace(&mut self, msg: String) {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_ability_misuse() {
+    let err = compile("class A extends UIAbility { onDestroy(reason: string) {} }").expect_err("extra param");
+    assert_eq!(err.code, "InvalidLifecycleParams");
+
+    let err = parse_program("class A extends UIAbility { count: number = 0; }").expect_err("field");
+    assert_eq!(err.code, "UnsupportedClassMember");
+
+    let err = compile(
+        r#"function check(): void { throw new Error("bad"); }
+class A extends UIAbility {
+  onCreate() { check(); }
+}"#,
+    )
+    .expect_err("throwing lifecycle method");
+    assert_eq!(err.code, "UnsupportedAbilityThrow");
+}
+
+#[test]
+fn generated_ability_runs_lifecycle() {
+    let rust = compile(ABILITY).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "onCreate\nEntryAbility: window stage\nloadContent pages/Index\nonForeground\nonDestroy\n"
+    );
+}
@@ -0,0 +1,59 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, ArithMode, CodegenOptions, ExternBindings, NumberMode, OutputMode};
+
+mod common;
+
+fn compile_arith(src: &str, arith: ArithMode) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        arith,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+const SRC: &str = "function mul(a: number, b: number): number {\n  return a * b + 1;\n}\n\
+                   let x = 2147483647;\nlet y = -x;\n\
+                   try {\n  console.log(mul(x, 2));\n} catch (e) {\n  console.log('caught', e);\n}\n\
+                   console.log((x - 1) % 7, y);";
+
+#[test]
+fn plain_is_the_default() {
+    let rust = compile(SRC).unwrap();
+    assert!(rust.contains("return a * b + 1i32;"), "got:\n{rust}");
+    assert!(rust.contains("let y = -x;"), "got:\n{rust}");
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    let rust = compile_arith(SRC, ArithMode::Wrapping);
+    assert!(rust.contains("fn mul(a: i32, b: i32) -> i32 {\n    return a.wrapping_mul(b).wrapping_add(1i32);\n"), "got:\n{rust}");
+    assert!(rust.contains("let y = x.wrapping_neg();"), "got:\n{rust}");
+    assert!(rust.contains("x.wrapping_sub(1i32).wrapping_rem(7i32)"), "got:\n{rust}");
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "-1\n0 -2147483647\n");
+    }
+}
+
+#[test]
+fn checked_arithmetic_throws() {
+    let rust = compile_arith(SRC, ArithMode::Checked);
+    assert!(rust.contains("fn mul(a: i32, b: i32) -> Result<i32, ArkError> {"), "got:\n{rust}");
+    assert!(
+        rust.contains("a.checked_mul(b).ok_or_else(|| ArkError::Error(String::from(\"integer overflow\")))?"),
+        "got:\n{rust}"
+    );
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "caught integer overflow\n0 -2147483647\n");
+    }
+
+    // f64 模式不受影响
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions {
+        arith: ArithMode::Checked,
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    assert!(rust.contains("fn mul(a: f64, b: f64) -> f64 {\n    return a * b + 1f64;\n"), "got:\n{rust}");
+}
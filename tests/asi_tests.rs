@@ -0,0 +1,61 @@
+use arkts2rust::codegen::generate_as;
+use arkts2rust::{parse_program, parse_program_with, OutputMode, ParseOptions, Program};
+
+mod common;
+
+fn parse_asi(src: &str) -> Program {
+    let options = ParseOptions { asi: true };
+    parse_program_with(src, &options).unwrap()
+}
+
+#[test]
+fn newline_and_brace_end_statements() {
+    let mut p = parse_asi("let a = 1\nlet b = a +\n  2\nfunction f(): number { return b }\nconsole.log(f())");
+    let at: Vec<(usize, usize)> = p.warnings.iter().map(|w| (w.span.start_line, w.span.start_col)).collect();
+    assert_eq!(at, vec![(1, 10), (3, 4), (4, 32), (5, 17)]);
+    assert!(p.warnings.iter().all(|w| w.code == "InsertedSemicolon"));
+    p.warnings.clear();
+    assert_eq!(
+        p,
+        parse_program("let a = 1; let b = a + 2; function f(): number { return b; } console.log(f());").unwrap()
+    );
+}
+
+#[test]
+fn return_followed_by_newline_returns_nothing() {
+    let p = parse_asi("function f(): void {\n  return\n  g()\n}\nfunction g(): void {}");
+    let expected = parse_program("function f(): void { return; g(); } function g(): void {}").unwrap();
+    assert_eq!(p.funcs, expected.funcs);
+}
+
+#[test]
+fn error_missing_semicolon_on_same_line() {
+    let options = ParseOptions { asi: true };
+    let err = parse_program_with("let a = 1 let b = 2", &options).expect_err("same line");
+    assert_eq!(err.code, "MissingSemicolon");
+    let err = parse_program("let a = 1\nlet b = 2").expect_err("asi is off by default");
+    assert_eq!(err.code, "MissingSemicolon");
+}
+
+#[test]
+fn generated_code_without_semicolons_runs() {
+    let src = r#"
+function sum(n: number): number {
+  let total = 0
+  let i = 1
+  while (i <= n) {
+    total = total + i
+    i = i + 1
+  }
+  return total
+}
+console.log(sum(4), "done")
+"#;
+
+    let rust = generate_as(&parse_asi(src), OutputMode::Binary).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "10 done\n");
+}
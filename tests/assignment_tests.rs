@@ -0,0 +1,164 @@
+use arkts2rust::ast::{AssignExpr, AssignStmt, AssignTarget, BinaryExpr, BinaryOp, Expr, Literal, Stmt};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+fn stmt(src: &str) -> Stmt {
+    let p = parse_program(src).unwrap();
+    assert_eq!(p.stmts.len(), 1);
+    p.stmts.into_iter().next().unwrap()
+}
+
+fn lit_i(n: i32) -> Expr {
+    Expr::Literal(Literal::Number(n), Default::default())
+}
+
+fn assign(target: AssignTarget, value: Expr) -> Expr {
+    Expr::Assign(AssignExpr {
+        target,
+        value: Box::new(value),
+        span: Default::default(),
+    })
+}
+
+#[test]
+fn parse_chained_assignment_is_right_assoc() {
+    assert_eq!(
+        stmt("a = b = 0;"),
+        Stmt::Assign(AssignStmt {
+            name: "a".into(),
+            value: assign(AssignTarget::Ident("b".into()), lit_i(0)),
+            span: Default::default(),
+        })
+    );
+}
+
+#[test]
+fn parse_assignment_inside_condition() {
+    let s = stmt("while ((x = next()) != 0) {}");
+    let Stmt::While(w) = s else {
+        panic!("expected while, got {s:?}");
+    };
+    let Expr::Binary(BinaryExpr { op, left, .. }) = w.cond else {
+        panic!("expected binary condition");
+    };
+    assert_eq!(op, BinaryOp::NotEq);
+    assert!(
+        matches!(*left, Expr::Group(ref inner) if matches!(**inner, Expr::Assign(_))),
+        "got {left:?}"
+    );
+}
+
+#[test]
+fn parse_member_and_index_targets() {
+    assert_eq!(
+        stmt("obj.x = 1;"),
+        Stmt::ExprStmt(assign(
+            AssignTarget::Member {
+                object: "obj".into(),
+                property: "x".into(),
+            },
+            lit_i(1)
+        ))
+    );
+    assert_eq!(
+        stmt("arr[i] = 2;"),
+        Stmt::ExprStmt(assign(
+            AssignTarget::Index {
+                object: "arr".into(),
+                index: Box::new(Expr::Ident("i".into(), Default::default())),
+            },
+            lit_i(2)
+        ))
+    );
+}
+
+#[test]
+fn error_index_read_needs_array_or_string() {
+    let err = compile("let n = 1; let ok = n[0] == 1;").expect_err("number is not indexable");
+    assert_eq!(err.code, "UnsupportedIndexAccess");
+}
+
+#[test]
+fn codegen_array_index_read() {
+    let rust = compile("function f(i: number, ...xs: string[]): string { return xs[i + 1]; }").unwrap();
+    assert!(rust.contains("    return xs[(i + 1i32) as usize].clone();\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_assignment_needs_parens_inside_operators() {
+    let err = parse_program("let a = 1; let b = 2; a + b = 3;").expect_err("bad target");
+    assert_eq!(err.code, "MissingSemicolon");
+}
+
+#[test]
+fn codegen_chained_assignment_splits_into_statements() {
+    let rust = compile("let a = 1; let b = 2; a = b = 7;").unwrap();
+    assert!(rust.contains("    b = 7i32;\n    a = b;\n"), "got:\n{rust}");
+
+    let rust = compile(r#"let s = ""; let t = ""; s = t = "hi";"#).unwrap();
+    assert!(
+        rust.contains("    t = String::from(\"hi\");\n    s = t.clone();\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_assignment_expression_is_block() {
+    let rust = compile(
+        "function next(): number { return 0; } \
+         let x = 1; \
+         while ((x = next()) != 0) { console.log(x); }",
+    )
+    .unwrap();
+    assert!(rust.contains("while ({ x = next(); x }) != 0i32 {"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_index_target_makes_param_mut() {
+    let rust = compile("function f(...xs: number[]): void { xs[0] = 1; }").unwrap();
+    assert!(rust.contains("fn f(mut xs: Vec<i32>) {"), "got:\n{rust}");
+    assert!(rust.contains("    xs[0] = 1i32;\n"), "got:\n{rust}");
+}
+
+#[test]
+fn generated_assignment_code_runs() {
+    let src = r#"
+function drain(): number {
+  let n = 0;
+  function next(): number {
+    n = n + 1;
+    if (n > 3) { return 0; } else { return n; }
+  }
+  let x = 0;
+  let total = 0;
+  while ((x = next()) != 0) {
+    total = total + x;
+  }
+  return total;
+}
+function first(...xs: number[]): number {
+  let k = 0;
+  let y = xs[k + 1] = 9;
+  xs[0] = 5;
+  const [p, q] = xs;
+  return p + q + y;
+}
+let a = 1;
+let b = 2;
+a = b = 7;
+console.log(a + b);
+console.log(drain());
+console.log(first(1, 2, 3));
+let c: number | null = null;
+c = a = 3;
+console.log(c);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "14\n6\n23\n3\n");
+}
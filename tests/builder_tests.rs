@@ -0,0 +1,187 @@
+use arkts2rust::ast::{CallExpr, Callee, Expr, Literal, MemberExpr, Param, Stmt, TypeAnn, UiArg, UiElement, UiNode};
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, Error, ExternBindings, OutputMode, UiBackendRef};
+
+mod common;
+
+fn builder(src: &str) -> Result<String, Error> {
+    let program = parse_program(src)?;
+    let options = CodegenOptions {
+        ui_backend: UiBackendRef::by_name("builder"),
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+const PAGE: &str = r#"
+@Builder
+function Header(title: string) {
+  Row() {
+    Text(title).fontSize(30)
+  }
+}
+
+@Builder function footer() {
+  Divider()
+}
+
+@Entry
+@Component
+struct Index {
+  @State count: number = 1;
+  @Builder item(n: number) {
+    Text("item", n)
+  }
+  build() {
+    Column() {
+      Header("Home")
+      this.item(this.count)
+      footer()
+    }
+  }
+  onPageShow() {
+    this.count = 2;
+  }
+}
+"#;
+
+#[test]
+fn parse_builder_functions_and_methods() {
+    let p = parse_program(PAGE).unwrap();
+    assert_eq!(p.builders.len(), 2);
+    assert!(p.funcs.is_empty());
+    let header = &p.builders[0];
+    assert_eq!(header.name, "Header");
+    assert_eq!(
+        header.params,
+        vec![Param {
+            name: "title".into(),
+            ty: Some(TypeAnn::String),
+            is_rest: false,
+            span: Default::default(),
+        }]
+    );
+    assert!(matches!(header.body.as_slice(), [UiNode::Element(e)] if e.name == "Row"));
+
+    let c = &p.components[0];
+    assert!(c.methods.iter().all(|m| m.name != "item"));
+    assert_eq!(c.builders.len(), 1);
+    assert_eq!(c.builders[0].name, "item");
+    // `this.item(..)` 和 `footer()` 末尾没有分号
+    assert_eq!(
+        c.build.as_deref().unwrap(),
+        [UiNode::Element(UiElement {
+            name: "Column".into(),
+            args: vec![],
+            children: vec![
+                UiNode::Element(UiElement {
+                    name: "Header".into(),
+                    args: vec![UiArg::Expr(Expr::Literal(Literal::String("Home".into()), Default::default()))],
+                    children: vec![],
+                    attrs: vec![],
+                    span: Default::default(),
+                }),
+                UiNode::Stmt(Stmt::ExprStmt(Expr::Call(CallExpr {
+                    callee: Callee::Member {
+                        object: "this".into(),
+                        property: "item".into(),
+                    },
+                    args: vec![Expr::Member(MemberExpr {
+                        object: Box::new(Expr::Ident("this".into(), Default::default())),
+                        property: "count".into(),
+                        span: Default::default(),
+                    })],
+                    span: Default::default(),
+                }))),
+                UiNode::Stmt(Stmt::ExprStmt(Expr::Call(CallExpr {
+                    callee: Callee::Ident("footer".into()),
+                    args: vec![],
+                    span: Default::default(),
+                }))),
+            ],
+            attrs: vec![],
+            span: Default::default(),
+        })]
+    );
+
+    let p = parse_program("@Builder export function A() { Divider() }").unwrap();
+    assert!(p.builders[0].is_export);
+}
+
+#[test]
+fn codegen_builders_without_backend() {
+    let rust = compile(PAGE).unwrap();
+    assert!(
+        rust.contains("// Row() {\n//     Text(title)\n//         .fontSize(30i32)\n// }\nfn Header(title: String) {\n}\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    fn build(&mut self) {\n        Header(String::from(\"Home\"));\n        \
+             self.item(self.count.value);\n        footer();\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    // Text(String::from(\"item\"), n)\n    fn item(&mut self, n: i32) {\n    }\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_builders_with_backend() {
+    let rust = builder(PAGE).unwrap();
+    assert!(
+        rust.contains(
+            "fn Header(title: String, ui: &mut ArkUi) {\n    ui.open(\"Row\", vec![]);\n        \
+             ui.open(\"Text\", vec![format!(\"{:?}\", title)]);\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("fn footer(ui: &mut ArkUi) {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "        ui.open(\"Column\", vec![]);\n            Header(String::from(\"Home\"), ui);\n            \
+             self.item(self.count.value, ui);\n            footer(ui);\n        ui.close();\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    fn item(&mut self, n: i32, ui: &mut ArkUi) {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_builder_misuse() {
+    let err = builder(
+        r#"@Builder function Header(title: string) { Text(title) }
+@Component struct A {
+  build() {
+    Header({ title: "x" })
+  }
+}"#,
+    )
+    .expect_err("object argument");
+    assert_eq!(err.code, "InvalidBuilderArgs");
+
+    let err = compile(
+        r#"function check(): void { throw new Error("bad"); }
+@Builder function Header() {
+  check();
+}"#,
+    )
+    .expect_err("throwing builder");
+    assert_eq!(err.code, "UnsupportedComponentThrow");
+}
+
+#[test]
+fn generated_builder_page_runs() {
+    let rust = builder(PAGE).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "Column()\n  Row()\n    Text(\"Home\").fontSize(30)\n  Text(\"item\", 1)\n  Divider()\n\
+         Column()\n  Row()\n    Text(\"Home\").fontSize(30)\n  Text(\"item\", 2)\n  Divider()\n"
+    );
+}
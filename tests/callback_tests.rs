@@ -0,0 +1,184 @@
+use arkts2rust::ast::{Callee, Expr, FuncTypeAnn, Stmt, TypeAnn};
+use arkts2rust::{compile, lex, parse_program, TokenKind};
+
+mod common;
+
+fn fn_type(params: Vec<TypeAnn>, ret: TypeAnn) -> TypeAnn {
+    TypeAnn::Function(FuncTypeAnn {
+        params,
+        ret: Box::new(ret),
+    })
+}
+
+#[test]
+fn lex_fat_arrow() {
+    let ks: Vec<TokenKind> = lex("= => ==").unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(ks, vec![TokenKind::Eq, TokenKind::FatArrow, TokenKind::EqEq]);
+}
+
+#[test]
+fn parse_callback_param_type() {
+    let p = parse_program("function apply(f: (x: number) => number, v: number): number { return f(v); }")
+        .unwrap();
+    assert_eq!(
+        p.funcs[0].params[0].ty,
+        Some(fn_type(vec![TypeAnn::Number], TypeAnn::Number))
+    );
+}
+
+#[test]
+fn parse_nested_callback_type() {
+    let p = parse_program(
+        "function on(cb: (done: (ok: boolean) => void, n: number) => void): void { return; }",
+    )
+    .unwrap();
+    assert_eq!(
+        p.funcs[0].params[0].ty,
+        Some(fn_type(
+            vec![fn_type(vec![TypeAnn::Boolean], TypeAnn::Void), TypeAnn::Number],
+            TypeAnn::Void
+        ))
+    );
+}
+
+#[test]
+fn codegen_callback_param_is_dyn_fn_ref() {
+    let rust = compile("function apply(f: (x: number) => number, v: number): number { return f(v); }")
+        .unwrap();
+    assert!(
+        rust.starts_with("fn apply(f: &dyn Fn(i32) -> i32, v: i32) -> i32 {"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_void_callback_omits_return_type() {
+    let rust = compile("function each(f: (s: string) => void): void { return; }").unwrap();
+    assert!(rust.starts_with("fn each(f: &dyn Fn(String)) {"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nested_callback_is_boxed() {
+    let rust = compile("function on(cb: (done: () => void) => void): void { return; }").unwrap();
+    assert!(
+        rust.starts_with("fn on(cb: &dyn Fn(Box<dyn Fn()>)) {"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_returned_callback_is_boxed() {
+    let rust = compile(
+        "function double(x: number): number { return x * 2; } \
+         function pick(): (x: number) => number { return double; }",
+    )
+    .unwrap();
+    assert!(rust.contains("fn pick() -> Box<dyn Fn(i32) -> i32> {"), "got:\n{rust}");
+    assert!(rust.contains("return Box::new(double);"), "got:\n{rust}");
+}
+
+#[test]
+fn parse_call_of_call_result() {
+    let p = parse_program("f(1)(2);").unwrap();
+    match &p.stmts[0] {
+        Stmt::ExprStmt(Expr::Call(call)) => match &call.callee {
+            Callee::Expr(inner) => assert!(matches!(**inner, Expr::Call(_))),
+            other => panic!("expected Callee::Expr, got {other:?}"),
+        },
+        other => panic!("expected call statement, got {other:?}"),
+    }
+}
+
+#[test]
+fn codegen_factory_returns_move_closure() {
+    let rust = compile(
+        "function makeAdder(n: number): (x: number) => number { \
+           function add(x: number): number { return x + n; } \
+           return add; \
+         } \
+         console.log(makeAdder(2)(3));",
+    )
+    .unwrap();
+    assert!(rust.contains("let add = move |x: i32| -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("makeAdder(2i32)(3i32)"), "got:\n{rust}");
+}
+
+#[test]
+fn error_function_type_requires_arrow() {
+    let err = parse_program("function f(cb: (x: number) number): void { return; }")
+        .expect_err("missing => should error");
+    assert_eq!(err.code, "ExpectedArrow");
+}
+
+#[test]
+fn generated_rust_with_callbacks_can_compile() {
+    let src = r#"
+function double(x: number): number { return x * 2; }
+function apply(f: (x: number) => number, v: number): number { return f(v); }
+function pick(): (x: number) => number { return double; }
+function twice(f: (x: number) => number, v: number): number { return f(f(v)); }
+console.log(apply(double, 3));
+console.log(twice(double, 3));
+console.log(apply(pick(), 5));
+function makeAdder(n: number): (x: number) => number {
+  function add(x: number): number { return x + n; }
+  return add;
+}
+function curry(a: number): (b: number) => (c: number) => number {
+  function second(b: number): (c: number) => number {
+    function third(c: number): number { return a * 100 + b * 10 + c; }
+    return third;
+  }
+  return second;
+}
+console.log(makeAdder(2)(3));
+console.log(curry(1)(2)(3));
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
+
+#[test]
+fn forwarded_callbacks_are_borrowed() {
+    let src = r#"
+function apply(f: (x: number) => number, v: number): number {
+  return f(v);
+}
+function both(f: (x: number) => number): number {
+  return apply(f, 1) + apply(f, 2);
+}
+function rec(f: (x: number) => number, n: number): number {
+  if (n > 0) {
+    return rec(f, n - 1) + f(n);
+  } else {
+    return 0;
+  }
+}
+function twice(x: number): number {
+  return x * 2;
+}
+function offset(k: number): number {
+  function add(x: number): number {
+    return x + k;
+  }
+  return both(add) + f2(add);
+}
+function f2(f: (x: number) => number): number {
+  return both(f) + f(0);
+}
+console.log(both(twice));
+console.log(rec(twice, 3));
+console.log(offset(10));
+"#;
+    let rust = compile(src).unwrap();
+    assert!(rust.contains("    return apply(&f, 1i32) + apply(&f, 2i32);\n"), "got:\n{rust}");
+    assert!(rust.contains("    return both(&add) + f2(&add);\n"), "got:\n{rust}");
+    assert!(rust.contains("println!(\"{}\", both(&twice));"), "got:\n{rust}");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "6\n12\n56\n");
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use arkts2rust::cargo::{manifest, package_name, project};
+use arkts2rust::{compile_with_options, CodegenOptions, CompileOptions, Edition, ExternBindings, OutputFile, OutputMode};
+
+fn single(code: String) -> Vec<OutputFile> {
+    vec![OutputFile {
+        path: "output.rs".into(),
+        code,
+    }]
+}
+
+#[test]
+fn binary_project_layout() {
+    let files = vec![
+        OutputFile {
+            path: "output.rs".into(),
+            code: "mod util;\n\nfn main() {\n    util::roll();\n}\n".to_string(),
+        },
+        OutputFile {
+            path: "util.rs".into(),
+            code: "pub(crate) fn roll() -> f64 {\n    rand::random::<f64>()\n}\n".to_string(),
+        },
+    ];
+    let options = CodegenOptions {
+        use_rand: true,
+        ..CodegenOptions::default()
+    };
+    let out = project("dice", &files, OutputMode::Binary, &ExternBindings::new(), &options);
+    let paths: Vec<&Path> = out.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(paths, [Path::new("Cargo.toml"), Path::new("src/main.rs"), Path::new("src/util.rs")]);
+    assert_eq!(
+        out[0].code,
+        "[package]\nname = \"dice\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nrand = \"0.8\"\n"
+    );
+    assert_eq!(out[2].code, files[1].code);
+}
+
+#[test]
+fn manifest_follows_options() {
+    let options = CompileOptions {
+        mode: OutputMode::NativeModule,
+        codegen: CodegenOptions {
+            use_serde_json: true,
+            edition: Edition::E2018,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    let code = compile_with_options("export function dump(n: number): string { return JSON.stringify(n); }", &options).unwrap();
+    let toml = manifest("native", &single(code), options.mode, &options.externs, &options.codegen);
+    assert_eq!(
+        toml,
+        "[package]\nname = \"native\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n\
+         [dependencies]\nserde_json = \"1\"\nnapi-ohos = \"1\"\nnapi-derive-ohos = \"1\"\n"
+    );
+
+    let out = project("native", &[], OutputMode::Library, &options.externs, &options.codegen);
+    assert_eq!(out.len(), 1);
+
+    // 没有用到的依赖不写
+    let toml = manifest("plain", &single(String::from("fn main() {}\n")), OutputMode::Binary, &options.externs, &options.codegen);
+    assert!(toml.ends_with("[dependencies]\n"), "got:\n{toml}");
+}
+
+#[test]
+fn configured_crates_are_left_to_the_user() {
+    let options = CompileOptions {
+        externs: ExternBindings::from([("now".to_string(), "my_runtime::clock::now".to_string())]),
+        ..CompileOptions::default()
+    };
+    let code = compile_with_options("declare function now(): number;\nconsole.log(now());", &options).unwrap();
+    let toml = manifest("clock", &single(code), OutputMode::Binary, &options.externs, &options.codegen);
+    assert!(
+        toml.ends_with("[dependencies]\n# my_runtime = \"*\"  # used by the configured Rust paths: fill in a version or path\n"),
+        "got:\n{toml}"
+    );
+}
+
+#[test]
+fn package_names() {
+    assert_eq!(package_name("My App"), "my-app");
+    assert_eq!(package_name("hello_world"), "hello_world");
+    assert_eq!(package_name("2048"), "app-2048");
+    assert_eq!(package_name("..."), "app");
+}
+
+#[test]
+fn runtime_crate_is_vendored() {
+    let options = CodegenOptions {
+        runtime: Some(arkts2rust::runtime::CRATE_NAME.to_string()),
+        ..CodegenOptions::default()
+    };
+    let files = single("use arkts_runtime::{__ark_random};\n\nfn main() {\n    println!(\"{}\", __ark_random());\n}\n".to_string());
+    let out = project("dice", &files, OutputMode::Binary, &ExternBindings::new(), &options);
+    let paths: Vec<&Path> = out.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        [
+            Path::new("Cargo.toml"),
+            Path::new("src/main.rs"),
+            Path::new("arkts_runtime/Cargo.toml"),
+            Path::new("arkts_runtime/src/lib.rs")
+        ]
+    );
+    assert!(out[0].code.ends_with("[dependencies]\narkts_runtime = { path = \"arkts_runtime\" }\n"), "got:\n{}", out[0].code);
+
+    // 没有用到运行时 crate 时不放进项目
+    let out = project("plain", &single(String::from("fn main() {}\n")), OutputMode::Binary, &ExternBindings::new(), &options);
+    assert_eq!(out.len(), 2);
+}
@@ -0,0 +1,122 @@
+use std::fs;
+
+use arkts2rust::{
+    compile, compile_tree, compile_with_options, lex, parse_program, CodegenOptions, CompileOptions, ExternBindings, OutputMode,
+    ResolveConfig,
+};
+
+const SRC: &str = r#"// 计算平方
+/* 多行
+ * 块注释
+ */
+export function square(n: number): number {
+  // 直接相乘
+  return n * n; // 结果
+}
+
+/// 不是文档注释
+let x = square(3); // 九
+if (x > 5) { // 大于五
+  console.log(x);
+} else {
+  console.log(0);
+} // if 结束
+"#;
+
+fn options() -> CodegenOptions {
+    CodegenOptions {
+        comments: true,
+        ..CodegenOptions::default()
+    }
+}
+
+fn compile_commented(src: &str) -> String {
+    let options = CompileOptions {
+        codegen: options(),
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options).unwrap()
+}
+
+#[test]
+fn comments_are_attached_to_tokens() {
+    let tokens = lex("let a = 1; // 一\n// 二\nlet b = 2;").unwrap();
+    assert_eq!(tokens[4].trailing.len(), 1);
+    assert_eq!(tokens[4].trailing[0].text, "// 一");
+    assert_eq!(tokens[5].leading[0].text, "// 二");
+    assert_eq!(tokens[5].leading[0].lines(), vec![" 二"]);
+
+    let tokens = lex("/**\n * 第一行\n *\n * 第二行\n */\nf();").unwrap();
+    assert!(tokens[0].leading[0].is_block());
+    assert_eq!(tokens[0].leading[0].lines(), vec!["第一行", "", "第二行"]);
+}
+
+#[test]
+fn comments_do_not_change_the_ast() {
+    let plain = "function f(): number {\n  return 1;\n}\nlet a = f();";
+    let commented = "// f\nfunction f(): number {\n  /* 一 */ return 1; // 返回\n}\nlet a = f(); // a";
+    assert_eq!(parse_program(commented).unwrap(), parse_program(plain).unwrap());
+    assert_eq!(compile(commented).unwrap(), compile(plain).unwrap());
+}
+
+#[test]
+fn comments_next_to_statements() {
+    assert_eq!(
+        compile_commented(SRC),
+        "// 计算平方
+// 多行
+// 块注释
+fn square(n: i32) -> i32 {
+    // 直接相乘
+    return n * n; // 结果
+}
+
+fn main() {
+    // / 不是文档注释
+    let x = square(3i32); // 九
+    if x > 5i32 {
+        // 大于五
+        println!(\"{}\", x);
+    } else {
+        println!(\"{}\", 0i32);
+    } // if 结束
+}
+"
+    );
+}
+
+#[test]
+fn comments_inside_expressions_stay_with_their_statement() {
+    let rust = compile_commented("let a = Math.max(\n  1, // 一\n  2\n); // 二\nconsole.log(a);");
+    assert!(rust.contains("    let a = 1i32.max(2i32); // 一 // 二\n    println!(\"{}\", a);\n"), "got:\n{rust}");
+
+    let rust = compile_commented("let a = 1 /* c */ + 2;\n// 输出\nconsole.log(a);");
+    assert!(rust.contains("    let a = 1i32 + 2i32; // c\n    // 输出\n    println!(\"{}\", a);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn comments_in_module_tree() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_comments_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("util.ets"), "// 乘二\nexport function twice(n: number): number {\n  return n * 2;\n}").unwrap();
+    fs::write(dir.join("main.ets"), "import { twice } from './util';\n// 输出 4\nconsole.log(twice(2));").unwrap();
+    let files = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &options(),
+    )
+    .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert!(files[0].code.contains("fn main() {\n    // 输出 4\n    println!("), "got:\n{}", files[0].code);
+    assert!(files[1].code.contains("// 乘二\npub(crate) fn twice(n: i32) -> i32 {\n"), "got:\n{}", files[1].code);
+}
@@ -0,0 +1,105 @@
+//! 集成测试共用的辅助函数：用 rustc 编译（并运行）生成的代码。测试文件里 `mod common;` 引入。
+//!
+//! 机器上没有 rustc 时，这些函数返回 None / 什么都不做，调用它们的测试直接跳过编译检查。
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// 能不能运行 rustc。
+pub fn has_rustc() -> bool {
+    Command::new("rustc").arg("--version").output().is_ok()
+}
+
+/// 临时目录下一个不会重复的路径：`arkts2rust_{name}_{进程号}_{纳秒}`，不创建文件。
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "arkts2rust_{name}_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ))
+}
+
+/// 用 rustc 编译 `src`，输出到 `out`，`args` 是额外的参数（`--edition`、`--crate-type` 等）。编译失败时 panic，带上 rustc 的输出。
+pub fn rustc(src: &Path, out: &Path, args: &[&str]) {
+    let result = Command::new("rustc").args(args).arg(src).arg("-o").arg(out).output().unwrap();
+    assert!(
+        result.status.success(),
+        "rustc failed: {}\n{}",
+        String::from_utf8_lossy(&result.stdout),
+        String::from_utf8_lossy(&result.stderr)
+    );
+}
+
+/// 确认生成的程序能通过 rustc 编译。
+pub fn assert_rustc_compiles(rust: &str) {
+    assert_rustc_compiles_with(rust, &[]);
+}
+
+/// 带额外 rustc 参数的 `assert_rustc_compiles`，例如库代码用 `["--crate-type", "lib"]`。
+pub fn assert_rustc_compiles_with(rust: &str, args: &[&str]) {
+    if !has_rustc() {
+        return;
+    }
+    let path = temp_path("check").with_extension("rs");
+    let out = path.with_extension("out");
+    fs::write(&path, rust).unwrap();
+    let result = std::panic::catch_unwind(|| rustc(&path, &out, args));
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&out);
+    if let Err(e) = result {
+        std::panic::resume_unwind(e);
+    }
+}
+
+/// 编译并运行生成的程序，返回运行结果（标准输出、标准错误）；没有 rustc 时为 None。
+pub fn compile_and_run(rust: &str) -> Option<Output> {
+    compile_and_run_with(rust, &[])
+}
+
+/// 编译并运行生成的程序，返回标准输出；没有 rustc 时为 None。
+pub fn run_stdout(rust: &str) -> Option<String> {
+    compile_and_run(rust).map(|run| String::from_utf8_lossy(&run.stdout).into_owned())
+}
+
+/// 带额外 rustc 参数的 `compile_and_run`。
+pub fn compile_and_run_with(rust: &str, args: &[&str]) -> Option<Output> {
+    if !has_rustc() {
+        return None;
+    }
+    let path = temp_path("run").with_extension("rs");
+    fs::write(&path, rust).unwrap();
+    let run = run_file(&path, args);
+    let _ = fs::remove_file(&path);
+    run
+}
+
+/// 编译并运行已经写到磁盘上的 crate 根 `main`（模块树的其它文件在它旁边）；没有 rustc 时为 None。
+pub fn run_file(main: &Path, args: &[&str]) -> Option<Output> {
+    if !has_rustc() {
+        return None;
+    }
+    let exe = main.with_extension("exe");
+    let result = std::panic::catch_unwind(|| rustc(main, &exe, args));
+    let run = result.is_ok().then(|| Command::new(&exe).output().unwrap());
+    let _ = fs::remove_file(&exe);
+    if let Err(e) = result {
+        std::panic::resume_unwind(e);
+    }
+    run
+}
+
+/// 在临时目录（见 `temp_path`）里写出一组 `(相对路径, 源码)` 文件，返回目录路径。
+pub fn write_project(files: &[(&str, &str)]) -> PathBuf {
+    let dir = temp_path("project");
+    for (name, src) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,42 @@
+use arkts2rust::{compile, compile_with_options, CodegenOptions, CompileOptions, Level, NumberMode, OutputMode, ParseOptions};
+
+#[test]
+fn default_options_match_compile() {
+    let src = "function add(a: number, b: number): number { return a + b; }\nconsole.log(add(1, 2));";
+    assert_eq!(compile_with_options(src, &CompileOptions::default()).unwrap(), compile(src).unwrap());
+}
+
+#[test]
+fn options_reach_every_stage() {
+    let mut options = CompileOptions {
+        mode: OutputMode::Library,
+        parse: ParseOptions { asi: true },
+        codegen: CodegenOptions {
+            number_mode: NumberMode::F64,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    options.externs.insert("nowMs".to_string(), "crate::clock::now_ms".to_string());
+    let rust = compile_with_options(
+        "declare function nowMs(): number\nexport function later(d: number): number { return nowMs() + d }",
+        &options,
+    )
+    .unwrap();
+    assert!(rust.contains("pub fn later(d: f64) -> f64 {"), "got:\n{rust}");
+    assert!(rust.contains("crate::clock::now_ms()"), "got:\n{rust}");
+    assert!(!rust.contains("fn main()"), "got:\n{rust}");
+
+    // deny 的 lint 让编译失败
+    let src = "let a = 1;\na = a;\nconsole.log(a);";
+    assert!(compile_with_options(src, &CompileOptions::default()).is_ok());
+    options = CompileOptions::default();
+    options.lints.set("self_assignment", Level::Deny);
+    assert_eq!(compile_with_options(src, &options).unwrap_err().code, "SelfAssignment");
+
+    // truthy 条件
+    let src = "let n = 1;\nif (n) { console.log(n); } else { }";
+    assert_eq!(compile_with_options(src, &CompileOptions::default()).unwrap_err().code, "ConditionMustBeBool");
+    options.codegen.truthy_conditions = true;
+    assert!(compile_with_options(src, &options).unwrap().contains("if n != 0 {"));
+}
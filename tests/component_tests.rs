@@ -0,0 +1,140 @@
+use arkts2rust::ast::{Expr, FieldDecl, Literal, TypeAnn, UiNode};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+#[test]
+fn parse_component_struct() {
+    let p = parse_program(
+        r#"@Entry
+@Component
+export struct Index {
+  @State message: string = "Hello";
+  private count: number;
+  build() {
+    console.log(this.message);
+  }
+}"#,
+    )
+    .unwrap();
+    let c = &p.components[0];
+    assert_eq!(c.name, "Index");
+    assert!(c.is_export);
+    assert!(c.has_decorator("Entry") && c.has_decorator("Component"));
+    assert_eq!(c.fields.len(), 2);
+    assert_eq!(c.fields[0].name, "message");
+    assert_eq!(c.fields[0].decorators[0].name, "State");
+    assert_eq!(c.fields[0].init, Some(Expr::Literal(Literal::String("Hello".into()), Default::default())));
+    assert_eq!(
+        c.fields[1],
+        FieldDecl {
+            decorators: vec![],
+            name: "count".into(),
+            ty: Some(TypeAnn::Number),
+            init: None,
+            span: Default::default(),
+        }
+    );
+    assert!(c.methods.is_empty());
+    assert!(matches!(c.build.as_deref(), Some([UiNode::Stmt(_)])), "{:?}", c.build);
+    // `struct` 不是关键字，普通标识符照常使用
+    parse_program("let struct = 1; console.log(struct);").unwrap();
+}
+
+#[test]
+fn codegen_component_struct_and_impl() {
+    let rust = compile(
+        r#"@Component
+struct Counter {
+  label: string;
+  count = 0;
+  add(n: number): number {
+    this.count = this.count + n;
+    return this.count;
+  }
+  build() {
+    console.log(this.label, this.add(1));
+  }
+}"#,
+    )
+    .unwrap();
+    assert!(rust.contains("struct Counter {\n    label: String,\n    count: i32,\n}\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "impl Counter {\n    fn new(label: String) -> Self {\n        Self {\n            label,\n            \
+             count: 0i32,\n        }\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    fn add(&mut self, n: i32) -> i32 {\n        self.count = self.count + n;\n        return self.count;\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("        println!(\"{} {}\", self.label.clone(), self.add(1i32));\n"),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("Counter::new()"), "got:\n{rust}");
+}
+
+#[test]
+fn error_component_misuse() {
+    for (src, code) in [
+        ("@Component struct A { x; build() {} }", "MissingFieldType"),
+        ("@Component struct A { \"x\": number = 1; }", "UnsupportedComponentMember"),
+        ("@Component struct A { @State }", "MisplacedDecorator"),
+        ("@Entry @Component struct A { build() {} } @Entry @Component struct B { build() {} }", "DuplicateEntry"),
+        ("@Entry @Component struct A { x: number; build() {} }", "EntryFieldNeedsInitializer"),
+        ("@Component struct A { build() { throw \"no\"; } }", "UnsupportedComponentThrow"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn unknown_component_decorators_warn() {
+    let p = parse_program("@Entry @Preview @Component struct A { @Watch('f') x: number = 1; build() {} }").unwrap();
+    let codes: Vec<&str> = p.warnings.iter().map(|w| w.code.as_str()).collect();
+    assert_eq!(codes, vec!["IgnoredDecorator"; 2]);
+}
+
+#[test]
+fn generated_entry_page_runs() {
+    let src = r#"
+@Entry
+@Component
+struct Index {
+  message: string = "Hello";
+  private count: number = 0;
+
+  aboutToAppear(): void {
+    this.count = this.count + 1;
+    console.log("appear", this.count);
+  }
+
+  bump(n: number): number {
+    this.count = this.count + n;
+    return this.count;
+  }
+
+  build() {
+    console.log(this.message, this.bump(2));
+  }
+
+  aboutToDisappear(): void {
+    console.log("bye", this.count);
+  }
+}
+console.log("start");
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "start\nappear 1\nHello 3\nbye 3\n");
+}
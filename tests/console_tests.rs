@@ -0,0 +1,156 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, NumberMode, OutputMode};
+
+mod common;
+
+fn compile_debug(src: &str) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        debug_format: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn codegen_display_placeholders() {
+    let rust = compile(
+        r#"function f(n: number | null, ...xs: number[]): void { console.log("n =", n, null, xs, true); }"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "    println!(\"{} {} {} {:?} {}\", String::from(\"n =\"), \
+             __ark_fmt_nullable(&n, \"null\"), \"null\", xs, true);\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_debug_format_option() {
+    let rust = compile_debug(r#"let s = "hi"; console.log(s, 1);"#);
+    assert!(rust.contains("    println!(\"{:?} {:?}\", s, 1i32);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_assertions() {
+    let rust = compile(
+        r#"function check(n: number): void {
+  console.assert(n > 0);
+  console.assert(n < 10, "too big:", n);
+  assert(n != 5, "five");
+}"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "    assert!(n > 0i32);\n    assert!(n < 10i32, \"{} {}\", String::from(\"too big:\"), n);\n    \
+             assert!(n != 5i32, \"{}\", String::from(\"five\"));\n"
+        ),
+        "got:\n{rust}"
+    );
+
+    let program = parse_program("console.assert(true, 1);").unwrap();
+    let options = CodegenOptions {
+        debug_assert: true,
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    assert!(rust.contains("    debug_assert!(true, \"{}\", 1i32);\n"), "got:\n{rust}");
+
+    for src in ["console.assert();", "assert(true, \"a\", \"b\");"] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "AssertArgCount", "{src}");
+    }
+    let rust = compile("function assert(ok: boolean): void {} assert(false);").unwrap();
+    assert!(rust.contains("    assert(false);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn generated_console_output_matches_js() {
+    let src = r#"
+function half(n: number): number | null {
+  if (n % 2 == 0) {
+    return n / 2;
+  } else {
+    return null;
+  }
+}
+let name = "ArkTS";
+console.log("hello", name, 42, -7, false);
+console.log(half(8), half(3));
+console.log(undefined);
+console.error("oops:", "bad \"input\"");
+console.assert(half(4) == 2, "half");
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "hello ArkTS 42 -7 false\n4 null\nundefined\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&run.stderr), "oops: bad \"input\"\n");
+}
+
+#[test]
+fn non_finite_numbers_and_undefined_print_like_js() {
+    let src = r#"
+function find(key: string): string | undefined {
+  if (key == "a") {
+    return "A";
+  } else {
+    return undefined;
+  }
+}
+let zero = 0;
+let n: number | null = null;
+console.log(1 / zero, -1 / zero, zero / zero, 2.5);
+console.log(find("a"), find("b"), n);
+console.log(String(1 / zero), (zero / zero).toString());
+"#;
+    let options = CodegenOptions {
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    let program = parse_program(src).unwrap();
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    assert!(rust.contains("__ark_fmt_nullable(&find(String::from(\"b\")), \"undefined\")"), "got:\n{rust}");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "Infinity -Infinity NaN 2.5\nA undefined null\nInfinity NaN\n"
+    );
+}
+
+#[test]
+fn numbers_switch_to_exponent_form_like_js() {
+    let src = r#"
+let zero = 0;
+let big = 1000000000 * 1000000000 * 1000;
+console.log(big, big * 100, big / 10, -big);
+console.log(0.000001, 0.00000015, 0.0000015, -0.000001 / 10);
+console.log(-zero, String(-zero), 123.456, -2.5);
+"#;
+    let options = CodegenOptions {
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    let program = parse_program(src).unwrap();
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "1e+21 1e+23 100000000000000000000 -1e+21\n0.000001 1.5e-7 0.0000015 -1e-7\n0 0 123.456 -2.5\n"
+    );
+}
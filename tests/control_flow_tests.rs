@@ -1,9 +1,7 @@
-use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-
 use arkts2rust::{compile, parse_program};
 
+mod common;
+
 fn assert_codegen(src: &str, expected: &str) {
     let got = compile(src).unwrap();
     assert_eq!(got, expected);
@@ -12,16 +10,16 @@ fn assert_codegen(src: &str, expected: &str) {
 #[test]
 fn if_else_basic_assign() {
     assert_codegen(
-        "if (true) x=1; else x=2;",
-        "fn main() {\n    if true {\n        x = 1i32;\n    } else {\n        x = 2i32;\n    }\n}\n",
+        "let x = 0; if (true) x=1; else x=2;",
+        "fn main() {\n    let mut x = 0i32;\n    if true {\n        x = 1i32;\n    } else {\n        x = 2i32;\n    }\n}\n",
     );
 }
 
 #[test]
 fn if_else_with_block_branches() {
     assert_codegen(
-        "if (false) { x=1; y=2; } else { x=3; }",
-        "fn main() {\n    if false {\n        x = 1i32;\n        y = 2i32;\n    } else {\n        x = 3i32;\n    }\n}\n",
+        "let x = 0; let y = 0; if (false) { x=1; y=2; } else { x=3; }",
+        "fn main() {\n    let mut x = 0i32;\n    let mut y = 0i32;\n    if false {\n        x = 1i32;\n        y = 2i32;\n    } else {\n        x = 3i32;\n    }\n}\n",
     );
 }
 
@@ -45,7 +43,7 @@ fn if_else_nested_if_in_else() {
 fn if_else_then_single_stmt_is_wrapped_in_rust_block() {
     assert_codegen(
         "if (true) console.log(1); else console.log(2);",
-        "fn main() {\n    if true {\n        println!(\"{:?}\", 1i32);\n    } else {\n        println!(\"{:?}\", 2i32);\n    }\n}\n",
+        "fn main() {\n    if true {\n        println!(\"{}\", 1i32);\n    } else {\n        println!(\"{}\", 2i32);\n    }\n}\n",
     );
 }
 
@@ -97,13 +95,22 @@ fn error_if_missing_else() {
 
 #[test]
 fn error_condition_must_be_bool() {
-    let err = parse_program("if (1) x=1; else x=2;").expect_err("truthy is not allowed");
+    let err = compile("let x = 0;\nif (1) x=1; else x=2;").expect_err("truthy is not allowed");
+    assert_eq!(err.code, "ConditionMustBeBool");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 5));
+
+    // 类型检查知道变量的类型：number 变量不能当条件，括号里的比较可以
+    let err = compile("let n = 1;\nif (n) { n = 2; } else { }").expect_err("number is not bool");
     assert_eq!(err.code, "ConditionMustBeBool");
+    compile("let a = 1;\nlet b = 2;\nif ((a + b) * 2 > (b - a)) { a = 3; } else { }\nconsole.log(a);").unwrap();
+
+    // 解析器只管结构
+    parse_program("if (1) x=1; else x=2;").unwrap();
 }
 
 #[test]
 fn error_while_condition_must_be_bool() {
-    let err = parse_program("while (1+2) { }").expect_err("arith expression is not bool");
+    let err = compile("while (1+2) { }").expect_err("arith expression is not bool");
     assert_eq!(err.code, "ConditionMustBeBool");
 }
 
@@ -118,38 +125,5 @@ return;
 
     let rust = compile(src).unwrap();
 
-    if Command::new("rustc").arg("--version").output().is_err() {
-        return;
-    }
-
-    let mut path: PathBuf = std::env::temp_dir();
-    let file_name = format!(
-        "arkts2rust_step5_{}_{}.rs",
-        std::process::id(),
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos()
-    );
-    path.push(file_name);
-
-    let mut exe_path = path.clone();
-    exe_path.set_extension("");
-
-    fs::write(&path, rust).unwrap();
-    let out = Command::new("rustc")
-        .arg(&path)
-        .arg("-o")
-        .arg(&exe_path)
-        .output()
-        .unwrap();
-    let _ = fs::remove_file(&path);
-    let _ = fs::remove_file(&exe_path);
-
-    assert!(
-        out.status.success(),
-        "rustc failed: {}\n{}",
-        String::from_utf8_lossy(&out.stdout),
-        String::from_utf8_lossy(&out.stderr)
-    );
+    common::assert_rustc_compiles(&rust);
 }
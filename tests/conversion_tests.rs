@@ -0,0 +1,103 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, NanMode, OutputMode};
+
+mod common;
+
+fn compile_nan(src: &str, nan: NanMode) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        nan,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn codegen_conversions() {
+    let rust = compile(
+        r#"function f(s: string, n: number, b: boolean): void {
+  let a = parseInt(s) + parseInt("ff", 16);
+  let x = Number(b) + Number(n + 1);
+  let t = String(n);
+  let u = b.toString();
+}"#,
+    )
+    .unwrap();
+    assert!(rust.starts_with("fn __ark_parse_int(s: &str, radix: i32) -> Option<i32> {\n"), "got:\n{rust}");
+    assert!(!rust.contains("fn __ark_number"), "got:\n{rust}");
+    assert!(
+        rust.contains("    let a = __ark_parse_int(&s, 0).unwrap_or(0) + __ark_parse_int(\"ff\", 16i32).unwrap_or(0);\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    let x = i32::from(b) + (n + 1i32);\n"), "got:\n{rust}");
+    assert!(rust.contains("    let t = n.to_string();\n"), "got:\n{rust}");
+    assert!(rust.contains("    let u = b.to_string();\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nan_modes() {
+    let src = "function read(s: string): number { return Number(s); }";
+    let rust = compile_nan(src, NanMode::Panic);
+    assert!(
+        rust.contains("    return __ark_number(&s).map(|n| n as i32).expect(\"Number: not a number\");\n"),
+        "got:\n{rust}"
+    );
+
+    let rust = compile_nan(
+        r#"function read(s: string): number { return Number(s); }
+try {
+  console.log(read("x"));
+} catch (e) {
+  console.log("NaN");
+}"#,
+        NanMode::Throw,
+    );
+    assert!(rust.contains("fn read(s: String) -> Result<i32, ArkError> {"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "    return Ok(__ark_number(&s).map(|n| n as i32).ok_or_else(|| ArkError::Error(String::from(\"Number: not a number\")))?);\n"
+        ),
+        "got:\n{rust}"
+    );
+
+    // 同名的用户函数不会被当成内置函数
+    let rust = compile_nan("function Number(s: string): number { return 1; } let n = Number(\"x\");", NanMode::Throw);
+    assert!(rust.contains("fn Number(s: String) -> i32 {"), "got:\n{rust}");
+}
+
+#[test]
+fn error_conversion_misuse() {
+    for (src, code) in [
+        ("let n = parseInt();", "ConversionArgCount"),
+        ("let n = Number(\"1\", 2);", "ConversionArgCount"),
+        ("let n = 1; let s = n.toString(2);", "ConversionArgCount"),
+        ("let d = new Date(); let s = String(d);", "UnsupportedConversion"),
+        ("let n = parseInt(true);", "UnsupportedConversion"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_conversion_code_runs() {
+    let src = r#"
+let s = " 42px";
+console.log(parseInt(s), parseInt("-0x1F"), parseInt("z", 36), parseInt("abc"), parseInt(7), parseInt("ff", 16));
+console.log(Math.round(parseFloat("3.25e2xyz")), parseFloat(".5") < 1, Math.floor(parseFloat("-2.7")));
+console.log(Number("  12 "), Number(""), Number("12px"), Number(true), Number("1e3"));
+let n = 12;
+let m: number | null = null;
+console.log(String(n), n.toString().length, false.toString(), String(undefined), String("a, b".split(" ")), String(m));
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "42 -31 35 0 7 255\n325 true -3\n12 0 0 1 1000\n12 2 false undefined a,,b null\n"
+    );
+}
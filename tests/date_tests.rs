@@ -0,0 +1,107 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::ast::{NullKind, Param, TypeAnn};
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, OutputMode};
+
+mod common;
+
+fn compile_chrono(src: &str) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        use_chrono: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn parse_date_type() {
+    let p = parse_program("function f(d: Date | null): void {}").unwrap();
+    assert_eq!(
+        p.funcs[0].params,
+        vec![Param {
+            name: "d".into(),
+            ty: Some(TypeAnn::Nullable(Box::new(TypeAnn::Date), NullKind::Null)),
+            is_rest: false,
+            span: Default::default(),
+        }]
+    );
+}
+
+#[test]
+fn codegen_date_with_std() {
+    let rust = compile("function f(d: Date): string { return d.toISOString(); } let t = Date.now(); let d = new Date();")
+        .unwrap();
+    assert!(rust.starts_with("type ArkDate = std::time::SystemTime;\n\nfn __ark_millis("), "got:\n{rust}");
+    assert!(rust.contains("fn f(d: ArkDate) -> String {\n    return __ark_iso_string(d);\n}"), "got:\n{rust}");
+    assert!(rust.contains("    let t = __ark_millis(std::time::SystemTime::now());\n"), "got:\n{rust}");
+    assert!(rust.contains("    let d = std::time::SystemTime::now();\n"), "got:\n{rust}");
+    assert!(!rust.contains("__ark_from_millis"), "got:\n{rust}");
+
+    let rust = compile("console.log(1);").unwrap();
+    assert!(!rust.contains("ArkDate"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_date_with_chrono() {
+    let rust = compile_chrono(
+        "let start = Date.now(); let d: Date = new Date(1000); \
+         console.log(d, d.getTime() - start < 5);",
+    );
+    assert!(rust.starts_with("type ArkDate = chrono::DateTime<chrono::Utc>;\n\n"), "got:\n{rust}");
+    assert!(!rust.contains("__ark_"), "got:\n{rust}");
+    assert!(
+        rust.contains("    let start = (chrono::Utc::now().timestamp_millis() as f64);\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    let d: ArkDate = chrono::DateTime::from_timestamp_millis(1000i32 as i64).unwrap();\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    println!(\"{} {}\", d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true), \
+             (d.timestamp_millis() as f64) - start < 5.0);\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_date_misuse() {
+    for (src, code) in [
+        ("let t = Date.parse(\"2024\");", "UnknownDateFunction"),
+        ("let t = Date.now(1);", "DateArgCount"),
+        ("let d = new Date(\"2024-01-01\");", "UnsupportedDateArgs"),
+        ("let d = new Date(2024, 1);", "DateArgCount"),
+        ("let d = new Date(); let y = d.getFullYear();", "UnknownDateMethod"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_date_code_runs() {
+    let src = r#"
+function stamp(d: Date): string {
+  return d.toISOString();
+}
+let start = Date.now();
+console.log(new Date(0), stamp(new Date(86400000 * 24 + 1234)));
+console.log(new Date(-1).toISOString(), new Date((Date.now() * 0 + 951782400) * 1000));
+let d: Date = new Date(start);
+let elapsed = Math.floor(Date.now() - d.getTime());
+console.log(elapsed >= 0 && elapsed < 1000, new Date().getTime() >= start);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "1970-01-01T00:00:00.000Z 1970-01-25T00:00:01.234Z\n\
+         1969-12-31T23:59:59.999Z 2000-02-29T00:00:00.000Z\ntrue true\n"
+    );
+}
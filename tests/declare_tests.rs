@@ -0,0 +1,142 @@
+use std::fs;
+
+use arkts2rust::{
+    compile, compile_as, compile_file, compile_file_with, parse_program, CodegenOptions, ExternBindings, OutputMode,
+    ResolveConfig,
+};
+
+mod common;
+
+#[test]
+fn parse_declare_function() {
+    let p = parse_program(
+        "declare function now(): number; export declare function log(msg: string): void; \
+         declare class Timeout extends Error {}",
+    )
+    .unwrap();
+    let names: Vec<(&str, bool)> = p.declare_funcs.iter().map(|f| (f.name.as_str(), f.is_export)).collect();
+    assert_eq!(names, vec![("now", false), ("log", true)]);
+    assert!(p.declare_funcs.iter().all(|f| f.body.stmts.is_empty()));
+    assert!(p.funcs.is_empty());
+    assert_eq!(p.classes[0].name, "Timeout");
+}
+
+#[test]
+fn parse_declare_class_members() {
+    let p = parse_program(
+        "declare class Foo {\n  bar(x: number): string;\n  static create(): void;\n  private readonly name: string;\n}",
+    )
+    .unwrap();
+    let methods: Vec<(&str, usize)> = p.classes[0].methods.iter().map(|m| (m.name.as_str(), m.params.len())).collect();
+    assert_eq!(methods, [("bar", 1), ("create", 0)]);
+    assert!(p.classes[0].methods.iter().all(|m| m.body.stmts.is_empty()));
+    assert_eq!(p.classes[0].fields[0].name, "name");
+
+    // 普通 class 的类体仍然必须为空，签名也要以分号结尾
+    let err = parse_program("class Foo { bar(x: number): string; }").expect_err("not ambient");
+    assert_eq!(err.code, "UnsupportedClassMember");
+    let err = parse_program("declare class Foo { bar(): void {} }").expect_err("body");
+    assert_eq!(err.code, "MissingSemicolon");
+}
+
+#[test]
+fn declare_function_requires_semicolon() {
+    let err = parse_program("declare function now(): number {}").expect_err("body");
+    assert_eq!(err.code, "MissingSemicolon");
+}
+
+#[test]
+fn declare_only_at_top_level() {
+    let err = parse_program("{ declare function now(): number; }").expect_err("nested declare");
+    assert_eq!(err.code, "DeclareNotAllowedHere");
+}
+
+#[test]
+fn unbound_declare_emits_stub() {
+    let rust = compile("declare function add(a: number, b: number): number; console.log(add(1, 2));").unwrap();
+    assert!(
+        rust.starts_with(
+            "fn add(_a: i32, _b: i32) -> i32 {\n    unimplemented!(\"extern function `add`\")\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("add(1i32, 2i32)"), "got:\n{rust}");
+}
+
+#[test]
+fn library_mode_exported_declare_is_pub() {
+    let rust = compile_as("export declare function now(): number;", OutputMode::Library).unwrap();
+    assert_eq!(rust, "pub fn now() -> i32 {\n    unimplemented!(\"extern function `now`\")\n}\n\n");
+}
+
+#[test]
+fn import_from_declaration_file() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { max } from './math'; console.log(max(1, 2));"),
+        ("math.d.ets", "export declare function max(a: number, b: number): number;"),
+    ]);
+    let mut externs = ExternBindings::new();
+    externs.insert("max".to_string(), "std::cmp::max".to_string());
+    let rust = compile_file_with(dir.join("main.ets"), OutputMode::Binary, &externs, &ResolveConfig::default(), &CodegenOptions::default()).unwrap();
+    assert!(
+        rust.starts_with("fn max(a: i32, b: i32) -> i32 {\n    std::cmp::max(a, b)\n}\n"),
+        "got:\n{rust}"
+    );
+
+    let mut externs = ExternBindings::new();
+    externs.insert("min".to_string(), "std::cmp::min".to_string());
+    let err = compile_file_with(dir.join("main.ets"), OutputMode::Binary, &externs, &ResolveConfig::default(), &CodegenOptions::default()).expect_err("unknown");
+    assert_eq!(err.code, "UnknownExternBinding");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn declaration_file_with_class_signatures() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { now } from './api';\nconsole.log(now());"),
+        (
+            "api.d.ets",
+            "export declare function now(): number;\nexport declare class Clock {\n  tick(ms: number): void;\n  label: string;\n}",
+        ),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(rust.contains("println!(\"{}\", now());"), "got:\n{rust}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn declaration_file_rejects_code() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './lib';"),
+        ("lib.d.ets", "export function f(): void {}"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("body in .d.ets");
+    assert_eq!(err.code, "CodeInDeclarationFile");
+    assert!(err.file.unwrap().ends_with("lib.d.ets"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn generated_binding_code_runs() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            "import { max, min } from './math';\nconsole.log(max(3, 7));\nconsole.log(min(3, 7));",
+        ),
+        (
+            "math.d.ets",
+            "export declare function max(a: number, b: number): number;\n\
+             export declare function min(a: number, b: number): number;",
+        ),
+    ]);
+    let mut externs = ExternBindings::new();
+    externs.insert("max".to_string(), "std::cmp::max".to_string());
+    externs.insert("min".to_string(), "std::cmp::min".to_string());
+    let rust = compile_file_with(dir.join("main.ets"), OutputMode::Binary, &externs, &ResolveConfig::default(), &CodegenOptions::default()).unwrap();
+    let _ = fs::remove_dir_all(dir);
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "7\n3\n");
+}
@@ -0,0 +1,53 @@
+use arkts2rust::{compile, lex, parse_program, Decorator, Expr, Literal, TokenKind};
+
+#[test]
+fn lex_at_sign() {
+    let kinds: Vec<TokenKind> = lex("@Entry").unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::At, TokenKind::Ident("Entry".into())]);
+}
+
+#[test]
+fn parse_decorators_on_functions_and_classes() {
+    let p = parse_program(
+        "@Entry @Component\nexport class Page {}\n@Watch('onChange') function f(): void { @Builder function g(): void {} }",
+    )
+    .unwrap();
+    let names: Vec<&str> = p.classes[0].decorators.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["Entry", "Component"]);
+    assert!(p.classes[0].is_export);
+    let watch = &p.funcs[0].decorators[0];
+    assert_eq!(watch.name, "Watch");
+    assert_eq!(watch.args, vec![Expr::Literal(Literal::String("onChange".into()), Default::default())]);
+    assert_eq!((watch.span.start_line, watch.span.start_col, watch.span.end_col), (3, 1, 19));
+    match &p.funcs[0].body.stmts[0] {
+        arkts2rust::Stmt::FuncDecl(g) => assert_eq!(g.decorators[0].name, "Builder"),
+        other => panic!("expected nested function, got {other:?}"),
+    }
+
+    let codes: Vec<&str> = p.warnings.iter().map(|w| w.code.as_str()).collect();
+    assert_eq!(codes, vec!["IgnoredDecorator"; 4]);
+    assert_eq!(p.warnings[2].span, watch.span);
+}
+
+#[test]
+fn undecorated_declarations_have_no_decorators() {
+    let p = parse_program("function f(): void {} class E extends Error {}").unwrap();
+    assert_eq!(p.funcs[0].decorators, Vec::<Decorator>::new());
+    assert!(p.classes[0].decorators.is_empty());
+    assert!(p.warnings.is_empty());
+}
+
+#[test]
+fn codegen_ignores_decorators() {
+    let rust = compile("@Concurrent function add(a: number, b: number): number { return a + b; } console.log(add(1, 2));")
+        .unwrap();
+    assert!(rust.contains("fn add(a: i32, b: i32) -> i32 {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_misplaced_decorator() {
+    for src in ["@State let x = 1;", "@Entry", "function f(): void { @Log x(); }"] {
+        let err = parse_program(src).expect_err(src);
+        assert_eq!(err.code, "MisplacedDecorator", "{src}");
+    }
+}
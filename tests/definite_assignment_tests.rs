@@ -0,0 +1,114 @@
+use arkts2rust::ast::{Declarator, Pattern, Stmt, TypeAnn, VarDecl};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+#[test]
+fn parse_let_without_initializer() {
+    let p = parse_program("let x: number;").unwrap();
+    assert_eq!(
+        p.stmts,
+        vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
+            is_const: false,
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("x".into()),
+                ty: Some(TypeAnn::Number),
+                init: None,
+                span: Default::default(),
+            }],
+            span: Default::default(),
+        })]
+    );
+}
+
+#[test]
+fn error_const_and_destructuring_need_initializer() {
+    let err = parse_program("const x: number;").expect_err("const needs init");
+    assert_eq!(err.code, "MissingInitializer");
+    assert_eq!(err.span.start_col, 16);
+    let err = parse_program("let [a, b];").expect_err("destructuring needs init");
+    assert_eq!(err.code, "MissingInitializer");
+}
+
+#[test]
+fn codegen_declaration_without_initializer() {
+    let rust = compile("let x: number; x = 1; let y; y = true; console.log(x);").unwrap();
+    assert!(
+        rust.contains("    let x: i32;\n    x = 1i32;\n    let y;\n    y = true;\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_read_before_assignment() {
+    let err = compile("let total: number;\nlet ok = true;\nconsole.log(ok, total + 1);").expect_err("never assigned");
+    assert_eq!(err.code, "UseOfUninitialized");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 17));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+    assert_eq!(err.notes[0].message(), "declared here");
+
+    for src in [
+        "let x: number; console.log(x);",
+        "let x: number; while (false) { x = 1; } console.log(x);",
+        "let x: number; let ok = false && (x = 1) == 1; console.log(x);",
+        "function g(): number { let t: number; { let t = 2; } return t; }",
+        "function g(): number { let t: number; function r(): number { return t; } t = 1; return r(); }",
+        "function outer(): void { function inner(): number { let u: number; return u; } console.log(inner()); }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "UseOfUninitialized", "{src}");
+    }
+}
+
+#[test]
+fn assigned_on_every_path_is_ok() {
+    for src in [
+        "let x: number; if (true) { x = 1; } else { x = 2; } console.log(x);",
+        "let x: number; if (true) { x = 1; } else { throw \"no\"; } console.log(x);",
+        "let x: number; try { x = 1; } catch (e) { x = 2; } console.log(x);",
+        "let x: number; try { console.log(1); } finally { x = 2; } console.log(x);",
+    ] {
+        compile(src).unwrap_or_else(|e| panic!("{src}: {e}"));
+    }
+}
+
+#[test]
+fn generated_code_with_late_assignment_runs() {
+    let src = r#"
+function pick(flag: boolean): string {
+  let label: string;
+  if (flag) {
+    label = "yes";
+  } else {
+    label = "no";
+  }
+  return label;
+}
+function count(): number {
+  let total: number;
+  total = 0;
+  function add(n: number): void { total = total + n; }
+  add(2);
+  add(3);
+  return total;
+}
+let x: number;
+try {
+  x = 1;
+} catch (e) {
+  x = 2;
+}
+console.log(x);
+console.log(pick(false));
+console.log(count());
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "1\nno\n5\n");
+}
@@ -0,0 +1,121 @@
+use arkts2rust::ast::{Pattern, PropPattern, Stmt, TypeAnn};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+fn ident(name: &str) -> Pattern {
+    Pattern::Ident(name.into())
+}
+
+fn pattern_of(src: &str) -> Pattern {
+    match parse_program(src).unwrap().stmts.into_iter().next() {
+        Some(Stmt::VarDecl(d)) => d.decls[0].pattern.clone(),
+        other => panic!("expected var decl, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_array_pattern() {
+    assert_eq!(
+        pattern_of("const [a, b] = pair;"),
+        Pattern::Array(vec![ident("a"), ident("b")])
+    );
+}
+
+#[test]
+fn parse_object_pattern_with_rename() {
+    assert_eq!(
+        pattern_of("const {x, y: py} = point;"),
+        Pattern::Object(vec![
+            PropPattern { key: "x".into(), value: ident("x") },
+            PropPattern { key: "y".into(), value: ident("py") },
+        ])
+    );
+}
+
+#[test]
+fn parse_nested_pattern_and_names() {
+    let p = pattern_of("let [a, [b, c], {d}] = v;");
+    assert_eq!(p.names(), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn parse_pattern_with_type_annotation() {
+    let p = parse_program("const [a, b]: number[] = pair;").unwrap();
+    let Stmt::VarDecl(d) = &p.stmts[0] else { panic!() };
+    assert_eq!(d.decls[0].ty, Some(TypeAnn::Array(Box::new(TypeAnn::Number))));
+}
+
+#[test]
+fn error_unclosed_array_pattern() {
+    let err = parse_program("const [a, b = pair;").expect_err("missing ]");
+    assert_eq!(err.code, "MissingRBracket");
+    assert_eq!(err.span.start_col, 13);
+}
+
+#[test]
+fn error_object_pattern_key_must_be_ident() {
+    let err = parse_program("const {1} = p;").expect_err("key must be ident");
+    assert_eq!(err.code, "ExpectedIdentifier");
+}
+
+#[test]
+fn codegen_array_destructure_from_variable() {
+    let rust = compile(
+        "function f(...xs: number[]): void { const [a, b] = xs; let [c] = xs; console.log(a + b + c); }",
+    )
+    .unwrap();
+    assert!(rust.contains("let a = xs[0];\n"), "got:\n{rust}");
+    assert!(rust.contains("let b = xs[1];\n"), "got:\n{rust}");
+    assert!(rust.contains("let c = xs[0];\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_destructure_call_uses_temp() {
+    let rust = compile(
+        "function names(...xs: string[]): string[] { return xs; } const [a, b] = names(\"p\", \"q\");",
+    )
+    .unwrap();
+    assert!(
+        rust.contains("let __destructure0 = names(vec![String::from(\"p\"), String::from(\"q\")]);"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("let a = __destructure0[0].clone();"), "got:\n{rust}");
+    assert!(rust.contains("let b = __destructure0[1].clone();"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_object_destructure_uses_field_access() {
+    let rust = compile("function show(point): void { const {x, y: py} = point; }").unwrap();
+    assert!(rust.contains("let x = point.x.clone();"), "got:\n{rust}");
+    assert!(rust.contains("let py = point.y.clone();"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nested_array_pattern_indexes_twice() {
+    let rust = compile("function show(grid): void { const [a, [b]] = grid; }").unwrap();
+    assert!(rust.contains("let b = grid[1][0].clone();"), "got:\n{rust}");
+}
+
+#[test]
+fn generated_rust_with_destructuring_can_compile() {
+    let src = r#"
+function pair(...xs: number[]): number[] { return xs; }
+function words(...ws: string[]): string[] { return ws; }
+const [lo, hi] = pair(1, 9);
+let [first, second] = words("a", "b");
+console.log(lo + hi);
+console.log(first);
+console.log(second);
+{
+    const ps = pair(3, 4);
+    const [x, y] = ps;
+    function sum(): number { return x + y; }
+    console.log(sum());
+}
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
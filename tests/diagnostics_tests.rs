@@ -0,0 +1,189 @@
+use arkts2rust::{compile, compile_with_diagnostics, parse_program, Severity};
+
+#[test]
+fn reports_all_errors_in_one_run() {
+    let src = "function a(n: number): string {\n  return n * 2;\n}\n\
+               function b(): void {\n  console.log(missing);\n}\n\
+               let x: number;\nconsole.log(x);\nlet s: string = 1;\nconsole.log(s);";
+    let result = compile_with_diagnostics(src);
+    assert!(!result.is_ok());
+    assert_eq!(result.output, None);
+    let found: Vec<(&str, usize)> =
+        result.diagnostics.errors().map(|d| (d.code.as_str(), d.span.start_line)).collect();
+    assert_eq!(
+        found,
+        [("UndeclaredVariable", 5), ("TypeMismatch", 9), ("TypeMismatch", 2), ("UseOfUninitialized", 8)]
+    );
+    // 相关位置保留在诊断信息里
+    let flow = result.diagnostics.errors().last().unwrap();
+    assert_eq!(flow.notes[0].message(), "declared here");
+    assert_eq!(flow.notes[0].span.start_line, 7);
+
+    // `compile` 仍然只返回第一个错误，和 `into_result` 一致
+    let first = compile(src).unwrap_err();
+    assert_eq!(first.code, "UndeclaredVariable");
+    assert_eq!(compile_with_diagnostics(src).into_result().unwrap_err(), first);
+}
+
+#[test]
+fn duplicate_top_level_declaration_does_not_hide_other_errors() {
+    let src = "function f(): void {}\nfunction f(): void {}\nconsole.log(yy);";
+    let result = compile_with_diagnostics(src);
+    let found: Vec<(&str, usize)> =
+        result.diagnostics.errors().map(|d| (d.code.as_str(), d.span.start_line)).collect();
+    assert_eq!(found, [("DuplicateDeclaration", 2), ("UndeclaredVariable", 3)]);
+    let duplicate = result.diagnostics.errors().next().unwrap();
+    assert_eq!((duplicate.notes[0].message(), duplicate.notes[0].span.start_line), ("first declared here".to_string(), 1));
+}
+
+#[test]
+fn errors_do_not_cascade() {
+    // 出错的声明语句后面使用这个变量，不再报 `UsedBeforeDeclaration`
+    let result = compile_with_diagnostics("let a = missing;\nconsole.log(a);");
+    let codes: Vec<&str> = result.diagnostics.errors().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, ["UndeclaredVariable"]);
+
+    // 同一个没有赋值的变量只报一次
+    let result = compile_with_diagnostics("let x: number;\nconsole.log(x);\nconsole.log(x + 1);");
+    let codes: Vec<&str> = result.diagnostics.errors().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, ["UseOfUninitialized"]);
+}
+
+#[test]
+fn collects_warnings_with_output() {
+    let result = compile_with_diagnostics("let unused = 1;\nconsole.log(2);");
+    assert!(result.is_ok());
+    assert!(!result.diagnostics.has_errors());
+    let warnings: Vec<_> = result.diagnostics.warnings().collect();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].code, "UnusedVariable");
+    assert!(warnings[0].to_string().starts_with("Warning(code=UnusedVariable, "), "{}", warnings[0]);
+
+    // 类型检查的警告也在里面
+    let program = parse_program("let n = 3;\nwhile (n % 2) { n = n - 1; }\nconsole.log(n);").unwrap();
+    let diagnostics = arkts2rust::check_program(&program, true);
+    let codes: Vec<&str> = diagnostics.warnings().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, ["TruthyCondition"]);
+}
+
+#[test]
+fn syntax_and_codegen_errors() {
+    // 语法错误全部报告，不再做后面的检查（`y` 没有声明也不报）
+    let result = compile_with_diagnostics("let = 1;\nconsole.log(y);\nlet z = ;");
+    let codes: Vec<&str> = result.diagnostics.errors().map(|d| d.code.as_str()).collect();
+    assert_eq!(codes, ["ExpectedIdentifier", "ExpectedExpr"]);
+
+    let result = compile_with_diagnostics("let s = 'a");
+    assert_eq!(result.diagnostics.len(), 1);
+    assert!(result.diagnostics.has_errors());
+
+    // 词法错误不再中断：和语法错误一起按位置报告
+    let result = compile_with_diagnostics("let a = 1 # 2;\nlet s = 'abc\nlet = 3;\nlet b = 0x10000000000000000;");
+    let found: Vec<(&str, usize)> =
+        result.diagnostics.errors().map(|d| (d.code.as_str(), d.span.start_line)).collect();
+    assert_eq!(
+        found,
+        [("UnexpectedChar", 1), ("UnterminatedString", 2), ("ExpectedIdentifier", 3), ("InvalidNumber", 4)]
+    );
+
+    // 只有 CodeGen 才发现的问题：补在检查的诊断信息后面
+    let src = "console.log(Math.cube(2));";
+    let err = compile(src).unwrap_err();
+    assert!(arkts2rust::check_program(&parse_program(src).unwrap(), false).errors().next().is_none());
+    let result = compile_with_diagnostics(src);
+    assert_eq!(result.diagnostics.first_error(), Some(err));
+}
+
+#[test]
+fn renders_like_rustc() {
+    let src = "let a = 1;\nconsole.log(1;\n";
+    let err = parse_program(src).unwrap_err().in_file("input.ets");
+    assert_eq!(
+        err.render(src),
+        "error[MissingRParen]: expected `,` or `)`, found `;`\n --> input.ets:2:14\n  |\n2 | console.log(1;\n  |              ^\n"
+    );
+
+    // 补充说明也带上源码行；tab 缩进照样对齐；检查阶段的诊断信息由调用方补上文件名
+    let src = "let x: number;\n\tconsole.log(x);";
+    let result = compile_with_diagnostics(src);
+    let diagnostics = result.diagnostics.in_file("input.ets");
+    let d = diagnostics.errors().next().unwrap();
+    assert_eq!(
+        d.render(src),
+        "error[UseOfUninitialized]: use of possibly unassigned variable `x`\n --> input.ets:2:14\n  |\n2 | \tconsole.log(x);\n  | \t            ^\n\
+         note: declared here\n --> input.ets:1:1\n  |\n1 | let x: number;\n  | ^^^^^^^^^^^^^^\n"
+    );
+
+    // 行号栏按最长的行号对齐；不知道文件时只有行列号
+    let src = "let x: number;\n\n\n\n\n\n\n\n\nconsole.log(x);";
+    let err = compile(src).unwrap_err();
+    assert!(err.render(src).starts_with("error[UseOfUninitialized]: use of possibly unassigned variable `x`\n  --> 10:13\n   |\n10 | console.log(x);\n"), "{}", err.render(src));
+
+    // 没有位置的诊断信息只有标题
+    let err = arkts2rust::Error::new(arkts2rust::ErrorCode::ModuleNotFound, arkts2rust::Span::default());
+    assert_eq!(err.render(src), "error[ModuleNotFound]: imported module not found\n");
+}
+
+#[test]
+fn error_codes_are_typed() {
+    use arkts2rust::ErrorCode;
+
+    let err = compile("let a = 1;\nb = 2;").unwrap_err();
+    assert!(matches!(err.code, ErrorCode::UndeclaredVariable));
+    // 字符串形式不变：可以和字符串比较、打印、解析回来
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!(err.code.to_string(), "UndeclaredVariable");
+    assert_eq!("UndeclaredVariable".parse::<ErrorCode>(), Ok(ErrorCode::UndeclaredVariable));
+    assert_eq!("NoSuchCode".parse::<ErrorCode>(), Err(()));
+
+    for code in ErrorCode::ALL {
+        assert_eq!(code.as_str().parse::<ErrorCode>(), Ok(*code));
+    }
+    let mut names: Vec<&str> = ErrorCode::ALL.iter().map(|c| c.as_str()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), ErrorCode::ALL.len());
+}
+
+#[test]
+fn renders_messages_in_chinese() {
+    use arkts2rust::{messages, ErrorCode, Lang};
+
+    let src = "let a = 1;\nconsole.log(1;\n";
+    let err = parse_program(src).unwrap_err().in_file("input.ets");
+    assert_eq!(
+        err.render_in(src, Lang::Zh),
+        "error[MissingRParen]: 应为 `,` 或 `)`，实际是 `;`\n --> input.ets:2:14\n  |\n2 | console.log(1;\n  |              ^\n"
+    );
+    // 英文和 `render` 相同
+    assert_eq!(err.render_in(src, Lang::En), err.render(src));
+
+    // 补充说明也翻译；错误码和 `-->` 不变
+    let src = "let total = 1;\nconsole.log(totl);";
+    let err = compile(src).unwrap_err();
+    assert_eq!(
+        err.render_in(src, Lang::Zh),
+        "error[UndeclaredVariable]: 使用了没有声明的变量 `totl`\n --> 2:13\n  |\n2 | console.log(totl);\n  |             ^^^^\n\
+         note: 是不是想写 `total`？\n --> 1:1\n  |\n1 | let total = 1;\n  | ^^^^^^^^^^^^^^\n"
+    );
+    let err = parse_program("let = 1;").unwrap_err();
+    assert_eq!(err.render_in("", Lang::Zh), "error[ExpectedIdentifier]: 应为 标识符，实际是 `=`\n --> 1:5\n");
+
+    // 说明文字里的参数两种语言都填上；没有的参数原样保留
+    let src = "const n = 1;\nn = 2;";
+    let err = compile(src).unwrap_err();
+    assert!(err.render_in(src, Lang::Zh).starts_with("error[ConstReassignment]: 不能给 const `n` 赋值\n"));
+    assert!(err.render(src).contains("cannot assign to const `n`\n"));
+    assert_eq!(messages::format("`{name}` and {other}", &[("name", "x".to_string())]), "`x` and {other}");
+
+    assert_eq!("zh".parse::<Lang>(), Ok(Lang::Zh));
+    assert_eq!("fr".parse::<Lang>(), Err(()));
+    assert_eq!(Lang::default(), Lang::En);
+
+    // 每个错误码在两种语言里都有说明
+    for code in ErrorCode::ALL {
+        assert!(!messages::message(*code, Lang::En).is_empty(), "{code}");
+        assert!(!messages::message(*code, Lang::Zh).is_empty(), "{code}");
+    }
+}
@@ -0,0 +1,86 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, Error, ExternBindings, NumberMode, OutputMode};
+
+mod common;
+
+fn compile_js_division(src: &str) -> Result<String, Error> {
+    let program = parse_program(src)?;
+    let options = CodegenOptions {
+        js_division: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+#[test]
+fn codegen_integer_division_by_default() {
+    let rust = compile("function f(a: number, b: number): number { return a / b + a % b; }").unwrap();
+    assert!(rust.contains("    return a / b + a % b;\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_js_division() {
+    let rust = compile_js_division(
+        "function f(a: number, b: number): number { \
+         let avg = (a + b) / 2; \
+         let twice = avg * 2; \
+         return Math.floor(a / b) + a % b; }",
+    )
+    .unwrap();
+    assert!(rust.contains("    let avg = f64::from(a + b) / 2.0;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let twice = avg * 2.0;\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("    return ((f64::from(a) / f64::from(b)).floor() as i32) + a % b;\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_fraction_into_typed_number() {
+    let err = compile_js_division("let x: number = 7 / 2;").expect_err("fraction into i32");
+    assert_eq!(err.code, "FractionalNumberNeedsF64");
+    let err = compile_js_division("function f(a: number): number { return a / 2; }").expect_err("fraction returned");
+    assert_eq!(err.code, "FractionalNumberNeedsF64");
+}
+
+#[test]
+fn generated_division_code_runs() {
+    let src = r#"
+let total = 7;
+let avg = total / 2;
+avg = avg + 1;
+console.log(7 / 2, -7 / 2, avg, Math.floor(-7 / 2), Math.round(total / 2), -7 % 2);
+"#;
+
+    let rust = compile_js_division(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "3.5 -3.5 4.5 -4 4 -1\n");
+}
+
+#[test]
+fn division_by_zero() {
+    // i32 整数除以常量 0：JS 的结果放不进 i32，报错而不是生成 rustc 拒绝编译的代码
+    let err = compile("let x = 7;\nconsole.log(x % 0);").expect_err("modulo by zero");
+    assert_eq!(err.code, "IntegerDivisionByZero");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 13));
+    assert_eq!(compile("let x = 7;\nconsole.log(x / (1 - 1));").unwrap_err().code, "IntegerDivisionByZero");
+    assert_eq!(compile_js_division("let x = 7;\nconsole.log(x % 0);").unwrap_err().code, "IntegerDivisionByZero");
+
+    // 小数除法和 f64 数字模式按 JS：NaN / Infinity
+    let rust = compile_js_division("let x = 7;\nconsole.log(x / 0);").unwrap();
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "Infinity\n");
+    }
+    let program = parse_program("let x = 7;\nlet z = 0;\nconsole.log(x % 0, x % z, -x % 0, -7 % 2, 5.5 % 2);").unwrap();
+    let options = CodegenOptions {
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "NaN NaN NaN -1 1.5\n");
+    }
+}
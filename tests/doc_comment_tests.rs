@@ -0,0 +1,101 @@
+use arkts2rust::{compile, compile_with_options, CodegenOptions, CompileOptions};
+
+fn compile_docs(src: &str, comments: bool) -> String {
+    let options = CompileOptions {
+        codegen: CodegenOptions {
+            doc_comments: true,
+            comments,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options).unwrap()
+}
+
+#[test]
+fn jsdoc_sections() {
+    let src = r#"/**
+ * 计算两个数的和。
+ *
+ * @param {number} a - 第一个数
+ * @param b 第二个数，
+ *   可以是负数
+ * @param [c] 可选
+ * @returns {number} 两数之和
+ * @example
+ * ```ts
+ * add(1, 2, 3);
+ * ```
+ */
+function add(a: number, b: number, c: number): number {
+  return a + b + c;
+}
+console.log(add(1, 2, 3));"#;
+    let rust = compile_docs(src, false);
+    assert!(
+        rust.starts_with(
+            "/// 计算两个数的和。
+///
+/// # Arguments
+///
+/// * `a` - 第一个数
+/// * `b` - 第二个数， 可以是负数
+/// * `c` - 可选
+///
+/// # Returns
+///
+/// 两数之和
+///
+/// @example
+/// ```text
+/// add(1, 2, 3);
+/// ```
+fn add(a: i32, b: i32, c: i32) -> i32 {
+"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn jsdoc_on_components_methods_and_classes() {
+    let src = r#"/** 找不到 */
+class NotFound extends Error {}
+
+/** 首页 */
+@Entry
+@Component
+struct Index {
+  @State count: number = 0;
+
+  /** 加一 */
+  inc(): void {
+    this.count = this.count + 1;
+  }
+
+  build() {
+    console.log(this.count);
+  }
+}"#;
+    let rust = compile_docs(src, false);
+    assert!(rust.contains("    Error(String),\n    /// 找不到\n    NotFound(String),\n"), "got:\n{rust}");
+    assert!(rust.contains("\n/// 首页\nstruct Index {\n"), "got:\n{rust}");
+    assert!(rust.contains("\n    /// 加一\n    fn inc(&mut self) {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn only_jsdoc_before_declarations() {
+    let src = "// 普通注释\n/** 说明 */\nfunction f(): number {\n  /** 不是声明 */\n  return 1; // 行尾\n}\nconsole.log(f());";
+    let rust = compile_docs(src, false);
+    assert!(rust.starts_with("/// 说明\nfn f() -> i32 {\n    return 1i32;\n}\n"), "got:\n{rust}");
+
+    // 同时保留普通注释时，语句前面的 JSDoc 注释是普通注释
+    let rust = compile_docs(src, true);
+    assert!(
+        rust.starts_with("// 普通注释\n/// 说明\nfn f() -> i32 {\n    // 不是声明\n    return 1i32; // 行尾\n}\n"),
+        "got:\n{rust}"
+    );
+
+    // 默认不生成文档注释
+    assert!(!compile(src).unwrap().contains("//"));
+}
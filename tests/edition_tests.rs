@@ -0,0 +1,89 @@
+use std::fs;
+
+use arkts2rust::{
+    compile_tree, compile_with_options, CodegenOptions, CompileOptions, Edition, ErrorCode, ExternBindings, OutputFile,
+    OutputMode, ResolveConfig,
+};
+
+mod common;
+
+fn options(edition: Edition) -> CodegenOptions {
+    CodegenOptions {
+        edition,
+        use_rand: true,
+        ..CodegenOptions::default()
+    }
+}
+
+fn compile_edition(src: &str, edition: Edition) -> Result<String, arkts2rust::Error> {
+    let options = CompileOptions {
+        codegen: options(edition),
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options)
+}
+
+fn file<'a>(files: &'a [OutputFile], path: &str) -> &'a str {
+    files
+        .iter()
+        .find(|f| f.path == std::path::Path::new(path))
+        .unwrap_or_else(|| panic!("missing {path}"))
+        .code
+        .as_str()
+}
+
+#[test]
+fn parse_edition() {
+    assert_eq!("2015".parse(), Ok(Edition::E2015));
+    assert_eq!("2024".parse(), Ok(Edition::E2024));
+    assert_eq!("2020".parse::<Edition>(), Err(()));
+    assert_eq!(Edition::default().as_str(), "2021");
+}
+
+#[test]
+fn extern_crates_in_2015() {
+    let src = "let x = Math.random();\nconsole.log(x * 2);";
+    let rust = compile_edition(src, Edition::E2015).unwrap();
+    assert!(rust.starts_with("extern crate rand;\n\nfn __ark_number_to_string("), "got:\n{rust}");
+
+    for edition in [Edition::E2018, Edition::E2021, Edition::E2024] {
+        let rust = compile_edition(src, edition).unwrap();
+        assert!(!rust.contains("extern crate"), "got:\n{rust}");
+    }
+
+    // `--bind` 绑定的路径
+    let options = CompileOptions {
+        codegen: options(Edition::E2015),
+        externs: ExternBindings::from([("now".to_string(), "my_runtime::clock::now".to_string())]),
+        ..CompileOptions::default()
+    };
+    let rust = compile_with_options("declare function now(): number;\nconsole.log(now());", &options).unwrap();
+    assert!(rust.starts_with("extern crate my_runtime;\n\n"), "got:\n{rust}");
+}
+
+#[test]
+fn extern_crates_in_module_tree() {
+    let dir = common::write_project(&[
+        ("util.ets", "export function roll(): number {\n  return Math.floor(Math.random() * 6);\n}"),
+        ("main.ets", "import { roll } from './util';\nconsole.log(roll());"),
+    ]);
+    let files = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &options(Edition::E2015),
+    )
+    .unwrap();
+    assert!(file(&files, "main.rs").starts_with("extern crate rand;\n\nmod util;\n"), "got:\n{}", file(&files, "main.rs"));
+    assert!(file(&files, "util.rs").starts_with("use rand;\n\n"), "got:\n{}", file(&files, "util.rs"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn async_needs_2018() {
+    let src = "function all(ps: Promise<number>[]): Promise<number[]> { return Promise.all(ps); }";
+    let err = compile_edition(src, Edition::E2015).unwrap_err();
+    assert_eq!(err.code, ErrorCode::AsyncNeedsEdition2018);
+    assert!(compile_edition(src, Edition::E2018).is_ok());
+}
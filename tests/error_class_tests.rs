@@ -0,0 +1,168 @@
+use arkts2rust::ast::{ClassDecl, Expr, Literal, NewExpr, Stmt, ThrowStmt};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+#[test]
+fn parse_error_class_declarations() {
+    let p = parse_program("class NotFound extends Error {} class Plain {}").unwrap();
+    assert_eq!(
+        p.classes,
+        vec![
+            ClassDecl {
+                is_export: false,
+                name: "NotFound".into(),
+                extends: Some("Error".into()),
+                decorators: vec![],
+                is_declare: false,
+                methods: vec![],
+                fields: vec![],
+                span: Default::default(),
+            },
+            ClassDecl {
+                is_export: false,
+                name: "Plain".into(),
+                extends: None,
+                decorators: vec![],
+                is_declare: false,
+                methods: vec![],
+                fields: vec![],
+                span: Default::default(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_throw_new_and_typed_catch() {
+    let p = parse_program("try { throw new NotFound(\"x\"); } catch (e: NotFound) { }").unwrap();
+    let Stmt::Try(t) = &p.stmts[0] else { panic!() };
+    assert_eq!(
+        t.block.stmts[0],
+        Stmt::Throw(ThrowStmt {
+            value: Expr::New(NewExpr {
+                class: "NotFound".into(),
+                args: vec![Expr::Literal(Literal::String("x".into()), Default::default())],
+                span: Default::default(),
+            }),
+            span: Default::default(),
+        })
+    );
+    assert_eq!(t.catch.as_ref().unwrap().ty, Some("NotFound".into()));
+}
+
+#[test]
+fn error_class_body_must_be_empty() {
+    let err = parse_program("class E extends Error { x }").expect_err("no members yet");
+    assert_eq!(err.code, "UnsupportedClassMember");
+    assert_eq!(err.span.start_col, 25);
+}
+
+#[test]
+fn error_class_only_at_top_level() {
+    let err = parse_program("{ class E extends Error {} }").expect_err("nested class");
+    assert_eq!(err.code, "ClassNotAllowedHere");
+}
+
+#[test]
+fn codegen_error_enum_with_display() {
+    let rust = compile("class NotFound extends Error {}").unwrap();
+    assert!(
+        rust.starts_with(
+            "#[derive(Debug, Clone, PartialEq)]\nenum ArkError {\n    Error(String),\n    NotFound(String),\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("ArkError::NotFound(m) => write!(f, \"NotFound: {m}\"),"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("impl std::error::Error for ArkError {}"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_throw_new_picks_variant() {
+    let rust = compile(
+        "class NotFound extends Error {} function f(): void { throw new NotFound(\"gone\"); } \
+         function g(): void { throw new Error(); }",
+    )
+    .unwrap();
+    assert!(
+        rust.contains("return Err(ArkError::NotFound(String::from(\"gone\")));"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("return Err(ArkError::Error(String::new()));"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_typed_catch_matches_subclasses_and_rethrows_others() {
+    let rust = compile(
+        "class A extends Error {} class B extends A {} class C extends Error {} \
+         function f(): void { try { throw new B(\"b\"); } catch (e: A) { console.log(e); } }",
+    )
+    .unwrap();
+    assert!(rust.contains("Err(ArkError::A(e) | ArkError::B(e)) => 'catch0: {"), "got:\n{rust}");
+    assert!(rust.contains("other => other,"), "got:\n{rust}");
+    assert!(rust.contains("fn f() -> Result<(), ArkError> {"), "got:\n{rust}");
+}
+
+#[test]
+fn error_unknown_error_class() {
+    let err = compile("throw new Missing(\"x\");").expect_err("class not declared");
+    assert_eq!(err.code, "UnknownErrorClass");
+    let err = compile("try { } catch (e: Missing) { }").expect_err("class not declared");
+    assert_eq!(err.code, "UnknownErrorClass");
+}
+
+#[test]
+fn error_class_must_extend_error() {
+    let err = compile("class Plain {}").expect_err("only error classes are supported");
+    assert_eq!(err.code, "UnsupportedClass");
+    let err = compile("class A extends B {} class B extends A {}").expect_err("cycle");
+    assert_eq!(err.code, "UnsupportedClass");
+}
+
+#[test]
+fn error_new_outside_throw() {
+    let err = compile("class E extends Error {} let e = new E(\"x\");").expect_err("new only in throw");
+    assert_eq!(err.code, "NewOnlyInThrow");
+}
+
+#[test]
+fn generated_rust_with_error_classes_can_compile_and_run() {
+    let src = r#"
+class NotFound extends Error {}
+class MissingFile extends NotFound {}
+class Timeout extends Error {}
+function load(n: number): string {
+    if (n == 0) { throw new MissingFile("a.txt"); } else { if (n == 1) { throw new Timeout("slow"); } else { return "ok"; } }
+}
+function tryLoad(n: number): string {
+    try {
+        return load(n);
+    } catch (e: NotFound) {
+        console.log(e);
+        return "fallback";
+    } finally {
+        console.log("cleanup");
+    }
+}
+console.log(tryLoad(0));
+console.log(tryLoad(2));
+try {
+    console.log(tryLoad(1));
+} catch (e: Timeout) {
+    console.log(e);
+}
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "a.txt\ncleanup\nfallback\ncleanup\nok\ncleanup\nslow\n"
+    );
+}
@@ -0,0 +1,188 @@
+use arkts2rust::ast::{BlockStmt, CatchClause, Expr, Literal, Stmt, ThrowStmt, TryStmt};
+use arkts2rust::{compile, lex, parse_program, TokenKind};
+
+mod common;
+
+#[test]
+fn lex_exception_keywords() {
+    let ks: Vec<TokenKind> = lex("try catch finally throw trying")
+        .unwrap()
+        .into_iter()
+        .map(|t| t.kind)
+        .collect();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::KwTry,
+            TokenKind::KwCatch,
+            TokenKind::KwFinally,
+            TokenKind::KwThrow,
+            TokenKind::Ident("trying".into()),
+        ]
+    );
+}
+
+#[test]
+fn parse_try_catch_finally() {
+    let p = parse_program("try { throw \"x\"; } catch (e) { } finally { }").unwrap();
+    assert_eq!(
+        p.stmts,
+        vec![Stmt::Try(TryStmt {
+            block: BlockStmt {
+                stmts: vec![Stmt::Throw(ThrowStmt {
+                    value: Expr::Literal(Literal::String("x".into()), Default::default()),
+                    span: Default::default(),
+                })],
+                span: Default::default(),
+            },
+            catch: Some(CatchClause {
+                param: Some("e".into()),
+                ty: None,
+                body: BlockStmt { stmts: vec![], span: Default::default() },
+                span: Default::default(),
+            }),
+            finally: Some(BlockStmt { stmts: vec![], span: Default::default() }),
+            span: Default::default(),
+        })]
+    );
+}
+
+#[test]
+fn parse_catch_without_param() {
+    let p = parse_program("try { } catch { }").unwrap();
+    let Stmt::Try(t) = &p.stmts[0] else { panic!() };
+    assert_eq!(t.catch.as_ref().unwrap().param, None);
+    assert_eq!(t.finally, None);
+}
+
+#[test]
+fn error_try_without_catch_or_finally() {
+    let err = parse_program("let x = 1; try { }").expect_err("needs catch or finally");
+    assert_eq!(err.code, "MissingCatchOrFinally");
+    assert_eq!(err.span.start_col, 12);
+}
+
+#[test]
+fn error_try_requires_block() {
+    let err = parse_program("try throw 1;").expect_err("try body must be a block");
+    assert_eq!(err.code, "ExpectedBlock");
+}
+
+#[test]
+fn codegen_throwing_function_returns_result() {
+    let rust = compile(
+        "function check(n: number): number { if (n < 0) { throw \"neg\"; } else { return n; } } \
+         function twice(n: number): void { check(n); check(n); }",
+    )
+    .unwrap();
+    assert!(rust.starts_with("#[derive(Debug, Clone, PartialEq)]\nenum ArkError {\n    Error(String),\n}\n"));
+    assert!(rust.contains("fn check(n: i32) -> Result<i32, ArkError> {"), "got:\n{rust}");
+    assert!(rust.contains("return Err(ArkError::Error(String::from(\"neg\")));"), "got:\n{rust}");
+    assert!(rust.contains("return Ok(n);"), "got:\n{rust}");
+    assert!(rust.contains("fn twice(n: i32) -> Result<(), ArkError> {"), "got:\n{rust}");
+    assert!(rust.contains("    check(n)?;\n"), "got:\n{rust}");
+    assert!(rust.contains("    Ok(())\n}"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_try_catch_uses_labeled_block() {
+    let rust = compile(
+        "function f(): number { throw 1; } let x = 0; try { x = f(); } catch (e) { console.log(e); }",
+    )
+    .unwrap();
+    assert!(rust.contains("fn main() {"), "caught errors keep main plain:\n{rust}");
+    assert!(
+        rust.contains("let __try0: Result<(), ArkError> = 'try0: {"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("x = (match f() { Ok(v) => v, Err(e) => break 'try0 Err(e) });"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("if let Err(ArkError::Error(e)) = __try0 {"), "got:\n{rust}");
+    assert!(
+        rust.contains("return Err(ArkError::Error(format!(\"{:?}\", 1i32)));"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_uncaught_throw_makes_main_return_result() {
+    let rust = compile("throw \"fatal\";").unwrap();
+    assert!(
+        rust.ends_with(
+            "fn main() -> Result<(), ArkError> {\n    return Err(ArkError::Error(String::from(\"fatal\")));\n    Ok(())\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_try_finally_rethrows_after_finally() {
+    let rust = compile("try { throw \"x\"; } finally { console.log(1); }").unwrap();
+    let finally_at = rust.find("println!").unwrap();
+    let rethrow_at = rust.find("if let Err(e) = __try0 {").unwrap();
+    assert!(finally_at < rethrow_at, "got:\n{rust}");
+    assert!(rust.contains("        return Err(e);"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_return_inside_try_waits_for_finally() {
+    let rust = compile(
+        "function f(): number { try { return 1; } finally { console.log(\"bye\"); } }",
+    )
+    .unwrap();
+    assert!(rust.contains("let mut __ret0 = None;"), "got:\n{rust}");
+    assert!(rust.contains("__ret0 = Some(1i32);\n        break 'try0 Ok(());"), "got:\n{rust}");
+    assert!(rust.contains("if let Some(v) = __ret0 {\n        return v;\n    }"), "got:\n{rust}");
+    assert!(rust.contains("unreachable!()"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_catch_that_rethrows_propagates() {
+    let rust = compile(
+        "function g(): void { try { throw \"a\"; } catch (e) { throw \"b\"; } }",
+    )
+    .unwrap();
+    assert!(rust.contains("fn g() -> Result<(), ArkError> {"), "got:\n{rust}");
+    assert!(rust.contains("Err(ArkError::Error(e)) => 'catch0: {"), "got:\n{rust}");
+    assert!(rust.contains("break 'catch0 Err(ArkError::Error(String::from(\"b\")));"), "got:\n{rust}");
+}
+
+#[test]
+fn generated_rust_with_exceptions_can_compile_and_run() {
+    let src = r#"
+function check(n: number): number {
+    if (n < 0) { throw "negative"; } else { return n * 2; }
+}
+function safe(n: number): number {
+    try {
+        return check(n) + 1;
+    } catch (e) {
+        console.log(e);
+        return 0;
+    } finally {
+        console.log("done");
+    }
+}
+console.log(safe(3));
+console.log(safe(-1));
+let count = 0;
+try {
+    try { count = check(-5); } finally { count = count + 10; }
+} catch {
+    count = count + 1;
+}
+console.log(count);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "done\n7\nnegative\ndone\n0\n11\n"
+    );
+}
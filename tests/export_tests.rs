@@ -0,0 +1,198 @@
+use std::fs;
+
+use arkts2rust::codegen::library_warnings;
+use arkts2rust::{
+    compile, compile_as, compile_file, compile_with_options, parse_program, CodegenOptions, CompileOptions, ErrorCode, OutputMode, Stmt,
+};
+
+mod common;
+
+#[test]
+fn parse_export_modifiers() {
+    let p = parse_program(
+        "export class E extends Error {} class F extends Error {} export const N = 1; const M = 2;",
+    )
+    .unwrap();
+    assert!(p.classes[0].is_export);
+    assert!(!p.classes[1].is_export);
+    let exported: Vec<bool> = p
+        .stmts
+        .iter()
+        .map(|s| match s {
+            Stmt::VarDecl(v) => v.is_export,
+            _ => panic!("expected VarDecl"),
+        })
+        .collect();
+    assert_eq!(exported, vec![true, false]);
+}
+
+#[test]
+fn export_let_is_unsupported() {
+    let err = parse_program("export let x = 1;").expect_err("mutable export");
+    assert_eq!(err.code, "UnsupportedExport");
+    assert_eq!(err.span.start_col, 8);
+}
+
+#[test]
+fn binary_mode_keeps_items_private() {
+    let rust = compile("export function f(): number { return 1; } console.log(f());").unwrap();
+    assert!(rust.starts_with("fn f() -> i32 {"), "got:\n{rust}");
+}
+
+#[test]
+fn library_mode_emits_pub_items() {
+    let rust = compile_as(
+        "export const LIMIT = 10; const NEG = -3; \
+         export function f(): number { return LIMIT; } function g(): boolean { return true; }",
+        OutputMode::Library,
+    )
+    .unwrap();
+    assert_eq!(
+        rust,
+        "pub const LIMIT: i32 = 10i32;\n\nconst NEG: i32 = -3i32;\n\n\
+         pub fn f() -> i32 {\n    return LIMIT;\n}\n\n\
+         fn g() -> bool {\n    return true;\n}\n\n"
+    );
+}
+
+#[test]
+fn library_mode_const_expressions() {
+    let rust = compile_as(
+        "export const W = 4, AREA = W * (W + 1); const BIG = AREA > 10;",
+        OutputMode::Library,
+    )
+    .unwrap();
+    assert_eq!(
+        rust,
+        "pub const W: i32 = 4i32;\n\n\
+         pub const AREA: i32 = W * (W + 1i32);\n\n\
+         const BIG: bool = AREA > 10i32;\n\n"
+    );
+    let err = compile_as(
+        "function f(): number { return 1; } export const N = f() + 1;",
+        OutputMode::Library,
+    )
+    .expect_err("call is not const");
+    assert_eq!(err.code, "TopLevelCodeInLibrary");
+}
+
+#[test]
+fn library_mode_error_enum_is_pub() {
+    let rust = compile_as("export class NotFound extends Error {}", OutputMode::Library).unwrap();
+    assert!(
+        rust.starts_with("#[derive(Debug, Clone, PartialEq)]\npub enum ArkError {\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn library_mode_rejects_top_level_code() {
+    let err = compile_as("console.log(1);", OutputMode::Library).expect_err("no main");
+    assert_eq!(err.code, "TopLevelCodeInLibrary");
+    let err = compile_as("export const S = \"s\";", OutputMode::Library).expect_err("string const");
+    assert_eq!(err.code, "TopLevelCodeInLibrary");
+    // 错误指向那条语句
+    let err = compile_as("export const N = 1;\nlet x = N;", OutputMode::Library).expect_err("let");
+    assert_eq!((err.code, err.span.start_line), (ErrorCode::TopLevelCodeInLibrary, 2));
+}
+
+#[test]
+fn library_mode_can_skip_top_level_code() {
+    let src = "export function add(a: number, b: number): number { return a + b; }\n\
+               export const N = 1;\nconsole.log(add(N, 2));";
+    let options = CompileOptions {
+        mode: OutputMode::Library,
+        codegen: CodegenOptions {
+            skip_top_level_code: true,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    let rust = compile_with_options(src, &options).unwrap();
+    assert_eq!(rust, "pub const N: i32 = 1i32;\n\npub fn add(a: i32, b: i32) -> i32 {\n    return a + b;\n}\n\n");
+
+    let program = parse_program(src).unwrap();
+    let warnings = library_warnings(&program, OutputMode::Library, &options.codegen);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!((warnings[0].code, warnings[0].span.start_line), (ErrorCode::TopLevelCodeSkipped, 3));
+    assert!(library_warnings(&program, OutputMode::Binary, &options.codegen).is_empty());
+}
+
+#[test]
+fn exported_class_can_be_imported() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            "import { NotFound } from './errors'; try { throw new NotFound(\"x\"); } catch (e: NotFound) { console.log(e); }",
+        ),
+        ("errors.ets", "export class NotFound extends Error {}"),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(rust.contains("NotFound(String),"), "got:\n{rust}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn exported_const_can_be_imported() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { N } from './consts'; console.log(N * 2);"),
+        ("consts.ets", "export const N = 2 + 1;\nconsole.log(N);"),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    let _ = fs::remove_dir_all(dir);
+    assert!(rust.contains("const N: i32 = 2i32 + 1i32;"), "got:\n{rust}");
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "3\n6\n");
+    }
+}
+
+#[test]
+fn reexported_const_can_be_imported() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { aa, PI2 } from './index';\nconsole.log(aa() + PI2);"),
+        ("index.ets", "export { a as aa, PI2 } from './a';"),
+        ("a.ets", "export const PI2 = 6;\nexport function a(): number { return 1; }"),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    let _ = fs::remove_dir_all(dir);
+    assert!(rust.contains("const PI2: i32 = 6i32;"), "got:\n{rust}");
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "7\n");
+    }
+}
+
+#[test]
+fn unsupported_const_imports_point_at_the_import() {
+    for (main, consts) in [
+        ("import { N as M } from './consts';\nconsole.log(M);", "export const N = 1;"),
+        ("import { N } from './consts';\nconsole.log(N);", "export const N = Math.random();"),
+    ] {
+        let dir = common::write_project(&[("main.ets", main), ("consts.ets", consts)]);
+        let err = compile_file(dir.join("main.ets")).expect_err("const import");
+        assert_eq!(err.code, "UnsupportedConstImport");
+        assert_eq!(err.file.as_deref(), Some(dir.join("main.ets").display().to_string().as_str()));
+        assert_eq!((err.span.start_line, err.span.start_col), (1, 10));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+#[test]
+fn generated_library_compiles() {
+    let src = r#"
+export class NotFound extends Error {}
+export const LIMIT = 10;
+const NEG: number = -3;
+export function clamp(x: number): number {
+  if (x > LIMIT) { return LIMIT; } else { return helper(x); }
+}
+function helper(x: number): number { return x + NEG - NEG; }
+export function find(x: number): number {
+  if (x < 0) { throw new NotFound("neg"); } else { return x; }
+}
+"#;
+
+    let rust = compile_as(src, OutputMode::Library).unwrap();
+
+    // 私有项只在导出函数内部使用；用 -D warnings 确认没有 dead_code 之类的警告
+    common::assert_rustc_compiles_with(&rust, &["--crate-type", "lib", "-D", "warnings"]);
+}
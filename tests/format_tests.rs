@@ -0,0 +1,60 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::layout::format;
+use arkts2rust::{parse_program, CodegenOptions, Edition, ErrorCode, ExternBindings, Formatter, OutputMode};
+
+const SRC: &str = "function f(n: number): number {\n  return n * 2;\n}\nlet x = (1 + 2) * f(3);\nconsole.log(x);";
+
+fn compile_formatted(formatter: Formatter) -> Result<String, arkts2rust::Error> {
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions {
+        formatter,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+/// 没有安装 rustfmt 时跳过。
+fn rustfmt_available() -> bool {
+    std::process::Command::new("rustfmt").arg("--version").output().is_ok()
+}
+
+#[test]
+fn rustfmt_formatting() {
+    if !rustfmt_available() {
+        return;
+    }
+    let rust = compile_formatted(Formatter::Rustfmt).unwrap();
+    assert_eq!(
+        rust,
+        "fn f(n: i32) -> i32 {\n    return n * 2i32;\n}\n\nfn main() {\n    let x = (1i32 + 2i32) * f(3i32);\n    println!(\"{}\", x);\n}\n"
+    );
+
+    let err = format("fn main( {", Formatter::Rustfmt, Edition::E2021).unwrap_err();
+    assert_eq!(err.code, ErrorCode::FormatFailed);
+}
+
+#[test]
+fn no_formatter_by_default() {
+    let rust = compile_formatted(Formatter::None).unwrap();
+    assert_eq!(rust, arkts2rust::compile(SRC).unwrap());
+}
+
+#[cfg(feature = "prettyplease")]
+#[test]
+fn prettyplease_formatting() {
+    let rust = compile_formatted(Formatter::Prettyplease).unwrap();
+    assert_eq!(
+        rust,
+        "fn f(n: i32) -> i32 {\n    return n * 2i32;\n}\nfn main() {\n    let x = (1i32 + 2i32) * f(3i32);\n    println!(\"{}\", x);\n}\n"
+    );
+
+    let err = format("fn main( {", Formatter::Prettyplease, Edition::E2021).unwrap_err();
+    assert_eq!(err.code, ErrorCode::FormatFailed);
+}
+
+#[cfg(not(feature = "prettyplease"))]
+#[test]
+fn prettyplease_needs_feature() {
+    let err = compile_formatted(Formatter::Prettyplease).unwrap_err();
+    assert_eq!(err.code, ErrorCode::FormatterUnavailable);
+}
@@ -1,22 +1,24 @@
-use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-
 use arkts2rust::ast::{
     BlockStmt, Expr, FuncDecl, Param, Program, Stmt, TypeAnn,
 };
 use arkts2rust::{compile, parse_program};
 
+mod common;
+
 fn program(funcs: Vec<FuncDecl>, stmts: Vec<Stmt>) -> Program {
-    Program { funcs, stmts }
+    Program {
+        funcs,
+        stmts,
+        ..Default::default()
+    }
 }
 
 fn block(stmts: Vec<Stmt>) -> BlockStmt {
-    BlockStmt { stmts }
+    BlockStmt { stmts, span: Default::default() }
 }
 
 fn ident(s: &str) -> Expr {
-    Expr::Ident(s.to_string())
+    Expr::Ident(s.to_string(), Default::default())
 }
 
 #[test]
@@ -26,15 +28,20 @@ fn parse_function_with_types() {
         p,
         program(
             vec![FuncDecl {
+                is_export: false,
                 name: "add".into(),
                 params: vec![
                     Param {
                         name: "a".into(),
                         ty: Some(TypeAnn::Number),
+                        is_rest: false,
+                        span: Default::default(),
                     },
                     Param {
                         name: "b".into(),
                         ty: Some(TypeAnn::Number),
+                        is_rest: false,
+                        span: Default::default(),
                     },
                 ],
                 ret_type: Some(TypeAnn::Number),
@@ -43,8 +50,12 @@ fn parse_function_with_types() {
                         op: arkts2rust::ast::BinaryOp::Add,
                         left: Box::new(ident("a")),
                         right: Box::new(ident("b")),
+                        span: Default::default(),
                     })),
+                    span: Default::default(),
                 })]),
+                decorators: vec![],
+                span: Default::default(),
             }],
             vec![]
         )
@@ -103,6 +114,18 @@ fn error_unknown_type() {
         .expect_err("unknown type should error");
     assert_eq!(err.code, "UnknownType");
     assert_eq!(err.span.start_line, 1);
+    assert!(err.notes.is_empty());
+
+    // 拼写相近（包括 TS 里大写的 `String` / `Number`）时给出建议
+    for (src, similar) in [
+        ("function f(a: numbr): void {}", "number"),
+        ("function f(a: String): void {}", "string"),
+        ("function f(): Promis<number> { return 1; }", "Promise"),
+    ] {
+        let err = parse_program(src).expect_err(src);
+        assert_eq!(err.code, "UnknownType");
+        assert_eq!(err.notes[0].message(), format!("did you mean `{similar}`?"), "{src}");
+    }
 }
 
 #[test]
@@ -134,40 +157,7 @@ if (always()) { return; } else { return; }
 
     let rust = compile(src).unwrap();
 
-    if Command::new("rustc").arg("--version").output().is_err() {
-        return;
-    }
-
-    let mut path: PathBuf = std::env::temp_dir();
-    let file_name = format!(
-        "arkts2rust_step6_{}_{}.rs",
-        std::process::id(),
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos()
-    );
-    path.push(file_name);
-
-    let mut exe_path = path.clone();
-    exe_path.set_extension("");
-
-    fs::write(&path, rust).unwrap();
-    let out = Command::new("rustc")
-        .arg(&path)
-        .arg("-o")
-        .arg(&exe_path)
-        .output()
-        .unwrap();
-    let _ = fs::remove_file(&path);
-    let _ = fs::remove_file(&exe_path);
-
-    assert!(
-        out.status.success(),
-        "rustc failed: {}\n{}",
-        String::from_utf8_lossy(&out.stdout),
-        String::from_utf8_lossy(&out.stderr)
-    );
+    common::assert_rustc_compiles(&rust);
 }
 
 #[test]
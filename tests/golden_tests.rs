@@ -7,7 +7,7 @@ fn assert_golden(src: &str, expected: &str) {
 
 #[test]
 fn golden_let_number() {
-    assert_golden("let x = 1;", "fn main() {\n    let mut x = 1i32;\n}\n");
+    assert_golden("let x = 1;", "fn main() {\n    let x = 1i32;\n}\n");
 }
 
 #[test]
@@ -22,7 +22,7 @@ fn golden_const_string() {
 fn golden_let_bool_false() {
     assert_golden(
         "let ok = false;",
-        "fn main() {\n    let mut ok = false;\n}\n",
+        "fn main() {\n    let ok = false;\n}\n",
     );
 }
 
@@ -30,7 +30,7 @@ fn golden_let_bool_false() {
 fn golden_console_log_number() {
     assert_golden(
         "console.log(1);",
-        "fn main() {\n    println!(\"{:?}\", 1i32);\n}\n",
+        "fn main() {\n    println!(\"{}\", 1i32);\n}\n",
     );
 }
 
@@ -38,7 +38,7 @@ fn golden_console_log_number() {
 fn golden_multi_stmts() {
     assert_golden(
         "let x = 1; console.log(\"a\");",
-        "fn main() {\n    let mut x = 1i32;\n    println!(\"{:?}\", String::from(\"a\"));\n}\n",
+        "fn main() {\n    let x = 1i32;\n    println!(\"{}\", String::from(\"a\"));\n}\n",
     );
 }
 
@@ -46,6 +46,6 @@ fn golden_multi_stmts() {
 fn golden_string_escape_quote_and_backslash() {
     assert_golden(
         "console.log(\"a\\\"b\\\\c\");",
-        "fn main() {\n    println!(\"{:?}\", String::from(\"a\\\"b\\\\c\"));\n}\n",
+        "fn main() {\n    println!(\"{}\", String::from(\"a\\\"b\\\\c\"));\n}\n",
     );
 }
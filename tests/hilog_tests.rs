@@ -0,0 +1,81 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, HilogMode, OutputMode};
+
+mod common;
+
+fn compile_hilog(src: &str, hilog: HilogMode) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        hilog,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn codegen_hilog_println() {
+    let rust = compile(
+        r#"function report(n: number, user: string | null): void {
+  hilog.info(0x0000, 'Index', 'count=%{public}d user=%{private}s', n, user);
+  hilog.debug(0x0000, 'Index', '{raw} 100%%');
+  hilog.fatal(0xFF00, 'Index', "crashed: %s", "oom");
+}"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "    println!(\"{}: count={} user={}\", String::from(\"Index\"), n, \
+             __ark_fmt_nullable(&user, \"null\"));\n    \
+             println!(\"{}: {{raw}} 100%\", String::from(\"Index\"));\n    \
+             eprintln!(\"{}: crashed: {}\", String::from(\"Index\"), String::from(\"oom\"));\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_hilog_log_and_tracing() {
+    let src = "hilog.warn(0x0000, 'Net', 'retry %d', 2); hilog.fatal(0x0000, 'Net', 'down');";
+    let rust = compile_hilog(src, HilogMode::Log);
+    assert!(
+        rust.contains(
+            "    log::warn!(\"{}: retry {}\", String::from(\"Net\"), 2i32);\n    \
+             log::error!(\"{}: down\", String::from(\"Net\"));\n"
+        ),
+        "got:\n{rust}"
+    );
+    let rust = compile_hilog(src, HilogMode::Tracing);
+    assert!(rust.contains("    tracing::warn!(\"{}: retry {}\", String::from(\"Net\"), 2i32);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_hilog_misuse() {
+    for (src, code) in [
+        ("hilog.info(0, 'T', 'a=%d');", "HilogArgCount"),
+        ("hilog.info(0, 'T', 'a', 1);", "HilogArgCount"),
+        ("hilog.info(0, 'T');", "HilogArgCount"),
+        ("let f = 'x'; hilog.info(0, 'T', f);", "HilogFormatNotLiteral"),
+        ("hilog.trace(0, 'T', 'x');", "UnknownHilogFunction"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_hilog_output() {
+    let src = r#"
+const TAG = 'Demo';
+let total = 42;
+hilog.info(0x0000, TAG, 'total=%{public}d (%{public}s)', total, "ok");
+hilog.error(0x0000, TAG, 'failed: %{public}s', "disk");
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "Demo: total=42 (ok)\n");
+    assert_eq!(String::from_utf8_lossy(&run.stderr), "Demo: failed: disk\n");
+}
@@ -0,0 +1,69 @@
+use arkts2rust::{compile, compile_with_options, CodegenOptions, CompileOptions, ErrorCode, Intrinsics};
+
+fn compile_mapped(src: &str, entries: &[(&str, &str)]) -> Result<String, arkts2rust::Error> {
+    let intrinsics: Intrinsics = entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let options = CompileOptions {
+        codegen: CodegenOptions {
+            intrinsics,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options)
+}
+
+#[test]
+fn console_and_math_templates() {
+    let rust = compile_mapped(
+        "let x = 7;\nconsole.log(\"x\", x);\nconsole.error(Math.max(x, 2));",
+        &[("console.log", "log::info!(\"{} {}\", $args)"), ("Math.max", "std::cmp::max($0, $1)")],
+    )
+    .unwrap();
+    assert!(rust.contains("    log::info!(\"{} {}\", String::from(\"x\"), x);\n"), "got:\n{rust}");
+    // 没有配置的 console 方法不变
+    assert!(rust.contains("    eprintln!(\"{}\", std::cmp::max(x, 2i32));\n"), "got:\n{rust}");
+}
+
+#[test]
+fn hilog_and_global_templates() {
+    let rust = compile_mapped(
+        "hilog.info(0x0000, \"app\", \"started\");\nlet n = parseInt(\"12\");\nconsole.log(n);",
+        &[
+            ("hilog.info", "my_log::info($1, $2)"),
+            ("parseInt", "my_conv::int(&$0)"),
+        ],
+    )
+    .unwrap();
+    assert!(rust.contains("    my_log::info(String::from(\"app\"), String::from(\"started\"));\n"), "got:\n{rust}");
+    assert!(rust.contains("    let n = my_conv::int(&String::from(\"12\"));\n"), "got:\n{rust}");
+    assert!(!rust.contains("__ark_parse_int"), "got:\n{rust}");
+}
+
+#[test]
+fn declarations_shadow_intrinsics() {
+    let src = "function parseInt(s: string): number {\n  return 1;\n}\nconsole.log(parseInt(\"2\"));";
+    let rust = compile_mapped(src, &[("parseInt", "my_conv::int($0)")]).unwrap();
+    assert_eq!(rust, compile(src).unwrap());
+}
+
+#[test]
+fn template_placeholders() {
+    // 不是 `$` 加数字或 `args` 的 `$` 原样保留
+    let rust = compile_mapped("console.log(1);", &[("console.log", "my_log!(\"$x\", $0, $0)")]).unwrap();
+    assert!(rust.contains("    my_log!(\"$x\", 1i32, 1i32);\n"), "got:\n{rust}");
+
+    let err = compile_mapped("console.log(1);", &[("console.log", "my_log!($0, $1)")]).unwrap_err();
+    assert_eq!(err.code, ErrorCode::IntrinsicArgCount);
+}
+
+#[test]
+fn templates_for_new_callees() {
+    let src = "let n = 2;\nconsole.log(foo(n));\nwidget.show(n);";
+    let rust = compile_mapped(src, &[("foo", "bar($0)"), ("widget.show", "my_ui::show($0)")]).unwrap();
+    assert!(rust.contains("    println!(\"{:?}\", bar(n));\n"), "got:\n{rust}");
+    assert!(rust.contains("    my_ui::show(n);\n"), "got:\n{rust}");
+
+    // 没有配置时仍然是未知函数
+    let err = compile(src).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnknownFunction);
+}
@@ -0,0 +1,55 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, Error, ExternBindings, OutputMode};
+
+fn compile_serde(src: &str) -> Result<String, Error> {
+    let program = parse_program(src)?;
+    let options = CodegenOptions {
+        use_serde_json: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+#[test]
+fn codegen_stringify() {
+    let rust = compile_serde("function show(n: number | null): string { return JSON.stringify(n); }").unwrap();
+    assert!(rust.contains("    return serde_json::to_string(&n).unwrap();\n"), "got:\n{rust}");
+}
+
+#[test]
+fn parse_throws_like_js() {
+    let rust = compile_serde(
+        r#"function load(s: string): number { let n: number = JSON.parse(s); return n; }
+try {
+  let v: number = JSON.parse("12");
+  console.log(v + load("1"));
+} catch (e) {
+  console.log("bad json");
+}"#,
+    )
+    .unwrap();
+    assert!(rust.contains("fn load(s: String) -> Result<i32, ArkError> {"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "    let n: i32 = serde_json::from_str(&s).map_err(|e| ArkError::Error(e.to_string()))?;\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "let v: i32 = (match serde_json::from_str(\"12\").map_err(|e| ArkError::Error(e.to_string())) \
+             { Ok(v) => v, Err(e) => break 'try0 Err(e) });"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_json_without_option() {
+    let err = compile("let s = JSON.stringify(1);").expect_err("needs serde option");
+    assert_eq!(err.code, "JsonNeedsSerde");
+    let err = compile_serde("let s = JSON.stringify(1, 2);").expect_err("too many args");
+    assert_eq!(err.code, "JsonArgCount");
+    let err = compile_serde("let s = JSON.dump(1);").expect_err("unknown function");
+    assert_eq!(err.code, "UnknownJsonFunction");
+}
@@ -0,0 +1,62 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::layout::apply;
+use arkts2rust::{compile, parse_program, BraceStyle, CodegenOptions, ExternBindings, Indent, LayoutOptions, OutputMode};
+
+const SRC: &str = "function f(n: number): number {\n  if (n > 0) {\n    return n;\n  } else {\n    return -n;\n  }\n}\n\
+                   console.log(f(-2));";
+
+fn compile_layout(layout: LayoutOptions) -> String {
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions {
+        layout,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn default_layout_is_unchanged() {
+    let rust = compile(SRC).unwrap();
+    assert_eq!(compile_layout(LayoutOptions::default()), rust);
+    assert!(rust.contains("fn f(n: i32) -> i32 {\n    if n > 0i32 {\n        return n;\n    } else {\n"), "got:\n{rust}");
+    assert!(rust.ends_with("}\n"), "got:\n{rust:?}");
+}
+
+#[test]
+fn indent_width_and_tabs() {
+    let rust = compile_layout(LayoutOptions {
+        indent: Indent::Spaces(2),
+        ..LayoutOptions::default()
+    });
+    assert!(rust.contains("fn f(n: i32) -> i32 {\n  if n > 0i32 {\n    return n;\n  } else {\n"), "got:\n{rust}");
+
+    let rust = compile_layout(LayoutOptions {
+        indent: Indent::Tabs,
+        ..LayoutOptions::default()
+    });
+    assert!(rust.contains("fn main() {\n\tprintln!(\"{}\", f(-2i32));\n}"), "got:\n{rust}");
+}
+
+#[test]
+fn braces_on_next_line() {
+    let rust = compile_layout(LayoutOptions {
+        brace_style: BraceStyle::NextLine,
+        trailing_newline: false,
+        ..LayoutOptions::default()
+    });
+    assert!(
+        rust.starts_with(
+            "fn f(n: i32) -> i32\n{\n    if n > 0i32\n    {\n        return n;\n    }\n    else\n    {\n        return -n;\n    }\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.ends_with("fn main()\n{\n    println!(\"{}\", f(-2i32));\n}"), "got:\n{rust:?}");
+
+    // 表达式里的大括号不动
+    let code = "fn main() {\n    let p = Point {\n        x: 1,\n    };\n}\n";
+    let options = LayoutOptions {
+        brace_style: BraceStyle::NextLine,
+        ..LayoutOptions::default()
+    };
+    assert_eq!(apply(code, &options), "fn main()\n{\n    let p = Point {\n        x: 1,\n    };\n}\n");
+}
@@ -1,4 +1,4 @@
-use arkts2rust::{lex, Error, TokenKind};
+use arkts2rust::{lex, lex_recovering, Error, ErrorCode, TokenKind};
 
 fn kinds(src: &str) -> Result<Vec<TokenKind>, Error> {
     Ok(lex(src)?.into_iter().map(|t| t.kind).collect())
@@ -33,6 +33,31 @@ fn lex_number() {
     );
 }
 
+#[test]
+fn lex_exponent_number() {
+    let ks = kinds("1e3 2.5E-4 1e+21 12e 3").unwrap();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::Number(1000),
+            TokenKind::Float("2.5E-4".into()),
+            TokenKind::Float("1e+21".into()),
+            TokenKind::Number(12),
+            TokenKind::Ident("e".into()),
+            TokenKind::Number(3),
+        ]
+    );
+}
+
+#[test]
+fn lex_hex_number() {
+    let ks = kinds("0x0000 0xFF00 0x1f").unwrap();
+    assert_eq!(
+        ks,
+        vec![TokenKind::Number(0), TokenKind::Number(0xFF00), TokenKind::Number(0x1f)]
+    );
+}
+
 #[test]
 fn lex_string_basic() {
     let ks = kinds(r#""hello""#).unwrap();
@@ -45,6 +70,18 @@ fn lex_string_escape() {
     assert_eq!(ks, vec![TokenKind::String("a\"b\\c\n".into())]);
 }
 
+#[test]
+fn lex_string_single_quoted() {
+    let ks = kinds(r#"'./util' 'it\'s "x"'"#).unwrap();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::String("./util".into()),
+            TokenKind::String("it's \"x\"".into()),
+        ]
+    );
+}
+
 #[test]
 fn lex_punctuations() {
     let ks = kinds("( ) { } , ;").unwrap();
@@ -111,6 +148,21 @@ const y
     );
 }
 
+#[test]
+fn unicode_identifiers() {
+    let ks = kinds("let 计数2 = $a;").unwrap();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::KwLet,
+            TokenKind::Ident("计数2".into()),
+            TokenKind::Eq,
+            TokenKind::Ident("$a".into()),
+            TokenKind::Semicolon
+        ]
+    );
+}
+
 #[test]
 fn skip_block_comment() {
     let ks = kinds("let/* hi */x").unwrap();
@@ -119,7 +171,7 @@ fn skip_block_comment() {
 
 #[test]
 fn error_unexpected_char() {
-    let err = lex("@").expect_err("should fail on illegal character");
+    let err = lex("#").expect_err("should fail on illegal character");
     assert_eq!(err.code, "UnexpectedChar");
     assert_eq!(err.span.start_line, 1);
     assert_eq!(err.span.start_col, 1);
@@ -133,6 +185,41 @@ fn error_unterminated_string() {
     assert_eq!(err.span.start_col, 1);
 }
 
+#[test]
+fn error_tokens_keep_scanning() {
+    let tokens = lex_recovering("let a = 1 # 2;\nlet s = 'abc\nlet b = 3; /* open");
+    let ks: Vec<TokenKind> = tokens.iter().map(|t| t.kind.clone()).collect();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::KwLet,
+            TokenKind::Ident("a".into()),
+            TokenKind::Eq,
+            TokenKind::Number(1),
+            TokenKind::Error(ErrorCode::UnexpectedChar),
+            TokenKind::Number(2),
+            TokenKind::Semicolon,
+            TokenKind::KwLet,
+            TokenKind::Ident("s".into()),
+            TokenKind::Eq,
+            TokenKind::Error(ErrorCode::UnterminatedString),
+            TokenKind::KwLet,
+            TokenKind::Ident("b".into()),
+            TokenKind::Eq,
+            TokenKind::Number(3),
+            TokenKind::Semicolon,
+            TokenKind::Error(ErrorCode::UnterminatedBlockComment),
+        ]
+    );
+    // Error token 覆盖出错的那段源码：没闭合的字符串到行尾为止
+    let string = &tokens[10].span;
+    assert_eq!((string.start_line, string.start_col, string.end_line, string.end_col), (2, 9, 2, 13));
+
+    // `lex` 返回第一个错误
+    let err = lex("let a = 1 # 2;\nlet s = 'abc").unwrap_err();
+    assert_eq!((err.code.as_str(), err.span.start_col), ("UnexpectedChar", 11));
+}
+
 #[test]
 fn span_line_col_across_newline() {
     let tokens = lex("let\nx").unwrap();
@@ -141,3 +228,22 @@ fn span_line_col_across_newline() {
     assert_eq!(tokens[1].span.start_line, 2);
     assert_eq!(tokens[1].span.start_col, 1);
 }
+
+#[test]
+fn lex_brackets_and_ellipsis() {
+    let ks = kinds("[ ] ... . f(...xs)").unwrap();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::LBracket,
+            TokenKind::RBracket,
+            TokenKind::Ellipsis,
+            TokenKind::Dot,
+            TokenKind::Ident("f".into()),
+            TokenKind::LParen,
+            TokenKind::Ellipsis,
+            TokenKind::Ident("xs".into()),
+            TokenKind::RParen,
+        ]
+    );
+}
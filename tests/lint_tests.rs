@@ -0,0 +1,146 @@
+use arkts2rust::{check_program, check_program_with, compile_with_diagnostics, compile_with_options, CompileOptions, ErrorCode, parse_program, Expr, Level, Lint, LintConfig, LintContext, Severity, TypeAnn};
+
+fn lint_codes(src: &str, config: &LintConfig) -> Vec<(String, usize, Severity)> {
+    let program = parse_program(src).unwrap();
+    check_program_with(&program, false, config)
+        .iter()
+        .map(|d| (d.code.to_string(), d.span.start_line, d.severity))
+        .collect()
+}
+
+#[test]
+fn builtin_lints_warn() {
+    let src = "let a = 1;\nlet b = 2;\na = a;\nif (a < a) { console.log(a); } else {}\nwhile (b > 3) {}\n\
+               if (b == 2) {} else { b = 3; }\nconsole.log(a == b, b);";
+    let found = lint_codes(src, &LintConfig::new());
+    assert_eq!(
+        found,
+        [
+            ("SelfAssignment".to_string(), 3, Severity::Warning),
+            ("SelfComparison".to_string(), 4, Severity::Warning),
+            ("EmptyBranch".to_string(), 5, Severity::Warning),
+            ("EmptyBranch".to_string(), 6, Severity::Warning),
+        ]
+    );
+
+    // 警告不影响生成代码
+    let result = compile_with_diagnostics(src);
+    assert!(result.is_ok());
+    assert_eq!(result.diagnostics.warnings().count(), 4);
+
+    // 函数里、嵌套函数里、字段赋值也检查；number 的 `x != x` 是判断 NaN，不报
+    let src = "function f(n: number): boolean {\n  function g(): void {\n    n = n;\n  }\n  g();\n  return n != n;\n}\n\
+               console.log(f(1));";
+    let found = lint_codes(src, &LintConfig::new());
+    assert_eq!(found, [("SelfAssignment".to_string(), 3, Severity::Warning)]);
+}
+
+#[test]
+fn lint_levels_are_configurable() {
+    let src = "let a = 1;\na = a;\nwhile (a > 3) {}\nconsole.log(a);";
+    let mut config = LintConfig::new();
+    config.set("empty_branch", Level::Allow);
+    config.set("self_assignment", Level::Deny);
+    assert_eq!(lint_codes(src, &config), [("SelfAssignment".to_string(), 2, Severity::Error)]);
+
+    // 有错误时不运行 lint
+    let program = parse_program("let a = 1;\na = a;\nconsole.log(b);").unwrap();
+    let codes: Vec<String> = check_program(&program, false).iter().map(|d| d.code.to_string()).collect();
+    assert_eq!(codes, ["UndeclaredVariable"]);
+}
+
+/// 自定义规则：字符串和它自己拼接（用 `type_of` 查类型）。
+struct StringDoubling;
+
+impl Lint for StringDoubling {
+    fn name(&self) -> &'static str {
+        "string_doubling"
+    }
+
+    fn message(&self) -> String {
+        "string is concatenated with itself".to_string()
+    }
+
+    fn check_expr(&self, e: &Expr, cx: &mut LintContext) {
+        if let Expr::Binary(b) = e {
+            if b.left == b.right && cx.type_of(&b.left) == Some(&TypeAnn::String) {
+                cx.report(b.span.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn custom_lints_see_types() {
+    let program = parse_program("let s = 'a';\nlet n = 1;\nconsole.log(s + s, n + n);").unwrap();
+    let types = arkts2rust::typeck::check(&program, false).unwrap();
+    let lints: Vec<Box<dyn Lint>> = vec![Box::new(StringDoubling)];
+    let found = arkts2rust::lint::run(&program, &types, &lints, &LintConfig::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].span.start_line, found[0].span.start_col), (3, 13));
+    assert_eq!(found[0].code, ErrorCode::CustomLint);
+    assert_eq!(found[0].args, [("lint", "string_doubling".to_string()), ("message", "string is concatenated with itself".to_string())]);
+    assert!(found[0].render("").starts_with("warning[CustomLint]: string is concatenated with itself (lint `string_doubling`)\n"));
+
+    let mut config = LintConfig::new();
+    config.set("string_doubling", Level::Allow);
+    assert!(arkts2rust::lint::run(&program, &types, &lints, &config).is_empty());
+}
+
+#[test]
+fn custom_lints_are_registered_in_options() {
+    let src = "let s = 'a';\nconsole.log(s + s);";
+    let mut config = LintConfig::new();
+    config.register(StringDoubling);
+    assert_eq!(lint_codes(src, &config), [("CustomLint".to_string(), 2, Severity::Warning)]);
+
+    config.set("string_doubling", Level::Deny);
+    let options = CompileOptions { lints: config, ..CompileOptions::default() };
+    let err = compile_with_options(src, &options).unwrap_err();
+    assert_eq!(err.code, ErrorCode::CustomLint);
+    assert!(err.render(src).starts_with("error[CustomLint]: string is concatenated with itself (lint `string_doubling`)\n"));
+    assert!(compile_with_options(src, &CompileOptions::default()).is_ok());
+}
+
+#[test]
+fn constant_conditions_and_infinite_loops() {
+    let src = "let a = 1;\nif (false) { console.log(a); } else {}\nif (1 + 1 > 3 || !true) { a = 3; } else { a = 4; }\n\
+               while (false) { a = 5; }\nwhile (true) { a = a + 1; }\nconsole.log(a);";
+    let found = lint_codes(src, &LintConfig::new());
+    assert_eq!(
+        found,
+        [
+            ("ConstantCondition".to_string(), 2, Severity::Warning),
+            ("ConstantCondition".to_string(), 3, Severity::Warning),
+            ("ConstantCondition".to_string(), 4, Severity::Warning),
+            ("InfiniteLoop".to_string(), 5, Severity::Warning),
+        ]
+    );
+
+    // 有 return / throw 的 `while (true)` 能结束；条件里有变量时不是常量
+    let src = "function f(n: number): number {\n  while (true) {\n    if (n > 3) { return n; } else { n = n + 1; }\n  }\n}\n\
+               function g(): void {\n  while (!false) {\n    throw new Error('x');\n  }\n}\n\
+               let b = true;\nif (b && true) { console.log(f(1)); } else { g(); }";
+    assert!(lint_codes(src, &LintConfig::new()).is_empty());
+}
+
+#[test]
+fn dead_stores() {
+    let src = "let a = 1;\na = 2;\na = 3;\nconsole.log(a);\nlet b = 0;\nif (a > 2) { b = 1; } else { b = 2; }\nconsole.log(b);\nb = 5;";
+    let found = lint_codes(src, &LintConfig::new());
+    assert_eq!(
+        found,
+        [
+            ("DeadStore".to_string(), 2, Severity::Warning),
+            ("DeadStore".to_string(), 8, Severity::Warning),
+        ]
+    );
+
+    // 循环里下一轮读取、catch 里读取、被嵌套函数捕获的变量都不算死存储；参数也检查
+    let src = "function sum(n: number, unused: number): number {\n  unused = 1;\n  let total = 0;\n  let i = 0;\n\
+               while (i < n) {\n    total = total + i;\n    i = i + 1;\n  }\n  return total;\n}\n\
+               function f(): number {\n  let x = 1;\n  try {\n    x = 2;\n    console.log(sum(x, 0));\n  } catch (e) {\n    return x;\n  }\n\
+               let y = 0;\n  function g(): number {\n    return y;\n  }\n  y = 3;\n  return g();\n}\nconsole.log(f());";
+    let found = lint_codes(src, &LintConfig::new());
+    assert_eq!(found, [("DeadStore".to_string(), 2, Severity::Warning)]);
+}
@@ -0,0 +1,35 @@
+use arkts2rust::codegen::{generate_with, warnings};
+use arkts2rust::{compile, parse_program, CodegenOptions, ErrorCode, ExternBindings, OutputMode};
+
+const SRC: &str = "function main(): void {\n  console.log(\"hi\");\n}\nmain();";
+
+#[test]
+fn user_main_is_renamed() {
+    let rust = compile(SRC).unwrap();
+    assert_eq!(
+        rust,
+        "fn main_() {\n    println!(\"{}\", String::from(\"hi\"));\n}\n\nfn main() {\n    main_();\n}\n"
+    );
+
+    // `main_` 已经被占用
+    let rust = compile("function main_(): number {\n  return 1;\n}\nfunction main(): number {\n  return main_();\n}\nconsole.log(main());")
+        .unwrap();
+    assert!(rust.contains("fn main_2() -> i32 {\n    return main_();\n}"), "got:\n{rust}");
+    assert!(rust.contains("println!(\"{}\", main_2());"), "got:\n{rust}");
+}
+
+#[test]
+fn renamed_main_warning() {
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions::default();
+    let found = warnings(&program, OutputMode::Binary, &options);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].code, ErrorCode::MainFunctionRenamed);
+    assert_eq!((found[0].span.start_line, found[0].span.start_col), (1, 1));
+
+    // 库代码没有生成的 `fn main`，不改名
+    let program = parse_program("export function main(): number {\n  return 1;\n}").unwrap();
+    assert!(warnings(&program, OutputMode::Library, &options).is_empty());
+    let rust = generate_with(&program, OutputMode::Library, &ExternBindings::new(), &options).unwrap();
+    assert!(rust.starts_with("pub fn main() -> i32 {\n"), "got:\n{rust}");
+}
@@ -0,0 +1,100 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, OutputMode};
+
+mod common;
+
+#[test]
+fn codegen_math_calls() {
+    let rust = compile(
+        "function f(a: number, b: number): number { \
+         let m = Math.min(a, b, 0) + Math.abs(-a); \
+         let ok = Math.sqrt(a) < 4; \
+         return Math.floor(a * b); }",
+    )
+    .unwrap();
+    assert!(rust.contains("    let m = a.min(b).min(0i32) + (-a).abs();\n"), "got:\n{rust}");
+    assert!(rust.contains("    let ok = (f64::from(a).sqrt() as i32) < 4i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("    return a * b;\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_random_with_bundled_helper() {
+    let rust = compile("let r = Math.floor(Math.random() * 6) + 1; let up = Math.random() * 2 < 1;").unwrap();
+    assert!(rust.starts_with("fn __ark_random() -> f64 {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("    let r = ((__ark_random() * 6.0).floor() as i32) + 1i32;\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    let up = __ark_random() * 2.0 < 1.0;\n"), "got:\n{rust}");
+
+    let rust = compile("console.log(Math.abs(-1));").unwrap();
+    assert!(!rust.contains("__ark_random"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_random_with_rand_crate() {
+    let program = parse_program("function f(n: number): number { return Math.round(Math.random() * n); }").unwrap();
+    let options = CodegenOptions {
+        use_rand: true,
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    assert!(
+        rust.contains("    return (rand::random::<f64>() * f64::from(n) + 0.5).floor() as i32;\n"),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("__ark_random"), "got:\n{rust}");
+}
+
+#[test]
+fn user_declared_math_is_not_builtin() {
+    let rust = compile(
+        "namespace Math { export function floor(x: number): number { return x - 1; } } \
+         console.log(Math.floor(3));",
+    )
+    .unwrap();
+    assert!(rust.contains("println!(\"{}\", math::floor(3i32));"), "got:\n{rust}");
+}
+
+#[test]
+fn error_math_misuse() {
+    for (src, code) in [
+        ("Math.pow(2);", "MathArgCount"),
+        ("Math.max();", "MathArgCount"),
+        ("Math.hypot(3, 4);", "UnknownMathFunction"),
+        ("let r = 2 * Math.PI;", "MathConstantNeedsFloat"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_math_code_runs() {
+    let src = r#"
+let a = 7;
+let b = -3;
+console.log(Math.abs(b), Math.min(a, b, 2), Math.max(a, 10), Math.round(a));
+console.log(Math.sqrt(a + 9), Math.sqrt(10) < 4, Math.pow(2, 10), Math.pow(2, -1));
+console.log(Math.max(1) + Math.sqrt(16) * 2);
+let ok = true;
+let i = 0;
+while (i < 1000) {
+  let r = Math.floor(Math.random() * 6) + 1;
+  let p = Math.round(Math.random() * 2 - 1);
+  if (r < 1 || r > 6 || p < -1 || p > 1) {
+    ok = false;
+  } else {
+  }
+  i = i + 1;
+}
+console.log(ok);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "3 -3 10 7\n4 true 1024 0\n9\ntrue\n");
+}
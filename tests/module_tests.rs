@@ -0,0 +1,625 @@
+use std::fs;
+use std::path::PathBuf;
+
+use arkts2rust::{
+    check_program_typed, compile, compile_as, compile_file, compile_file_as, compile_file_with, module,
+    parse_program, CodegenOptions, ExternBindings, ImportClause, ImportDecl, ImportSpec, LintConfig, NoteCode,
+    OutputMode, ReExportDecl, ResolveConfig, SourceFiles, Span,
+};
+
+mod common;
+
+fn spec(name: &str, alias: Option<&str>) -> ImportSpec {
+    ImportSpec {
+        name: name.into(),
+        alias: alias.map(String::from),
+        span: Default::default(),
+    }
+}
+
+#[test]
+fn parse_import_and_export() {
+    let p = parse_program(
+        "import { a, b } from './util'; export function f(): void { return; } function g(): void { return; }",
+    )
+    .unwrap();
+    assert_eq!(
+        p.imports,
+        vec![ImportDecl {
+            clause: ImportClause::Named(vec![spec("a", None), spec("b", None)]),
+            source: "./util".into(),
+            source_span: Span::new_with_line_col(21, 29, 1, 22, 1, 30),
+            span: Default::default(),
+        }]
+    );
+    assert!(p.funcs[0].is_export);
+    assert!(!p.funcs[1].is_export);
+}
+
+#[test]
+fn parse_aliases_namespaces_and_reexports() {
+    let p = parse_program(
+        "import { a as b, c } from './x'; import * as util from './util'; export { d, e as f } from './y';",
+    )
+    .unwrap();
+    assert_eq!(
+        p.imports,
+        vec![
+            ImportDecl {
+                clause: ImportClause::Named(vec![spec("a", Some("b")), spec("c", None)]),
+                source: "./x".into(),
+                source_span: Span::new_with_line_col(26, 31, 1, 27, 1, 32),
+                span: Default::default(),
+            },
+            ImportDecl {
+                clause: ImportClause::Namespace("util".into()),
+                source: "./util".into(),
+                source_span: Span::new_with_line_col(55, 63, 1, 56, 1, 64),
+                span: Default::default(),
+            },
+        ]
+    );
+    assert_eq!(
+        p.reexports,
+        vec![ReExportDecl {
+            specs: vec![spec("d", None), spec("e", Some("f"))],
+            source: "./y".into(),
+            source_span: Span::new_with_line_col(91, 96, 1, 92, 1, 97),
+        }]
+    );
+}
+
+#[test]
+fn namespace_import_requires_as() {
+    let err = parse_program("import * from './util';").expect_err("missing as");
+    assert_eq!(err.code, "ExpectedAs");
+}
+
+#[test]
+fn member_access_must_be_a_call() {
+    let err = compile("namespace util { export function f(): void {} } let f = util.f;")
+        .expect_err("no property values");
+    assert_eq!(err.code, "UnsupportedMemberAccess");
+}
+
+#[test]
+fn unknown_namespace_member() {
+    let err = compile("namespace util { export function f(): void {} } util.g();")
+        .expect_err("g is not a member of util");
+    assert_eq!(err.code, "UnknownMember");
+}
+
+#[test]
+fn import_requires_from() {
+    let err = parse_program("import { a } './util';").expect_err("missing from");
+    assert_eq!(err.code, "ExpectedFrom");
+    assert_eq!(err.span.start_col, 14);
+}
+
+#[test]
+fn import_and_export_only_at_top_level() {
+    let err = parse_program("{ import { a } from './a'; }").expect_err("nested import");
+    assert_eq!(err.code, "ImportNotAllowedHere");
+    let err = parse_program("{ export function f(): void { return; } }").expect_err("nested export");
+    assert_eq!(err.code, "ExportNotAllowedHere");
+}
+
+#[test]
+fn export_requires_function() {
+    let err = parse_program("export let x = 1;").expect_err("only functions");
+    assert_eq!(err.code, "UnsupportedExport");
+}
+
+#[test]
+fn single_source_compile_rejects_imports() {
+    let err = compile("import { f } from './util'; f();").expect_err("needs compile_file");
+    assert_eq!(err.code, "UnresolvedImport");
+}
+
+#[test]
+fn missing_module_reports_importing_file() {
+    let dir = common::write_project(&[("main.ets", "import { f } from './nope';")]);
+    let err = compile_file(dir.join("main.ets")).expect_err("missing module");
+    assert_eq!(err.code, "ModuleNotFound");
+    assert_eq!(err.span.start_col, 19);
+    assert!(err.file.unwrap().ends_with("main.ets"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+fn compile_with_resolve(entry: PathBuf, resolve: &ResolveConfig) -> Result<String, arkts2rust::Error> {
+    compile_file_with(entry, OutputMode::Binary, &ExternBindings::new(), resolve, &CodegenOptions::default())
+}
+
+#[test]
+fn path_alias_resolves_from_entry_dir() {
+    let dir = common::write_project(&[
+        ("app/main.ets", "import { log } from '@common/log'; log(1);"),
+        ("app/src/common/log.ets", "export function log(x: number): void { console.log(x); }"),
+    ]);
+    let resolve = ResolveConfig {
+        paths: vec![("@common/*".into(), vec!["src/common/*".into()])],
+        ..Default::default()
+    };
+    compile_with_resolve(dir.join("app/main.ets"), &resolve).unwrap();
+
+    let err = compile_file(dir.join("app/main.ets")).expect_err("no alias configured");
+    assert_eq!(err.code, "UnsupportedModulePath");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn path_alias_tries_targets_in_order_and_prefers_longest_prefix() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            "import { a } from '@lib/a'; import { b } from '@lib/special/b'; console.log(a() + b());",
+        ),
+        ("root/gen/a.ets", "export function a(): number { return 1; }"),
+        ("root/special/b.ets", "export function b(): number { return 2; }"),
+    ]);
+    let resolve = ResolveConfig {
+        base_dir: Some(dir.join("root")),
+        paths: vec![
+            ("@lib/*".into(), vec!["src/*".into(), "gen/*".into()]),
+            ("@lib/special/*".into(), vec!["special/*".into()]),
+        ],
+        ..Default::default()
+    };
+    compile_with_resolve(dir.join("main.ets"), &resolve).unwrap();
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn base_dir_resolves_bare_paths_with_custom_extensions() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from 'util/f'; f();"),
+        ("lib/util/f.ts", "export function f(): void {}"),
+    ]);
+    let mut resolve = ResolveConfig {
+        base_dir: Some(dir.join("lib")),
+        ..Default::default()
+    };
+    let err = compile_with_resolve(dir.join("main.ets"), &resolve).expect_err(".ts not tried");
+    assert_eq!(err.code, "ModuleNotFound");
+    resolve.extensions = vec![".ts".into()];
+    compile_with_resolve(dir.join("main.ets"), &resolve).unwrap();
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn circular_import_lists_cycle() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { a } from './a';\nconsole.log(a());"),
+        ("a.ets", "import { b } from './b';\nexport function a(): number { return 1; }"),
+        ("b.ets", "export function b(): void {}\nexport { a } from './a';"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("cycle");
+    assert_eq!(err.code, "CircularImport");
+    assert!(err.file.as_deref().unwrap().ends_with("b.ets"));
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 19));
+    let notes: Vec<(String, usize, usize, String)> = err
+        .notes
+        .iter()
+        .map(|n| {
+            let file = |p: &str| PathBuf::from(p).file_name().unwrap().to_string_lossy().into_owned();
+            assert_eq!(n.code, NoteCode::Imports);
+            (file(&n.file), n.span.start_line, n.span.start_col, file(&n.args[0].1))
+        })
+        .collect();
+    assert_eq!(
+        notes,
+        vec![
+            ("a.ets".to_string(), 1, 19, "b.ets".to_string()),
+            ("b.ets".to_string(), 2, 19, "a.ets".to_string()),
+        ]
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn self_import_is_circular() {
+    let dir = common::write_project(&[("main.ets", "import { f } from './main'; export function f(): void {}")]);
+    let err = compile_file(dir.join("main.ets")).expect_err("self import");
+    assert_eq!(err.code, "CircularImport");
+    assert_eq!(err.notes.len(), 1);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn diamond_import_is_not_circular() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { b } from './b'; import { c } from './c'; console.log(b() + c());"),
+        ("b.ets", "import { d } from './d'; export function b(): number { return d(); }"),
+        ("c.ets", "import { d } from './d'; export function c(): number { return d(); }"),
+        ("d.ets", "export function d(): number { return 1; }"),
+    ]);
+    compile_file(dir.join("main.ets")).unwrap();
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn bare_module_path_is_rejected() {
+    let dir = common::write_project(&[("main.ets", "import { f } from 'util';")]);
+    let err = compile_file(dir.join("main.ets")).expect_err("bare specifier");
+    assert_eq!(err.code, "UnsupportedModulePath");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn parse_error_reports_dependency_file() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';"),
+        ("util.ets", "export function f(): void { return }"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("syntax error in util");
+    assert_eq!(err.code, "ExpectedExpr");
+    assert!(err.file.unwrap().ends_with("util.ets"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn import_must_be_exported() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { hidden } from './util';"),
+        ("util.ets", "function hidden(): void { return; }"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("not exported");
+    assert_eq!(err.code, "ImportNotExported");
+    // 指向 import 的那一项
+    assert!(err.file.as_deref().unwrap().ends_with("main.ets"));
+    assert_eq!((err.span.start_line, err.span.start_col, err.span.end_col), (1, 10, 16));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn private_functions_with_the_same_name_in_different_modules() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            "import { f } from './util';\nfunction helper(): number { return 1; }\nconsole.log(helper() + f());",
+        ),
+        (
+            "util.ets",
+            "export function f(): number { return helper(); }\nfunction helper(): number { return 10; }",
+        ),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    // 入口模块保留原来的名字，util.ets 的私有函数改名
+    assert!(rust.contains("fn helper() -> i32 {\n    return 1i32;\n}"), "got:\n{rust}");
+    assert!(rust.contains("fn helper_2() -> i32 {\n    return 10i32;\n}"), "got:\n{rust}");
+    assert!(rust.contains("return helper_2();"), "got:\n{rust}");
+
+    // 只有 import 进来的名字才和模块自己的名字冲突
+    fs::write(dir.join("util.ets"), "export function f(): number { return 1; }\nexport function helper(): number { return 10; }").unwrap();
+    fs::write(dir.join("main.ets"), "import { f, helper } from './util';\nfunction helper(): number { return 1; }").unwrap();
+    let err = compile_file(dir.join("main.ets")).expect_err("helper is imported and defined");
+    assert_eq!(err.code, "DuplicateSymbol");
+    assert!(err.file.unwrap().ends_with("main.ets"));
+    assert_eq!((err.span.start_line, err.span.start_col), (1, 13));
+
+    let run = common::compile_and_run(&rust);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "11\n");
+}
+
+#[test]
+fn duplicate_function_within_one_module_is_duplicate_declaration() {
+    let dir = common::write_project(&[(
+        "main.ets",
+        "function helper(): void { return; }\nfunction helper(): void { return; }\nhelper();",
+    )]);
+    let err = compile_file(dir.join("main.ets")).expect_err("helper declared twice");
+    assert_eq!(err.code, "DuplicateDeclaration");
+    assert_eq!(err.span.start_line, 2);
+    assert!(err.file.as_deref().unwrap().ends_with("main.ets"));
+    assert_eq!((err.notes[0].message(), err.notes[0].span.start_line), ("first declared here".to_string(), 1));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn duplicate_declaration_in_a_module_does_not_hide_other_errors() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        (
+            "util.ets",
+            "export function f(): number { return helper(); }\nfunction helper(): number { return 1; }\nfunction helper(): number { return missing; }",
+        ),
+    ]);
+    let modules = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).unwrap();
+    let files = SourceFiles::of(&modules);
+    let program = module::link(modules).unwrap();
+    let (diagnostics, _) = check_program_typed(&program, &CodegenOptions::default(), &LintConfig::default());
+    let _ = fs::remove_dir_all(dir);
+    let found: Vec<(&str, usize, bool)> = diagnostics
+        .locate(&files)
+        .errors()
+        .map(|e| (e.code.as_str(), e.span.start_line, e.file.as_deref().unwrap().ends_with("util.ets")))
+        .collect();
+    assert_eq!(found, [("DuplicateDeclaration", 3, true), ("UndeclaredVariable", 3, true)]);
+}
+
+#[test]
+fn link_reports_every_error() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { hidden } from './a';\nimport b from './b';"),
+        ("a.ets", "export function f(): void {}\nfunction hidden(): void {}"),
+        ("b.ets", "export function f(): void {}"),
+    ]);
+    let modules = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).unwrap();
+    let diagnostics = module::link(modules).expect_err("three link errors");
+    let _ = fs::remove_dir_all(dir);
+    let found: Vec<(&str, &str)> = diagnostics
+        .errors()
+        .map(|e| (e.code.as_str(), e.file.as_deref().unwrap().rsplit(['/', '\\']).next().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        [("ImportNotExported", "main.ets"), ("UnsupportedDefaultImport", "main.ets"), ("DuplicateSymbol", "b.ets")]
+    );
+}
+
+#[test]
+fn codegen_alias_and_namespace_items() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            "import { double as twice } from './util'; import * as u from './util'; console.log(twice(u.double(1)));",
+        ),
+        (
+            "util.ets",
+            "export function double(x: number): number { return x * 2; } export function half(x: number): number { return x / 2; }",
+        ),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(
+        rust.starts_with(
+            "use crate::double as twice;\n\nmod u {\n    pub(crate) use super::double;\n    pub(crate) use super::half;\n}\n\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("println!(\"{}\", twice(u::double(1i32)));"), "got:\n{rust}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn library_mode_reexport_alias_is_pub_use() {
+    let dir = common::write_project(&[
+        ("index.ets", "export { double as twice } from './util';"),
+        ("util.ets", "export function double(x: number): number { return x * 2; }"),
+    ]);
+    let rust = compile_file_as(dir.join("index.ets"), OutputMode::Library).unwrap();
+    assert!(rust.starts_with("pub use crate::double as twice;\n\npub fn double("), "got:\n{rust}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn reexport_of_unexported_name_fails() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './index';"),
+        ("index.ets", "export { f } from './util';"),
+        ("util.ets", "function f(): void { return; }"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("f is private");
+    assert_eq!(err.code, "ImportNotExported");
+    assert_eq!((err.span.start_line, err.span.start_col), (1, 10));
+    assert!(err.file.unwrap().ends_with("index.ets"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn check_diagnostics_point_into_the_imported_file() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        ("util.ets", "export function f(): number {\n  let s: string = 1;\n  return 1;\n}"),
+    ]);
+    let modules = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).unwrap();
+    let files = SourceFiles::of(&modules);
+    let program = module::link(modules).unwrap();
+    let (diagnostics, _) = check_program_typed(&program, &CodegenOptions::default(), &LintConfig::default());
+    let err = diagnostics.locate(&files).errors().next().cloned().expect("type error in util.ets");
+    assert_eq!(err.code, "TypeMismatch");
+    assert!(err.file.as_deref().unwrap().ends_with("util.ets"), "{:?}", err.file);
+    let rendered = err.render(&fs::read_to_string(dir.join("util.ets")).unwrap());
+    assert!(rendered.contains("util.ets:2:7\n"), "{rendered}");
+    assert!(rendered.contains("2 |   let s: string = 1;\n"), "{rendered}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn compile_file_errors_name_the_module_they_come_from() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        ("util.ets", "export function f(): number {\n  return missing;\n}"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("undeclared name in util.ets");
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert!(err.file.as_deref().unwrap().ends_with("util.ets"), "{:?}", err.file);
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 10));
+
+    // 只有 CodeGen 才发现的问题也一样
+    fs::write(dir.join("util.ets"), "export function f(): number {\n  return Math.nope(1);\n}").unwrap();
+    let err = compile_file(dir.join("main.ets")).expect_err("unknown Math function");
+    assert!(err.file.as_deref().unwrap().ends_with("util.ets"), "{:?}", err.file);
+    assert_eq!(err.span.start_line, 2);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn loading_reports_every_syntax_error_of_a_module() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        ("util.ets", "export function f(): number {\n  let a = (1;\n  let b = 2 +;\n  return 1;\n}"),
+    ]);
+    let diagnostics = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).expect_err("two syntax errors");
+    let errors: Vec<(usize, bool)> = diagnostics
+        .errors()
+        .map(|e| (e.span.start_line, e.file.as_deref().unwrap().ends_with("util.ets")))
+        .collect();
+    assert_eq!(errors, [(2, true), (3, true)]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn loading_reports_every_lexical_error_of_a_module() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        ("util.ets", "export function f(): number {\n  let a = 1 # 2;\n  let s = 'open;\n  return 1;\n}"),
+    ]);
+    let diagnostics = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).expect_err("two lexical errors");
+    let errors: Vec<(&str, usize)> = diagnostics.errors().map(|e| (e.code.as_str(), e.span.start_line)).collect();
+    assert_eq!(errors, [("UnexpectedChar", 2), ("UnterminatedString", 3)]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn alias_only_conflicts_with_names_of_the_importing_module() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f as g } from './a';\nimport * as h from './a';\nconsole.log(g() + h.f());"),
+        (
+            "a.ets",
+            "export function f(): number { return g() + h(); }\nfunction g(): number { return 1; }\nfunction h(): number { return 2; }",
+        ),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(rust.contains("use crate::f as g;"), "got:\n{rust}");
+    assert!(rust.contains("return g_2() + h_2();"), "got:\n{rust}");
+
+    // 和导入它的模块自己的函数重名才是冲突
+    fs::write(dir.join("main.ets"), "import { f as g } from './a';\nfunction g(): number { return 3; }").unwrap();
+    let err = compile_file(dir.join("main.ets")).expect_err("g already defined in main.ets");
+    assert_eq!(err.code, "DuplicateSymbol");
+    assert!(err.file.unwrap().ends_with("main.ets"));
+    assert_eq!((err.span.start_line, err.span.start_col), (1, 10));
+
+    let run = common::compile_and_run(&rust);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "6\n");
+}
+
+#[test]
+fn same_alias_name_in_two_modules_can_point_to_different_functions() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { one as pick } from './a';\nimport { show } from './b';\nshow();\nconsole.log(pick());"),
+        ("a.ets", "export function one(): number { return 1; }\nexport function two(): number { return 2; }"),
+        ("b.ets", "import { two as pick } from './a';\nexport function show(): void { console.log(pick()); }"),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(rust.contains("use crate::one as pick;"), "got:\n{rust}");
+    assert!(rust.contains("use crate::two as pick_2;"), "got:\n{rust}");
+
+    let run = common::compile_and_run(&rust);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "2\n1\n");
+}
+
+#[test]
+fn class_alias_is_unsupported() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { NotFound as Missing } from './errors';"),
+        ("errors.ets", "export class NotFound extends Error {}"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("classes keep their name");
+    assert_eq!(err.code, "UnsupportedClassAlias");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn single_source_compile_rejects_reexports() {
+    let err = compile_as("export { f } from './util';", OutputMode::Library).expect_err("needs files");
+    assert_eq!(err.code, "UnresolvedImport");
+}
+
+#[test]
+fn generated_barrel_program_runs() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            r#"
+import { double as twice, check } from './lib/index';
+import * as m from './lib/math';
+console.log(twice(m.square(3)));
+console.log(m.cube(2));
+try { check(-1); } catch (e) { console.log(e); }
+"#,
+        ),
+        ("lib/index.ets", "export { double, validate as check } from './util';"),
+        (
+            "lib/util.ets",
+            r#"
+export function double(x: number): number { return x * 2; }
+export function validate(x: number): void { if (x < 0) { throw "negative"; } else { return; } }
+"#,
+        ),
+        (
+            "lib/math.ets",
+            r#"
+import { validate } from './util';
+export function square(x: number): number { return x * x; }
+export function cube(x: number): number { validate(x); return x * x * x; }
+"#,
+        ),
+    ]);
+
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+
+    let run = common::compile_and_run(&rust);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "18\n8\nnegative\n");
+}
+
+#[test]
+fn generated_multi_file_program_runs() {
+    let dir = common::write_project(&[
+        (
+            "main.ets",
+            r#"
+import { double, greet } from './lib/util';
+import { square } from './lib/math.ets';
+console.log(double(square(3)));
+greet("main");
+"#,
+        ),
+        (
+            "lib/util.ets",
+            r#"
+import { square } from './math';
+export function double(x: number): number { return x * 2; }
+export function greet(s: string): void { console.log(s); }
+console.log(square(2));
+"#,
+        ),
+        (
+            "lib/math.ets",
+            r#"
+export function square(x: number): number { return x * x; }
+let loaded = "math loaded";
+console.log(loaded);
+"#,
+        ),
+    ]);
+
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert_eq!(rust.matches("fn square(").count(), 1, "got:\n{rust}");
+
+    let run = common::compile_and_run(&rust);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "math loaded\n4\n18\nmain\n"
+    );
+}
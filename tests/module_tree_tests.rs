@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::PathBuf;
+
+use arkts2rust::{
+    check_program, compile_file_with, compile_tree, module, CodegenOptions, ExternBindings, OutputFile, OutputMode, ResolveConfig,
+};
+
+mod common;
+
+fn tree(entry: PathBuf, mode: OutputMode) -> Vec<OutputFile> {
+    compile_tree(entry, mode, &ExternBindings::new(), &ResolveConfig::default(), &CodegenOptions::default()).unwrap()
+}
+
+fn file<'a>(files: &'a [OutputFile], path: &str) -> &'a str {
+    files
+        .iter()
+        .find(|f| f.path == std::path::Path::new(path))
+        .unwrap_or_else(|| panic!("missing {path}"))
+        .code
+        .as_str()
+}
+
+#[test]
+fn single_file_project_is_just_the_root() {
+    let dir = common::write_project(&[("main.ets", "console.log(1);")]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, PathBuf::from("main.rs"));
+    assert_eq!(files[0].code, "fn main() {\n    println!(\"{}\", 1i32);\n}\n");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn one_rust_module_per_file() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { sq as square } from './math-utils'; console.log(square(2));"),
+        (
+            "math-utils.ets",
+            "export function sq(x: number): number { return mul(x, x); }\n\
+             function mul(a: number, b: number): number { return a * b; }",
+        ),
+    ]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    let paths: Vec<&PathBuf> = files.iter().map(|f| &f.path).collect();
+    assert_eq!(paths, vec![&PathBuf::from("main.rs"), &PathBuf::from("math_utils.rs")]);
+    assert_eq!(
+        file(&files, "main.rs"),
+        "mod math_utils;\n\nuse crate::math_utils::sq as square;\n\n\
+         fn main() {\n    println!(\"{}\", square(2i32));\n}\n"
+    );
+    assert_eq!(
+        file(&files, "math_utils.rs"),
+        "pub(crate) fn sq(x: i32) -> i32 {\n    return mul(x, x);\n}\n\n\
+         fn mul(a: i32, b: i32) -> i32 {\n    return a * b;\n}\n\n"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn directories_become_nested_modules() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './a/b/c'; import { g } from './a'; f(); g();"),
+        ("a.ets", "export function g(): void {}"),
+        ("a/b/c.ets", "export function f(): void {}"),
+    ]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    let mut paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+    paths.sort();
+    let expected: Vec<PathBuf> = ["a/b/c.rs", "a/b/mod.rs", "a/mod.rs", "main.rs"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(paths, expected);
+    assert_eq!(file(&files, "a/mod.rs"), "pub(crate) mod b;\n\npub(crate) fn g() {\n}\n\n");
+    assert_eq!(file(&files, "a/b/mod.rs"), "pub(crate) mod c;\n\n");
+    assert!(file(&files, "main.rs").contains("use crate::a::b::c::f;\n"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn library_tree_uses_pub_items() {
+    let dir = common::write_project(&[
+        ("lib.ets", "export { sq } from './util'; export function one(): number { return 1; }"),
+        ("util.ets", "export function sq(x: number): number { return x * x; }"),
+    ]);
+    let files = tree(dir.join("lib.ets"), OutputMode::Library);
+    assert_eq!(
+        file(&files, "lib.rs"),
+        "pub mod util;\n\npub use crate::util::sq;\n\npub fn one() -> i32 {\n    return 1i32;\n}\n\n"
+    );
+    assert_eq!(file(&files, "util.rs"), "pub fn sq(x: i32) -> i32 {\n    return x * x;\n}\n\n");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn colliding_module_names_are_rejected() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './a-b'; import { g } from './a_b'; f(); g();"),
+        ("a-b.ets", "export function f(): void {}"),
+        ("a_b.ets", "export function g(): void {}"),
+    ]);
+    let err = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &CodegenOptions::default(),
+    )
+    .expect_err("a-b and a_b are both `a_b`");
+    assert_eq!(err.code, "DuplicateModuleName");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn both_entry_points_check_the_linked_program() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './util';\nconsole.log(f());"),
+        ("util.ets", "export function f(): number {\n  let x: number;\n  return x;\n}"),
+    ]);
+    let entry = dir.join("main.ets");
+    let modules = module::load_modules_with(&entry, &ResolveConfig::default()).unwrap();
+    let expected = check_program(&module::link(modules).unwrap(), false).first_error().unwrap();
+    assert_eq!(expected.code, "UseOfUninitialized");
+    let options = CodegenOptions::default();
+    let err = compile_tree(&entry, OutputMode::Binary, &ExternBindings::new(), &ResolveConfig::default(), &options).unwrap_err();
+    assert_eq!((err.code, err.span), (expected.code, expected.span));
+    let err =
+        compile_file_with(&entry, OutputMode::Binary, &ExternBindings::new(), &ResolveConfig::default(), &options).unwrap_err();
+    assert_eq!((err.code, err.span), (expected.code, expected.span));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn imported_consts_are_module_items() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { LIMIT } from './consts'; console.log(LIMIT);"),
+        ("consts.ets", "export const LIMIT = 10;\nexport const NAME = \"x\";\nconsole.log(NAME);"),
+    ]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    let _ = fs::remove_dir_all(dir);
+    assert!(file(&files, "main.rs").contains("use crate::consts::LIMIT;\n"));
+    let consts = file(&files, "consts.rs");
+    assert!(consts.starts_with("pub(crate) const LIMIT: i32 = 10i32;\n"), "got:\n{consts}");
+    // 没有被 import、也不是常量表达式的导出 const 还是 `__init` 里的局部变量
+    assert!(consts.contains("let NAME"), "got:\n{consts}");
+}
+
+#[test]
+fn private_functions_with_the_same_name_compile() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { f } from './a';\nfunction helper(): number { return 1; }\nconsole.log(helper() + f());"),
+        ("a.ets", "export function f(): number { return helper(); }\nfunction helper(): number { return 10; }"),
+    ]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    let out_dir = dir.join("out");
+    for f in &files {
+        fs::create_dir_all(out_dir.join(&f.path).parent().unwrap()).unwrap();
+        fs::write(out_dir.join(&f.path), &f.code).unwrap();
+    }
+    assert!(file(&files, "a.rs").contains("fn helper_2() -> i32"), "got:\n{}", file(&files, "a.rs"));
+
+    let run = common::run_file(&out_dir.join("main.rs"), &[]);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "11\n");
+}
+
+#[test]
+fn reexported_consts_are_pub_use() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { aa, PI2 } from './index';\nconsole.log(aa() + PI2);"),
+        ("index.ets", "export { a as aa, PI2 } from './a';"),
+        ("a.ets", "export const PI2 = 6;\nexport function a(): number { return 1; }"),
+    ]);
+    let files = tree(dir.join("main.ets"), OutputMode::Binary);
+    let out_dir = dir.join("out");
+    for f in &files {
+        fs::create_dir_all(out_dir.join(&f.path).parent().unwrap()).unwrap();
+        fs::write(out_dir.join(&f.path), &f.code).unwrap();
+    }
+    assert_eq!(file(&files, "index.rs"), "pub(crate) use crate::a::a as aa;\npub(crate) use crate::a::PI2;\n\n");
+    assert!(file(&files, "main.rs").contains("use crate::a::PI2;\n"), "got:\n{}", file(&files, "main.rs"));
+    assert!(file(&files, "a.rs").starts_with("pub(crate) const PI2: i32 = 6i32;\n"), "got:\n{}", file(&files, "a.rs"));
+
+    let run = common::run_file(&out_dir.join("main.rs"), &[]);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "7\n");
+}
+
+#[test]
+fn generated_tree_compiles_and_runs() {
+    let dir = common::write_project(&[
+        (
+            "src/main.ets",
+            "import { log } from './common/log';\n\
+             import * as m from './math';\n\
+             import { Errors } from './common/errors';\n\
+             log(m.add(1, 2));\n\
+             try { log(m.safeDiv(1, 0)); } catch (e) { console.log(e); }\n\
+             log(Errors.code());",
+        ),
+        (
+            "src/common/log.ets",
+            "export function log(x: number): void { console.log(x); }\nconsole.log(\"log loaded\");",
+        ),
+        (
+            "src/common/errors.ets",
+            "export namespace Errors { export function code(): number { return 7; } }",
+        ),
+        (
+            "src/math.ets",
+            "import { log } from './common/log';\n\
+             export function add(a: number, b: number): number { return a + b; }\n\
+             export function safeDiv(a: number, b: number): number {\n\
+               if (b == 0) { throw \"div by zero\"; } else { return a / b; }\n\
+             }\n\
+             log(0);",
+        ),
+    ]);
+    let files = tree(dir.join("src/main.ets"), OutputMode::Binary);
+    let out_dir = dir.join("out");
+    for f in &files {
+        let path = out_dir.join(&f.path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, &f.code).unwrap();
+    }
+
+    let run = common::run_file(&out_dir.join("main.rs"), &[]);
+    let _ = fs::remove_dir_all(dir);
+    let Some(run) = run else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "log loaded\n0\n3\ndiv by zero\n7\n"
+    );
+}
@@ -0,0 +1,33 @@
+use arkts2rust::compile;
+
+#[test]
+fn codegen_mut_only_for_reassigned_variables() {
+    let rust = compile(
+        "let total = 0;\nlet label = 'n';\nconst p = 1;\ntotal = total + p;\n\
+         function f(n: number): number { let a = n; let b = 0; while (b < a) { b = b + 1; } return b; }\n\
+         function g(): number { let count = 0; function bump(): void { count = count + 1; } bump(); return count; }\n\
+         console.log(label, total, f(1), g());",
+    )
+    .unwrap();
+    assert!(rust.contains("    let mut total = 0i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let label = String::from(\"n\");\n"), "got:\n{rust}");
+    assert!(rust.contains("    let p = 1i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let a = n;\n    let mut b = 0i32;\n"), "got:\n{rust}");
+    // 被闭包捕获又被赋值的变量放进 Cell，不需要 mut
+    assert!(rust.contains("    let count = std::cell::Cell::new(0i32);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_mut_for_uninitialized_assigned_twice() {
+    let rust = compile(
+        "function f(c: boolean): number {\n  let x: number;\n  if (c) { x = 1; } else { x = 2; }\n  \
+         let y: number;\n  y = 1;\n  y = y + x;\n  let z: number;\n  while (c) { z = 1; c = false; }\n  return y; }",
+    )
+    .unwrap();
+    // 每条路径上只赋值一次：只是初始化
+    assert!(rust.contains("    let x: i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let mut y: i32;\n"), "got:\n{rust}");
+    // 循环里的赋值可能执行多次
+    assert!(rust.contains("    let mut z: i32;\n"), "got:\n{rust}");
+    assert!(rust.starts_with("fn f(mut c: bool) -> i32 {\n"), "got:\n{rust}");
+}
@@ -0,0 +1,124 @@
+use std::fs;
+
+use arkts2rust::{compile, compile_as, compile_file, parse_program, OutputMode};
+
+mod common;
+
+#[test]
+fn parse_namespace_decl() {
+    let p = parse_program(
+        "namespace Utils { export function f(): void {} function g(): void {} } export namespace Api {}",
+    )
+    .unwrap();
+    assert_eq!(p.namespace_decls.len(), 2);
+    let utils = &p.namespace_decls[0];
+    assert_eq!(utils.name, "Utils");
+    assert!(!utils.is_export);
+    let exported: Vec<(&str, bool)> = utils.funcs.iter().map(|f| (f.name.as_str(), f.is_export)).collect();
+    assert_eq!(exported, vec![("f", true), ("g", false)]);
+    assert!(p.namespace_decls[1].is_export);
+    assert!(p.funcs.is_empty());
+}
+
+#[test]
+fn namespace_only_allows_functions() {
+    let err = parse_program("namespace Utils { const x = 1; }").expect_err("const member");
+    assert_eq!(err.code, "UnsupportedNamespaceMember");
+    let err = parse_program("namespace Utils { function f(): void {}").expect_err("unclosed");
+    assert_eq!(err.code, "MissingRBrace");
+}
+
+#[test]
+fn namespace_only_at_top_level() {
+    let err = parse_program("{ namespace Utils {} }").expect_err("nested namespace");
+    assert_eq!(err.code, "NamespaceNotAllowedHere");
+}
+
+#[test]
+fn codegen_namespace_as_mod() {
+    let rust = compile(
+        "namespace MathUtils { export function one(): number { return helper(); } \
+         function helper(): number { return 1; } } \
+         console.log(MathUtils.one());",
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "mod math_utils {\n    pub fn one() -> i32 {\n        return helper();\n    }\n\n\
+             \x20   fn helper() -> i32 {\n        return 1i32;\n    }\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("println!(\"{}\", math_utils::one());"), "got:\n{rust}");
+}
+
+#[test]
+fn private_namespace_member_is_unknown() {
+    let err = compile("namespace Utils { function hidden(): void {} } Utils.hidden();")
+        .expect_err("not exported");
+    assert_eq!(err.code, "UnknownMember");
+}
+
+#[test]
+fn library_mode_exported_namespace_is_pub_mod() {
+    let rust = compile_as(
+        "export namespace Api { export function f(): number { return 1; } } \
+         namespace Internal { export function g(): number { return 2; } } \
+         export function h(): number { return Internal.g(); }",
+        OutputMode::Library,
+    )
+    .unwrap();
+    assert!(rust.contains("pub mod api {\n    pub fn f() -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("\nmod internal {\n    pub fn g() -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("return internal::g();"), "got:\n{rust}");
+}
+
+#[test]
+fn exported_namespace_can_be_imported() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { Utils } from './utils'; console.log(Utils.f());"),
+        ("utils.ets", "export namespace Utils { export function f(): number { return 1; } }"),
+    ]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(rust.contains("mod utils {"), "got:\n{rust}");
+    assert!(rust.contains("utils::f()"), "got:\n{rust}");
+
+    let err = {
+        fs::write(dir.join("main.ets"), "import { Utils as U } from './utils';").unwrap();
+        compile_file(dir.join("main.ets")).expect_err("renamed namespace")
+    };
+    assert_eq!(err.code, "UnsupportedNamespaceAlias");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn generated_namespace_code_runs() {
+    let src = r#"
+function twice(x: number): number { return x * 2; }
+namespace MathUtils {
+  export function quad(x: number): number { return double(double(x)); }
+  function double(x: number): number { return twice(x) / 2 + x; }
+  export function check(x: number): number {
+    if (x < 0) { throw "negative"; } else { return x; }
+  }
+}
+namespace Greeter {
+  export function hello(): void { console.log("hello"); }
+}
+function double(x: number): number { return x; }
+console.log(MathUtils.quad(3));
+console.log(double(3));
+Greeter.hello();
+try { console.log(MathUtils.check(-1)); } catch (e) { console.log(e); }
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "12\n3\nhello\nnegative\n"
+    );
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::PathBuf;
+
+use arkts2rust::{compile_as, compile_tree, CodegenOptions, ExternBindings, OutputMode, ResolveConfig};
+
+mod common;
+
+const LIB: &str = r#"
+export function add(a: number, b: number): number {
+  return a + b;
+}
+
+export function greet(name: string | null): string {
+  if (name == null) {
+    return "hello";
+  } else {
+    return name;
+  }
+}
+
+export function check(n: number): number {
+  if (n < 0) {
+    throw new Error("negative");
+  } else {
+    return n;
+  }
+}
+
+export function reset(): void {
+  console.log("reset");
+}
+
+function helper(): number {
+  return 1;
+}
+"#;
+
+#[test]
+fn codegen_napi_wrappers() {
+    let rust = compile_as(LIB, OutputMode::NativeModule).unwrap();
+    assert!(rust.contains("pub fn add(a: i32, b: i32) -> i32 {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "use napi_derive_ohos::napi;\n\n\
+             #[napi(js_name = \"add\")]\npub fn napi_add(a: i32, b: i32) -> i32 {\n    add(a, b)\n}\n\n\
+             #[napi(js_name = \"greet\")]\npub fn napi_greet(name: Option<String>) -> String {\n    greet(name)\n}\n\n\
+             #[napi(js_name = \"check\")]\npub fn napi_check(n: i32) -> napi_ohos::Result<i32> {\n    \
+             check(n).map_err(|e| napi_ohos::Error::from_reason(e.to_string()))\n}\n\n\
+             #[napi(js_name = \"reset\")]\npub fn napi_reset() {\n    reset()\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("napi_helper"), "got:\n{rust}");
+    assert!(!rust.contains("fn main"), "got:\n{rust}");
+
+    // 普通的库模式不生成包装
+    let rust = compile_as(LIB, OutputMode::Library).unwrap();
+    assert!(!rust.contains("napi"), "got:\n{rust}");
+}
+
+#[test]
+fn native_module_tree_wraps_reexports() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_native_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("index.ets"),
+        "export { square as sq } from './math';\nexport function twice(n: number): number { return n * 2; }",
+    )
+    .unwrap();
+    fs::write(dir.join("math.ets"), "export function square(n: number): number { return n * n; }").unwrap();
+
+    let files = compile_tree(
+        dir.join("index.ets"),
+        OutputMode::NativeModule,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &CodegenOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(files[0].path, PathBuf::from("lib.rs"));
+    let root = &files[0].code;
+    assert!(
+        root.contains("#[napi(js_name = \"twice\")]\npub fn napi_twice(n: i32) -> i32 {\n    twice(n)\n}\n"),
+        "got:\n{root}"
+    );
+    assert!(
+        root.contains("#[napi(js_name = \"sq\")]\npub fn napi_sq(n: i32) -> i32 {\n    sq(n)\n}\n"),
+        "got:\n{root}"
+    );
+    assert!(!files[1].code.contains("napi"), "got:\n{}", files[1].code);
+}
+
+#[test]
+fn error_unsupported_native_signatures() {
+    for (src, code) in [
+        ("export function run(cb: () => void): void { cb(); }", "UnsupportedNativeType"),
+        ("export function later(): Promise<number> { return Promise.resolve(1); }", "UnsupportedNativeType"),
+        ("export function sum(...xs: number[]): number { return xs.length; }", "UnsupportedNativeSignature"),
+    ] {
+        let err = compile_as(src, OutputMode::NativeModule).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_napi_wrappers_typecheck() {
+    // 没有 napi 运行时：去掉属性宏，换成一个最小的 `napi_ohos`，检查包装函数能编译、调用结果正确
+    let rust = compile_as(LIB, OutputMode::NativeModule).unwrap();
+    let mut rust: String = rust
+        .lines()
+        .filter(|l| !l.starts_with("#[napi") && !l.starts_with("use napi_derive_ohos"))
+        .map(|l| format!("{l}\n"))
+        .collect();
+    rust.push_str(
+        "\nmod napi_ohos {\n    #[derive(Debug)]\n    pub struct Error(pub String);\n    \
+         impl Error {\n        pub fn from_reason(reason: String) -> Self {\n            Error(reason)\n        }\n    }\n    \
+         pub type Result<T> = std::result::Result<T, Error>;\n}\n\n\
+         fn main() {\n    println!(\"{}\", napi_add(2, 3));\n    println!(\"{}\", napi_greet(None));\n    \
+         println!(\"{:?}\", napi_check(-1));\n    napi_reset();\n}\n",
+    );
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "5\nhello\nErr(Error(\"Error: negative\"))\nreset\n"
+    );
+}
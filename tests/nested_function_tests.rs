@@ -0,0 +1,206 @@
+use arkts2rust::ast::Stmt;
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+fn assert_codegen(src: &str, expected: &str) {
+    let got = compile(src).unwrap();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn parse_function_inside_block() {
+    let p = parse_program("{ function f(): void { return; } f(); }").unwrap();
+    assert!(p.funcs.is_empty());
+    match &p.stmts[0] {
+        Stmt::Block(b) => assert!(matches!(&b.stmts[0], Stmt::FuncDecl(f) if f.name == "f")),
+        other => panic!("expected block, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_function_inside_function() {
+    let p = parse_program("function outer(): void { function inner(): void { return; } inner(); }")
+        .unwrap();
+    assert!(matches!(&p.funcs[0].body.stmts[0], Stmt::FuncDecl(f) if f.name == "inner"));
+}
+
+#[test]
+fn non_capturing_nested_function_becomes_fn_item() {
+    assert_codegen(
+        "function outer(): number { function sq(x: number): number { return x * x; } return sq(3); }",
+        "fn outer() -> i32 {\n    fn sq(x: i32) -> i32 {\n        return x * x;\n    }\n    return sq(3i32);\n}\n\nfn main() {\n}\n",
+    );
+}
+
+#[test]
+fn nested_fn_item_is_hoisted_naturally() {
+    // Rust 的嵌套 fn 在整个块内可见，所以保持原位置即可在声明前调用。
+    assert_codegen(
+        "{ console.log(two()); function two(): number { return 2; } }",
+        "fn main() {\n    {\n        println!(\"{}\", two());\n        fn two() -> i32 {\n            return 2i32;\n        }\n    }\n}\n",
+    );
+}
+
+#[test]
+fn capturing_function_becomes_closure() {
+    assert_codegen(
+        "function outer(base: number): number { function add(x: number): number { return x + base; } return add(1); }",
+        "fn outer(base: i32) -> i32 {\n    let add = |x: i32| -> i32 {\n        return x + base;\n    };\n    return add(1i32);\n}\n\nfn main() {\n}\n",
+    );
+}
+
+#[test]
+fn closure_is_hoisted_to_after_captured_declaration() {
+    assert_codegen(
+        "{ let a = 1; show(); let b = 2; console.log(b); function show(): void { console.log(a); } }",
+        "fn main() {\n    {\n        let a = 1i32;\n        let show = || {\n            println!(\"{}\", a);\n        };\n        show();\n        let b = 2i32;\n        println!(\"{}\", b);\n    }\n}\n",
+    );
+}
+
+#[test]
+fn captured_assigned_var_becomes_cell() {
+    let rust = compile("{ let n = 0; function bump(): void { n = n + 1; } bump(); }").unwrap();
+    assert!(rust.contains("let n = std::cell::Cell::new(0i32);"), "got:\n{rust}");
+    assert!(rust.contains("let bump = || {\n            n.set(n.get() + 1i32);"), "got:\n{rust}");
+}
+
+#[test]
+fn captured_assigned_string_and_param_become_cells() {
+    let rust = compile(
+        "function f(k: number): string { let s = 'a'; function add(x: string): void { s = s + x; k = k + 1; } add('b'); return s; }",
+    )
+    .unwrap();
+    assert!(rust.starts_with("fn f(k: i32) -> String {\n    let k = std::cell::Cell::new(k);\n"), "got:\n{rust}");
+    assert!(rust.contains("let s = std::cell::RefCell::new(String::from(\"a\"));"), "got:\n{rust}");
+    assert!(rust.contains("*s.borrow_mut() = format!(\"{}{}\", s.clone().into_inner(), x);"), "got:\n{rust}");
+}
+
+#[test]
+fn closures_sharing_mutated_state_run() {
+    // 定义闭包之后再给捕获的变量赋值（E0506）、两次调用之间读取被闭包修改的变量（E0502）
+    let src = r#"
+function f1(): number {
+    let base = 1;
+    function add(x: number): number { return x + base; }
+    base = 10;
+    return add(1);
+}
+function f2(start: number): number {
+    let n = start;
+    function inc(): void { n = n + 1; start = start + 10; }
+    inc();
+    console.log(n);
+    inc();
+    return n + start;
+}
+function f3(): string {
+    let s = " x ";
+    let best: number | null = null;
+    function grow(v: string): void {
+        s = s + v;
+        if (best == null) { best = 1; } else { best = best + 1; }
+    }
+    grow(s);
+    s = s.trim();
+    grow(s);
+    console.log(best);
+    return s;
+}
+console.log(f1());
+console.log(f2(0));
+console.log(f3());
+"#;
+    let rust = compile(src).unwrap();
+    if let Some(out) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "11\n1\n22\n2\nx  xx  x\n");
+    }
+}
+
+#[test]
+fn function_calling_closure_is_also_closure() {
+    let rust = compile(
+        "{ let k = 2; function a(x: number): number { return x * k; } function b(x: number): number { return a(x) + 1; } b(1); }",
+    )
+    .unwrap();
+    assert!(rust.contains("let a = |x: i32| -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("let b = |x: i32| -> i32 {"), "got:\n{rust}");
+    assert!(rust.find("let a =").unwrap() < rust.find("let b =").unwrap());
+}
+
+#[test]
+fn calling_top_level_function_is_not_a_capture() {
+    let rust = compile(
+        "function top(): number { return 1; } function outer(): number { function inner(): number { return top(); } return inner(); }",
+    )
+    .unwrap();
+    assert!(rust.contains("    fn inner() -> i32 {"), "got:\n{rust}");
+}
+
+#[test]
+fn nested_rest_param_collects_args() {
+    let rust = compile("{ function sum(...xs: number[]): void { return; } sum(1, 2); }").unwrap();
+    assert!(rust.contains("sum(vec![1i32, 2i32]);"), "got:\n{rust}");
+}
+
+#[test]
+fn error_recursive_closure() {
+    let err = compile("{ let d = 1; function f(n: number): number { return f(n - d); } }")
+        .expect_err("closures cannot recurse");
+    assert_eq!(err.code, "RecursiveClosureUnsupported");
+}
+
+#[test]
+fn error_mutually_recursive_closures() {
+    let err = compile(
+        "{ let d = 1; function even(n: number): boolean { return n == 0 || odd(n - d); } \
+         function odd(n: number): boolean { return n != 0 && even(n - d); } }",
+    )
+    .expect_err("closures cannot call each other");
+    assert_eq!(err.code, "RecursiveClosureUnsupported");
+}
+
+#[test]
+fn closures_in_one_slot_follow_their_calls() {
+    let src = r#"
+function outer(k: number): number {
+    function twice(x: number): number { return helper(helper(x)); }
+    function helper(x: number): number { return x + k; }
+    return twice(1);
+}
+console.log(outer(2));
+"#;
+    let rust = compile(src).unwrap();
+    let helper = rust.find("let helper = ").expect("helper closure");
+    let twice = rust.find("let twice = ").expect("twice closure");
+    assert!(helper < twice, "got:\n{rust}");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "5\n");
+}
+
+#[test]
+fn generated_rust_with_nested_functions_can_compile() {
+    let src = r#"
+function outer(base: number): number {
+    let total = helper(base);
+    function helper(x: number): number { return x * 2; }
+    function addBase(x: number): number { return x + base; }
+    return addBase(total);
+}
+{
+    let count = 0;
+    function inc(): void { count = count + 1; }
+    inc();
+    inc();
+    console.log(count);
+}
+console.log(outer(3));
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
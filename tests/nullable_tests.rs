@@ -0,0 +1,144 @@
+use arkts2rust::ast::{Declarator, Expr, Literal, NullKind, Pattern, Stmt, TypeAnn, VarDecl};
+use arkts2rust::{compile, lex, parse_program, TokenKind};
+
+mod common;
+
+fn nullable(t: TypeAnn) -> TypeAnn {
+    TypeAnn::Nullable(Box::new(t), NullKind::Null)
+}
+
+#[test]
+fn lex_null_undefined_and_pipe() {
+    let ks: Vec<TokenKind> = lex("null undefined | ||").unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(
+        ks,
+        vec![TokenKind::KwNull, TokenKind::KwUndefined, TokenKind::Pipe, TokenKind::OrOr]
+    );
+}
+
+#[test]
+fn parse_nullable_var_decl() {
+    let p = parse_program("let s: string | null = null;").unwrap();
+    assert_eq!(
+        p.stmts,
+        vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
+            is_const: false,
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("s".into()),
+                ty: Some(nullable(TypeAnn::String)),
+                init: Some(Expr::Literal(Literal::Null, Default::default())),
+                span: Default::default(),
+            }],
+            span: Default::default(),
+        })]
+    );
+}
+
+#[test]
+fn parse_union_order_and_undefined() {
+    let p = parse_program("function f(a: undefined | number, b: number[] | null): void { return; }")
+        .unwrap();
+    assert_eq!(p.funcs[0].params[0].ty, Some(TypeAnn::Nullable(Box::new(TypeAnn::Number), NullKind::Undefined)));
+    assert_eq!(
+        p.funcs[0].params[1].ty,
+        Some(nullable(TypeAnn::Array(Box::new(TypeAnn::Number))))
+    );
+}
+
+#[test]
+fn error_union_of_two_concrete_types() {
+    let err = parse_program("let x: string | number = 1;").expect_err("only T | null is supported");
+    assert_eq!(err.code, "UnsupportedUnionType");
+    assert_eq!(err.span.start_col, 17);
+}
+
+#[test]
+fn codegen_nullable_decl_and_assignments() {
+    let rust = compile("let n: number | null = null; n = 5; n = undefined;").unwrap();
+    assert_eq!(
+        rust,
+        "fn main() {\n    let mut n: Option<i32> = None;\n    n = Some(5i32);\n    n = None;\n}\n"
+    );
+}
+
+#[test]
+fn codegen_null_comparisons() {
+    let rust = compile(
+        "let n: number | null = 1; let a = n == null; let b = null != n; let c = n == 3;",
+    )
+    .unwrap();
+    assert!(rust.contains("let a = n.is_none();"), "got:\n{rust}");
+    assert!(rust.contains("let b = n.is_some();"), "got:\n{rust}");
+    assert!(rust.contains("let c = n == Some(3i32);"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nullable_params_and_returns() {
+    let rust = compile(
+        "function find(key: string): string | undefined { if (key == \"a\") { return \"A\"; } else { return; } } \
+         function show(s: string | null): void { return; } \
+         show(\"x\"); show(null); show(find(\"a\"));",
+    )
+    .unwrap();
+    assert!(rust.contains("fn find(key: String) -> Option<String> {"), "got:\n{rust}");
+    assert!(rust.contains("return Some(String::from(\"A\"));"), "got:\n{rust}");
+    assert!(rust.contains("return None;"), "got:\n{rust}");
+    assert!(rust.contains("show(Some(String::from(\"x\")));"), "got:\n{rust}");
+    assert!(rust.contains("show(None);"), "got:\n{rust}");
+    assert!(rust.contains("show(find(String::from(\"a\")));"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_null_check_narrows_then_branch() {
+    let rust = compile("let n: number | null = 1; if (n != null) { console.log(n + 1); } else { n = 0; }")
+        .unwrap();
+    assert!(rust.contains("if n.is_some() {"), "got:\n{rust}");
+    assert!(rust.contains("println!(\"{}\", n.unwrap() + 1i32);"), "got:\n{rust}");
+    assert!(rust.contains("n = Some(0i32);"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_eq_null_narrows_else_branch() {
+    let rust = compile("let s: string | null = \"a\"; if (s == null) { return; } else { console.log(s); }")
+        .unwrap();
+    assert!(rust.contains("println!(\"{}\", s.clone().unwrap());"), "got:\n{rust}");
+}
+
+#[test]
+fn error_null_requires_annotation() {
+    let err = compile("let x = null;").expect_err("cannot infer Option<T>");
+    assert_eq!(err.code, "NullRequiresTypeAnnotation");
+}
+
+#[test]
+fn error_null_assigned_to_non_nullable() {
+    let err = compile("let x: number = 1; x = null;").expect_err("number is not nullable");
+    assert_eq!(err.code, "NullNotAllowed");
+}
+
+#[test]
+fn error_null_is_not_a_condition() {
+    let err = compile("if (null) { } else { }").expect_err("null is not bool");
+    assert_eq!(err.code, "ConditionMustBeBool");
+}
+
+#[test]
+fn generated_rust_with_nullable_can_compile() {
+    let src = r#"
+function find(key: string): string | null {
+    if (key == "a") { return "A"; } else { return null; }
+}
+let hit: string | null = find("a");
+let miss = find("b");
+if (hit != null) { console.log(hit); } else { console.log("none"); }
+if (miss == null) { console.log("none"); } else { console.log(miss); }
+let count: number | undefined = undefined;
+count = 3;
+while (count != null) { console.log(count * 2); count = null; }
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
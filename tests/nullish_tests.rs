@@ -0,0 +1,131 @@
+use arkts2rust::ast::{BinaryExpr, BinaryOp, Expr, Literal, Stmt};
+use arkts2rust::{compile, lex, parse_program, TokenKind};
+
+mod common;
+
+fn init_of(src: &str) -> Expr {
+    let p = parse_program(src).unwrap();
+    match p.stmts.last() {
+        Some(Stmt::VarDecl(d)) => d.decls[0].init.clone().expect("initializer"),
+        other => panic!("expected var decl, got {other:?}"),
+    }
+}
+
+#[test]
+fn lex_question_question() {
+    let ks: Vec<TokenKind> = lex("a ?? b").unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(
+        ks,
+        vec![
+            TokenKind::Ident("a".into()),
+            TokenKind::QuestionQuestion,
+            TokenKind::Ident("b".into()),
+        ]
+    );
+}
+
+#[test]
+fn error_single_question_mark() {
+    let err = lex("a ? b").expect_err("lone `?` is not supported");
+    assert_eq!(err.code, "UnexpectedChar");
+    assert_eq!(err.span.start_col, 3);
+}
+
+#[test]
+fn parse_nullish_binds_looser_than_comparison() {
+    let e = init_of("let x = a ?? b == c;");
+    let Expr::Binary(BinaryExpr { op, right, .. }) = e else {
+        panic!("expected binary");
+    };
+    assert_eq!(op, BinaryOp::NullishCoalesce);
+    assert!(matches!(*right, Expr::Binary(BinaryExpr { op: BinaryOp::EqEq, .. })));
+}
+
+#[test]
+fn parse_nullish_is_left_associative() {
+    let e = init_of("let x = a ?? b ?? 1;");
+    let Expr::Binary(BinaryExpr { left, right, .. }) = e else {
+        panic!("expected binary");
+    };
+    assert!(matches!(
+        *left,
+        Expr::Binary(BinaryExpr { op: BinaryOp::NullishCoalesce, .. })
+    ));
+    assert_eq!(*right, Expr::Literal(Literal::Number(1), Default::default()));
+}
+
+#[test]
+fn error_mixing_nullish_with_logical_ops() {
+    let err = parse_program("let x = a || b ?? c;").expect_err("needs parentheses");
+    assert_eq!(err.code, "MixedNullishAndLogical");
+    assert_eq!(err.span.start_col, 16);
+
+    let err = parse_program("let x = a ?? b && c;").expect_err("needs parentheses");
+    assert_eq!(err.code, "MixedNullishAndLogical");
+
+    assert!(parse_program("let x = (a || b) ?? c;").is_ok());
+}
+
+#[test]
+fn codegen_nullable_number_uses_unwrap_or() {
+    let rust = compile("let n: number | null = null; let m = n ?? 0; let k = (n ?? 1) + 2;").unwrap();
+    assert!(rust.contains("let m = n.unwrap_or(0i32);"), "got:\n{rust}");
+    assert!(rust.contains("let k = (n.unwrap_or(1i32)) + 2i32;"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nullable_string_clones_variable() {
+    let rust = compile("let s: string | null = null; let t = s ?? \"none\"; console.log(s);").unwrap();
+    assert!(
+        rust.contains("let t = s.clone().unwrap_or(String::from(\"none\"));"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_side_effecting_fallback_is_lazy() {
+    let rust = compile(
+        "function dflt(): number { return 7; } let n: number | null = 1; let m = n ?? dflt();",
+    )
+    .unwrap();
+    assert!(rust.contains("let m = n.unwrap_or_else(|| dflt());"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_nullable_chain_uses_or() {
+    let rust =
+        compile("let a: number | null = null; let b: number | null = 2; let c = a ?? b ?? 0;").unwrap();
+    assert!(rust.contains("let c = a.or(b).unwrap_or(0i32);"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_non_nullable_left_is_plain_fallback() {
+    let rust = compile("let x = 1; let y = x ?? 2; let z = null ?? 3;").unwrap();
+    assert!(rust.contains("let y = x;"), "got:\n{rust}");
+    assert!(rust.contains("let z = 3i32;"), "got:\n{rust}");
+}
+
+#[test]
+fn generated_rust_with_nullish_can_compile() {
+    let src = r#"
+function lookup(key: string): string | null {
+    if (key == "a") { return "A"; } else { return null; }
+}
+function fallback(): number { return 42; }
+let name = lookup("b") ?? "anonymous";
+console.log(name);
+let hit: string | undefined = lookup("a");
+console.log(hit ?? "missing");
+console.log(hit);
+let n: number | null = null;
+let total = (n ?? fallback()) * 2;
+console.log(total);
+let first: number | null = null;
+let second: number | null = 5;
+console.log(first ?? second ?? 0);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
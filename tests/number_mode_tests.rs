@@ -0,0 +1,101 @@
+use arkts2rust::ast::{Expr, Literal, Stmt};
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, ExternBindings, NumberMode, OutputMode};
+
+mod common;
+
+fn compile_f64(src: &str) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn parse_float_literal() {
+    let p = parse_program("0.25;").unwrap();
+    assert_eq!(p.stmts, vec![Stmt::ExprStmt(Expr::Literal(Literal::Float("0.25".into()), Default::default()))]);
+}
+
+#[test]
+fn codegen_f64_mode() {
+    let rust = compile_f64(
+        "function area(r: number, xs: number[]): number { let n = xs.length / 2; return Math.PI * r * r + n; } \
+         let x: number | null = 7 / 2; let y = Math.pow(x ?? 1.5, 2);",
+    );
+    assert!(rust.contains("fn area(r: f64, xs: Vec<f64>) -> f64 {\n"), "got:\n{rust}");
+    assert!(rust.contains("    let n = (xs.len() as f64) / 2f64;\n"), "got:\n{rust}");
+    assert!(rust.contains("    return std::f64::consts::PI * r * r + n;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let x: Option<f64> = Some(7f64 / 2f64);\n"), "got:\n{rust}");
+    assert!(rust.contains("    let y = x.unwrap_or(1.5f64).powf(2f64);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_floats_need_f64_mode() {
+    let err = compile("let x = 0.5;").expect_err("float literal in i32 mode");
+    assert_eq!(err.code, "FloatLiteralNeedsF64");
+    let err = compile("let r = Math.E;").expect_err("Math.E in i32 mode");
+    assert_eq!(err.code, "MathConstantNeedsFloat");
+}
+
+#[test]
+fn integers_beyond_i32_need_f64_mode() {
+    let rust = compile_f64("let big = 3000000000;\nlet mask = 0xFFFFFFFF;\nconsole.log(big + 1, mask);");
+    assert!(rust.contains("    let big = 3000000000f64;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let mask = 4294967295f64;\n"), "got:\n{rust}");
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "3000000001 4294967295\n");
+    }
+
+    let err = compile("let big = 3000000000;").expect_err("out of range in i32 mode");
+    assert_eq!(err.code, "InvalidNumber");
+    assert_eq!((err.span.start_line, err.span.start_col), (1, 11));
+}
+
+#[test]
+fn exponent_literals() {
+    let rust = compile_f64("let big = 1e21;\nlet small = 2.5e-7;\nlet k = 1E3;\nconsole.log(big, small, k + 1);");
+    assert!(rust.contains("    let big = 1e21f64;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let small = 2.5e-7f64;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let k = 1000f64;\n"), "got:\n{rust}");
+    if let Some(run) = common::compile_and_run(&rust) {
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "1e+21 2.5e-7 1001\n");
+    }
+
+    // i32 模式下整数值照常使用，小数和超出范围的值和直接写出来的一样报错
+    assert!(compile("let k = 1e3;\nconsole.log(k);").unwrap().contains("    let k = 1000i32;\n"));
+    assert_eq!(compile("let x = 5e-1;").unwrap_err().code, "FloatLiteralNeedsF64");
+    assert_eq!(compile("let x = 1e10;").unwrap_err().code, "InvalidNumber");
+}
+
+#[test]
+fn generated_f64_code_matches_js() {
+    let src = r#"
+function area(r: number): number {
+  return Math.PI * r * r;
+}
+let half: number | null = 7 / 2;
+console.log(7 / 2, -7 % 3, 0.1 + 0.2, 10 / 4 * 2, half);
+console.log(Math.round(area(2) * 100) / 100, Math.floor(-2.5), Math.round(2.5), Math.sqrt(2) < 1.5);
+console.log(parseInt("42") / 5, parseFloat("2.5x") * 2, Number("1.5"), Number("x"), parseInt(3.7));
+let s = "hello";
+console.log(s.length / 2, s.indexOf("l"), s.charCodeAt(9), s.slice(1.9, 3), s[1], String(1.5));
+"#;
+
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        number_mode: NumberMode::F64,
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "3.5 -1 0.30000000000000004 5 3.5\n12.57 -3 3 true\n8.4 5 1.5 NaN 3\n2.5 2 NaN el e 1.5\n"
+    );
+}
@@ -10,21 +10,22 @@ fn stmt(src: &str) -> Stmt {
 }
 
 fn lit_i(n: i32) -> Expr {
-    Expr::Literal(Literal::Number(n))
+    Expr::Literal(Literal::Number(n), Default::default())
 }
 
 fn lit_b(b: bool) -> Expr {
-    Expr::Literal(Literal::Bool(b))
+    Expr::Literal(Literal::Bool(b), Default::default())
 }
 
 fn ident(name: &str) -> Expr {
-    Expr::Ident(name.to_string())
+    Expr::Ident(name.to_string(), Default::default())
 }
 
 fn unary(op: UnaryOp, expr: Expr) -> Expr {
     Expr::Unary(UnaryExpr {
         op,
         expr: Box::new(expr),
+        span: Default::default(),
     })
 }
 
@@ -33,6 +34,7 @@ fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
         op,
         left: Box::new(left),
         right: Box::new(right),
+        span: Default::default(),
     })
 }
 
@@ -44,6 +46,7 @@ fn call(name: &str, args: Vec<Expr>) -> Expr {
     Expr::Call(CallExpr {
         callee: Callee::Ident(name.to_string()),
         args,
+        span: Default::default(),
     })
 }
 
@@ -203,6 +206,7 @@ fn assign_stmt_basic() {
                 lit_i(1),
                 binary(BinaryOp::Mul, lit_i(2), lit_i(3))
             ),
+            span: Default::default(),
         })
     );
 }
@@ -215,6 +219,7 @@ fn assign_stmt_with_call() {
         Stmt::Assign(AssignStmt {
             name: "x".into(),
             value: call("f", vec![lit_i(1), lit_i(2)]),
+            span: Default::default(),
         })
     );
 }
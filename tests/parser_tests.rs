@@ -1,12 +1,12 @@
 use arkts2rust::{
-    parse_program, parse_tokens, Callee, CallExpr, Expr, Literal, Program, Stmt, TokenKind,
+    parse_program, parse_tokens, Callee, CallExpr, ConsoleFn, Declarator, Expr, Literal, Pattern, Program, Stmt, TokenKind,
     VarDecl,
 };
 
 fn program(stmts: Vec<Stmt>) -> Program {
     Program {
-        funcs: vec![],
         stmts,
+        ..Default::default()
     }
 }
 
@@ -16,9 +16,15 @@ fn parse_let_number() {
     assert_eq!(
         p,
         program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
             is_const: false,
-            name: "x".into(),
-            init: Literal::Number(1),
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("x".into()),
+                ty: None,
+                init: Some(Expr::Literal(Literal::Number(1), Default::default())),
+                span: Default::default(),
+            }],
+            span: Default::default(),
         })])
     );
 }
@@ -29,9 +35,15 @@ fn parse_const_string() {
     assert_eq!(
         p,
         program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
             is_const: true,
-            name: "s".into(),
-            init: Literal::String("hi".into()),
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("s".into()),
+                ty: None,
+                init: Some(Expr::Literal(Literal::String("hi".into()), Default::default())),
+                span: Default::default(),
+            }],
+            span: Default::default(),
         })])
     );
 }
@@ -42,9 +54,15 @@ fn parse_let_bool_true() {
     assert_eq!(
         p,
         program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
             is_const: false,
-            name: "ok".into(),
-            init: Literal::Bool(true),
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("ok".into()),
+                ty: None,
+                init: Some(Expr::Literal(Literal::Bool(true), Default::default())),
+                span: Default::default(),
+            }],
+            span: Default::default(),
         })])
     );
 }
@@ -55,8 +73,9 @@ fn parse_console_log_number() {
     assert_eq!(
         p,
         program(vec![Stmt::ExprStmt(Expr::Call(CallExpr {
-            callee: Callee::ConsoleLog,
-            args: vec![Expr::Literal(Literal::Number(1))],
+            callee: Callee::Console(ConsoleFn::Log),
+            args: vec![Expr::Literal(Literal::Number(1), Default::default())],
+            span: Default::default(),
         }))])
     );
 }
@@ -67,16 +86,37 @@ fn parse_console_log_string() {
     assert_eq!(
         p,
         program(vec![Stmt::ExprStmt(Expr::Call(CallExpr {
-            callee: Callee::ConsoleLog,
-            args: vec![Expr::Literal(Literal::String("a".into()))],
+            callee: Callee::Console(ConsoleFn::Log),
+            args: vec![Expr::Literal(Literal::String("a".into()), Default::default())],
+            span: Default::default(),
         }))])
     );
 }
 
 #[test]
 fn parse_multiple_stmts() {
-    let p = parse_program("let x = 1; console.log(x);").unwrap_err();
-    assert_eq!(p.code, "ExpectedLiteral");
+    let p = parse_program("let x = 1; console.log(x);").unwrap();
+    assert_eq!(
+        p,
+        program(vec![
+            Stmt::VarDecl(VarDecl {
+                is_export: false,
+                is_const: false,
+                decls: vec![Declarator {
+                    pattern: Pattern::Ident("x".into()),
+                    ty: None,
+                    init: Some(Expr::Literal(Literal::Number(1), Default::default())),
+                    span: Default::default(),
+                }],
+                span: Default::default(),
+            }),
+            Stmt::ExprStmt(Expr::Call(CallExpr {
+                callee: Callee::Console(ConsoleFn::Log),
+                args: vec![Expr::Ident("x".into(), Default::default())],
+                span: Default::default(),
+            })),
+        ])
+    );
 }
 
 #[test]
@@ -91,13 +131,20 @@ let x = 1;
         p,
         program(vec![
             Stmt::VarDecl(VarDecl {
+                is_export: false,
                 is_const: false,
-                name: "x".into(),
-                init: Literal::Number(1),
+                decls: vec![Declarator {
+                    pattern: Pattern::Ident("x".into()),
+                    ty: None,
+                    init: Some(Expr::Literal(Literal::Number(1), Default::default())),
+                    span: Default::default(),
+                }],
+                span: Default::default(),
             }),
             Stmt::ExprStmt(Expr::Call(CallExpr {
-                callee: Callee::ConsoleLog,
-                args: vec![Expr::Literal(Literal::Bool(true))],
+                callee: Callee::Console(ConsoleFn::Log),
+                args: vec![Expr::Literal(Literal::Bool(true), Default::default())],
+                span: Default::default(),
             })),
         ])
     );
@@ -110,6 +157,59 @@ fn error_missing_semicolon() {
     assert_eq!(err.span.start_line, 1);
 }
 
+#[test]
+fn parse_console_log_expression_and_empty() {
+    let p = parse_program("console.log(a + f(1)); console.log();").unwrap();
+    let Stmt::ExprStmt(Expr::Call(first)) = &p.stmts[0] else { panic!() };
+    assert_eq!(first.callee, Callee::Console(ConsoleFn::Log));
+    assert!(matches!(first.args.as_slice(), [Expr::Binary(_)]));
+    assert_eq!(
+        p.stmts[1],
+        Stmt::ExprStmt(Expr::Call(CallExpr {
+            callee: Callee::Console(ConsoleFn::Log),
+            args: vec![],
+            span: Default::default(),
+        }))
+    );
+}
+
+#[test]
+fn codegen_console_log_expression_and_empty() {
+    let rust = arkts2rust::compile("let a = 1; console.log(a * 2); console.log();").unwrap();
+    assert!(
+        rust.contains("    println!(\"{}\", a * 2i32);\n    println!();\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_console_log_multiple_args() {
+    let rust = arkts2rust::compile(r#"let a = 1; console.log(a, "b", a + 1);"#).unwrap();
+    assert!(
+        rust.contains("    println!(\"{} {} {}\", a, String::from(\"b\"), a + 1i32);\n"),
+        "got:\n{rust}"
+    );
+    let err = arkts2rust::compile("function f(...xs: number[]): void { console.log(1, ...xs); }")
+        .expect_err("spread in console.log");
+    assert_eq!(err.code, "UnsupportedConsoleArgs");
+}
+
+#[test]
+fn codegen_console_methods() {
+    let rust = arkts2rust::compile(
+        r#"console.error("bad", 1); console.warn(2); console.info(3); console.debug(); console.log(4);"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "    eprintln!(\"{} {}\", String::from(\"bad\"), 1i32);\n    eprintln!(\"{}\", 2i32);\n    println!(\"{}\", 3i32);\n    println!();\n    println!(\"{}\", 4i32);\n"
+        ),
+        "got:\n{rust}"
+    );
+    let err = parse_program("console.table(1);").expect_err("unknown console method");
+    assert_eq!(err.code, "UnknownStructure");
+}
+
 #[test]
 fn error_missing_rparen() {
     let err = parse_program("console.log(1;").expect_err("missing rparen should error");
@@ -124,11 +224,84 @@ fn parse_ident_call() {
         p,
         program(vec![Stmt::ExprStmt(Expr::Call(CallExpr {
             callee: Callee::Ident("foo".into()),
-            args: vec![Expr::Literal(Literal::Number(1))],
+            args: vec![Expr::Literal(Literal::Number(1), Default::default())],
+            span: Default::default(),
         }))])
     );
 }
 
+#[test]
+fn parse_let_with_call_initializer() {
+    let p = parse_program("let y = f(2);").unwrap();
+    assert_eq!(
+        p,
+        program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
+            is_const: false,
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("y".into()),
+                ty: None,
+                init: Some(Expr::Call(CallExpr {
+                    callee: Callee::Ident("f".into()),
+                    args: vec![Expr::Literal(Literal::Number(2), Default::default())],
+                    span: Default::default(),
+                })),
+                span: Default::default(),
+            }],
+            span: Default::default(),
+        })])
+    );
+}
+
+#[test]
+fn parse_multiple_declarators() {
+    let p = parse_program("let a = 1, b: string, c = a;").unwrap();
+    assert_eq!(
+        p,
+        program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
+            is_const: false,
+            decls: vec![
+                Declarator {
+                    pattern: Pattern::Ident("a".into()),
+                    ty: None,
+                    init: Some(Expr::Literal(Literal::Number(1), Default::default())),
+                    span: Default::default(),
+                },
+                Declarator {
+                    pattern: Pattern::Ident("b".into()),
+                    ty: Some(arkts2rust::TypeAnn::String),
+                    init: None,
+                    span: Default::default(),
+                },
+                Declarator {
+                    pattern: Pattern::Ident("c".into()),
+                    ty: None,
+                    init: Some(Expr::Ident("a".into(), Default::default())),
+                    span: Default::default(),
+                },
+            ],
+            span: Default::default(),
+        })])
+    );
+}
+
+#[test]
+fn error_const_declarator_without_initializer() {
+    let err = parse_program("const a = 1, b;").expect_err("const needs init");
+    assert_eq!(err.code, "MissingInitializer");
+    assert_eq!(err.span.start_col, 15);
+}
+
+#[test]
+fn codegen_one_let_per_declarator() {
+    let rust = arkts2rust::compile("let a = 1, b = a + 1; const c = b, d = c;").unwrap();
+    assert_eq!(
+        rust,
+        "fn main() {\n    let a = 1i32;\n    let b = a + 1i32;\n    let c = b;\n    let d = c;\n}\n"
+    );
+}
+
 #[test]
 fn parse_from_tokens_directly() {
     let tokens = arkts2rust::lex("let x = 1;").unwrap();
@@ -136,9 +309,15 @@ fn parse_from_tokens_directly() {
     assert_eq!(
         p,
         program(vec![Stmt::VarDecl(VarDecl {
+            is_export: false,
             is_const: false,
-            name: "x".into(),
-            init: Literal::Number(1),
+            decls: vec![Declarator {
+                pattern: Pattern::Ident("x".into()),
+                ty: None,
+                init: Some(Expr::Literal(Literal::Number(1), Default::default())),
+                span: Default::default(),
+            }],
+            span: Default::default(),
         })])
     );
 }
@@ -163,3 +342,66 @@ fn lexer_produces_dot_token_for_console_log() {
         ]
     );
 }
+
+#[test]
+fn recovers_after_syntax_errors() {
+    let errors_of = |src: &str| {
+        let tokens = arkts2rust::lex(src).unwrap();
+        let (program, errors) = arkts2rust::parse_recovering(&tokens, &Default::default());
+        let found: Vec<(String, usize)> = errors.into_iter().map(|e| (e.code.to_string(), e.span.start_line)).collect();
+        (program, found)
+    };
+
+    // 顶层语句：跳到 `;` 之后接着解析
+    let (program, found) = errors_of("let = 1;\nlet y = 2;\nconsole.log(y +);\nlet z = 3;");
+    assert_eq!(found, [("ExpectedIdentifier".to_string(), 1), ("ExpectedExpr".to_string(), 3)]);
+    assert_eq!(program.stmts.len(), 2);
+
+    // 函数体里的每条语句单独恢复；出错的函数声明跳到它的 `}` 为止
+    let (program, found) = errors_of(
+        "function f(a b): void {\n  let x = 1;\n}\n\
+         function g(): void {\n  let = 2;\n  console.log(1);\n  let q = ;\n}\nlet ok = 1;",
+    );
+    let codes: Vec<(&str, usize)> = found.iter().map(|(c, l)| (c.as_str(), *l)).collect();
+    assert_eq!(codes, [("UnexpectedToken", 1), ("ExpectedIdentifier", 5), ("ExpectedExpr", 7)]);
+    assert_eq!(program.funcs.len(), 1);
+    assert_eq!(program.funcs[0].body.stmts.len(), 1);
+    assert_eq!(program.stmts.len(), 1);
+
+    // 组件里的错误跳过整个组件；没有闭合的代码块只报一次
+    let (_, found) = errors_of("@Entry\n@Component\nstruct Index {\n  build() {\n    Text(\n  }\n}\nlet y = ;\n");
+    assert_eq!(found, [("ExpectedExpr".to_string(), 6), ("ExpectedExpr".to_string(), 8)]);
+    let (_, found) = errors_of("function f(): void {\n  if (true) {\n    let a = 1;\n");
+    assert_eq!(found, [("MissingRBrace".to_string(), 3)]);
+
+    // `parse_tokens` 仍然只返回第一个错误
+    let err = parse_program("let = 1;\nlet y = ;").unwrap_err();
+    assert_eq!(err.code, "ExpectedIdentifier");
+}
+
+#[test]
+fn errors_list_expected_tokens() {
+    let err = parse_program("function f(a b): void {}").unwrap_err();
+    assert_eq!(err.code, "UnexpectedToken");
+    let expected = err.expected.as_deref().unwrap();
+    assert_eq!(expected.tokens, ["`,`", "`)`"]);
+    assert_eq!(expected.found, "identifier `b`");
+    assert_eq!(err.message().unwrap(), "expected `,` or `)`, found identifier `b`");
+    assert!(err.to_string().ends_with(": expected `,` or `)`, found identifier `b`"), "{err}");
+
+    for (src, message) in [
+        ("console.log(1;", "expected `,` or `)`, found `;`"),
+        ("let x = 1 let y = 2;", "expected `;`, found `let`"),
+        ("let s = ;", "expected expression, found `;`"),
+        ("function f(): void {\n  let a = 1;\n", "expected `}`, found end of file"),
+        ("import { a } 'mod';", "expected `from`, found string \"mod\""),
+    ] {
+        let err = parse_program(src).unwrap_err();
+        assert_eq!(err.message().as_deref(), Some(message), "{src}");
+    }
+
+    // 语法以外的错误没有这项说明
+    let err = arkts2rust::compile("let a = 1;\nb = 2;").unwrap_err();
+    assert_eq!(err.expected, None);
+    assert_eq!(err.message(), None);
+}
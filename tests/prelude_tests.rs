@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::PathBuf;
+
+use arkts2rust::{
+    compile, compile_tree, compile_with_options, CodegenOptions, CompileOptions, Edition, ExternBindings, OutputMode,
+    ResolveConfig,
+};
+
+const PRELUDE: &str = "#![allow(dead_code)]\n\nuse std::fmt::Write as _;\n";
+
+fn options(prelude: &str) -> CodegenOptions {
+    CodegenOptions {
+        prelude: Some(prelude.to_string()),
+        ..CodegenOptions::default()
+    }
+}
+
+fn compile_prelude(src: &str, codegen: CodegenOptions) -> String {
+    let options = CompileOptions {
+        codegen,
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options).unwrap()
+}
+
+#[test]
+fn prelude_goes_first() {
+    let src = "let n = parseInt(\"1\");\nconsole.log(n);";
+    let rust = compile_prelude(src, options(PRELUDE));
+    assert_eq!(rust, format!("{PRELUDE}\n{}", compile(src).unwrap()));
+    assert!(rust.starts_with("#![allow(dead_code)]\n\nuse std::fmt::Write as _;\n\nfn __ark_parse_int("), "got:\n{rust}");
+
+    // extern crate 也在 prelude 后面
+    let rust = compile_prelude(
+        "console.log(Math.random());",
+        CodegenOptions {
+            use_rand: true,
+            edition: Edition::E2015,
+            ..options("#![allow(unused)]")
+        },
+    );
+    assert!(rust.starts_with("#![allow(unused)]\n\nextern crate rand;\n\nfn __ark_number_to_string("), "got:\n{rust}");
+}
+
+#[test]
+fn empty_prelude_is_ignored() {
+    let src = "console.log(1);";
+    assert_eq!(compile_prelude(src, options("\n")), compile(src).unwrap());
+}
+
+#[test]
+fn prelude_only_in_crate_root() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_prelude_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("util.ets"), "export function twice(n: number): number {\n  return n * 2;\n}").unwrap();
+    fs::write(dir.join("main.ets"), "import { twice } from './util';\nconsole.log(twice(2));").unwrap();
+    let files = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &options(PRELUDE),
+    )
+    .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(files[0].path, PathBuf::from("main.rs"));
+    assert!(files[0].code.starts_with(&format!("{PRELUDE}\nmod util;\n")), "got:\n{}", files[0].code);
+    assert!(!files[1].code.contains("allow(dead_code)"), "got:\n{}", files[1].code);
+}
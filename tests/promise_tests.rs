@@ -0,0 +1,108 @@
+use arkts2rust::ast::{Expr, Literal, Stmt};
+use arkts2rust::{compile, parse_program, CallExpr, Callee, PromiseFn, TypeAnn};
+
+mod common;
+
+#[test]
+fn parse_promise_type() {
+    let p = parse_program("let p: Promise<number[]> = xs;").unwrap();
+    let Stmt::VarDecl(d) = &p.stmts[0] else { panic!() };
+    assert_eq!(
+        d.decls[0].ty,
+        Some(TypeAnn::Promise(Box::new(TypeAnn::Array(Box::new(
+            TypeAnn::Number
+        )))))
+    );
+}
+
+#[test]
+fn parse_promise_type_missing_gt() {
+    let err = parse_program("let p: Promise<number = xs;").expect_err("unclosed");
+    assert_eq!(err.code, "MissingGt");
+}
+
+#[test]
+fn parse_promise_builtin_call() {
+    let p = parse_program("Promise.resolve(1);").unwrap();
+    assert_eq!(
+        p.stmts[0],
+        Stmt::ExprStmt(Expr::Call(CallExpr {
+            callee: Callee::Promise(PromiseFn::Resolve),
+            args: vec![Expr::Literal(Literal::Number(1), Default::default())],
+            span: Default::default(),
+        }))
+    );
+}
+
+#[test]
+fn parse_unknown_promise_method() {
+    let err = parse_program("Promise.race(ps);").expect_err("race not supported");
+    assert_eq!(err.code, "UnknownStructure");
+    assert_eq!(err.span.start_col, 1);
+}
+
+#[test]
+fn codegen_promise_signatures() {
+    let rust = compile(
+        "function f(p: Promise<number>): Promise<number> { return Promise.resolve(1); }",
+    )
+    .unwrap();
+    assert!(rust.contains("enum ArkError"));
+    assert!(rust.contains(
+        "fn f(p: impl std::future::Future<Output = Result<i32, ArkError>>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<i32, ArkError>>>> {"
+    ));
+    assert!(rust.contains("return Box::pin(std::future::ready(Ok::<_, ArkError>(1i32)));"));
+}
+
+#[test]
+fn codegen_promise_reject_and_all() {
+    let rust = compile(
+        r#"
+function fail(): Promise<number> { return Promise.reject("boom"); }
+function all(ps: Promise<number>[]): Promise<number[]> { return Promise.all(ps); }
+"#,
+    )
+    .unwrap();
+    assert!(rust.contains(
+        "return Box::pin(std::future::ready(Err(ArkError::Error(String::from(\"boom\")))));"
+    ));
+    assert!(rust.contains(
+        "return Box::pin(async move { let mut out = Vec::new(); for p in ps { out.push(p.await?); } Ok::<_, ArkError>(out) });"
+    ));
+}
+
+#[test]
+fn promise_builtin_takes_one_arg() {
+    let err = compile("Promise.resolve(1, 2);").expect_err("too many args");
+    assert_eq!(err.code, "PromiseArgCount");
+}
+
+#[test]
+fn generated_promise_code_compiles() {
+    let src = r#"
+class Timeout extends Error {}
+function fetchNum(n: number): Promise<number> {
+  return Promise.resolve(n * 2);
+}
+function fail(): Promise<number> {
+  return Promise.reject(new Timeout("slow"));
+}
+function waitAll(ps: Promise<number>[]): Promise<number[]> {
+  return Promise.all(ps);
+}
+function ignore(p: Promise<number>): number {
+  return 0;
+}
+let a: Promise<number> = fetchNum(1);
+let b: Promise<string> = Promise.reject("boom");
+console.log(ignore(fail()));
+"#;
+
+    let rust = compile(src).unwrap();
+
+    // async 块需要 2018 及以上 edition
+    let Some(run) = common::compile_and_run_with(&rust, &["--edition", "2021"]) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "0\n");
+}
@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::PathBuf;
+
+use arkts2rust::{
+    compile_file_with, compile_tree, compile_with_options, CodegenOptions, CompileOptions, ExternBindings, OutputMode,
+    ResolveConfig,
+};
+
+const UTIL: &str = "export function twice(n: number): number {\n  return n * 2;\n}";
+const MAIN: &str = "import { twice } from './util';\n\nlet n = twice(2);\nconsole.log(n);";
+
+fn options() -> CodegenOptions {
+    CodegenOptions {
+        provenance: true,
+        ..CodegenOptions::default()
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_{name}_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn line_comments_without_file() {
+    let src = "function f(): number {\n  return 1;\n}\n\nif (f() > 0) {\n  console.log(1);\n} else {\n  console.log(2);\n}";
+    let options = CompileOptions {
+        codegen: options(),
+        ..CompileOptions::default()
+    };
+    let rust = compile_with_options(src, &options).unwrap();
+    assert_eq!(
+        rust,
+        "// line 1
+fn f() -> i32 {
+    // line 2
+    return 1i32;
+}
+
+fn main() {
+    // line 5
+    if f() > 0i32 {
+        // line 6
+        println!(\"{}\", 1i32);
+    } else {
+        // line 8
+        println!(\"{}\", 2i32);
+    }
+}
+"
+    );
+}
+
+#[test]
+fn file_name_from_loaded_module() {
+    let dir = temp_dir("provenance_file");
+    let path = dir.join("main.ets");
+    fs::write(&path, "let n = 2;\nconsole.log(n);").unwrap();
+    let rust = compile_file_with(&path, OutputMode::Binary, &ExternBindings::new(), &ResolveConfig::default(), &options());
+    let file = path.display().to_string();
+
+    // 合并多个文件时只写行号
+    fs::write(dir.join("util.ets"), UTIL).unwrap();
+    fs::write(&path, MAIN).unwrap();
+    let merged = compile_file_with(&path, OutputMode::Binary, &ExternBindings::new(), &ResolveConfig::default(), &options());
+    let _ = fs::remove_dir_all(&dir);
+
+    let rust = rust.unwrap();
+    assert!(rust.contains(&format!("    // {file}:1\n    let n = 2i32;\n    // {file}:2\n    println!(")), "got:\n{rust}");
+    let merged = merged.unwrap();
+    assert!(merged.contains("// line 1\nfn twice(n: i32) -> i32 {\n"), "got:\n{merged}");
+    assert!(merged.contains("    // line 3\n    let n = twice(2i32);\n"), "got:\n{merged}");
+}
+
+#[test]
+fn file_name_per_module_in_tree() {
+    let dir = temp_dir("provenance_tree");
+    fs::write(dir.join("util.ets"), UTIL).unwrap();
+    fs::write(dir.join("main.ets"), MAIN).unwrap();
+    let files = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &options(),
+    )
+    .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let main = dir.join("main.ets").display().to_string();
+    let util = dir.join("util.ets").display().to_string();
+    assert!(files[0].code.contains(&format!("    // {main}:3\n    let n = twice(2i32);\n")), "got:\n{}", files[0].code);
+    assert!(
+        files[1].code.contains(&format!("// {util}:1\npub(crate) fn twice(n: i32) -> i32 {{\n    // {util}:2\n")),
+        "got:\n{}",
+        files[1].code
+    );
+}
@@ -0,0 +1,182 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::rename::{is_keyword, rust_ident, snake_case, snake_case_map};
+use arkts2rust::{
+    compile, compile_tree, parse_program, CodegenOptions, Edition, ExternBindings, OutputFile, OutputMode, ResolveConfig,
+};
+
+mod common;
+
+fn snake(src: &str, mode: OutputMode) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        snake_case: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, mode, &ExternBindings::new(), &options).unwrap()
+}
+
+fn file<'a>(files: &'a [OutputFile], path: &str) -> &'a str {
+    files
+        .iter()
+        .find(|f| f.path == std::path::Path::new(path))
+        .unwrap_or_else(|| panic!("missing {path}"))
+        .code
+        .as_str()
+}
+
+#[test]
+fn converts_camel_case() {
+    assert_eq!(snake_case("fooBar"), "foo_bar");
+    assert_eq!(snake_case("parseHTTPResponse"), "parse_http_response");
+    assert_eq!(snake_case("x1Value"), "x1_value");
+    assert_eq!(snake_case("_privateName"), "_private_name");
+    assert_eq!(snake_case("Total"), "total");
+    assert_eq!(snake_case("already_snake"), "already_snake");
+    assert_eq!(snake_case("MAX_SIZE"), "MAX_SIZE");
+}
+
+#[test]
+fn renames_functions_variables_and_params() {
+    let rust = snake(
+        "function addOne(inputValue: number): number {\n  let resultValue = inputValue + 1;\n  return resultValue;\n}\n\
+         let itemCount = addOne(2);\nitemCount = itemCount + 1;\nconst MAX_SIZE = 3;\n\
+         try { console.log(itemCount, MAX_SIZE); } catch (errorInfo) { console.log(errorInfo); }",
+        OutputMode::Binary,
+    );
+    assert!(rust.contains("fn add_one(input_value: i32) -> i32 {\n    let result_value = input_value + 1i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("    let mut item_count = add_one(2i32);\n    item_count = item_count + 1i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("let MAX_SIZE = 3i32;"), "got:\n{rust}");
+    assert!(!rust.contains("errorInfo"), "got:\n{rust}");
+}
+
+#[test]
+fn collisions_get_a_suffix() {
+    let program = parse_program(
+        "function fooBar(): number { return 1; }\nfunction foo_bar(): number { return 2; }\n\
+         declare function nativeCall(): void;\nconsole.log(fooBar() + foo_bar());",
+    )
+    .unwrap();
+    let names = snake_case_map(&program);
+    // 已经是 snake_case 的名字不变，改名的名字让开它；外部函数不改名
+    assert_eq!(names.iter().collect::<Vec<_>>(), [("fooBar", "foo_bar_2")]);
+    assert_eq!(names.original("foo_bar_2"), Some("fooBar"));
+    assert_eq!(names.to_json(), "{\n  \"fooBar\": \"foo_bar_2\"\n}\n");
+
+    let rust = snake(
+        "function fooBar(): number { return 1; }\nfunction foo_bar(): number { return 2; }\nconsole.log(fooBar() + foo_bar());",
+        OutputMode::Binary,
+    );
+    assert!(rust.contains("fn foo_bar_2() -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("foo_bar_2() + foo_bar()"), "got:\n{rust}");
+}
+
+#[test]
+fn keeps_names_visible_outside_rust() {
+    // 原生模块：Rust 函数改名，JS 那边的名字不变
+    let rust = snake("export function addTwo(firstValue: number): number { return firstValue + 2; }", OutputMode::NativeModule);
+    assert!(rust.contains("pub fn add_two(first_value: i32) -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("#[napi(js_name = \"addTwo\")]\npub fn napi_add_two(first_value: i32) -> i32 {\n    add_two(first_value)\n"), "got:\n{rust}");
+
+    // namespace 里的函数跟着改，namespace 本身按模块名规则
+    let rust = snake(
+        "namespace MathUtils { export function doubleIt(x: number): number { return x * 2; } }\nconsole.log(MathUtils.doubleIt(2));",
+        OutputMode::Binary,
+    );
+    assert!(rust.contains("mod math_utils {\n    pub fn double_it(x: i32) -> i32 {"), "got:\n{rust}");
+    assert!(rust.contains("math_utils::double_it(2i32)"), "got:\n{rust}");
+}
+
+#[test]
+fn renames_across_module_tree() {
+    let dir = common::write_project(&[
+        ("main.ets", "import { squareOf } from './util';\nconsole.log(squareOf(3));"),
+        ("util.ets", "export function squareOf(someValue: number): number { return someValue * someValue; }"),
+    ]);
+    let options = CodegenOptions {
+        snake_case: true,
+        ..CodegenOptions::default()
+    };
+    let files = compile_tree(dir.join("main.ets"), OutputMode::Binary, &ExternBindings::new(), &ResolveConfig::default(), &options).unwrap();
+    assert!(file(&files, "main.rs").contains("use crate::util::square_of;\n"), "got:\n{}", file(&files, "main.rs"));
+    assert!(file(&files, "main.rs").contains("square_of(3i32)"));
+    assert!(file(&files, "util.rs").contains("pub(crate) fn square_of(some_value: i32) -> i32 {"), "got:\n{}", file(&files, "util.rs"));
+}
+
+fn with_edition(src: &str, edition: Edition) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        edition,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn escapes_rust_keywords() {
+    let rust = compile(
+        "function impl(match: number, self: number): number {\n  let loop = match + self;\n  return loop;\n}\n\
+         let type = impl(1, 2);\nconsole.log(type);",
+    )
+    .unwrap();
+    assert!(
+        rust.starts_with("fn r#impl(r#match: i32, self_: i32) -> i32 {\n    let r#loop = r#match + self_;\n    return r#loop;\n}\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    let r#type = r#impl(1i32, 2i32);\n    println!(\"{}\", r#type);\n"), "got:\n{rust}");
+
+    // 原生模块：包装函数的名字不能带 `r#`，JS 那边的名字不变
+    let program = parse_program("export function impl(n: number): number { return n; }").unwrap();
+    let rust = generate_with(&program, OutputMode::NativeModule, &ExternBindings::new(), &CodegenOptions::default()).unwrap();
+    assert!(rust.contains("#[napi(js_name = \"impl\")]\npub fn napi_impl(n: i32) -> i32 {\n    r#impl(n)\n"), "got:\n{rust}");
+}
+
+#[test]
+fn keywords_follow_edition() {
+    let src = "let async = 1;\nlet gen = 2;\nconsole.log(async + gen);";
+    let rust = with_edition(src, Edition::E2015);
+    assert!(rust.contains("println!(\"{}\", async + gen);"), "got:\n{rust}");
+    let rust = with_edition(src, Edition::E2021);
+    assert!(rust.contains("println!(\"{}\", r#async + gen);"), "got:\n{rust}");
+    let rust = with_edition(src, Edition::E2024);
+    assert!(rust.contains("println!(\"{}\", r#async + r#gen);"), "got:\n{rust}");
+
+    assert!(is_keyword("Self", Edition::E2015));
+    assert!(is_keyword("try", Edition::E2018));
+    assert!(!is_keyword("try", Edition::E2015));
+    assert!(!is_keyword("union", Edition::E2024));
+}
+
+#[test]
+fn escapes_after_snake_case() {
+    // `self_` 已经被占用时加后缀；snake_case 后的名字不会变成关键字（`Type` 保持原样）
+    let rust = snake("function f(self: number, self_: number, Type: number): number { return self + self_ + Type; }", OutputMode::Binary);
+    assert!(rust.starts_with("fn f(self_2: i32, self_: i32, Type: i32) -> i32 {\n    return self_2 + self_ + Type;\n"), "got:\n{rust}");
+}
+
+#[test]
+fn sanitizes_identifiers() {
+    let rust = compile(
+        "function $double(n: number): number {\n  return n * 2;\n}\n\
+         let 计数 = $double(3);\nlet _count = 1;\nlet $count = 2;\nlet __ctx = 5;\nconsole.log(计数, _count + $count, __ctx);",
+    )
+    .unwrap();
+    assert!(rust.starts_with("fn _double(n: i32) -> i32 {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "    let _u8ba1_u6570 = _double(3i32);\n    let _count = 1i32;\n    let _count_2 = 2i32;\n    let x__ctx = 5i32;\n\
+             \x20   println!(\"{} {} {}\", _u8ba1_u6570, _count + _count_2, x__ctx);\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn rust_ident_rules() {
+    assert_eq!(rust_ident("count", Edition::E2021), "count");
+    assert_eq!(rust_ident("type", Edition::E2021), "r#type");
+    assert_eq!(rust_ident("crate", Edition::E2021), "crate_");
+    assert_eq!(rust_ident("$r", Edition::E2021), "_r");
+    assert_eq!(rust_ident("$", Edition::E2021), "x_");
+    assert_eq!(rust_ident("__ark_random", Edition::E2021), "x__ark_random");
+    assert_eq!(rust_ident("é1", Edition::E2021), "_ue91");
+}
@@ -0,0 +1,103 @@
+use arkts2rust::ast::{CallExpr, Callee, Expr, Literal};
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, lex, parse_program, CodegenOptions, ExternBindings, OutputMode, Stmt, TokenKind};
+
+mod common;
+
+#[test]
+fn parse_resource_refs() {
+    let kinds: Vec<TokenKind> = lex("$r $rawfile").unwrap().into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds[..2], [TokenKind::Ident("$r".into()), TokenKind::Ident("$rawfile".into())]);
+
+    let p = parse_program("let title = $r('app.string.title');").unwrap();
+    let Stmt::VarDecl(v) = &p.stmts[0] else {
+        panic!("expected a variable declaration");
+    };
+    assert_eq!(
+        v.decls[0].init,
+        Some(Expr::Call(CallExpr {
+            callee: Callee::Ident("$r".into()),
+            args: vec![Expr::Literal(Literal::String("app.string.title".into()), Default::default())],
+            span: Default::default(),
+        }))
+    );
+}
+
+const PAGE: &str = r#"
+function logo(): string {
+  return $rawfile('img/logo.png');
+}
+
+@Entry
+@Component
+struct Index {
+  title: string = $r('app.string.title');
+  build() {
+    Column() {
+      Text(this.title).fontColor($r('sys.color.font_primary'))
+      Image(logo())
+    }
+  }
+}
+"#;
+
+#[test]
+fn codegen_resource_constants() {
+    let rust = compile(PAGE).unwrap();
+    assert!(rust.contains("    return String::from(\"rawfile/img/logo.png\");\n"), "got:\n{rust}");
+    assert!(rust.contains("            title: String::from(\"app.string.title\"),\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("    //         .fontColor(String::from(\"sys.color.font_primary\"))\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_resource_lookup_option() {
+    let program = parse_program(PAGE).unwrap();
+    let options = CodegenOptions {
+        resource_lookup: Some("res::lookup".into()),
+        ..CodegenOptions::default()
+    };
+    let rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    assert!(rust.contains("    return res::lookup(\"rawfile/img/logo.png\");\n"), "got:\n{rust}");
+    assert!(rust.contains("            title: res::lookup(\"app.string.title\"),\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_invalid_resource_ref() {
+    for src in [
+        "let a = $r('title');",
+        "let a = $r('app.string');",
+        "let a = $r('user.string.title');",
+        "let name = 'app.string.title'; let a = $r(name);",
+        "let a = $rawfile('a.png', 1);",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "InvalidResourceRef", "{src}");
+    }
+}
+
+#[test]
+fn generated_resource_lookup_runs() {
+    let src = r#"
+let title = $r('app.string.title');
+console.log(title, $rawfile('data.json'));
+"#;
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        resource_lookup: Some("res::lookup".into()),
+        ..CodegenOptions::default()
+    };
+    let mut rust = generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    rust.push_str(
+        "\nmod res {\n    pub fn lookup(name: &str) -> String {\n        \
+         match name {\n            \"app.string.title\" => String::from(\"Hello\"),\n            \
+         other => format!(\"<{other}>\"),\n        }\n    }\n}\n",
+    );
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "Hello <rawfile/data.json>\n");
+}
@@ -0,0 +1,136 @@
+use arkts2rust::ast::{CallExpr, Callee, Expr, Literal, Param, Stmt, TypeAnn};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+#[test]
+fn parse_rest_param() {
+    let p = parse_program("function sum(first: number, ...rest: number[]): number { return first; }")
+        .unwrap();
+    assert_eq!(
+        p.funcs[0].params,
+        vec![
+            Param {
+                name: "first".into(),
+                ty: Some(TypeAnn::Number),
+                is_rest: false,
+                span: Default::default(),
+            },
+            Param {
+                name: "rest".into(),
+                ty: Some(TypeAnn::Array(Box::new(TypeAnn::Number))),
+                is_rest: true,
+                span: Default::default(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_spread_argument() {
+    let p = parse_program("f(1, ...xs);").unwrap();
+    assert_eq!(
+        p.stmts,
+        vec![Stmt::ExprStmt(Expr::Call(CallExpr {
+            callee: Callee::Ident("f".into()),
+            args: vec![
+                Expr::Literal(Literal::Number(1), Default::default()),
+                Expr::Spread(Box::new(Expr::Ident("xs".into(), Default::default()))),
+            ],
+            span: Default::default(),
+        }))]
+    );
+}
+
+#[test]
+fn parse_nested_array_type() {
+    let p = parse_program("function f(m: string[][]): void { return; }").unwrap();
+    assert_eq!(
+        p.funcs[0].params[0].ty,
+        Some(TypeAnn::Array(Box::new(TypeAnn::Array(Box::new(TypeAnn::String)))))
+    );
+}
+
+#[test]
+fn codegen_rest_param_is_vec() {
+    let rust = compile("function log(...msgs: string[]): void { return; }").unwrap();
+    assert!(rust.starts_with("fn log(msgs: Vec<String>) {"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_untyped_rest_param_defaults_to_vec_i32() {
+    let rust = compile("function f(...xs) { return; }").unwrap();
+    assert!(rust.starts_with("fn f(xs: Vec<i32>) {"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_call_collects_rest_args() {
+    let rust = compile("function f(a: number, ...xs: number[]): void { return; } f(1, 2, 3);").unwrap();
+    assert!(rust.contains("f(1i32, vec![2i32, 3i32]);"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_call_with_no_rest_args_passes_empty_vec() {
+    let rust = compile("function f(a: number, ...xs: number[]): void { return; } f(1);").unwrap();
+    assert!(rust.contains("f(1i32, Vec::new());"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_spread_only_forwards_vec() {
+    let rust = compile(
+        "function f(...xs: number[]): void { return; } \
+         function g(...ys: number[]): void { f(...ys); }",
+    )
+    .unwrap();
+    assert!(rust.contains("f(ys.to_vec());"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_mixed_spread_concatenates() {
+    let rust = compile(
+        "function f(...xs: number[]): void { return; } \
+         function g(...ys: number[]): void { f(0, ...ys, 1, 2); }",
+    )
+    .unwrap();
+    assert!(
+        rust.contains("f([vec![0i32], ys.to_vec(), vec![1i32, 2i32]].concat());"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_rest_param_must_be_last() {
+    let err = parse_program("function f(...xs: number[], y: number) { return; }")
+        .expect_err("rest param must be last");
+    assert_eq!(err.code, "RestParamMustBeLast");
+}
+
+#[test]
+fn error_rest_param_must_be_array() {
+    let err = parse_program("function f(...xs: number) { return; }")
+        .expect_err("rest param must be an array");
+    assert_eq!(err.code, "RestParamMustBeArray");
+    assert_eq!(err.span.start_col, 19);
+}
+
+#[test]
+fn error_spread_into_fixed_params() {
+    let err = compile("function f(a: number): void { return; } function g(...xs: number[]): void { f(...xs); }")
+        .expect_err("spread needs a rest param");
+    assert_eq!(err.code, "SpreadRequiresRestParam");
+}
+
+#[test]
+fn generated_rust_with_rest_params_can_compile() {
+    let src = r#"
+function count(label: string, ...xs: number[]): void { return; }
+function forward(...ys: number[]): void { count("a", ...ys); count("b", 1, ...ys, 2); }
+count("c");
+count("d", 1, 2, 3);
+forward(4, 5);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    common::assert_rustc_compiles(&rust);
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use arkts2rust::runtime::{project, source, CRATE_NAME};
+use arkts2rust::{compile, compile_with_options, CodegenOptions, CompileOptions, Edition};
+
+mod common;
+
+const SRC: &str = "let n = parseInt(\"42\");\nlet d = new Date();\nconsole.log(n, d.toISOString());";
+
+const PAGE: &str = r#"
+@Entry
+@Component
+struct Index {
+  @State count: number = 0;
+  @Provide title: string = "Count";
+
+  aboutToAppear(): void {
+    this.count = parseInt("41") + 1;
+  }
+
+  build() {
+    console.log(this.title, this.count);
+  }
+}
+"#;
+
+const ABILITY: &str = r#"
+export default class EntryAbility extends UIAbility {
+  onWindowStageCreate(windowStage: window.WindowStage): void {
+    windowStage.loadContent('pages/Index');
+  }
+}
+function half(n: number): number | null {
+  if (n % 2 == 0) {
+    return n / 2;
+  } else {
+    return null;
+  }
+}
+function check(n: number): number {
+  if (n < 0) {
+    throw "negative";
+  } else {
+    return n;
+  }
+}
+console.log(half(3), "v=" + half(4));
+try {
+  check(-1);
+} catch (e) {
+  console.log("caught", e);
+}
+"#;
+
+fn compile_with_runtime(src: &str) -> String {
+    let options = CompileOptions {
+        codegen: CodegenOptions {
+            runtime: Some(CRATE_NAME.to_string()),
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options).unwrap()
+}
+
+#[test]
+fn helpers_inline_by_default() {
+    let rust = compile(SRC).unwrap();
+    assert!(rust.contains("fn __ark_parse_int(s: &str, radix: i32) -> Option<i32> {"), "got:\n{rust}");
+    assert!(rust.contains("fn __ark_iso_string(t: ArkDate) -> String {"), "got:\n{rust}");
+    assert!(!rust.contains("use "), "got:\n{rust}");
+}
+
+#[test]
+fn helpers_from_runtime_crate() {
+    let rust = compile_with_runtime(SRC);
+    assert!(
+        rust.starts_with("use arkts_runtime::{__ark_parse_int, __ark_iso_string};\n\nfn main() {\n"),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("fn __ark_"), "got:\n{rust}");
+
+    // 只用到一项时不加大括号；没有用到辅助项时不加 use
+    let rust = compile_with_runtime("console.log(parseInt(\"7\"));");
+    assert!(rust.starts_with("use arkts_runtime::__ark_parse_int;\n\nfn main() {\n"), "got:\n{rust}");
+    let rust = compile_with_runtime("console.log(1);");
+    assert!(rust.starts_with("fn main() {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn only_emitted_helpers_are_added() {
+    // 字符串里出现辅助项的名字不算用到
+    let rust = compile("console.log(\"ArkState ArkDate __ark_random()\");").unwrap();
+    assert_eq!(rust, "fn main() {\n    println!(\"{}\", String::from(\"ArkState ArkDate __ark_random()\"));\n}\n");
+
+    // 定义里用到的辅助项一起加上，只用到的才从运行时 crate 引入
+    let src = "let d: Date = new Date(0);\nconsole.log(d.toISOString(), \"abc\".slice(1));";
+    let rust = compile(src).unwrap();
+    assert!(rust.starts_with("type ArkDate = std::time::SystemTime;\n\nfn __ark_millis(t: ArkDate) -> f64 {\n"), "got:\n{rust}");
+    assert!(rust.contains("\nfn __ark_slice(s: &str, start: i32, end: Option<i32>) -> String {\n"), "got:\n{rust}");
+    assert!(rust.contains("__ark_slice(&String::from(\"abc\"), 1i32, None)"), "got:\n{rust}");
+    let rust = compile_with_runtime(src);
+    assert!(
+        rust.starts_with(
+            "type ArkDate = std::time::SystemTime;\n\nuse arkts_runtime::{__ark_from_millis, __ark_iso_string, __ark_slice};\n\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn runtime_source_is_public() {
+    let lib = source();
+    assert!(lib.starts_with("#![allow(non_snake_case)]\n\npub type ArkDate = std::time::SystemTime;\n\npub struct ArkState<T> {\n    pub value: T,\n"), "got:\n{lib}");
+    assert!(lib.contains("\n    pub fn consume<T: 'static>(&self, key: &str) -> ArkLink<T> {\n"), "got:\n{lib}");
+    assert!(lib.contains("\npub fn __ark_random() -> f64 {\n"), "got:\n{lib}");
+    assert!(lib.contains("\npub fn __ark_index_of(s: &str, pat: &str) -> i32 {\n"), "got:\n{lib}");
+    // trait impl 里的方法不能加 pub
+    assert!(lib.contains("impl<T> Clone for ArkLink<T> {\n    fn clone(&self) -> Self {\n"), "got:\n{lib}");
+
+    let files = project(Edition::E2018);
+    assert_eq!(files[0].path, PathBuf::from("Cargo.toml"));
+    assert!(files[0].code.contains("name = \"arkts_runtime\"\nversion = \"0.1.0\"\nedition = \"2018\"\n"));
+    assert_eq!(files[1].path, PathBuf::from("src/lib.rs"));
+    assert_eq!(files[1].code, lib);
+}
+
+#[test]
+fn errors_abilities_and_formatting_come_from_runtime() {
+    let rust = compile(ABILITY).unwrap();
+    assert!(rust.contains("enum ArkError {\n    Error(String),\n}\n"), "got:\n{rust}");
+    assert!(rust.contains("\ntrait UIAbility {\n"), "got:\n{rust}");
+    assert!(rust.contains("\nfn __ark_fmt_nullable<T: ArkToString>(v: &Option<T>, absent: &str) -> String {\n"), "got:\n{rust}");
+
+    let rust = compile_with_runtime(ABILITY);
+    assert!(
+        rust.starts_with(
+            "use arkts_runtime::ArkError;\n\nuse arkts_runtime::{__ark_fmt_nullable, Want, LaunchParam, WindowStage, UIAbility};\n\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("enum ArkError") && !rust.contains("trait "), "got:\n{rust}");
+}
+
+#[test]
+fn generated_page_links_runtime_crate() {
+    if !common::has_rustc() {
+        return;
+    }
+
+    let dir = common::temp_path("runtime");
+    fs::create_dir_all(&dir).unwrap();
+    let lib_path = dir.join("lib.rs");
+    let rlib_path = dir.join("libarkts_runtime.rlib");
+    let main_path = dir.join("main.rs");
+    let exe_path = dir.join("main");
+    fs::write(&lib_path, source()).unwrap();
+    fs::write(&main_path, compile_with_runtime(PAGE)).unwrap();
+
+    // 运行时 crate 本身编译时没有警告
+    let lib = Command::new("rustc")
+        .args(["--edition", "2021", "--crate-type", "lib", "--crate-name", CRATE_NAME])
+        .arg(&lib_path)
+        .arg("-o")
+        .arg(&rlib_path)
+        .output()
+        .unwrap();
+    assert!(lib.status.success() && lib.stderr.is_empty(), "{}", String::from_utf8_lossy(&lib.stderr));
+    let extern_arg = format!("{CRATE_NAME}={}", rlib_path.display());
+    common::rustc(&main_path, &exe_path, &["--edition", "2021", "--extern", &extern_arg]);
+
+    let run = Command::new(&exe_path).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "Count 42\n");
+
+    fs::write(&main_path, compile_with_runtime(ABILITY)).unwrap();
+    common::rustc(&main_path, &exe_path, &["--edition", "2021", "--extern", &extern_arg]);
+    let run = Command::new(&exe_path).output().unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "null v=2\ncaught negative\nloadContent pages/Index\n");
+}
@@ -0,0 +1,254 @@
+use arkts2rust::{compile, parse_program, sema};
+
+mod common;
+
+#[test]
+fn error_undeclared_variable() {
+    let err = compile("let total = 0;\nx = 1;").expect_err("x is not declared");
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 1));
+
+    let err = compile("function f(n: number): number {\n  return n + missing;\n}").expect_err("read");
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 14));
+
+    for src in [
+        "util.f();",
+        "{ let a = 1; } console.log(a);",
+        "function f(n: number): void {} console.log(n);",
+        "try { throw new Error('x'); } catch (e) {} console.log(e);",
+        "function f(): void { let inner = 1; } function g(): number { return inner; }",
+        "function f(...xs: number[]): void { ys[0] = xs[0]; }",
+        "if (true) { let t = 1; } else { t = 2; }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "UndeclaredVariable", "{src}");
+    }
+}
+
+#[test]
+fn suggests_similar_variable_names() {
+    let err = compile("let total = 0;\nfunction f(count: number): number {\n  return count + totl;\n}").unwrap_err();
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!(err.notes[0].message(), "did you mean `total`?");
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+
+    // 参数和内置对象也是候选
+    let err = compile("function f(count: number): number { return cont; }").unwrap_err();
+    assert_eq!(err.notes[0].message(), "did you mean `count`?");
+    let err = compile("console.log(Mth.max(1, 2));").unwrap_err();
+    assert!(err.to_string().ends_with("\n  note: did you mean `Math`?"), "{err}");
+
+    let err = compile("console.log(somethingElse);").unwrap_err();
+    assert!(err.notes.is_empty());
+}
+
+#[test]
+fn resolves_declared_names() {
+    let src = r#"
+let count = 0;
+const LIMIT = 10;
+
+function bump(count: number, step: number): number {
+  function apply(n: number): number {
+    return n + step + LIMIT;
+  }
+  count = apply(count);
+  return Math.max(count, later);
+}
+
+const later = 1;
+
+namespace util {
+  export function twice(n: number): number { return n * 2; }
+  export function quad(n: number): number { return twice(twice(n)); }
+}
+
+try {
+  bump(count, 1);
+} catch (e) {
+  console.log(e);
+}
+console.log(util.quad(count));
+"#;
+    sema::check(&parse_program(src).unwrap()).unwrap();
+}
+
+#[test]
+fn resolves_component_names() {
+    let src = r#"
+@Entry
+@Component
+struct Index {
+  @State count: number = 0;
+  build() {
+    Column() {
+      Text('n').fontWeight(FontWeight.Bold)
+      Button('+').onClick(() => { this.count = this.count + 1; })
+      ForEach(this.items, (item: string) => {
+        Text(item)
+      })
+    }
+  }
+}
+"#;
+    sema::check(&parse_program(src).unwrap()).unwrap();
+
+    let err = compile("@Entry\n@Component\nstruct Index {\n  build() {\n    Text(title)\n  }\n}")
+        .expect_err("title is not declared");
+    assert_eq!(err.code, "UndeclaredVariable");
+}
+
+#[test]
+fn error_const_reassignment() {
+    let err = compile("const LIMIT = 10;\nlet n = 0;\nn = LIMIT;\nLIMIT = 20;").expect_err("const");
+    assert_eq!(err.code, "ConstReassignment");
+    assert_eq!((err.span.start_line, err.span.start_col), (4, 1));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+    assert_eq!(err.notes[0].message(), "declared as const here");
+    assert!(err.to_string().ends_with("\n  note: 1:1: declared as const here"), "{err}");
+
+    // 赋值表达式、解构出来的 const 也一样
+    for src in [
+        "function f(): void { const a = 1; let b = 0; b = (a = 2); }",
+        "function f(...xs: number[]): void { const [first] = xs; first = 0; }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "ConstReassignment", "{src}");
+    }
+
+    // 内层的 let 遮蔽了外层的 const
+    sema::check(&parse_program("const x = 1; function f(): void { let x = 2; x = 3; }").unwrap()).unwrap();
+}
+
+#[test]
+fn error_duplicate_declaration() {
+    let err = compile("let total = 0;\nfunction f(): void {}\nlet total = 1;").expect_err("duplicate let");
+    assert_eq!(err.code, "DuplicateDeclaration");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 1));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+    assert_eq!(err.notes[0].message(), "first declared here");
+
+    for src in [
+        "function f(): void {}\nfunction f(): void {}",
+        "let a = 1, a = 2;",
+        "function f(): void { let helper = 1; function helper(): void {} }",
+        "function f(a: number, a: number): void {}",
+        "function f(a: number): void { const a = 1; }",
+        "try { throw new Error('x'); } catch (e) { let e = 1; }",
+        "namespace util { export function f(): void {} function f(): void {} }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "DuplicateDeclaration", "{src}");
+    }
+
+    // 不同的作用域里可以同名（内层遮蔽外层）
+    let src = "let a = 1;\nfunction f(a: number): void { { let a = 2; } }\nif (true) { let b = 1; } else { let b = 2; }";
+    sema::check(&parse_program(src).unwrap()).unwrap();
+}
+
+#[test]
+fn error_used_before_declaration() {
+    let err = compile("let a = 1;\nconsole.log(b);\nlet b = a;").expect_err("tdz");
+    assert_eq!(err.code, "UsedBeforeDeclaration");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 13));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (3, 1));
+    assert_eq!(err.notes[0].message(), "declared here");
+
+    for src in [
+        "function f(): void { x = 1; let x = 0; }",
+        "const n = n + 1;",
+        "let a = b, b = 1;",
+        "function f(): void { while (true) { console.log(limit); } const limit = 3; }",
+        // 内层代码块里的同名变量从块的开头起就遮蔽外层的变量
+        "let v = 1; { console.log(v); let v = 2; }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "UsedBeforeDeclaration", "{src}");
+    }
+
+    // 函数要等被调用时才执行：先声明的函数可以使用后声明的变量
+    let src = "function show(): void { console.log(total); }\nconst total = 1;\nshow();\n\
+               function f(): void { function g(): number { return n; } let n = 1; console.log(g()); }";
+    sema::check(&parse_program(src).unwrap()).unwrap();
+}
+
+#[test]
+fn error_unknown_function() {
+    let err = compile("function compute(n: number): number { return n; }\nlet x = 1;\nconsole.log(comptue(x));")
+        .expect_err("typo");
+    assert_eq!(err.code, "UnknownFunction");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 13));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+    assert_eq!(err.notes[0].message(), "did you mean `compute`?");
+
+    // 内置函数没有位置，说明里只有文字
+    let err = compile("let n = parseInteger('1');").expect_err("builtin typo");
+    assert_eq!(err.code, "UnknownFunction");
+    assert!(err.to_string().ends_with("\n  note: did you mean `parseInt`?"), "{err}");
+
+    let err = compile("frobnicate();").expect_err("nothing similar");
+    assert_eq!(err.code, "UnknownFunction");
+    assert!(err.notes.is_empty());
+
+    // 参数、变量里的回调、块内函数和内置函数都可以调用
+    let src = "function run(cb: () => void): void { cb(); }\n\
+               function outer(): void { function helper(): void {} helper(); run(helper); }\n\
+               let n = parseInt('1');\nassert(n == 1);";
+    sema::check(&parse_program(src).unwrap()).unwrap();
+}
+
+#[test]
+fn warns_unused_names() {
+    let src = "let unused = 1;\nlet count = 0;\ncount = 2;\nfunction helper(): void {}\n\
+               function used(n: number): number {\n  const _skip = 3;\n  let tmp = n;\n  return tmp;\n}\n\
+               export function api(): void {}\nexport const LIMIT = 1;\nconsole.log(used(1));";
+    let warnings = sema::warnings(&parse_program(src).unwrap());
+    let found: Vec<(&str, usize)> = warnings.iter().map(|w| (w.code.as_str(), w.span.start_line)).collect();
+    assert_eq!(found, [("UnusedVariable", 1), ("UnusedVariable", 2), ("UnusedFunction", 4)]);
+    assert!(warnings[0].render(src).starts_with("warning[UnusedVariable]: unused variable `unused`\n"), "{}", warnings[0]);
+    assert!(warnings[2].render(src).starts_with("warning[UnusedFunction]: unused function `helper`\n"), "{}", warnings[2]);
+
+    // 当作回调传出去、namespace 里互相调用、块内函数的调用也算使用
+    let src = "function run(cb: () => void): void { cb(); }\nfunction tick(): void {}\nrun(tick);\n\
+               function fact(n: number): number { if (n > 1) { return n * fact(n - 1); } else { return 1; } }\n\
+               namespace util { function twice(n: number): number { return n * 2; } \
+               export function quad(n: number): number { return twice(twice(n)); } }\n\
+               function outer(): void { function inner(): void {} inner(); }\nouter();\nconsole.log(fact(3));";
+    let warnings = sema::warnings(&parse_program(src).unwrap());
+    let found: Vec<(&str, usize)> = warnings.iter().map(|w| (w.code.as_str(), w.span.start_line)).collect();
+    assert!(found.is_empty(), "{found:?}");
+
+    // 有语义错误时没有警告
+    assert!(sema::warnings(&parse_program("let a = 1; b = 2;").unwrap()).is_empty());
+}
+
+#[test]
+fn top_level_bindings_in_functions() {
+    // 顶层的 let 是 main 的局部变量，函数里看不到
+    let err = compile("let total = 0;\nfunction add(n: number): void {\n  total = total + n;\n}\nadd(1);").unwrap_err();
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 3));
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].message().as_str()), (1, "top-level `let` variables are local to `main`"));
+
+    // 函数里用到的顶层 const 生成模块级的常量，main 里不再声明
+    let src = "const K = 3;\nconst LABEL = 'k';\nconst TWICE = K * 2;\nfunction f(n: number): number { return n * TWICE; }\n\
+               console.log(LABEL, K, f(1));";
+    let rust = compile(src).unwrap();
+    assert!(rust.starts_with("const K: i32 = 3i32;\n\nconst TWICE: i32 = K * 2i32;\n\nfn f("), "got:\n{rust}");
+    assert!(rust.contains("fn main() {\n    let LABEL = String::from(\"k\");\n    println!"), "got:\n{rust}");
+    if let Some(out) = common::compile_and_run_with(&rust, &["-A", "warnings"]) {
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "k 3 6\n");
+    }
+
+    // 不是常量表达式就不能生成模块级的常量
+    let err = compile("function g(): number { return 2; }\nconst m = g();\nfunction f(): number { return m; }\nconsole.log(f());")
+        .unwrap_err();
+    assert_eq!(err.code, "SharedConstNotConstant");
+    assert_eq!(err.span.start_line, 2);
+}
@@ -0,0 +1,109 @@
+use std::fs;
+
+use arkts2rust::codegen::{generate_mapped, generate_tree_mapped, generate_with};
+use arkts2rust::{
+    module, parse_program, BraceStyle, CodegenOptions, ErrorCode, ExternBindings, Formatter, LayoutOptions, OutputMode,
+    ResolveConfig,
+};
+
+const SRC: &str = "function f(n: number): number {\n  return n + 1;\n}\n\nlet x = f(2);\nconsole.log(x);";
+
+#[test]
+fn mappings_point_at_first_generated_line() {
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions::default();
+    let (rust, map) = generate_mapped(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    // 位置标记不留在代码里，代码和不生成映射时一样
+    assert_eq!(rust, generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap());
+    assert!(map.sources.is_empty());
+
+    let found: Vec<(usize, usize, usize)> =
+        map.mappings.iter().map(|m| (m.line, m.col, m.original.start_line)).collect();
+    assert_eq!(found, vec![(1, 1, 1), (2, 5, 2), (6, 5, 5), (7, 5, 6)]);
+
+    // 生成代码 -> 源码
+    assert_eq!(map.original(2).unwrap().original.start_line, 2);
+    assert_eq!(map.original(3).unwrap().original.start_line, 2);
+    assert_eq!(map.original(7).unwrap().original.start_line, 6);
+    // 源码 -> 生成代码：取包含这个位置的最小区间
+    assert_eq!(map.generated(2, 10).unwrap().line, 2);
+    assert_eq!(map.generated(1, 5).unwrap().line, 1);
+    assert_eq!(map.generated(5, 9).unwrap().line, 6);
+    assert!(map.generated(4, 1).is_none());
+}
+
+#[test]
+fn mappings_follow_layout_and_comments() {
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions {
+        provenance: true,
+        layout: LayoutOptions {
+            brace_style: BraceStyle::NextLine,
+            ..LayoutOptions::default()
+        },
+        ..CodegenOptions::default()
+    };
+    let (rust, map) = generate_mapped(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    let lines: Vec<&str> = rust.lines().collect();
+    for m in &map.mappings {
+        // 每一项映射的前一行是同一个源码行的位置注释
+        assert_eq!(lines[m.line - 2].trim(), format!("// line {}", m.original.start_line), "got:\n{rust}");
+        assert_eq!(m.col, lines[m.line - 1].len() - lines[m.line - 1].trim_start().len() + 1);
+    }
+    assert_eq!(map.mappings.len(), 4);
+}
+
+#[test]
+fn prettyplease_is_rejected() {
+    // prettyplease 会丢掉位置标记，不能返回一个空的映射
+    let program = parse_program(SRC).unwrap();
+    let options = CodegenOptions {
+        formatter: Formatter::Prettyplease,
+        ..CodegenOptions::default()
+    };
+    let err = generate_mapped(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap_err();
+    assert_eq!(err.code, ErrorCode::SourceMapFormatter);
+    let err = generate_tree_mapped(&program, &[], OutputMode::Binary, &ExternBindings::new(), &options).unwrap_err();
+    assert_eq!(err.code, ErrorCode::SourceMapFormatter);
+}
+
+#[test]
+fn json_and_module_tree() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_source_map_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("util.ets"), "export function twice(n: number): number {\n  return n * 2;\n}").unwrap();
+    fs::write(dir.join("main.ets"), "import { twice } from './util';\nconsole.log(twice(2));").unwrap();
+    let modules = module::load_modules_with(&dir.join("main.ets"), &ResolveConfig::default()).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let (program, units) = module::link_tree(modules).unwrap();
+    let files =
+        generate_tree_mapped(&program, &units, OutputMode::Binary, &ExternBindings::new(), &CodegenOptions::default()).unwrap();
+
+    let (main, main_map) = &files[0];
+    assert!(!main.code.contains("//@"), "got:\n{}", main.code);
+    assert_eq!(main_map.mappings.len(), 1);
+    assert_eq!(main_map.source(&main_map.mappings[0]), Some(dir.join("main.ets").display().to_string().as_str()));
+
+    let (util, util_map) = &files[1];
+    assert!(util.code.starts_with("pub(crate) fn twice(n: i32) -> i32 {\n"), "got:\n{}", util.code);
+    let util_file = dir.join("util.ets").display().to_string();
+    assert_eq!(util_map.sources, vec![util_file.clone()]);
+    let json = util_map.to_json("util.rs");
+    assert!(
+        json.starts_with(&format!(
+            "{{\n  \"version\": 1,\n  \"file\": \"util.rs\",\n  \"sources\": [\"{}\"],\n  \"mappings\": [\n    {{\"line\": 1, \"col\": 1, \"source\": 0, \"original\": {{\"start\": 7,",
+            util_file.replace('\\', "\\\\")
+        )),
+        "got:\n{json}"
+    );
+    assert!(json.contains("{\"line\": 2, \"col\": 5, \"source\": 0,"), "got:\n{json}");
+    assert!(json.ends_with("}\n  ]\n}\n"), "got:\n{json}");
+}
@@ -0,0 +1,81 @@
+use arkts2rust::ast::{Expr, Stmt};
+use arkts2rust::{compile, parse_program};
+
+#[test]
+fn parse_node_spans() {
+    let p = parse_program("let a = 1;\nfoo(a + 2, 3);\nfunction f(): void {\n  return;\n}").unwrap();
+    let Stmt::VarDecl(v) = &p.stmts[0] else {
+        panic!("expected a variable declaration");
+    };
+    assert_eq!((v.span.0.start_line, v.span.0.start_col, v.span.0.end_col), (1, 1, 11));
+
+    let Stmt::ExprStmt(Expr::Call(call)) = &p.stmts[1] else {
+        panic!("expected a call");
+    };
+    assert_eq!((call.span.0.start_line, call.span.0.start_col, call.span.0.end_col), (2, 1, 14));
+    let Expr::Binary(b) = &call.args[0] else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!((b.span.0.start_col, b.span.0.end_col), (5, 10));
+    // 标识符、字面量有自己的位置
+    assert_eq!((b.left.span().start_col, b.left.span().end_col), (5, 6));
+    assert_eq!((b.right.span().start_col, b.right.span().end_col), (9, 10));
+    assert_eq!((call.args[1].span().start_col, call.args[1].span().end_col), (12, 13));
+
+    let f = &p.funcs[0];
+    assert_eq!((f.span.0.start_line, f.span.0.end_line), (3, 5));
+    assert_eq!(f.body.stmts[0].span().start_line, 4);
+    assert_eq!((f.body.span.0.start_line, f.body.span.0.start_col, f.body.span.0.end_line), (3, 20, 5));
+
+    let p = parse_program("if (ok) {
+  x = 1;
+} else { }").unwrap();
+    let Stmt::If(i) = &p.stmts[0] else {
+        panic!("expected an if statement");
+    };
+    let span = i.then_branch.span();
+    assert_eq!((span.start_line, span.start_col, span.end_line, span.end_col), (1, 9, 3, 2));
+    assert_eq!((i.cond.span().start_col, i.cond.span().end_col), (5, 7));
+}
+
+#[test]
+fn name_errors_point_at_the_identifier() {
+    let err = compile("let a = 1;
+console.log(a + missing);").expect_err("undeclared variable");
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!((err.span.start_line, err.span.start_col, err.span.end_col), (2, 17, 24));
+
+    // 不支持的 `break` 是一个未声明的名字，报在它自己的位置，不是 while 的开头
+    let err = compile("let i = 0;\nwhile (i < 3) {\n  i = i + 1;\n  break;\n}").expect_err("break is not supported");
+    assert_eq!(err.code, "UndeclaredVariable");
+    assert_eq!((err.span.start_line, err.span.start_col, err.span.end_col), (4, 3, 8));
+}
+
+#[test]
+fn codegen_errors_carry_spans() {
+    let err = compile("function f(): number {\n  return;\n}").expect_err("non-void return needs value");
+    assert_eq!(err.code, "ReturnValueRequired");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 3));
+
+    // 最内层的节点提供位置
+    let err = compile("let x = 1;\nconsole.log(x, hilog.info(0, 'T', 'a=%d'));").expect_err("hilog in expression");
+    assert_eq!(err.code, "HilogArgCount");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 16));
+}
+
+#[test]
+fn member_errors_point_at_the_member() {
+    // 第二个声明项的错误报在它自己的位置，不是语句开头
+    let err = compile("let a = 1,\n  b: string = 2;").expect_err("number assigned to string");
+    assert_eq!(err.code, "TypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 3));
+
+    let err = compile("@Component\nstruct Counter {\n  @State count: number;\n  build() {\n    Text('x')\n  }\n}")
+        .expect_err("state without initializer");
+    assert_eq!(err.code, "StateFieldNeedsInitializer");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 10));
+
+    let err = compile("try {\n  console.log('a');\n} catch (e: Missing) {\n}").expect_err("unknown error class");
+    assert_eq!(err.code, "UnknownErrorClass");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 3));
+}
@@ -0,0 +1,171 @@
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+#[test]
+fn state_decorators_are_known() {
+    let p = parse_program(
+        "@Component struct A { @State n: number = 0; @Prop p: number; @Link l: number; \
+         @Provide('k') v: number = 1; @Consume('k') c: number; build() {} }",
+    )
+    .unwrap();
+    assert!(p.components[0].fields[0].has_decorator("State"));
+    assert!(p.warnings.is_empty(), "{:?}", p.warnings);
+}
+
+#[test]
+fn codegen_state_fields() {
+    let rust = compile(
+        r#"@Component
+struct Counter {
+  @State count: number = 0;
+  @State label: string = "n";
+  step = 1;
+  build() {
+    this.count = this.count + this.step;
+    let copy = this.label = "m";
+  }
+}"#,
+    )
+    .unwrap();
+    assert!(rust.starts_with("struct ArkState<T> {\n    value: T,\n    version: u64,\n}\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("struct Counter {\n    count: ArkState<i32>,\n    label: ArkState<String>,\n    step: i32,\n}\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("            count: ArkState::new(0i32),\n            label: ArkState::new(String::from(\"n\")),\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("        self.count.set(self.count.value + self.step);\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("        let copy = { self.label.set(String::from(\"m\")); self.label.value.clone() };\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("        let version = self.count.version + self.label.version;\n"),
+        "got:\n{rust}"
+    );
+
+    let rust = compile("@Component struct A { n = 0; build() {} }").unwrap();
+    assert!(!rust.contains("ArkState") && !rust.contains("__ark_rerender"), "got:\n{rust}");
+}
+
+#[test]
+fn error_state_without_initializer() {
+    let err = compile("@Component struct A { @State n: number; build() {} }").expect_err("needs init");
+    assert_eq!(err.code, "StateFieldNeedsInitializer");
+}
+
+#[test]
+fn codegen_prop_link_provide_consume() {
+    let rust = compile(
+        r#"@Component
+struct Child {
+  @Prop label: string;
+  @Prop size: number = 10;
+  @Link count: number;
+  @Consume('theme') color: string;
+  build() {
+    this.count = this.count + this.size;
+    console.log(this.label, this.color);
+  }
+}
+@Component
+struct Parent {
+  @Provide('theme') color: string = "dark";
+  @Provide total: number = 0;
+  build() {}
+}"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "struct Child {\n    label: ArkProp<String>,\n    size: ArkProp<i32>,\n    count: ArkLink<i32>,\n    \
+             color: ArkLink<String>,\n}\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    fn new(label: String, count: ArkLink<i32>, ctx: &ArkContext) -> Self {\n        Self {\n            \
+             label: ArkProp::new(label),\n            size: ArkProp::new(10i32),\n            count,\n            \
+             color: ctx.consume(\"theme\"),\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("        self.count.set(self.count.get() + self.size.value);\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("        println!(\"{} {}\", self.label.value.clone(), self.color.get());\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    fn __ark_provide(&self, ctx: &mut ArkContext) {\n        \
+             ctx.provide(\"theme\", self.color.clone());\n        ctx.provide(\"total\", self.total.clone());\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("            color: ArkLink::new(String::from(\"dark\")),\n"), "got:\n{rust}");
+    for helper in ["struct ArkState<T>", "struct ArkProp<T>", "struct ArkLink<T>", "struct ArkContext"] {
+        assert!(rust.contains(helper), "{helper} missing:\n{rust}");
+    }
+}
+
+#[test]
+fn error_state_decorator_misuse() {
+    for (src, code) in [
+        ("@Component struct A { @Provide n: number; build() {} }", "StateFieldNeedsInitializer"),
+        ("@Component struct A { @Link n: number = 1; build() {} }", "StateFieldHasInitializer"),
+        ("@Component struct A { @Consume n = 1; build() {} }", "StateFieldHasInitializer"),
+        ("@Component struct A { @State @Prop n: number = 1; build() {} }", "ConflictingStateDecorators"),
+        ("@Component struct A { @Consume(1) n: number; build() {} }", "InvalidDecoratorArgs"),
+        ("@Entry @Component struct A { @Link n: number; build() {} }", "EntryFieldNeedsInitializer"),
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_state_page_rerenders() {
+    let src = r#"
+@Entry
+@Component
+struct Index {
+  @State count: number = 0;
+  @Provide title: string = "Count";
+  total: number = 0;
+
+  aboutToAppear(): void {
+    this.count = 1;
+  }
+
+  build() {
+    this.total = this.total + 1;
+    console.log(this.title, this.count, this.total);
+  }
+
+  onPageShow(): void {
+    this.count = this.count + 1;
+  }
+
+  onPageHide(): void {
+    this.total = this.total + 10;
+  }
+
+  aboutToDisappear(): void {
+    this.title = "Bye";
+    console.log(this.title, this.total);
+  }
+}
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    // onPageHide 只改了普通字段，不会重新 build
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "Count 1 1\nCount 2 2\nBye 12\n");
+}
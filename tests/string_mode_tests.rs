@@ -0,0 +1,89 @@
+use arkts2rust::{compile, compile_with_options, CodegenOptions, CompileOptions, ErrorCode, OutputMode, StringMode};
+
+mod common;
+
+fn compile_strings(src: &str, mode: OutputMode, string_mode: StringMode) -> Result<String, arkts2rust::Error> {
+    let options = CompileOptions {
+        mode,
+        codegen: CodegenOptions {
+            string_mode,
+            ..CodegenOptions::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_options(src, &options)
+}
+
+const SRC: &str = "function pick(b: boolean): string {\n  if (b) {\n    return 'yes';\n  } else {\n    return 'no';\n  }\n}\n\
+                   const GREETING = 'hello';\nlet a: string = GREETING;\nlet b: string = pick(a == 'hello');\n\
+                   console.log(a, b, a.length);\nconsole.log('a: ' + a);";
+
+#[test]
+fn owned_is_the_default() {
+    let rust = compile(SRC).unwrap();
+    assert!(rust.contains("fn pick(b: bool) -> String {"), "got:\n{rust}");
+    assert!(rust.contains("let GREETING = String::from(\"hello\");"), "got:\n{rust}");
+}
+
+#[test]
+fn static_strings() {
+    let rust = compile_strings(SRC, OutputMode::Binary, StringMode::Static).unwrap();
+    assert!(rust.contains("fn pick(b: bool) -> &'static str {"), "got:\n{rust}");
+    assert!(rust.contains("        return \"yes\";\n"), "got:\n{rust}");
+    assert!(rust.contains("let a: &'static str = GREETING;"), "got:\n{rust}");
+    assert!(rust.contains("let b: &'static str = pick(a == \"hello\");"), "got:\n{rust}");
+    assert!(rust.contains("println!(\"{}\", format!(\"{}{}\", \"a: \", a));"), "got:\n{rust}");
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "hello yes 5\na: hello\n");
+    }
+
+    // 运行时生成的字符串不能存成 `&'static str`
+    for src in ["let s: string = 'a' + 'b';", "let s = 'a';\nlet t: string = s.trim();"] {
+        let err = compile_strings(src, OutputMode::Binary, StringMode::Static).unwrap_err();
+        assert_eq!(err.code, ErrorCode::StaticStringNotLiteral, "{src}");
+    }
+}
+
+#[test]
+fn static_string_constants_in_library() {
+    let src = "export const NAME = 'demo';\nexport function name(): string {\n  return NAME;\n}";
+    let rust = compile_strings(src, OutputMode::Library, StringMode::Static).unwrap();
+    assert!(rust.contains("pub const NAME: &'static str = \"demo\";"), "got:\n{rust}");
+
+    let err = compile_strings(src, OutputMode::Library, StringMode::Owned).unwrap_err();
+    assert_eq!(err.code, ErrorCode::TopLevelCodeInLibrary);
+}
+
+#[test]
+fn shared_strings() {
+    let rust = compile_strings(SRC, OutputMode::Binary, StringMode::Shared).unwrap();
+    assert!(rust.contains("fn pick(b: bool) -> std::rc::Rc<str> {"), "got:\n{rust}");
+    assert!(rust.contains("return std::rc::Rc::<str>::from(\"yes\");"), "got:\n{rust}");
+    assert!(rust.contains("let a: std::rc::Rc<str> = GREETING.clone();"), "got:\n{rust}");
+    // 和字面量比较时不新建 `Rc<str>`
+    assert!(rust.contains("let b: std::rc::Rc<str> = pick(&*a == \"hello\");"), "got:\n{rust}");
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "hello yes 5\na: hello\n");
+    }
+
+    let src = "let s = 'x';\nconsole.log('x' != s, s + '!' == 'x!');";
+    let rust = compile_strings(src, OutputMode::Binary, StringMode::Shared).unwrap();
+    assert!(rust.contains("\"x\" != &*s, &*format!(\"{}{}\", s, \"!\") == \"x!\""), "got:\n{rust}");
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "false true\n");
+    }
+}
+
+#[test]
+fn shared_strings_are_cloned_not_moved() {
+    let src = "function id(s: string): string {\n  return s;\n}\nfunction pass(s: string): string {\n  let t = s;\n  return id(s) + t;\n}\n\
+               let a = 'x';\nlet b = a;\nlet c: string = a;\nlet d = id(b);\nlet g = a.trim();\nconsole.log(a, b, c, d, g, pass(a));";
+    let rust = compile_strings(src, OutputMode::Binary, StringMode::Shared).unwrap();
+    assert!(rust.contains("    let b = a.clone();\n"), "got:\n{rust}");
+    assert!(rust.contains("    return s.clone();\n"), "got:\n{rust}");
+    assert!(rust.contains("let d = id(b.clone());"), "got:\n{rust}");
+    assert!(rust.contains("let g = std::rc::Rc::<str>::from(a.trim().to_string());"), "got:\n{rust}");
+    if let Some(out) = common::run_stdout(&rust) {
+        assert_eq!(out, "x x x x x xx\n");
+    }
+}
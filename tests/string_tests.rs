@@ -0,0 +1,181 @@
+use arkts2rust::ast::{BinaryExpr, BinaryOp, CallExpr, Callee, Expr, IndexExpr, Literal, MemberExpr, Stmt};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+fn expr(src: &str) -> Expr {
+    let p = parse_program(src).unwrap();
+    match p.stmts.into_iter().next().unwrap() {
+        Stmt::ExprStmt(e) => e,
+        other => panic!("expected expression statement, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_property_and_method_chain() {
+    assert_eq!(
+        expr(r#""a b".split(" ").length;"#),
+        Expr::Member(MemberExpr {
+            object: Box::new(Expr::Call(CallExpr {
+                callee: Callee::Method {
+                    object: Box::new(Expr::Literal(Literal::String("a b".into()), Default::default())),
+                    method: "split".into(),
+                },
+                args: vec![Expr::Literal(Literal::String(" ".into()), Default::default())],
+                span: Default::default(),
+            })),
+            property: "length".into(),
+            span: Default::default(),
+        })
+    );
+    assert_eq!(
+        expr("s.trim();"),
+        Expr::Call(CallExpr {
+            callee: Callee::Member {
+                object: "s".into(),
+                property: "trim".into(),
+            },
+            args: vec![],
+            span: Default::default(),
+        })
+    );
+}
+
+#[test]
+fn codegen_string_methods() {
+    let rust = compile(
+        r#"function f(s: string, t: string): void {
+  let n = s.length + 1;
+  let ok = s.length < 3 && s.includes(t);
+  let u = s.trim().toUpperCase();
+  let r = s.replace("a", t);
+}"#,
+    )
+    .unwrap();
+    assert!(rust.contains("    let n = (s.chars().count() as i32) + 1i32;\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("    let ok = (s.chars().count() as i32) < 3i32 && s.contains(&t);\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    let u = s.trim().to_string().to_uppercase();\n"), "got:\n{rust}");
+    assert!(rust.contains("    let r = s.replacen(\"a\", &t, 1);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn parse_index_read() {
+    assert_eq!(
+        expr("s[i + 1];"),
+        Expr::Index(IndexExpr {
+            object: Box::new(Expr::Ident("s".into(), Default::default())),
+            index: Box::new(Expr::Binary(BinaryExpr {
+                op: BinaryOp::Add,
+                left: Box::new(Expr::Ident("i".into(), Default::default())),
+                right: Box::new(Expr::Literal(Literal::Number(1), Default::default())),
+                span: Default::default(),
+            })),
+            span: Default::default(),
+        })
+    );
+}
+
+#[test]
+fn codegen_string_index_and_char_code() {
+    let rust = compile("function f(s: string, i: number): void { let c = s[i]; let k = s.charCodeAt(0); }").unwrap();
+    assert!(
+        rust.contains("    let c = s.chars().nth(i as usize).map(String::from).unwrap_or_default();\n"),
+        "got:\n{rust}"
+    );
+    assert!(rust.contains("    let k = s.chars().nth(0).map_or(-1i32, |c| c as i32);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_unknown_string_members() {
+    let err = compile(r#"let s = "x"; s.padStart(3);"#).expect_err("unknown method");
+    assert_eq!(err.code, "UnknownStringMethod");
+    let err = compile(r#"let s = "x"; console.log(s.size);"#).expect_err("unknown property");
+    assert_eq!(err.code, "UnknownStringMethod");
+    let err = compile(r#"let s = "x"; s.slice();"#).expect_err("missing argument");
+    assert_eq!(err.code, "StringMethodArgCount");
+    let err = compile("let n = 1; n.trim();").expect_err("not a string");
+    assert_eq!(err.code, "UnknownMethod");
+}
+
+#[test]
+fn generated_string_code_runs() {
+    let src = r#"
+function shout(s: string): string {
+  return s.trim().toUpperCase();
+}
+let t = "  Hello, World  ".trim();
+console.log(t.length, t.includes("World"), t.indexOf("o"), t.indexOf("zz"));
+console.log(t.slice(7), t.slice(-5, -1), t.slice(3, 1).length);
+console.log(t.substring(5, 0), t.substring(-3, 2), t.toLowerCase());
+let parts = t.split(", ");
+console.log(parts, "abc".split("").length);
+console.log(t.replace("l", "L"), shout(" hi "));
+console.log(t[0], t.charAt(4), t.charAt(99).length, t.charCodeAt(0), t.charCodeAt(-1), "é€".charCodeAt(1));
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "12 true 4 -1\nWorld Worl 0\nHello He hello, world\n[\"Hello\", \"World\"] 3\nHeLlo, World HI\nH o 0 72 -1 8364\n"
+    );
+}
+
+#[test]
+fn string_concatenation_compiles() {
+    let src = r#"
+function greet(n: string): string {
+  return "hi " + n;
+}
+let s = greet("ark");
+let n = 1;
+let t = s + "!" + n;
+console.log(t);
+console.log(1 + 2 + s);
+console.log(s + (1 + 2));
+console.log(t + s);
+console.log(s);
+"#;
+    let rust = compile(src).unwrap();
+    assert!(rust.contains("    return format!(\"{}{}\", \"hi \", n);\n"), "got:\n{rust}");
+    assert!(rust.contains("    let t = format!(\"{}{}{}\", s, \"!\", n);\n"), "got:\n{rust}");
+    // 左边的数字加法先算
+    assert!(rust.contains("format!(\"{}{}\", 1i32 + 2i32, s)"), "got:\n{rust}");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "hi ark!1\n3hi ark\nhi ark3\nhi ark!1hi ark\nhi ark\n");
+}
+
+#[test]
+fn nullable_operands_concatenate_like_js() {
+    let src = r#"
+function half(n: number): number | null {
+  if (n % 2 == 0) {
+    return n / 2;
+  } else {
+    return null;
+  }
+}
+let x = half(3);
+let y = half(4);
+let name: string | undefined = undefined;
+console.log("a" + x, "b" + y);
+console.log("v=" + null + "," + undefined);
+console.log("hi " + name);
+"#;
+    let rust = compile(src).unwrap();
+    assert!(rust.contains("format!(\"{}{}\", \"a\", __ark_fmt_nullable(&x, \"null\"))"), "got:\n{rust}");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "anull b2\nv=null,undefined\nhi undefined\n");
+}
@@ -0,0 +1,158 @@
+use arkts2rust::ast::{Expr, Literal, UiArg, UiAttr};
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, CodegenOptions, Error, ExternBindings, OutputMode, UiBackendRef};
+
+mod common;
+
+fn builder(src: &str) -> Result<String, Error> {
+    let program = parse_program(src)?;
+    let options = CodegenOptions {
+        ui_backend: UiBackendRef::by_name("builder"),
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+const PAGE: &str = r#"
+@Styles function card() {
+  .width(100)
+  .padding(8)
+}
+
+@Extend(Text) function title(size: number) {
+  .fontSize(size)
+  .fontWeight(FontWeight.Bold)
+}
+
+@Entry
+@Component
+struct Index {
+  @State level: number = 1;
+  @Styles dimmed() {
+    .opacity(this.level)
+  }
+  build() {
+    Column() {
+      Text("hi").title(24).dimmed()
+      Row() {
+        Divider()
+      }.card()
+    }
+  }
+  onPageShow() {
+    this.level = 2;
+  }
+}
+"#;
+
+#[test]
+fn parse_styles_and_extend() {
+    let p = parse_program(PAGE).unwrap();
+    assert_eq!(p.styles.len(), 2);
+    assert!(p.funcs.is_empty());
+    let card = &p.styles[0];
+    assert_eq!(card.name, "card");
+    assert_eq!(card.extend, None);
+    assert_eq!(
+        card.attrs[0],
+        UiAttr {
+            name: "width".into(),
+            args: vec![UiArg::Expr(Expr::Literal(Literal::Number(100), Default::default()))],
+            span: Default::default(),
+        }
+    );
+    let title = &p.styles[1];
+    assert_eq!(title.extend.as_deref(), Some("Text"));
+    assert_eq!(title.params[0].name, "size");
+    assert_eq!(title.attrs.len(), 2);
+
+    let c = &p.components[0];
+    assert_eq!(c.styles.len(), 1);
+    assert_eq!(c.styles[0].name, "dimmed");
+    assert!(c.methods.iter().all(|m| m.name != "dimmed"));
+}
+
+#[test]
+fn codegen_styles_without_backend() {
+    let rust = compile(PAGE).unwrap();
+    assert!(
+        rust.contains("// @Styles card() {\n//     .width(100i32)\n//     .padding(8i32)\n// }\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("// @Extend(Text) title(size) {\n//     .fontSize(size)\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    // @Styles dimmed() {\n    //     .opacity(self.level.value)\n    // }\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_styles_with_backend() {
+    let rust = builder(PAGE).unwrap();
+    assert!(
+        rust.contains(
+            "fn title(size: i32, ui: &mut ArkUi) {\n    ui.attr(\"fontSize\", vec![format!(\"{:?}\", size)]);\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "            ui.open(\"Text\", vec![format!(\"{:?}\", String::from(\"hi\"))]);\n                \
+             title(24i32, ui);\n                self.dimmed(ui);\n            ui.close();\n            \
+             ui.open(\"Row\", vec![]);\n                card(ui);\n                ui.open(\"Divider\", vec![]);\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    fn dimmed(&mut self, ui: &mut ArkUi) {\n        ui.attr(\"opacity\""),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_styles_misuse() {
+    let err = parse_program("@Extend function big() { .fontSize(40) }").expect_err("no target");
+    assert_eq!(err.code, "InvalidDecoratorArgs");
+
+    let err = parse_program("@Styles function big() { fontSize(40) }").expect_err("not an attribute");
+    assert_eq!(err.code, "ExpectedUiAttrCall");
+
+    let err = builder(
+        r#"@Extend(Text) function big() { .fontSize(40) }
+@Component struct A {
+  build() {
+    Row() {}.big()
+  }
+}"#,
+    )
+    .expect_err("wrong element");
+    assert_eq!(err.code, "ExtendTargetMismatch");
+
+    let err = builder(
+        r#"@Styles function card() { .width(1) }
+@Component struct A {
+  build() {
+    Row() {}.card({ width: 1 })
+  }
+}"#,
+    )
+    .expect_err("object argument");
+    assert_eq!(err.code, "InvalidStyleArgs");
+}
+
+#[test]
+fn generated_styles_page_runs() {
+    let rust = builder(PAGE).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "Column()\n  Text(\"hi\").fontSize(24).fontWeight(FontWeight.Bold).opacity(1)\n  Row().width(100).padding(8)\n    Divider()\n\
+         Column()\n  Text(\"hi\").fontSize(24).fontWeight(FontWeight.Bold).opacity(2)\n  Row().width(100).padding(8)\n    Divider()\n"
+    );
+}
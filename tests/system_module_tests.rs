@@ -0,0 +1,131 @@
+use arkts2rust::{
+    compile_file, compile_file_with, compile_tree, parse_program, CodegenOptions, ExternBindings, ImportClause,
+    OutputMode, ResolveConfig,
+};
+
+mod common;
+
+#[test]
+fn parse_default_import() {
+    let p = parse_program("import preferences from '@ohos.data.preferences';").unwrap();
+    assert_eq!(p.imports[0].clause, ImportClause::Default("preferences".into()));
+    assert_eq!(p.imports[0].source, "@ohos.data.preferences");
+}
+
+const MAIN: &str = r#"
+import hilog from '@ohos.hilog';
+import { UIAbility } from '@kit.AbilityKit';
+import preferences from '@ohos.data.preferences';
+import { readText as read } from '@ohos.file.fs';
+
+function load(): void {
+  preferences.put('theme', 'dark');
+  console.log(read('/data/a.txt'));
+  hilog.info(0x0000, 'Main', 'loaded');
+}
+
+load();
+"#;
+
+#[test]
+fn codegen_system_imports() {
+    let dir = common::write_project(&[("main.ets", MAIN)]);
+    let rust = compile_file(dir.join("main.ets")).unwrap();
+    assert!(
+        rust.starts_with(
+            "use ohos_runtime::data::preferences;\nuse ohos_runtime::file::fs::readText as read;\n\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    preferences::put(String::from(\"theme\"), String::from(\"dark\"));\n    \
+             println!(\"{:?}\", read(String::from(\"/data/a.txt\")));\n    \
+             println!(\"{}: loaded\", String::from(\"Main\"));\n"
+        ),
+        "got:\n{rust}"
+    );
+
+    // 模块树输出：`use` 放在 import 它的文件里
+    let dir = common::write_project(&[
+        ("main.ets", "import { save } from './store'; save();"),
+        (
+            "store.ets",
+            "import * as prefs from '@ohos.data.preferences';\nexport function save(): void { prefs.flush(); }",
+        ),
+    ]);
+    let files = compile_tree(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &ResolveConfig::default(),
+        &CodegenOptions::default(),
+    )
+    .unwrap();
+    let store = files.iter().find(|f| f.path.ends_with("store.rs")).unwrap();
+    assert!(
+        store.code.starts_with("use ohos_runtime::data::preferences as prefs;\n\npub(crate) fn save() {\n    prefs::flush();\n"),
+        "got:\n{}",
+        store.code
+    );
+    assert!(!files[0].code.contains("ohos_runtime"), "got:\n{}", files[0].code);
+}
+
+#[test]
+fn custom_system_module_mapping() {
+    let dir = common::write_project(&[("main.ets", "import router from '@ohos.router';\nrouter.pushUrl('pages/Detail');")]);
+    let mut resolve = ResolveConfig::default();
+    resolve.system_modules.insert("@ohos.router".into(), Some("my_app::nav".into()));
+    let rust = compile_file_with(
+        dir.join("main.ets"),
+        OutputMode::Binary,
+        &ExternBindings::new(),
+        &resolve,
+        &CodegenOptions::default(),
+    )
+    .unwrap();
+    assert!(rust.starts_with("use my_app::nav as router;\n\n"), "got:\n{rust}");
+    assert!(rust.contains("    router::pushUrl(String::from(\"pages/Detail\"));\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_system_module_misuse() {
+    let dir = common::write_project(&[("main.ets", "import sensor from '@ohos.sensor';")]);
+    let err = compile_file(dir.join("main.ets")).expect_err("unknown module");
+    assert_eq!(err.code, "UnknownSystemModule");
+    assert!(err.file.unwrap().ends_with("main.ets"));
+
+    let dir = common::write_project(&[
+        ("main.ets", "import util from './util';"),
+        ("util.ets", "export function f(): void {}"),
+    ]);
+    let err = compile_file(dir.join("main.ets")).expect_err("default import of a file");
+    assert_eq!(err.code, "UnsupportedDefaultImport");
+
+    let dir = common::write_project(&[(
+        "main.ets",
+        "import router from '@ohos.router';\nfunction router(): void {}",
+    )]);
+    let err = compile_file(dir.join("main.ets")).expect_err("name clash");
+    assert_eq!(err.code, "DuplicateSymbol");
+}
+
+#[test]
+fn generated_system_calls_run() {
+    let dir = common::write_project(&[(
+        "main.ets",
+        "import preferences from '@ohos.data.preferences';\npreferences.put('theme', 'dark');",
+    )]);
+    let mut rust = compile_file(dir.join("main.ets")).unwrap();
+    rust.push_str(
+        "\nmod ohos_runtime {\n    pub mod data {\n        pub mod preferences {\n            \
+         pub fn put(key: String, value: String) {\n                println!(\"put {key}={value}\");\n            \
+         }\n        }\n    }\n}\n",
+    );
+    let rust = rust.replace("use ohos_runtime::", "use crate::ohos_runtime::");
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "put theme=dark\n");
+}
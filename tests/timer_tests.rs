@@ -0,0 +1,77 @@
+use arkts2rust::compile;
+
+mod common;
+
+#[test]
+fn codegen_set_timeout_sleeps_then_calls() {
+    let rust = compile("function tick(): void { return; } setTimeout(tick, 100);").unwrap();
+    assert!(
+        rust.contains(
+            "    { std::thread::sleep(std::time::Duration::from_millis(100i32.max(0) as u64)); tick(); };\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_set_timeout_without_delay_forwards_args() {
+    let rust =
+        compile("function greet(s: string): void { return; } setTimeout(greet);").unwrap();
+    assert!(rust.contains("    { greet(); };\n"), "got:\n{rust}");
+
+    let rust = compile("function greet(s: string): void { return; } setTimeout(greet, 0, \"hi\");")
+        .unwrap();
+    assert!(rust.contains("greet(String::from(\"hi\")); };"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_set_interval_loops() {
+    let rust = compile("function tick(): void { return; } setInterval(tick, 5);").unwrap();
+    assert!(
+        rust.contains(
+            "    loop { std::thread::sleep(std::time::Duration::from_millis(5i32.max(0) as u64)); tick(); };\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn user_function_shadows_timer_builtin() {
+    let rust = compile("function setTimeout(n: number): void { return; } setTimeout(1);").unwrap();
+    assert!(rust.contains("    setTimeout(1i32);\n"), "got:\n{rust}");
+}
+
+#[test]
+fn timer_callback_must_be_function_name() {
+    let err = compile("setTimeout(1, 2);").expect_err("callback is not a function");
+    assert_eq!(err.code, "TimerCallbackNotFunction");
+}
+
+#[test]
+fn generated_timer_code_runs() {
+    // 回调是同步执行的（见 `gen_timer_call`），这里的定时器按延迟从小到大写在最后，输出顺序和 JS 相同
+    let src = r#"
+function tick(): void { console.log("tick"); }
+function greet(name: string): void { console.log(name); }
+{
+  let count = 0;
+  function bump(): void { count = count + 1; }
+  function report(): void { console.log(count); }
+  setTimeout(bump);
+  setTimeout(bump, 1);
+  setTimeout(report, 1);
+}
+setTimeout(greet, 1 + 1, "hi");
+setTimeout(tick, 10);
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "2\nhi\ntick\n"
+    );
+}
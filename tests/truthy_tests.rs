@@ -0,0 +1,90 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{compile, parse_program, typeck, CodegenOptions, ExternBindings, OutputMode};
+
+mod common;
+
+fn compile_truthy(src: &str) -> String {
+    let program = parse_program(src).unwrap();
+    let options = CodegenOptions {
+        truthy_conditions: true,
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap()
+}
+
+#[test]
+fn truthy_condition_is_a_warning() {
+    let p = parse_program("let n = 1;\nwhile (n - 1) { n = 0; }\nif (n > 0) { } else { }").unwrap();
+    let warnings = typeck::warnings(&p, true);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, "TruthyCondition");
+    assert_eq!((warnings[0].span.start_line, warnings[0].span.start_col), (2, 8));
+
+    // `!`、`&&`、`||` 的每个被转换的操作数各报一次
+    let p = parse_program("let s = \"a\";\nlet n = 1;\nwhile (s && n) { n = 0; }\nif (!s || n > 0) { } else { }").unwrap();
+    let warnings: Vec<(usize, usize)> =
+        typeck::warnings(&p, true).iter().map(|w| (w.span.start_line, w.span.start_col)).collect();
+    assert_eq!(warnings, [(3, 8), (3, 13), (4, 6)]);
+
+    let err = compile("while (1) { }").expect_err("still an error by default");
+    assert_eq!(err.code, "ConditionMustBeBool");
+}
+
+#[test]
+fn codegen_truthy_conditions() {
+    let rust = compile_truthy(
+        r#"function f(n: number, s: string, m: number | null, ok: boolean): void {
+  if (n) { } else { }
+  while (!s) { s = "x"; }
+  if (s && !m) { } else { }
+  if (!(n || ok)) { } else { }
+  if (m) { console.log(m); } else { }
+}"#,
+    );
+    assert!(rust.contains("    if n != 0 {\n"), "got:\n{rust}");
+    assert!(rust.contains("    while s.is_empty() {\n"), "got:\n{rust}");
+    assert!(rust.contains("    if !s.is_empty() && m.is_none() {\n"), "got:\n{rust}");
+    assert!(rust.contains("    if !(n != 0 || ok) {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains("    if m.is_some() {\n        println!(\"{}\", m.unwrap());\n"),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn generated_truthy_code_runs() {
+    let src = r#"
+function describe(n: number, s: string, m: number | null): string {
+  if (!n) {
+    return "zero";
+  } else {
+  }
+  if (s && m) {
+    return s;
+  } else {
+    return "none";
+  }
+}
+function next(m: number | null): number {
+  if (m) {
+    return m + 1;
+  } else {
+    return 0;
+  }
+}
+let i = 3;
+let total = 0;
+while (i) {
+  total = total + i;
+  i = i - 1;
+}
+console.log(total, describe(0, "a", 1), describe(1, "", 1), describe(1, "b", 2), describe(1, "c", null), next(4), next(null));
+"#;
+
+    let rust = compile_truthy(src);
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "6 zero none b none 5 0\n");
+}
@@ -0,0 +1,224 @@
+use arkts2rust::ast::{Expr, Literal, NullKind};
+use arkts2rust::{compile, parse_program, typeck, CodegenOptions, NumberMode, Stmt, TypeAnn};
+
+#[test]
+fn error_type_mismatch() {
+    let err = compile("let a = 1;\nlet s: string = a + 1;").expect_err("number into string");
+    assert_eq!(err.code, "TypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 5));
+
+    // 最内层的表达式提供位置
+    let err = compile("let ok = true;\nconsole.log(1 + (ok - 1));").expect_err("boolean operand");
+    assert_eq!(err.code, "TypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 18));
+
+    let err = compile("function f(n: number): string {\n  return n * 2;\n}").expect_err("return");
+    assert_eq!(err.code, "TypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 3));
+
+    for src in [
+        "let n = 0; n = 'a';",
+        "let s = 'a'; if (s == 1) { console.log(s); } else { console.log(1); }",
+        "let ok = true; let n = ok + 1;",
+        "let s = 'a'; let n = -s;",
+        "let n = 1; let b = !n;",
+        "let a = 1; let b = a && true;",
+        "function f(...xs: number[]): void { xs[0] = 'x'; }",
+        "function f(s: string): boolean { return s.length; }",
+        "function g(): number { return 1; } let s: string = g();",
+        "let s = 'abc'; let c = s['a'];",
+        "namespace util { export function f(): string { return 'a'; } } let n: number = util.f();",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "TypeMismatch", "{src}");
+    }
+
+    let err = compile(
+        "@Entry\n@Component\nstruct Index {\n  @State count: number = 0;\n  build() {\n    Button('+').onClick(() => { this.count = 'x'; })\n  }\n}",
+    )
+    .expect_err("component field");
+    assert_eq!(err.code, "TypeMismatch");
+    assert_eq!(err.span.start_line, 6);
+}
+
+#[test]
+fn accepts_well_typed_programs() {
+    let src = r#"
+let total = 0;
+let label = "n=" + total;
+const ratio: number = total / 2;
+
+function half(n: number): number | null {
+  if (n % 2 == 0) {
+    return n / 2;
+  } else {
+    return null;
+  }
+}
+
+function show(items: string[], limit: number | null): void {
+  let first: string = items[0];
+  let n = limit ?? 0;
+  console.log(first.toUpperCase(), items.length + n, half(4) == 2);
+  return;
+}
+
+let maybe: number | null = null;
+maybe = 3;
+total = total + parseInt("4");
+console.log(label, String(ratio), Math.max(1, 2), JSON.stringify(total));
+"#;
+    typeck::check(&parse_program(src).unwrap(), false).unwrap();
+}
+
+#[test]
+fn truthy_conditions_skip_condition_checks() {
+    let src = "let n = 1;\nwhile (n) { n = n - 1; }\nlet ok = !n && true;";
+    let program = parse_program(src).unwrap();
+    assert_eq!(typeck::check(&program, false).unwrap_err().code, "ConditionMustBeBool");
+    typeck::check(&program, true).unwrap();
+    let warnings: Vec<_> = typeck::warnings(&program, true).into_iter().map(|w| (w.code.to_string(), w.span.start_line)).collect();
+    assert_eq!(warnings, [("TruthyCondition".to_string(), 2)]);
+}
+
+#[test]
+fn records_expression_types() {
+    let program = parse_program("let n = 1;\nlet s = 'n=' + n;\nlet ok = s.length > n;").unwrap();
+    let types = typeck::check(&program, false).unwrap();
+    let init = |i: usize| match &program.stmts[i] {
+        Stmt::VarDecl(v) => v.decls[0].init.as_ref().unwrap(),
+        other => panic!("expected a variable declaration, got {other:?}"),
+    };
+    assert_eq!(types.get(init(0)), Some(&TypeAnn::Number));
+    assert_eq!(types.get(init(1)), Some(&TypeAnn::String));
+    assert_eq!(types.get(init(2)), Some(&TypeAnn::Boolean));
+    // 按节点编号登记：克隆出来的节点一样查得到，新构造的节点查不到
+    assert_eq!(types.get(&init(1).clone()), Some(&TypeAnn::String));
+    assert_eq!(types.get(&Expr::Literal(Literal::Number(1), Default::default())), None);
+}
+
+#[test]
+fn records_narrowed_and_float_types() {
+    let src = "function f(x: number | null): number {\n  console.log(x);\n  if (x != null) {\n    return x;\n  } else {\n    return 0;\n  }\n}\n\
+               let r = Math.random() * 6;\nlet k = Math.floor(r);\nlet h = k / 2;";
+    let program = parse_program(src).unwrap();
+    let body = &program.funcs[0].body.stmts;
+    let Stmt::ExprStmt(Expr::Call(log)) = &body[0] else {
+        panic!("expected console.log");
+    };
+    let Stmt::If(i) = &body[1] else {
+        panic!("expected an if statement");
+    };
+    let Stmt::Block(then_branch) = i.then_branch.as_ref() else {
+        panic!("expected a block");
+    };
+    let Stmt::Return(ret) = &then_branch.stmts[0] else {
+        panic!("expected a return");
+    };
+    let init = |i: usize| match &program.stmts[i] {
+        Stmt::VarDecl(v) => v.decls[0].init.as_ref().unwrap(),
+        other => panic!("expected a variable declaration, got {other:?}"),
+    };
+
+    let types = typeck::check(&program, false).unwrap();
+    // 可空变量在 `x != null` 的分支里收窄
+    assert_eq!(types.get(&log.args[0]), Some(&TypeAnn::Nullable(Box::new(TypeAnn::Number), NullKind::Null)));
+    assert_eq!(types.get(ret.value.as_ref().unwrap()), Some(&TypeAnn::Number));
+    // 小数经过运算和变量传下去，`Math.floor` 转回整数
+    assert_eq!(types.get(init(0)), Some(&TypeAnn::Number));
+    assert!(types.is_float(init(0)));
+    assert!(!types.is_float(init(1)));
+    assert!(!types.is_float(init(2)));
+
+    let options = CodegenOptions { js_division: true, ..CodegenOptions::default() };
+    assert!(typeck::check_with(&program, &options).unwrap().is_float(init(2)));
+    let options = CodegenOptions { number_mode: NumberMode::F64, ..CodegenOptions::default() };
+    assert!(!typeck::check_with(&program, &options).unwrap().is_float(init(0)));
+}
+
+#[test]
+fn error_wrong_arg_count() {
+    let err = compile("function add(a: number, b: number): number {\n  return a + b;\n}\nlet n = add(1);")
+        .expect_err("missing argument");
+    assert_eq!(err.code, "WrongArgCount");
+    assert_eq!((err.span.start_line, err.span.start_col), (4, 9));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 1));
+    assert_eq!(err.notes[0].message(), "function declared here");
+
+    for src in [
+        "function f(): void {} f(1);",
+        "function sum(first: number, ...rest: number[]): void {} sum();",
+        "function outer(): void { function inner(a: number): void {} inner(); }",
+        "namespace util { export function f(a: number): void {} } util.f(1, 2);",
+        "@Entry\n@Component\nstruct Index {\n  bump(n: number): void {}\n  build() {\n    Button('+').onClick(() => { this.bump(); })\n  }\n}",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "WrongArgCount", "{src}");
+    }
+
+    let src = "function sum(first: number, ...rest: number[]): void {}\n\
+               function f(...xs: number[]): void { sum(1); sum(1, 2, 3); sum(...xs); }";
+    typeck::check(&parse_program(src).unwrap(), false).unwrap();
+}
+
+#[test]
+fn error_arg_type_mismatch() {
+    let err = compile("function greet(name: string, times: number): void {}\ngreet('a', 'b');").expect_err("string for number");
+    assert_eq!(err.code, "ArgTypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 12));
+    assert_eq!(err.notes.len(), 1);
+    assert_eq!((err.notes[0].span.start_line, err.notes[0].span.start_col), (1, 30));
+    assert_eq!(err.notes[0].message(), "parameter declared here");
+
+    // 带位置的实参自己提供位置
+    let err = compile("function twice(n: number): number { return n * 2; }\nlet s = 'a';\ntwice(s + 1);").expect_err("concat");
+    assert_eq!(err.code, "ArgTypeMismatch");
+    assert_eq!((err.span.start_line, err.span.start_col), (3, 7));
+
+    for src in [
+        // 省略标注的参数是 number
+        "function f(n): void {} f(true);",
+        "function sum(...xs: number[]): void {} sum(1, 2, 'x');",
+        "function f(s: string | null): void {} f(1);",
+        "namespace util { export function f(ok: boolean): void {} } util.f(1);",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "ArgTypeMismatch", "{src}");
+    }
+
+    let src = "function f(s: string | null, ...xs: number[]): void {}\n\
+               function g(...ys: number[]): void { f('a', 1, 2); f(null); f('b', ...ys); }";
+    typeck::check(&parse_program(src).unwrap(), false).unwrap();
+}
+
+#[test]
+fn error_missing_return() {
+    let err = compile("let x = 1;\nfunction sign(n: number): number {\n  while (n > 0) {\n    return 1;\n  }\n}")
+        .expect_err("falls off the end");
+    assert_eq!(err.code, "MissingReturn");
+    assert_eq!((err.span.start_line, err.span.start_col), (2, 1));
+
+    for src in [
+        "function f(): string {}",
+        "function f(n: number): number { if (n > 0) { return 1; } else { console.log(n); } }",
+        "function f(n: number): number { if (n > 0) { console.log(n); } else { return n; } }",
+        "function f(): number { try { return 1; } catch (e) { console.log(e); } }",
+        // 没有标注返回类型、有 `return x;` 的函数返回 number
+        "function f(n: number) { while (n > 0) { return n; } }",
+    ] {
+        let err = compile(src).expect_err(src);
+        assert_eq!(err.code, "MissingReturn", "{src}");
+    }
+
+    let src = r#"
+function a(n: number): number { if (n > 0) { return 1; } else { return 2; } }
+function b(n: number): number { if (n > 0) { return 1; } else if (n < 0) { return -1; } else { throw new Error('zero'); } }
+function c(): number { while (true) { console.log(1); } }
+function d(): number { try { return 1; } catch (e) { return 2; } }
+function e(): number { try { console.log(1); } finally { return 3; } }
+function f(): void { if (true) { return; } else { console.log(1); } }
+function g(n: number): number { { return n; } }
+"#;
+    typeck::check(&parse_program(src).unwrap(), false).unwrap();
+}
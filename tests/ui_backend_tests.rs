@@ -0,0 +1,319 @@
+use arkts2rust::codegen::generate_with;
+use arkts2rust::{
+    compile, parse_program, CodegenOptions, Error, ExternBindings, OutputMode, UiBackend, UiBackendRef, UiCall,
+    UiCode,
+};
+
+mod common;
+
+fn compile_ui(src: &str, backend: UiBackendRef) -> Result<String, Error> {
+    let program = parse_program(src)?;
+    let options = CodegenOptions {
+        ui_backend: Some(backend),
+        ..CodegenOptions::default()
+    };
+    generate_with(&program, OutputMode::Binary, &ExternBindings::new(), &options)
+}
+
+fn builder(src: &str) -> Result<String, Error> {
+    compile_ui(src, UiBackendRef::by_name("builder").unwrap())
+}
+
+const COUNTER: &str = r#"
+@Entry
+@Component
+struct Index {
+  @State count: number = 0;
+  title: string = "Counter";
+  build() {
+    Column({ space: 8 }) {
+      Text(this.title)
+        .fontSize(20)
+        .fontWeight(FontWeight.Bold)
+      if (this.count > 0) {
+        Text("clicked")
+      }
+      Button("+1").onClick(() => {
+        this.count = this.count + 1;
+      })
+    }
+  }
+  onPageShow() {
+    this.count = 2;
+  }
+}
+"#;
+
+#[test]
+fn codegen_builder_backend() {
+    let rust = builder(COUNTER).unwrap();
+    assert!(rust.contains("\nstruct ArkUi {\n"), "got:\n{rust}");
+    assert!(
+        rust.contains(
+            "    fn build(&mut self, ui: &mut ArkUi) {\n        \
+             ui.open(\"Column\", vec![format!(\"{{ space: {} }}\", format!(\"{:?}\", 8i32))]);\n            \
+             ui.open(\"Text\", vec![format!(\"{:?}\", self.title.clone())]);\n            \
+             ui.attr(\"fontSize\", vec![format!(\"{:?}\", 20i32)]);\n            \
+             ui.attr(\"fontWeight\", vec![String::from(\"FontWeight.Bold\")]);\n            \
+             ui.close();\n            \
+             if self.count.value > 0i32 {\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    fn __ark_rerender(&mut self, rendered: &mut Option<u64>, ui: &mut ArkUi) {\n"),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains(
+            "    let mut __entry = Index::new();\n    let mut __ui = ArkUi::new();\n    \
+             let mut __rendered = None;\n    __entry.__ark_rerender(&mut __rendered, &mut __ui);\n    \
+             __entry.onPageShow();\n    __entry.__ark_rerender(&mut __rendered, &mut __ui);\n"
+        ),
+        "got:\n{rust}"
+    );
+
+    // 没有配置后端时还是注释形式，不附带 ArkUi
+    let rust = compile(COUNTER).unwrap();
+    assert!(!rust.contains("ArkUi"), "got:\n{rust}");
+    assert!(rust.contains("    // Column({ space: 8i32 }) {\n"), "got:\n{rust}");
+}
+
+#[test]
+fn codegen_egui_backend() {
+    let rust = compile_ui(COUNTER, UiBackendRef::by_name("egui").unwrap()).unwrap();
+    assert!(
+        rust.contains(
+            "    fn build(&mut self, ui: &mut egui::Ui) {\n        \
+             ui.vertical(|ui| {\n            \
+             ui.label(format!(\"{}\", self.title.clone()));\n            \
+             if self.count.value > 0i32 {\n                \
+             ui.label(format!(\"{}\", String::from(\"clicked\")));\n            \
+             }\n            \
+             if ui.button(format!(\"{}\", String::from(\"+1\"))).clicked() {\n                \
+             self.count.set(self.count.value + 1i32);\n            \
+             }\n        \
+             });\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    // egui 由事件循环调用 build，main 只调用其它生命周期方法
+    assert!(
+        rust.contains("    let mut __entry = Index::new();\n    __entry.onPageShow();\n}\n"),
+        "got:\n{rust}"
+    );
+    assert!(!rust.contains("ArkUi"), "got:\n{rust}");
+}
+
+/// 测试用的后端：每个组件生成一行 `out.push(..)`。
+struct ListBackend;
+
+impl UiBackend for ListBackend {
+    fn name(&self) -> &str {
+        "list"
+    }
+
+    fn build_params(&self) -> Vec<(String, String)> {
+        vec![("out".to_string(), "&mut Vec<String>".to_string())]
+    }
+
+    fn open_element(&self, call: &UiCall) -> Vec<String> {
+        let arg = match call.args.first() {
+            Some(UiCode::Expr(e)) => format!("format!(\"{{}}\", {e})"),
+            _ => "String::new()".to_string(),
+        };
+        vec![format!("out.push(format!(\"<{}> {{}}\", {arg}));", call.name)]
+    }
+
+    fn close_element(&self, call: &UiCall) -> Vec<String> {
+        vec![format!("out.push(String::from(\"</{}>\"));", call.name)]
+    }
+
+    fn entry_build(&self) -> Option<(Vec<String>, Vec<String>)> {
+        Some((vec!["let mut __out = Vec::new();".to_string()], vec!["&mut __out".to_string()]))
+    }
+}
+
+#[test]
+fn codegen_custom_backend() {
+    let src = r#"@Entry @Component struct A {
+  build() {
+    Text("hi")
+  }
+}"#;
+    let rust = compile_ui(src, UiBackendRef::new(ListBackend)).unwrap();
+    assert!(
+        rust.contains(
+            "    fn build(&mut self, out: &mut Vec<String>) {\n        \
+             out.push(format!(\"<Text> {}\", format!(\"{}\", String::from(\"hi\"))));\n        \
+             out.push(String::from(\"</Text>\"));\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+    assert!(
+        rust.contains("    let mut __entry = A::new();\n    let mut __out = Vec::new();\n    __entry.build(&mut __out);\n"),
+        "got:\n{rust}"
+    );
+    assert_eq!(UiBackendRef::new(ListBackend), UiBackendRef::new(ListBackend));
+    assert!(UiBackendRef::by_name("qt").is_none());
+}
+
+#[test]
+fn codegen_child_components_and_for_each() {
+    let rust = builder(
+        r#"@Component struct Item {
+  @Prop label: string;
+  @Link total: number;
+  @Consume('theme') theme: string;
+  note: string = "";
+  build() {
+    Text(this.label)
+  }
+}
+@Entry @Component struct List {
+  @Provide('theme') theme: string = "dark";
+  @Provide total: number = 0;
+  names: string[] = "a b".split(" ");
+  build() {
+    ForEach(this.names, (name: string, i: number) => {
+      Item({ label: name, total: this.total, note: "*" })
+    }, (name: string) => name)
+  }
+}"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "        for (i, name) in self.names.clone().into_iter().enumerate() {\n            \
+             let i = i as i32;\n            {\n                \
+             let mut __ctx = ArkContext::new();\n                \
+             self.__ark_provide(&mut __ctx);\n                \
+             let mut __child = Item::new(name, self.total.clone(), &__ctx);\n                \
+             __child.note = String::from(\"*\");\n                \
+             __child.build(ui);\n            }\n        }\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn codegen_mut_for_variables_assigned_in_for_each() {
+    let rust = builder(
+        r#"@Entry
+@Component
+struct Index {
+  names: string[] = "a b".split(" ");
+  build() {
+    let shown = 0;
+    let title = "list";
+    Column() {
+      Text(title)
+      ForEach(this.names, (name: string) => {
+        Text(name)
+        shown = shown + 1;
+      })
+    }
+  }
+}"#,
+    )
+    .unwrap();
+    assert!(rust.contains("        let mut shown = 0i32;\n"), "got:\n{rust}");
+    assert!(rust.contains("        let title = String::from(\"list\");\n"), "got:\n{rust}");
+}
+
+#[test]
+fn error_invalid_ui_usage() {
+    let child = r#"@Component struct Item {
+  @Prop label: string;
+  @Link total: number;
+  build() {
+    Text(this.label)
+  }
+}
+"#;
+    for (body, code) in [
+        (r#"Item({ total: this.total })"#, "MissingComponentProp"),
+        (r#"Item({ label: "x", total: this.total, size: 1 })"#, "UnknownComponentProp"),
+        (r#"Item({ label: "x", total: this.count })"#, "LinkNeedsSharedSource"),
+        (r#"Item("x")"#, "InvalidComponentArgs"),
+        (r#"ForEach(this.count)"#, "InvalidForEach"),
+    ] {
+        let src = format!(
+            "{child}@Component struct Parent {{\n  @State count: number = 0;\n  @Link total: number;\n  \
+             build() {{\n    {body}\n  }}\n}}"
+        );
+        let err = builder(&src).expect_err(body);
+        assert_eq!(err.code, code, "{body}");
+    }
+
+    let err = builder(
+        r#"@Component struct Item {
+  @Consume theme: string;
+  build() {
+    Text(this.theme)
+  }
+}
+@Component struct Parent {
+  build() {
+    Item()
+  }
+}"#,
+    )
+    .expect_err("no provider");
+    assert_eq!(err.code, "ConsumeWithoutProvide");
+}
+
+#[test]
+fn generated_builder_ui_runs() {
+    let src = r#"
+@Component
+struct Row2 {
+  @Prop label: string;
+  @Link total: number;
+  build() {
+    Row() {
+      Text(this.label)
+    }
+  }
+}
+
+@Entry
+@Component
+struct Index {
+  @State message: string = "Hello";
+  @Provide total: number = 0;
+  names: string[] = "a b".split(" ");
+  build() {
+    Column({ space: 10 }) {
+      Text(this.message)
+        .fontSize(20)
+        .fontWeight(FontWeight.Bold)
+      if (this.total > 0) {
+        Text("total", this.total)
+      } else {
+        Divider()
+      }
+      ForEach(this.names, (name: string) => {
+        Row2({ label: name, total: this.total })
+      })
+    }
+  }
+  onPageShow() {
+    this.total = 2;
+  }
+}
+"#;
+
+    let rust = builder(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "Column({ space: 10 })\n  Text(\"Hello\").fontSize(20).fontWeight(FontWeight.Bold)\n  Divider()\n  \
+         Row()\n    Text(\"a\")\n  Row()\n    Text(\"b\")\n\
+         Column({ space: 10 })\n  Text(\"Hello\").fontSize(20).fontWeight(FontWeight.Bold)\n  Text(\"total\", 2)\n  \
+         Row()\n    Text(\"a\")\n  Row()\n    Text(\"b\")\n"
+    );
+}
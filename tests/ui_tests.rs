@@ -0,0 +1,263 @@
+use arkts2rust::ast::{
+    AssignExpr, AssignTarget, BinaryExpr, BinaryOp, Expr, Literal, MemberExpr, Param, Stmt, TypeAnn, UiArg, UiAttr,
+    UiElement, UiHandler, UiIf, UiNode,
+};
+use arkts2rust::{compile, parse_program};
+
+mod common;
+
+fn build(src: &str) -> Vec<UiNode> {
+    let p = parse_program(src).unwrap();
+    p.components.into_iter().next().unwrap().build.unwrap()
+}
+
+fn this(property: &str) -> Expr {
+    Expr::Member(MemberExpr {
+        object: Box::new(Expr::Ident("this".into(), Default::default())),
+        property: property.into(),
+        span: Default::default(),
+    })
+}
+
+fn text(s: &str) -> UiNode {
+    UiNode::Element(UiElement {
+        name: "Text".into(),
+        args: vec![UiArg::Expr(Expr::Literal(Literal::String(s.into()), Default::default()))],
+        children: vec![],
+        attrs: vec![],
+        span: Default::default(),
+    })
+}
+
+#[test]
+fn parse_elements_children_and_attrs() {
+    let nodes = build(
+        r#"@Component struct A {
+  msg: string = "hi";
+  build() {
+    Column({ space: 10 }) {
+      Text(this.msg)
+        .fontSize(20)
+        .fontWeight(FontWeight.Bold)
+      Divider();
+    }
+    .width('100%')
+  }
+}"#,
+    );
+    assert_eq!(
+        nodes,
+        vec![UiNode::Element(UiElement {
+            name: "Column".into(),
+            args: vec![UiArg::Object(vec![("space".into(), Expr::Literal(Literal::Number(10), Default::default()))])],
+            children: vec![
+                UiNode::Element(UiElement {
+                    name: "Text".into(),
+                    args: vec![UiArg::Expr(this("msg"))],
+                    children: vec![],
+                    attrs: vec![
+                        UiAttr {
+                            name: "fontSize".into(),
+                            args: vec![UiArg::Expr(Expr::Literal(Literal::Number(20), Default::default()))],
+                            span: Default::default(),
+                        },
+                        UiAttr {
+                            name: "fontWeight".into(),
+                            args: vec![UiArg::Expr(Expr::Member(MemberExpr {
+                                object: Box::new(Expr::Ident("FontWeight".into(), Default::default())),
+                                property: "Bold".into(),
+                                span: Default::default(),
+                            }))],
+                            span: Default::default(),
+                        },
+                    ],
+                    span: Default::default(),
+                }),
+                UiNode::Element(UiElement {
+                    name: "Divider".into(),
+                    args: vec![],
+                    children: vec![],
+                    attrs: vec![],
+                    span: Default::default(),
+                }),
+            ],
+            attrs: vec![UiAttr {
+                name: "width".into(),
+                args: vec![UiArg::Expr(Expr::Literal(Literal::String("100%".into()), Default::default()))],
+                span: Default::default(),
+            }],
+            span: Default::default(),
+        })]
+    );
+}
+
+#[test]
+fn parse_conditions_handlers_and_statements() {
+    let nodes = build(
+        r#"@Component struct A {
+  n: number = 0;
+  items: string[];
+  build() {
+    if (this.n > 0) {
+      Text("pos")
+    } else if (this.n < 0) {
+      Text("neg")
+    }
+    Button("+1").onClick(() => { this.n = 1; })
+    ForEach(this.items, (item: string) => Text(item), item => item)
+    console.log("built");
+  }
+}"#,
+    );
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(
+        nodes[0],
+        UiNode::If(UiIf {
+            cond: Expr::Binary(BinaryExpr {
+                op: BinaryOp::Gt,
+                left: Box::new(this("n")),
+                right: Box::new(Expr::Literal(Literal::Number(0), Default::default())),
+                span: Default::default(),
+            }),
+            then_branch: vec![text("pos")],
+            else_branch: vec![UiNode::If(UiIf {
+                cond: Expr::Binary(BinaryExpr {
+                    op: BinaryOp::Lt,
+                    left: Box::new(this("n")),
+                    right: Box::new(Expr::Literal(Literal::Number(0), Default::default())),
+                    span: Default::default(),
+                }),
+                then_branch: vec![text("neg")],
+                else_branch: vec![],
+                span: Default::default(),
+            })],
+            span: Default::default(),
+        })
+    );
+    let UiNode::Element(button) = &nodes[1] else {
+        panic!("expected Button, got {:?}", nodes[1]);
+    };
+    assert_eq!(
+        button.attrs[0].args,
+        vec![UiArg::Handler(UiHandler {
+            params: vec![],
+            body: vec![UiNode::Stmt(Stmt::ExprStmt(Expr::Assign(AssignExpr {
+                target: AssignTarget::Member {
+                    object: "this".into(),
+                    property: "n".into(),
+                },
+                value: Box::new(Expr::Literal(Literal::Number(1), Default::default())),
+                span: Default::default(),
+            })))],
+            span: Default::default(),
+        })]
+    );
+    let UiNode::Element(for_each) = &nodes[2] else {
+        panic!("expected ForEach, got {:?}", nodes[2]);
+    };
+    let item = |ty| Param {
+        name: "item".into(),
+        ty,
+        is_rest: false,
+        span: Default::default(),
+    };
+    assert_eq!(
+        for_each.args[1],
+        UiArg::Handler(UiHandler {
+            params: vec![item(Some(TypeAnn::String))],
+            body: vec![UiNode::Element(UiElement {
+                name: "Text".into(),
+                args: vec![UiArg::Expr(Expr::Ident("item".into(), Default::default()))],
+                children: vec![],
+                attrs: vec![],
+                span: Default::default(),
+            })],
+            span: Default::default(),
+        })
+    );
+    assert_eq!(
+        for_each.args[2],
+        UiArg::Handler(UiHandler {
+            params: vec![item(None)],
+            body: vec![UiNode::Stmt(Stmt::ExprStmt(Expr::Ident("item".into(), Default::default())))],
+            span: Default::default(),
+        })
+    );
+    assert!(matches!(nodes[3], UiNode::Stmt(Stmt::ExprStmt(Expr::Call(_)))));
+}
+
+#[test]
+fn codegen_ui_outline() {
+    let rust = compile(
+        r#"@Component struct Page {
+  @State n: number = 0;
+  build() {
+    Column() {
+      Text("count").fontColor(Color.Red)
+      if (this.n > 0) {
+        Text("pos")
+      } else {
+        Text("zero")
+      }
+      Button("+1").onClick(() => { this.n = this.n + 1; })
+    }
+    .width("100%")
+  }
+}"#,
+    )
+    .unwrap();
+    assert!(
+        rust.contains(
+            "    // Column() {\n    //     Text(String::from(\"count\"))\n    //         .fontColor(Color::Red)\n    \
+             //     if self.n.value > 0i32 {\n    //         Text(String::from(\"pos\"))\n    //     } else {\n    \
+             //         Text(String::from(\"zero\"))\n    //     }\n    //     Button(String::from(\"+1\"))\n    \
+             //         .onClick(|| { .. })\n    // }\n    //     .width(String::from(\"100%\"))\n    \
+             fn build(&mut self) {\n        if self.n.value > 0i32 {\n        } else {\n        }\n    }\n"
+        ),
+        "got:\n{rust}"
+    );
+}
+
+#[test]
+fn error_ui_syntax() {
+    for (src, code) in [
+        ("@Component struct A { build() { Text('a').fontSize } }", "ExpectedUiAttrCall"),
+        ("@Component struct A { build() {} build() {} }", "DuplicateBuild"),
+        ("@Component struct A { build() { Column() { Text('a') } ", "MissingRBrace"),
+    ] {
+        let err = parse_program(src).expect_err(src);
+        assert_eq!(err.code, code, "{src}");
+    }
+}
+
+#[test]
+fn generated_page_with_ui_runs() {
+    let src = r#"
+@Entry
+@Component
+struct Index {
+  @State count: number = 2;
+
+  build() {
+    Column() {
+      Text("Counter").fontSize(24)
+      if (this.count > 1) {
+        Text("many")
+        console.log("many", this.count);
+      } else {
+        console.log("few");
+      }
+      Button("+1").onClick(() => { this.count = this.count + 1; })
+    }
+  }
+}
+"#;
+
+    let rust = compile(src).unwrap();
+
+    let Some(run) = common::compile_and_run(&rust) else {
+        return;
+    };
+    // 事件处理函数不会执行
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "many 2\n");
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use arkts2rust::codegen::generate_mapped;
+use arkts2rust::verify::{map_back, parse_diagnostics, rustc};
+use arkts2rust::{parse_program, CodegenOptions, Edition, ErrorCode, ExternBindings, Lang, OutputMode, Severity};
+
+const RUSTC_JSON: &str = r#"{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":"Expected type did not match the received type.\n"},"level":"error","spans":[{"file_name":"out/main.rs","byte_start":40,"byte_end":45,"line_start":3,"line_end":3,"column_start":13,"column_end":18,"is_primary":false,"text":[],"label":"expected","suggested_replacement":null,"suggestion_applicability":null,"expansion":null},{"file_name":"out/main.rs","byte_start":50,"byte_end":55,"line_start":3,"line_end":3,"column_start":23,"column_end":28,"is_primary":true,"text":[],"label":"expected `i32`, found `&str`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[],"rendered":"error[E0308]: mismatched types\n → \"quoted\"\n"}
+{"$message_type":"diagnostic","message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"children":[],"rendered":"error: aborting due to 1 previous error\n\n"}"#;
+
+#[test]
+fn parse_rustc_and_cargo_json() {
+    let found = parse_diagnostics(RUSTC_JSON);
+    assert_eq!(found.len(), 2);
+    assert!(found[0].is_error());
+    assert_eq!(found[0].title(), "error[E0308]: mismatched types");
+    assert_eq!(found[0].file.as_deref(), Some("out/main.rs"));
+    assert_eq!((found[0].span.start_line, found[0].span.start_col, found[0].span.end_col), (3, 23, 28));
+    assert_eq!(found[1].file, None);
+
+    // cargo 把诊断信息包在 `compiler-message` 里，其它消息跳过
+    let cargo = format!(
+        "{{\"reason\":\"compiler-artifact\",\"package_id\":\"x\"}}\n{{\"reason\":\"compiler-message\",\"package_id\":\"x\",\"message\":{}}}\nnot json\n{{\"reason\":\"build-finished\",\"success\":false}}",
+        RUSTC_JSON.lines().next().unwrap()
+    );
+    let found = parse_diagnostics(&cargo);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].code.as_deref(), Some("E0308"));
+}
+
+#[test]
+fn errors_map_back_to_statements() {
+    let src = "let x = 1;\nlet s: string = \"a\";\nconsole.log(x);";
+    let program = parse_program(src).unwrap();
+    let (_, map) = generate_mapped(&program, OutputMode::Binary, &ExternBindings::new(), &CodegenOptions::default()).unwrap();
+    let found = parse_diagnostics(RUSTC_JSON);
+
+    let d = map_back(&found[0], Some(&map));
+    assert_eq!(d.code, ErrorCode::GeneratedCodeError);
+    assert_eq!(d.severity, Severity::Error);
+    assert_eq!((d.span.start_line, d.span.start_col, d.span.end_col), (2, 1, 21));
+    assert_eq!(
+        d.render_in(src, Lang::En),
+        "error[GeneratedCodeError]: the generated Rust code does not compile
+ --> 2:1
+  |
+2 | let s: string = \"a\";
+  | ^^^^^^^^^^^^^^^^^^^^
+note: error[E0308]: mismatched types
+ --> out/main.rs:3:23
+"
+    );
+
+    // 没有位置的报错不对应源码
+    let d = map_back(&found[1], Some(&map));
+    assert_eq!(d.span, arkts2rust::Span::default());
+    assert_eq!(d.render(src), "error[GeneratedCodeError]: the generated Rust code does not compile\nnote: error: aborting due to 1 previous error\n");
+}
+
+#[test]
+fn rustc_reports_against_source() {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "arkts2rust_verify_test_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let program = parse_program("let x = 1;\nconsole.log(x);\nconsole.error(x);").unwrap();
+    let options = CodegenOptions {
+        intrinsics: [("console.error".to_string(), "missing::log($0)".to_string())].into_iter().collect(),
+        ..CodegenOptions::default()
+    };
+    let (rust, map) = generate_mapped(&program, OutputMode::Binary, &ExternBindings::new(), &options).unwrap();
+    let root = dir.join("main.rs");
+    fs::write(&root, rust).unwrap();
+    let found = rustc(&root, OutputMode::Binary, Edition::E2021);
+    let _ = fs::remove_dir_all(&dir);
+
+    let found = found.unwrap();
+    let errors: Vec<_> = found.iter().filter(|d| d.is_error() && d.file.is_some()).collect();
+    assert_eq!(errors.len(), 1, "got: {found:?}");
+    assert_eq!(map_back(errors[0], Some(&map)).span.start_line, 3);
+}